@@ -0,0 +1,93 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.devicetransfer;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import java.io.IOException;
+import java.io.InputStream;
+import org.signal.libsignal.internal.Native;
+
+/**
+ * Decrypts the payload of an archive received during a device transfer.
+ *
+ * <p>Each chunk is authenticated before any of its payload is returned. Reaching the end of the
+ * stream checks that the archive was complete, with nothing appended to it; any problem with the
+ * archive is reported as an {@link IOException}.
+ */
+public final class ArchiveInputStream extends InputStream {
+  private static final int READ_SIZE = 64 * 1024;
+
+  private final long reader;
+  private final InputStream inner;
+  private final byte[] readBuffer = new byte[READ_SIZE];
+  private byte[] payload = new byte[0];
+  private int positionInPayload = 0;
+  private boolean finished = false;
+  private boolean closed = false;
+
+  /**
+   * Reads an archive from {@code inner}.
+   *
+   * @throws IllegalArgumentException if the key is not 32 bytes.
+   */
+  public ArchiveInputStream(InputStream inner, byte[] key) {
+    this.reader = Native.DeviceTransfer_ArchiveReaderNew(key);
+    this.inner = inner;
+  }
+
+  @Override
+  public int read() throws IOException {
+    byte[] bytes = new byte[1];
+    int read = read(bytes, 0, 1);
+    return read == -1 ? -1 : bytes[0] & 0xFF;
+  }
+
+  @Override
+  public int read(byte[] buffer, int offset, int length) throws IOException {
+    if (length == 0) {
+      return 0;
+    }
+    while (this.positionInPayload == this.payload.length) {
+      if (this.finished) {
+        return -1;
+      }
+      int read = this.inner.read(this.readBuffer);
+      if (read == -1) {
+        this.finished = true;
+        filterExceptions(
+            IOException.class, () -> Native.DeviceTransfer_ArchiveReaderFinish(this.reader));
+        return -1;
+      }
+      this.payload =
+          filterExceptions(
+              IOException.class,
+              () ->
+                  Native.DeviceTransfer_ArchiveReaderUpdate(
+                      this.reader, this.readBuffer, 0, read));
+      this.positionInPayload = 0;
+    }
+    int count = Math.min(length, this.payload.length - this.positionInPayload);
+    System.arraycopy(this.payload, this.positionInPayload, buffer, offset, count);
+    this.positionInPayload += count;
+    return count;
+  }
+
+  @Override
+  public int available() {
+    return this.payload.length - this.positionInPayload;
+  }
+
+  @Override
+  public void close() throws IOException {
+    if (this.closed) {
+      return;
+    }
+    this.closed = true;
+    Native.DeviceTransferArchiveReader_Destroy(this.reader);
+    this.inner.close();
+  }
+}
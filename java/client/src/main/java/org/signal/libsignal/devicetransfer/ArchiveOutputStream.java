@@ -0,0 +1,77 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.devicetransfer;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import java.io.IOException;
+import java.io.OutputStream;
+import org.signal.libsignal.internal.Native;
+
+/**
+ * Encrypts a payload of known length into an archive, to be sent during a device transfer.
+ *
+ * <p>Encrypted chunks are written to the underlying stream as they fill up. {@link #close()} checks
+ * that exactly the declared number of bytes were written.
+ */
+public final class ArchiveOutputStream extends OutputStream {
+  private final long writer;
+  private final OutputStream inner;
+  private boolean closed = false;
+
+  /**
+   * Starts an archive with a payload of {@code totalLength} bytes.
+   *
+   * @throws IllegalArgumentException if the key is not 32 bytes, or the payload is too long.
+   */
+  public ArchiveOutputStream(OutputStream inner, byte[] key, long totalLength) {
+    this.writer = filterExceptions(() -> Native.DeviceTransfer_ArchiveWriterNew(key, totalLength));
+    this.inner = inner;
+  }
+
+  @Override
+  public void write(int b) throws IOException {
+    // According to the spec the narrowing conversion to byte is expected here
+    byte[] bytes = {(byte) b};
+    write(bytes, 0, 1);
+  }
+
+  @Override
+  public void write(byte[] buffer, int offset, int length) throws IOException {
+    byte[] archiveBytes =
+        filterExceptions(
+            IOException.class,
+            () -> Native.DeviceTransfer_ArchiveWriterUpdate(this.writer, buffer, offset, length));
+    this.inner.write(archiveBytes);
+  }
+
+  @Override
+  public void flush() throws IOException {
+    this.inner.flush();
+  }
+
+  /**
+   * Writes the rest of the archive and closes the underlying stream.
+   *
+   * @throws IOException if fewer bytes were written than declared, or the underlying stream fails.
+   */
+  @Override
+  public void close() throws IOException {
+    if (this.closed) {
+      return;
+    }
+    this.closed = true;
+    try {
+      byte[] archiveBytes =
+          filterExceptions(
+              IOException.class, () -> Native.DeviceTransfer_ArchiveWriterFinish(this.writer));
+      this.inner.write(archiveBytes);
+    } finally {
+      Native.DeviceTransferArchiveWriter_Destroy(this.writer);
+      this.inner.close();
+    }
+  }
+}
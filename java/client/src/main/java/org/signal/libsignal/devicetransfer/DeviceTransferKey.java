@@ -16,6 +16,15 @@ public class DeviceTransferKey {
     this.keyMaterial = Native.DeviceTransfer_GeneratePrivateKey();
   }
 
+  private DeviceTransferKey(byte[] keyMaterial) {
+    this.keyMaterial = keyMaterial;
+  }
+
+  /** Generates an Ed25519 key, which is much faster to generate than the default RSA key. */
+  public static DeviceTransferKey generateEd25519() {
+    return new DeviceTransferKey(Native.DeviceTransfer_GenerateEd25519PrivateKey());
+  }
+
   public byte[] keyMaterial() {
     return this.keyMaterial;
   }
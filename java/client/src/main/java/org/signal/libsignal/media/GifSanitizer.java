@@ -0,0 +1,40 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.media;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import java.io.IOException;
+import java.io.InputStream;
+import org.signal.libsignal.internal.Native;
+
+/**
+ * A GIF format sanitizer.
+ *
+ * <p>The sanitizer checks the validity of a GIF file input and removes blocks that aren't needed to
+ * display it, such as comments and XMP metadata.
+ */
+public class GifSanitizer {
+
+  /**
+   * Sanitize a GIF input.
+   *
+   * <p>The input is read until the end of the GIF file; anything after that is ignored.
+   *
+   * @param input A GIF format input stream.
+   * @return The sanitized GIF file and its dimensions.
+   * @throws IOException If an IO error on the input occurs.
+   * @throws ParseException If the input could not be parsed.
+   */
+  public static SanitizedImage sanitize(InputStream input) throws IOException, ParseException {
+    long sanitizedImageHandle =
+        filterExceptions(
+            IOException.class,
+            ParseException.class,
+            () -> Native.GifSanitizer_Sanitize(TrustedSkipInputStream.makeTrusted(input)));
+    return SanitizedImage.fromNativeHandle(sanitizedImageHandle);
+  }
+}
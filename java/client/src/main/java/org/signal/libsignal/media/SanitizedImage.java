@@ -0,0 +1,72 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.media;
+
+import org.signal.libsignal.internal.Native;
+
+/** A GIF or WebP file with its metadata removed, along with its dimensions. */
+public class SanitizedImage {
+
+  private byte[] data;
+  private int width;
+  private int height;
+  private int frameCount;
+
+  public SanitizedImage(byte[] data, int width, int height, int frameCount) {
+    this.data = data;
+    this.width = width;
+    this.height = height;
+    this.frameCount = frameCount;
+  }
+
+  static SanitizedImage fromNativeHandle(long sanitizedImageHandle) {
+    try {
+      return new SanitizedImage(
+          Native.SanitizedImage_GetData(sanitizedImageHandle),
+          Native.SanitizedImage_GetWidth(sanitizedImageHandle),
+          Native.SanitizedImage_GetHeight(sanitizedImageHandle),
+          Native.SanitizedImage_GetFrameCount(sanitizedImageHandle));
+    } finally {
+      Native.SanitizedImage_Destroy(sanitizedImageHandle);
+    }
+  }
+
+  /**
+   * Get the sanitized file.
+   *
+   * @return The sanitized file, ready to be sent in place of the input.
+   */
+  public byte[] getData() {
+    return data;
+  }
+
+  /**
+   * Get the width of the image.
+   *
+   * @return The width of the image, in pixels.
+   */
+  public int getWidth() {
+    return width;
+  }
+
+  /**
+   * Get the height of the image.
+   *
+   * @return The height of the image, in pixels.
+   */
+  public int getHeight() {
+    return height;
+  }
+
+  /**
+   * Get the number of frames in the image.
+   *
+   * @return The number of frames in the image; 1 unless it is animated.
+   */
+  public int getFrameCount() {
+    return frameCount;
+  }
+}
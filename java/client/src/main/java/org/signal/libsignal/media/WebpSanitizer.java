@@ -36,6 +36,30 @@ public class WebpSanitizer {
         () -> Native.WebpSanitizer_Sanitize(TrustedSkipInputStream.makeTrusted(input)));
   }
 
+  /**
+   * Sanitize a WebP input, removing its metadata.
+   *
+   * <p>The input is checked as by {@link #sanitize(InputStream)} and copied as it is read, without
+   * its EXIF and XMP metadata or any unknown chunks. The bodies of the chunks that are left out are
+   * skipped rather than read.
+   *
+   * @param input A WebP format input stream.
+   * @return The sanitized WebP file and its dimensions.
+   * @throws IOException If an IO error on the input occurs.
+   * @throws ParseException If the input could not be parsed.
+   */
+  public static SanitizedImage sanitizeAndStripMetadata(InputStream input)
+      throws IOException, ParseException {
+    long sanitizedImageHandle =
+        filterExceptions(
+            IOException.class,
+            ParseException.class,
+            () ->
+                Native.WebpSanitizer_SanitizeAndStripMetadata(
+                    TrustedSkipInputStream.makeTrusted(input)));
+    return SanitizedImage.fromNativeHandle(sanitizedImageHandle);
+  }
+
   /**
    * Sanitize a WebP input.
    *
@@ -36,7 +36,7 @@ public class MessageBackup {
     DEVICE_TRANSFER(0),
     REMOTE_BACKUP(1);
 
-    private final int value;
+    final int value;
 
     private Purpose(int value) {
       this.value = value;
@@ -62,22 +62,49 @@ public class MessageBackup {
     InputStream first = streamFactory.get();
     InputStream second = streamFactory.get();
 
-    Pair<String, String[]> result;
     try (NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
-
-      Object output =
+      return validationResult(
           filterExceptions(
               IOException.class,
               ValidationError.class,
               () ->
                   Native.MessageBackupValidator_Validate(
-                      keyGuard.nativeHandle(), first, second, streamLength, purpose.value));
+                      keyGuard.nativeHandle(), first, second, streamLength, purpose.value)));
+    }
+  }
 
-      // Rust conversion code is generating an instance of this class.
-      @SuppressWarnings("unchecked")
-      Pair<String, String[]> outputPair = (Pair<String, String[]>) output;
-      result = outputPair;
+  /**
+   * Validates an encrypted message backup bundle in a single pass over {@code stream}.
+   *
+   * <p>Unlike {@link #validate(MessageBackupKey, Purpose, Supplier, long)}, this doesn't need the
+   * length of the input up front or a second copy of the stream.
+   *
+   * <p>Returns an error if the input cannot be read or if validation fails.
+   *
+   * @param key the key to use to decrypt the backup
+   * @param purpose whether the input was created for device-to-device transfer or remote backup
+   * @param stream an <code>InputStream</code> that produces the input
+   * @return informational result about the successful validation
+   * @throws ValidationError with an error message if the input is invalid
+   * @throws IOException if the input could not be read
+   */
+  public static ValidationResult validate(MessageBackupKey key, Purpose purpose, InputStream stream)
+      throws ValidationError, IOException {
+    try (NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
+      return validationResult(
+          filterExceptions(
+              IOException.class,
+              ValidationError.class,
+              () ->
+                  Native.MessageBackupValidator_ValidateStream(
+                      keyGuard.nativeHandle(), stream, purpose.value)));
     }
+  }
+
+  private static ValidationResult validationResult(Object output) throws ValidationError {
+    // Rust conversion code is generating an instance of this class.
+    @SuppressWarnings("unchecked")
+    Pair<String, String[]> result = (Pair<String, String[]>) output;
 
     String errorMessage = result.first();
     if (errorMessage != null) {
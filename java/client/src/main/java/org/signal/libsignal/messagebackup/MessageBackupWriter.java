@@ -0,0 +1,83 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import org.signal.libsignal.internal.FilterExceptions;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * Produces an encrypted message backup bundle from serialized {@code Frame}s.
+ *
+ * <p>Each frame is validated before it is written, using the same checks as {@link
+ * MessageBackup#validate}, so a frame that would make the backup invalid is rejected without being
+ * written. The encrypted bundle is returned a piece at a time, and is only complete once {@link
+ * #finish} succeeds.
+ */
+public class MessageBackupWriter extends NativeHandleGuard.SimpleOwner {
+  /**
+   * Starts a new backup bundle.
+   *
+   * @param key the key to encrypt the backup with
+   * @param purpose whether the backup is for device-to-device transfer or remote backup
+   * @param backupInfo the serialized {@code BackupInfo} that the bundle starts with
+   * @throws IllegalArgumentException if {@code backupInfo} can't be parsed
+   */
+  public MessageBackupWriter(
+      MessageBackupKey key, MessageBackup.Purpose purpose, byte[] backupInfo) {
+    super(newWriter(key, purpose, backupInfo));
+  }
+
+  private static long newWriter(
+      MessageBackupKey key, MessageBackup.Purpose purpose, byte[] backupInfo) {
+    try (NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
+      return filterExceptions(
+          () -> Native.MessageBackupWriter_New(keyGuard.nativeHandle(), backupInfo, purpose.value));
+    }
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.MessageBackupWriter_Destroy(nativeHandle);
+  }
+
+  /**
+   * Validates and writes a serialized {@code Frame}.
+   *
+   * <p>A rejected frame leaves the writer usable.
+   *
+   * @param frame the serialized frame
+   * @return the next piece of the encrypted bundle, which may be empty
+   * @throws ValidationError if the frame is rejected, or the writer has been finished
+   */
+  public byte[] writeFrame(byte[] frame) throws ValidationError {
+    return rethrowAsValidationError(
+        () -> guardedMapChecked((writer) -> Native.MessageBackupWriter_WriteFrame(writer, frame)));
+  }
+
+  /**
+   * Checks that the backup is complete and returns the rest of the encrypted bundle.
+   *
+   * <p>The writer can't be used afterwards, even if this fails.
+   *
+   * @return the last piece of the encrypted bundle
+   * @throws ValidationError if the backup is incomplete, or the writer has already been finished
+   */
+  public byte[] finish() throws ValidationError {
+    return rethrowAsValidationError(() -> guardedMapChecked(Native::MessageBackupWriter_Finish));
+  }
+
+  private static byte[] rethrowAsValidationError(
+      FilterExceptions.ThrowingNativeOperation<byte[]> f) throws ValidationError {
+    try {
+      return filterExceptions(f);
+    } catch (IllegalArgumentException e) {
+      throw new ValidationError(e.getMessage(), new String[0]);
+    }
+  }
+}
@@ -21,12 +21,15 @@ class CdsiLookup implements NativeHandleGuard.Owner {
         NativeHandleGuard connectionManager =
             new NativeHandleGuard(network.getConnectionManager())) {
 
-      return Native.CdsiLookup_new(
-              asyncRuntime.nativeHandle(),
-              connectionManager.nativeHandle(),
-              username,
-              password,
-              nativeRequest.getHandle())
+      return network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.CdsiLookup_new(
+                  asyncRuntime.nativeHandle(),
+                  connectionManager.nativeHandle(),
+                  username,
+                  password,
+                  nativeRequest.getHandle()))
           .thenApply((Long nativeHandle) -> new CdsiLookup(nativeHandle, network));
     }
   }
@@ -34,7 +37,10 @@ class CdsiLookup implements NativeHandleGuard.Owner {
   public CompletableFuture<CdsiLookupResponse> complete() {
     try (NativeHandleGuard asyncRuntime = new NativeHandleGuard(this.network.getAsyncContext());
         NativeHandleGuard self = new NativeHandleGuard(this)) {
-      return Native.CdsiLookup_complete(asyncRuntime.nativeHandle(), self.nativeHandle())
+      return this.network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.CdsiLookup_complete(asyncRuntime.nativeHandle(), self.nativeHandle()))
           .thenApply(response -> (CdsiLookupResponse) response);
     }
   }
@@ -0,0 +1,38 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+/**
+ * Receives messages pushed by the Chat Service over an authenticated connection.
+ *
+ * <p>Callbacks are made on a background thread, one at a time, in the order the server sent them.
+ *
+ * @see ChatService#setListener(ChatListener)
+ */
+public interface ChatListener {
+  /**
+   * Called for each message delivered by the server.
+   *
+   * <p>Every message must be acknowledged exactly once using {@code ack}.
+   *
+   * @param envelope the serialized envelope
+   * @param serverDeliveryTimestamp the time the server delivered the message, in milliseconds since
+   *     the epoch
+   */
+  void onIncomingMessage(
+      ChatService chat, byte[] envelope, long serverDeliveryTimestamp, ServerMessageAck ack);
+
+  /** Called once all messages queued while the client was offline have been delivered. */
+  void onQueueEmpty(ChatService chat);
+
+  /**
+   * Called when the authenticated connection is lost for any reason other than a call to {@link
+   * ChatService#disconnect()}.
+   *
+   * <p>Messages will stop arriving until the service is connected again.
+   */
+  default void onConnectionInterrupted(ChatService chat, ChatServiceException disconnectCause) {}
+}
@@ -5,8 +5,10 @@
 
 package org.signal.libsignal.net;
 
+import java.lang.ref.WeakReference;
 import java.net.MalformedURLException;
 import java.util.Map;
+import org.signal.libsignal.internal.BridgeChatListener;
 import org.signal.libsignal.internal.CalledFromNative;
 import org.signal.libsignal.internal.CompletableFuture;
 import org.signal.libsignal.internal.FilterExceptions;
@@ -35,6 +37,12 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
     this.tokioAsyncContext = tokioAsyncContext;
   }
 
+  /** Wraps an existing native chat service, such as one connected to a fake server in tests. */
+  ChatService(final TokioAsyncContext tokioAsyncContext, final long nativeHandle) {
+    super(nativeHandle);
+    this.tokioAsyncContext = tokioAsyncContext;
+  }
+
   /**
    * Initiates termination of the underlying connection to the Chat Service. After the service is
    * disconnected, it will not attempt to automatically reconnect until you call {@link
@@ -75,7 +83,9 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
         asyncContextHandle ->
             guardedMap(
                 chatServiceHandle ->
-                    Native.ChatService_connect_auth(asyncContextHandle, chatServiceHandle)
+                    tokioAsyncContext
+                        .makeCancellable(
+                            Native.ChatService_connect_auth(asyncContextHandle, chatServiceHandle))
                         .thenApply(o -> (DebugInfo) o)));
   }
 
@@ -97,10 +107,36 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
         asyncContextHandle ->
             guardedMap(
                 chatServiceHandle ->
-                    Native.ChatService_connect_unauth(asyncContextHandle, chatServiceHandle)
+                    tokioAsyncContext
+                        .makeCancellable(
+                            Native.ChatService_connect_unauth(asyncContextHandle, chatServiceHandle))
                         .thenApply(o -> (DebugInfo) o)));
   }
 
+  /**
+   * Warms up the connections to the Chat Service ahead of the first request, to hide the latency of
+   * establishing them.
+   *
+   * <p>The authenticated connection is fully established, as with {@link #connectAuthenticated()}.
+   * For the unauthenticated connection, only the DNS lookup and the TCP and TLS handshakes are done
+   * ahead of time; the result is kept for a short while and used by the next call to {@link
+   * #connectUnauthenticated()}.
+   *
+   * <p>The resulting future may fail with {@link AppExpiredException} or {@link
+   * DeviceDeregisteredException} (inside an {@link java.util.concurrent.ExecutionException
+   * ExecutionException}), along with other {@link ChatServiceException}s.
+   *
+   * @return a future that completes when the connections are ready.
+   */
+  public CompletableFuture<Void> preconnect() {
+    return tokioAsyncContext.guardedMap(
+        asyncContextHandle ->
+            guardedMap(
+                chatServiceHandle ->
+                    tokioAsyncContext.makeCancellable(
+                        Native.ChatService_preconnect(asyncContextHandle, chatServiceHandle))));
+  }
+
   /**
    * Sends request to the Chat Service over an unauthenticated channel.
    *
@@ -210,6 +246,75 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
     }
   }
 
+  /**
+   * Sets the listener for messages pushed by the server, replacing any previous listener.
+   *
+   * <p>Passing {@code null} stops delivering messages. Messages that arrive while there is no
+   * listener are left unacknowledged, so the server will deliver them again.
+   *
+   * <p>The listener is held strongly until it is replaced or this service is closed.
+   */
+  public void setListener(final ChatListener listener) {
+    final BridgeChatListener bridgeListener =
+        listener != null ? new ListenerBridge(this, listener) : null;
+    tokioAsyncContext.guardedRun(
+        asyncContextHandle ->
+            guardedRun(
+                chatServiceHandle ->
+                    Native.ChatServer_SetListener(
+                        asyncContextHandle, chatServiceHandle, bridgeListener)));
+  }
+
+  /**
+   * Adapts a {@link ChatListener} to the callbacks made from Rust.
+   *
+   * <p>The service is held weakly, since it (indirectly) owns the bridge.
+   */
+  private static class ListenerBridge implements BridgeChatListener {
+    private final WeakReference<ChatService> chatService;
+    private final TokioAsyncContext tokioAsyncContext;
+    private final ChatListener listener;
+
+    ListenerBridge(final ChatService chatService, final ChatListener listener) {
+      this.chatService = new WeakReference<>(chatService);
+      this.tokioAsyncContext = chatService.tokioAsyncContext;
+      this.listener = listener;
+    }
+
+    @Override
+    public void onIncomingMessage(
+        final byte[] envelope, final long serverDeliveryTimestamp, final long ackHandle) {
+      final ServerMessageAck ack = new ServerMessageAck(tokioAsyncContext, ackHandle);
+      final ChatService chatService = this.chatService.get();
+      if (chatService == null) {
+        return;
+      }
+      listener.onIncomingMessage(chatService, envelope, serverDeliveryTimestamp, ack);
+    }
+
+    @Override
+    public void onQueueEmpty() {
+      final ChatService chatService = this.chatService.get();
+      if (chatService == null) {
+        return;
+      }
+      listener.onQueueEmpty(chatService);
+    }
+
+    @Override
+    public void onConnectionInterrupted(final Throwable disconnectCause) {
+      final ChatService chatService = this.chatService.get();
+      if (chatService == null) {
+        return;
+      }
+      final ChatServiceException cause =
+          disconnectCause instanceof ChatServiceException chatServiceException
+              ? chatServiceException
+              : new ChatServiceException(disconnectCause.getMessage());
+      listener.onConnectionInterrupted(chatService, cause);
+    }
+  }
+
   static InternalRequest buildInternalRequest(final Request req) throws MalformedURLException {
     final InternalRequest result =
         new InternalRequest(req.method(), req.pathAndQuery(), req.body());
@@ -231,6 +336,10 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
               () -> Native.HttpRequest_new(method, pathAndQuery, body)));
     }
 
+    InternalRequest(final long nativeHandle) {
+      super(nativeHandle);
+    }
+
     @Override
     protected void release(final long nativeHandle) {
       Native.HttpRequest_Destroy(nativeHandle);
@@ -0,0 +1,101 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import java.util.Optional;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * Something that happened while establishing a connection.
+ *
+ * <p>Events never contain proxy hostnames or IP addresses, only the (public) hostnames of the
+ * services being connected to. See {@link Network#takeConnectionEvents()}.
+ */
+public class ConnectionEvent extends NativeHandleGuard.SimpleOwner {
+  /** The order of values in this enum should match {@code ConnectionEvent} in libsignal-net. */
+  public enum Kind {
+    DNS_LOOKUP,
+    TCP_CONNECT,
+    TLS_HANDSHAKE,
+    WEB_SOCKET_UPGRADE,
+    ROUTE_ATTEMPT,
+    ROUTE_COOLING_DOWN
+  }
+
+  ConnectionEvent(long nativeHandle) {
+    super(nativeHandle);
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.ConnectionEvent_Destroy(nativeHandle);
+  }
+
+  public Kind getKind() {
+    return Kind.values()[guardedMap(Native::ConnectionEvent_GetKind)];
+  }
+
+  /** The kind of route used, e.g. {@code direct} or {@code proxyf}. */
+  public String getRoute() {
+    return guardedMap(Native::ConnectionEvent_GetRoute);
+  }
+
+  /** The service being connected to; empty for DNS lookups and TCP connections. */
+  public Optional<String> getHost() {
+    return Optional.ofNullable(guardedMap(Native::ConnectionEvent_GetHost));
+  }
+
+  /**
+   * How long the step took or, for {@link Kind#ROUTE_COOLING_DOWN}, how long the route has left
+   * to cool down.
+   */
+  public int getDurationMillis() {
+    return guardedMap(Native::ConnectionEvent_GetDurationMillis);
+  }
+
+  /**
+   * Whether a {@link Kind#TCP_CONNECT} or {@link Kind#TLS_HANDSHAKE} succeeded; {@code false} for
+   * other kinds of events.
+   */
+  public boolean getSucceeded() {
+    return guardedMap(Native::ConnectionEvent_GetSucceeded);
+  }
+
+  /** Where a DNS lookup's result came from; empty if the lookup failed. */
+  public Optional<String> getDnsSource() {
+    return Optional.ofNullable(guardedMap(Native::ConnectionEvent_GetDnsSource));
+  }
+
+  /** The IP version of a {@link Kind#TCP_CONNECT}. */
+  public Optional<IpType> getIpType() {
+    int ipType = guardedMap(Native::ConnectionEvent_GetIpType);
+    return ipType == -1 ? Optional.empty() : Optional.of(IpType.values()[ipType]);
+  }
+
+  /** The HTTP status of the server's response to a {@link Kind#WEB_SOCKET_UPGRADE}, if any. */
+  public Optional<Integer> getHttpStatus() {
+    int status = guardedMap(Native::ConnectionEvent_GetHttpStatus);
+    return status == -1 ? Optional.empty() : Optional.of(status);
+  }
+
+  /**
+   * How a {@link Kind#ROUTE_ATTEMPT} ended: {@code succeeded}, {@code failed}, or {@code
+   * timedOut}.
+   */
+  public Optional<String> getRouteAttemptOutcome() {
+    return Optional.ofNullable(guardedMap(Native::ConnectionEvent_GetRouteAttemptOutcome));
+  }
+
+  /**
+   * How many times in a row the route of a {@link Kind#ROUTE_ATTEMPT} has failed, counting this
+   * attempt.
+   */
+  public Optional<Integer> getConsecutiveFailures() {
+    int failures = guardedMap(Native::ConnectionEvent_GetConsecutiveFailures);
+    return failures == -1 ? Optional.empty() : Optional.of(failures);
+  }
+}
@@ -8,6 +8,8 @@ package org.signal.libsignal.net;
 import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 
 import java.io.IOException;
+import java.util.ArrayList;
+import java.util.List;
 import java.util.concurrent.ExecutionException;
 import java.util.function.Consumer;
 import org.signal.libsignal.internal.CompletableFuture;
@@ -43,6 +45,21 @@ public class Network {
     this.svr3 = new Svr3(this);
   }
 
+  /**
+   * Like {@link #Network(Environment, String)}, but with different timeouts.
+   *
+   * <p>{@code connectTimeoutMillis} bounds each attempt to connect to a server over a single route,
+   * for chat, CDSI, and SVR3 alike. {@code requestTimeoutMillis} is how long to wait for each
+   * response from CDSI or SVR3 once connected.
+   */
+  public Network(
+      Environment env, String userAgent, int connectTimeoutMillis, int requestTimeoutMillis) {
+    this.tokioAsyncContext = new TokioAsyncContext();
+    this.connectionManager =
+        new ConnectionManager(env, userAgent, connectTimeoutMillis, requestTimeoutMillis);
+    this.svr3 = new Svr3(this);
+  }
+
   /**
    * Sets the proxy host to be used for all new connections (until overridden).
    *
@@ -56,16 +73,102 @@ public class Network {
     this.connectionManager.setProxy(host, port);
   }
 
+  /**
+   * Sets a SOCKS5 proxy to be used for all new connections (until overridden).
+   *
+   * <p>Hostnames of Signal services are passed to the proxy unresolved, so DNS lookups for them
+   * happen on the proxy's side. {@code username} and {@code password} must either both be provided
+   * or both be null.
+   *
+   * @throws IOException if the host, port, or credentials are not (structurally) valid, such as a
+   *     port that doesn't fit in u16.
+   */
+  public void setSocksProxy(String host, int port, String username, String password)
+      throws IOException {
+    this.connectionManager.setSocksProxy(host, port, username, password);
+  }
+
+  /**
+   * Sets an HTTP CONNECT proxy to be used for all new connections (until overridden).
+   *
+   * <p>Hostnames of Signal services are passed to the proxy unresolved, so DNS lookups for them
+   * happen on the proxy's side. {@code username} and {@code password} must either both be provided
+   * or both be null.
+   *
+   * @throws IOException if the host, port, or credentials are not (structurally) valid, such as a
+   *     port that doesn't fit in u16.
+   */
+  public void setHttpProxy(String host, int port, String username, String password)
+      throws IOException {
+    this.connectionManager.setHttpProxy(host, port, username, password);
+  }
+
   /**
    * Ensures that future connections will be made directly, not through a proxy.
    *
-   * <p>Clears any proxy configuration set via {@link #setProxy}. If none was set, calling this
+   * <p>Clears any proxy configuration set via {@link #setProxy}, {@link #setSocksProxy}, or {@link
+   * #setHttpProxy}. If none was set, calling this
    * method is a no-op.
    */
   public void clearProxy() {
     this.connectionManager.clearProxy();
   }
 
+  /**
+   * Changes how long to wait before retrying a server that couldn't be reached.
+   *
+   * <p>After each consecutive failure the delay grows by {@code multiplierPercent} percent of
+   * itself (200 doubles it), starting at {@code initialDelayMillis} and capped at {@code
+   * maxDelayMillis}. Up to {@code jitterPercent} percent of each delay is randomly taken off it,
+   * so that many clients don't all retry at once. After {@code maxAttempts} consecutive failures,
+   * the server is not retried for a long cooldown, after which the attempts start over; pass 0 to
+   * keep retrying indefinitely. This can be used to back off more aggressively while the app is in
+   * the background.
+   */
+  public void setBackoffPolicy(
+      int initialDelayMillis,
+      int maxDelayMillis,
+      int multiplierPercent,
+      int jitterPercent,
+      int maxAttempts) {
+    this.connectionManager.setBackoffPolicy(
+        initialDelayMillis, maxDelayMillis, multiplierPercent, jitterPercent, maxAttempts);
+  }
+
+  /**
+   * Like {@link #setBackoffPolicy(int, int, int, int, int)}, doubling the delay after each failure
+   * and taking up to 20% off it at random.
+   */
+  public void setBackoffPolicy(int initialDelayMillis, int maxDelayMillis, int maxAttempts) {
+    setBackoffPolicy(initialDelayMillis, maxDelayMillis, 200, 20, maxAttempts);
+  }
+
+  /** Restores the default delays between attempts to reach a server. */
+  public void resetBackoffPolicy() {
+    this.connectionManager.resetBackoffPolicy();
+  }
+
+  /**
+   * Returns the connection events recorded since the last call, and forgets them.
+   *
+   * <p>Events are returned oldest first. Only the most recent events are kept, so this should be
+   * called regularly if events are being collected.
+   */
+  public List<ConnectionEvent> takeConnectionEvents() {
+    return this.connectionManager.takeConnectionEvents();
+  }
+
+  /**
+   * Notifies libsignal that the device has switched networks, e.g. from Wi-Fi to cellular.
+   *
+   * <p>Cached DNS results and delays before retrying unreachable servers are discarded, connection
+   * attempts in progress are abandoned, and open connections are re-established on the new
+   * network.
+   */
+  public void networkChanged() {
+    this.connectionManager.networkChanged();
+  }
+
   public Svr3 svr3() {
     return this.svr3;
   }
@@ -125,16 +228,75 @@ public class Network {
       super(Native.ConnectionManager_new(env.value, userAgent));
     }
 
+    private ConnectionManager(
+        Environment env, String userAgent, int connectTimeoutMillis, int requestTimeoutMillis) {
+      super(
+          Native.ConnectionManager_new_with_timeouts(
+              env.value, userAgent, connectTimeoutMillis, requestTimeoutMillis));
+    }
+
     private void setProxy(String host, int port) throws IOException {
       filterExceptions(
           IOException.class,
           () -> guardedRunChecked(h -> Native.ConnectionManager_set_proxy(h, host, port)));
     }
 
+    private void setSocksProxy(String host, int port, String username, String password)
+        throws IOException {
+      filterExceptions(
+          IOException.class,
+          () ->
+              guardedRunChecked(
+                  h -> Native.ConnectionManager_set_socks_proxy(h, host, port, username, password)));
+    }
+
+    private void setHttpProxy(String host, int port, String username, String password)
+        throws IOException {
+      filterExceptions(
+          IOException.class,
+          () ->
+              guardedRunChecked(
+                  h -> Native.ConnectionManager_set_http_proxy(h, host, port, username, password)));
+    }
+
     private void clearProxy() {
       guardedRun(Native::ConnectionManager_clear_proxy);
     }
 
+    private void setBackoffPolicy(
+        int initialDelayMillis,
+        int maxDelayMillis,
+        int multiplierPercent,
+        int jitterPercent,
+        int maxAttempts) {
+      guardedRun(
+          h ->
+              Native.ConnectionManager_set_backoff_policy(
+                  h,
+                  initialDelayMillis,
+                  maxDelayMillis,
+                  multiplierPercent,
+                  jitterPercent,
+                  maxAttempts));
+    }
+
+    private void resetBackoffPolicy() {
+      guardedRun(Native::ConnectionManager_reset_backoff_policy);
+    }
+
+    private List<ConnectionEvent> takeConnectionEvents() {
+      List<ConnectionEvent> events = new ArrayList<>();
+      long event;
+      while ((event = guardedMap(Native::ConnectionManager_take_connection_event)) != 0) {
+        events.add(new ConnectionEvent(event));
+      }
+      return events;
+    }
+
+    private void networkChanged() {
+      guardedRun(Native::ConnectionManager_network_changed);
+    }
+
     @Override
     protected void release(final long nativeHandle) {
       Native.ConnectionManager_Destroy(nativeHandle);
@@ -0,0 +1,55 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import org.signal.libsignal.internal.CompletableFuture;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * Acknowledges a message delivered to a {@link ChatListener}.
+ *
+ * <p>Each message must be acknowledged exactly once, with either {@link #send()} or {@link
+ * #sendNack()}.
+ */
+public class ServerMessageAck extends NativeHandleGuard.SimpleOwner {
+  private final TokioAsyncContext tokioAsyncContext;
+
+  ServerMessageAck(final TokioAsyncContext tokioAsyncContext, final long nativeHandle) {
+    super(nativeHandle);
+    this.tokioAsyncContext = tokioAsyncContext;
+  }
+
+  /**
+   * Tells the server the message was processed, so it can be removed from the queue.
+   *
+   * @return a future that completes once the acknowledgement has been sent.
+   */
+  public CompletableFuture<Void> send() {
+    return tokioAsyncContext.guardedMap(
+        asyncContextHandle ->
+            guardedMap(ackHandle -> Native.ServerMessageAck_Send(asyncContextHandle, ackHandle)));
+  }
+
+  /**
+   * Tells the server the message could not be processed right now.
+   *
+   * <p>The message stays queued and will be delivered again later.
+   *
+   * @return a future that completes once the response has been sent.
+   */
+  public CompletableFuture<Void> sendNack() {
+    return tokioAsyncContext.guardedMap(
+        asyncContextHandle ->
+            guardedMap(
+                ackHandle -> Native.ServerMessageAck_SendNack(asyncContextHandle, ackHandle)));
+  }
+
+  @Override
+  protected void release(final long nativeHandle) {
+    Native.ServerMessageAck_Destroy(nativeHandle);
+  }
+}
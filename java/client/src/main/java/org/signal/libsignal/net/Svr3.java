@@ -85,14 +85,17 @@ public final class Svr3 {
         NativeHandleGuard connectionManager =
             new NativeHandleGuard(this.network.getConnectionManager())) {
 
-      return Native.Svr3Backup(
-          asyncRuntime.nativeHandle(),
-          connectionManager.nativeHandle(),
-          what,
-          password,
-          maxTries,
-          auth.username,
-          auth.password);
+      return this.network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.Svr3Backup(
+                  asyncRuntime.nativeHandle(),
+                  connectionManager.nativeHandle(),
+                  what,
+                  password,
+                  maxTries,
+                  auth.username,
+                  auth.password));
     }
   }
 
@@ -145,13 +148,16 @@ public final class Svr3 {
         NativeHandleGuard connectionManager =
             new NativeHandleGuard(this.network.getConnectionManager())) {
 
-      return Native.Svr3Restore(
-              asyncRuntime.nativeHandle(),
-              connectionManager.nativeHandle(),
-              password,
-              shareSet,
-              auth.username,
-              auth.password)
+      return this.network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.Svr3Restore(
+                  asyncRuntime.nativeHandle(),
+                  connectionManager.nativeHandle(),
+                  password,
+                  shareSet,
+                  auth.username,
+                  auth.password))
           .thenApply(RestoredSecret::deserialize);
     }
   }
@@ -190,11 +196,117 @@ public final class Svr3 {
         NativeHandleGuard connectionManager =
             new NativeHandleGuard(this.network.getConnectionManager())) {
 
-      return Native.Svr3Remove(
-          asyncRuntime.nativeHandle(),
-          connectionManager.nativeHandle(),
-          auth.username,
-          auth.password);
+      return this.network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.Svr3Remove(
+                  asyncRuntime.nativeHandle(),
+                  connectionManager.nativeHandle(),
+                  auth.username,
+                  auth.password));
+    }
+  }
+
+  /**
+   * Move a secret backed up to the SVR3 enclaves of {@code previous} to the ones of this instance.
+   *
+   * <p>The secret is restored from the previous enclaves, using up one of its tries there, then
+   * backed up again here with {@code maxTries} tries. The share set from the previous backup is no
+   * longer needed once the returned Future completes; the new one should be stored instead.
+   *
+   * <p>If the backup to the new enclaves fails, {@code state} keeps hold of the restored secret.
+   * Calling this method again with the same {@code state} then skips the previous enclaves
+   * entirely, so a failed migration can be retried without using up more tries there. Once the
+   * migration succeeds, the secret is cleared from {@code state}.
+   *
+   * <p>As noted above due to the asynchronous nature of the API all the expected errors will only
+   * be thrown when the Future is awaited, and furthermore will be wrapped in {@link
+   * java.util.concurrent.ExecutionException}.
+   *
+   * <p>Exception messages are log-safe and do not contain any sensitive data.
+   *
+   * @param previous the {@link Svr3} instance of the {@link Network} the secret is currently
+   *     backed up to.
+   * @param state the progress of this migration, to be reused when retrying it.
+   * @param password user-provided password that the secret was backed up with. It is used for the
+   *     new backup as well.
+   * @param shareSet a serialized masked share set returned by a call to {@link #backup} on {@code
+   *     previous}.
+   * @param maxTries number of times the migrated secret will be allowed to be guessed. Must be
+   *     positive.
+   * @param auth an instance of {@link org.signal.libsignal.net.EnclaveAuth} containing the username
+   *     and password obtained from the Chat Server, used for both sets of enclaves.
+   * @return an instance of {@link org.signal.libsignal.internal.CompletableFuture} which-when
+   *     awaited-will return a byte array with the serialized masked share set of the new backup.
+   * @throws {@link org.signal.libsignal.net.NetworkException} in case of network connection errors,
+   *     like connect timeout.
+   * @throws {@link org.signal.libsignal.net.NetworkProtocolException} more specifically in cases
+   *     when connection cannot be established on a higher level of the network stack. For example,
+   *     receiving an error HTTP status code.
+   * @throws {@link org.signal.libsignal.svr.DataMissingException} when the maximum restore attempts
+   *     number has been exceeded on the previous enclaves or if the value has never been backed up
+   *     there.
+   * @throws {@link org.signal.libsignal.svr.RestoreFailedException} when the combination of the
+   *     password and masked share set does not result in successful restoration of the secret.
+   * @throws {@link org.signal.libsignal.svr.SvrException} when the de-serialization of a masked
+   *     share set fails, or when the server requests fail for reasons other than "maximum attempts
+   *     exceeded".
+   * @throws {@link org.signal.libsignal.attest.AttestationDataException} when the server
+   *     attestation document is malformed or incomplete.
+   * @throws {@link org.signal.libsignal.attest.AttestationFailedException} when an attempt to
+   *     validate the server attestation document fails.
+   * @throws {@link org.signal.libsignal.sgxsession.SgxCommunicationFailureException} when a Noise
+   *     connection error happens.
+   */
+  public final CompletableFuture<byte[]> migrate(
+      Svr3 previous,
+      MigrationState state,
+      String password,
+      byte[] shareSet,
+      int maxTries,
+      EnclaveAuth auth) {
+    try (NativeHandleGuard asyncRuntime = new NativeHandleGuard(this.network.getAsyncContext());
+        NativeHandleGuard connectionManager =
+            new NativeHandleGuard(this.network.getConnectionManager());
+        NativeHandleGuard previousConnectionManager =
+            new NativeHandleGuard(previous.network.getConnectionManager());
+        NativeHandleGuard migrationState = new NativeHandleGuard(state)) {
+
+      return this.network
+          .getAsyncContext()
+          .makeCancellable(
+              Native.Svr3Migrate(
+                  asyncRuntime.nativeHandle(),
+                  connectionManager.nativeHandle(),
+                  previousConnectionManager.nativeHandle(),
+                  migrationState.nativeHandle(),
+                  password,
+                  shareSet,
+                  maxTries,
+                  auth.username,
+                  auth.password));
+    }
+  }
+
+  /**
+   * The progress of a {@link #migrate} that can be retried.
+   *
+   * <p>Once the secret has been restored from the previous enclaves, this holds on to it until the
+   * migration succeeds, so it should not be kept around any longer than necessary.
+   */
+  public static final class MigrationState extends NativeHandleGuard.SimpleOwner {
+    public MigrationState() {
+      super(Native.Svr3MigrationState_new());
+    }
+
+    @Override
+    protected void release(long nativeHandle) {
+      Native.Svr3MigrationState_Destroy(nativeHandle);
+    }
+
+    /** Whether the secret has been restored, so the previous enclaves are no longer needed. */
+    public boolean hasRestoredSecret() {
+      return guardedMap(Native::Svr3MigrationState_hasRestoredSecret);
     }
   }
 
@@ -19,6 +19,16 @@ class TokioAsyncContext extends NativeHandleGuard.SimpleOwner {
     return (CompletableFuture<Class<Object>>) Native.AsyncLoadClass(this, className);
   }
 
+  /**
+   * Allows {@code future} to be {@linkplain CompletableFuture#cancel cancelled}, stopping the task
+   * on this context that will complete it.
+   *
+   * <p>{@code future} must have been returned by a native function that was run on this context.
+   */
+  <T> CompletableFuture<T> makeCancellable(CompletableFuture<T> future) {
+    return future.makeCancellable(id -> guardedRun(h -> Native.TokioAsyncContext_cancel(h, id)));
+  }
+
   @Override
   protected void release(final long nativeHandle) {
     Native.TokioAsyncContext_Destroy(nativeHandle);
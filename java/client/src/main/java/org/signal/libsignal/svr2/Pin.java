@@ -37,12 +37,32 @@ public class Pin {
     return filterExceptions(() -> Native.Pin_LocalHash(pin));
   }
 
+  /**
+   * Create an encoded password hash string with a caller-selected Argon2id cost.
+   *
+   * <p>This creates a hashed pin that should be used for local pin verification only. Devices that
+   * can't spare the memory for {@link Pin#localHash} can use less memory and more iterations
+   * instead, but the total cost (memory times iterations) may not go below that of {@link
+   * Pin#localHash}.
+   *
+   * @param pin A normalized, UTF-8 encoded byte representation of the pin
+   * @param memoryKib The memory to use, in KiB; at least 512
+   * @param iterations The number of passes over memory; at least 2
+   * @return A hashed pin string that can be verified later
+   * @throws IllegalArgumentException if the parameters are below the minimums
+   */
+  public static String localHash(final byte[] pin, final int memoryKib, final int iterations) {
+    return filterExceptions(() -> Native.Pin_LocalHashWithParams(pin, memoryKib, iterations));
+  }
+
   /**
    * Verify an encoded password hash against a pin
    *
    * @param encodedHash An encoded string of the hash, as returned by {@link Pin#localHash}
    * @param pin A normalized, UTF-8 encoded byte representation of the pin to verify
    * @return true if the pin matches the hash, false otherwise
+   * @throws IllegalArgumentException if the hash can't be parsed, uses an unsupported algorithm, or
+   *     has a cost below the minimums of {@link Pin#localHash(byte[], int, int)}
    */
   public static boolean verifyLocalHash(final String encodedHash, final byte[] pin) {
     return filterExceptions(() -> Native.Pin_VerifyLocalHash(encodedHash, pin));
@@ -0,0 +1,82 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.devicetransfer;
+
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertThrows;
+
+import java.io.ByteArrayInputStream;
+import java.io.ByteArrayOutputStream;
+import java.io.IOException;
+import java.util.Arrays;
+import org.junit.Test;
+
+public class ArchiveTest {
+  private static final byte[] KEY = new byte[32];
+
+  private static byte[] payload(int length) {
+    byte[] payload = new byte[length];
+    for (int i = 0; i < length; i++) {
+      payload[i] = (byte) (i % 251);
+    }
+    return payload;
+  }
+
+  private static byte[] writeArchive(byte[] payload) throws IOException {
+    ByteArrayOutputStream archive = new ByteArrayOutputStream();
+    try (ArchiveOutputStream writer = new ArchiveOutputStream(archive, KEY, payload.length)) {
+      writer.write(payload, 0, payload.length / 2);
+      writer.write(payload, payload.length / 2, payload.length - payload.length / 2);
+    }
+    return archive.toByteArray();
+  }
+
+  private static byte[] readArchive(byte[] archive) throws IOException {
+    try (ArchiveInputStream reader =
+        new ArchiveInputStream(new ByteArrayInputStream(archive), KEY)) {
+      return reader.readAllBytes();
+    }
+  }
+
+  @Test
+  public void testRoundTrip() throws IOException {
+    for (int length : new int[] {0, 1, 64 * 1024, 200_000}) {
+      byte[] payload = payload(length);
+      assertArrayEquals(payload, readArchive(writeArchive(payload)));
+    }
+  }
+
+  @Test
+  public void testWritingWrongLengthFails() throws IOException {
+    ArchiveOutputStream writer = new ArchiveOutputStream(new ByteArrayOutputStream(), KEY, 10);
+    writer.write(new byte[5]);
+    assertThrows(IOException.class, writer::close);
+  }
+
+  @Test
+  public void testTruncationIsDetected() throws IOException {
+    byte[] archive = writeArchive(payload(200_000));
+    byte[] truncated = Arrays.copyOf(archive, archive.length - 1);
+    assertThrows(IOException.class, () -> readArchive(truncated));
+  }
+
+  @Test
+  public void testAppendedBytesAreDetected() throws IOException {
+    byte[] archive = writeArchive(payload(200_000));
+    byte[] extended = Arrays.copyOf(archive, archive.length + 1);
+    assertThrows(IOException.class, () -> readArchive(extended));
+  }
+
+  @Test
+  public void testWrongKeyIsDetected() throws IOException {
+    byte[] archive = writeArchive(payload(100));
+    byte[] wrongKey = new byte[32];
+    wrongKey[0] = 1;
+    assertThrows(
+        IOException.class,
+        () -> new ArchiveInputStream(new ByteArrayInputStream(archive), wrongKey).readAllBytes());
+  }
+}
@@ -0,0 +1,70 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.media;
+
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertThrows;
+
+import java.io.ByteArrayInputStream;
+import java.io.ByteArrayOutputStream;
+import java.io.IOException;
+import org.junit.Assume;
+import org.junit.Before;
+import org.junit.Test;
+import org.signal.libsignal.internal.Native;
+
+public class GifSanitizerTest {
+  @Before
+  public void checkLibsignalMediaAvailable() {
+    try {
+      Native.SignalMedia_CheckAvailable();
+    } catch (UnsatisfiedLinkError e) {
+      Assume.assumeNoException(e);
+    }
+  }
+
+  @Test
+  public void testEmptyGif() {
+    byte[] data = new byte[] {};
+    assertThrows(
+        "empty gif accepted",
+        ParseException.class,
+        () -> GifSanitizer.sanitize(new ByteArrayInputStream(data)));
+  }
+
+  @Test
+  public void testMinimalGif() throws Exception {
+    byte[] data = gif(new byte[] {});
+    SanitizedImage sanitized = GifSanitizer.sanitize(new ByteArrayInputStream(data));
+    assertArrayEquals(data, sanitized.getData());
+    assertEquals(1, sanitized.getWidth());
+    assertEquals(1, sanitized.getHeight());
+    assertEquals(1, sanitized.getFrameCount());
+  }
+
+  @Test
+  public void testGifCommentRemoved() throws Exception {
+    byte[] comment = new byte[] {0x21, (byte) 0xfe, 2, 'h', 'i', 0};
+    SanitizedImage sanitized = GifSanitizer.sanitize(new ByteArrayInputStream(gif(comment)));
+    assertArrayEquals(gif(new byte[] {}), sanitized.getData());
+  }
+
+  /** A 1x1 GIF with the given extension blocks before its image. */
+  private static byte[] gif(byte[] extensions) throws IOException {
+    ByteArrayOutputStream gifOutputStream = new ByteArrayOutputStream();
+
+    gifOutputStream.write("GIF89a".getBytes()); // header
+    gifOutputStream.write(new byte[] {1, 0, 1, 0, (byte) 0x80, 0, 0}); // logical screen descriptor
+    gifOutputStream.write(new byte[] {0, 0, 0, (byte) 0xff, (byte) 0xff, (byte) 0xff}); // colors
+    gifOutputStream.write(extensions);
+    gifOutputStream.write(new byte[] {0x2c, 0, 0, 0, 0, 1, 0, 1, 0, 0}); // image descriptor
+    gifOutputStream.write(new byte[] {2, 2, 0x44, 0x01, 0}); // image data
+    gifOutputStream.write(new byte[] {0x3b}); // trailer
+
+    return gifOutputStream.toByteArray();
+  }
+}
@@ -5,6 +5,8 @@
 
 package org.signal.libsignal.media;
 
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertEquals;
 import static org.junit.Assert.assertThrows;
 
 import java.io.ByteArrayInputStream;
@@ -51,6 +53,25 @@ public class WebpSanitizerTest {
     WebpSanitizer.sanitize(new ByteArrayInputStream(data));
   }
 
+  @Test
+  public void testMinimalWebpUnchangedByStripping() throws Exception {
+    byte[] data = webp();
+    SanitizedImage sanitized =
+        WebpSanitizer.sanitizeAndStripMetadata(new ByteArrayInputStream(data));
+    assertArrayEquals(data, sanitized.getData());
+    assertEquals(1, sanitized.getWidth());
+    assertEquals(1, sanitized.getHeight());
+    assertEquals(1, sanitized.getFrameCount());
+  }
+
+  @Test
+  public void testWebpExifRemoved() throws Exception {
+    byte[] data = extendedWebp(true);
+    SanitizedImage sanitized =
+        WebpSanitizer.sanitizeAndStripMetadata(new ByteArrayInputStream(data));
+    assertArrayEquals(extendedWebp(false), sanitized.getData());
+  }
+
   @Test
   public void testWebpIoError() throws Exception {
     try (InputStream ioErrorStream = new IoErrorInputStream()) {
@@ -77,6 +98,34 @@ public class WebpSanitizerTest {
     return webpOutputStream.toByteArray();
   }
 
+  /** A 1x1 WebP with an extended header, optionally followed by EXIF metadata. */
+  private static byte[] extendedWebp(boolean withExif) throws IOException {
+    ByteArrayOutputStream webpOutputStream = new ByteArrayOutputStream();
+    DataOutputStream webpDataOutputStream = new DataOutputStream(webpOutputStream);
+
+    webpDataOutputStream.write("RIFF".getBytes()); // chunk type
+    webpDataOutputStream.write(new byte[] {(byte) (withExif ? 50 : 38), 0, 0, 0}); // chunk size
+    webpDataOutputStream.write("WEBP".getBytes()); // webp header
+
+    webpDataOutputStream.write("VP8X".getBytes()); // chunk type
+    webpDataOutputStream.write(new byte[] {10, 0, 0, 0}); // chunk size
+    webpDataOutputStream.write(new byte[] {(byte) (withExif ? 0x08 : 0), 0, 0, 0}); // flags
+    webpDataOutputStream.write(new byte[] {0, 0, 0, 0, 0, 0}); // canvas size
+
+    webpDataOutputStream.write("VP8L".getBytes()); // chunk type
+    webpDataOutputStream.write(new byte[] {8, 0, 0, 0}); // chunk size
+    webpDataOutputStream.write(
+        new byte[] {0x2f, 0, 0, 0, 0, (byte) 0x88, (byte) 0x88, 8}); // VP8L data
+
+    if (withExif) {
+      webpDataOutputStream.write("EXIF".getBytes()); // chunk type
+      webpDataOutputStream.write(new byte[] {4, 0, 0, 0}); // chunk size
+      webpDataOutputStream.write(new byte[] {1, 2, 3, 4}); // EXIF data
+    }
+
+    return webpOutputStream.toByteArray();
+  }
+
   private static class IoErrorInputStream extends InputStream {
     @Override
     public int read() throws IOException {
@@ -49,6 +49,30 @@ public class MessageBackupValidationTest {
     assertArrayEquals(result.unknownFieldMessages, new String[0]);
   }
 
+  @Test
+  public void validBackupFileStream() throws IOException, ValidationError {
+    MessageBackupKey key = makeMessageBackupKey();
+    MessageBackup.ValidationResult result;
+    try (InputStream input =
+        MessageBackupValidationTest.class.getResourceAsStream(VALID_BACKUP_RESOURCE_NAME)) {
+      result = MessageBackup.validate(key, BACKUP_PURPOSE, input);
+    }
+    assertArrayEquals(result.unknownFieldMessages, new String[0]);
+  }
+
+  @Test
+  public void emptyBackupFileStream() {
+    MessageBackupKey key = makeMessageBackupKey();
+
+    ValidationError error =
+        assertThrows(
+            ValidationError.class,
+            () -> {
+              MessageBackup.validate(key, BACKUP_PURPOSE, new ByteArrayInputStream(new byte[] {}));
+            });
+    assertEquals(error.getMessage(), "not enough bytes for an HMAC");
+  }
+
   @Test
   public void emptyBackupFile() {
     Supplier<InputStream> factory =
@@ -0,0 +1,58 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertThrows;
+
+import java.io.ByteArrayInputStream;
+import java.io.ByteArrayOutputStream;
+import java.io.IOException;
+import org.junit.Test;
+import org.signal.libsignal.protocol.util.Hex;
+
+public class MessageBackupWriterTest {
+  // A Frame containing AccountData with only a profile key and the required account settings.
+  static final byte[] ACCOUNT_DATA_FRAME =
+      Hex.fromStringCondensedAssert(
+          "0a270a20aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa4a03880101");
+
+  @Test
+  public void writtenBackupIsValid() throws IOException, ValidationError {
+    MessageBackupKey key = MessageBackupValidationTest.makeMessageBackupKey();
+    MessageBackupWriter writer =
+        new MessageBackupWriter(key, MessageBackupValidationTest.BACKUP_PURPOSE, new byte[] {});
+
+    ByteArrayOutputStream output = new ByteArrayOutputStream();
+    // A frame with no item is rejected, but doesn't stop the writer.
+    assertThrows(ValidationError.class, () -> writer.writeFrame(new byte[] {}));
+    output.write(writer.writeFrame(ACCOUNT_DATA_FRAME));
+    output.write(writer.finish());
+
+    MessageBackup.ValidationResult result =
+        MessageBackup.validate(
+            key,
+            MessageBackupValidationTest.BACKUP_PURPOSE,
+            new ByteArrayInputStream(output.toByteArray()));
+    assertArrayEquals(result.unknownFieldMessages, new String[0]);
+
+    assertThrows(ValidationError.class, () -> writer.finish());
+  }
+
+  @Test
+  public void incompleteBackupIsRejected() {
+    MessageBackupKey key = MessageBackupValidationTest.makeMessageBackupKey();
+    MessageBackupWriter writer =
+        new MessageBackupWriter(key, MessageBackupValidationTest.BACKUP_PURPOSE, new byte[] {});
+    assertThrows(ValidationError.class, () -> writer.finish());
+  }
+
+  @Test(expected = IllegalArgumentException.class)
+  public void badBackupInfo() {
+    MessageBackupKey key = MessageBackupValidationTest.makeMessageBackupKey();
+    new MessageBackupWriter(key, MessageBackupValidationTest.BACKUP_PURPOSE, new byte[] {0x0a});
+  }
+}
@@ -9,10 +9,17 @@ import static org.junit.Assert.*;
 
 import java.io.IOException;
 import java.nio.charset.StandardCharsets;
+import java.time.Duration;
+import java.util.ArrayList;
+import java.util.Base64;
+import java.util.List;
 import java.util.Map;
+import java.util.concurrent.CompletableFuture;
+import java.util.concurrent.TimeUnit;
 import org.junit.Assume;
 import org.junit.Test;
 import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.protocol.util.Hex;
 import org.signal.libsignal.util.TestEnvironment;
 
 public class ChatServiceTest {
@@ -79,6 +86,9 @@ public class ChatServiceTest {
     assertChatServiceErrorIs("AppExpired", AppExpiredException.class);
     assertChatServiceErrorIs("DeviceDeregistered", DeviceDeregisteredException.class);
     assertChatServiceErrorIs("ServiceInactive", ChatServiceInactiveException.class);
+    RetryLaterException retryLater =
+        assertChatServiceErrorIs("RetryAfter42Seconds", RetryLaterException.class);
+    assertEquals(Duration.ofSeconds(42), retryLater.duration);
 
     assertChatServiceErrorIs("WebSocket", ChatServiceException.class);
     assertChatServiceErrorIs("UnexpectedFrameReceived", ChatServiceException.class);
@@ -118,6 +128,120 @@ public class ChatServiceTest {
                 internal.guardedMap(h -> Native.TESTING_ChatRequestGetHeaderValue(h, name))));
   }
 
+  @Test
+  public void testListenerCallbacks() throws Exception {
+    final List<String> events = new ArrayList<>();
+    final CompletableFuture<ChatServiceException> interrupted = new CompletableFuture<>();
+
+    final Network net = new Network(Network.Environment.STAGING, USER_AGENT);
+    final ChatService chat = net.createChatService("", "");
+    chat.setListener(
+        new ChatListener() {
+          @Override
+          public void onIncomingMessage(
+              ChatService chat,
+              byte[] envelope,
+              long serverDeliveryTimestamp,
+              ServerMessageAck ack) {
+            events.add(
+                "message " + serverDeliveryTimestamp + ": " + Hex.toStringCondensed(envelope));
+          }
+
+          @Override
+          public void onQueueEmpty(ChatService chat) {
+            events.add("queue empty");
+          }
+
+          @Override
+          public void onConnectionInterrupted(
+              ChatService chat, ChatServiceException disconnectCause) {
+            interrupted.complete(disconnectCause);
+          }
+        });
+
+    // The following payloads were generated via protoscope.
+    // % protoscope -s | base64
+    // The fields are described by chat_websocket.proto in the libsignal-net crate.
+
+    // 1: {"PUT"}
+    // 2: {"/api/v1/message"}
+    // 3: {1000i64}
+    // 5: {"x-signal-timestamp:1000"}
+    // 4: 1
+    injectServerRequest(
+        chat, "CgNQVVQSDy9hcGkvdjEvbWVzc2FnZRoI6AMAAAAAAAAqF3gtc2lnbmFsLXRpbWVzdGFtcDoxMDAwIAE=");
+    // 1: {"PUT"}
+    // 2: {"/api/v1/queue/empty"}
+    // 4: 99
+    injectServerRequest(chat, "CgNQVVQSEy9hcGkvdjEvcXVldWUvZW1wdHkgYw==");
+    chat.guardedRun(Native::TESTING_ChatService_InjectConnectionInterrupted);
+
+    assertNotNull(interrupted.get(2, TimeUnit.SECONDS));
+    assertEquals(List.of("message 1000: e803000000000000", "queue empty"), events);
+  }
+
+  private static void injectServerRequest(ChatService chat, String base64) {
+    final byte[] bytes = Base64.getDecoder().decode(base64);
+    chat.guardedRun(h -> Native.TESTING_ChatService_InjectRawServerRequest(h, bytes));
+  }
+
+  @Test
+  public void testFakeServer() throws Exception {
+    final Network net = new Network(Network.Environment.STAGING, USER_AGENT);
+    final FakeChatServer server = new FakeChatServer(net);
+    assertThrows(ChatServiceInactiveException.class, server::sendQueueEmpty);
+
+    final List<String> events = new ArrayList<>();
+    final CompletableFuture<Void> queueEmpty = new CompletableFuture<>();
+    final ChatService chat = server.createChatService(net, "", "");
+    chat.setListener(
+        new ChatListener() {
+          @Override
+          public void onIncomingMessage(
+              ChatService chat,
+              byte[] envelope,
+              long serverDeliveryTimestamp,
+              ServerMessageAck ack) {
+            events.add(
+                "message " + serverDeliveryTimestamp + ": " + Hex.toStringCondensed(envelope));
+          }
+
+          @Override
+          public void onQueueEmpty(ChatService chat) {
+            queueEmpty.complete(null);
+          }
+
+          @Override
+          public void onConnectionInterrupted(
+              ChatService chat, ChatServiceException disconnectCause) {}
+        });
+    chat.connectUnauthenticated().get();
+
+    final var responseFuture =
+        chat.unauthenticatedSend(
+            new ChatService.Request("GET", "/v1/config", Map.of("x-test", "value"), null, 5000));
+    final FakeChatServer.ReceivedRequest received = server.receiveRequest().get();
+    final ChatService.InternalRequest request = received.getRequest();
+    assertEquals("GET", request.guardedMap(Native::TESTING_ChatRequestGetMethod));
+    assertEquals("/v1/config", request.guardedMap(Native::TESTING_ChatRequestGetPath));
+    assertEquals(
+        "value", request.guardedMap(h -> Native.TESTING_ChatRequestGetHeaderValue(h, "x-test")));
+    received.respond(200, "config".getBytes(StandardCharsets.UTF_8), 0);
+
+    final ChatService.Response response = responseFuture.get();
+    assertEquals(200, response.status());
+    assertArrayEquals("config".getBytes(StandardCharsets.UTF_8), response.body());
+
+    // Envelopes go to the most recently connected client, which is now the authenticated one.
+    chat.connectAuthenticated().get();
+    server.sendEnvelope(new byte[] {1, 2, 3}, 1000);
+    server.sendQueueEmpty();
+    queueEmpty.get(2, TimeUnit.SECONDS);
+    assertEquals(List.of("message 1000: 010203"), events);
+
+    chat.disconnect().get();
+  }
+
   @Test
   public void testConnectUnauth() throws Exception {
     // Use the presence of the proxy server environment setting to know whether we should make
@@ -0,0 +1,115 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import org.signal.libsignal.internal.CompletableFuture;
+import org.signal.libsignal.internal.FilterExceptions;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/** An in-process chat server, for testing {@link ChatService} end to end. */
+class FakeChatServer extends NativeHandleGuard.SimpleOwner {
+  private final TokioAsyncContext tokioAsyncContext;
+
+  FakeChatServer(final Network net) {
+    super(Native.TESTING_FakeChatServer_New());
+    this.tokioAsyncContext = net.getAsyncContext();
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.FakeChatServer_Destroy(nativeHandle);
+  }
+
+  /** Like {@link Network#createChatService}, but connects to this server instead. */
+  ChatService createChatService(final Network net, final String username, final String password) {
+    return new ChatService(
+        tokioAsyncContext,
+        net.getConnectionManager()
+            .guardedMap(
+                connectionManager ->
+                    guardedMap(
+                        server ->
+                            Native.TESTING_ChatService_NewWithFakeServer(
+                                connectionManager, server, username, password))));
+  }
+
+  void setRefuseConnections(final boolean refuse) {
+    guardedRun(h -> Native.TESTING_FakeChatServer_SetRefuseConnections(h, refuse));
+  }
+
+  void disconnectAll() {
+    guardedRun(Native::TESTING_FakeChatServer_DisconnectAll);
+  }
+
+  /**
+   * Delivers an envelope to the most recently connected client.
+   *
+   * @throws ChatServiceInactiveException if no client is connected.
+   */
+  void sendEnvelope(final byte[] envelope, final long serverDeliveryTimestamp)
+      throws ChatServiceInactiveException {
+    FilterExceptions.filterExceptions(
+        ChatServiceInactiveException.class,
+        () ->
+            guardedRunChecked(
+                h ->
+                    Native.TESTING_FakeChatServer_SendEnvelope(
+                        h, envelope, serverDeliveryTimestamp)));
+  }
+
+  /**
+   * Tells the most recently connected client that there are no more queued envelopes.
+   *
+   * @throws ChatServiceInactiveException if no client is connected.
+   */
+  void sendQueueEmpty() throws ChatServiceInactiveException {
+    FilterExceptions.filterExceptions(
+        ChatServiceInactiveException.class,
+        () -> guardedRunChecked(Native::TESTING_FakeChatServer_SendQueueEmpty));
+  }
+
+  /** Waits for the next request sent by any client. */
+  CompletableFuture<ReceivedRequest> receiveRequest() {
+    return tokioAsyncContext.guardedMap(
+        asyncContext ->
+            guardedMap(
+                server ->
+                    Native.TESTING_FakeChatServer_ReceiveRequest(asyncContext, server)
+                        .thenApply(handle -> new ReceivedRequest(tokioAsyncContext, handle))));
+  }
+
+  /** A request received by a {@link FakeChatServer}, waiting for a response. */
+  static class ReceivedRequest extends NativeHandleGuard.SimpleOwner {
+    private final TokioAsyncContext tokioAsyncContext;
+
+    private ReceivedRequest(final TokioAsyncContext tokioAsyncContext, final long nativeHandle) {
+      super(nativeHandle);
+      this.tokioAsyncContext = tokioAsyncContext;
+    }
+
+    @Override
+    protected void release(long nativeHandle) {
+      Native.FakeChatServerRequest_Destroy(nativeHandle);
+    }
+
+    /** The request, for inspection with the {@code TESTING_ChatRequestGet*} functions. */
+    ChatService.InternalRequest getRequest() {
+      return new ChatService.InternalRequest(
+          guardedMap(Native::TESTING_FakeChatServerRequest_GetHttpRequest));
+    }
+
+    /** Responds with {@code status} after {@code delayMillis}; an empty body is sent as none. */
+    void respond(final int status, final byte[] body, final int delayMillis) {
+      tokioAsyncContext.guardedRun(
+          asyncContext ->
+              guardedRun(
+                  h ->
+                      Native.TESTING_FakeChatServerRequest_Respond(
+                          asyncContext, h, status, body, delayMillis)));
+    }
+  }
+}
@@ -130,6 +130,61 @@ public class Svr3Test {
     }
   }
 
+  @Test
+  public void migrate() throws Exception {
+    final int tries = 3;
+    var previous = new Network(Network.Environment.STAGING, USER_AGENT);
+    byte[] previousShareSet =
+        previous.svr3().backup(STORED_SECRET, TEST_PASSWORD, tries, state.auth()).get();
+
+    var migrationState = new Svr3.MigrationState();
+    byte[] shareSet =
+        state
+            .net()
+            .svr3()
+            .migrate(
+                previous.svr3(),
+                migrationState,
+                TEST_PASSWORD,
+                previousShareSet,
+                tries,
+                state.auth())
+            .get();
+    assertFalse(migrationState.hasRestoredSecret());
+
+    Svr3.RestoredSecret restored =
+        state.net().svr3().restore(TEST_PASSWORD, shareSet, state.auth()).get();
+    assertEquals(Hex.toStringCondensed(STORED_SECRET), Hex.toStringCondensed(restored.value()));
+    assertEquals(tries - 1, restored.triesRemaining());
+  }
+
+  @Test
+  public void failedMigrate() throws Exception {
+    var previous = new Network(Network.Environment.STAGING, USER_AGENT);
+    byte[] previousShareSet =
+        previous.svr3().backup(STORED_SECRET, TEST_PASSWORD, 2, state.auth()).get();
+
+    var migrationState = new Svr3.MigrationState();
+    try {
+      state
+          .net()
+          .svr3()
+          .migrate(
+              previous.svr3(),
+              migrationState,
+              "wrong password",
+              previousShareSet,
+              2,
+              state.auth())
+          .get();
+      fail("Must have thrown");
+    } catch (ExecutionException ex) {
+      Throwable cause = ex.getCause();
+      assertTrue("Unexpected exception: " + cause, cause instanceof RestoreFailedException);
+    }
+    assertFalse(migrationState.hasRestoredSecret());
+  }
+
   @Test
   public void zeroTries() throws Exception {
     assertThrows(
@@ -7,10 +7,14 @@ package org.signal.libsignal.net;
 
 import static org.junit.Assert.*;
 
+import java.util.concurrent.CancellationException;
 import java.util.concurrent.ExecutionException;
 import java.util.concurrent.Future;
 import java.util.concurrent.TimeUnit;
+import java.util.concurrent.TimeoutException;
 import org.junit.Test;
+import org.signal.libsignal.internal.CompletableFuture;
+import org.signal.libsignal.internal.Native;
 
 public class TokioAsyncContextTest {
   @Test
@@ -60,6 +64,38 @@ public class TokioAsyncContextTest {
         cause instanceof ClassNotFoundException || cause instanceof NoClassDefFoundError);
   }
 
+  @Test
+  @SuppressWarnings("unchecked")
+  public void cancelRunningFuture() throws Exception {
+    TokioAsyncContext context = new TokioAsyncContext();
+    CompletableFuture<Void> pending =
+        context.makeCancellable(
+            (CompletableFuture<Void>)
+                context.guardedMap(Native::TESTING_OnlyCompletesByCancellation));
+    assertThrows(TimeoutException.class, () -> pending.get(200, TimeUnit.MILLISECONDS));
+
+    assertTrue(pending.cancel(true));
+    assertTrue(pending.isCancelled());
+    assertThrows(CancellationException.class, () -> pending.get(10, TimeUnit.SECONDS));
+    assertFalse(pending.cancel(true));
+  }
+
+  @Test
+  @SuppressWarnings("unchecked")
+  public void cancelChainedFuture() throws Exception {
+    TokioAsyncContext context = new TokioAsyncContext();
+    CompletableFuture<Void> pending =
+        context.makeCancellable(
+            (CompletableFuture<Void>)
+                context.guardedMap(Native::TESTING_OnlyCompletesByCancellation));
+    CompletableFuture<String> chained = pending.thenApply(v -> "unreachable");
+
+    assertTrue(chained.cancel(true));
+    assertTrue(chained.isCancelled());
+    assertTrue(pending.isCancelled());
+    assertThrows(CancellationException.class, () -> chained.get(10, TimeUnit.SECONDS));
+  }
+
   @Test
   public void runNetworkClassLoadTestFunction() throws ExecutionException, InterruptedException {
     Network.checkClassesCanBeLoadedAsyncForTest();
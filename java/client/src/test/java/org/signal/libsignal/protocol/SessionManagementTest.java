@@ -0,0 +1,61 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.protocol;
+
+import static org.junit.Assert.assertEquals;
+
+import java.time.Duration;
+import java.time.Instant;
+import java.time.temporal.ChronoUnit;
+import java.util.Arrays;
+import java.util.Collections;
+import java.util.List;
+import org.junit.Test;
+import org.signal.libsignal.protocol.state.SignalProtocolStore;
+
+public class SessionManagementTest {
+  @Test
+  public void testBulkSessionManagement() throws Exception {
+    Instant now = Instant.now();
+    SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+
+    List<SignalProtocolAddress> addresses =
+        Arrays.asList(
+            new SignalProtocolAddress("+14151111111", 1),
+            new SignalProtocolAddress("+14152222222", 1),
+            new SignalProtocolAddress("+14153333333", 1));
+    // The first session was last used 10 days ago; the others were just created.
+    List<Instant> lastUsed = Arrays.asList(now.minus(10, ChronoUnit.DAYS), now, now);
+    for (int i = 0; i < addresses.size(); i++) {
+      SignalProtocolStore bobStore = new TestInMemorySignalProtocolStore();
+      new SessionBuilder(aliceStore, addresses.get(i))
+          .process(new PQXDHBundleFactory().createBundle(bobStore), lastUsed.get(i));
+    }
+
+    SignalProtocolAddress missing = new SignalProtocolAddress("+14159999999", 1);
+    assertEquals(
+        Collections.singletonList(addresses.get(2)),
+        SessionManagement.archiveSessions(Arrays.asList(addresses.get(2), missing), aliceStore));
+
+    assertEquals(
+        Collections.emptyList(),
+        SessionManagement.expireSessions(
+            addresses, Duration.ofSeconds(Long.MAX_VALUE), aliceStore, now));
+    assertEquals(
+        Collections.singletonList(addresses.get(0)),
+        SessionManagement.expireSessions(addresses, Duration.ofDays(7), aliceStore, now));
+
+    for (int i = 0; i < addresses.size(); i++) {
+      assertEquals(i == 1, aliceStore.loadSession(addresses.get(i)).hasSenderChain(now));
+    }
+
+    assertEquals(
+        Arrays.asList(addresses.get(0), addresses.get(2)),
+        SessionManagement.prunePreviousSessions(addresses, 0, aliceStore));
+    assertEquals(
+        Collections.emptyList(), SessionManagement.prunePreviousSessions(addresses, 0, aliceStore));
+  }
+}
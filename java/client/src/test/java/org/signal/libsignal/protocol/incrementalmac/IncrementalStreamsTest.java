@@ -9,6 +9,7 @@ import java.io.ByteArrayInputStream;
 import java.io.ByteArrayOutputStream;
 import java.io.IOException;
 import java.io.OutputStream;
+import java.util.Arrays;
 import junit.framework.TestCase;
 import org.signal.libsignal.protocol.util.Hex;
 
@@ -104,6 +105,73 @@ public class IncrementalStreamsTest extends TestCase {
     }
   }
 
+  public void testResumeAfterFailure() throws IOException {
+    // Long enough that the corrupted chunk is not the last one.
+    String[] parts = {String.join("", TEST_INPUT_PARTS), String.join("", TEST_INPUT_PARTS)};
+    byte[] digest = fullIncrementalDigest(new ByteArrayOutputStream(), parts);
+    byte[] input = String.join("", parts).getBytes();
+    byte[] corruptInput = input.clone();
+    corruptInput[42] ^= 0xff;
+
+    byte[] checkpoint;
+    try (IncrementalMacInputStream incrementalIn =
+        new IncrementalMacInputStream(
+            new ByteArrayInputStream(corruptInput), TEST_HMAC_KEY, SIZE_CHOICE, digest)) {
+      byte[] buffer = new byte[CHUNK_SIZE];
+      assertEquals(CHUNK_SIZE, incrementalIn.read(buffer));
+      try {
+        incrementalIn.read(buffer);
+        fail("The read should have failed");
+      } catch (InvalidMacException _ex) {
+      }
+      assertEquals(CHUNK_SIZE, incrementalIn.validatedOffset());
+      checkpoint = incrementalIn.checkpoint();
+    }
+
+    // Fetch the rest again, in what could be another process.
+    ByteArrayInputStream savedInput = new ByteArrayInputStream(input, 0, CHUNK_SIZE);
+    ByteArrayInputStream rest =
+        new ByteArrayInputStream(input, CHUNK_SIZE, input.length - CHUNK_SIZE);
+    try (IncrementalMacInputStream incrementalIn =
+        new IncrementalMacInputStream(
+            rest, TEST_HMAC_KEY, SIZE_CHOICE, digest, checkpoint, savedInput)) {
+      byte[] buffer = new byte[1024];
+      int totalBytesRead = 0;
+      int bytesRead;
+      while ((bytesRead = incrementalIn.read(buffer)) != -1) {
+        totalBytesRead += bytesRead;
+      }
+      assertEquals(input.length - CHUNK_SIZE, totalBytesRead);
+    }
+  }
+
+  public void testResumeWithWrongSavedInput() throws IOException {
+    byte[] digest = fullIncrementalDigest(new ByteArrayOutputStream(), TEST_INPUT_PARTS);
+    byte[] input = String.join("", TEST_INPUT_PARTS).getBytes();
+
+    byte[] checkpoint;
+    try (IncrementalMacInputStream incrementalIn =
+        new IncrementalMacInputStream(
+            new ByteArrayInputStream(input), TEST_HMAC_KEY, SIZE_CHOICE, digest)) {
+      assertEquals(CHUNK_SIZE, incrementalIn.read(new byte[CHUNK_SIZE]));
+      checkpoint = incrementalIn.checkpoint();
+    }
+
+    byte[] corruptSavedInput = Arrays.copyOf(input, CHUNK_SIZE);
+    corruptSavedInput[1] ^= 0xff;
+    try {
+      new IncrementalMacInputStream(
+          new ByteArrayInputStream(input, CHUNK_SIZE, input.length - CHUNK_SIZE),
+          TEST_HMAC_KEY,
+          SIZE_CHOICE,
+          digest,
+          checkpoint,
+          new ByteArrayInputStream(corruptSavedInput));
+      fail("The saved input should have been rejected");
+    } catch (IOException _ex) {
+    }
+  }
+
   public void testSingleByteRead() throws IOException {
     byte[] digest = fullIncrementalDigest(new ByteArrayOutputStream(), TEST_INPUT_PARTS);
     ByteArrayInputStream in = new ByteArrayInputStream(new byte[] {});
@@ -35,6 +35,28 @@ public class PinTest {
     assertFalse(Pin.verifyLocalHash(pwhash, "badpassword".getBytes(StandardCharsets.UTF_8)));
   }
 
+  @Test
+  public void verifyWithParams() {
+    byte[] pin = "password".getBytes(StandardCharsets.UTF_8);
+    String pwhash = Pin.localHash(pin, 1024, 32);
+    assertTrue(pwhash.startsWith("$argon2id$"));
+    assertTrue(Pin.verifyLocalHash(pwhash, pin));
+    assertFalse(Pin.verifyLocalHash(pwhash, "badpassword".getBytes(StandardCharsets.UTF_8)));
+  }
+
+  @Test(expected = IllegalArgumentException.class)
+  public void weakParams() {
+    Pin.localHash("password".getBytes(StandardCharsets.UTF_8), 512, 1);
+  }
+
+  @Test(expected = IllegalArgumentException.class)
+  public void weakEncodedHash() {
+    // The cost is checked before the pin, so the hash output doesn't matter.
+    Pin.verifyLocalHash(
+        "$argon2id$v=19$m=512,t=1,p=1$ICEiIyQlJicoKSorLC0uLw$NeZzhiNv4cRmRMct9scf7d838bzmHJvrZtU/0BH0v/U",
+        "password".getBytes(StandardCharsets.UTF_8));
+  }
+
   @Test
   public void known() throws IOException {
     final byte[] salt =
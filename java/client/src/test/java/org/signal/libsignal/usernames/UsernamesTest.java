@@ -34,6 +34,56 @@ public class UsernamesTest {
     }
   }
 
+  @Test
+  public void testCandidatesWithProofsFromRanges() throws BaseUsernameException {
+    List<Username.CandidateWithProof> candidates =
+        Username.candidatesWithProofsFrom(
+            "SiGNAl",
+            5,
+            3,
+            32,
+            List.of(new Username.DiscriminatorRange(100, 106, 5)),
+            List.of(103));
+    assertEquals(5, candidates.size());
+    for (Username.CandidateWithProof candidate : candidates) {
+      String username = candidate.getUsername().getUsername();
+      assertTrue(username, username.matches("SiGNAl\\.10[0-5]"));
+      assertFalse(username, username.equals("SiGNAl.103"));
+      Username.verifyProof(candidate.getProof(), candidate.getUsername().getHash());
+    }
+  }
+
+  @Test
+  public void testCandidatesWithProofsFromInvalidRanges() {
+    assertThrows(
+        DiscriminatorCannotBeSingleDigitException.class,
+        () ->
+            Username.candidatesWithProofsFrom(
+                "SiGNAl", 5, 3, 32, List.of(new Username.DiscriminatorRange(1, 10, 5)), List.of()));
+    assertThrows(
+        DiscriminatorTooLargeException.class,
+        () ->
+            Username.candidatesWithProofsFrom(
+                "SiGNAl",
+                5,
+                3,
+                32,
+                List.of(new Username.DiscriminatorRange(100, 2_000_000_000, 5)),
+                List.of()));
+    assertThrows(
+        IllegalArgumentException.class,
+        () ->
+            Username.candidatesWithProofsFrom(
+                "SiGNAl",
+                5,
+                3,
+                32,
+                List.of(
+                    new Username.DiscriminatorRange(100, 200, 5),
+                    new Username.DiscriminatorRange(150, 250, 5)),
+                List.of()));
+  }
+
   @Test
   public void testInvalidNicknameValidation() throws BaseUsernameException {
     List<String> invalidNicknames =
@@ -18,6 +18,7 @@ import org.signal.libsignal.protocol.ServiceId;
 import org.signal.libsignal.protocol.ServiceId.Aci;
 import org.signal.libsignal.protocol.ServiceId.Pni;
 import org.signal.libsignal.protocol.util.Hex;
+import org.signal.libsignal.zkgroup.BatchVerificationFailedException;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 import org.signal.libsignal.zkgroup.NotarySignature;
 import org.signal.libsignal.zkgroup.SecureRandomTest;
@@ -355,6 +356,46 @@ public final class ZkGroupTest extends SecureRandomTest {
       // expected
     }
 
+    // SERVER - Verify a batch of presentations, some of them bad
+    {
+      byte[] temp = presentation.serialize();
+      temp[3] += 5;
+      AuthCredentialPresentation badPresentation = new AuthCredentialPresentation(temp);
+
+      serverZkAuth.verifyAuthCredentialPresentations(
+          Arrays.asList(groupPublicParams, groupPublicParams),
+          Arrays.asList(presentation, presentation),
+          redemptionTime);
+
+      try {
+        serverZkAuth.verifyAuthCredentialPresentations(
+            Arrays.asList(groupPublicParams, groupPublicParams, groupPublicParams),
+            Arrays.asList(presentation, badPresentation, presentation),
+            redemptionTime);
+        throw new AssertionError("verifyAuthCredentialPresentations should fail #1!");
+      } catch (BatchVerificationFailedException e) {
+        assertArrayEquals(new int[] {1}, e.getFailedIndexes());
+      }
+
+      try {
+        serverZkAuth.verifyAuthCredentialPresentations(
+            Arrays.asList(groupPublicParams, groupPublicParams),
+            Arrays.asList(badPresentation, presentation),
+            redemptionTime.plus(3, ChronoUnit.DAYS));
+        throw new AssertionError("verifyAuthCredentialPresentations should fail #2!");
+      } catch (BatchVerificationFailedException e) {
+        assertArrayEquals(new int[] {0, 1}, e.getFailedIndexes());
+      }
+
+      try {
+        serverZkAuth.verifyAuthCredentialPresentations(
+            Arrays.asList(groupPublicParams), Arrays.asList(presentation, presentation));
+        throw new AssertionError("verifyAuthCredentialPresentations should fail #3!");
+      } catch (IllegalArgumentException e) {
+        // expected
+      }
+    }
+
     try {
       byte[] temp = presentation.serialize();
       temp[0] = 0; // This interprets a V3 as V1, so should fail
@@ -698,6 +739,36 @@ public final class ZkGroupTest extends SecureRandomTest {
     } catch (VerificationFailedException e) {
       // expected
     }
+
+    // Verify a batch of presentations, some of them bad
+    {
+      byte[] temp = presentation.serialize();
+      temp[2] += 8;
+      ProfileKeyCredentialPresentation badPresentation =
+          new ProfileKeyCredentialPresentation(temp);
+
+      serverZkProfile.verifyProfileKeyCredentialPresentations(
+          Arrays.asList(groupPublicParams, groupPublicParams),
+          Arrays.asList(presentation, presentation));
+
+      try {
+        serverZkProfile.verifyProfileKeyCredentialPresentations(
+            Arrays.asList(
+                groupPublicParams, groupPublicParams, groupPublicParams, groupPublicParams),
+            Arrays.asList(presentation, badPresentation, presentation, v1Presentation));
+        throw new AssertionError("verifyProfileKeyCredentialPresentations should fail 1");
+      } catch (BatchVerificationFailedException e) {
+        assertArrayEquals(new int[] {1, 3}, e.getFailedIndexes());
+      }
+
+      try {
+        serverZkProfile.verifyProfileKeyCredentialPresentations(
+            Arrays.asList(groupPublicParams), Arrays.asList(presentation), expiration);
+        throw new AssertionError("verifyProfileKeyCredentialPresentations should fail 2");
+      } catch (BatchVerificationFailedException e) {
+        assertArrayEquals(new int[] {0}, e.getFailedIndexes());
+      }
+    }
   }
 
   @Test
@@ -0,0 +1,20 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.internal;
+
+/**
+ * The low-level callbacks for messages pushed by the chat server.
+ *
+ * <p>This is an implementation detail of {@code ChatService}; use {@code ChatListener} instead.
+ */
+@CalledFromNative
+public interface BridgeChatListener {
+  void onIncomingMessage(byte[] envelope, long serverDeliveryTimestamp, long ackHandle);
+
+  void onQueueEmpty();
+
+  void onConnectionInterrupted(Throwable disconnectCause);
+}
@@ -23,20 +23,65 @@ public class CompletableFuture<T> implements Future<T> {
   private Throwable exception;
   private List<ThenApplyCompleter<T>> consumers;
 
+  /** Assigned by native code if the operation that will complete this future can be cancelled. */
+  private long cancellationId;
+
+  /** Stops the work that would complete this future; run when this future is cancelled. */
+  private Runnable onCancel;
+
   @CalledFromNative
   public CompletableFuture() {
     this.consumers = new ArrayList<>();
   }
 
+  @CalledFromNative
+  synchronized void setCancellationId(long cancellationId) {
+    this.cancellationId = cancellationId;
+  }
+
+  /**
+   * Allows {@link #cancel} to stop the native operation that will complete this future.
+   *
+   * <p>When this future is cancelled, {@code canceller} will be called with the ID native code
+   * assigned to the operation. If the operation doesn't support cancellation, this has no effect.
+   *
+   * @return this future
+   */
+  public synchronized CompletableFuture<T> makeCancellable(Consumer<Long> canceller) {
+    if (cancellationId != 0) {
+      final long id = cancellationId;
+      this.onCancel = () -> canceller.accept(id);
+    }
+    return this;
+  }
+
+  /**
+   * Completes this future with a {@link CancellationException}, unless it has already completed.
+   *
+   * <p>If the native operation that would complete this future was made cancellable (see {@link
+   * #makeCancellable}), it is stopped as well, closing any connections it was using. Cancelling a
+   * future produced by {@link #thenApply}, {@link #thenCompose}, or {@link #whenComplete} cancels
+   * the future it is waiting on.
+   *
+   * @param mayInterruptIfRunning ignored; native operations are always stopped if possible
+   * @return whether this future was cancelled by this call
+   */
   @Override
-  public synchronized boolean cancel(boolean mayInterruptIfRunning) {
-    // We do not currently support cancellation.
-    return false;
+  public boolean cancel(boolean mayInterruptIfRunning) {
+    if (!completeExceptionally(new CancellationException())) return false;
+
+    Runnable onCancel;
+    synchronized (this) {
+      onCancel = this.onCancel;
+      this.onCancel = null;
+    }
+    if (onCancel != null) onCancel.run();
+    return true;
   }
 
   @Override
   public synchronized boolean isCancelled() {
-    return false;
+    return exception instanceof CancellationException;
   }
 
   @Override
@@ -85,6 +130,7 @@ public class CompletableFuture<T> implements Future<T> {
       throws CancellationException, ExecutionException, InterruptedException {
     while (!completed) wait();
 
+    if (exception instanceof CancellationException) throw (CancellationException) exception;
     if (exception != null) throw new ExecutionException(exception);
 
     return result;
@@ -150,6 +196,7 @@ public class CompletableFuture<T> implements Future<T> {
             future.completeExceptionally(e);
             return;
           }
+          future.forwardCancellationTo(output);
           output.addCompleter(
               new ThenApplyCompleter<>(future::complete, future::completeExceptionally));
         },
@@ -198,10 +245,23 @@ public class CompletableFuture<T> implements Future<T> {
         new ThenApplyCompleter<T>(
             (T value) -> complete.accept(future, value),
             (Throwable exception) -> completeExceptionally.accept(future, exception));
+    future.forwardCancellationTo(this);
     this.addCompleter(completer);
     return future;
   }
 
+  /** Arranges for cancelling this future to cancel {@code upstream} as well. */
+  private void forwardCancellationTo(CompletableFuture<?> upstream) {
+    synchronized (this) {
+      if (!isCancelled()) {
+        this.onCancel = () -> upstream.cancel(true);
+        return;
+      }
+    }
+    // This future was cancelled before upstream existed.
+    upstream.cancel(true);
+  }
+
   private void addCompleter(ThenApplyCompleter<T> completer) {
     T result;
     Throwable exception;
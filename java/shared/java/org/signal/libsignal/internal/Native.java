@@ -170,15 +170,36 @@ public final class Native {
   public static native CompletableFuture<Object> ChatService_connect_unauth(long asyncRuntime, long chat);
   public static native CompletableFuture ChatService_disconnect(long asyncRuntime, long chat);
   public static native long ChatService_new(long connectionManager, String username, String password);
+  public static native CompletableFuture<Void> ChatService_preconnect(long asyncRuntime, long chat);
   public static native CompletableFuture<Object> ChatService_unauth_send(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
   public static native CompletableFuture<Object> ChatService_unauth_send_and_debug(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
+  public static native void ChatServer_SetListener(long runtime, long chat, BridgeChatListener makeListener);
 
   public static native void Chat_Destroy(long handle);
 
+  public static native void ConnectionEvent_Destroy(long handle);
+  public static native int ConnectionEvent_GetConsecutiveFailures(long event);
+  public static native String ConnectionEvent_GetDnsSource(long event);
+  public static native int ConnectionEvent_GetDurationMillis(long event);
+  public static native String ConnectionEvent_GetHost(long event);
+  public static native int ConnectionEvent_GetHttpStatus(long event);
+  public static native int ConnectionEvent_GetIpType(long event);
+  public static native int ConnectionEvent_GetKind(long event);
+  public static native String ConnectionEvent_GetRoute(long event);
+  public static native String ConnectionEvent_GetRouteAttemptOutcome(long event);
+  public static native boolean ConnectionEvent_GetSucceeded(long event);
+
   public static native void ConnectionManager_Destroy(long handle);
   public static native void ConnectionManager_clear_proxy(long connectionManager);
+  public static native void ConnectionManager_network_changed(long connectionManager);
   public static native long ConnectionManager_new(int environment, String userAgent);
+  public static native long ConnectionManager_new_with_timeouts(int environment, String userAgent, int connectTimeoutMillis, int requestTimeoutMillis);
+  public static native void ConnectionManager_reset_backoff_policy(long connectionManager);
+  public static native void ConnectionManager_set_backoff_policy(long connectionManager, int initialDelayMillis, int maxDelayMillis, int multiplierPercent, int jitterPercent, int maxAttempts);
+  public static native void ConnectionManager_set_http_proxy(long connectionManager, String host, int port, String username, String password) throws Exception;
   public static native void ConnectionManager_set_proxy(long connectionManager, String host, int port) throws Exception;
+  public static native void ConnectionManager_set_socks_proxy(long connectionManager, String host, int port, String username, String password) throws Exception;
+  public static native long ConnectionManager_take_connection_event(long connectionManager);
 
   public static native void CreateCallLinkCredentialPresentation_CheckValidContents(byte[] presentationBytes) throws Exception;
   public static native void CreateCallLinkCredentialPresentation_Verify(byte[] presentationBytes, byte[] roomId, long now, byte[] serverParamsBytes, byte[] callLinkParamsBytes) throws Exception;
@@ -221,7 +242,18 @@ public final class Native {
   public static native byte[] DecryptionErrorMessage_GetSerialized(long obj) throws Exception;
   public static native long DecryptionErrorMessage_GetTimestamp(long obj) throws Exception;
 
+  public static native void DeviceTransferArchiveReader_Destroy(long handle);
+
+  public static native void DeviceTransferArchiveWriter_Destroy(long handle);
+
+  public static native void DeviceTransfer_ArchiveReaderFinish(long reader) throws Exception;
+  public static native long DeviceTransfer_ArchiveReaderNew(byte[] key);
+  public static native byte[] DeviceTransfer_ArchiveReaderUpdate(long reader, byte[] bytes, int offset, int length) throws Exception;
+  public static native byte[] DeviceTransfer_ArchiveWriterFinish(long writer) throws Exception;
+  public static native long DeviceTransfer_ArchiveWriterNew(byte[] key, long totalLength) throws Exception;
+  public static native byte[] DeviceTransfer_ArchiveWriterUpdate(long writer, byte[] bytes, int offset, int length) throws Exception;
   public static native byte[] DeviceTransfer_GenerateCertificate(byte[] privateKey, String name, int daysToExpire) throws Exception;
+  public static native byte[] DeviceTransfer_GenerateEd25519PrivateKey();
   public static native byte[] DeviceTransfer_GeneratePrivateKey();
 
   public static native byte[] ECPrivateKey_Agree(long privateKey, long publicKey) throws Exception;
@@ -245,12 +277,18 @@ public final class Native {
   public static native void ExpiringProfileKeyCredential_CheckValidContents(byte[] buffer) throws Exception;
   public static native long ExpiringProfileKeyCredential_GetExpirationTime(byte[] credential);
 
+  public static native void FakeChatServerRequest_Destroy(long handle);
+
+  public static native void FakeChatServer_Destroy(long handle);
+
   public static native void GenericServerPublicParams_CheckValidContents(byte[] paramsBytes) throws Exception;
 
   public static native void GenericServerSecretParams_CheckValidContents(byte[] paramsBytes) throws Exception;
   public static native byte[] GenericServerSecretParams_GenerateDeterministic(byte[] randomness);
   public static native byte[] GenericServerSecretParams_GetPublicParams(byte[] paramsBytes);
 
+  public static native long GifSanitizer_Sanitize(InputStream input) throws Exception;
+
   public static native byte[] GroupCipher_DecryptMessage(long sender, byte[] message, SenderKeyStore store) throws Exception;
   public static native CiphertextMessage GroupCipher_EncryptMessage(long sender, UUID distributionId, byte[] message, SenderKeyStore store) throws Exception;
 
@@ -360,6 +398,12 @@ public final class Native {
   public static native long MessageBackupKey_New(byte[] masterKey, byte[] aci);
 
   public static native Object MessageBackupValidator_Validate(long key, InputStream firstStream, InputStream secondStream, long len, int purpose) throws Exception;
+  public static native Object MessageBackupValidator_ValidateStream(long key, InputStream stream, int purpose) throws Exception;
+
+  public static native void MessageBackupWriter_Destroy(long handle);
+  public static native byte[] MessageBackupWriter_Finish(long writer) throws Exception;
+  public static native long MessageBackupWriter_New(long key, byte[] backupInfo, int purpose) throws Exception;
+  public static native byte[] MessageBackupWriter_WriteFrame(long writer, byte[] frame) throws Exception;
 
   public static native long Mp4Sanitizer_Sanitize(InputStream input, long len) throws Exception;
 
@@ -377,6 +421,7 @@ public final class Native {
   public static native long PinHash_FromUsernameMrenclave(byte[] pin, String username, byte[] mrenclave) throws Exception;
 
   public static native String Pin_LocalHash(byte[] pin) throws Exception;
+  public static native String Pin_LocalHashWithParams(byte[] pin, int memoryKib, int iterations) throws Exception;
   public static native boolean Pin_VerifyLocalHash(String encodedHash, byte[] pin) throws Exception;
 
   public static native long PlaintextContent_Deserialize(byte[] data) throws Exception;
@@ -460,6 +505,12 @@ public final class Native {
   public static native long ReceiptCredential_GetReceiptExpirationTime(byte[] receiptCredential);
   public static native long ReceiptCredential_GetReceiptLevel(byte[] receiptCredential);
 
+  public static native void SanitizedImage_Destroy(long handle);
+  public static native byte[] SanitizedImage_GetData(long sanitized);
+  public static native int SanitizedImage_GetFrameCount(long sanitized);
+  public static native int SanitizedImage_GetHeight(long sanitized);
+  public static native int SanitizedImage_GetWidth(long sanitized);
+
   public static native void SanitizedMetadata_Destroy(long handle);
   public static native long SanitizedMetadata_GetDataLen(long sanitized);
   public static native long SanitizedMetadata_GetDataOffset(long sanitized);
@@ -523,6 +574,7 @@ public final class Native {
 
   public static native void ServerMessageAck_Destroy(long handle);
   public static native CompletableFuture<Void> ServerMessageAck_Send(long asyncRuntime, long ack);
+  public static native CompletableFuture<Void> ServerMessageAck_SendNack(long asyncRuntime, long ack);
 
   public static native byte[] ServerPublicParams_CreateAuthCredentialWithPniPresentationDeterministic(long serverPublicParams, byte[] randomness, byte[] groupSecretParams, byte[] authCredentialWithPniBytes);
   public static native byte[] ServerPublicParams_CreateExpiringProfileKeyCredentialPresentationDeterministic(long serverPublicParams, byte[] randomness, byte[] groupSecretParams, byte[] profileKeyCredential);
@@ -548,8 +600,11 @@ public final class Native {
   public static native byte[] ServerSecretParams_Serialize(long handle);
   public static native byte[] ServerSecretParams_SignDeterministic(long params, byte[] randomness, byte[] message);
   public static native void ServerSecretParams_VerifyAuthCredentialPresentation(long serverSecretParams, byte[] groupPublicParams, byte[] presentationBytes, long currentTimeInSeconds) throws Exception;
+  public static native void ServerSecretParams_VerifyAuthCredentialPresentations(long serverSecretParams, ByteBuffer[] groupPublicParams, ByteBuffer[] presentations, long currentTimeInSeconds) throws Exception;
   public static native void ServerSecretParams_VerifyProfileKeyCredentialPresentation(long serverSecretParams, byte[] groupPublicParams, byte[] presentationBytes, long currentTimeInSeconds) throws Exception;
+  public static native void ServerSecretParams_VerifyProfileKeyCredentialPresentations(long serverSecretParams, ByteBuffer[] groupPublicParams, ByteBuffer[] presentations, long currentTimeInSeconds) throws Exception;
   public static native void ServerSecretParams_VerifyReceiptCredentialPresentation(long serverSecretParams, byte[] presentation) throws Exception;
+  public static native void ServerSecretParams_VerifyReceiptCredentialPresentations(long serverSecretParams, ByteBuffer[] presentations) throws Exception;
 
   public static native byte[] ServiceId_ParseFromServiceIdBinary(byte[] input) throws Exception;
   public static native byte[] ServiceId_ParseFromServiceIdString(String input) throws Exception;
@@ -563,6 +618,10 @@ public final class Native {
   public static native byte[] SessionCipher_DecryptSignalMessage(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore) throws Exception;
   public static native CiphertextMessage SessionCipher_EncryptMessage(byte[] ptext, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long now) throws Exception;
 
+  public static native byte[] SessionManagement_ArchiveSessions(long[] addresses, SessionStore sessionStore) throws Exception;
+  public static native byte[] SessionManagement_ExpireSessions(long[] addresses, int maxAgeSeconds, SessionStore sessionStore, long now) throws Exception;
+  public static native byte[] SessionManagement_PrunePreviousSessions(long[] addresses, int maxPreviousSessions, SessionStore sessionStore) throws Exception;
+
   public static native void SessionRecord_ArchiveCurrentState(long sessionRecord) throws Exception;
   public static native boolean SessionRecord_CurrentRatchetKeyMatches(long s, long key) throws Exception;
   public static native long SessionRecord_Deserialize(byte[] data) throws Exception;
@@ -613,6 +672,12 @@ public final class Native {
 
   public static native CompletableFuture<byte[]> Svr3Backup(long asyncRuntime, long connectionManager, byte[] secret, String password, int maxTries, String username, String enclavePassword);
 
+  public static native CompletableFuture<byte[]> Svr3Migrate(long asyncRuntime, long connectionManager, long previousConnectionManager, long migrationState, String password, byte[] shareSet, int maxTries, String username, String enclavePassword);
+
+  public static native void Svr3MigrationState_Destroy(long handle);
+  public static native boolean Svr3MigrationState_hasRestoredSecret(long state);
+  public static native long Svr3MigrationState_new();
+
   public static native CompletableFuture<Void> Svr3Remove(long asyncRuntime, long connectionManager, String username, String enclavePassword);
 
   public static native CompletableFuture<byte[]> Svr3Restore(long asyncRuntime, long connectionManager, String password, byte[] shareSet, String username, String enclavePassword);
@@ -627,13 +692,23 @@ public final class Native {
   public static native void TESTING_ChatServiceErrorConvert(String errorDescription) throws Exception;
   public static native Object TESTING_ChatServiceResponseAndDebugInfoConvert() throws Exception;
   public static native Object TESTING_ChatServiceResponseConvert(boolean bodyPresent) throws Exception;
+  public static native void TESTING_ChatService_InjectConnectionInterrupted(long chat);
   public static native void TESTING_ChatService_InjectRawServerRequest(long chat, byte[] bytes);
+  public static native long TESTING_ChatService_NewWithFakeServer(long connectionManager, long server, String username, String password);
   public static native void TESTING_ErrorOnBorrowAsync(Object input);
   public static native CompletableFuture TESTING_ErrorOnBorrowIo(long asyncRuntime, Object input);
   public static native void TESTING_ErrorOnBorrowSync(Object input);
   public static native Object TESTING_ErrorOnReturnAsync(Object needsCleanup);
   public static native CompletableFuture<Object> TESTING_ErrorOnReturnIo(long asyncRuntime, Object needsCleanup);
   public static native Object TESTING_ErrorOnReturnSync(Object needsCleanup);
+  public static native long TESTING_FakeChatServerRequest_GetHttpRequest(long request);
+  public static native void TESTING_FakeChatServerRequest_Respond(long runtime, long request, int status, byte[] body, int delayMillis);
+  public static native void TESTING_FakeChatServer_DisconnectAll(long server);
+  public static native long TESTING_FakeChatServer_New();
+  public static native CompletableFuture<Long> TESTING_FakeChatServer_ReceiveRequest(long asyncRuntime, long server);
+  public static native void TESTING_FakeChatServer_SendEnvelope(long server, byte[] envelope, long serverDeliveryTimestamp) throws Exception;
+  public static native void TESTING_FakeChatServer_SendQueueEmpty(long server) throws Exception;
+  public static native void TESTING_FakeChatServer_SetRefuseConnections(long server, boolean refuse);
   public static native CompletableFuture<Integer> TESTING_FutureFailure(long asyncRuntime, int input);
   public static native CompletableFuture<Long> TESTING_FutureProducesOtherPointerType(long asyncRuntime, String input);
   public static native CompletableFuture<Long> TESTING_FutureProducesPointerType(long asyncRuntime, int input);
@@ -678,6 +753,7 @@ public final class Native {
   public static native String UsernameLink_DecryptUsername(byte[] entropy, byte[] encryptedUsername) throws Exception;
 
   public static native Object[] Username_CandidatesFrom(String nickname, int minLen, int maxLen) throws Exception;
+  public static native byte[][] Username_CandidatesWithProofsFrom(String nickname, int minLen, int maxLen, byte[] ranges, byte[] excluded, int count) throws Exception;
   public static native byte[] Username_Hash(String username) throws Exception;
   public static native byte[] Username_HashFromParts(String nickname, String discriminator, int minLen, int maxLen) throws Exception;
   public static native byte[] Username_Proof(String username, byte[] randomness) throws Exception;
@@ -685,12 +761,16 @@ public final class Native {
 
   public static native void UuidCiphertext_CheckValidContents(byte[] buffer) throws Exception;
 
+  public static native byte[] ValidatingMac_Checkpoint(long mac);
   public static native void ValidatingMac_Destroy(long handle);
   public static native int ValidatingMac_Finalize(long mac);
   public static native long ValidatingMac_Initialize(byte[] key, int chunkSize, byte[] digests);
+  public static native long ValidatingMac_Resume(byte[] key, int chunkSize, byte[] digests, byte[] checkpoint, InputStream savedInput) throws Exception;
   public static native int ValidatingMac_Update(long mac, byte[] bytes, int offset, int length);
+  public static native long ValidatingMac_ValidatedOffset(long mac);
 
   public static native void WebpSanitizer_Sanitize(InputStream input) throws Exception;
+  public static native long WebpSanitizer_SanitizeAndStripMetadata(InputStream input) throws Exception;
 
   public static native void initializeLibrary();
 }
@@ -0,0 +1,119 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.protocol;
+
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
+import java.time.Duration;
+import java.time.Instant;
+import java.util.ArrayList;
+import java.util.List;
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.protocol.state.SessionStore;
+
+/**
+ * Maintenance operations that apply to many sessions at once.
+ *
+ * <p>Each operation loads all the affected sessions from the {@link SessionStore} and stores back
+ * only the ones that changed. Each returns the addresses whose sessions were changed, in the order
+ * they were given.
+ */
+public final class SessionManagement {
+  private SessionManagement() {}
+
+  /**
+   * Archives the current session state for each of {@code addresses}.
+   *
+   * <p>Addresses with no session, or no current session state, are skipped.
+   */
+  public static List<SignalProtocolAddress> archiveSessions(
+      List<SignalProtocolAddress> addresses, SessionStore sessionStore) {
+    return updateSessions(
+        addresses,
+        handles -> Native.SessionManagement_ArchiveSessions(handles, sessionStore));
+  }
+
+  /**
+   * Archives the current session state for each of {@code addresses} that has not been used to
+   * encrypt or decrypt a message within {@code maxAge} of now.
+   *
+   * <p>Sessions that have never recorded a use are left alone.
+   */
+  public static List<SignalProtocolAddress> expireSessions(
+      List<SignalProtocolAddress> addresses, Duration maxAge, SessionStore sessionStore) {
+    return expireSessions(addresses, maxAge, sessionStore, Instant.now());
+  }
+
+  /**
+   * Archives the current session state for each of {@code addresses} that has not been used to
+   * encrypt or decrypt a message within {@code maxAge} of {@code now}.
+   *
+   * <p>Sessions that have never recorded a use are left alone.
+   */
+  public static List<SignalProtocolAddress> expireSessions(
+      List<SignalProtocolAddress> addresses,
+      Duration maxAge,
+      SessionStore sessionStore,
+      Instant now) {
+    if (maxAge.isNegative()) {
+      throw new IllegalArgumentException("maxAge must not be negative");
+    }
+    // Anything longer than this is more than 68 years, which is as good as never expiring.
+    int maxAgeSeconds = (int) Math.min(maxAge.getSeconds(), Integer.MAX_VALUE);
+    return updateSessions(
+        addresses,
+        handles ->
+            Native.SessionManagement_ExpireSessions(
+                handles, maxAgeSeconds, sessionStore, now.toEpochMilli()));
+  }
+
+  /**
+   * Discards all but the {@code maxPreviousSessions} most recent archived session states for each
+   * of {@code addresses}.
+   *
+   * <p>The current session state is never affected.
+   */
+  public static List<SignalProtocolAddress> prunePreviousSessions(
+      List<SignalProtocolAddress> addresses, int maxPreviousSessions, SessionStore sessionStore) {
+    if (maxPreviousSessions < 0) {
+      throw new IllegalArgumentException("maxPreviousSessions must not be negative");
+    }
+    return updateSessions(
+        addresses,
+        handles ->
+            Native.SessionManagement_PrunePreviousSessions(
+                handles, maxPreviousSessions, sessionStore));
+  }
+
+  private interface BulkOperation {
+    byte[] apply(long[] addressHandles) throws Exception;
+  }
+
+  private static List<SignalProtocolAddress> updateSessions(
+      List<SignalProtocolAddress> addresses, BulkOperation operation) {
+    // Unsafely access the native handles for the addresses,
+    // because try-with-resources syntax doesn't support a List of resources.
+    long[] addressHandles = new long[addresses.size()];
+    int i = 0;
+    for (SignalProtocolAddress nextAddress : addresses) {
+      addressHandles[i] = nextAddress.unsafeNativeHandleWithoutGuard();
+      i++;
+    }
+
+    byte[] changed = filterExceptions(() -> operation.apply(addressHandles));
+    // Manually keep the list of addresses from being garbage collected
+    // while we're using their native handles.
+    Native.keepAlive(addresses);
+
+    List<SignalProtocolAddress> result = new ArrayList<>();
+    for (i = 0; i < changed.length; i++) {
+      if (changed[i] != 0) {
+        result.add(addresses.get(i));
+      }
+    }
+    return result;
+  }
+}
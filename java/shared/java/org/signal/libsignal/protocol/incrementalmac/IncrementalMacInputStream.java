@@ -5,6 +5,8 @@
 
 package org.signal.libsignal.protocol.incrementalmac;
 
+import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
+
 import java.io.IOException;
 import java.io.InputStream;
 import java.nio.ByteBuffer;
@@ -23,13 +25,78 @@ public final class IncrementalMacInputStream extends InputStream {
 
   public IncrementalMacInputStream(
       InputStream inner, byte[] key, ChunkSizeChoice sizeChoice, byte[] digest) {
-    int chunkSize = sizeChoice.getSizeInBytes();
+    this(inner, sizeChoice.getSizeInBytes(), key, digest);
+  }
+
+  /**
+   * Resumes validation from a checkpoint saved with {@link #checkpoint()}, possibly by another
+   * process.
+   *
+   * <p>{@code savedInput} must produce the input from the start up to the checkpoint, as saved from
+   * an earlier stream; it is read and validated again, but doesn't have to be fetched again. {@code
+   * inner} must then continue from there.
+   *
+   * @throws IOException if {@code savedInput} can't be read, doesn't match, or is too short, or if
+   *     the checkpoint was made for a different chunk size
+   */
+  public IncrementalMacInputStream(
+      InputStream inner,
+      byte[] key,
+      ChunkSizeChoice sizeChoice,
+      byte[] digest,
+      byte[] checkpoint,
+      InputStream savedInput)
+      throws IOException {
+    this(inner, sizeChoice.getSizeInBytes(), key, digest, checkpoint, savedInput);
+  }
+
+  private IncrementalMacInputStream(InputStream inner, int chunkSize, byte[] key, byte[] digest) {
+    this(inner, chunkSize, Native.ValidatingMac_Initialize(key, chunkSize, digest));
+  }
+
+  private IncrementalMacInputStream(
+      InputStream inner,
+      int chunkSize,
+      byte[] key,
+      byte[] digest,
+      byte[] checkpoint,
+      InputStream savedInput)
+      throws IOException {
+    this(
+        inner,
+        chunkSize,
+        filterExceptions(
+            IOException.class,
+            () -> Native.ValidatingMac_Resume(key, chunkSize, digest, checkpoint, savedInput)));
+  }
+
+  private IncrementalMacInputStream(InputStream inner, int chunkSize, long validatingMac) {
     this.currentChunk = ByteBuffer.allocateDirect(chunkSize);
     this.currentChunk.limit(0);
-    this.validatingMac = Native.ValidatingMac_Initialize(key, chunkSize, digest);
+    this.validatingMac = validatingMac;
     this.inner = Channels.newChannel(inner);
   }
 
+  /**
+   * The number of bytes from the start of the input that have been validated so far.
+   *
+   * <p>A chunk is validated before any of it is returned from {@code read}. If a chunk fails to
+   * validate, this is where it starts.
+   */
+  public long validatedOffset() {
+    return Native.ValidatingMac_ValidatedOffset(this.validatingMac);
+  }
+
+  /**
+   * Saves how far validation has got, to resume from later with the resuming constructor.
+   *
+   * <p>This covers everything up to {@link #validatedOffset()}, so the input saved alongside it must
+   * include all of the current chunk. It must be called before the stream is closed.
+   */
+  public byte[] checkpoint() {
+    return Native.ValidatingMac_Checkpoint(this.validatingMac);
+  }
+
   @Override
   public int read() throws IOException {
     byte[] bytes = new byte[1];
@@ -7,9 +7,13 @@ package org.signal.libsignal.usernames;
 
 import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 
+import java.nio.ByteBuffer;
+import java.nio.charset.StandardCharsets;
 import java.security.SecureRandom;
 import java.util.ArrayList;
 import java.util.Arrays;
+import java.util.Collection;
+import java.util.Collections;
 import java.util.List;
 import java.util.Objects;
 import org.signal.libsignal.internal.Native;
@@ -52,6 +56,49 @@ public final class Username {
     }
   }
 
+  /** Picks {@code count} discriminators from {@code start} (inclusive) to {@code end}. */
+  public static class DiscriminatorRange {
+    private final int start;
+    private final int end;
+    private final int count;
+
+    public DiscriminatorRange(final int start, final int end, final int count) {
+      this.start = start;
+      this.end = end;
+      this.count = count;
+    }
+
+    public int getStart() {
+      return start;
+    }
+
+    public int getEnd() {
+      return end;
+    }
+
+    public int getCount() {
+      return count;
+    }
+  }
+
+  public static class CandidateWithProof {
+    private final Username username;
+    private final byte[] proof;
+
+    private CandidateWithProof(final Username username, final byte[] proof) {
+      this.username = username;
+      this.proof = proof;
+    }
+
+    public Username getUsername() {
+      return username;
+    }
+
+    public byte[] getProof() {
+      return proof;
+    }
+  }
+
   public Username(String username) throws BaseUsernameException {
     this.username = Objects.requireNonNull(username, "username");
     this.hash = hash(username);
@@ -83,6 +130,70 @@ public final class Username {
     return result;
   }
 
+  /**
+   * Generates up to {@code count} candidates, preferring shorter discriminators, along with a
+   * proof for each one.
+   */
+  public static List<CandidateWithProof> candidatesWithProofsFrom(
+      String nickname, int count, int minNicknameLength, int maxNicknameLength)
+      throws BaseUsernameException {
+    return candidatesWithProofsFrom(
+        nickname,
+        count,
+        minNicknameLength,
+        maxNicknameLength,
+        Collections.emptyList(),
+        Collections.emptyList());
+  }
+
+  /**
+   * Generates up to {@code count} candidates, taking discriminators from each of {@code ranges} in
+   * order and never using any in {@code excluded}, along with a proof for each one.
+   *
+   * <p>If {@code ranges} is empty, the default ranges are used, preferring shorter discriminators.
+   *
+   * @throws IllegalArgumentException if a range is empty, overlaps another one, or has fewer values
+   *     than its count.
+   * @throws BaseUsernameException if a range includes zero, single-digit, or too-large
+   *     discriminators, or the nickname is invalid.
+   */
+  public static List<CandidateWithProof> candidatesWithProofsFrom(
+      String nickname,
+      int count,
+      int minNicknameLength,
+      int maxNicknameLength,
+      List<DiscriminatorRange> ranges,
+      Collection<Integer> excluded)
+      throws BaseUsernameException {
+    ByteBuffer packedRanges = ByteBuffer.allocate(ranges.size() * 12);
+    for (DiscriminatorRange range : ranges) {
+      packedRanges.putInt(range.getStart()).putInt(range.getEnd()).putInt(range.getCount());
+    }
+    ByteBuffer packedExcluded = ByteBuffer.allocate(excluded.size() * 4);
+    for (int discriminator : excluded) {
+      packedExcluded.putInt(discriminator);
+    }
+    byte[][] flattened =
+        filterExceptions(
+            BaseUsernameException.class,
+            () ->
+                Native.Username_CandidatesWithProofsFrom(
+                    nickname,
+                    minNicknameLength,
+                    maxNicknameLength,
+                    packedRanges.array(),
+                    packedExcluded.array(),
+                    count));
+    // Each candidate is represented as its username, hash, and proof.
+    ArrayList<CandidateWithProof> result = new ArrayList<>(flattened.length / 3);
+    for (int i = 0; i < flattened.length; i += 3) {
+      Username username =
+          new Username(new String(flattened[i], StandardCharsets.UTF_8), flattened[i + 1]);
+      result.add(new CandidateWithProof(username, flattened[i + 2]));
+    }
+    return result;
+  }
+
   public static Username fromParts(
       String nickname, String discriminator, int minNicknameLength, int maxNicknameLength)
       throws BaseUsernameException {
@@ -0,0 +1,21 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.zkgroup;
+
+/** Thrown when one or more items in a batch fail verification. */
+public class BatchVerificationFailedException extends VerificationFailedException {
+  private final int[] failedIndexes;
+
+  public BatchVerificationFailedException(String msg, int[] failedIndexes) {
+    super(msg);
+    this.failedIndexes = failedIndexes;
+  }
+
+  /** The indexes of every item in the batch that failed verification, in increasing order. */
+  public int[] getFailedIndexes() {
+    return failedIndexes.clone();
+  }
+}
@@ -10,13 +10,16 @@ import static org.signal.libsignal.zkgroup.internal.Constants.RANDOM_LENGTH;
 
 import java.security.SecureRandom;
 import java.time.Instant;
+import java.util.List;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.protocol.ServiceId.Aci;
 import org.signal.libsignal.protocol.ServiceId.Pni;
+import org.signal.libsignal.zkgroup.BatchVerificationFailedException;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 import org.signal.libsignal.zkgroup.ServerSecretParams;
 import org.signal.libsignal.zkgroup.VerificationFailedException;
 import org.signal.libsignal.zkgroup.groups.GroupPublicParams;
+import org.signal.libsignal.zkgroup.internal.ByteArray;
 
 public class ServerZkAuthOperations {
 
@@ -104,4 +107,41 @@ public class ServerZkAuthOperations {
                         authCredentialPresentation.getInternalContentsForJNI(),
                         currentTime.getEpochSecond())));
   }
+
+  public void verifyAuthCredentialPresentations(
+      List<GroupPublicParams> groupPublicParams, List<AuthCredentialPresentation> presentations)
+      throws BatchVerificationFailedException {
+    verifyAuthCredentialPresentations(groupPublicParams, presentations, Instant.now());
+  }
+
+  /**
+   * Verifies many auth credential presentations at once.
+   *
+   * <p>This is equivalent to calling {@link #verifyAuthCredentialPresentation} for each
+   * presentation, but is much faster for large batches.
+   *
+   * @param groupPublicParams the group each presentation is for, in the same order as {@code
+   *     presentations}
+   * @throws BatchVerificationFailedException listing every presentation that failed
+   * @throws IllegalArgumentException if the two lists are different sizes
+   */
+  public void verifyAuthCredentialPresentations(
+      List<GroupPublicParams> groupPublicParams,
+      List<AuthCredentialPresentation> presentations,
+      Instant currentTime)
+      throws BatchVerificationFailedException {
+    if (groupPublicParams.size() != presentations.size()) {
+      throw new IllegalArgumentException("each presentation needs its group's public params");
+    }
+    filterExceptions(
+        BatchVerificationFailedException.class,
+        () ->
+            serverSecretParams.guardedRunChecked(
+                (secretParams) ->
+                    Native.ServerSecretParams_VerifyAuthCredentialPresentations(
+                        secretParams,
+                        ByteArray.toDirectBuffersForJNI(groupPublicParams),
+                        ByteArray.toDirectBuffersForJNI(presentations),
+                        currentTime.getEpochSecond())));
+  }
 }
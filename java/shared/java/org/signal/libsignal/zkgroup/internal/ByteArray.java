@@ -5,7 +5,9 @@
 
 package org.signal.libsignal.zkgroup.internal;
 
+import java.nio.ByteBuffer;
 import java.util.Arrays;
+import java.util.List;
 import java.util.Locale;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 
@@ -56,6 +58,18 @@ public abstract class ByteArray {
     return contents.clone();
   }
 
+  /** Copies the contents of each item into a direct buffer, for passing a batch to JNI. */
+  public static ByteBuffer[] toDirectBuffersForJNI(List<? extends ByteArray> items) {
+    ByteBuffer[] buffers = new ByteBuffer[items.size()];
+    int nextOffset = 0;
+    for (ByteArray next : items) {
+      buffers[nextOffset] = ByteBuffer.allocateDirect(next.contents.length);
+      buffers[nextOffset].put(next.contents);
+      ++nextOffset;
+    }
+    return buffers;
+  }
+
   @Override
   public int hashCode() {
     return getClass().hashCode() * 31 + Arrays.hashCode(contents);
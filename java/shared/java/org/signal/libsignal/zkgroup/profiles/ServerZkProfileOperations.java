@@ -11,12 +11,15 @@ import static org.signal.libsignal.zkgroup.internal.Constants.RANDOM_LENGTH;
 import java.security.SecureRandom;
 import java.time.Instant;
 import java.time.temporal.ChronoUnit;
+import java.util.List;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.protocol.ServiceId.Aci;
+import org.signal.libsignal.zkgroup.BatchVerificationFailedException;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 import org.signal.libsignal.zkgroup.ServerSecretParams;
 import org.signal.libsignal.zkgroup.VerificationFailedException;
 import org.signal.libsignal.zkgroup.groups.GroupPublicParams;
+import org.signal.libsignal.zkgroup.internal.ByteArray;
 
 public class ServerZkProfileOperations {
 
@@ -99,4 +102,42 @@ public class ServerZkProfileOperations {
                         profileKeyCredentialPresentation.getInternalContentsForJNI(),
                         now.getEpochSecond())));
   }
+
+  public void verifyProfileKeyCredentialPresentations(
+      List<GroupPublicParams> groupPublicParams,
+      List<ProfileKeyCredentialPresentation> presentations)
+      throws BatchVerificationFailedException {
+    verifyProfileKeyCredentialPresentations(groupPublicParams, presentations, Instant.now());
+  }
+
+  /**
+   * Verifies many profile key credential presentations at once.
+   *
+   * <p>This is equivalent to calling {@link #verifyProfileKeyCredentialPresentation} for each
+   * presentation, but is much faster for large batches.
+   *
+   * @param groupPublicParams the group each presentation is for, in the same order as {@code
+   *     presentations}
+   * @throws BatchVerificationFailedException listing every presentation that failed
+   * @throws IllegalArgumentException if the two lists are different sizes
+   */
+  public void verifyProfileKeyCredentialPresentations(
+      List<GroupPublicParams> groupPublicParams,
+      List<ProfileKeyCredentialPresentation> presentations,
+      Instant now)
+      throws BatchVerificationFailedException {
+    if (groupPublicParams.size() != presentations.size()) {
+      throw new IllegalArgumentException("each presentation needs its group's public params");
+    }
+    filterExceptions(
+        BatchVerificationFailedException.class,
+        () ->
+            serverSecretParams.guardedRunChecked(
+                (secretParams) ->
+                    Native.ServerSecretParams_VerifyProfileKeyCredentialPresentations(
+                        secretParams,
+                        ByteArray.toDirectBuffersForJNI(groupPublicParams),
+                        ByteArray.toDirectBuffersForJNI(presentations),
+                        now.getEpochSecond())));
+  }
 }
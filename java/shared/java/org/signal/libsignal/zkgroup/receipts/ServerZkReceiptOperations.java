@@ -9,10 +9,13 @@ import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 import static org.signal.libsignal.zkgroup.internal.Constants.RANDOM_LENGTH;
 
 import java.security.SecureRandom;
+import java.util.List;
 import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.zkgroup.BatchVerificationFailedException;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 import org.signal.libsignal.zkgroup.ServerSecretParams;
 import org.signal.libsignal.zkgroup.VerificationFailedException;
+import org.signal.libsignal.zkgroup.internal.ByteArray;
 
 public class ServerZkReceiptOperations {
 
@@ -68,4 +71,23 @@ public class ServerZkReceiptOperations {
                     Native.ServerSecretParams_VerifyReceiptCredentialPresentation(
                         secretParams, receiptCredentialPresentation.getInternalContentsForJNI())));
   }
+
+  /**
+   * Verifies many receipt credential presentations at once.
+   *
+   * <p>This is equivalent to calling {@link #verifyReceiptCredentialPresentation} for each
+   * presentation, but is much faster for large batches.
+   *
+   * @throws BatchVerificationFailedException listing every presentation that failed
+   */
+  public void verifyReceiptCredentialPresentations(
+      List<ReceiptCredentialPresentation> presentations) throws BatchVerificationFailedException {
+    filterExceptions(
+        BatchVerificationFailedException.class,
+        () ->
+            serverSecretParams.guardedRunChecked(
+                (secretParams) ->
+                    Native.ServerSecretParams_VerifyReceiptCredentialPresentations(
+                        secretParams, ByteArray.toDirectBuffersForJNI(presentations))));
+  }
 }
@@ -55,6 +55,19 @@ interface SealedSenderMultiRecipientMessage {
   offsetOfSharedData: number;
 }
 
+interface SealedSenderDecryptionMetadata {
+  senderUuid: string;
+  senderE164: string | null;
+  senderDeviceId: number;
+  certificateExpiration: Timestamp;
+  timestampSkewMillis: number | null;
+}
+
+interface SealedSenderDecryptedMessage {
+  metadata: SealedSenderDecryptionMetadata;
+  message: Buffer;
+}
+
 export abstract class IdentityKeyStore {
   _getIdentityKey(): Promise<PrivateKey>;
   _getLocalRegistrationId(): Promise<number>;
@@ -114,6 +127,16 @@ export abstract class InputStream {
 
 export abstract class SyncInputStream extends Buffer {}
 
+export abstract class ChatListener {
+  _incoming_message(
+    envelope: Buffer,
+    timestamp: number,
+    ack: ServerMessageAck
+  ): void;
+  _queue_empty(): void;
+  _connection_interrupted(error: Error): void;
+}
+
 type Wrapper<T> = Readonly<{
   _nativeHandle: T;
 }>;
@@ -174,15 +197,34 @@ export function ChatService_connect_auth(asyncRuntime: Wrapper<TokioAsyncContext
 export function ChatService_connect_unauth(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>): Promise<ChatServiceDebugInfo>;
 export function ChatService_disconnect(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>): Promise<void>;
 export function ChatService_new(connectionManager: Wrapper<ConnectionManager>, username: string, password: string): Chat;
+export function ChatService_preconnect(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>): Promise<void>;
 export function ChatService_unauth_send(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ChatResponse>;
 export function ChatService_unauth_send_and_debug(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ResponseAndDebugInfo>;
+export function ChatServer_SetListener(runtime: Wrapper<TokioAsyncContext>, chat: Wrapper<Chat>, makeListener: ChatListener | null): void;
 export function CiphertextMessage_FromPlaintextContent(m: Wrapper<PlaintextContent>): CiphertextMessage;
 export function CiphertextMessage_Serialize(obj: Wrapper<CiphertextMessage>): Buffer;
 export function CiphertextMessage_Type(msg: Wrapper<CiphertextMessage>): number;
+export function ConnectionEvent_GetConsecutiveFailures(event: Wrapper<ConnectionEvent>): number | null;
+export function ConnectionEvent_GetDnsSource(event: Wrapper<ConnectionEvent>): string | null;
+export function ConnectionEvent_GetDurationMillis(event: Wrapper<ConnectionEvent>): number;
+export function ConnectionEvent_GetHost(event: Wrapper<ConnectionEvent>): string | null;
+export function ConnectionEvent_GetHttpStatus(event: Wrapper<ConnectionEvent>): number | null;
+export function ConnectionEvent_GetIpType(event: Wrapper<ConnectionEvent>): number | null;
+export function ConnectionEvent_GetKind(event: Wrapper<ConnectionEvent>): number;
+export function ConnectionEvent_GetRoute(event: Wrapper<ConnectionEvent>): string;
+export function ConnectionEvent_GetRouteAttemptOutcome(event: Wrapper<ConnectionEvent>): string | null;
+export function ConnectionEvent_GetSucceeded(event: Wrapper<ConnectionEvent>): boolean;
 export function ConnectionManager_clear_proxy(connectionManager: Wrapper<ConnectionManager>): void;
+export function ConnectionManager_network_changed(connectionManager: Wrapper<ConnectionManager>): void;
 export function ConnectionManager_new(environment: number, userAgent: string): ConnectionManager;
+export function ConnectionManager_new_with_timeouts(environment: number, userAgent: string, connectTimeoutMillis: number, requestTimeoutMillis: number): ConnectionManager;
+export function ConnectionManager_reset_backoff_policy(connectionManager: Wrapper<ConnectionManager>): void;
+export function ConnectionManager_set_backoff_policy(connectionManager: Wrapper<ConnectionManager>, initialDelayMillis: number, maxDelayMillis: number, multiplierPercent: number, jitterPercent: number, maxAttempts: number): void;
+export function ConnectionManager_set_http_proxy(connectionManager: Wrapper<ConnectionManager>, host: string, port: number, username: string | null, password: string | null): void;
 export function ConnectionManager_set_ipv6_enabled(connectionManager: Wrapper<ConnectionManager>, ipv6Enabled: boolean): void;
 export function ConnectionManager_set_proxy(connectionManager: Wrapper<ConnectionManager>, host: string, port: number): void;
+export function ConnectionManager_set_socks_proxy(connectionManager: Wrapper<ConnectionManager>, host: string, port: number, username: string | null, password: string | null): void;
+export function ConnectionManager_take_connection_event(connectionManager: Wrapper<ConnectionManager>): ConnectionEvent | null;
 export function CreateCallLinkCredentialPresentation_CheckValidContents(presentationBytes: Buffer): void;
 export function CreateCallLinkCredentialPresentation_Verify(presentationBytes: Buffer, roomId: Buffer, now: Timestamp, serverParamsBytes: Buffer, callLinkParamsBytes: Buffer): void;
 export function CreateCallLinkCredentialRequestContext_CheckValidContents(contextBytes: Buffer): void;
@@ -213,6 +255,7 @@ export function GenericServerPublicParams_CheckValidContents(paramsBytes: Buffer
 export function GenericServerSecretParams_CheckValidContents(paramsBytes: Buffer): void;
 export function GenericServerSecretParams_GenerateDeterministic(randomness: Buffer): Buffer;
 export function GenericServerSecretParams_GetPublicParams(paramsBytes: Buffer): Buffer;
+export function GifSanitizer_Sanitize(input: SyncInputStream): SanitizedImage;
 export function GroupCipher_DecryptMessage(sender: Wrapper<ProtocolAddress>, message: Buffer, store: SenderKeyStore): Promise<Buffer>;
 export function GroupCipher_EncryptMessage(sender: Wrapper<ProtocolAddress>, distributionId: Uuid, message: Buffer, store: SenderKeyStore): Promise<CiphertextMessage>;
 export function GroupMasterKey_CheckValidContents(buffer: Buffer): void;
@@ -286,6 +329,10 @@ export function LookupRequest_setReturnAcisWithoutUaks(request: Wrapper<LookupRe
 export function LookupRequest_setToken(request: Wrapper<LookupRequest>, token: Buffer): void;
 export function MessageBackupKey_New(masterKey: Buffer, aci: Buffer): MessageBackupKey;
 export function MessageBackupValidator_Validate(key: Wrapper<MessageBackupKey>, firstStream: InputStream, secondStream: InputStream, len: bigint, purpose: number): Promise<MessageBackupValidationOutcome>;
+export function MessageBackupValidator_ValidateStream(key: Wrapper<MessageBackupKey>, stream: InputStream, purpose: number): Promise<MessageBackupValidationOutcome>;
+export function MessageBackupWriter_Finish(writer: Wrapper<MessageBackupWriter>): Buffer;
+export function MessageBackupWriter_New(key: Wrapper<MessageBackupKey>, backupInfo: Buffer, purpose: number): MessageBackupWriter;
+export function MessageBackupWriter_WriteFrame(writer: Wrapper<MessageBackupWriter>, frame: Buffer): Buffer;
 export function MinidumpToJSONString(buffer: Buffer): string;
 export function Mp4Sanitizer_Sanitize(input: InputStream, len: bigint): Promise<SanitizedMetadata>;
 export function PlaintextContent_Deserialize(data: Buffer): PlaintextContent;
@@ -355,6 +402,10 @@ export function ReceiptCredentialResponse_CheckValidContents(buffer: Buffer): vo
 export function ReceiptCredential_CheckValidContents(buffer: Buffer): void;
 export function ReceiptCredential_GetReceiptExpirationTime(receiptCredential: Serialized<ReceiptCredential>): Timestamp;
 export function ReceiptCredential_GetReceiptLevel(receiptCredential: Serialized<ReceiptCredential>): bigint;
+export function SanitizedImage_GetData(sanitized: Wrapper<SanitizedImage>): Buffer;
+export function SanitizedImage_GetFrameCount(sanitized: Wrapper<SanitizedImage>): number;
+export function SanitizedImage_GetHeight(sanitized: Wrapper<SanitizedImage>): number;
+export function SanitizedImage_GetWidth(sanitized: Wrapper<SanitizedImage>): number;
 export function SanitizedMetadata_GetDataLen(sanitized: Wrapper<SanitizedMetadata>): bigint;
 export function SanitizedMetadata_GetDataOffset(sanitized: Wrapper<SanitizedMetadata>): bigint;
 export function SanitizedMetadata_GetMetadata(sanitized: Wrapper<SanitizedMetadata>): Buffer;
@@ -365,10 +416,12 @@ export function SealedSenderDecryptionResult_GetSenderUuid(obj: Wrapper<SealedSe
 export function SealedSenderDecryptionResult_Message(obj: Wrapper<SealedSenderDecryptionResult>): Buffer;
 export function SealedSenderMultiRecipientMessage_Parse(buffer: Buffer): SealedSenderMultiRecipientMessage;
 export function SealedSender_DecryptMessage(message: Buffer, trustRoot: Wrapper<PublicKey>, timestamp: Timestamp, localE164: string | null, localUuid: string, localDeviceId: number, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, kyberPrekeyStore: KyberPreKeyStore): Promise<SealedSenderDecryptionResult>;
+export function SealedSender_DecryptMessageWithOptions(message: Buffer, trustRoot: Wrapper<PublicKey>, timestamp: Timestamp, localE164: string | null, localUuid: string, localDeviceId: number, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, kyberPrekeyStore: KyberPreKeyStore, maxPlaintextSize: number | null, localTime: Timestamp | null, paddingBucketSize: number | null): Promise<SealedSenderDecryptedMessage>;
 export function SealedSender_DecryptToUsmc(ctext: Buffer, identityStore: IdentityKeyStore): Promise<UnidentifiedSenderMessageContent>;
 export function SealedSender_Encrypt(destination: Wrapper<ProtocolAddress>, content: Wrapper<UnidentifiedSenderMessageContent>, identityKeyStore: IdentityKeyStore): Promise<Buffer>;
 export function SealedSender_MultiRecipientEncrypt(recipients: Wrapper<ProtocolAddress>[], recipientSessions: Wrapper<SessionRecord>[], excludedRecipients: Buffer, content: Wrapper<UnidentifiedSenderMessageContent>, identityKeyStore: IdentityKeyStore): Promise<Buffer>;
 export function SealedSender_MultiRecipientMessageForSingleRecipient(encodedMultiRecipientMessage: Buffer): Buffer;
+export function SealedSender_PadPlaintext(plaintext: Buffer, bucketSize: number): Buffer;
 export function SenderCertificate_Deserialize(data: Buffer): SenderCertificate;
 export function SenderCertificate_GetCertificate(obj: Wrapper<SenderCertificate>): Buffer;
 export function SenderCertificate_GetDeviceId(obj: Wrapper<SenderCertificate>): number;
@@ -408,6 +461,7 @@ export function ServerCertificate_GetSerialized(obj: Wrapper<ServerCertificate>)
 export function ServerCertificate_GetSignature(obj: Wrapper<ServerCertificate>): Buffer;
 export function ServerCertificate_New(keyId: number, serverKey: Wrapper<PublicKey>, trustRoot: Wrapper<PrivateKey>): ServerCertificate;
 export function ServerMessageAck_Send(asyncRuntime: Wrapper<TokioAsyncContext>, ack: Wrapper<ServerMessageAck>): Promise<void>;
+export function ServerMessageAck_SendNack(asyncRuntime: Wrapper<TokioAsyncContext>, ack: Wrapper<ServerMessageAck>): Promise<void>;
 export function ServerPublicParams_CreateAuthCredentialWithPniPresentationDeterministic(serverPublicParams: Wrapper<ServerPublicParams>, randomness: Buffer, groupSecretParams: Serialized<GroupSecretParams>, authCredentialWithPniBytes: Buffer): Buffer;
 export function ServerPublicParams_CreateExpiringProfileKeyCredentialPresentationDeterministic(serverPublicParams: Wrapper<ServerPublicParams>, randomness: Buffer, groupSecretParams: Serialized<GroupSecretParams>, profileKeyCredential: Serialized<ExpiringProfileKeyCredential>): Buffer;
 export function ServerPublicParams_CreateProfileKeyCredentialRequestContextDeterministic(serverPublicParams: Wrapper<ServerPublicParams>, randomness: Buffer, userId: Buffer, profileKey: Serialized<ProfileKey>): Serialized<ProfileKeyCredentialRequestContext>;
@@ -440,6 +494,9 @@ export function SessionBuilder_ProcessPreKeyBundle(bundle: Wrapper<PreKeyBundle>
 export function SessionCipher_DecryptPreKeySignalMessage(message: Wrapper<PreKeySignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, kyberPrekeyStore: KyberPreKeyStore): Promise<Buffer>;
 export function SessionCipher_DecryptSignalMessage(message: Wrapper<SignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore): Promise<Buffer>;
 export function SessionCipher_EncryptMessage(ptext: Buffer, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, now: Timestamp): Promise<CiphertextMessage>;
+export function SessionManagement_ArchiveSessions(addresses: Wrapper<ProtocolAddress>[], sessionStore: SessionStore): Promise<Buffer>;
+export function SessionManagement_ExpireSessions(addresses: Wrapper<ProtocolAddress>[], maxAgeSeconds: number, sessionStore: SessionStore, now: Timestamp): Promise<Buffer>;
+export function SessionManagement_PrunePreviousSessions(addresses: Wrapper<ProtocolAddress>[], maxPreviousSessions: number, sessionStore: SessionStore): Promise<Buffer>;
 export function SessionRecord_ArchiveCurrentState(sessionRecord: Wrapper<SessionRecord>): void;
 export function SessionRecord_CurrentRatchetKeyMatches(s: Wrapper<SessionRecord>, key: Wrapper<PublicKey>): boolean;
 export function SessionRecord_Deserialize(data: Buffer): SessionRecord;
@@ -468,6 +525,9 @@ export function SignedPreKeyRecord_GetTimestamp(obj: Wrapper<SignedPreKeyRecord>
 export function SignedPreKeyRecord_New(id: number, timestamp: Timestamp, pubKey: Wrapper<PublicKey>, privKey: Wrapper<PrivateKey>, signature: Buffer): SignedPreKeyRecord;
 export function SignedPreKeyRecord_Serialize(obj: Wrapper<SignedPreKeyRecord>): Buffer;
 export function Svr3Backup(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, secret: Buffer, password: string, maxTries: number, username: string, enclavePassword: string): Promise<Buffer>;
+export function Svr3Migrate(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, previousConnectionManager: Wrapper<ConnectionManager>, migrationState: Wrapper<Svr3MigrationState>, password: string, shareSet: Buffer, maxTries: number, username: string, enclavePassword: string): Promise<Buffer>;
+export function Svr3MigrationState_hasRestoredSecret(state: Wrapper<Svr3MigrationState>): boolean;
+export function Svr3MigrationState_new(): Svr3MigrationState;
 export function Svr3Remove(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, enclavePassword: string): Promise<void>;
 export function Svr3Restore(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, password: string, shareSet: Buffer, username: string, enclavePassword: string): Promise<Buffer>;
 export function TESTING_CdsiLookupErrorConvert(errorDescription: string): void;
@@ -480,13 +540,23 @@ export function TESTING_ChatServiceDebugInfoConvert(): ChatServiceDebugInfo;
 export function TESTING_ChatServiceErrorConvert(errorDescription: string): void;
 export function TESTING_ChatServiceResponseAndDebugInfoConvert(): ResponseAndDebugInfo;
 export function TESTING_ChatServiceResponseConvert(bodyPresent: boolean): ChatResponse;
+export function TESTING_ChatService_InjectConnectionInterrupted(chat: Wrapper<Chat>): void;
 export function TESTING_ChatService_InjectRawServerRequest(chat: Wrapper<Chat>, bytes: Buffer): void;
+export function TESTING_ChatService_NewWithFakeServer(connectionManager: Wrapper<ConnectionManager>, server: Wrapper<FakeChatServer>, username: string, password: string): Chat;
 export function TESTING_ErrorOnBorrowAsync(_input: null): Promise<void>;
 export function TESTING_ErrorOnBorrowIo(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, _input: null): Promise<void>;
 export function TESTING_ErrorOnBorrowSync(_input: null): void;
 export function TESTING_ErrorOnReturnAsync(_needsCleanup: null): Promise<null>;
 export function TESTING_ErrorOnReturnIo(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, _needsCleanup: null): Promise<null>;
 export function TESTING_ErrorOnReturnSync(_needsCleanup: null): null;
+export function TESTING_FakeChatServerRequest_GetHttpRequest(request: Wrapper<FakeChatServerRequest>): HttpRequest;
+export function TESTING_FakeChatServerRequest_Respond(runtime: Wrapper<TokioAsyncContext>, request: Wrapper<FakeChatServerRequest>, status: number, body: Buffer, delayMillis: number): void;
+export function TESTING_FakeChatServer_DisconnectAll(server: Wrapper<FakeChatServer>): void;
+export function TESTING_FakeChatServer_New(): FakeChatServer;
+export function TESTING_FakeChatServer_ReceiveRequest(asyncRuntime: Wrapper<TokioAsyncContext>, server: Wrapper<FakeChatServer>): Promise<FakeChatServerRequest>;
+export function TESTING_FakeChatServer_SendEnvelope(server: Wrapper<FakeChatServer>, envelope: Buffer, serverDeliveryTimestamp: Timestamp): void;
+export function TESTING_FakeChatServer_SendQueueEmpty(server: Wrapper<FakeChatServer>): void;
+export function TESTING_FakeChatServer_SetRefuseConnections(server: Wrapper<FakeChatServer>, refuse: boolean): void;
 export function TESTING_FutureFailure(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, _input: number): Promise<number>;
 export function TESTING_FutureProducesOtherPointerType(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, input: string): Promise<OtherTestingHandleType>;
 export function TESTING_FutureProducesPointerType(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, input: number): Promise<TestingHandleType>;
@@ -522,24 +592,32 @@ export function UnidentifiedSenderMessageContent_Serialize(obj: Wrapper<Unidenti
 export function UsernameLink_Create(username: string, entropy: Buffer | null): Buffer;
 export function UsernameLink_DecryptUsername(entropy: Buffer, encryptedUsername: Buffer): string;
 export function Username_CandidatesFrom(nickname: string, minLen: number, maxLen: number): string[];
+export function Username_CandidatesWithProofsFrom(nickname: string, minLen: number, maxLen: number, ranges: Buffer, excluded: Buffer, count: number): Buffer[];
 export function Username_Hash(username: string): Buffer;
 export function Username_HashFromParts(nickname: string, discriminator: string, minLen: number, maxLen: number): Buffer;
 export function Username_Proof(username: string, randomness: Buffer): Buffer;
 export function Username_Verify(proof: Buffer, hash: Buffer): void;
 export function UuidCiphertext_CheckValidContents(buffer: Buffer): void;
+export function ValidatingMac_Checkpoint(mac: Wrapper<ValidatingMac>): Buffer;
 export function ValidatingMac_Finalize(mac: Wrapper<ValidatingMac>): number;
 export function ValidatingMac_Initialize(key: Buffer, chunkSize: number, digests: Buffer): ValidatingMac;
+export function ValidatingMac_Resume(key: Buffer, chunkSize: number, digests: Buffer, checkpoint: Buffer, savedInput: InputStream): Promise<ValidatingMac>;
 export function ValidatingMac_Update(mac: Wrapper<ValidatingMac>, bytes: Buffer, offset: number, length: number): number;
+export function ValidatingMac_ValidatedOffset(mac: Wrapper<ValidatingMac>): bigint;
 export function WebpSanitizer_Sanitize(input: SyncInputStream): void;
+export function WebpSanitizer_SanitizeAndStripMetadata(input: SyncInputStream): SanitizedImage;
 export function initLogger(maxLevel: LogLevel, callback: (level: LogLevel, target: string, file: string | null, line: number | null, message: string) => void): void
 interface Aes256GcmSiv { readonly __type: unique symbol; }
 interface CdsiLookup { readonly __type: unique symbol; }
 interface Chat { readonly __type: unique symbol; }
 interface CiphertextMessage { readonly __type: unique symbol; }
+interface ConnectionEvent { readonly __type: unique symbol; }
 interface ConnectionManager { readonly __type: unique symbol; }
 interface DecryptionErrorMessage { readonly __type: unique symbol; }
 interface ExpiringProfileKeyCredential { readonly __type: unique symbol; }
 interface ExpiringProfileKeyCredentialResponse { readonly __type: unique symbol; }
+interface FakeChatServer { readonly __type: unique symbol; }
+interface FakeChatServerRequest { readonly __type: unique symbol; }
 interface Fingerprint { readonly __type: unique symbol; }
 interface GroupMasterKey { readonly __type: unique symbol; }
 interface GroupPublicParams { readonly __type: unique symbol; }
@@ -553,6 +631,7 @@ interface KyberPublicKey { readonly __type: unique symbol; }
 interface KyberSecretKey { readonly __type: unique symbol; }
 interface LookupRequest { readonly __type: unique symbol; }
 interface MessageBackupKey { readonly __type: unique symbol; }
+interface MessageBackupWriter { readonly __type: unique symbol; }
 interface NonSuspendingBackgroundThreadRuntime { readonly __type: unique symbol; }
 interface OtherTestingHandleType { readonly __type: unique symbol; }
 interface PlaintextContent { readonly __type: unique symbol; }
@@ -572,6 +651,7 @@ interface ReceiptCredentialPresentation { readonly __type: unique symbol; }
 interface ReceiptCredentialRequest { readonly __type: unique symbol; }
 interface ReceiptCredentialRequestContext { readonly __type: unique symbol; }
 interface ReceiptCredentialResponse { readonly __type: unique symbol; }
+interface SanitizedImage { readonly __type: unique symbol; }
 interface SanitizedMetadata { readonly __type: unique symbol; }
 interface SealedSenderDecryptionResult { readonly __type: unique symbol; }
 interface SenderCertificate { readonly __type: unique symbol; }
@@ -586,6 +666,7 @@ interface SessionRecord { readonly __type: unique symbol; }
 interface SgxClientState { readonly __type: unique symbol; }
 interface SignalMessage { readonly __type: unique symbol; }
 interface SignedPreKeyRecord { readonly __type: unique symbol; }
+interface Svr3MigrationState { readonly __type: unique symbol; }
 interface TestingHandleType { readonly __type: unique symbol; }
 interface TokioAsyncContext { readonly __type: unique symbol; }
 interface UnidentifiedSenderMessageContent { readonly __type: unique symbol; }
@@ -16,6 +16,9 @@ export enum ErrorCode {
   VerificationFailed,
   InvalidSession,
   InvalidSenderKeySession,
+  SealedSenderUntrustedCertificate,
+  SealedSenderPlaintextTooLarge,
+  SealedSenderInvalidPadding,
 
   NicknameCannotBeEmpty,
   CannotStartWithDigit,
@@ -58,6 +61,15 @@ export class LibSignalErrorBase extends Error {
   public readonly code: ErrorCode;
   public readonly operation: string;
   readonly _addr?: string | Native.ProtocolAddress;
+  /**
+   * Who a sealed sender message was from, if decryption got far enough to
+   * find out.
+   *
+   * Only set by `sealedSenderDecryptMessageWithOptions`. The sender
+   * certificate has not been validated if the error is a
+   * `SealedSenderUntrustedCertificate` error.
+   */
+  readonly sealedSenderMetadata?: Native.SealedSenderDecryptionMetadata;
 
   constructor(
     message: string,
@@ -137,6 +149,25 @@ export type InvalidSenderKeySessionError = LibSignalErrorCommon & {
   distributionId: string;
 };
 
+export type SealedSenderUntrustedCertificateError = LibSignalErrorCommon & {
+  code: ErrorCode.SealedSenderUntrustedCertificate;
+  sealedSenderMetadata: Native.SealedSenderDecryptionMetadata;
+};
+
+export type SealedSenderPlaintextTooLargeError = LibSignalErrorCommon & {
+  code: ErrorCode.SealedSenderPlaintextTooLarge;
+  sealedSenderMetadata: Native.SealedSenderDecryptionMetadata;
+  /** A lower bound on the size of the plaintext, in bytes. */
+  readonly size: number;
+  readonly maxSize: number;
+};
+
+export type SealedSenderInvalidPaddingError = LibSignalErrorCommon & {
+  code: ErrorCode.SealedSenderInvalidPadding;
+  sealedSenderMetadata: Native.SealedSenderDecryptionMetadata;
+  readonly bucketSize: number;
+};
+
 export type NicknameCannotBeEmptyError = LibSignalErrorCommon & {
   code: ErrorCode.NicknameCannotBeEmpty;
 };
@@ -253,6 +284,9 @@ export type LibSignalError =
   | VerificationFailedError
   | InvalidSessionError
   | InvalidSenderKeySessionError
+  | SealedSenderUntrustedCertificateError
+  | SealedSenderPlaintextTooLargeError
+  | SealedSenderInvalidPaddingError
   | NicknameCannotBeEmptyError
   | CannotStartWithDigitError
   | MissingSeparatorError
@@ -0,0 +1,36 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+/**
+ * A GIF format sanitizer.
+ *
+ * The sanitizer checks the validity of a GIF file input and removes blocks that aren't needed to
+ * display it, such as comments and XMP metadata.
+ *
+ * @module GifSanitizer
+ */
+
+import * as Native from '../Native';
+import {
+  IoError,
+  InvalidMediaInputError,
+  UnsupportedMediaInputError,
+} from './Errors';
+import { SanitizedImage } from './SanitizedImage';
+
+export { SanitizedImage } from './SanitizedImage';
+
+/**
+ * Sanitize a GIF input.
+ *
+ * @param input A GIF format input stream.
+ * @returns The sanitized GIF file and its dimensions.
+ * @throws {IoError} If an IO error on the input occurs.
+ * @throws {InvalidMediaInputError} If the input could not be parsed because it was invalid.
+ * @throws {UnsupportedMediaInputError} If the input could not be parsed because it's unsupported in some way.
+ */
+export function sanitize(input: Buffer): SanitizedImage {
+  return SanitizedImage._fromNativeHandle(Native.GifSanitizer_Sanitize(input));
+}
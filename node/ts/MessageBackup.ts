@@ -71,6 +71,63 @@ export enum Purpose {
   RemoteBackup = 1,
 }
 
+/**
+ * Produces an encrypted message backup bundle from serialized `Frame`s.
+ *
+ * Each frame is validated before it is written, using the same checks as
+ * {@link validate}, so a frame that would make the backup invalid is rejected
+ * without being written. The encrypted bundle is returned a piece at a time,
+ * and is only complete once {@link MessageBackupWriter#finish} succeeds.
+ */
+export class MessageBackupWriter {
+  readonly _nativeHandle: Native.MessageBackupWriter;
+
+  /**
+   * Start a new backup bundle.
+   *
+   * @param backupKey The key to use to encrypt the backup contents.
+   * @param purpose Whether the backup is intended for device-to-device transfer or remote storage.
+   * @param backupInfo The serialized `BackupInfo` that the bundle starts with.
+   * @throws LibSignalError If `backupInfo` can't be parsed.
+   */
+  public constructor(
+    backupKey: MessageBackupKey,
+    purpose: Purpose,
+    backupInfo: Buffer
+  ) {
+    this._nativeHandle = Native.MessageBackupWriter_New(
+      backupKey,
+      backupInfo,
+      purpose
+    );
+  }
+
+  /**
+   * Validate and write a serialized `Frame`.
+   *
+   * A rejected frame leaves the writer usable.
+   *
+   * @returns The next piece of the encrypted bundle, which may be empty.
+   * @throws LibSignalError If the frame is rejected, or the writer has been finished.
+   */
+  writeFrame(frame: Buffer): Buffer {
+    return Native.MessageBackupWriter_WriteFrame(this, frame);
+  }
+
+  /**
+   * Check that the backup is complete and return the rest of the encrypted
+   * bundle.
+   *
+   * The writer can't be used afterwards, even if this fails.
+   *
+   * @returns The last piece of the encrypted bundle.
+   * @throws LibSignalError If the backup is incomplete, or the writer has already been finished.
+   */
+  finish(): Buffer {
+    return Native.MessageBackupWriter_Finish(this);
+  }
+}
+
 /**
  * Validate a backup file
  *
@@ -99,3 +156,29 @@ export async function validate(
     )
   );
 }
+
+/**
+ * Validate a backup file in a single pass over `stream`.
+ *
+ * Unlike {@link validate}, this doesn't need the length of the input up front
+ * or a second copy of the stream.
+ *
+ * @param backupKey The key to use to decrypt the backup contents.
+ * @param purpose Whether the backup is intended for device-to-device transfer or remote storage.
+ * @param stream An input stream that reads the backup contents.
+ * @returns The outcome of validation, including any errors and warnings.
+ * @throws IoError If an IO error on the input occurs.
+ */
+export async function validateStream(
+  backupKey: MessageBackupKey,
+  purpose: Purpose,
+  stream: InputStream
+): Promise<ValidationOutcome> {
+  return new ValidationOutcome(
+    await Native.MessageBackupValidator_ValidateStream(
+      backupKey,
+      stream,
+      purpose
+    )
+  );
+}
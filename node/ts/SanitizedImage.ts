@@ -0,0 +1,53 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import * as Native from '../Native';
+
+/**
+ * A GIF or WebP file with its metadata removed, along with its dimensions.
+ */
+export class SanitizedImage {
+  readonly _nativeHandle: Native.SanitizedImage;
+
+  private constructor(handle: Native.SanitizedImage) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(handle: Native.SanitizedImage): SanitizedImage {
+    return new SanitizedImage(handle);
+  }
+
+  /**
+   * Get the sanitized file.
+   * @returns The sanitized file, ready to be sent in place of the input.
+   */
+  getData(): Buffer {
+    return Native.SanitizedImage_GetData(this);
+  }
+
+  /**
+   * Get the width of the image.
+   * @returns The width of the image, in pixels.
+   */
+  getWidth(): number {
+    return Native.SanitizedImage_GetWidth(this);
+  }
+
+  /**
+   * Get the height of the image.
+   * @returns The height of the image, in pixels.
+   */
+  getHeight(): number {
+    return Native.SanitizedImage_GetHeight(this);
+  }
+
+  /**
+   * Get the number of frames in the image.
+   * @returns The number of frames in the image; 1 unless it is animated.
+   */
+  getFrameCount(): number {
+    return Native.SanitizedImage_GetFrameCount(this);
+  }
+}
@@ -18,6 +18,9 @@ import {
   InvalidMediaInputError,
   UnsupportedMediaInputError,
 } from './Errors';
+import { SanitizedImage } from './SanitizedImage';
+
+export { SanitizedImage } from './SanitizedImage';
 
 /**
  * Sanitize a WebP input.
@@ -30,3 +33,21 @@ import {
 export function sanitize(input: Buffer): void {
   Native.WebpSanitizer_Sanitize(input);
 }
+
+/**
+ * Sanitize a WebP input, removing its metadata.
+ *
+ * The input is checked as by {@link sanitize}, then copied without its EXIF and XMP metadata or any
+ * unknown chunks.
+ *
+ * @param input A WebP format input stream.
+ * @returns The sanitized WebP file and its dimensions.
+ * @throws {IoError} If an IO error on the input occurs.
+ * @throws {InvalidMediaInputError} If the input could not be parsed because it was invalid.
+ * @throws {UnsupportedMediaInputError} If the input could not be parsed because it's unsupported in some way.
+ */
+export function sanitizeAndStripMetadata(input: Buffer): SanitizedImage {
+  return SanitizedImage._fromNativeHandle(
+    Native.WebpSanitizer_SanitizeAndStripMetadata(input)
+  );
+}
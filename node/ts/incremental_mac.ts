@@ -6,6 +6,7 @@
 import * as Native from '../Native';
 import * as stream from 'stream';
 import { LibSignalErrorBase } from './Errors';
+import { InputStream } from './io';
 
 export type ChunkSizeChoice =
   | { kind: 'everyN'; n: number }
@@ -76,10 +77,56 @@ export class ValidatingWritable extends stream.Writable {
     );
   }
 
+  /**
+   * Resumes validation from a checkpoint saved with {@link checkpoint}, possibly by another
+   * process.
+   *
+   * `savedInput` must produce the input from the start up to the checkpoint. It is read and
+   * validated again, but doesn't have to be fetched again. The rest of the input can then be
+   * written starting from {@link validatedSize}.
+   *
+   * Rejects if `savedInput` can't be read, doesn't match, or is too short, or if the checkpoint was
+   * made for a different chunk size.
+   */
+  static async resume(
+    key: Buffer,
+    sizeChoice: ChunkSizeChoice,
+    digest: Buffer,
+    checkpoint: Buffer,
+    savedInput: InputStream
+  ): Promise<ValidatingWritable> {
+    const nativeHandle = await Native.ValidatingMac_Resume(
+      key,
+      chunkSizeInBytes(sizeChoice),
+      digest,
+      checkpoint,
+      savedInput
+    );
+    const writable = new ValidatingWritable(key, sizeChoice, digest);
+    writable._nativeHandle = nativeHandle;
+    writable._validatedBytes = Number(
+      Native.ValidatingMac_ValidatedOffset(writable)
+    );
+    return writable;
+  }
+
+  /**
+   * The number of bytes from the start of the input that have been validated.
+   *
+   * If a write fails, the chunk that didn't match is dropped, and this is where it starts. It can
+   * then be fetched again and written from here.
+   */
   validatedSize(): number {
     return this._validatedBytes;
   }
 
+  /**
+   * Saves how far validation has got, to resume from later with {@link ValidatingWritable.resume}.
+   */
+  checkpoint(): Buffer {
+    return Native.ValidatingMac_Checkpoint(this);
+  }
+
   _write(
     // eslint-disable-next-line @typescript-eslint/no-explicit-any, @typescript-eslint/explicit-module-boundary-types
     chunk: any,
@@ -19,6 +19,7 @@ export * as Net from './net';
 
 export * as Mp4Sanitizer from './Mp4Sanitizer';
 export * as WebpSanitizer from './WebpSanitizer';
+export * as GifSanitizer from './GifSanitizer';
 
 import * as Native from '../Native';
 
@@ -1572,15 +1573,99 @@ export function signalDecryptPreKey(
   );
 }
 
+function changedAddresses(
+  addresses: ProtocolAddress[],
+  changed: Buffer
+): ProtocolAddress[] {
+  return addresses.filter((_address, i) => changed[i] !== 0);
+}
+
+/**
+ * Archives the current session state for each of `addresses`.
+ *
+ * Addresses with no session, or no current session state, are skipped. Returns
+ * the addresses whose sessions were archived.
+ */
+export async function archiveSessions(
+  addresses: ProtocolAddress[],
+  sessionStore: SessionStore
+): Promise<ProtocolAddress[]> {
+  const changed = await Native.SessionManagement_ArchiveSessions(
+    addresses,
+    sessionStore
+  );
+  return changedAddresses(addresses, changed);
+}
+
+/**
+ * Archives the current session state for each of `addresses` that has not been
+ * used to encrypt or decrypt a message within `maxAgeSeconds` of `now`.
+ *
+ * Sessions that have never recorded a use are left alone. Returns the
+ * addresses whose sessions were archived.
+ */
+export async function expireSessions(
+  addresses: ProtocolAddress[],
+  maxAgeSeconds: number,
+  sessionStore: SessionStore,
+  now: Date = new Date()
+): Promise<ProtocolAddress[]> {
+  // Anything longer than this is more than 136 years, which is as good as
+  // never expiring.
+  const clampedMaxAgeSeconds = Math.min(maxAgeSeconds, 0xffffffff);
+  const changed = await Native.SessionManagement_ExpireSessions(
+    addresses,
+    clampedMaxAgeSeconds,
+    sessionStore,
+    now.getTime()
+  );
+  return changedAddresses(addresses, changed);
+}
+
+/**
+ * Discards all but the `maxPreviousSessions` most recent archived session
+ * states for each of `addresses`.
+ *
+ * The current session state is never affected. Returns the addresses whose
+ * sessions were pruned.
+ */
+export async function prunePreviousSessions(
+  addresses: ProtocolAddress[],
+  maxPreviousSessions: number,
+  sessionStore: SessionStore
+): Promise<ProtocolAddress[]> {
+  const changed = await Native.SessionManagement_PrunePreviousSessions(
+    addresses,
+    maxPreviousSessions,
+    sessionStore
+  );
+  return changedAddresses(addresses, changed);
+}
+
+export type SealedSenderEncryptOptions = {
+  /**
+   * Pads the plaintext to a multiple of this many bytes before encrypting
+   * it.
+   *
+   * The recipient must pass the same `paddingBucketSize` when decrypting.
+   */
+  paddingBucketSize?: number;
+};
+
 export async function sealedSenderEncryptMessage(
   message: Buffer,
   address: ProtocolAddress,
   senderCert: SenderCertificate,
   sessionStore: SessionStore,
-  identityStore: IdentityKeyStore
+  identityStore: IdentityKeyStore,
+  options: SealedSenderEncryptOptions = {}
 ): Promise<Buffer> {
+  const plaintext =
+    options.paddingBucketSize === undefined
+      ? message
+      : Native.SealedSender_PadPlaintext(message, options.paddingBucketSize);
   const ciphertext = await signalEncrypt(
-    message,
+    plaintext,
     address,
     sessionStore,
     identityStore
@@ -1689,6 +1774,71 @@ export async function sealedSenderDecryptMessage(
   return SealedSenderDecryptionResult._fromNativeHandle(ssdr);
 }
 
+export type SealedSenderDecryptOptions = {
+  /**
+   * The largest inner plaintext to accept, in bytes.
+   *
+   * This is checked before the inner message is decrypted, against the
+   * length of its ciphertext, so the limit is effectively rounded up to one
+   * less than a multiple of 16.
+   */
+  maxPlaintextSize?: number;
+  /**
+   * The local time the message was received, in milliseconds since the
+   * epoch, used to compute `timestampSkewMillis`.
+   */
+  localTime?: number;
+  /**
+   * Requires the plaintext to be padded to a multiple of this many bytes, as
+   * done by `sealedSenderEncryptMessage` with the same option, and strips the
+   * padding.
+   *
+   * `maxPlaintextSize` is checked against the padded plaintext. A message
+   * with the wrong padding has already advanced the session by the time it
+   * is rejected.
+   */
+  paddingBucketSize?: number;
+};
+
+/**
+ * Like {@link sealedSenderDecryptMessage}, but reports more about the
+ * message, and can reject oversized or incorrectly padded plaintexts.
+ *
+ * If decryption fails after the sender certificate has been read, the thrown
+ * error has a `sealedSenderMetadata` property saying who the message was from.
+ */
+export async function sealedSenderDecryptMessageWithOptions(
+  message: Buffer,
+  trustRoot: PublicKey,
+  timestamp: number,
+  localE164: string | null,
+  localUuid: string,
+  localDeviceId: number,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore,
+  signedPrekeyStore: SignedPreKeyStore,
+  kyberPrekeyStore: KyberPreKeyStore,
+  options: SealedSenderDecryptOptions = {}
+): Promise<Native.SealedSenderDecryptedMessage> {
+  return Native.SealedSender_DecryptMessageWithOptions(
+    message,
+    trustRoot,
+    timestamp,
+    localE164,
+    localUuid,
+    localDeviceId,
+    sessionStore,
+    identityStore,
+    prekeyStore,
+    signedPrekeyStore,
+    kyberPrekeyStore,
+    options.maxPlaintextSize ?? null,
+    options.localTime ?? null,
+    options.paddingBucketSize ?? null
+  );
+}
+
 export async function sealedSenderDecryptToUsmc(
   message: Buffer,
   identityStore: IdentityKeyStore
@@ -38,6 +38,18 @@ export type CDSRequestOptionsType = {
   abortSignal?: AbortSignal;
 };
 
+/**
+ * Timeouts to use in place of the defaults; see {@link Net}.
+ *
+ * `connectTimeoutMillis` bounds each attempt to connect to a server over a single route, for chat,
+ * CDSI, and SVR3 alike. `requestTimeoutMillis` is how long to wait for each response from CDSI or
+ * SVR3 once connected.
+ */
+export type NetTimeouts = {
+  connectTimeoutMillis: number;
+  requestTimeoutMillis: number;
+};
+
 export type CDSResponseEntryType<Aci, Pni> = {
   aci: Aci | undefined;
   pni: Pni | undefined;
@@ -61,6 +73,57 @@ export type ChatRequest = Readonly<{
   timeoutMillis?: number;
 }>;
 
+/**
+ * Something that happened while establishing a connection.
+ *
+ * See {@link Net#takeConnectionEvents}.
+ */
+export type ConnectionEvent = Readonly<
+  | {
+      type: 'dnsLookup';
+      route: string;
+      durationMillis: number;
+      /** `null` if the lookup failed. */
+      source: string | null;
+    }
+  | {
+      type: 'tcpConnect';
+      route: string;
+      ipType: 'unknown' | 'v4' | 'v6';
+      durationMillis: number;
+      succeeded: boolean;
+    }
+  | {
+      type: 'tlsHandshake';
+      route: string;
+      host: string;
+      durationMillis: number;
+      succeeded: boolean;
+    }
+  | {
+      type: 'webSocketUpgrade';
+      route: string;
+      host: string;
+      durationMillis: number;
+      /** The HTTP status of the server's response, if there was one. */
+      status: number | null;
+    }
+  | {
+      type: 'routeAttempt';
+      route: string;
+      host: string;
+      durationMillis: number;
+      outcome: 'succeeded' | 'failed' | 'timedOut';
+      consecutiveFailures: number;
+    }
+  | {
+      type: 'routeCoolingDown';
+      route: string;
+      host: string;
+      remainingMillis: number;
+    }
+>;
+
 type ConnectionManager = Wrapper<Native.ConnectionManager>;
 
 function newNativeHandle<T>(handle: T): Wrapper<T> {
@@ -69,6 +132,73 @@ function newNativeHandle<T>(handle: T): Wrapper<T> {
   };
 }
 
+/** In the order of libsignal-net's IpType. */
+const IP_TYPES = ['unknown', 'v4', 'v6'] as const;
+
+function connectionEventFromNative(
+  native: Native.ConnectionEvent
+): ConnectionEvent {
+  const event = newNativeHandle(native);
+  const route = Native.ConnectionEvent_GetRoute(event);
+  const durationMillis = Native.ConnectionEvent_GetDurationMillis(event);
+  // The order matches ConnectionEvent_GetKind.
+  switch (Native.ConnectionEvent_GetKind(event)) {
+    case 0:
+      return {
+        type: 'dnsLookup',
+        route,
+        durationMillis,
+        source: Native.ConnectionEvent_GetDnsSource(event),
+      };
+    case 1:
+      return {
+        type: 'tcpConnect',
+        route,
+        ipType: IP_TYPES[Native.ConnectionEvent_GetIpType(event) ?? 0],
+        durationMillis,
+        succeeded: Native.ConnectionEvent_GetSucceeded(event),
+      };
+    case 2:
+      return {
+        type: 'tlsHandshake',
+        route,
+        host: Native.ConnectionEvent_GetHost(event) ?? '',
+        durationMillis,
+        succeeded: Native.ConnectionEvent_GetSucceeded(event),
+      };
+    case 3:
+      return {
+        type: 'webSocketUpgrade',
+        route,
+        host: Native.ConnectionEvent_GetHost(event) ?? '',
+        durationMillis,
+        status: Native.ConnectionEvent_GetHttpStatus(event),
+      };
+    case 4:
+      return {
+        type: 'routeAttempt',
+        route,
+        host: Native.ConnectionEvent_GetHost(event) ?? '',
+        durationMillis,
+        outcome: Native.ConnectionEvent_GetRouteAttemptOutcome(event) as
+          | 'succeeded'
+          | 'failed'
+          | 'timedOut',
+        consecutiveFailures:
+          Native.ConnectionEvent_GetConsecutiveFailures(event) ?? 0,
+      };
+    case 5:
+      return {
+        type: 'routeCoolingDown',
+        route,
+        host: Native.ConnectionEvent_GetHost(event) ?? '',
+        remainingMillis: durationMillis,
+      };
+    default:
+      throw new Error('unknown connection event kind');
+  }
+}
+
 /** Low-level async runtime control, mostly just exported for testing. */
 export class TokioAsyncContext {
   readonly _nativeHandle: Native.TokioAsyncContext;
@@ -101,6 +231,69 @@ export class TokioAsyncContext {
   }
 }
 
+/**
+ * Acknowledges a message delivered to a {@link ChatServiceListener}.
+ *
+ * Each message must be acknowledged exactly once, with either {@link #send} or {@link #sendNack}.
+ */
+export class ChatServerMessageAck {
+  readonly _nativeHandle: Native.ServerMessageAck;
+
+  constructor(
+    private readonly asyncContext: TokioAsyncContext,
+    handle: Native.ServerMessageAck
+  ) {
+    this._nativeHandle = handle;
+  }
+
+  /**
+   * Tells the server the message was processed, so it can be removed from the queue.
+   */
+  send(): Promise<void> {
+    return Native.ServerMessageAck_Send(this.asyncContext, this);
+  }
+
+  /**
+   * Tells the server the message could not be processed right now.
+   *
+   * The message stays queued and will be delivered again later.
+   */
+  sendNack(): Promise<void> {
+    return Native.ServerMessageAck_SendNack(this.asyncContext, this);
+  }
+}
+
+/**
+ * Receives messages pushed by the Chat Service over an authenticated connection.
+ *
+ * See {@link ChatService#setListener}.
+ */
+export interface ChatServiceListener {
+  /**
+   * Called for each message delivered by the server, in the order the server sent them.
+   *
+   * `timestamp` is the time the server delivered the message, in milliseconds since the epoch.
+   */
+  onIncomingMessage(
+    envelope: Buffer,
+    timestamp: number,
+    ack: ChatServerMessageAck
+  ): void;
+
+  /**
+   * Called once all messages queued while the client was offline have been delivered.
+   */
+  onQueueEmpty(): void;
+
+  /**
+   * Called when the authenticated connection is lost for any reason other than a call to
+   * {@link ChatService#disconnect}.
+   *
+   * Messages will stop arriving until the service is connected again.
+   */
+  onConnectionInterrupted(cause: LibSignalError): void;
+}
+
 /**
  * Provides API methods to connect and communicate with the Chat Service.
  * Before using either authenticated or unauthenticated channels,
@@ -170,6 +363,26 @@ export class ChatService {
     );
   }
 
+  /**
+   * Warms up the connections to the Chat Service ahead of the first request, to hide the latency of
+   * establishing them.
+   *
+   * The authenticated connection is fully established, as with {@link #connectAuthenticated()}.
+   * For the unauthenticated connection, only the DNS lookup and the TCP and TLS handshakes are done
+   * ahead of time; the result is kept for a short while and used by the next call to
+   * {@link #connectUnauthenticated()}.
+   *
+   * @throws {AppExpiredError} if the current app version is too old (as judged by the server).
+   * @throws {DeviceDelinkedError} if the current device has been delinked.
+   * @throws {LibSignalError} with other codes for other failures.
+   */
+  preconnect(options?: { abortSignal?: AbortSignal }): Promise<void> {
+    return this.asyncContext.makeCancellable(
+      options?.abortSignal,
+      Native.ChatService_preconnect(this.asyncContext, this.chatService)
+    );
+  }
+
   /**
    * Sends request to the Chat Service over an unauthenticated channel.
    *
@@ -260,6 +473,44 @@ export class ChatService {
     );
   }
 
+  /**
+   * Sets the listener for messages pushed by the server, replacing any previous listener.
+   *
+   * Passing `null` stops delivering messages. Messages that arrive while there is no listener are
+   * left unacknowledged, so the server will deliver them again.
+   */
+  setListener(listener: ChatServiceListener | null): void {
+    if (listener === null) {
+      Native.ChatServer_SetListener(this.asyncContext, this.chatService, null);
+      return;
+    }
+    const asyncContext = this.asyncContext;
+    const nativeListener: Native.ChatListener = {
+      _incoming_message(
+        envelope: Buffer,
+        timestamp: number,
+        ack: Native.ServerMessageAck
+      ): void {
+        listener.onIncomingMessage(
+          envelope,
+          timestamp,
+          new ChatServerMessageAck(asyncContext, ack)
+        );
+      },
+      _queue_empty(): void {
+        listener.onQueueEmpty();
+      },
+      _connection_interrupted(cause: Error): void {
+        listener.onConnectionInterrupted(cause as LibSignalError);
+      },
+    };
+    Native.ChatServer_SetListener(
+      this.asyncContext,
+      this.chatService,
+      nativeListener
+    );
+  }
+
   static buildHttpRequest(chatRequest: ChatRequest): {
     _nativeHandle: Native.HttpRequest;
   } {
@@ -286,10 +537,17 @@ export class Net {
    */
   svr3: Svr3Client;
 
-  constructor(env: Environment, userAgent: string) {
+  constructor(env: Environment, userAgent: string, timeouts?: NetTimeouts) {
     this.asyncContext = new TokioAsyncContext(Native.TokioAsyncContext_new());
     this.connectionManager = newNativeHandle(
-      Native.ConnectionManager_new(env, userAgent)
+      timeouts === undefined
+        ? Native.ConnectionManager_new(env, userAgent)
+        : Native.ConnectionManager_new_with_timeouts(
+            env,
+            userAgent,
+            timeouts.connectTimeoutMillis,
+            timeouts.requestTimeoutMillis
+          )
     );
     this.svr3 = new Svr3ClientImpl(this.asyncContext, this.connectionManager);
   }
@@ -326,16 +584,133 @@ export class Net {
     Native.ConnectionManager_set_proxy(this.connectionManager, host, port);
   }
 
+  /**
+   * Sets a SOCKS5 proxy to be used for all new connections (until overridden).
+   *
+   * Hostnames of Signal services are passed to the proxy unresolved, so DNS
+   * lookups for them happen on the proxy's side.
+   *
+   * Throws if the host or port is structurally invalid, such as a port that doesn't fit in u16.
+   */
+  setSocksProxy(
+    host: string,
+    port: number,
+    credentials?: Readonly<{ username: string; password: string }>
+  ): void {
+    Native.ConnectionManager_set_socks_proxy(
+      this.connectionManager,
+      host,
+      port,
+      credentials?.username ?? null,
+      credentials?.password ?? null
+    );
+  }
+
+  /**
+   * Sets an HTTP CONNECT proxy to be used for all new connections (until overridden).
+   *
+   * Hostnames of Signal services are passed to the proxy unresolved, so DNS
+   * lookups for them happen on the proxy's side.
+   *
+   * Throws if the host or port is structurally invalid, such as a port that doesn't fit in u16.
+   */
+  setHttpProxy(
+    host: string,
+    port: number,
+    credentials?: Readonly<{ username: string; password: string }>
+  ): void {
+    Native.ConnectionManager_set_http_proxy(
+      this.connectionManager,
+      host,
+      port,
+      credentials?.username ?? null,
+      credentials?.password ?? null
+    );
+  }
+
   /**
    * Ensures that future connections will be made directly, not through a proxy.
    *
-   * Clears any proxy configuration set via {@link #setProxy}. If none was set, calling this
+   * Clears any proxy configuration set via {@link #setProxy}, {@link #setSocksProxy},
+   * or {@link #setHttpProxy}. If none was set, calling this
    * method is a no-op.
    */
   clearProxy(): void {
     Native.ConnectionManager_clear_proxy(this.connectionManager);
   }
 
+  /**
+   * Changes how long to wait before retrying a server that couldn't be reached.
+   *
+   * After each consecutive failure the delay grows by `multiplierPercent`
+   * percent of itself (200, the default, doubles it), starting at
+   * `initialDelayMillis` and capped at `maxDelayMillis`. Up to `jitterPercent`
+   * percent (default 20) of each delay is randomly taken off it, so that many
+   * clients don't all retry at once. After `maxAttempts` consecutive failures,
+   * the server is not retried for a long cooldown, after which the attempts
+   * start over; if omitted, attempts continue indefinitely.
+   */
+  setBackoffPolicy({
+    initialDelayMillis,
+    maxDelayMillis,
+    multiplierPercent,
+    jitterPercent,
+    maxAttempts,
+  }: Readonly<{
+    initialDelayMillis: number;
+    maxDelayMillis: number;
+    multiplierPercent?: number;
+    jitterPercent?: number;
+    maxAttempts?: number;
+  }>): void {
+    Native.ConnectionManager_set_backoff_policy(
+      this.connectionManager,
+      initialDelayMillis,
+      maxDelayMillis,
+      multiplierPercent ?? 200,
+      jitterPercent ?? 20,
+      maxAttempts ?? 0
+    );
+  }
+
+  /**
+   * Restores the default delays between attempts to reach a server.
+   */
+  resetBackoffPolicy(): void {
+    Native.ConnectionManager_reset_backoff_policy(this.connectionManager);
+  }
+
+  /**
+   * Returns the connection events recorded since the last call, and forgets them.
+   *
+   * Only the most recent events are kept, so this should be called regularly
+   * if events are being collected.
+   */
+  takeConnectionEvents(): ConnectionEvent[] {
+    const events: ConnectionEvent[] = [];
+    for (;;) {
+      const event = Native.ConnectionManager_take_connection_event(
+        this.connectionManager
+      );
+      if (event === null) {
+        return events;
+      }
+      events.push(connectionEventFromNative(event));
+    }
+  }
+
+  /**
+   * Notifies libsignal that the device has switched networks, e.g. from Wi-Fi
+   * to cellular.
+   *
+   * Cached DNS results and delays before retrying unreachable servers are
+   * discarded, connection attempts in progress are abandoned, and open
+   * connections are re-established on the new network.
+   */
+  networkChanged(): void {
+    Native.ConnectionManager_network_changed(this.connectionManager);
+  }
+
   async cdsiLookup(
     { username, password }: Readonly<ServiceAuth>,
     {
@@ -512,6 +887,74 @@ export interface Svr3Client {
     auth: Readonly<ServiceAuth>,
     options?: { abortSignal?: AbortSignal }
   ): Promise<void>;
+
+  /**
+   * Move a secret backed up to the SVR3 enclaves of `previous` to the ones of
+   * this client.
+   *
+   * The secret is restored from the previous enclaves, using up one of its
+   * tries there, then backed up again here with `maxTries` tries. The share set
+   * from the previous backup is no longer needed once the returned `Promise`
+   * resolves; the new one should be stored instead.
+   *
+   * If the backup to the new enclaves fails, `state` keeps hold of the restored
+   * secret. Calling this method again with the same `state` then skips the
+   * previous enclaves entirely, so a failed migration can be retried without
+   * using up more tries there. Once the migration succeeds, the secret is
+   * cleared from `state`.
+   *
+   * Error messages are log-safe and do not contain any sensitive data.
+   *
+   * @param previous - The client for the enclaves the secret is currently
+   * backed up to. Must have been obtained from a {@link Net} instance.
+   * @param state - The progress of this migration, to be reused when retrying
+   * it.
+   * @param password - User-provided password that the secret was backed up
+   * with. It is used for the new backup as well.
+   * @param shareSet - a serialized masked share set returned by a call to
+   * {@link Svr3Client#backup} on `previous`.
+   * @param maxTries - Number of times the migrated secret will be allowed to
+   * be guessed. Must be positive.
+   * @param auth - An instance of {@link ServiceAuth} containing the username
+   * and password obtained from the Chat Server, used for both sets of enclaves.
+   * @returns A `Promise` which--when awaited--will return a byte array with the
+   * serialized masked share set of the new backup.
+   *
+   * The returned `Promise` can fail for the same reasons as the ones returned
+   * by {@link Svr3Client#backup} and {@link Svr3Client#restore}.
+   */
+  migrate(
+    previous: Svr3Client,
+    state: Svr3MigrationState,
+    password: string,
+    shareSet: Buffer,
+    maxTries: number,
+    auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<Buffer>;
+}
+
+/**
+ * The progress of a {@link Svr3Client#migrate} that can be retried.
+ *
+ * Once the secret has been restored from the previous enclaves, this holds on
+ * to it until the migration succeeds, so it should not be kept around any
+ * longer than necessary.
+ */
+export class Svr3MigrationState {
+  readonly _nativeHandle: Native.Svr3MigrationState;
+
+  constructor() {
+    this._nativeHandle = Native.Svr3MigrationState_new();
+  }
+
+  /**
+   * Whether the secret has been restored, so the previous enclaves are no
+   * longer needed.
+   */
+  hasRestoredSecret(): boolean {
+    return Native.Svr3MigrationState_hasRestoredSecret(this);
+  }
 }
 
 /**
@@ -588,4 +1031,34 @@ class Svr3ClientImpl implements Svr3Client {
       )
     );
   }
+
+  async migrate(
+    previous: Svr3Client,
+    state: Svr3MigrationState,
+    password: string,
+    shareSet: Buffer,
+    maxTries: number,
+    auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<Buffer> {
+    if (!(previous instanceof Svr3ClientImpl)) {
+      throw new TypeError(
+        'previous must be the svr3 client of a Net instance'
+      );
+    }
+    return this.asyncContext.makeCancellable(
+      options?.abortSignal,
+      Native.Svr3Migrate(
+        this.asyncContext,
+        this.connectionManager,
+        previous.connectionManager,
+        state,
+        password,
+        shareSet,
+        maxTries,
+        auth.username,
+        auth.password
+      )
+    );
+  }
 }
@@ -13,6 +13,8 @@ import {
   chunkSizeInBytes,
 } from '../incremental_mac';
 import { LibSignalErrorBase } from '../Errors';
+import * as Native from '../../Native';
+import { Uint8ArrayInputStream } from './ioutil';
 
 import * as stream from 'stream';
 
@@ -126,6 +128,64 @@ describe('Incremental MAC', () => {
       validating.end();
       assert.equal(50, validating.validatedSize());
     });
+
+    it('drops a corrupted chunk so it can be fetched again', () => {
+      const input = Buffer.from(TEST_INPUT.join(''));
+      const badInput = Buffer.from(input);
+      badInput[10] ^= 0xff;
+      const mac = {
+        _nativeHandle: Native.ValidatingMac_Initialize(
+          TEST_KEY,
+          chunkSizeInBytes(CHUNK_SIZE),
+          TEST_DIGEST
+        ),
+      };
+      assert.equal(-1, Native.ValidatingMac_Update(mac, badInput, 0, 40));
+      assert.equal(BigInt(0), Native.ValidatingMac_ValidatedOffset(mac));
+      assert.equal(
+        32,
+        Native.ValidatingMac_Update(mac, input, 0, input.length)
+      );
+      assert.equal(18, Native.ValidatingMac_Finalize(mac));
+    });
+
+    it('can resume from a checkpoint', async () => {
+      const input = Buffer.from(TEST_INPUT.join(''));
+      const validating = new ValidatingWritable(
+        TEST_KEY,
+        CHUNK_SIZE,
+        TEST_DIGEST
+      );
+      validating.write(input.subarray(0, 40));
+      assert.equal(32, validating.validatedSize());
+      const checkpoint = validating.checkpoint();
+
+      const resumed = await ValidatingWritable.resume(
+        TEST_KEY,
+        CHUNK_SIZE,
+        TEST_DIGEST,
+        checkpoint,
+        new Uint8ArrayInputStream(input.subarray(0, 32))
+      );
+      assert.equal(32, resumed.validatedSize());
+      await stream.promises.pipeline(
+        stream.Readable.from([input.subarray(32)]),
+        resumed
+      );
+      assert.equal(50, resumed.validatedSize());
+
+      const badSavedInput = Buffer.from(input.subarray(0, 32));
+      badSavedInput[1] ^= 0xff;
+      await assert.isRejected(
+        ValidatingWritable.resume(
+          TEST_KEY,
+          CHUNK_SIZE,
+          TEST_DIGEST,
+          checkpoint,
+          new Uint8ArrayInputStream(badSavedInput)
+        )
+      );
+    });
   });
 });
 
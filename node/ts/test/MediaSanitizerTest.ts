@@ -6,6 +6,7 @@
 import { assert } from 'chai';
 import * as Mp4Sanitizer from '../Mp4Sanitizer';
 import * as WebpSanitizer from '../WebpSanitizer';
+import * as GifSanitizer from '../GifSanitizer';
 import { SanitizedMetadata } from '../Mp4Sanitizer';
 import * as util from './util';
 import { ErrorCode, LibSignalErrorBase } from '../Errors';
@@ -114,8 +115,69 @@ describe('WebpSanitizer', () => {
       WebpSanitizer.sanitize(Buffer.from(input));
     });
   });
+
+  describe('sanitizeAndStripMetadata', () => {
+    it('leaves a minimal webp unchanged', () => {
+      const input = Buffer.from(webp());
+      const sanitized = WebpSanitizer.sanitizeAndStripMetadata(input);
+      assert.deepEqual(sanitized.getData(), input);
+      assert.equal(sanitized.getWidth(), 1);
+      assert.equal(sanitized.getHeight(), 1);
+      assert.equal(sanitized.getFrameCount(), 1);
+    });
+
+    it('removes EXIF metadata', () => {
+      const sanitized = WebpSanitizer.sanitizeAndStripMetadata(
+        Buffer.from(extendedWebp(true))
+      );
+      assert.deepEqual(sanitized.getData(), Buffer.from(extendedWebp(false)));
+    });
+  });
 });
 
+describe('GifSanitizer', () => {
+  describe('sanitize', () => {
+    it('throws on empty input', () => {
+      try {
+        GifSanitizer.sanitize(Buffer.of());
+        assert.fail('did not throw');
+      } catch (e) {
+        assert(e instanceof LibSignalErrorBase);
+        assert.equal(e.code, ErrorCode.InvalidMediaInput);
+      }
+    });
+
+    it('accepts a minimal gif', () => {
+      const input = Buffer.from(gif([]));
+      const sanitized = GifSanitizer.sanitize(input);
+      assert.deepEqual(sanitized.getData(), input);
+      assert.equal(sanitized.getWidth(), 1);
+      assert.equal(sanitized.getHeight(), 1);
+      assert.equal(sanitized.getFrameCount(), 1);
+    });
+
+    it('removes comments', () => {
+      const comment = [0x21, 0xfe, 2, 0x68, 0x69, 0]; // "hi"
+      const sanitized = GifSanitizer.sanitize(Buffer.from(gif(comment)));
+      assert.deepEqual(sanitized.getData(), Buffer.from(gif([])));
+    });
+  });
+});
+
+/** A 1x1 GIF with the given extension blocks before its image. */
+function gif(extensions: Array<number>): Array<number> {
+  const array: number[] = [];
+  return array.concat(
+    Array.from(Buffer.from('GIF89a')), // header
+    [1, 0, 1, 0, 0x80, 0, 0], // logical screen descriptor
+    [0, 0, 0, 0xff, 0xff, 0xff], // global color table
+    extensions,
+    [0x2c, 0, 0, 0, 0, 1, 0, 1, 0, 0], // image descriptor
+    [2, 2, 0x44, 0x01, 0], // image data
+    [0x3b] // trailer
+  );
+}
+
 function ftyp(): Array<number> {
   const array: number[] = [];
   return array.concat(
@@ -180,6 +242,27 @@ function webp(): Array<number> {
   );
 }
 
+/** A 1x1 WebP with an extended header, optionally followed by EXIF metadata. */
+function extendedWebp(withExif: boolean): Array<number> {
+  const array: number[] = [];
+  return array.concat(
+    fourcc('RIFF'), // chunk type
+    [withExif ? 50 : 38, 0, 0, 0], // chunk size
+    fourcc('WEBP'), // webp header
+
+    fourcc('VP8X'), // chunk type
+    [10, 0, 0, 0], // chunk size
+    [withExif ? 0x08 : 0, 0, 0, 0], // flags
+    [0, 0, 0, 0, 0, 0], // canvas size
+
+    fourcc('VP8L'), // chunk type
+    [8, 0, 0, 0], // chunk size
+    [0x2f, 0, 0, 0, 0, 0x88, 0x88, 8], // VP8L data
+
+    withExif ? fourcc('EXIF').concat([4, 0, 0, 0], [1, 2, 3, 4]) : []
+  );
+}
+
 function fourcc(fourccStr: string): Array<number> {
   return [
     fourccStr.charCodeAt(0),
@@ -59,4 +59,70 @@ describe('MessageBackup', () => {
       }
     });
   });
+
+  describe('validateStream', () => {
+    it('successfully validates a minimal backup', async () => {
+      const input = fs.readFileSync(
+        path.join(__dirname, '../../ts/test/new_account.binproto.encrypted')
+      );
+
+      const outcome = await MessageBackup.validateStream(
+        testKey,
+        purpose,
+        new Uint8ArrayInputStream(input)
+      );
+      assert.equal(outcome.errorMessage, null);
+    });
+
+    it('produces an error message on empty input', async () => {
+      const outcome = await MessageBackup.validateStream(
+        testKey,
+        purpose,
+        new Uint8ArrayInputStream(new Uint8Array())
+      );
+      assert.equal(outcome.errorMessage, 'not enough bytes for an HMAC');
+    });
+  });
+
+  describe('MessageBackupWriter', () => {
+    // A Frame containing AccountData with only a profile key and the required
+    // account settings.
+    const accountDataFrame = Buffer.concat([
+      Buffer.from([0x0a, 0x27, 0x0a, 0x20]),
+      Buffer.alloc(32, 0xaa),
+      Buffer.from([0x4a, 0x03, 0x88, 0x01, 0x01]),
+    ]);
+
+    it('writes a valid backup', async () => {
+      const writer = new MessageBackup.MessageBackupWriter(
+        testKey,
+        purpose,
+        Buffer.of()
+      );
+      // A frame with no item is rejected, but doesn't stop the writer.
+      assert.throws(() => writer.writeFrame(Buffer.of()));
+      const output = Buffer.concat([
+        writer.writeFrame(accountDataFrame),
+        writer.finish(),
+      ]);
+
+      const outcome = await MessageBackup.validateStream(
+        testKey,
+        purpose,
+        new Uint8ArrayInputStream(output)
+      );
+      assert.equal(outcome.errorMessage, null);
+
+      assert.throws(() => writer.finish());
+    });
+
+    it('rejects an incomplete backup', () => {
+      const writer = new MessageBackup.MessageBackupWriter(
+        testKey,
+        purpose,
+        Buffer.of()
+      );
+      assert.throws(() => writer.finish(), /AccountData/);
+    });
+  });
 });
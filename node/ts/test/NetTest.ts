@@ -8,8 +8,14 @@ import * as chaiAsPromised from 'chai-as-promised';
 import * as util from './util';
 import { Aci, Pni } from '../Address';
 import * as Native from '../../Native';
-import { ErrorCode, LibSignalErrorBase } from '../Errors';
-import { ChatService, Environment, Net, ServiceAuth } from '../net';
+import { ErrorCode, LibSignalError, LibSignalErrorBase } from '../Errors';
+import {
+  ChatService,
+  Environment,
+  Net,
+  ServiceAuth,
+  Svr3MigrationState,
+} from '../net';
 import { randomBytes } from 'crypto';
 import { ChatResponse } from '../../Native';
 
@@ -26,6 +32,7 @@ describe('chat service api', () => {
       ['AppExpired', ErrorCode.AppExpired],
       ['DeviceDeregistered', ErrorCode.DeviceDelinked],
       ['ServiceInactive', ErrorCode.ChatServiceInactive],
+      ['RetryAfter42Seconds', ErrorCode.RateLimitedError],
 
       ['WebSocket', ErrorCode.IoError],
       ['UnexpectedFrameReceived', ErrorCode.IoError],
@@ -137,6 +144,115 @@ describe('chat service api', () => {
     );
   });
 
+  it('delivers messages and interruptions to the listener', async () => {
+    const net = new Net(Environment.Staging, userAgent);
+    const chat = net.newChatService();
+    const events: Array<string> = [];
+    let resolveInterrupted: (cause: LibSignalError) => void;
+    const interrupted = new Promise<LibSignalError>((resolve) => {
+      resolveInterrupted = resolve;
+    });
+    chat.setListener({
+      onIncomingMessage(envelope: Buffer, timestamp: number): void {
+        events.push(`message ${timestamp}: ${envelope.toString('hex')}`);
+      },
+      onQueueEmpty(): void {
+        events.push('queue empty');
+      },
+      onConnectionInterrupted(cause: LibSignalError): void {
+        resolveInterrupted(cause);
+      },
+    });
+
+    // The following payloads were generated via protoscope.
+    // % protoscope -s | base64
+    // The fields are described by chat_websocket.proto in the libsignal-net crate.
+    const nativeChat = chat['chatService'];
+
+    // 1: {"PUT"}
+    // 2: {"/api/v1/message"}
+    // 3: {1000i64}
+    // 5: {"x-signal-timestamp:1000"}
+    // 4: 1
+    Native.TESTING_ChatService_InjectRawServerRequest(
+      nativeChat,
+      Buffer.from(
+        'CgNQVVQSDy9hcGkvdjEvbWVzc2FnZRoI6AMAAAAAAAAqF3gtc2lnbmFsLXRpbWVzdGFtcDoxMDAwIAE=',
+        'base64'
+      )
+    );
+    // 1: {"PUT"}
+    // 2: {"/api/v1/queue/empty"}
+    // 4: 99
+    Native.TESTING_ChatService_InjectRawServerRequest(
+      nativeChat,
+      Buffer.from('CgNQVVQSEy9hcGkvdjEvcXVldWUvZW1wdHkgYw==', 'base64')
+    );
+    Native.TESTING_ChatService_InjectConnectionInterrupted(nativeChat);
+
+    const cause = await interrupted;
+    expect(cause).instanceOf(LibSignalErrorBase);
+    expect(cause.code).equals(ErrorCode.IoError);
+    expect(events).deep.equals([
+      'message 1000: e803000000000000',
+      'queue empty',
+    ]);
+  });
+
+  it('can exchange requests with an in-process fake server', async () => {
+    const net = new Net(Environment.Staging, userAgent);
+    const asyncContext = net['asyncContext'];
+    const server = { _nativeHandle: Native.TESTING_FakeChatServer_New() };
+    const chat = {
+      _nativeHandle: Native.TESTING_ChatService_NewWithFakeServer(
+        net['connectionManager'],
+        server,
+        '',
+        ''
+      ),
+    };
+    await Native.ChatService_connect_unauth(asyncContext, chat);
+
+    const responsePromise = Native.ChatService_unauth_send(
+      asyncContext,
+      chat,
+      ChatService.buildHttpRequest({
+        verb: 'GET',
+        path: '/v1/config',
+        headers: [['x-test', 'value']],
+      }),
+      5000
+    );
+
+    const received = {
+      _nativeHandle: await Native.TESTING_FakeChatServer_ReceiveRequest(
+        asyncContext,
+        server
+      ),
+    };
+    const request = {
+      _nativeHandle:
+        Native.TESTING_FakeChatServerRequest_GetHttpRequest(received),
+    };
+    expect(Native.TESTING_ChatRequestGetMethod(request)).equals('GET');
+    expect(Native.TESTING_ChatRequestGetPath(request)).equals('/v1/config');
+    expect(
+      Native.TESTING_ChatRequestGetHeaderValue(request, 'x-test')
+    ).equals('value');
+    Native.TESTING_FakeChatServerRequest_Respond(
+      asyncContext,
+      received,
+      200,
+      Buffer.from('config'),
+      0
+    );
+
+    const response = await responsePromise;
+    expect(response.status).equals(200);
+    expect(response.body).deep.equals(Buffer.from('config'));
+    await Native.ChatService_disconnect(asyncContext, chat);
+  });
+
   it('invalid proxies are rejected', () => {
     // The default TLS proxy config doesn't support staging, so we connect to production.
     const net = new Net(Environment.Production, userAgent);
@@ -412,6 +528,60 @@ describe('SVR3', () => {
       ).to.eventually.be.rejectedWith(LibSignalErrorBase);
     }).timeout(10000);
 
+    it('Migrate between enclave configurations', async () => {
+      const secret = randomBytes(32);
+      const tries = 10;
+      const previous = new Net(Environment.Staging, userAgent);
+      const previousShareSet = await previous.svr3.backup(
+        secret,
+        'password',
+        tries,
+        state!.auth
+      );
+      const migrationState = new Svr3MigrationState();
+      const shareSet = await state!.net.svr3.migrate(
+        previous.svr3,
+        migrationState,
+        'password',
+        previousShareSet,
+        tries,
+        state!.auth
+      );
+      expect(migrationState.hasRestoredSecret()).to.be.false;
+      const restoredSecret = await state!.net.svr3.restore(
+        'password',
+        shareSet,
+        state!.auth
+      );
+      expect(restoredSecret.value).to.eql(secret);
+      expect(restoredSecret.triesRemaining).to.eql(tries - 1);
+    }).timeout(20000);
+
+    it('Migrate with wrong password', async () => {
+      const secret = randomBytes(32);
+      const previous = new Net(Environment.Staging, userAgent);
+      const previousShareSet = await previous.svr3.backup(
+        secret,
+        'password',
+        10,
+        state!.auth
+      );
+      const migrationState = new Svr3MigrationState();
+      await expect(
+        state!.net.svr3.migrate(
+          previous.svr3,
+          migrationState,
+          'wrong password',
+          previousShareSet,
+          10,
+          state!.auth
+        )
+      )
+        .to.eventually.be.rejectedWith(LibSignalErrorBase)
+        .and.have.property('code', ErrorCode.SvrRestoreFailed);
+      expect(migrationState.hasRestoredSecret()).to.be.false;
+    }).timeout(20000);
+
     it('Exceed maxTries', async () => {
       const secret = randomBytes(32);
       const shareSet = await state!.net.svr3.backup(
@@ -1229,6 +1229,76 @@ describe('SignalClient', () => {
           )
         );
       });
+
+      it('manages sessions in bulk', async () => {
+        const aliceStores = new TestStores();
+        const now = new Date('2020-01-11');
+
+        const addresses = ['+14155550000', '+14155550001', '+14155550002'].map(
+          (name) => SignalClient.ProtocolAddress.new(name, 1)
+        );
+        // The first session was last used 10 days ago; the others 1 day ago.
+        for (const [i, address] of addresses.entries()) {
+          const bundle = await testCase.makeBundle(address, new TestStores());
+          await SignalClient.processPreKeyBundle(
+            bundle,
+            address,
+            aliceStores.session,
+            aliceStores.identity,
+            new Date(i == 0 ? '2020-01-01' : '2020-01-10')
+          );
+        }
+
+        const missing = SignalClient.ProtocolAddress.new('+14155559999', 1);
+        assert.deepEqual(
+          await SignalClient.archiveSessions(
+            [addresses[2], missing],
+            aliceStores.session
+          ),
+          [addresses[2]]
+        );
+
+        assert.deepEqual(
+          await SignalClient.expireSessions(
+            addresses,
+            Number.MAX_SAFE_INTEGER,
+            aliceStores.session,
+            now
+          ),
+          []
+        );
+        assert.deepEqual(
+          await SignalClient.expireSessions(
+            addresses,
+            7 * 24 * 60 * 60,
+            aliceStores.session,
+            now
+          ),
+          [addresses[0]]
+        );
+
+        for (const [i, address] of addresses.entries()) {
+          const session = await aliceStores.session.getSession(address);
+          assert.equal(session?.hasCurrentState(now), i == 1, address.name());
+        }
+
+        assert.deepEqual(
+          await SignalClient.prunePreviousSessions(
+            addresses,
+            0,
+            aliceStores.session
+          ),
+          [addresses[0], addresses[2]]
+        );
+        assert.deepEqual(
+          await SignalClient.prunePreviousSessions(
+            addresses,
+            0,
+            aliceStores.session
+          ),
+          []
+        );
+      });
     });
   }
 
@@ -1353,6 +1423,132 @@ describe('SignalClient', () => {
       assert.deepEqual(bPlaintext.senderAci()?.getServiceIdString(), aUuid);
       assert.deepEqual(bPlaintext.deviceId(), aDeviceId);
 
+      const aLongPlaintext = Buffer.alloc(40, 1);
+      const aLongCiphertext = await SignalClient.sealedSenderEncryptMessage(
+        aLongPlaintext,
+        bAddress,
+        senderCert,
+        aSess,
+        aKeys
+      );
+
+      try {
+        await SignalClient.sealedSenderDecryptMessageWithOptions(
+          aLongCiphertext,
+          trustRoot.getPublicKey(),
+          43, // timestamp,
+          bE164,
+          bUuid,
+          bDeviceId,
+          bSess,
+          bKeys,
+          bPreK,
+          bSPreK,
+          kyberStore,
+          { maxPlaintextSize: 20 }
+        );
+        assert.fail();
+      } catch (e) {
+        assert.instanceOf(e, SignalClient.LibSignalErrorBase);
+        const err = e as SignalClient.LibSignalError;
+        assert.equal(err.name, 'SealedSenderPlaintextTooLarge');
+        assert.equal(
+          err.code,
+          SignalClient.ErrorCode.SealedSenderPlaintextTooLarge
+        );
+        const tooLarge = err as SignalClient.SealedSenderPlaintextTooLargeError;
+        assert.equal(tooLarge.size, 32);
+        assert.equal(tooLarge.maxSize, 20);
+        assert.equal(tooLarge.sealedSenderMetadata.senderUuid, aUuid);
+        assert.equal(tooLarge.sealedSenderMetadata.senderDeviceId, aDeviceId);
+      }
+
+      // The rejected message didn't advance the session, so it still decrypts.
+      const bLongPlaintext =
+        await SignalClient.sealedSenderDecryptMessageWithOptions(
+          aLongCiphertext,
+          trustRoot.getPublicKey(),
+          43, // timestamp,
+          bE164,
+          bUuid,
+          bDeviceId,
+          bSess,
+          bKeys,
+          bPreK,
+          bSPreK,
+          kyberStore,
+          { localTime: 40 }
+        );
+      assert.deepEqual(bLongPlaintext.message, aLongPlaintext);
+      assert.deepEqual(bLongPlaintext.metadata, {
+        senderUuid: aUuid,
+        senderE164: aE164,
+        senderDeviceId: aDeviceId,
+        certificateExpiration: expires,
+        timestampSkewMillis: 3,
+      });
+
+      const aPaddedCiphertext = await SignalClient.sealedSenderEncryptMessage(
+        aPlaintext,
+        bAddress,
+        senderCert,
+        aSess,
+        aKeys,
+        { paddingBucketSize: 160 }
+      );
+      const bUnpaddedPlaintext =
+        await SignalClient.sealedSenderDecryptMessageWithOptions(
+          aPaddedCiphertext,
+          trustRoot.getPublicKey(),
+          43, // timestamp,
+          bE164,
+          bUuid,
+          bDeviceId,
+          bSess,
+          bKeys,
+          bPreK,
+          bSPreK,
+          kyberStore,
+          { paddingBucketSize: 160 }
+        );
+      assert.deepEqual(bUnpaddedPlaintext.message, aPlaintext);
+
+      const aUnpaddedCiphertext = await SignalClient.sealedSenderEncryptMessage(
+        aPlaintext,
+        bAddress,
+        senderCert,
+        aSess,
+        aKeys
+      );
+      try {
+        await SignalClient.sealedSenderDecryptMessageWithOptions(
+          aUnpaddedCiphertext,
+          trustRoot.getPublicKey(),
+          43, // timestamp,
+          bE164,
+          bUuid,
+          bDeviceId,
+          bSess,
+          bKeys,
+          bPreK,
+          bSPreK,
+          kyberStore,
+          { paddingBucketSize: 160 }
+        );
+        assert.fail();
+      } catch (e) {
+        assert.instanceOf(e, SignalClient.LibSignalErrorBase);
+        const err = e as SignalClient.LibSignalError;
+        assert.equal(err.name, 'SealedSenderInvalidPadding');
+        assert.equal(
+          err.code,
+          SignalClient.ErrorCode.SealedSenderInvalidPadding
+        );
+        const badPadding = err as SignalClient.SealedSenderInvalidPaddingError;
+        assert.equal(badPadding.bucketSize, 160);
+        assert.equal(badPadding.sealedSenderMetadata.senderUuid, aUuid);
+      }
+
       const innerMessage = await SignalClient.signalEncrypt(
         aPlaintext,
         bAddress,
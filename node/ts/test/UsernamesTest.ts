@@ -165,6 +165,52 @@ describe('usernames', () => {
     });
   });
 
+  describe('generateCandidatesWithProofs', () => {
+    it('takes discriminators from the given ranges', () => {
+      const candidates = usernames.generateCandidatesWithProofs(
+        'SiGNAl',
+        5,
+        3,
+        32,
+        { ranges: [{ start: 100, end: 106, count: 5 }], excluded: [103] }
+      );
+      assert.sameMembers(
+        candidates.map((candidate) => candidate.username),
+        ['SiGNAl.100', 'SiGNAl.101', 'SiGNAl.102', 'SiGNAl.104', 'SiGNAl.105']
+      );
+      for (const candidate of candidates) {
+        usernames.verifyProof(candidate.proof, candidate.hash);
+      }
+    });
+
+    it('will error on invalid ranges', () => {
+      expect(() =>
+        usernames.generateCandidatesWithProofs('SiGNAl', 5, 3, 32, {
+          ranges: [{ start: 1, end: 10, count: 5 }],
+        })
+      )
+        .throws(LibSignalErrorBase)
+        .with.property('code', ErrorCode.DiscriminatorCannotBeSingleDigit);
+      expect(() =>
+        usernames.generateCandidatesWithProofs('SiGNAl', 5, 3, 32, {
+          ranges: [{ start: 100, end: 2_000_000_000, count: 5 }],
+        })
+      )
+        .throws(LibSignalErrorBase)
+        .with.property('code', ErrorCode.DiscriminatorTooLarge);
+      expect(() =>
+        usernames.generateCandidatesWithProofs('SiGNAl', 5, 3, 32, {
+          ranges: [
+            { start: 100, end: 200, count: 5 },
+            { start: 150, end: 250, count: 5 },
+          ],
+        })
+      )
+        .throws(LibSignalErrorBase)
+        .with.property('code', ErrorCode.Generic);
+    });
+  });
+
   describe('link', () => {
     it('works end to end with valid data', () => {
       const expectedUsername = 'signal_test.42';
@@ -10,6 +10,13 @@ import { RANDOM_LENGTH } from './zkgroup/internal/Constants';
 import * as Native from '../Native';
 
 export type UsernameLink = { entropy: Buffer; encryptedUsername: Buffer };
+/** Picks `count` discriminators from `start` (inclusive) to `end` (exclusive). */
+export type DiscriminatorRange = { start: number; end: number; count: number };
+export type UsernameCandidate = {
+  username: string;
+  hash: Buffer;
+  proof: Buffer;
+};
 
 export function generateCandidates(
   nickname: string,
@@ -23,6 +30,55 @@ export function generateCandidates(
   );
 }
 
+/**
+ * Generates up to `count` candidates, along with the hash and proof for each one.
+ *
+ * Discriminators are taken from each of `options.ranges` in order, and never from
+ * `options.excluded`. If no ranges are given, the default ranges are used, preferring shorter
+ * discriminators.
+ *
+ * Throws a LibSignalError with code `Generic` if a range is empty, overlaps another
+ * one, or has fewer values than its count.
+ */
+export function generateCandidatesWithProofs(
+  nickname: string,
+  count: number,
+  minNicknameLength: number,
+  maxNicknameLength: number,
+  options: { ranges?: DiscriminatorRange[]; excluded?: number[] } = {}
+): UsernameCandidate[] {
+  const ranges = options.ranges ?? [];
+  const excluded = options.excluded ?? [];
+  const packedRanges = Buffer.alloc(ranges.length * 12);
+  ranges.forEach((range, i) => {
+    packedRanges.writeUInt32BE(range.start, i * 12);
+    packedRanges.writeUInt32BE(range.end, i * 12 + 4);
+    packedRanges.writeUInt32BE(range.count, i * 12 + 8);
+  });
+  const packedExcluded = Buffer.alloc(excluded.length * 4);
+  excluded.forEach((discriminator, i) => {
+    packedExcluded.writeUInt32BE(discriminator, i * 4);
+  });
+  const flattened = Native.Username_CandidatesWithProofsFrom(
+    nickname,
+    minNicknameLength,
+    maxNicknameLength,
+    packedRanges,
+    packedExcluded,
+    count
+  );
+  // Each candidate is represented as its username, hash, and proof.
+  const candidates: UsernameCandidate[] = [];
+  for (let i = 0; i < flattened.length; i += 3) {
+    candidates.push({
+      username: flattened[i].toString('utf8'),
+      hash: flattened[i + 1],
+      proof: flattened[i + 2],
+    });
+  }
+  return candidates;
+}
+
 export function fromParts(
   nickname: string,
   discriminator: string,
@@ -4,6 +4,8 @@
 //
 
 //! Implements SGX DCAP attestation verification.
+//!
+//! TDX quotes are verified against the same collateral; see [`tdx`].
 // https://www.intel.com/content/dam/develop/external/us/en/documents/intel-sgx-support-for-third-party-attestation-801017.pdf
 // https://api.portal.trustedservices.intel.com/provisioning-certification
 
@@ -41,12 +43,14 @@ use uuid::Uuid;
 
 use crate::dcap::ecdsa::EcdsaSigned;
 use crate::dcap::endorsements::{
-    EnclaveType, QeTcbStatus, SgxEndorsements, TcbInfo, TcbLevel, TcbStatus,
+    EnclaveType, QeTcbStatus, SgxEndorsements, TcbInfo, TcbInfoId, TcbLevel, TcbStatus,
 };
-use crate::dcap::evidence::Evidence;
+use crate::dcap::evidence::{CustomClaims, Evidence};
+use crate::dcap::sgx_quote::SgxQuoteSupport;
 pub use crate::dcap::sgx_report_body::MREnclave;
 use crate::dcap::sgx_report_body::SgxFlags;
 use crate::dcap::sgx_x509::SgxPckExtension;
+pub use crate::dcap::tdx_quote::{MrTd, TdMeasurements, TdxMeasurement};
 use crate::enclave::AttestationError;
 use crate::error::{Context, ContextError};
 use crate::expireable::Expireable;
@@ -58,6 +62,8 @@ mod revocation_list;
 mod sgx_quote;
 mod sgx_report_body;
 mod sgx_x509;
+pub mod tdx;
+mod tdx_quote;
 
 #[cfg(test)]
 mod fakes;
@@ -94,18 +100,7 @@ pub fn verify_remote_attestation(
     let attestation = attest(evidence_bytes, endorsement_bytes, current_time)?;
 
     // 4. Verify the status of the Intel® SGX TCB described in the chain.
-    if let TcbStanding::SWHardeningNeeded { advisory_ids } = attestation.tcb_standing {
-        if advisory_ids
-            .iter()
-            .any(|id| !acceptable_sw_advisories.contains(&id.as_str()))
-        {
-            return Err(Error::new(format!(
-                "TCB contains unmitigated unaccepted advisory ids: {:?}",
-                advisory_ids
-            ))
-            .into());
-        }
-    }
+    verify_sw_advisories(&attestation.tcb_standing, acceptable_sw_advisories)?;
 
     // 5. Verify the enclave measurements in the Quote reflect an enclave identity expected.
     if expected_mrenclave != &attestation.mrenclave {
@@ -120,6 +115,25 @@ pub fn verify_remote_attestation(
     Ok(attestation.claims)
 }
 
+/// Checks that any advisories for the remote TCB are known to be mitigated
+fn verify_sw_advisories(
+    tcb_standing: &TcbStanding,
+    acceptable_sw_advisories: &[&str],
+) -> Result<()> {
+    if let TcbStanding::SWHardeningNeeded { advisory_ids } = tcb_standing {
+        if advisory_ids
+            .iter()
+            .any(|id| !acceptable_sw_advisories.contains(&id.as_str()))
+        {
+            return Err(Error::new(format!(
+                "TCB contains unmitigated unaccepted advisory ids: {:?}",
+                advisory_ids
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Parses evidence/endorsements and builds a map of metrics
 pub fn attestation_metrics(
     evidence_bytes: &[u8],
@@ -235,19 +249,37 @@ fn attest_impl(
     // verify the time parameter falls within “not before” and “not after” metadata
     verify_expiration(current_time, &evidence).context("evidence")?;
     verify_expiration(current_time, &endorsements).context("endorsements")?;
-    verify_certificates(trusted_root_pkey, &evidence, &endorsements, current_time)?;
+    verify_certificates(
+        trusted_root_pkey,
+        &evidence.quote.support,
+        &endorsements,
+        current_time,
+    )?;
 
     // 3. Verify the Quoting Enclave is from a suitable source and is up to date
     // verify the quoting enclave identity
-    verify_enclave_source(&evidence, &endorsements)?;
-    verify_enclave_signatures(&evidence)?;
+    verify_enclave_source(
+        &evidence.quote.quote_body.qe_vendor_id,
+        &evidence.quote.support,
+        &endorsements,
+        EnclaveType::Qe,
+    )?;
+    verify_enclave_signatures(&evidence.quote, &evidence.quote.support)?;
 
     // find the TCB standing of the enclave
-    let tcb_standing = verify_tcb_status(&evidence, &endorsements)?;
+    let tcb_standing = verify_tcb_status(
+        &evidence.quote.support.pck_extension,
+        &endorsements,
+        TcbInfoId::Sgx,
+        None,
+    )?;
 
     // everything in the quote is verified. lastly, check the custom claims hash matches
     // the report data, and then return the claims map
-    verify_claims_hash(&evidence)?;
+    verify_claims_hash(
+        &evidence.claims,
+        &evidence.quote.quote_body.report_body.sgx_report_data_bytes,
+    )?;
 
     // clients should only trust MRENCLAVE values from a non-debug
     // build. But, as an extra precaution, verify that the remote
@@ -280,7 +312,7 @@ lazy_static! {
 /// in `trusted_pkey`
 fn verify_certificates(
     trusted_pkey: &PKeyRef<Public>,
-    evidence: &Evidence,
+    quote_support: &SgxQuoteSupport,
     endorsements: &SgxEndorsements,
     current_time: SystemTime,
 ) -> Result<()> {
@@ -313,9 +345,7 @@ fn verify_certificates(
         .tcb_issuer_chain
         .validate_chain(&trusted, &[])
         .context("tcb issuer")?;
-    evidence
-        .quote
-        .support
+    quote_support
         .pck_cert_chain
         .validate_chain(&trusted, &[])
         .context("pck")?;
@@ -405,36 +435,39 @@ fn verify_expiration(timestamp: SystemTime, expireable: &dyn Expireable) -> Resu
 
 /// Verify the reported enclave matches a valid, up-to-date quoting enclave issued by intel
 ///
+/// `expected_qe` is the kind of quoting enclave that should have produced the quote.
+///
 /// This follows the steps outlined in:
 /// <https://api.portal.trustedservices.intel.com/documentation#pcs-qe-identity-v3>
-fn verify_enclave_source(evidence: &Evidence, endorsements: &SgxEndorsements) -> Result<()> {
+fn verify_enclave_source(
+    qe_vendor_id: &[u8; 16],
+    quote_support: &SgxQuoteSupport,
+    endorsements: &SgxEndorsements,
+    expected_qe: EnclaveType,
+) -> Result<()> {
     // verify the qe vendor is intel
-    Uuid::from_slice(&evidence.quote.quote_body.qe_vendor_id)
+    Uuid::from_slice(qe_vendor_id)
         .ok()
         .filter(|uuid| uuid == &INTEL_QE_VENDOR_ID)
         .ok_or_else(|| {
             Error::new(format!(
                 "QE Vendor ID: {} not Intel",
-                evidence
-                    .quote
-                    .quote_body
-                    .qe_vendor_id
-                    .encode_hex::<String>()
+                qe_vendor_id.encode_hex::<String>()
             ))
         })?;
 
     // compare mrsigner from QE identity and quote’s QE report
     let qe_identity = &endorsements.qe_id_info;
-    if qe_identity.mrsigner != evidence.quote.support.qe_report_body.mrsigner {
+    if qe_identity.mrsigner != quote_support.qe_report_body.mrsigner {
         return Err(Error::new(format!(
             "qe mrsigner mismatch: expected {}, actual {}",
             hex::encode(qe_identity.mrsigner),
-            hex::encode(evidence.quote.support.qe_report_body.mrsigner)
+            hex::encode(quote_support.qe_report_body.mrsigner)
         )));
     }
 
     // compare isvprodid in report vs collateral
-    let report_isvprodid = evidence.quote.support.qe_report_body.isvprodid.value();
+    let report_isvprodid = quote_support.qe_report_body.isvprodid.value();
     let collateral_isvprodid = qe_identity.isvprodid;
     if report_isvprodid != collateral_isvprodid {
         return Err(Error::new(format!(
@@ -444,14 +477,14 @@ fn verify_enclave_source(evidence: &Evidence, endorsements: &SgxEndorsements) ->
     }
 
     // compare miscselect from QE identity and masked miscselect from quote’s QE report
-    let qe_report_miscselect = evidence.quote.support.qe_report_body.miscselect.value();
+    let qe_report_miscselect = quote_support.qe_report_body.miscselect.value();
     if qe_report_miscselect & qe_identity.miscselect_mask.value() != qe_identity.miscselect.value()
     {
         return Err(Error::new("qe miscselect mismatch"));
     }
 
     // compare attributes from QE identity and masked attributes from quote’s QE report
-    let qe_report_attributes = evidence.quote.support.qe_report_body.sgx_attributes;
+    let qe_report_attributes = quote_support.qe_report_body.sgx_attributes;
 
     let calculated_mask = qe_identity
         .attributes_mask
@@ -466,7 +499,7 @@ fn verify_enclave_source(evidence: &Evidence, endorsements: &SgxEndorsements) ->
         return Err(Error::new("attributes mismatch"));
     }
 
-    if qe_identity.id != EnclaveType::Qe {
+    if qe_identity.id != expected_qe {
         return Err(Error::new(format!(
             "Invalid enclave identity for quoting enclave : {:?}",
             qe_identity.id
@@ -476,7 +509,7 @@ fn verify_enclave_source(evidence: &Evidence, endorsements: &SgxEndorsements) ->
     // Later, we will also lookup the tcb status in the TcbInfo but if
     // the Enclave Identity tcb status isn't up to date, we can fail right
     // away
-    let report_isvsvn = evidence.quote.support.qe_report_body.isvsvn.value();
+    let report_isvsvn = quote_support.qe_report_body.isvsvn.value();
     let tcb_status = qe_identity.tcb_status(report_isvsvn);
     if tcb_status != &QeTcbStatus::UpToDate {
         return Err(Error::new(format!(
@@ -489,54 +522,53 @@ fn verify_enclave_source(evidence: &Evidence, endorsements: &SgxEndorsements) ->
 }
 
 /// Verify that the quoting enclave report is signed, contains
-/// the expected contents, and that the ISV report is signed by
+/// the expected contents, and that the ISV report (`quote`) is signed by
 /// the quoting enclave
-fn verify_enclave_signatures(evidence: &Evidence) -> Result<()> {
+fn verify_enclave_signatures(
+    quote: &dyn EcdsaSigned,
+    quote_support: &SgxQuoteSupport,
+) -> Result<()> {
     // the quoting enclave (QE) report should be signed by the pck certificate
-    let pck_pkey = evidence
-        .quote
-        .support
+    let pck_pkey = quote_support
         .pck_cert_chain
         .leaf_pub_key()
         .context("pck cert chain")?;
-    evidence
-        .quote
-        .support
+    quote_support
         .verify_signature(&pck_pkey)
         .context("QE report")?;
 
     // the QE report should be the SHA256 of the attest key and auth data
-    evidence
-        .quote
-        .support
-        .verify_qe_report()
-        .context("QE report")?;
+    quote_support.verify_qe_report().context("QE report")?;
 
     // and finally, the isv report should be signed by the attest key in the quote
-    let attest_key = &*evidence
-        .quote
-        .support
-        .attest_key()
-        .context("quote attest key")?;
-    evidence
-        .quote
-        .verify_signature(attest_key)
-        .context("ISV report")?;
+    let attest_key = &*quote_support.attest_key().context("quote attest key")?;
+    quote.verify_signature(attest_key).context("ISV report")?;
 
     Ok(())
 }
 
 /// Get the tcb status of the quoting enclave
 ///
-/// Looks at the pck extension to determine the TCB level in the [`TcbInfo`]
-/// If the TCB level is found, returns the status of that TCB level
+/// Looks at the pck extension (and for TDX, the TEE tcb svn from the TD report) to determine the
+/// TCB level in the [`TcbInfo`]. If the TCB level is found, returns the status of that TCB level
 ///
 /// This follows the steps outlined in:
 /// <https://api.portal.trustedservices.intel.com/documentation#pcs-tcb-info-v3>
-fn verify_tcb_status(evidence: &Evidence, endorsements: &SgxEndorsements) -> Result<TcbStanding> {
+fn verify_tcb_status(
+    pck_ext: &SgxPckExtension,
+    endorsements: &SgxEndorsements,
+    expected_id: TcbInfoId,
+    tee_tcb_svn: Option<&[u8; 16]>,
+) -> Result<TcbStanding> {
     // the tcb should be signed by the tcb issuer chain
     let tcb_info = &endorsements.tcb_info;
-    let pck_ext = &evidence.quote.support.pck_extension;
+
+    if tcb_info.id != expected_id {
+        return Err(Error::new(format!(
+            "tcb info is for {:?}, expected {:?}",
+            tcb_info.id, expected_id
+        )));
+    }
 
     // make sure the tcb_info matches our enclave's model/PCE version
     if pck_ext.fmspc != tcb_info.fmspc {
@@ -555,20 +587,15 @@ fn verify_tcb_status(evidence: &Evidence, endorsements: &SgxEndorsements) -> Res
     // Find the tcb status corresponding to our enclave in the tcb info
     // the consumer of dcap needs to decide which statuses are acceptable (either by
     // returning this up, or configuring acceptable statuses)
-    TcbStanding::lookup(pck_ext, tcb_info)
+    TcbStanding::lookup(pck_ext, tcb_info, tee_tcb_svn)
 }
 
 /// Verify that the hash of the custom claims matches
 /// the value in the ISV enclave report
-fn verify_claims_hash(evidence: &Evidence) -> Result<()> {
-    let claims_sha256 = evidence.claims.data_sha256();
+fn verify_claims_hash(claims: &CustomClaims, report_data: &[u8; 64]) -> Result<()> {
+    let claims_sha256 = claims.data_sha256();
 
-    let (report_sha256, empty_bytes) = evidence
-        .quote
-        .quote_body
-        .report_body
-        .sgx_report_data_bytes
-        .split_at(32);
+    let (report_sha256, empty_bytes) = report_data.split_at(32);
 
     if empty_bytes != [0u8; 32] {
         return Err(Error::new("report data hash had unexpected data"));
@@ -607,9 +634,15 @@ impl TcbStanding {
     /// but may return success if the status should be interpreted by the
     /// user (e.g., [`TcbStatus::SWHardeningNeeded`])
     ///
+    /// For TDX, `tee_tcb_svn` must also be at least the level's TDX components.
+    ///
     /// This follows the steps 3.a-b outlined
     /// in <https://api.portal.trustedservices.intel.com/documentation#pcs-tcb-info-v3>
-    fn lookup(pck_extension: &SgxPckExtension, tcb_info: &TcbInfo) -> Result<TcbStanding> {
+    fn lookup(
+        pck_extension: &SgxPckExtension,
+        tcb_info: &TcbInfo,
+        tee_tcb_svn: Option<&[u8; 16]>,
+    ) -> Result<TcbStanding> {
         // Go over the tcb_levels in the provided order and stop on the first tcb level
         // where the pck compsvn/pcesvn is >= tcb compsvn/pcesvn.
         // We assume these are sorted in the correct order based on the tcb info
@@ -617,7 +650,7 @@ impl TcbStanding {
         let first_matching_level = tcb_info
             .tcb_levels
             .iter()
-            .find(|level| Self::in_tcb_level(level, pck_extension));
+            .find(|level| Self::in_tcb_level(level, pck_extension, tee_tcb_svn));
 
         first_matching_level
             .map(|level| match level.tcb_status {
@@ -634,16 +667,38 @@ impl TcbStanding {
     }
 
     /// Returns true if all the pck components are >= all the tcb level components AND
-    /// the pck pcesvn is >= tcb pcesvn
-    fn in_tcb_level(level: &TcbLevel, pck_extension: &SgxPckExtension) -> bool {
+    /// the pck pcesvn is >= tcb pcesvn (AND for TDX, the TEE tcb svn is >= the level's TDX
+    /// components)
+    fn in_tcb_level(
+        level: &TcbLevel,
+        pck_extension: &SgxPckExtension,
+        tee_tcb_svn: Option<&[u8; 16]>,
+    ) -> bool {
         const SVN_LENGTH: usize = 16;
         let pck_components: &[u8; SVN_LENGTH] = &pck_extension.tcb.compsvn;
 
-        pck_components
+        let sgx_matches = pck_components
             .iter()
             .zip(level.tcb.components())
             .all(|(&p, l)| p >= l)
-            && pck_extension.tcb.pcesvn >= level.tcb.pcesvn()
+            && pck_extension.tcb.pcesvn >= level.tcb.pcesvn();
+
+        let tdx_matches = match (tee_tcb_svn, level.tcb.tdx_components()) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(tee_tcb_svn), Some(tdx_components)) => {
+                // For TDX modules after major version 0, the first two components are the
+                // module's own version, which is checked against the module identity instead.
+                let skip = if tee_tcb_svn[1] > 0 { 2 } else { 0 };
+                tee_tcb_svn
+                    .iter()
+                    .zip(tdx_components)
+                    .skip(skip)
+                    .all(|(&t, l)| t >= l)
+            }
+        };
+
+        sgx_matches && tdx_matches
     }
 }
 
@@ -351,11 +351,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_tcb_info_tdx() {
+        let components = |svn: u8| vec![serde_json::json!({ "svn": svn }); 16];
+        let json = serde_json::json!({
+            "id": "TDX",
+            "version": 3,
+            "issueDate": "2024-05-01T00:00:00Z",
+            "nextUpdate": "2024-06-01T00:00:00Z",
+            "fmspc": "00806F050000",
+            "pceId": "0000",
+            "tcbType": 0,
+            "tcbEvaluationDataNumber": 16,
+            "tdxModule": {
+                "mrsigner": "00".repeat(48),
+                "attributes": "0000000000000000",
+                "attributesMask": "FFFFFFFFFFFFFFFF",
+            },
+            "tdxModuleIdentities": [{
+                "id": "TDX_01",
+                "mrsigner": "11".repeat(48),
+                "attributes": "0000000000000000",
+                "attributesMask": "FFFFFFFFFFFFFFFF",
+                "tcbLevels": [{
+                    "tcb": { "isvsvn": 2 },
+                    "tcbDate": "2024-03-13T00:00:00Z",
+                    "tcbStatus": "UpToDate",
+                }],
+            }],
+            "tcbLevels": [{
+                "tcb": {
+                    "sgxtcbcomponents": components(2),
+                    "pcesvn": 13,
+                    "tdxtcbcomponents": components(5),
+                },
+                "tcbDate": "2024-03-13T00:00:00Z",
+                "tcbStatus": "UpToDate",
+            }],
+        });
+        let tcb_info: TcbInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(TcbInfoId::Tdx, tcb_info.id);
+        assert_eq!([0; 48], tcb_info.tdx_module.unwrap().mrsigner);
+        assert_eq!("TDX_01", tcb_info.tdx_module_identities[0].id);
+        assert_eq!(
+            TcbStatus::UpToDate,
+            tcb_info.tdx_module_identities[0].tcb_status(3)
+        );
+        assert_eq!(
+            TcbStatus::Revoked,
+            tcb_info.tdx_module_identities[0].tcb_status(1)
+        );
+        assert_eq!(Some([5; 16]), tcb_info.tcb_levels[0].tcb.tdx_components());
+    }
+
     #[test]
     fn parse_tcb_info_v2() {
         const DATA: &[u8] = include_bytes!("../../tests/data/tcb_info_v2.json");
         let tcb_info: TcbInfo = serde_json::from_slice(DATA).unwrap();
         assert_eq!(TcbInfoVersion::V2, tcb_info.version);
+        assert_eq!(TcbInfoId::Sgx, tcb_info.id);
         assert_eq!(hex!("00606A000000"), tcb_info.fmspc);
         assert_eq!(
             TcbStatus::SWHardeningNeeded,
@@ -404,6 +458,19 @@ impl TcbInfoAndSignature<'_> {
             )));
         }
 
+        if tcb_info.id == TcbInfoId::Tdx {
+            if tcb_info.version != TcbInfoVersion::V3 {
+                return Err(Error::new("TDX tcb info must be at least version 3"));
+            }
+            if tcb_info
+                .tcb_levels
+                .iter()
+                .any(|level| level.tcb.tdx_components().is_none())
+            {
+                return Err(Error::new("TDX tcb info is missing TDX tcb components"));
+            }
+        }
+
         // tcb_type determines how to compare tcb level
         // currently, only 0 is valid
         if tcb_info.tcb_type != 0 {
@@ -440,9 +507,21 @@ impl TryFrom<u16> for TcbInfoVersion {
     }
 }
 
+/// Which kind of platform a [`TcbInfo`] describes
+#[derive(Deserialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub(crate) enum TcbInfoId {
+    /// TcbInfo V2 (from the PCS V3 API) has no `id`, and is always for SGX
+    #[default]
+    Sgx,
+    Tdx,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct TcbInfo {
+    #[serde(default)]
+    pub id: TcbInfoId,
     version: TcbInfoVersion,
     _issue_date: chrono::DateTime<Utc>,
     pub next_update: chrono::DateTime<Utc>,
@@ -453,6 +532,69 @@ pub(crate) struct TcbInfo {
     tcb_type: u16,
     _tcb_evaluation_data_number: u16,
     pub tcb_levels: Vec<TcbLevel>,
+    /// The expected identity of the TDX module, for TDX tcb info
+    #[serde(default)]
+    pub tdx_module: Option<TdxModule>,
+    /// The expected identities of the TDX module for each major version after 0, for TDX tcb info
+    #[serde(default)]
+    pub tdx_module_identities: Vec<TdxModuleIdentity>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TdxModule {
+    #[serde(with = "hex")]
+    pub mrsigner: [u8; 48],
+    #[serde(with = "hex")]
+    pub attributes: [u8; 8],
+    #[serde(with = "hex")]
+    pub attributes_mask: [u8; 8],
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TdxModuleIdentity {
+    /// `TDX_` followed by the two-digit major version of the module
+    pub id: String,
+    #[serde(flatten)]
+    pub module: TdxModule,
+    pub tcb_levels: Vec<TdxModuleTcbLevel>,
+}
+
+impl TdxModuleIdentity {
+    /// Find the latest tcb level that the TDX module's security version is at least.
+    pub fn tcb_status(&self, isvsvn: u8) -> TcbStatus {
+        // tcb_levels is in descending order by ISVSVN according to spec
+        self.tcb_levels
+            .iter()
+            .find(|level| level.tcb.isvsvn <= isvsvn)
+            .map(|level| level.tcb_status)
+            .unwrap_or(TcbStatus::Revoked)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TdxModuleTcbLevel {
+    tcb: TdxModuleTcb,
+    _tcb_date: chrono::DateTime<Utc>,
+    tcb_status: TcbStatus,
+}
+
+#[cfg(test)]
+impl TdxModuleTcbLevel {
+    pub(crate) fn from_parts(tcb_status: TcbStatus, isvsvn: u8) -> Self {
+        Self {
+            tcb: TdxModuleTcb { isvsvn },
+            _tcb_date: Utc::now(),
+            tcb_status,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TdxModuleTcb {
+    isvsvn: u8,
 }
 
 impl Expireable for TcbInfo {
@@ -508,6 +650,7 @@ impl TcbLevel {
             TcbInfoVersion::V3 => Tcb::V3(TcbV3 {
                 sgxtcbcomponents: tcbcompsvn.map(|x| TcbComponentV3 { svn: x }),
                 pcesvn,
+                tdxtcbcomponents: None,
             }),
         };
         Self {
@@ -517,6 +660,25 @@ impl TcbLevel {
             advisory_ids,
         }
     }
+
+    /// Test only constructor for TDX tcb levels (which are always V3)
+    pub(crate) fn from_parts_tdx(
+        tcbcompsvn: [u8; 16],
+        pcesvn: u16,
+        tdxcompsvn: [u8; 16],
+        tcb_status: TcbStatus,
+    ) -> TcbLevel {
+        Self {
+            tcb: Tcb::V3(TcbV3 {
+                sgxtcbcomponents: tcbcompsvn.map(|x| TcbComponentV3 { svn: x }),
+                pcesvn,
+                tdxtcbcomponents: Some(tdxcompsvn.map(|x| TcbComponentV3 { svn: x })),
+            }),
+            _tcb_date: Utc::now(),
+            tcb_status,
+            advisory_ids: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Deserialize)]
@@ -551,6 +713,9 @@ impl Tcb {
 pub(crate) struct TcbV3 {
     sgxtcbcomponents: [TcbComponentV3; 16],
     pcesvn: u16,
+    /// Only present for TDX
+    #[serde(default)]
+    tdxtcbcomponents: Option<[TcbComponentV3; 16]>,
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -610,6 +775,16 @@ impl Tcb {
             Self::V3(v3) => v3.sgxtcbcomponents.map(|comp| comp.svn),
         }
     }
+
+    /// The TEE tcb components, for TDX tcb levels
+    pub fn tdx_components(&self) -> Option<[u8; 16]> {
+        match self {
+            Self::V2(_) => None,
+            Self::V3(v3) => v3
+                .tdxtcbcomponents
+                .map(|components| components.map(|comp| comp.svn)),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -703,6 +878,9 @@ pub(crate) enum EnclaveType {
     Qe,
     /// Quote Verification Enclave (which we won't use)
     Qve,
+    /// TDX Quoting Enclave
+    #[serde(rename = "TD_QE")]
+    TdQe,
 }
 
 #[derive(Deserialize, Debug)]
@@ -9,6 +9,7 @@ use sha2::Digest;
 use std::collections::HashMap;
 
 use crate::dcap::sgx_quote::SgxQuote;
+use crate::dcap::tdx_quote::TdxQuote;
 use crate::dcap::{Error, Expireable};
 use crate::error::Context;
 use crate::util;
@@ -45,6 +46,32 @@ impl Expireable for Evidence<'_> {
     }
 }
 
+/// Evidence from a TDX trust domain, which has the same layout as [`Evidence`] but with a
+/// [`TdxQuote`].
+pub(crate) struct TdxEvidence<'a> {
+    pub quote: TdxQuote<'a>,
+    pub claims: CustomClaims<'a>,
+}
+
+impl<'a> TryFrom<&'a [u8]> for TdxEvidence<'a> {
+    type Error = super::Error;
+
+    fn try_from(mut bytes: &'a [u8]) -> super::Result<Self> {
+        let quote = TdxQuote::read(&mut bytes).context("quote")?;
+
+        // bytes should now point at the start of custom_claims
+        let claims: CustomClaims = bytes.try_into().context("claims")?;
+
+        Ok(TdxEvidence { quote, claims })
+    }
+}
+
+impl Expireable for TdxEvidence<'_> {
+    fn valid_at(&self, timestamp: std::time::SystemTime) -> bool {
+        self.quote.valid_at(timestamp)
+    }
+}
+
 /// Version of oe_custom_claims_header_t/oe_custom_claims_entry_t
 const OE_CLAIMS_V1: u64 = 1;
 
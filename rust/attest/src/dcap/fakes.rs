@@ -11,13 +11,18 @@
 //! 2. Manipulate the default certificates on the enclosed [`SigningInfo`]
 //! 3. Manipulate the default evidence/endorsements
 //! 4. Create the final evidence/endorsements with [`FakeAttestation::sign`]
+//!
+//! [`FakeTdxAttestation::builder`] works the same way for TDX evidence, reusing the SGX
+//! quote support and collateral.
 
 use crate::cert_chain::testutil::TestCert;
 use crate::cert_chain::CertChain;
 use crate::dcap::ecdsa::EcdsaSigned;
-use crate::dcap::endorsements::SgxEndorsements;
-use crate::dcap::evidence::Evidence;
+use crate::dcap::endorsements::{EnclaveType, SgxEndorsements, TcbInfoId, TcbLevel, TdxModule};
+use crate::dcap::evidence::{Evidence, TdxEvidence};
 use crate::dcap::revocation_list::RevocationList;
+use crate::dcap::tdx::{self, TdxAttestation};
+use crate::dcap::tdx_quote::{fake_quote_body, MrTd, TdMeasurements, TdxQuote, TdxQuoteBody};
 use crate::dcap::{attest_impl, Attestation};
 use boring::asn1::{Asn1Integer, Asn1IntegerRef};
 use boring::bn::{BigNum, BigNumContext};
@@ -236,3 +241,103 @@ impl FakeAttestationBuilder {
         }
     }
 }
+
+pub(crate) struct FakeTdxAttestation {
+    pub root_key: PKey<Public>,
+    pub evidence: TdxEvidence<'static>,
+    pub endorsements: SgxEndorsements,
+}
+
+impl FakeTdxAttestation {
+    /// The MRTD of the default fake trust domain
+    pub const MRTD: MrTd = [0xAB; 48];
+
+    /// All the measurements of the default fake trust domain
+    pub const MEASUREMENTS: TdMeasurements = TdMeasurements {
+        mrtd: Self::MRTD,
+        mrconfigid: [0xCD; 48],
+        rtmrs: [[0x10; 48], [0x11; 48], [0x12; 48], [0x13; 48]],
+    };
+
+    /// Create a `[FakeTdxAttestationBuilder]` with unsigned evidence/endorsements
+    ///
+    /// As with [`FakeAttestation::builder`], the initial values will pass attestation when
+    /// signed. The SGX endorsements are rewritten to describe a TDX platform with a version 0
+    /// TDX module.
+    pub fn builder() -> FakeTdxAttestationBuilder {
+        let mut sgx = FakeAttestation::builder();
+        let tcb_info = &mut sgx.uendorsements.tcb_info;
+        tcb_info.id = TcbInfoId::Tdx;
+        tcb_info.tdx_module = Some(TdxModule {
+            mrsigner: [0; 48],
+            attributes: [0; 8],
+            attributes_mask: [0xFF; 8],
+        });
+        for level in &mut tcb_info.tcb_levels {
+            *level = TcbLevel::from_parts_tdx(
+                level.tcb.components(),
+                level.tcb.pcesvn(),
+                [0; 16],
+                level.tcb_status,
+            );
+        }
+        sgx.uendorsements.qe_id_info.id = EnclaveType::TdQe;
+
+        let mut quote_body = fake_quote_body(
+            sgx.uevidence.quote.quote_body.qe_vendor_id,
+            [0; 16],
+            Self::MRTD,
+        );
+        quote_body.report_body.mrconfigid = Self::MEASUREMENTS.mrconfigid;
+        quote_body.report_body.rtmrs = Self::MEASUREMENTS.rtmrs;
+        FakeTdxAttestationBuilder { sgx, quote_body }
+    }
+
+    pub fn attest(self) -> Result<TdxAttestation, super::Error> {
+        tdx::attest_impl(
+            self.evidence,
+            self.endorsements,
+            &self.root_key,
+            SystemTime::now(),
+        )
+    }
+}
+
+pub(crate) struct FakeTdxAttestationBuilder {
+    /// Supplies the signing info, quote support, claims and endorsements
+    pub sgx: FakeAttestationBuilder,
+
+    // unsigned quote body
+    pub quote_body: TdxQuoteBody,
+}
+
+impl FakeTdxAttestationBuilder {
+    /// Create TDX Evidence/Endorsements that have the appropriate report signatures
+    ///
+    /// The report data of `quote_body` is overwritten with the hash of the claims.
+    pub fn sign(mut self) -> FakeTdxAttestation {
+        let attest_key = self.sgx.signing_info.attest_key.clone();
+        let FakeAttestation {
+            root_key,
+            evidence,
+            endorsements,
+        } = self.sgx.sign();
+
+        self.quote_body.report_body.report_data =
+            evidence.quote.quote_body.report_body.sgx_report_data_bytes;
+        let mut quote = TdxQuote {
+            quote_body: self.quote_body,
+            support: evidence.quote.support,
+        };
+        quote.support.isv_signature = FakeAttestationBuilder::sign_data(quote.data(), &attest_key);
+
+        FakeTdxAttestation {
+            root_key,
+            evidence: TdxEvidence {
+                quote,
+                claims: evidence.claims,
+            },
+            endorsements,
+        }
+    }
+}
@@ -78,7 +78,7 @@ impl<'a> SgxQuote<'a> {
 ///
 /// Rust can only check the alignment of the top-level type; it can't check the repr or the
 /// properties of fields. Be careful!
-unsafe fn bytes_of<T>(value: &T) -> &[u8] {
+pub(crate) unsafe fn bytes_of<T>(value: &T) -> &[u8] {
     assert_eq!(1, std::mem::align_of::<T>());
     let len = std::mem::size_of::<T>();
     let ptr = value as *const T;
@@ -187,7 +187,8 @@ enum CertificationKeyType {
     _PpidRsa3072Encrypted,
     _PckCleartext,
     PckCertChain,
-    _EcdsaSigAuxData,
+    /// Wraps the QE report and the PCK certification data in version 4 quotes
+    QeReportCertificationData,
 }
 
 /// In the intel docs, this is A4.4: "ECDSA 256-bit Quote Signature Data Structure"
@@ -233,10 +234,78 @@ impl<'a> SgxQuoteSupport<'a> {
             util::read_array::<{ std::mem::size_of::<SgxEcdsaSignatureHeader>() }>(src);
         let header = SgxEcdsaSignatureHeader::try_from(header_bytes).context("signature header")?;
 
-        if src.len() < header.auth_data_size.value() as usize {
+        Self::read_certified_qe_report(
+            &header.signature,
+            header.attest_pub_key,
+            header.qe_report_body,
+            &header.qe_report_signature,
+            header.auth_data_size.value(),
+            src,
+        )
+    }
+
+    /// Reads the "Quote Signature Data" of a version 4 quote, as produced for TDX.
+    ///
+    /// This has the same contents as in a version 3 quote, but the QE report, its signature, and
+    /// the PCK certificate chain are wrapped in a [`CertificationKeyType::QeReportCertificationData`]
+    /// section.
+    pub fn read_v4(src: &mut &'a [u8]) -> super::Result<Self> {
+        const KEYS_AND_CERT_DATA_HEADER_LEN: usize =
+            64 + 64 + std::mem::size_of::<u16>() + std::mem::size_of::<u32>();
+        if src.len() < KEYS_AND_CERT_DATA_HEADER_LEN {
+            return Err(Error::new("incorrect buffer size"));
+        }
+
+        let signature = util::read_array::<64>(src);
+        let attest_pub_key = util::read_array::<64>(src);
+        let cert_data_type = util::read_u16_le(src);
+        if cert_data_type != CertificationKeyType::QeReportCertificationData as u16 {
+            return Err(Error::new("unsupported certification data type"));
+        }
+        let cert_data_size = util::read_u32_le(src) as usize;
+        if src.len() < cert_data_size {
+            return Err(Error::new("remaining data does not match expected size"));
+        }
+        let mut cert_data = util::read_bytes(src, cert_data_size);
+
+        if cert_data.len() < std::mem::size_of::<QeReportCertificationDataHeader>() {
+            return Err(Error::new("incorrect QE report certification data size"));
+        }
+        let header_bytes = util::read_array::<
+            { std::mem::size_of::<QeReportCertificationDataHeader>() },
+        >(&mut cert_data);
+        let header = QeReportCertificationDataHeader::try_from(header_bytes)
+            .context("QE report certification data")?;
+
+        let support = Self::read_certified_qe_report(
+            &signature,
+            attest_pub_key,
+            header.qe_report_body,
+            &header.qe_report_signature,
+            header.auth_data_size.value(),
+            &mut cert_data,
+        )?;
+        if !cert_data.is_empty() {
+            return Err(Error::new(
+                "unexpected extra data in QE report certification data",
+            ));
+        }
+        Ok(support)
+    }
+
+    /// Reads the QE authentication data and the PCK certification data that follow the QE report.
+    fn read_certified_qe_report(
+        isv_signature: &[u8; 64],
+        attest_pub_key: [u8; 64],
+        qe_report_body: SgxReportBody,
+        qe_report_signature: &[u8; 64],
+        auth_data_size: u16,
+        src: &mut &'a [u8],
+    ) -> super::Result<Self> {
+        if src.len() < auth_data_size as usize {
             return Err(Error::new("buffer underflow"));
         }
-        let auth_data = util::read_bytes(src, header.auth_data_size.value() as usize);
+        let auth_data = util::read_bytes(src, auth_data_size as usize);
         if src.len() < std::mem::size_of::<u16>() + std::mem::size_of::<u32>() {
             return Err(Error::new("buffer underflow"));
         }
@@ -269,11 +338,10 @@ impl<'a> SgxQuoteSupport<'a> {
             SgxPckExtension::from_der(pck_ext.data().as_slice()).context("SgxPckExtension")?;
 
         let signature = SgxQuoteSupport {
-            isv_signature: ecdsa_signature_from_bytes(&header.signature)
-                .context("isv_signature")?,
-            attest_pub_key: header.attest_pub_key,
-            qe_report_body: header.qe_report_body,
-            qe_report_signature: ecdsa_signature_from_bytes(&header.qe_report_signature)
+            isv_signature: ecdsa_signature_from_bytes(isv_signature).context("isv_signature")?,
+            attest_pub_key,
+            qe_report_body,
+            qe_report_signature: ecdsa_signature_from_bytes(qe_report_signature)
                 .context("qe_report_signature")?,
             auth_data,
             pck_cert_chain,
@@ -354,6 +422,45 @@ impl TryFrom<[u8; std::mem::size_of::<SgxEcdsaSignatureHeader>()]> for SgxEcdsaS
     }
 }
 
+/// The start of the "QE Report Certification Data" in a version 4 quote
+#[derive(Debug)]
+#[repr(C)]
+struct QeReportCertificationDataHeader {
+    qe_report_body: SgxReportBody,
+    qe_report_signature: [u8; 64],
+    auth_data_size: UInt16LE,
+}
+
+static_assertions::const_assert_eq!(1, std::mem::align_of::<QeReportCertificationDataHeader>());
+static_assertions::const_assert_eq!(450, std::mem::size_of::<QeReportCertificationDataHeader>());
+
+impl TryFrom<[u8; std::mem::size_of::<QeReportCertificationDataHeader>()]>
+    for QeReportCertificationDataHeader
+{
+    type Error = super::Error;
+
+    fn try_from(
+        bytes: [u8; std::mem::size_of::<QeReportCertificationDataHeader>()],
+    ) -> super::Result<Self> {
+        Ok(unsafe { transmute(bytes) })
+    }
+}
+
+/// Rewraps the "Quote Signature Data" from a version 3 quote in the version 4 layout.
+#[cfg(test)]
+pub(crate) fn v3_support_to_v4(v3_support: &[u8]) -> Vec<u8> {
+    let (keys, certified_qe_report) = v3_support.split_at(64 + 64);
+    let mut v4_support = keys.to_vec();
+    v4_support.extend((CertificationKeyType::QeReportCertificationData as u16).to_le_bytes());
+    v4_support.extend(
+        u32::try_from(certified_qe_report.len())
+            .expect("small enough")
+            .to_le_bytes(),
+    );
+    v4_support.extend(certified_qe_report);
+    v4_support
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -541,6 +648,35 @@ mod tests {
         assert!(SgxQuote::read(&mut support.as_slice()).is_err());
     }
 
+    #[test]
+    fn read_v4_support() {
+        let v3_bytes = quote_support_bytes();
+        let v3 = SgxQuoteSupport::read(&mut v3_bytes.as_slice()).unwrap();
+
+        let v4_bytes = v3_support_to_v4(&v3_bytes);
+        let mut v4_slice = v4_bytes.as_slice();
+        let v4 = SgxQuoteSupport::read_v4(&mut v4_slice).unwrap();
+        assert!(v4_slice.is_empty());
+
+        assert_eq!(v3.attest_pub_key, v4.attest_pub_key);
+        assert_eq!(v3.auth_data, v4.auth_data);
+        assert_eq!(
+            v3.qe_report_body.sgx_report_data_bytes,
+            v4.qe_report_body.sgx_report_data_bytes
+        );
+        v4.verify_signature(&v4.pck_cert_chain.leaf_pub_key().unwrap())
+            .expect("QE report should be signed by pck cert");
+        v4.verify_qe_report().expect("QE report should be valid");
+    }
+
+    #[test]
+    fn read_v4_support_requires_qe_report_certification_data() {
+        let mut v4_bytes = v3_support_to_v4(&quote_support_bytes());
+        // the certification data type follows the ISV signature and attest key
+        v4_bytes[128] = CertificationKeyType::PckCertChain as u8;
+        assert!(SgxQuoteSupport::read_v4(&mut v4_bytes.as_slice()).is_err());
+    }
+
     fn quote_bytes() -> Vec<u8> {
         fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/dcap.evidence"))
             .expect("failed to read file")
@@ -0,0 +1,362 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Implements DCAP attestation verification for TDX trust domains.
+//!
+//! TDX quotes are produced by a TD quoting enclave and certified by the same PCK certificate chain
+//! as SGX quotes, so verification follows the same steps as for SGX with a few additions:
+//! - the quoting enclave identity must be the TD quoting enclave
+//! - the TDX module that launched the trust domain must match the one in the TCB info
+//! - the TCB level must also cover the TEE tcb svn from the TD report
+// https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use boring::pkey::{PKeyRef, Public};
+use hex::ToHex;
+
+use crate::dcap::endorsements::{
+    EnclaveType, SgxEndorsements, TcbInfo, TcbInfoId, TcbStatus, TdxModule,
+};
+use crate::dcap::evidence::TdxEvidence;
+use crate::dcap::tdx_quote::{TdMeasurements, TdReportBody, TdxMeasurement};
+use crate::dcap::{
+    verify_certificates, verify_claims_hash, verify_enclave_signatures, verify_enclave_source,
+    verify_expiration, verify_sw_advisories, verify_tcb_status, Error, Result, TcbStanding,
+    INTEL_PKEY,
+};
+use crate::enclave::AttestationError;
+use crate::error::Context;
+
+/// Returns a `Result` containing a map of claims extracted from the evidence when successful,
+/// or an attestation verification error when not
+///
+/// * `expected_measurements` - The MRTD, MRCONFIGID, and RTMRs that the TD report must match
+/// * `acceptable_sw_advisories` - In the event that the remote TCB has known vulnerabilities that
+///                                require SW mitigations, the list of vulnerabilities that are
+///                                known to be mitigated in the expected trust domain.
+/// * `current_time` - The current system time
+pub fn verify_remote_attestation(
+    evidence_bytes: &[u8],
+    endorsement_bytes: &[u8],
+    expected_measurements: &TdMeasurements,
+    acceptable_sw_advisories: &[&str],
+    current_time: SystemTime,
+) -> std::result::Result<HashMap<String, Vec<u8>>, AttestationError> {
+    let attestation = attest(evidence_bytes, endorsement_bytes, current_time)?;
+
+    verify_sw_advisories(&attestation.tcb_standing, acceptable_sw_advisories)?;
+    verify_measurements(expected_measurements, &attestation.measurements)?;
+
+    Ok(attestation.claims)
+}
+
+/// Trust domain information returned by an intel-trusted
+/// quoting enclave. The receiver must check that the trust domain:
+/// - is running the expected image (via `measurements`)
+/// - has an up to date tcb OR has acceptable SW advisories
+#[derive(Debug)]
+pub(crate) struct TdxAttestation {
+    tcb_standing: TcbStanding,
+    measurements: TdMeasurements,
+    claims: HashMap<String, Vec<u8>>,
+}
+
+/// Verify that every measurement in the TD report matches the expected one
+fn verify_measurements(expected: &TdMeasurements, actual: &TdMeasurements) -> Result<()> {
+    fn check(name: &str, expected: &TdxMeasurement, actual: &TdxMeasurement) -> Result<()> {
+        if expected != actual {
+            return Err(Error::new(format!(
+                "expected {name} {}, was {}",
+                expected.encode_hex::<String>(),
+                actual.encode_hex::<String>(),
+            )));
+        }
+        Ok(())
+    }
+
+    check("mrtd", &expected.mrtd, &actual.mrtd)?;
+    check("mrconfigid", &expected.mrconfigid, &actual.mrconfigid)?;
+    for (i, (expected_rtmr, actual_rtmr)) in expected.rtmrs.iter().zip(&actual.rtmrs).enumerate() {
+        check(&format!("rtmr{i}"), expected_rtmr, actual_rtmr)?;
+    }
+    Ok(())
+}
+
+fn attest(
+    evidence_bytes: &[u8],
+    endorsement_bytes: &[u8],
+    current_time: SystemTime,
+) -> Result<TdxAttestation> {
+    let evidence = TdxEvidence::try_from(evidence_bytes).context("evidence")?;
+    let endorsements = SgxEndorsements::try_from(endorsement_bytes).context("endorsements")?;
+    attest_impl(evidence, endorsements, &INTEL_PKEY, current_time)
+}
+
+pub(crate) fn attest_impl(
+    evidence: TdxEvidence,
+    endorsements: SgxEndorsements,
+    trusted_root_pkey: &PKeyRef<Public>,
+    current_time: SystemTime,
+) -> Result<TdxAttestation> {
+    verify_expiration(current_time, &evidence).context("evidence")?;
+    verify_expiration(current_time, &endorsements).context("endorsements")?;
+    verify_certificates(
+        trusted_root_pkey,
+        &evidence.quote.support,
+        &endorsements,
+        current_time,
+    )?;
+
+    verify_enclave_source(
+        &evidence.quote.quote_body.qe_vendor_id,
+        &evidence.quote.support,
+        &endorsements,
+        EnclaveType::TdQe,
+    )?;
+    verify_enclave_signatures(&evidence.quote, &evidence.quote.support)?;
+
+    let report = &evidence.quote.quote_body.report_body;
+    verify_tdx_module(report, &endorsements.tcb_info)?;
+    let tcb_standing = verify_tcb_status(
+        &evidence.quote.support.pck_extension,
+        &endorsements,
+        TcbInfoId::Tdx,
+        Some(&report.tee_tcb_svn),
+    )?;
+
+    verify_claims_hash(&evidence.claims, &report.report_data)?;
+
+    // As with SGX, a debuggable trust domain can't keep secrets from the host
+    if report.is_debug() {
+        return Err(Error::new("Trust domain in debug mode"));
+    }
+
+    Ok(TdxAttestation {
+        tcb_standing,
+        measurements: TdMeasurements::from_report(report),
+        claims: evidence.claims.map,
+    })
+}
+
+/// Verify that the TD was launched by a TDX module whose identity is in the [`TcbInfo`]
+///
+/// For major version 0 this is the `tdxModule`; later versions are listed in
+/// `tdxModuleIdentities`, which also record whether the module itself is up to date.
+fn verify_tdx_module(report: &TdReportBody, tcb_info: &TcbInfo) -> Result<()> {
+    let module_version = report.tdx_module_version();
+    let expected_module: &TdxModule = if module_version == 0 {
+        tcb_info
+            .tdx_module
+            .as_ref()
+            .ok_or_else(|| Error::new("tcb info is missing the TDX module"))?
+    } else {
+        let id = format!("TDX_{:02}", module_version);
+        let identity = tcb_info
+            .tdx_module_identities
+            .iter()
+            .find(|identity| identity.id == id)
+            .ok_or_else(|| Error::new(format!("tcb info has no identity for {id}")))?;
+        let status = identity.tcb_status(report.tdx_module_isvsvn());
+        if status != TcbStatus::UpToDate {
+            return Err(Error::new(format!(
+                "TDX module tcb not up to date (was {:?})",
+                status
+            )));
+        }
+        &identity.module
+    };
+
+    if report.mrsignerseam != expected_module.mrsigner {
+        return Err(Error::new(format!(
+            "TDX module mrsigner mismatch: expected {}, actual {}",
+            hex::encode(expected_module.mrsigner),
+            hex::encode(report.mrsignerseam)
+        )));
+    }
+
+    if report
+        .seam_attributes
+        .iter()
+        .zip(expected_module.attributes_mask)
+        .map(|(attribute, mask)| attribute & mask)
+        .ne(expected_module.attributes)
+    {
+        return Err(Error::new("TDX module attributes mismatch"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dcap::endorsements::{TcbLevel, TdxModuleIdentity, TdxModuleTcbLevel};
+    use crate::dcap::fakes::FakeTdxAttestation;
+
+    use super::*;
+
+    #[test]
+    fn fake_attestation() {
+        let attestation = FakeTdxAttestation::builder().sign().attest().unwrap();
+        assert_eq!(attestation.measurements, FakeTdxAttestation::MEASUREMENTS);
+        assert!(attestation.claims.contains_key("pk"));
+        verify_measurements(&FakeTdxAttestation::MEASUREMENTS, &attestation.measurements).unwrap();
+    }
+
+    #[test]
+    fn mrtd_mismatch() {
+        let attestation = FakeTdxAttestation::builder().sign().attest().unwrap();
+        let mut expected = FakeTdxAttestation::MEASUREMENTS;
+        expected.mrtd[0] ^= 1;
+        assert!(verify_measurements(&expected, &attestation.measurements).is_err());
+    }
+
+    #[test]
+    fn mrconfigid_mismatch() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.quote_body.report_body.mrconfigid = [0; 48];
+        let attestation = builder.sign().attest().unwrap();
+        assert_eq!(attestation.measurements.mrtd, FakeTdxAttestation::MRTD);
+        assert!(
+            verify_measurements(&FakeTdxAttestation::MEASUREMENTS, &attestation.measurements)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rtmr_mismatch() {
+        for i in 0..4 {
+            let mut builder = FakeTdxAttestation::builder();
+            builder.quote_body.report_body.rtmrs[i][47] ^= 1;
+            let attestation = builder.sign().attest().unwrap();
+            assert_eq!(attestation.measurements.mrtd, FakeTdxAttestation::MRTD);
+            let err =
+                verify_measurements(&FakeTdxAttestation::MEASUREMENTS, &attestation.measurements)
+                    .expect_err("RTMR differs");
+            assert!(err.to_string().contains(&format!("rtmr{i}")), "{err}");
+        }
+    }
+
+    #[test]
+    fn debug_attribute() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.quote_body.report_body.td_attributes[0] |= 0x1;
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn sgx_quoting_enclave() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.sgx.uendorsements.qe_id_info.id = EnclaveType::Qe;
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn sgx_tcb_info() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.sgx.uendorsements.tcb_info.id = TcbInfoId::Sgx;
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn tdx_module_mrsigner_mismatch() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.quote_body.report_body.mrsignerseam = [1; 48];
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn tdx_module_attributes_mismatch() {
+        let mut builder = FakeTdxAttestation::builder();
+        builder.quote_body.report_body.seam_attributes[0] = 1;
+        assert!(builder.sign().attest().is_err());
+
+        // but not if they're masked off
+        let mut builder = FakeTdxAttestation::builder();
+        builder.quote_body.report_body.seam_attributes[0] = 1;
+        let module = builder
+            .sgx
+            .uendorsements
+            .tcb_info
+            .tdx_module
+            .as_mut()
+            .unwrap();
+        module.attributes_mask[0] = 0;
+        builder.sign().attest().unwrap();
+    }
+
+    #[test]
+    fn tee_tcb_svn_below_tcb_level() {
+        let mut builder = FakeTdxAttestation::builder();
+        for level in &mut builder.sgx.uendorsements.tcb_info.tcb_levels {
+            *level = TcbLevel::from_parts_tdx(
+                level.tcb.components(),
+                level.tcb.pcesvn(),
+                [1; 16],
+                level.tcb_status,
+            );
+        }
+        builder.quote_body.report_body.tee_tcb_svn = [0; 16];
+        assert!(builder.sign().attest().is_err());
+    }
+
+    fn with_module_identity(
+        builder: &mut crate::dcap::fakes::FakeTdxAttestationBuilder,
+        status: TcbStatus,
+    ) {
+        let module = builder
+            .sgx
+            .uendorsements
+            .tcb_info
+            .tdx_module
+            .take()
+            .unwrap();
+        builder.sgx.uendorsements.tcb_info.tdx_module_identities = vec![TdxModuleIdentity {
+            id: "TDX_01".to_owned(),
+            module,
+            tcb_levels: vec![TdxModuleTcbLevel::from_parts(status, 2)],
+        }];
+        // major version 1, minor version 3
+        builder.quote_body.report_body.tee_tcb_svn[0] = 3;
+        builder.quote_body.report_body.tee_tcb_svn[1] = 1;
+    }
+
+    #[test]
+    fn tdx_module_identity() {
+        let mut builder = FakeTdxAttestation::builder();
+        with_module_identity(&mut builder, TcbStatus::UpToDate);
+        builder.sign().attest().unwrap();
+    }
+
+    #[test]
+    fn tdx_module_identity_out_of_date() {
+        let mut builder = FakeTdxAttestation::builder();
+        with_module_identity(&mut builder, TcbStatus::OutOfDate);
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn tdx_module_identity_missing() {
+        let mut builder = FakeTdxAttestation::builder();
+        with_module_identity(&mut builder, TcbStatus::UpToDate);
+        builder.quote_body.report_body.tee_tcb_svn[1] = 2;
+        assert!(builder.sign().attest().is_err());
+    }
+
+    #[test]
+    fn sgx_quote_is_not_tdx_evidence() {
+        let evidence_bytes = include_bytes!("../../tests/data/dcap.evidence");
+        let endorsements_bytes = include_bytes!("../../tests/data/dcap.endorsements");
+        assert!(verify_remote_attestation(
+            evidence_bytes,
+            endorsements_bytes,
+            &FakeTdxAttestation::MEASUREMENTS,
+            &[],
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1674105089000),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! TDX quote (version 4 of the DCAP quote format).
+//!
+//! See <https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf> appendix A.3
+
+use boring::ecdsa::EcdsaSigRef;
+
+use std::intrinsics::transmute;
+use std::time::SystemTime;
+
+use crate::dcap::ecdsa::EcdsaSigned;
+use crate::dcap::sgx_quote::{bytes_of, SgxQuoteSupport};
+use crate::dcap::{Error, Expireable};
+use crate::endian::*;
+use crate::error::Context;
+use crate::util;
+
+const TDX_HASH_SIZE: usize = 48;
+
+/// The measurement of the initial contents of a trust domain
+pub type MrTd = [u8; TDX_HASH_SIZE];
+
+/// A software-defined configuration ID, or the value of a runtime measurement register
+pub type TdxMeasurement = [u8; TDX_HASH_SIZE];
+
+/// The measurements that identify the software running in a trust domain.
+///
+/// The MRTD only covers the TD firmware (TDVF), so on its own it would accept any kernel and
+/// configuration booted by that firmware. The RTMRs hold the measurements of what the firmware
+/// loaded (its configuration, the kernel and its command line, and the application), and the
+/// MRCONFIGID is set by the host when it launches the trust domain. MROWNER and MROWNERCONFIG
+/// identify who is running the trust domain rather than what it runs, so they are not included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TdMeasurements {
+    pub mrtd: MrTd,
+    pub mrconfigid: TdxMeasurement,
+    pub rtmrs: [TdxMeasurement; 4],
+}
+
+impl TdMeasurements {
+    /// The size of the encoded measurements: `MRTD || MRCONFIGID || RTMR0 || ... || RTMR3`
+    pub const ENCODED_SIZE: usize = 6 * TDX_HASH_SIZE;
+
+    pub(crate) fn from_report(report: &TdReportBody) -> Self {
+        Self {
+            mrtd: report.mrtd,
+            mrconfigid: report.mrconfigid,
+            rtmrs: report.rtmrs,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for TdMeasurements {
+    type Error = std::array::TryFromSliceError;
+
+    /// Decodes measurements encoded as `MRTD || MRCONFIGID || RTMR0 || ... || RTMR3`
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: &[u8; TdMeasurements::ENCODED_SIZE] = bytes.try_into()?;
+        let mut chunks = bytes
+            .chunks_exact(TDX_HASH_SIZE)
+            .map(|chunk| TdxMeasurement::try_from(chunk).expect("exact chunks"));
+        let mut next = || chunks.next().expect("six chunks");
+        Ok(Self {
+            mrtd: next(),
+            mrconfigid: next(),
+            rtmrs: [next(), next(), next(), next()],
+        })
+    }
+}
+
+pub(crate) struct TdxQuote<'a> {
+    /// The Quote Header (A.3.1) and the TD report (A.3.2)
+    pub quote_body: TdxQuoteBody,
+
+    /// Contains signatures, the quoting enclave report, and other
+    /// material for verifying `quote_body`. The "Quote Signature Data"
+    /// in A.3.8
+    pub support: SgxQuoteSupport<'a>,
+}
+
+impl<'a> TdxQuote<'a> {
+    /// Read a TdxQuote from the `bytes`, advancing bytes
+    /// by the number of bytes consumed
+    pub fn read(bytes: &mut &'a [u8]) -> super::Result<Self> {
+        if bytes.len() < std::mem::size_of::<TdxQuoteBody>() {
+            return Err(Error::new("incorrect buffer size"));
+        }
+
+        let quote_body = util::read_array::<{ std::mem::size_of::<TdxQuoteBody>() }>(bytes);
+        let quote_body = TdxQuoteBody::try_from(quote_body)?;
+
+        if bytes.len() < std::mem::size_of::<u32>() {
+            return Err(Error::new("underflow reading signature length"));
+        }
+        let signature_len = util::read_u32_le(bytes) as usize;
+        if bytes.len() < signature_len {
+            return Err(Error::new("underflow reading signature"));
+        }
+        let mut signature_data = util::read_bytes(bytes, signature_len);
+        let support = SgxQuoteSupport::read_v4(&mut signature_data).context("signature data")?;
+        if !signature_data.is_empty() {
+            return Err(Error::new("unexpected extra data in signature"));
+        }
+
+        Ok(TdxQuote {
+            quote_body,
+            support,
+        })
+    }
+}
+
+/// Verifies the signature of the quote header + TD report, which must be signed
+/// by the quoting enclave attest key
+impl<'a> EcdsaSigned for TdxQuote<'a> {
+    fn data(&self) -> &[u8] {
+        unsafe { bytes_of(&self.quote_body) }
+    }
+
+    fn signature(&self) -> &EcdsaSigRef {
+        &self.support.isv_signature
+    }
+}
+
+impl<'a> Expireable for TdxQuote<'a> {
+    fn valid_at(&self, timestamp: SystemTime) -> bool {
+        // quote_body is not expireable
+        self.support.valid_at(timestamp)
+    }
+}
+
+/// The version of the TDX Quote (A.3.1)
+const QUOTE_V4: u16 = 4;
+
+/// `tee_type` for TDX; SGX quotes use 0
+const TEE_TYPE_TDX: u32 = 0x81;
+
+/// ECDSA-256-with-P-256 curve, the only attestation key type we support
+const ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+
+#[derive(Debug)]
+#[repr(C)]
+pub(crate) struct TdxQuoteBody {
+    /* (0) */
+    version: UInt16LE,
+    /* (2) */
+    attestation_key_type: UInt16LE,
+    /* (4) */
+    tee_type: UInt32LE,
+    /* (8) */
+    _reserved1: [u8; 2],
+    /* (10) */
+    _reserved2: [u8; 2],
+    /* (12) */
+    pub qe_vendor_id: [u8; 16],
+    /* (28) */
+    _user_data: [u8; 20],
+    /* (48) */
+    pub report_body: TdReportBody,
+    /* (632) */
+}
+
+static_assertions::const_assert_eq!(1, std::mem::align_of::<TdxQuoteBody>());
+static_assertions::const_assert_eq!(632, std::mem::size_of::<TdxQuoteBody>());
+
+impl TryFrom<[u8; std::mem::size_of::<TdxQuoteBody>()]> for TdxQuoteBody {
+    type Error = super::Error;
+
+    fn try_from(bytes: [u8; std::mem::size_of::<TdxQuoteBody>()]) -> super::Result<Self> {
+        let quote_body: TdxQuoteBody = unsafe { transmute(bytes) };
+        if quote_body.version.value() != QUOTE_V4 {
+            return Err(Error::new(format!(
+                "unsupported TDX quote version: {}",
+                quote_body.version.value(),
+            )));
+        }
+        if quote_body.tee_type.value() != TEE_TYPE_TDX {
+            return Err(Error::new(format!(
+                "unsupported TEE type: {:#x}",
+                quote_body.tee_type.value(),
+            )));
+        }
+        if quote_body.attestation_key_type.value() != ATTESTATION_KEY_TYPE_ECDSA_P256 {
+            return Err(Error::new(format!(
+                "unsupported TDX attestation algorithm: {}",
+                quote_body.attestation_key_type.value(),
+            )));
+        }
+
+        Ok(quote_body)
+    }
+}
+
+/// The TD report body (A.3.2)
+#[derive(Debug)]
+#[repr(C)]
+pub(crate) struct TdReportBody {
+    /// (0) Security version numbers of the TDX module and other TCB components
+    pub tee_tcb_svn: [u8; 16],
+    /// (16) Measurement of the TDX module
+    pub mrseam: [u8; TDX_HASH_SIZE],
+    /// (64) Measurement of the TDX module's signer
+    pub mrsignerseam: [u8; TDX_HASH_SIZE],
+    /// (112) Attributes of the TDX module
+    pub seam_attributes: [u8; 8],
+    /// (120) Attributes of the trust domain
+    pub td_attributes: [u8; 8],
+    /// (128) CPU extended features enabled for the trust domain
+    _xfam: [u8; 8],
+    /// (136) Measurement of the initial contents of the trust domain
+    pub mrtd: MrTd,
+    /// (184) Software-defined configuration ID
+    pub mrconfigid: TdxMeasurement,
+    /// (232) Software-defined ID for the owner of the trust domain
+    _mrowner: [u8; TDX_HASH_SIZE],
+    /// (280) Software-defined owner configuration
+    _mrownerconfig: [u8; TDX_HASH_SIZE],
+    /// (328) Runtime-extendable measurement registers
+    pub rtmrs: [TdxMeasurement; 4],
+    /// (520) User report data
+    pub report_data: [u8; 64],
+    /* (584) */
+}
+
+static_assertions::const_assert_eq!(1, std::mem::align_of::<TdReportBody>());
+static_assertions::const_assert_eq!(584, std::mem::size_of::<TdReportBody>());
+
+impl TdReportBody {
+    /// Whether the trust domain can be inspected by the host (the TUD.DEBUG attribute)
+    pub fn is_debug(&self) -> bool {
+        self.td_attributes[0] & 0x1 != 0
+    }
+
+    /// The major version of the TDX module
+    pub fn tdx_module_version(&self) -> u8 {
+        self.tee_tcb_svn[1]
+    }
+
+    /// The security version of the TDX module, within its major version
+    pub fn tdx_module_isvsvn(&self) -> u8 {
+        self.tee_tcb_svn[0]
+    }
+}
+
+/// Builds an unsigned TDX quote body with the given report body fields set.
+#[cfg(test)]
+pub(crate) fn fake_quote_body(
+    qe_vendor_id: [u8; 16],
+    tee_tcb_svn: [u8; 16],
+    mrtd: MrTd,
+) -> TdxQuoteBody {
+    let mut bytes = [0u8; std::mem::size_of::<TdxQuoteBody>()];
+    bytes[0..2].copy_from_slice(&QUOTE_V4.to_le_bytes());
+    bytes[2..4].copy_from_slice(&ATTESTATION_KEY_TYPE_ECDSA_P256.to_le_bytes());
+    bytes[4..8].copy_from_slice(&TEE_TYPE_TDX.to_le_bytes());
+    let mut body = TdxQuoteBody::try_from(bytes).expect("valid header");
+    body.qe_vendor_id = qe_vendor_id;
+    body.report_body.tee_tcb_svn = tee_tcb_svn;
+    body.report_body.mrtd = mrtd;
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dcap::sgx_quote::v3_support_to_v4;
+
+    /// The "Quote Signature Data" from the SGX test evidence, rewrapped for a version 4 quote.
+    fn v4_support_bytes() -> Vec<u8> {
+        const EVIDENCE: &[u8] = include_bytes!("../../tests/data/dcap.evidence");
+        // the SGX quote body is 432 bytes, followed by the signature length
+        let mut slice = &EVIDENCE[432..];
+        let signature_len = util::read_u32_le(&mut slice);
+        v3_support_to_v4(&slice[..signature_len as usize])
+    }
+
+    fn quote_bytes(body: &TdxQuoteBody) -> Vec<u8> {
+        let support = v4_support_bytes();
+        let mut bytes = unsafe { bytes_of(body) }.to_vec();
+        bytes.extend(u32::try_from(support.len()).unwrap().to_le_bytes());
+        bytes.extend(support);
+        bytes
+    }
+
+    #[test]
+    fn read_quote() {
+        let body = fake_quote_body([1; 16], [2; 16], [3; 48]);
+        let bytes = quote_bytes(&body);
+        let mut slice = bytes.as_slice();
+        let quote = TdxQuote::read(&mut slice).expect("can parse");
+        assert!(slice.is_empty());
+
+        assert_eq!(quote.quote_body.qe_vendor_id, [1; 16]);
+        assert_eq!(quote.quote_body.report_body.tee_tcb_svn, [2; 16]);
+        assert_eq!(quote.quote_body.report_body.mrtd, [3; 48]);
+        quote
+            .support
+            .verify_qe_report()
+            .expect("QE report should be valid");
+    }
+
+    #[test]
+    fn reject_sgx_tee_type() {
+        let body = fake_quote_body([1; 16], [2; 16], [3; 48]);
+        let mut bytes = quote_bytes(&body);
+        bytes[4] = 0;
+        assert!(TdxQuote::read(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn reject_v3_quote() {
+        const EVIDENCE: &[u8] = include_bytes!("../../tests/data/dcap.evidence");
+        assert!(TdxQuote::read(&mut &EVIDENCE[..]).is_err());
+    }
+
+    #[test]
+    fn reject_truncated_signature() {
+        let body = fake_quote_body([1; 16], [2; 16], [3; 48]);
+        let bytes = quote_bytes(&body);
+        let mut truncated = &bytes[..bytes.len() - 1];
+        assert!(TdxQuote::read(&mut truncated).is_err());
+    }
+}
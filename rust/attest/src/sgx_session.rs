@@ -21,7 +21,7 @@ const INVALID_MRENCLAVE: &str = "MREnclave value does not fit expected format";
 
 /// How much to offset when checking for time-based validity checks
 /// to adjust for clock skew on clients
-const SKEW_ADJUSTMENT: Duration = Duration::from_secs(24 * 60 * 60);
+pub(crate) const SKEW_ADJUSTMENT: Duration = Duration::from_secs(24 * 60 * 60);
 
 impl Handshake {
     pub(crate) fn for_sgx(
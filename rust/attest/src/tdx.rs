@@ -0,0 +1,123 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Supports creating a noise encrypted message channel to a remote TDX trust domain.
+//!
+//! This works like [`crate::sgx_session`], except the evidence is a TDX quote and the enclave is
+//! identified by its [`TdMeasurements`] rather than an MRENCLAVE: the MRTD, MRCONFIGID, and RTMRs,
+//! concatenated in that order.
+use std::time::SystemTime;
+
+use prost::Message;
+
+use crate::constants::{ACCEPTABLE_SW_ADVISORIES, DEFAULT_SW_ADVISORIES};
+use crate::dcap::{self, TdMeasurements};
+use crate::enclave::{Claims, Error, Handshake, Result, UnvalidatedHandshake};
+use crate::proto::svr;
+use crate::sgx_session::SKEW_ADJUSTMENT;
+use crate::svr2::RaftConfig;
+
+const INVALID_EVIDENCE: &str = "Evidence does not fit expected format";
+const INVALID_ENDORSEMENT: &str = "Endorsement does not fit expected format";
+const INVALID_MEASUREMENTS: &str = "TD measurements value does not fit expected format";
+
+pub fn new_handshake(
+    measurements: &[u8],
+    attestation_msg: &[u8],
+    current_time: SystemTime,
+    expected_raft_config: &'static RaftConfig,
+) -> Result<Handshake> {
+    let handshake_start = svr::ClientHandshakeStart::decode(attestation_msg)?;
+    Handshake::for_tdx(
+        measurements,
+        &handshake_start.evidence,
+        &handshake_start.endorsement,
+        ACCEPTABLE_SW_ADVISORIES
+            .get(&measurements)
+            .unwrap_or(&DEFAULT_SW_ADVISORIES),
+        current_time,
+    )?
+    .validate(expected_raft_config)
+}
+
+impl Handshake {
+    pub(crate) fn for_tdx(
+        measurements: &[u8],
+        evidence: &[u8],
+        endorsements: &[u8],
+        acceptable_sw_advisories: &[&str],
+        current_time: SystemTime,
+    ) -> Result<UnvalidatedHandshake> {
+        if evidence.is_empty() {
+            return Err(Error::AttestationDataError {
+                reason: String::from(INVALID_EVIDENCE),
+            });
+        }
+        if endorsements.is_empty() {
+            return Err(Error::AttestationDataError {
+                reason: String::from(INVALID_ENDORSEMENT),
+            });
+        }
+
+        let measurements =
+            TdMeasurements::try_from(measurements).map_err(|_| Error::AttestationDataError {
+                reason: String::from(INVALID_MEASUREMENTS),
+            })?;
+
+        // verify the remote attestation and extract the custom claims
+        let claims = dcap::tdx::verify_remote_attestation(
+            evidence,
+            endorsements,
+            &measurements,
+            acceptable_sw_advisories,
+            current_time + SKEW_ADJUSTMENT,
+        )?;
+
+        Self::with_claims(Claims::from_custom_claims(claims)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::sgx_session::testutil;
+
+    #[test]
+    fn rejects_short_measurements() {
+        // an MRENCLAVE is too short to be an MRTD
+        let result = Handshake::for_tdx(
+            &testutil::mrenclave_bytes(),
+            testutil::EVIDENCE_BYTES,
+            testutil::ENDORSEMENT_BYTES,
+            &[],
+            testutil::valid_start(),
+        );
+        assert_matches!(result, Err(Error::AttestationDataError { reason }) if reason == INVALID_MEASUREMENTS);
+
+        // and an MRTD alone doesn't pin the rest of the trust domain
+        let result = Handshake::for_tdx(
+            &[0; 48],
+            testutil::EVIDENCE_BYTES,
+            testutil::ENDORSEMENT_BYTES,
+            &[],
+            testutil::valid_start(),
+        );
+        assert_matches!(result, Err(Error::AttestationDataError { reason }) if reason == INVALID_MEASUREMENTS);
+    }
+
+    #[test]
+    fn rejects_sgx_evidence() {
+        let result = Handshake::for_tdx(
+            &[0; TdMeasurements::ENCODED_SIZE],
+            testutil::EVIDENCE_BYTES,
+            testutil::ENDORSEMENT_BYTES,
+            &[],
+            testutil::valid_start(),
+        );
+        assert_matches!(result, Err(Error::AttestationError(_)));
+    }
+}
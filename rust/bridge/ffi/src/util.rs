@@ -152,9 +152,8 @@ impl From<&SignalFfiError> for SignalErrorCode {
 
             SignalFfiError::Pin(PinError::Argon2Error(_))
             | SignalFfiError::Pin(PinError::DecodingError(_))
-            | SignalFfiError::Pin(PinError::MrenclaveLookupError) => {
-                SignalErrorCode::InvalidArgument
-            }
+            | SignalFfiError::Pin(PinError::MrenclaveLookupError)
+            | SignalFfiError::Pin(PinError::WeakParams) => SignalErrorCode::InvalidArgument,
 
             SignalFfiError::Signal(SignalProtocolError::SessionNotFound(_))
             | SignalFfiError::Signal(SignalProtocolError::NoSenderKeyState { .. }) => {
@@ -282,6 +281,10 @@ impl From<&SignalFfiError> for SignalErrorCode {
                 SignalErrorCode::UsernameDiscriminatorTooLarge
             }
 
+            SignalFfiError::UsernameError(UsernameError::InvalidDiscriminatorRange) => {
+                SignalErrorCode::InvalidArgument
+            }
+
             SignalFfiError::UsernameProofError(usernames::ProofVerificationFailure) => {
                 SignalErrorCode::VerificationFailure
             }
@@ -327,6 +330,19 @@ impl From<&SignalFfiError> for SignalErrorCode {
                     }
                 }
             }
+
+            #[cfg(feature = "signal-media")]
+            SignalFfiError::GifSanitizeParse(err) => {
+                use signal_media::sanitize::gif::ParseError;
+                match err.kind {
+                    ParseError::InvalidBlockLayout
+                    | ParseError::InvalidInput
+                    | ParseError::MissingRequiredBlock(_)
+                    | ParseError::TruncatedBlock => SignalErrorCode::InvalidMediaInput,
+
+                    ParseError::UnsupportedVersion => SignalErrorCode::UnsupportedMediaInput,
+                }
+            }
             SignalFfiError::WebSocket(_) => SignalErrorCode::WebSocket,
             SignalFfiError::ConnectionTimedOut => SignalErrorCode::ConnectionTimedOut,
             SignalFfiError::ConnectionFailed => SignalErrorCode::ConnectionFailed,
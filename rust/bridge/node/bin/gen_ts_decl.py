@@ -64,6 +64,7 @@ def translate_to_ts(typ):
         "E164": "string",
         "ServiceIdSequence<'_>": "Buffer",
         "PathAndQuery": "string",
+        "&dynMakeChatListener": "ChatListener",
     }
 
     if typ in type_map:
@@ -101,6 +102,9 @@ def translate_to_ts(typ):
         assert typ.endswith('>')
         return translate_to_ts(typ[7:-1]) + ' | null'
 
+    if typ.startswith('std::result::Result<'):
+        return translate_to_ts(typ[len('std::result::'):])
+
     if typ.startswith('Result<'):
         assert typ.endswith('>')
         if ',' in typ:
@@ -156,10 +156,16 @@ fn bridge_io_body(
                 // Wrap the actual work to catch any panics.
                 let __future = jni::catch_unwind(std::panic::AssertUnwindSafe(async {
                     #(#input_loading)*
-                    let __result = #orig_name(#(#input_names),*).await;
-                    // If the original function can't fail, wrap the result in Ok for uniformity.
-                    // See TransformHelper::ok_if_needed.
-                    Ok(TransformHelper(__result).ok_if_needed()?.0)
+                    ::tokio::select! {
+                        __result = #orig_name(#(#input_names),*) => {
+                            // If the original function can't fail, wrap the result in Ok for uniformity.
+                            // See TransformHelper::ok_if_needed.
+                            Ok(TransformHelper(__result).ok_if_needed()?.0)
+                        }
+                        _ = __cancel => {
+                            Err(jni::SignalJniError::Cancelled)
+                        }
+                    }
                 }));
                 // Pass the stored inputs to the reporter to drop them while attached to the JVM.
 
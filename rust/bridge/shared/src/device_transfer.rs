@@ -3,6 +3,12 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::collections::VecDeque;
+use std::io::{Read as _, Write as _};
+
+use ::device_transfer::archive::{
+    ArchiveError, ArchiveReader, ArchiveWriter, ARCHIVE_KEY_LEN, HEADER_LEN, TAG_LEN,
+};
 use ::device_transfer::{self, KeyFormat};
 use libsignal_bridge_macros::*;
 
@@ -27,6 +33,11 @@ fn DeviceTransfer_GeneratePrivateKeyWithFormat(
     device_transfer::create_rsa_private_key(DEVICE_TRANSFER_KEY_BITS, KeyFormat::from(key_format))
 }
 
+#[bridge_fn(node = false)]
+fn DeviceTransfer_GenerateEd25519PrivateKey() -> Vec<u8> {
+    device_transfer::create_ed25519_private_key().expect("no internal failures")
+}
+
 #[bridge_fn(node = false)]
 fn DeviceTransfer_GenerateCertificate(
     private_key: &[u8],
@@ -35,3 +46,132 @@ fn DeviceTransfer_GenerateCertificate(
 ) -> Result<Vec<u8>, device_transfer::Error> {
     device_transfer::create_self_signed_cert(private_key, &name, days_to_expire)
 }
+
+pub struct DeviceTransferArchiveWriter(Option<ArchiveWriter<Vec<u8>>>);
+
+bridge_handle!(DeviceTransferArchiveWriter, clone = false, mut = true, node = false);
+
+/// Starts an archive with a payload of `total_length` bytes.
+///
+/// The header is returned from the first call to [`DeviceTransfer_ArchiveWriterUpdate`] (or
+/// [`DeviceTransfer_ArchiveWriterFinish`], if the payload is empty).
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveWriterNew(
+    key: &[u8; 32],
+    total_length: u64,
+) -> Result<DeviceTransferArchiveWriter, ArchiveError> {
+    Ok(DeviceTransferArchiveWriter(Some(ArchiveWriter::new(
+        Vec::new(),
+        key,
+        total_length,
+    )?)))
+}
+
+/// Encrypts the next part of the payload, returning whatever archive bytes are ready.
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveWriterUpdate(
+    writer: &mut DeviceTransferArchiveWriter,
+    bytes: &[u8],
+    offset: u32,
+    length: u32,
+) -> Result<Vec<u8>, ArchiveError> {
+    let writer = writer.0.as_mut().expect("archive writer used after finish");
+    writer.write_all(&bytes[offset as usize..][..length as usize])?;
+    Ok(std::mem::take(writer.get_mut()))
+}
+
+/// Checks that the whole payload has been written, returning the rest of the archive.
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveWriterFinish(
+    writer: &mut DeviceTransferArchiveWriter,
+) -> Result<Vec<u8>, ArchiveError> {
+    writer
+        .0
+        .take()
+        .expect("archive writer used after finish")
+        .finish()
+}
+
+pub enum DeviceTransferArchiveReader {
+    /// Waiting for the rest of the header.
+    Header {
+        key: [u8; ARCHIVE_KEY_LEN],
+        received: Vec<u8>,
+    },
+    Payload(ArchiveReader<VecDeque<u8>>),
+    Finished,
+}
+
+bridge_handle!(DeviceTransferArchiveReader, clone = false, mut = true, node = false);
+
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveReaderNew(key: &[u8; 32]) -> DeviceTransferArchiveReader {
+    DeviceTransferArchiveReader::Header {
+        key: *key,
+        received: Vec::with_capacity(HEADER_LEN),
+    }
+}
+
+/// Adds the next part of the archive, returning the payload of every chunk that is now complete.
+///
+/// Nothing is returned until a whole chunk has been received and authenticated.
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveReaderUpdate(
+    reader: &mut DeviceTransferArchiveReader,
+    bytes: &[u8],
+    offset: u32,
+    length: u32,
+) -> Result<Vec<u8>, ArchiveError> {
+    let bytes = &bytes[offset as usize..][..length as usize];
+    match &mut *reader {
+        DeviceTransferArchiveReader::Header { key, received } => {
+            received.extend_from_slice(bytes);
+            if received.len() < HEADER_LEN {
+                return Ok(vec![]);
+            }
+            let received = VecDeque::from(std::mem::take(received));
+            let archive_reader = ArchiveReader::new(received, key)?;
+            *reader = DeviceTransferArchiveReader::Payload(archive_reader);
+        }
+        DeviceTransferArchiveReader::Payload(archive_reader) => {
+            if archive_reader.next_sealed_chunk_len().is_none() && !bytes.is_empty() {
+                return Err(ArchiveError::LengthMismatch);
+            }
+            archive_reader.get_mut().extend(bytes);
+        }
+        DeviceTransferArchiveReader::Finished => {}
+    }
+    let DeviceTransferArchiveReader::Payload(archive_reader) = reader else {
+        panic!("archive reader used after finish")
+    };
+
+    let mut payload = vec![];
+    while let Some(sealed_len) = archive_reader.next_sealed_chunk_len() {
+        if archive_reader.get_mut().len() < sealed_len {
+            break;
+        }
+        let chunk_len = (sealed_len - TAG_LEN) as u64;
+        (&mut *archive_reader)
+            .take(chunk_len)
+            .read_to_end(&mut payload)?;
+    }
+    Ok(payload)
+}
+
+/// Checks that the whole archive has been received.
+#[bridge_fn(node = false)]
+fn DeviceTransfer_ArchiveReaderFinish(
+    reader: &mut DeviceTransferArchiveReader,
+) -> Result<(), ArchiveError> {
+    match std::mem::replace(reader, DeviceTransferArchiveReader::Finished) {
+        DeviceTransferArchiveReader::Payload(archive_reader)
+            if archive_reader.next_sealed_chunk_len().is_none() =>
+        {
+            Ok(())
+        }
+        DeviceTransferArchiveReader::Header { .. } | DeviceTransferArchiveReader::Payload(_) => {
+            Err(ArchiveError::LengthMismatch)
+        }
+        DeviceTransferArchiveReader::Finished => panic!("archive reader used after finish"),
+    }
+}
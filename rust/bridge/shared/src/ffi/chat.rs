@@ -5,6 +5,8 @@
 
 use super::*;
 
+use libsignal_net::chat::ChatServiceError;
+
 use crate::net::chat::{ChatListener, MakeChatListener, ServerMessageAck};
 
 use std::ffi::{c_uchar, c_void};
@@ -16,6 +18,7 @@ type ReceivedIncomingMessage = extern "C" fn(
     cleanup: *mut ServerMessageAck,
 );
 type ReceivedQueueEmpty = extern "C" fn(ctx: *mut c_void);
+type ConnectionInterrupted = extern "C" fn(ctx: *mut c_void, error: *mut SignalFfiError);
 type DestroyChatListener = extern "C" fn(ctx: *mut c_void);
 
 /// Callbacks for [`ChatListener`].
@@ -29,6 +32,7 @@ pub struct FfiChatListenerStruct {
     ctx: *mut c_void,
     received_incoming_message: ReceivedIncomingMessage,
     received_queue_empty: ReceivedQueueEmpty,
+    connection_interrupted: ConnectionInterrupted,
     destroy: DestroyChatListener,
 }
 
@@ -73,4 +77,9 @@ impl ChatListener for ChatListenerStruct {
     fn received_queue_empty(&mut self) {
         (self.0.received_queue_empty)(self.0.ctx)
     }
+
+    fn connection_interrupted(&mut self, disconnect_cause: ChatServiceError) {
+        let error = Box::new(SignalFfiError::from(disconnect_cause));
+        (self.0.connection_interrupted)(self.0.ctx, Box::into_raw(error))
+    }
 }
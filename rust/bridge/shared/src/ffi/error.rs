@@ -5,9 +5,11 @@
 
 use std::fmt;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::time::Duration;
 
 use attest::enclave::Error as EnclaveError;
 use attest::hsm_enclave::Error as HsmEnclaveError;
+use device_transfer::archive::ArchiveError;
 use device_transfer::Error as DeviceTransferError;
 use libsignal_net::chat::ChatServiceError;
 use libsignal_net::infra::ws::{WebSocketConnectError, WebSocketServiceError};
@@ -53,6 +55,8 @@ pub enum SignalFfiError {
     Mp4SanitizeParse(signal_media::sanitize::mp4::ParseErrorReport),
     #[cfg(feature = "signal-media")]
     WebpSanitizeParse(signal_media::sanitize::webp::ParseErrorReport),
+    #[cfg(feature = "signal-media")]
+    GifSanitizeParse(signal_media::sanitize::gif::ParseErrorReport),
     NullPointer,
     InvalidUtf8String,
     InvalidArgument(String),
@@ -104,6 +108,10 @@ impl fmt::Display for SignalFfiError {
             SignalFfiError::WebpSanitizeParse(e) => {
                 write!(f, "WebP sanitizer failed to parse webp file: {}", e)
             }
+            #[cfg(feature = "signal-media")]
+            SignalFfiError::GifSanitizeParse(e) => {
+                write!(f, "GIF sanitizer failed to parse gif file: {}", e)
+            }
             SignalFfiError::NullPointer => write!(f, "null pointer"),
             SignalFfiError::InvalidUtf8String => write!(f, "invalid UTF8 string"),
             SignalFfiError::InvalidArgument(msg) => write!(f, "invalid argument: {msg}"),
@@ -128,6 +136,18 @@ impl From<DeviceTransferError> for SignalFfiError {
     }
 }
 
+impl From<ArchiveError> for SignalFfiError {
+    fn from(e: ArchiveError) -> SignalFfiError {
+        match e {
+            ArchiveError::PayloadTooLong => SignalFfiError::InvalidArgument(e.to_string()),
+            ArchiveError::InternalError(s) => {
+                SignalFfiError::DeviceTransfer(DeviceTransferError::InternalError(s))
+            }
+            _ => SignalFfiError::Io(e.into()),
+        }
+    }
+}
+
 impl From<HsmEnclaveError> for SignalFfiError {
     fn from(e: HsmEnclaveError) -> SignalFfiError {
         SignalFfiError::HsmEnclave(e)
@@ -206,6 +226,14 @@ impl From<IoError> for SignalFfiError {
     }
 }
 
+impl SignalFfiError {
+    fn rate_limited(retry_after: Duration) -> Self {
+        Self::RateLimited {
+            retry_after_seconds: retry_after.as_secs().try_into().unwrap_or(u32::MAX),
+        }
+    }
+}
+
 impl From<libsignal_net::cdsi::LookupError> for SignalFfiError {
     fn from(value: libsignal_net::cdsi::LookupError) -> Self {
         use libsignal_net::cdsi::LookupError;
@@ -214,18 +242,16 @@ impl From<libsignal_net::cdsi::LookupError> for SignalFfiError {
             LookupError::AttestationError(e) => SignalFfiError::Sgx(e),
             LookupError::ConnectTransport(e) => SignalFfiError::Io(e.into()),
             LookupError::WebSocket(e) => SignalFfiError::WebSocket(e),
-            LookupError::ConnectionTimedOut => SignalFfiError::ConnectionTimedOut,
+            LookupError::ConnectionTimedOut | LookupError::RequestTimedOut => {
+                SignalFfiError::ConnectionTimedOut
+            }
             LookupError::ParseError
             | LookupError::Protocol
             | LookupError::InvalidResponse
             | LookupError::Server { reason: _ } => {
                 SignalFfiError::NetworkProtocol(value.to_string())
             }
-            LookupError::RateLimited {
-                retry_after_seconds: retry_after,
-            } => SignalFfiError::RateLimited {
-                retry_after_seconds: retry_after,
-            },
+            LookupError::RateLimited { retry_after } => SignalFfiError::rate_limited(retry_after),
             LookupError::InvalidToken => SignalFfiError::CdsiInvalidToken,
             LookupError::InvalidArgument { server_reason: _ } => {
                 SignalFfiError::Signal(SignalProtocolError::InvalidArgument(value.to_string()))
@@ -243,9 +269,12 @@ impl From<Svr3Error> for SignalFfiError {
                 WebSocketConnectError::WebSocketError(e) => WebSocketServiceError::from(e).into(),
             },
             Svr3Error::Service(e) => SignalFfiError::WebSocket(e),
-            Svr3Error::ConnectionTimedOut => SignalFfiError::ConnectionTimedOut,
+            Svr3Error::ConnectionTimedOut | Svr3Error::RequestTimedOut => {
+                SignalFfiError::ConnectionTimedOut
+            }
             Svr3Error::AttestationError(inner) => SignalFfiError::Sgx(inner),
             Svr3Error::Protocol(inner) => SignalFfiError::NetworkProtocol(inner.to_string()),
+            Svr3Error::RateLimited { retry_after } => SignalFfiError::rate_limited(retry_after),
             Svr3Error::RequestFailed(_) | Svr3Error::RestoreFailed(_) | Svr3Error::DataMissing => {
                 SignalFfiError::Svr(err)
             }
@@ -277,6 +306,9 @@ impl From<ChatServiceError> for SignalFfiError {
             ChatServiceError::ServiceInactive => SignalFfiError::ChatServiceInactive,
             ChatServiceError::AppExpired => SignalFfiError::AppExpired,
             ChatServiceError::DeviceDeregistered => SignalFfiError::DeviceDeregistered,
+            ChatServiceError::RateLimited { retry_after } => {
+                SignalFfiError::rate_limited(retry_after)
+            }
         }
     }
 }
@@ -309,6 +341,17 @@ impl From<signal_media::sanitize::webp::Error> for SignalFfiError {
     }
 }
 
+#[cfg(feature = "signal-media")]
+impl From<signal_media::sanitize::gif::Error> for SignalFfiError {
+    fn from(e: signal_media::sanitize::gif::Error) -> SignalFfiError {
+        use signal_media::sanitize::gif::Error;
+        match e {
+            Error::Io(e) => Self::Io(e),
+            Error::Parse(e) => Self::GifSanitizeParse(e),
+        }
+    }
+}
+
 impl From<NullPointerError> for SignalFfiError {
     fn from(_: NullPointerError) -> SignalFfiError {
         SignalFfiError::NullPointer
@@ -10,8 +10,11 @@ use hmac::digest::typenum::Unsigned;
 use hmac::Hmac;
 
 use libsignal_bridge_macros::*;
-use libsignal_protocol::incremental_mac::{calculate_chunk_size, Incremental, Validating};
+use libsignal_protocol::incremental_mac::{
+    calculate_chunk_size, Incremental, StreamValidationError, Validating, ValidationCheckpoint,
+};
 
+use crate::io::{AsyncInput, InputStream};
 use crate::support::*;
 use crate::*;
 
@@ -77,6 +80,33 @@ pub fn ValidatingMac_Initialize(key: &[u8], chunk_size: u32, digests: &[u8]) ->
     ValidatingMac(Some(incremental.validating(macs)))
 }
 
+#[bridge_fn]
+async fn ValidatingMac_Resume(
+    key: &[u8],
+    chunk_size: u32,
+    digests: &[u8],
+    checkpoint: &[u8],
+    saved_input: &mut dyn InputStream,
+) -> Result<ValidatingMac, std::io::Error> {
+    let to_io_error = |e: StreamValidationError| match e {
+        StreamValidationError::Io(e) => e,
+        e @ (StreamValidationError::InvalidChunk { .. }
+        | StreamValidationError::InvalidCheckpoint) => {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+        }
+    };
+    let checkpoint = ValidationCheckpoint::deserialize(checkpoint).map_err(to_io_error)?;
+    let hmac =
+        Hmac::<Digest>::new_from_slice(key).expect("Should be able to create a new HMAC instance");
+    let incremental = Incremental::new(hmac, chunk_size as usize);
+    let macs = digests.chunks(<Digest as OutputSizeUser>::OutputSize::USIZE);
+    let validating = incremental
+        .resume_validating(macs, checkpoint, AsyncInput::new(saved_input, None))
+        .await
+        .map_err(to_io_error)?;
+    Ok(ValidatingMac(Some(validating)))
+}
+
 #[bridge_fn]
 pub fn ValidatingMac_Update(
     mac: &mut ValidatingMac,
@@ -95,6 +125,24 @@ pub fn ValidatingMac_Update(
         .unwrap_or(-1)
 }
 
+#[bridge_fn]
+pub fn ValidatingMac_ValidatedOffset(mac: &ValidatingMac) -> u64 {
+    mac.0
+        .as_ref()
+        .expect("MAC used after finalize")
+        .validated_offset()
+}
+
+#[bridge_fn]
+pub fn ValidatingMac_Checkpoint(mac: &ValidatingMac) -> Vec<u8> {
+    mac.0
+        .as_ref()
+        .expect("MAC used after finalize")
+        .checkpoint()
+        .serialize()
+        .to_vec()
+}
+
 #[bridge_fn]
 pub fn ValidatingMac_Finalize(mac: &mut ValidatingMac) -> i32 {
     mac.0
@@ -103,11 +103,11 @@ pub struct AsyncInput<'a> {
     stream: &'a dyn InputStream,
     state: AsyncInputState<'a>,
     pos: u64,
-    len: u64,
+    len: Option<u64>,
 }
 
 impl<'a> AsyncInput<'a> {
-    pub fn new(stream: &'a dyn InputStream, len: u64) -> Self {
+    pub fn new(stream: &'a dyn InputStream, len: Option<u64>) -> Self {
         Self {
             stream,
             state: AsyncInputState::default(),
@@ -150,7 +150,12 @@ impl Skip for SyncInput<'_> {
     }
 
     fn stream_len(&mut self) -> io::Result<u64> {
-        Ok(self.len.expect("stream length provided for this input"))
+        self.len.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "stream length not provided for this input",
+            )
+        })
     }
 }
 
@@ -231,6 +236,11 @@ impl AsyncSkip for AsyncInput<'_> {
     }
 
     fn poll_stream_len(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
-        Poll::Ready(Ok(self.len))
+        Poll::Ready(self.len.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "stream length not provided for this input",
+            )
+        }))
     }
 }
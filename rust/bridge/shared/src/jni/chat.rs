@@ -0,0 +1,117 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use super::*;
+
+use crate::net::chat::{ChatListener, MakeChatListener, ServerMessageAck};
+
+pub type JavaBridgeChatListener<'a> = JObject<'a>;
+
+/// Holds on to a Java `BridgeChatListener` so it can be used from any thread.
+pub struct JniMakeChatListener {
+    vm: JavaVM,
+    listener: GlobalRef,
+}
+
+impl JniMakeChatListener {
+    pub fn new(env: &mut JNIEnv, listener: &JObject) -> Result<Self, BridgeLayerError> {
+        check_jobject_type(
+            env,
+            listener,
+            ClassName("org.signal.libsignal.internal.BridgeChatListener"),
+        )?;
+        Ok(Self {
+            vm: env.get_java_vm()?,
+            listener: env.new_global_ref(listener)?,
+        })
+    }
+}
+
+impl MakeChatListener for JniMakeChatListener {
+    fn make_listener(&self) -> Box<dyn ChatListener> {
+        // SAFETY: the pointer came from a valid JavaVM, and JavaVMs are never destroyed.
+        let vm = unsafe { JavaVM::from_raw(self.vm.get_java_vm_pointer()) }
+            .expect("pointer is not null");
+        Box::new(JniChatListener {
+            vm,
+            listener: self.listener.clone(),
+        })
+    }
+}
+
+struct JniChatListener {
+    vm: JavaVM,
+    listener: GlobalRef,
+}
+
+impl JniChatListener {
+    /// Attaches the current thread to the JVM and runs `callback`, logging any failures.
+    ///
+    /// There's nobody to report errors to, since the listener is called from a background task.
+    fn attach_and_log_on_error(
+        &self,
+        operation: &'static str,
+        callback: impl FnOnce(&mut JNIEnv) -> Result<(), BridgeLayerError>,
+    ) {
+        let mut env = match self.vm.attach_current_thread() {
+            Ok(attach_guard) => attach_guard,
+            Err(e) => {
+                log::error!("failed to attach to JVM: {e}");
+                return;
+            }
+        };
+        if let Err(e) = env.with_local_frame(8, callback) {
+            log::error!("failed to call chat listener for {operation}: {e}");
+        }
+    }
+}
+
+impl ChatListener for JniChatListener {
+    fn received_incoming_message(
+        &mut self,
+        envelope: Vec<u8>,
+        timestamp: Timestamp,
+        ack: ServerMessageAck,
+    ) {
+        self.attach_and_log_on_error("incoming message", |env| {
+            let envelope = envelope.convert_into(env)?;
+            let timestamp = timestamp.convert_into(env)?;
+            let ack = ack.convert_into(env)?;
+            call_method_checked(
+                env,
+                &self.listener,
+                "onIncomingMessage",
+                jni_args!((envelope => [byte], timestamp => long, ack => long) -> void),
+            )
+        })
+    }
+
+    fn received_queue_empty(&mut self) {
+        self.attach_and_log_on_error("queue empty", |env| {
+            call_method_checked(env, &self.listener, "onQueueEmpty", jni_args!(() -> void))
+        })
+    }
+
+    fn connection_interrupted(&mut self, disconnect_cause: ChatServiceError) {
+        let listener = &self.listener;
+        self.attach_and_log_on_error("connection interrupted", |env| {
+            convert_to_exception(env, disconnect_cause.into(), |env, throwable, error| {
+                throwable
+                    .and_then(|throwable| {
+                        call_method_checked(
+                            env,
+                            listener,
+                            "onConnectionInterrupted",
+                            jni_args!((throwable => java.lang.Throwable) -> void),
+                        )
+                    })
+                    .unwrap_or_else(|e| {
+                        log::error!("failed to report disconnect \"{error}\" to listener: {e}");
+                    })
+            });
+            Ok(())
+        })
+    }
+}
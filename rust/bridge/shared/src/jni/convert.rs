@@ -16,7 +16,7 @@ use std::ops::Deref;
 
 use crate::io::{InputStream, SyncInputStream};
 use crate::message_backup::MessageBackupValidationOutcome;
-use crate::net::chat::ResponseAndDebugInfo;
+use crate::net::chat::{MakeChatListener, ResponseAndDebugInfo};
 use crate::support::{Array, AsType, FixedLengthBincodeSerializable, Serialized};
 
 use super::*;
@@ -442,6 +442,28 @@ bridge_trait!(KyberPreKeyStore);
 bridge_trait!(InputStream);
 bridge_trait!(SyncInputStream);
 
+/// Converts `null` to `None`.
+impl<'storage, 'param: 'storage, 'context: 'param> ArgTypeInfo<'storage, 'param, 'context>
+    for Option<&'storage dyn MakeChatListener>
+{
+    type ArgType = JavaBridgeChatListener<'context>;
+    type StoredType = Option<JniMakeChatListener>;
+    fn borrow(
+        env: &mut JNIEnv<'context>,
+        foreign: &'param Self::ArgType,
+    ) -> Result<Self::StoredType, BridgeLayerError> {
+        if foreign.is_null() {
+            return Ok(None);
+        }
+        JniMakeChatListener::new(env, foreign).map(Some)
+    }
+    fn load_from(stored: &'storage mut Self::StoredType) -> Self {
+        stored
+            .as_ref()
+            .map(|listener| listener as &'storage dyn MakeChatListener)
+    }
+}
+
 /// A translation from a Java interface where the implementing class wraps the Rust handle.
 impl<'a> SimpleArgTypeInfo<'a> for CiphertextMessageRef<'a> {
     type ArgType = JavaCiphertextMessage<'a>;
@@ -1313,6 +1335,9 @@ macro_rules! jni_arg_type {
     (&mut dyn $typ:ty) => {
         paste!(jni::[<Java $typ>]<'local>)
     };
+    (Option<&dyn MakeChatListener>) => {
+        jni::JavaBridgeChatListener<'local>
+    };
     (& $typ:ty) => {
         jni::ObjectHandle
     };
@@ -5,12 +5,12 @@
 use http::uri::InvalidUri;
 use std::fmt;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::time::Duration;
 
 use jni::objects::{GlobalRef, JObject, JString, JThrowable};
 use jni::{JNIEnv, JavaVM};
 
 use attest::hsm_enclave::Error as HsmEnclaveError;
+use device_transfer::archive::ArchiveError;
 use device_transfer::Error as DeviceTransferError;
 use libsignal_net::chat::ChatServiceError;
 use libsignal_net::infra::ws::{WebSocketConnectError, WebSocketServiceError};
@@ -18,7 +18,9 @@ use libsignal_protocol::*;
 use signal_crypto::Error as SignalCryptoError;
 use signal_pin::Error as PinError;
 use usernames::{UsernameError, UsernameLinkError};
-use zkgroup::{ZkGroupDeserializationFailure, ZkGroupVerificationFailure};
+use zkgroup::{
+    ZkGroupBatchVerificationFailure, ZkGroupDeserializationFailure, ZkGroupVerificationFailure,
+};
 
 use crate::net::cdsi::CdsiError;
 use crate::support::describe_panic;
@@ -36,6 +38,7 @@ pub enum SignalJniError {
     Pin(PinError),
     ZkGroupDeserializationFailure(ZkGroupDeserializationFailure),
     ZkGroupVerificationFailure(ZkGroupVerificationFailure),
+    ZkGroupBatchVerificationFailure(ZkGroupBatchVerificationFailure),
     UsernameError(UsernameError),
     UsernameProofError(usernames::ProofVerificationFailure),
     UsernameLinkError(UsernameLinkError),
@@ -44,12 +47,15 @@ pub enum SignalJniError {
     Mp4SanitizeParse(signal_media::sanitize::mp4::ParseErrorReport),
     #[cfg(feature = "signal-media")]
     WebpSanitizeParse(signal_media::sanitize::webp::ParseErrorReport),
+    #[cfg(feature = "signal-media")]
+    GifSanitizeParse(signal_media::sanitize::gif::ParseErrorReport),
     Cdsi(CdsiError),
     Svr3(libsignal_net::svr3::Error),
     WebSocket(#[from] WebSocketServiceError),
     ChatService(ChatServiceError),
     InvalidUri(InvalidUri),
     ConnectTimedOut,
+    Cancelled,
     Bridge(BridgeLayerError),
     #[cfg(feature = "testing-fns")]
     TestingError {
@@ -84,6 +90,7 @@ impl fmt::Display for SignalJniError {
             SignalJniError::Pin(e) => write!(f, "{}", e),
             SignalJniError::SignalCrypto(s) => write!(f, "{}", s),
             SignalJniError::ZkGroupVerificationFailure(e) => write!(f, "{}", e),
+            SignalJniError::ZkGroupBatchVerificationFailure(e) => write!(f, "{}", e),
             SignalJniError::ZkGroupDeserializationFailure(e) => write!(f, "{}", e),
             SignalJniError::UsernameError(e) => write!(f, "{}", e),
             SignalJniError::UsernameProofError(e) => write!(f, "{}", e),
@@ -93,11 +100,14 @@ impl fmt::Display for SignalJniError {
             SignalJniError::Mp4SanitizeParse(e) => write!(f, "{}", e),
             #[cfg(feature = "signal-media")]
             SignalJniError::WebpSanitizeParse(e) => write!(f, "{}", e),
+            #[cfg(feature = "signal-media")]
+            SignalJniError::GifSanitizeParse(e) => write!(f, "{}", e),
             SignalJniError::Cdsi(e) => write!(f, "{}", e),
             SignalJniError::ChatService(e) => write!(f, "{}", e),
             SignalJniError::InvalidUri(e) => write!(f, "{}", e),
             SignalJniError::WebSocket(e) => write!(f, "{e}"),
             SignalJniError::ConnectTimedOut => write!(f, "connect timed out"),
+            SignalJniError::Cancelled => write!(f, "cancelled"),
             SignalJniError::Svr3(e) => write!(f, "{}", e),
             SignalJniError::Bridge(e) => write!(f, "{}", e),
             #[cfg(feature = "testing-fns")]
@@ -153,6 +163,20 @@ impl From<DeviceTransferError> for SignalJniError {
     }
 }
 
+impl From<ArchiveError> for SignalJniError {
+    fn from(e: ArchiveError) -> SignalJniError {
+        match e {
+            ArchiveError::PayloadTooLong => {
+                SignalJniError::Bridge(BridgeLayerError::BadArgument(e.to_string()))
+            }
+            ArchiveError::InternalError(s) => {
+                SignalJniError::DeviceTransfer(DeviceTransferError::InternalError(s))
+            }
+            _ => SignalJniError::Io(e.into()),
+        }
+    }
+}
+
 impl From<HsmEnclaveError> for SignalJniError {
     fn from(e: HsmEnclaveError) -> SignalJniError {
         SignalJniError::HsmEnclave(e)
@@ -183,6 +207,12 @@ impl From<ZkGroupVerificationFailure> for SignalJniError {
     }
 }
 
+impl From<ZkGroupBatchVerificationFailure> for SignalJniError {
+    fn from(e: ZkGroupBatchVerificationFailure) -> SignalJniError {
+        SignalJniError::ZkGroupBatchVerificationFailure(e)
+    }
+}
+
 impl From<ZkGroupDeserializationFailure> for SignalJniError {
     fn from(e: ZkGroupDeserializationFailure) -> SignalJniError {
         SignalJniError::ZkGroupDeserializationFailure(e)
@@ -247,11 +277,24 @@ impl From<signal_media::sanitize::webp::Error> for SignalJniError {
     }
 }
 
+#[cfg(feature = "signal-media")]
+impl From<signal_media::sanitize::gif::Error> for SignalJniError {
+    fn from(e: signal_media::sanitize::gif::Error) -> Self {
+        use signal_media::sanitize::gif::Error;
+        match e {
+            Error::Io(e) => Self::Io(e),
+            Error::Parse(e) => Self::GifSanitizeParse(e),
+        }
+    }
+}
+
 impl From<libsignal_net::cdsi::LookupError> for SignalJniError {
     fn from(e: libsignal_net::cdsi::LookupError) -> SignalJniError {
         use libsignal_net::cdsi::LookupError;
         SignalJniError::Cdsi(match e {
-            LookupError::ConnectionTimedOut => return SignalJniError::ConnectTimedOut,
+            LookupError::ConnectionTimedOut | LookupError::RequestTimedOut => {
+                return SignalJniError::ConnectTimedOut
+            }
             LookupError::AttestationError(e) => return e.into(),
             LookupError::ConnectTransport(e) => return IoError::from(e).into(),
             LookupError::WebSocket(e) => return e.into(),
@@ -262,11 +305,7 @@ impl From<libsignal_net::cdsi::LookupError> for SignalJniError {
             }
             LookupError::InvalidResponse => CdsiError::InvalidResponse,
             LookupError::Protocol => CdsiError::Protocol,
-            LookupError::RateLimited {
-                retry_after_seconds,
-            } => CdsiError::RateLimited {
-                retry_after: Duration::from_secs(retry_after_seconds.into()),
-            },
+            LookupError::RateLimited { retry_after } => CdsiError::RateLimited { retry_after },
             LookupError::ParseError => CdsiError::ParseError,
             LookupError::InvalidToken => CdsiError::InvalidToken,
             LookupError::Server { reason } => CdsiError::Server { reason },
@@ -294,13 +333,16 @@ impl From<Svr3Error> for SignalJniError {
                 WebSocketConnectError::Transport(e) => SignalJniError::Io(e.into()),
                 WebSocketConnectError::WebSocketError(e) => WebSocketServiceError::from(e).into(),
             },
-            Svr3Error::ConnectionTimedOut => SignalJniError::ConnectTimedOut,
+            Svr3Error::ConnectionTimedOut | Svr3Error::RequestTimedOut => {
+                SignalJniError::ConnectTimedOut
+            }
             Svr3Error::Service(inner) => inner.into(),
             Svr3Error::AttestationError(inner) => inner.into(),
             Svr3Error::Protocol(_)
             | Svr3Error::RequestFailed(_)
             | Svr3Error::RestoreFailed(_)
-            | Svr3Error::DataMissing => SignalJniError::Svr3(err),
+            | Svr3Error::DataMissing
+            | Svr3Error::RateLimited { retry_after: _ } => SignalJniError::Svr3(err),
         }
     }
 }
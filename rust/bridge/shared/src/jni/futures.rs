@@ -4,7 +4,7 @@
 //
 
 use super::*;
-use crate::support::{AsyncRuntime, ResultReporter};
+use crate::support::{AsyncRuntime, CancellationId, ResultReporter};
 
 use futures_util::{FutureExt, TryFutureExt};
 
@@ -168,7 +168,18 @@ where
         jni_args!(() -> void),
     )?;
     let completer = FutureCompleter::new(env, &java_future)?;
-    runtime.run_future(future, completer);
+    let cancellation_id = runtime.run_future(future, completer);
+    if cancellation_id != CancellationId::NotSupported {
+        // The future may already have completed on another thread; in that case a later request
+        // to cancel it will be ignored.
+        let raw_cancellation_id = u64::from(cancellation_id) as jlong;
+        call_method_checked(
+            env,
+            &java_future,
+            "setCancellationId",
+            jni_args!((raw_cancellation_id => long) -> void),
+        )?;
+    }
     Ok(java_future.into())
 }
 
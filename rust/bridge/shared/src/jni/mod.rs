@@ -33,6 +33,9 @@ pub use args::*;
 mod class_lookup;
 pub use class_lookup::*;
 
+mod chat;
+pub use chat::*;
+
 #[macro_use]
 mod convert;
 pub use convert::*;
@@ -283,7 +286,9 @@ impl<'env> ConsumableException<'env> {
                 };
             }
 
-            SignalJniError::Cdsi(CdsiError::RateLimited { retry_after }) => {
+            SignalJniError::Cdsi(CdsiError::RateLimited { retry_after })
+            | SignalJniError::Svr3(Svr3Error::RateLimited { retry_after })
+            | SignalJniError::ChatService(ChatServiceError::RateLimited { retry_after }) => {
                 let retry_after_seconds = retry_after
                     .as_secs()
                     .try_into()
@@ -445,7 +450,8 @@ impl<'env> ConsumableException<'env> {
 
             SignalJniError::Pin(PinError::Argon2Error(_))
             | SignalJniError::Pin(PinError::DecodingError(_))
-            | SignalJniError::Pin(PinError::MrenclaveLookupError) => {
+            | SignalJniError::Pin(PinError::MrenclaveLookupError)
+            | SignalJniError::Pin(PinError::WeakParams) => {
                 (ClassName("java.lang.IllegalArgumentException"), error)
             }
 
@@ -459,6 +465,41 @@ impl<'env> ConsumableException<'env> {
                 error,
             ),
 
+            SignalJniError::ZkGroupBatchVerificationFailure(ref failure) => {
+                let failed_indexes: Vec<jint> = failure
+                    .failed_indexes
+                    .iter()
+                    .map(|&index| index.try_into().expect("batch index overflows int"))
+                    .collect();
+                let throwable = env
+                    .new_string(error.to_string())
+                    .and_then(|message| {
+                        let indexes = env.new_int_array(
+                            failed_indexes
+                                .len()
+                                .try_into()
+                                .expect("batch size overflows int"),
+                        )?;
+                        env.set_int_array_region(&indexes, 0, &failed_indexes)?;
+                        Ok((message, indexes))
+                    })
+                    .map_err(BridgeLayerError::from)
+                    .and_then(|(message, indexes)| {
+                        new_instance(
+                            env,
+                            ClassName(
+                                "org.signal.libsignal.zkgroup.BatchVerificationFailedException",
+                            ),
+                            jni_args!((message => java.lang.String, indexes => [int]) -> void),
+                        )
+                    });
+
+                return ConsumableException {
+                    throwable: throwable.map(Into::into),
+                    error: error.into(),
+                };
+            }
+
             SignalJniError::UsernameError(UsernameError::NicknameCannotBeEmpty) => (
                 ClassName("org.signal.libsignal.usernames.CannotBeEmptyException"),
                 error,
@@ -523,6 +564,10 @@ impl<'env> ConsumableException<'env> {
                 error,
             ),
 
+            SignalJniError::UsernameError(UsernameError::InvalidDiscriminatorRange) => {
+                (ClassName("java.lang.IllegalArgumentException"), error)
+            }
+
             SignalJniError::UsernameProofError(usernames::ProofVerificationFailure) => (
                 ClassName("org.signal.libsignal.usernames.ProofVerificationFailureException"),
                 error,
@@ -546,7 +591,9 @@ impl<'env> ConsumableException<'env> {
             SignalJniError::Io(_) => (ClassName("java.io.IOException"), error),
 
             #[cfg(feature = "signal-media")]
-            SignalJniError::Mp4SanitizeParse(_) | SignalJniError::WebpSanitizeParse(_) => (
+            SignalJniError::Mp4SanitizeParse(_)
+            | SignalJniError::WebpSanitizeParse(_)
+            | SignalJniError::GifSanitizeParse(_) => (
                 ClassName("org.signal.libsignal.media.ParseException"),
                 error,
             ),
@@ -610,6 +657,11 @@ impl<'env> ConsumableException<'env> {
 
             SignalJniError::InvalidUri(_) => (ClassName("java.net.MalformedURLException"), error),
 
+            SignalJniError::Cancelled => (
+                ClassName("java.util.concurrent.CancellationException"),
+                error,
+            ),
+
             SignalJniError::ChatService(ChatServiceError::ServiceInactive) => (
                 ClassName("org.signal.libsignal.net.ChatServiceInactiveException"),
                 error,
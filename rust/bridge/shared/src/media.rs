@@ -5,7 +5,7 @@
 
 use libsignal_bridge_macros::*;
 use signal_media::sanitize::mp4::SanitizedMetadata;
-use signal_media::sanitize::{mp4, webp};
+use signal_media::sanitize::{gif, mp4, webp};
 
 use crate::io::{AsyncInput, InputStream, SyncInput, SyncInputStream};
 
@@ -28,7 +28,7 @@ async fn Mp4Sanitizer_Sanitize(
     input: &mut dyn InputStream,
     len: u64,
 ) -> Result<SanitizedMetadata, mp4::Error> {
-    let input = AsyncInput::new(input, len);
+    let input = AsyncInput::new(input, Some(len));
     let metadata = mp4::sanitize(input).await?;
     Ok(metadata)
 }
@@ -40,6 +40,72 @@ fn WebpSanitizer_Sanitize(input: &mut dyn SyncInputStream) -> Result<(), webp::E
     Ok(())
 }
 
+#[bridge_fn]
+fn WebpSanitizer_SanitizeAndStripMetadata(
+    input: &mut dyn SyncInputStream,
+) -> Result<SanitizedImage, webp::Error> {
+    let input = SyncInput::new(input, None);
+    let mut data = std::io::Cursor::new(Vec::new());
+    let webp::SanitizedMetadata {
+        width,
+        height,
+        frame_count,
+    } = webp::sanitize_and_strip_metadata(input, &mut data)?;
+    Ok(SanitizedImage {
+        data: data.into_inner(),
+        width,
+        height,
+        frame_count,
+    })
+}
+
+#[bridge_fn]
+fn GifSanitizer_Sanitize(input: &mut dyn SyncInputStream) -> Result<SanitizedImage, gif::Error> {
+    let input = SyncInput::new(input, None);
+    let mut data = Vec::new();
+    let gif::SanitizedMetadata {
+        width,
+        height,
+        frame_count,
+    } = gif::sanitize(input, &mut data)?;
+    Ok(SanitizedImage {
+        data,
+        width: width.into(),
+        height: height.into(),
+        frame_count,
+    })
+}
+
+/// A GIF or WebP file with its metadata removed, along with its dimensions.
+pub struct SanitizedImage {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    frame_count: usize,
+}
+
+bridge_handle!(SanitizedImage, clone = false);
+
+#[bridge_fn]
+fn SanitizedImage_GetData(sanitized: &SanitizedImage) -> &[u8] {
+    &sanitized.data
+}
+
+#[bridge_fn]
+fn SanitizedImage_GetWidth(sanitized: &SanitizedImage) -> u32 {
+    sanitized.width
+}
+
+#[bridge_fn]
+fn SanitizedImage_GetHeight(sanitized: &SanitizedImage) -> u32 {
+    sanitized.height
+}
+
+#[bridge_fn]
+fn SanitizedImage_GetFrameCount(sanitized: &SanitizedImage) -> u32 {
+    sanitized.frame_count.try_into().unwrap_or(u32::MAX)
+}
+
 bridge_handle!(SanitizedMetadata);
 
 #[bridge_fn]
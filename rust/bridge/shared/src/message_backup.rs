@@ -3,17 +3,19 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-#[cfg(any(feature = "jni", feature = "ffi"))]
 use futures_util::FutureExt as _;
 use libsignal_bridge_macros::*;
-use libsignal_message_backup::backup::Purpose;
+use libsignal_message_backup::backup::{Purpose, ValidationError as BackupValidationError};
 use libsignal_message_backup::frame::{
     LimitedReaderFactory, ValidationError as FrameValidationError,
 };
 use libsignal_message_backup::key::{BackupKey, MessageBackupKey as MessageBackupKeyInner};
 use libsignal_message_backup::parse::ParseError;
-use libsignal_message_backup::{BackupReader, Error, FoundUnknownField, ReadResult};
-use libsignal_protocol::Aci;
+use libsignal_message_backup::proto::backup::{BackupInfo, Frame};
+use libsignal_message_backup::{BackupReader, BackupWriter, Error, FoundUnknownField, ReadResult};
+use libsignal_protocol::{Aci, SignalProtocolError};
+use protobuf::Message as _;
+use rand::RngCore as _;
 
 use crate::io::{AsyncInput, InputStream};
 use crate::support::*;
@@ -97,23 +99,49 @@ async fn MessageBackupValidator_Validate(
     let MessageBackupKey(key) = key;
 
     let streams = [
-        AsyncInput::new(first_stream, len),
-        AsyncInput::new(second_stream, len),
+        AsyncInput::new(first_stream, Some(len)),
+        AsyncInput::new(second_stream, Some(len)),
     ];
     let factory = LimitedReaderFactory::new(streams);
 
-    let (error, found_unknown_fields) =
+    let outcome =
         match BackupReader::new_encrypted_compressed(key, factory, purpose.into_inner()).await {
-            Err(e) => (Some(e.into()), Vec::new()),
-            Ok(reader) => {
-                let ReadResult {
-                    result,
-                    found_unknown_fields,
-                } = reader.validate_all().await;
-
-                (result.err().map(Into::into), found_unknown_fields)
-            }
+            Err(e) => Err(e.into()),
+            Ok(reader) => Ok(reader.validate_all().await),
+        };
+    validation_outcome(outcome)
+}
+
+#[bridge_fn]
+async fn MessageBackupValidator_ValidateStream(
+    key: &MessageBackupKey,
+    stream: &mut dyn InputStream,
+    purpose: AsType<Purpose, u8>,
+) -> Result<MessageBackupValidationOutcome, std::io::Error> {
+    let MessageBackupKey(key) = key;
+
+    let stream = AsyncInput::new(stream, None);
+
+    let outcome =
+        match BackupReader::new_encrypted_compressed_streaming(key, stream, purpose.into_inner())
+            .await
+        {
+            Err(e) => Err(e.into()),
+            Ok(reader) => Ok(reader.validate_all().await),
         };
+    validation_outcome(outcome)
+}
+
+fn validation_outcome(
+    outcome: Result<ReadResult<()>, MessageBackupValidationError>,
+) -> Result<MessageBackupValidationOutcome, std::io::Error> {
+    let (error, found_unknown_fields) = match outcome {
+        Err(e) => (Some(e), Vec::new()),
+        Ok(ReadResult {
+            result,
+            found_unknown_fields,
+        }) => (result.err().map(Into::into), found_unknown_fields),
+    };
 
     let error_message = error
         .map(|m| match m {
@@ -127,3 +155,92 @@ async fn MessageBackupValidator_Validate(
         found_unknown_fields,
     })
 }
+
+/// Produces an encrypted backup in memory.
+///
+/// The ciphertext is handed back as it is produced, so apps can write it out
+/// without holding the whole backup in memory. The writer is taken by
+/// [`MessageBackupWriter_Finish`], after which it can't be used.
+pub struct MessageBackupWriter(std::sync::Mutex<Option<BackupWriter<Vec<u8>>>>);
+
+bridge_handle!(MessageBackupWriter, clone = false);
+
+fn invalid_argument(error: impl std::fmt::Display) -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument(error.to_string())
+}
+
+fn already_finished() -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument("backup writer already finished".to_owned())
+}
+
+#[bridge_fn]
+fn MessageBackupWriter_New(
+    key: &MessageBackupKey,
+    backup_info: &[u8],
+    purpose: AsType<Purpose, u8>,
+) -> Result<MessageBackupWriter, SignalProtocolError> {
+    let MessageBackupKey(key) = key;
+    let backup_info = BackupInfo::parse_from_bytes(backup_info).map_err(invalid_argument)?;
+
+    let mut iv = [0; 16];
+    rand::rngs::OsRng.fill_bytes(&mut iv);
+
+    let writer = BackupWriter::new_encrypted_compressed(
+        key,
+        iv,
+        Vec::new(),
+        backup_info,
+        purpose.into_inner(),
+    )
+    .now_or_never()
+    .expect("writing to a Vec never blocks")
+    .map_err(invalid_argument)?;
+    Ok(MessageBackupWriter(Some(writer).into()))
+}
+
+/// Validates and writes a serialized `Frame`, returning the ciphertext produced
+/// so far.
+///
+/// A rejected frame is reported as an invalid argument, and leaves the writer
+/// usable.
+#[bridge_fn]
+fn MessageBackupWriter_WriteFrame(
+    writer: &MessageBackupWriter,
+    frame: &[u8],
+) -> Result<Vec<u8>, SignalProtocolError> {
+    let mut guard = writer.0.lock().expect("not poisoned");
+    let writer = guard.as_mut().ok_or_else(already_finished)?;
+
+    let item = Frame::parse_from_bytes(frame)
+        .map_err(invalid_argument)?
+        .item
+        .ok_or(BackupValidationError::EmptyFrame)
+        .map_err(invalid_argument)?;
+    writer
+        .write_frame(item)
+        .now_or_never()
+        .expect("writing to a Vec never blocks")
+        .map_err(invalid_argument)?;
+
+    Ok(std::mem::take(writer.get_mut()))
+}
+
+/// Checks that the backup is complete, returning the rest of the ciphertext.
+///
+/// The writer can't be used afterwards, even if the backup is incomplete.
+#[bridge_fn]
+fn MessageBackupWriter_Finish(
+    writer: &MessageBackupWriter,
+) -> Result<Vec<u8>, SignalProtocolError> {
+    let writer = writer
+        .0
+        .lock()
+        .expect("not poisoned")
+        .take()
+        .ok_or_else(already_finished)?;
+    writer
+        .finish()
+        .now_or_never()
+        .expect("writing to a Vec never blocks")
+        .map_err(invalid_argument)
+}
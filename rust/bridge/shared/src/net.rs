@@ -4,8 +4,10 @@
 //
 
 use std::convert::TryInto as _;
-use std::num::{NonZeroU16, NonZeroU32};
+use std::num::{NonZeroU16, NonZeroU32, NonZeroUsize};
 use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
 
 use base64::prelude::{Engine, BASE64_STANDARD};
 use http::uri::PathAndQuery;
@@ -16,16 +18,25 @@ use libsignal_net::enclave::{
 };
 use libsignal_net::env;
 use libsignal_net::env::{add_user_agent_header, Env, Svr3Env};
-use libsignal_net::infra::connection_manager::MultiRouteConnectionManager;
+use libsignal_net::infra::backoff::{
+    DefaultBackoffPolicy, ExponentialBackoff, SwitchableBackoffPolicy,
+};
+use libsignal_net::infra::connection_manager::{
+    MultiRouteConnectionManager, NetworkChangeNotifier,
+};
 use libsignal_net::infra::dns::DnsResolver;
+use libsignal_net::infra::events::{ConnectionEvent, ConnectionEventLog, RouteAttemptOutcome};
+use libsignal_net::infra::tcp_ssl::proxy::{ProxyAuth, TunnelProtocol, TunnelProxyConnector};
+use libsignal_net::infra::tcp_ssl::session_cache::TlsSessionCache;
 use libsignal_net::infra::tcp_ssl::{
     DirectConnector as TcpSslDirectConnector, ProxyConnector as TcpSslProxyConnector,
     TcpSslConnector, TcpSslConnectorStream,
 };
-use libsignal_net::infra::{make_ws_config, EndpointConnection};
+use libsignal_net::infra::{make_ws_config, ConnectionParams, EndpointConnection};
 use libsignal_net::svr::{self, SvrConnection};
 use libsignal_net::svr3::{self, OpaqueMaskedShareSet, PpssOps as _};
-use libsignal_net::timeouts::ONE_ROUTE_CONNECTION_TIMEOUT;
+use libsignal_net::timeouts::TimeoutConfig;
+use nonzero_ext::nonzero;
 use rand::rngs::OsRng;
 
 use crate::support::*;
@@ -63,12 +74,24 @@ pub struct ConnectionManager {
         EnclaveEndpointConnection<Tpm2Snp, MultiRouteConnectionManager>,
     ),
     transport_connector: std::sync::Mutex<TcpSslConnector>,
+    backoff_policy: SwitchableBackoffPolicy,
+    connection_events: Arc<ConnectionEventLog>,
+    network_change_notifier: NetworkChangeNotifier,
+    tls_session_cache: TlsSessionCache,
 }
 
 impl RefUnwindSafe for ConnectionManager {}
 
 impl ConnectionManager {
     fn new(environment: Environment, user_agent: String) -> Self {
+        Self::with_timeouts(environment, user_agent, TimeoutConfig::default())
+    }
+
+    fn with_timeouts(
+        environment: Environment,
+        user_agent: String,
+        timeouts: TimeoutConfig,
+    ) -> Self {
         log::info!("Initializing connection manager for {}...", &environment);
         let dns_resolver =
             DnsResolver::new_with_static_fallback(environment.env().static_fallback());
@@ -80,30 +103,82 @@ impl ConnectionManager {
             .chat_domain_config
             .connection_params_with_fallback();
         let chat_connection_params = add_user_agent_header(chat_connection_params, &user_agent);
-        let chat_ws_config = make_ws_config(chat_endpoint, ONE_ROUTE_CONNECTION_TIMEOUT);
+        let chat_ws_config = make_ws_config(chat_endpoint, timeouts.one_route_connection);
+        let backoff_policy = SwitchableBackoffPolicy::default();
+        let connection_events = Arc::new(ConnectionEventLog::new(CONNECTION_EVENT_LOG_CAPACITY));
+        let network_change_notifier = NetworkChangeNotifier::default();
+        let tls_session_cache = TlsSessionCache::new(TLS_SESSION_CACHE_CAPACITY);
+        let shared = SharedRouteSettings {
+            user_agent: &user_agent,
+            backoff_policy: &backoff_policy,
+            connection_events: &connection_events,
+            network_change_notifier: &network_change_notifier,
+            timeouts: &timeouts,
+            tls_session_cache: &tls_session_cache,
+        };
         Self {
             chat: EndpointConnection::new_multi(
-                chat_connection_params,
-                ONE_ROUTE_CONNECTION_TIMEOUT,
+                shared.observe(chat_connection_params),
+                &timeouts,
                 chat_ws_config,
-            ),
-            cdsi: Self::endpoint_connection(&environment.env().cdsi, &user_agent),
+            )
+            .with_backoff_policy(Arc::new(backoff_policy.clone()))
+            .with_network_change_listener(network_change_notifier.listener()),
+            cdsi: shared.endpoint_connection(&environment.env().cdsi),
             svr3: (
-                Self::endpoint_connection(environment.env().svr3.sgx(), &user_agent),
-                Self::endpoint_connection(environment.env().svr3.nitro(), &user_agent),
-                Self::endpoint_connection(environment.env().svr3.tpm2snp(), &user_agent),
+                shared.endpoint_connection(environment.env().svr3.sgx()),
+                shared.endpoint_connection(environment.env().svr3.nitro()),
+                shared.endpoint_connection(environment.env().svr3.tpm2snp()),
             ),
             transport_connector,
+            backoff_policy,
+            connection_events,
+            network_change_notifier,
+            tls_session_cache,
         }
     }
+}
+
+/// How many [`ConnectionEvent`]s are kept for `ConnectionManager_take_connection_event` to return.
+const CONNECTION_EVENT_LOG_CAPACITY: NonZeroUsize = nonzero!(256usize);
+
+/// How many routes a [`ConnectionManager`] keeps TLS sessions for.
+///
+/// This only needs to cover the direct and fallback routes of each endpoint.
+const TLS_SESSION_CACHE_CAPACITY: NonZeroUsize = nonzero!(32usize);
+
+/// Settings that are applied to every route of a [`ConnectionManager`].
+struct SharedRouteSettings<'a> {
+    user_agent: &'a str,
+    backoff_policy: &'a SwitchableBackoffPolicy,
+    connection_events: &'a Arc<ConnectionEventLog>,
+    network_change_notifier: &'a NetworkChangeNotifier,
+    timeouts: &'a TimeoutConfig,
+    tls_session_cache: &'a TlsSessionCache,
+}
+
+impl SharedRouteSettings<'_> {
+    fn observe(&self, params: Vec<ConnectionParams>) -> Vec<ConnectionParams> {
+        params
+            .into_iter()
+            .map(|params| {
+                params
+                    .with_event_observer(self.connection_events.clone())
+                    .with_timeouts(*self.timeouts)
+                    .with_tls_session_cache(self.tls_session_cache.clone())
+            })
+            .collect()
+    }
 
     fn endpoint_connection<E: EnclaveKind>(
+        &self,
         endpoint: &EnclaveEndpoint<'static, E>,
-        user_agent: &str,
     ) -> EnclaveEndpointConnection<E, MultiRouteConnectionManager> {
         let params = endpoint.domain_config.connection_params_with_fallback();
-        let params = add_user_agent_header(params, user_agent);
-        EnclaveEndpointConnection::new_multi(endpoint, params, ONE_ROUTE_CONNECTION_TIMEOUT)
+        let params = add_user_agent_header(params, self.user_agent);
+        EnclaveEndpointConnection::new_multi(endpoint, self.observe(params), self.timeouts)
+            .with_backoff_policy(Arc::new(self.backoff_policy.clone()))
+            .with_network_change_listener(self.network_change_notifier.listener())
     }
 }
 
@@ -115,6 +190,24 @@ fn ConnectionManager_new(
     ConnectionManager::new(environment.into_inner(), user_agent)
 }
 
+/// Like `ConnectionManager_new`, but with the given timeouts in place of the defaults.
+///
+/// `connect_timeout_millis` bounds each attempt to connect over a single route, for chat, CDSI,
+/// and SVR3 alike. `request_timeout_millis` is how long to wait for each response from CDSI or
+/// SVR3 once connected.
+#[bridge_fn]
+fn ConnectionManager_new_with_timeouts(
+    environment: AsType<Environment, u8>,
+    user_agent: String,
+    connect_timeout_millis: u32,
+    request_timeout_millis: u32,
+) -> ConnectionManager {
+    let timeouts = TimeoutConfig::default()
+        .with_one_route_connection(Duration::from_millis(connect_timeout_millis.into()))
+        .with_request(Duration::from_millis(request_timeout_millis.into()));
+    ConnectionManager::with_timeouts(environment.into_inner(), user_agent, timeouts)
+}
+
 #[bridge_fn]
 fn ConnectionManager_set_proxy(
     connection_manager: &ConnectionManager,
@@ -125,36 +218,116 @@ fn ConnectionManager_set_proxy(
         .transport_connector
         .lock()
         .expect("not poisoned");
-    // We take port as an i32 because Java 'short' is signed and thus can't represent all port
-    // numbers, and we want too-large port numbers to be handled the same way as 0.
-    match u16::try_from(port)
-        .ok()
-        .and_then(|port| NonZeroU16::try_from(port).ok())
-    {
+    match parse_proxy_port(port) {
         Some(port) => {
             let proxy_addr = (host.as_str(), port);
             match &mut *guard {
                 TcpSslConnector::Direct(direct) => *guard = direct.with_proxy(proxy_addr).into(),
                 TcpSslConnector::Proxied(proxied) => proxied.set_proxy(proxy_addr),
-                TcpSslConnector::Invalid(dns_resolver) => {
+                TcpSslConnector::Tunneled(TunnelProxyConnector { dns_resolver, .. })
+                | TcpSslConnector::Invalid(dns_resolver) => {
                     *guard = TcpSslProxyConnector::new(dns_resolver.clone(), proxy_addr).into()
                 }
             };
             Ok(())
         }
         None => {
-            match &*guard {
-                TcpSslConnector::Direct(TcpSslDirectConnector { dns_resolver, .. })
-                | TcpSslConnector::Proxied(TcpSslProxyConnector { dns_resolver, .. }) => {
-                    *guard = TcpSslConnector::Invalid(dns_resolver.clone())
+            invalidate_transport_connector(&mut guard);
+            Err(std::io::ErrorKind::InvalidInput.into())
+        }
+    }
+}
+
+#[bridge_fn]
+fn ConnectionManager_set_socks_proxy(
+    connection_manager: &ConnectionManager,
+    host: String,
+    port: i32,
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<(), std::io::Error> {
+    set_tunnel_proxy(connection_manager, host, port, username, password, |auth| {
+        TunnelProtocol::Socks5 { auth }
+    })
+}
+
+#[bridge_fn]
+fn ConnectionManager_set_http_proxy(
+    connection_manager: &ConnectionManager,
+    host: String,
+    port: i32,
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<(), std::io::Error> {
+    set_tunnel_proxy(connection_manager, host, port, username, password, |auth| {
+        TunnelProtocol::HttpConnect { auth }
+    })
+}
+
+fn set_tunnel_proxy(
+    connection_manager: &ConnectionManager,
+    host: String,
+    port: i32,
+    username: Option<String>,
+    password: Option<String>,
+    make_protocol: impl FnOnce(Option<ProxyAuth>) -> TunnelProtocol,
+) -> Result<(), std::io::Error> {
+    let mut guard = connection_manager
+        .transport_connector
+        .lock()
+        .expect("not poisoned");
+    let auth = match (username, password) {
+        (None, None) => Some(None),
+        (Some(username), Some(password)) => Some(Some(ProxyAuth { username, password })),
+        // A username without a password (or vice versa) is a configuration error.
+        (Some(_), None) | (None, Some(_)) => None,
+    };
+    match (parse_proxy_port(port), auth) {
+        (Some(port), Some(auth)) => {
+            let proxy_addr = (host.as_str(), port);
+            let protocol = make_protocol(auth);
+            match &mut *guard {
+                TcpSslConnector::Direct(direct) => {
+                    *guard = direct.with_tunnel_proxy(proxy_addr, protocol).into()
                 }
-                TcpSslConnector::Invalid(_dns_resolver) => (),
-            }
+                TcpSslConnector::Tunneled(tunneled) => tunneled.set_proxy(proxy_addr, protocol),
+                TcpSslConnector::Proxied(TcpSslProxyConnector { dns_resolver, .. })
+                | TcpSslConnector::Invalid(dns_resolver) => {
+                    *guard =
+                        TunnelProxyConnector::new(dns_resolver.clone(), proxy_addr, protocol).into()
+                }
+            };
+            Ok(())
+        }
+        (None, _) | (_, None) => {
+            invalidate_transport_connector(&mut guard);
             Err(std::io::ErrorKind::InvalidInput.into())
         }
     }
 }
 
+/// Converts a port number passed in from the app.
+///
+/// We take port as an i32 because Java 'short' is signed and thus can't represent all port
+/// numbers, and we want too-large port numbers to be handled the same way as 0.
+fn parse_proxy_port(port: i32) -> Option<NonZeroU16> {
+    u16::try_from(port)
+        .ok()
+        .and_then(|port| NonZeroU16::try_from(port).ok())
+}
+
+/// Makes all future connections fail rather than silently bypassing a misconfigured proxy.
+fn invalidate_transport_connector(connector: &mut TcpSslConnector) {
+    match &*connector {
+        TcpSslConnector::Direct(TcpSslDirectConnector { dns_resolver, .. })
+        | TcpSslConnector::Proxied(TcpSslProxyConnector { dns_resolver, .. })
+        | TcpSslConnector::Tunneled(TunnelProxyConnector { dns_resolver, .. }) => {
+            *connector = TcpSslConnector::Invalid(dns_resolver.clone())
+        }
+        TcpSslConnector::Invalid(_dns_resolver) => (),
+    }
+}
+
 #[bridge_fn]
 fn ConnectionManager_clear_proxy(connection_manager: &ConnectionManager) {
     let mut guard = connection_manager
@@ -164,12 +337,70 @@ fn ConnectionManager_clear_proxy(connection_manager: &ConnectionManager) {
     match &*guard {
         TcpSslConnector::Direct(_direct) => (),
         TcpSslConnector::Proxied(TcpSslProxyConnector { dns_resolver, .. })
+        | TcpSslConnector::Tunneled(TunnelProxyConnector { dns_resolver, .. })
         | TcpSslConnector::Invalid(dns_resolver) => {
             *guard = TcpSslDirectConnector::new(dns_resolver.clone()).into()
         }
     };
 }
 
+/// Replaces the backoff policy used for all of the manager's routes.
+///
+/// `multiplier_percent` is the growth of the delay after each failure (200 doubles it), and
+/// `jitter_percent` the largest share of each delay that can randomly be taken off it. A
+/// `max_attempts` of 0 means routes are never given up on.
+#[bridge_fn]
+fn ConnectionManager_set_backoff_policy(
+    connection_manager: &ConnectionManager,
+    initial_delay_millis: u32,
+    max_delay_millis: u32,
+    multiplier_percent: u32,
+    jitter_percent: u32,
+    max_attempts: u32,
+) {
+    let mut policy = ExponentialBackoff::new(Duration::from_millis(initial_delay_millis.into()))
+        .max_delay(Duration::from_millis(max_delay_millis.into()))
+        .multiplier(f64::from(multiplier_percent) / 100.0)
+        .jitter(f64::from(jitter_percent) / 100.0);
+    if let Some(max_attempts) = NonZeroU32::new(max_attempts) {
+        policy = policy.max_attempts(NonZeroU16::try_from(max_attempts).unwrap_or(NonZeroU16::MAX));
+    }
+    connection_manager.backoff_policy.set(Arc::new(policy));
+}
+
+#[bridge_fn]
+fn ConnectionManager_reset_backoff_policy(connection_manager: &ConnectionManager) {
+    connection_manager
+        .backoff_policy
+        .set(Arc::new(DefaultBackoffPolicy));
+}
+
+/// Removes and returns the oldest recorded connection event, or `None` if there are none left.
+///
+/// Only the most recent events are kept if this isn't called often enough.
+#[bridge_fn]
+fn ConnectionManager_take_connection_event(
+    connection_manager: &ConnectionManager,
+) -> Option<ConnectionEvent> {
+    connection_manager.connection_events.take_event()
+}
+
+/// Tells the manager that the device switched networks (e.g. from Wi-Fi to cellular).
+///
+/// Cached DNS results, saved TLS sessions, and route cooldowns are discarded, connection attempts
+/// in progress are abandoned, and long-lived connections are re-established.
+#[bridge_fn]
+fn ConnectionManager_network_changed(connection_manager: &ConnectionManager) {
+    connection_manager
+        .transport_connector
+        .lock()
+        .expect("not poisoned")
+        .dns_resolver()
+        .clear_cache();
+    connection_manager.tls_session_cache.clear();
+    connection_manager.network_change_notifier.network_changed();
+}
+
 #[bridge_fn(jni = false, ffi = false)]
 fn ConnectionManager_set_ipv6_enabled(connection_manager: &ConnectionManager, ipv6_enabled: bool) {
     let mut guard = connection_manager
@@ -181,6 +412,131 @@ fn ConnectionManager_set_ipv6_enabled(connection_manager: &ConnectionManager, ip
 
 bridge_handle!(ConnectionManager, clone = false);
 
+bridge_handle!(ConnectionEvent, clone = false);
+
+/// Identifies the kind of event, in the order of the [`ConnectionEvent`] variants.
+#[bridge_fn]
+fn ConnectionEvent_GetKind(event: &ConnectionEvent) -> u8 {
+    match event {
+        ConnectionEvent::DnsLookup { .. } => 0,
+        ConnectionEvent::TcpConnect { .. } => 1,
+        ConnectionEvent::TlsHandshake { .. } => 2,
+        ConnectionEvent::WebSocketUpgrade { .. } => 3,
+        ConnectionEvent::RouteAttempt { .. } => 4,
+        ConnectionEvent::RouteCoolingDown { .. } => 5,
+    }
+}
+
+#[bridge_fn]
+fn ConnectionEvent_GetRoute(event: &ConnectionEvent) -> String {
+    match event {
+        ConnectionEvent::DnsLookup { route, .. }
+        | ConnectionEvent::TcpConnect { route, .. }
+        | ConnectionEvent::TlsHandshake { route, .. }
+        | ConnectionEvent::WebSocketUpgrade { route, .. }
+        | ConnectionEvent::RouteAttempt { route, .. }
+        | ConnectionEvent::RouteCoolingDown { route, .. } => route.to_string(),
+    }
+}
+
+/// The service being connected to; `None` for DNS lookups and TCP connections.
+#[bridge_fn]
+fn ConnectionEvent_GetHost(event: &ConnectionEvent) -> Option<String> {
+    match event {
+        ConnectionEvent::DnsLookup { .. } | ConnectionEvent::TcpConnect { .. } => None,
+        ConnectionEvent::TlsHandshake { host, .. }
+        | ConnectionEvent::WebSocketUpgrade { host, .. }
+        | ConnectionEvent::RouteAttempt { host, .. }
+        | ConnectionEvent::RouteCoolingDown { host, .. } => Some(host.to_string()),
+    }
+}
+
+/// How long the step took or, for a route that is cooling down, how long it has left.
+#[bridge_fn]
+fn ConnectionEvent_GetDurationMillis(event: &ConnectionEvent) -> u32 {
+    let duration = match event {
+        ConnectionEvent::DnsLookup { duration, .. }
+        | ConnectionEvent::TcpConnect { duration, .. }
+        | ConnectionEvent::TlsHandshake { duration, .. }
+        | ConnectionEvent::WebSocketUpgrade { duration, .. }
+        | ConnectionEvent::RouteAttempt { duration, .. } => duration,
+        ConnectionEvent::RouteCoolingDown { remaining, .. } => remaining,
+    };
+    duration.as_millis().try_into().unwrap_or(u32::MAX)
+}
+
+/// Whether a TCP connection or TLS handshake succeeded; `false` for other kinds of events.
+#[bridge_fn]
+fn ConnectionEvent_GetSucceeded(event: &ConnectionEvent) -> bool {
+    match event {
+        ConnectionEvent::TcpConnect { succeeded, .. }
+        | ConnectionEvent::TlsHandshake { succeeded, .. } => *succeeded,
+        ConnectionEvent::DnsLookup { .. }
+        | ConnectionEvent::WebSocketUpgrade { .. }
+        | ConnectionEvent::RouteAttempt { .. }
+        | ConnectionEvent::RouteCoolingDown { .. } => false,
+    }
+}
+
+/// Where a DNS lookup's result came from; `None` if it failed or for other kinds of events.
+#[bridge_fn]
+fn ConnectionEvent_GetDnsSource(event: &ConnectionEvent) -> Option<String> {
+    match event {
+        ConnectionEvent::DnsLookup { source, .. } => source.map(|source| source.to_string()),
+        _ => None,
+    }
+}
+
+/// The [`IpType`] of a TCP connection, as its numeric value; `None` for other kinds of events.
+///
+/// [`IpType`]: libsignal_net::infra::IpType
+#[bridge_fn]
+fn ConnectionEvent_GetIpType(event: &ConnectionEvent) -> Option<u32> {
+    match event {
+        ConnectionEvent::TcpConnect { ip_type, .. } => Some((*ip_type as u8).into()),
+        _ => None,
+    }
+}
+
+/// The HTTP status of the server's response to a websocket upgrade, if there was one.
+#[bridge_fn]
+fn ConnectionEvent_GetHttpStatus(event: &ConnectionEvent) -> Option<u32> {
+    match event {
+        ConnectionEvent::WebSocketUpgrade { status, .. } => status.map(u32::from),
+        _ => None,
+    }
+}
+
+/// How a route attempt ended (`succeeded`, `failed`, or `timedOut`); `None` for other kinds of
+/// events.
+#[bridge_fn]
+fn ConnectionEvent_GetRouteAttemptOutcome(event: &ConnectionEvent) -> Option<String> {
+    match event {
+        ConnectionEvent::RouteAttempt { outcome, .. } => Some(
+            match outcome {
+                RouteAttemptOutcome::Succeeded => "succeeded",
+                RouteAttemptOutcome::Failed => "failed",
+                RouteAttemptOutcome::TimedOut => "timedOut",
+            }
+            .to_owned(),
+        ),
+        _ => None,
+    }
+}
+
+/// How many times in a row a route has failed, counting this attempt; `None` for events other
+/// than route attempts.
+#[bridge_fn]
+fn ConnectionEvent_GetConsecutiveFailures(event: &ConnectionEvent) -> Option<u32> {
+    match event {
+        ConnectionEvent::RouteAttempt {
+            consecutive_failures,
+            ..
+        } => Some((*consecutive_failures).into()),
+        _ => None,
+    }
+}
+
 #[bridge_fn]
 fn CreateOTP(username: String, secret: &[u8]) -> String {
     Auth::otp(&username, secret, std::time::SystemTime::now())
@@ -244,6 +600,67 @@ async fn Svr3Remove(
     Ok(())
 }
 
+/// Progress of an [`Svr3Migrate`] that can be retried.
+///
+/// The state is taken for the duration of a migration, so concurrent migrations using the same
+/// state don't see each other's progress.
+#[derive(Default)]
+pub struct Svr3MigrationState(std::sync::Mutex<svr3::MigrationState>);
+
+bridge_handle!(Svr3MigrationState, clone = false);
+
+#[bridge_fn]
+fn Svr3MigrationState_new() -> Svr3MigrationState {
+    Svr3MigrationState::default()
+}
+
+#[bridge_fn]
+fn Svr3MigrationState_hasRestoredSecret(state: &Svr3MigrationState) -> bool {
+    state.0.lock().expect("not poisoned").has_restored_secret()
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn Svr3Migrate(
+    connection_manager: &ConnectionManager,
+    previous_connection_manager: &ConnectionManager,
+    migration_state: &Svr3MigrationState,
+    password: String,
+    share_set: Box<[u8]>,
+    max_tries: AsType<NonZeroU32, u32>,
+    username: String,         // hex-encoded uid
+    enclave_password: String, // timestamp:otp(...)
+) -> Result<Vec<u8>, svr3::Error> {
+    let mut rng = OsRng;
+    let share_set = OpaqueMaskedShareSet::deserialize(&share_set)?;
+    let connections = svr3_connect(
+        connection_manager,
+        username.clone(),
+        enclave_password.clone(),
+    )
+    .await?;
+    let connect_previous = || async {
+        svr3_connect(previous_connection_manager, username, enclave_password)
+            .await
+            .map_err(svr3::Error::from)
+    };
+
+    let mut state = std::mem::take(&mut *migration_state.0.lock().expect("not poisoned"));
+    let result = Svr3Env::migrate_backup(
+        &mut state,
+        connect_previous,
+        connections,
+        &password,
+        share_set,
+        max_tries.into_inner(),
+        &mut rng,
+    )
+    .await;
+    *migration_state.0.lock().expect("not poisoned") = state;
+
+    let share_set = result?;
+    Ok(share_set.serialize().expect("can serialize the share set"))
+}
+
 async fn svr3_connect<'a>(
     connection_manager: &ConnectionManager,
     username: String,
@@ -255,6 +672,7 @@ async fn svr3_connect<'a>(
         cdsi: _cdsi,
         svr3: (sgx, nitro, tpm2snp),
         transport_connector,
+        ..
     } = connection_manager;
     let transport_connector = transport_connector.lock().expect("not poisoned").clone();
     let sgx = SvrConnection::connect(auth.clone(), sgx, transport_connector.clone()).await?;
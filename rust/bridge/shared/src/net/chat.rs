@@ -62,7 +62,7 @@ pub struct Chat {
     >,
     listener: std::sync::Mutex<ChatListenerState>,
     #[cfg(feature = "testing-fns")]
-    synthetic_request_tx: mpsc::Sender<chat::ws::ServerRequest<TcpSslConnectorStream>>,
+    synthetic_request_tx: mpsc::Sender<chat::ws::ServerEvent<TcpSslConnectorStream>>,
 }
 
 impl RefUnwindSafe for Chat {}
@@ -191,6 +191,11 @@ async fn ChatService_connect_auth(chat: &Chat) -> Result<ChatServiceDebugInfo, C
     chat.service.connect_authenticated().await
 }
 
+#[bridge_io(TokioAsyncContext)]
+async fn ChatService_preconnect(chat: &Chat) -> Result<(), ChatServiceError> {
+    chat.service.preconnect().await
+}
+
 #[bridge_io(TokioAsyncContext)]
 async fn ChatService_unauth_send(
     chat: &Chat,
@@ -286,6 +291,7 @@ pub trait ChatListener: Send {
         ack: ServerMessageAck,
     );
     fn received_queue_empty(&mut self);
+    fn connection_interrupted(&mut self, disconnect_cause: ChatServiceError);
 }
 
 impl dyn ChatListener {
@@ -297,13 +303,16 @@ impl dyn ChatListener {
                 request_id: _,
                 envelope,
                 server_delivery_timestamp,
-                send_ack,
+                responder,
             } => self.received_incoming_message(
                 envelope,
                 server_delivery_timestamp,
-                ServerMessageAck::new(send_ack),
+                ServerMessageAck::new(responder),
             ),
             chat::server_requests::ServerMessage::QueueEmpty => self.received_queue_empty(),
+            chat::server_requests::ServerMessage::ConnectionInterrupted(disconnect_cause) => {
+                self.connection_interrupted(disconnect_cause)
+            }
         }
     }
 
@@ -380,7 +389,7 @@ pub trait MakeChatListener {
     fn make_listener(&self) -> Box<dyn ChatListener>;
 }
 
-#[bridge_fn(jni = false, node = false)]
+#[bridge_fn]
 fn ChatServer_SetListener(
     runtime: &TokioAsyncContext,
     chat: &Chat,
@@ -437,19 +446,202 @@ fn TESTING_ChatService_InjectRawServerRequest(chat: &Chat, bytes: &[u8]) {
     let request_proto = <chat::RequestProto as prost::Message>::decode(bytes)
         .expect("invalid protobuf cannot use this endpoint to test");
     chat.synthetic_request_tx
-        .blocking_send(chat::ws::ServerRequest::fake(request_proto))
+        .blocking_send(chat::ws::ServerEvent::Request(
+            chat::ws::ServerRequest::fake(request_proto),
+        ))
         .expect("not closed");
 }
 
-/// Wraps a named type and a single-use guard around [`chat::server_requests::AckEnvelopeFuture`].
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_ChatService_InjectConnectionInterrupted(chat: &Chat) {
+    chat.synthetic_request_tx
+        .blocking_send(chat::ws::ServerEvent::ConnectionInterrupted(
+            ChatServiceError::WebSocket(
+                libsignal_net::infra::ws::WebSocketServiceError::ChannelClosed,
+            ),
+        ))
+        .expect("not closed");
+}
+
+/// An in-process chat server, for testing apps' use of [`Chat`] end to end.
+#[cfg(feature = "testing-fns")]
+pub struct FakeChatServer(chat::fake::FakeChatServer);
+
+#[cfg(feature = "testing-fns")]
+impl RefUnwindSafe for FakeChatServer {}
+
+#[cfg(feature = "testing-fns")]
+bridge_handle!(FakeChatServer, clone = false);
+
+/// A request received by a [`FakeChatServer`].
+///
+/// Destroying the request without responding means the client never gets a response.
+#[cfg(feature = "testing-fns")]
+pub struct FakeChatServerRequest {
+    request: chat::RequestProto,
+    received: AtomicTake<chat::fake::ReceivedRequest>,
+}
+
+#[cfg(feature = "testing-fns")]
+bridge_handle!(FakeChatServerRequest, clone = false);
+
+// See the comment on the equivalent impl for ServerMessageAck below.
+#[cfg(feature = "testing-fns")]
+impl RefUnwindSafe for FakeChatServerRequest {}
+
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServer_New() -> FakeChatServer {
+    FakeChatServer(chat::fake::FakeChatServer::new())
+}
+
+/// Like [`ChatService_new`], but connects to `server` instead of the network.
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_ChatService_NewWithFakeServer(
+    connection_manager: &ConnectionManager,
+    server: &FakeChatServer,
+    username: String,
+    password: String,
+) -> Chat {
+    let (incoming_tx, incoming_rx) = mpsc::channel(1);
+    // Synthetic requests can't be sent over the fake server's connections, so they get their own
+    // channel.
+    let (synthetic_request_tx, synthetic_request_rx) = mpsc::channel(1);
+    let incoming_stream = futures_util::stream::select(
+        chat::server_requests::stream_incoming_messages(incoming_rx),
+        chat::server_requests::stream_incoming_messages(synthetic_request_rx),
+    );
+
+    Chat {
+        service: chat::chat_service(
+            &connection_manager.chat,
+            server.0.connector(),
+            incoming_tx,
+            username,
+            password,
+        )
+        .into_dyn(),
+        listener: std::sync::Mutex::new(ChatListenerState::Inactive(Box::pin(incoming_stream))),
+        synthetic_request_tx,
+    }
+}
+
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServer_SetRefuseConnections(server: &FakeChatServer, refuse: bool) {
+    server.0.set_refuse_connections(refuse)
+}
+
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServer_DisconnectAll(server: &FakeChatServer) {
+    server.0.disconnect_all()
+}
+
+/// Fails with [`ChatServiceError::ServiceInactive`] if no client is connected.
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServer_SendEnvelope(
+    server: &FakeChatServer,
+    envelope: &[u8],
+    server_delivery_timestamp: Timestamp,
+) -> Result<(), ChatServiceError> {
+    // The client's ack or nack isn't reported back; tests can observe it on the client side.
+    let _ = server
+        .0
+        .send_envelope(envelope.to_vec(), server_delivery_timestamp)
+        .map_err(|chat::fake::NoConnectedClient| ChatServiceError::ServiceInactive)?;
+    Ok(())
+}
+
+/// Fails with [`ChatServiceError::ServiceInactive`] if no client is connected.
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServer_SendQueueEmpty(server: &FakeChatServer) -> Result<(), ChatServiceError> {
+    let _ = server
+        .0
+        .send_queue_empty()
+        .map_err(|chat::fake::NoConnectedClient| ChatServiceError::ServiceInactive)?;
+    Ok(())
+}
+
+#[cfg(feature = "testing-fns")]
+#[bridge_io(TokioAsyncContext)]
+async fn TESTING_FakeChatServer_ReceiveRequest(server: &FakeChatServer) -> FakeChatServerRequest {
+    let received = server.0.next_request().await;
+    FakeChatServerRequest {
+        request: received.request.clone(),
+        received: AtomicTake::new(received),
+    }
+}
+
+/// Produces the request as an [`HttpRequest`], so it can be inspected with the existing
+/// `TESTING_ChatRequestGet*` functions.
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServerRequest_GetHttpRequest(request: &FakeChatServerRequest) -> HttpRequest {
+    let chat::RequestProto {
+        verb,
+        path,
+        body,
+        headers,
+        id: _,
+    } = request.request.clone();
+    let headers = headers
+        .iter()
+        .map(|header| {
+            let (name, value) = header.split_once(':').expect("header has a name and value");
+            (
+                HeaderName::try_from(name.trim()).expect("valid header name"),
+                HeaderValue::from_str(value.trim()).expect("valid header value"),
+            )
+        })
+        .collect();
+    HttpRequest {
+        method: verb.unwrap_or_default().parse().expect("valid method"),
+        path: path.unwrap_or_default().try_into().expect("valid path"),
+        body: body.map(Vec::into_boxed_slice),
+        headers: std::sync::Mutex::new(headers),
+    }
+}
+
+/// Responds to `request` with `status`, after waiting `delay_millis`.
+///
+/// An empty `body` is sent as no body at all.
+#[cfg(feature = "testing-fns")]
+#[bridge_fn]
+fn TESTING_FakeChatServerRequest_Respond(
+    runtime: &TokioAsyncContext,
+    request: &FakeChatServerRequest,
+    status: u16,
+    body: &[u8],
+    delay_millis: u32,
+) {
+    let received = request
+        .received
+        .take()
+        .expect("a request is only responded to once");
+    let status = http::StatusCode::from_u16(status).expect("valid status code");
+    let body = (!body.is_empty()).then(|| body.to_vec());
+    let delay = Duration::from_millis(delay_millis.into());
+
+    runtime.rt.spawn(async move {
+        tokio::time::sleep(delay).await;
+        received.respond_with_status(status, body)
+    });
+}
+
+/// Wraps a named type and a single-use guard around [`chat::server_requests::EnvelopeResponder`].
 pub struct ServerMessageAck {
-    inner: AtomicTake<chat::server_requests::AckEnvelopeFuture>,
+    inner: AtomicTake<chat::server_requests::EnvelopeResponder>,
 }
 
 impl ServerMessageAck {
-    fn new(send_ack: chat::server_requests::AckEnvelopeFuture) -> Self {
+    fn new(responder: chat::server_requests::EnvelopeResponder) -> Self {
         Self {
-            inner: AtomicTake::new(send_ack),
+            inner: AtomicTake::new(responder),
         }
     }
 }
@@ -463,14 +655,23 @@ impl std::panic::RefUnwindSafe for ServerMessageAck {}
 
 #[bridge_io(TokioAsyncContext)]
 async fn ServerMessageAck_Send(ack: &ServerMessageAck) -> Result<(), ChatServiceError> {
-    let future = ack.inner.take().expect("a message is only acked once");
-    future.await
+    let responder = ack.inner.take().expect("a message is only acked once");
+    responder.ack().await
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn ServerMessageAck_SendNack(ack: &ServerMessageAck) -> Result<(), ChatServiceError> {
+    let responder = ack.inner.take().expect("a message is only acked once");
+    responder.nack().await
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::net::{ConnectionManager, ConnectionManager_set_proxy, Environment};
+    use crate::net::{
+        ConnectionManager, ConnectionManager_set_proxy, ConnectionManager_set_socks_proxy,
+        Environment,
+    };
     use assert_matches::assert_matches;
     use libsignal_net::chat::ChatServiceError;
 
@@ -500,4 +701,65 @@ mod test {
             Err(ChatServiceError::AllConnectionRoutesFailed { .. })
         );
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn cannot_connect_through_socks_proxy_with_partial_credentials() {
+        let cm = ConnectionManager::new(Environment::Staging, "test-user-agent".to_string());
+
+        assert_matches!(
+            ConnectionManager_set_socks_proxy(
+                &cm,
+                "signalfoundation.org".to_string(),
+                1080,
+                Some("user".to_string()),
+                None
+            ),
+            Err(_)
+        );
+
+        let chat = ChatService_new(&cm, "".to_string(), "".to_string());
+        assert_matches!(
+            ChatService_connect_unauth(&chat).await,
+            Err(ChatServiceError::AllConnectionRoutesFailed { .. })
+        );
+    }
+
+    #[cfg(feature = "testing-fns")]
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_send_leaves_connection_usable() {
+        let cm = ConnectionManager::new(Environment::Staging, "test-user-agent".to_string());
+        let server = TESTING_FakeChatServer_New();
+        let chat = TESTING_ChatService_NewWithFakeServer(&cm, &server, "".into(), "".into());
+        ChatService_connect_unauth(&chat).await.expect("connected");
+
+        let request = HttpRequest {
+            method: http::Method::GET,
+            path: PathAndQuery::from_static("/v1/test"),
+            body: None,
+            headers: Default::default(),
+        };
+
+        // Dropping the send future is what cancelling its bridged task does.
+        let abandoned = tokio::select! {
+            result = ChatService_unauth_send(&chat, &request, 10_000) => {
+                panic!("the server hasn't responded yet, but got {result:?}")
+            }
+            received = server.0.next_request() => received,
+        };
+        abandoned.respond_with_status(http::StatusCode::OK, None);
+
+        let (response, ()) =
+            tokio::join!(ChatService_unauth_send(&chat, &request, 10_000), async {
+                server
+                    .0
+                    .next_request()
+                    .await
+                    .respond_with_status(http::StatusCode::NO_CONTENT, None)
+            },);
+        assert_eq!(
+            response.expect("response").status,
+            http::StatusCode::NO_CONTENT
+        );
+        assert_eq!(server.0.connection_count(), 1);
+    }
 }
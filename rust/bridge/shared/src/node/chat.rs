@@ -0,0 +1,110 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::sync::Arc;
+
+use libsignal_net::chat::ChatServiceError;
+use signal_neon_futures::call_method;
+
+use super::*;
+
+use crate::net::chat::{ChatListener, MakeChatListener, ServerMessageAck};
+
+/// Holds on to a JavaScript `ChatListener` so it can be called from any thread.
+#[derive(Clone)]
+pub struct NodeMakeChatListener {
+    js_channel: Channel,
+    listener: Arc<Root<JsObject>>,
+    error_module: Arc<Root<JsObject>>,
+}
+
+impl NodeMakeChatListener {
+    pub(crate) fn new(cx: &mut FunctionContext, listener: Handle<JsObject>) -> NeonResult<Self> {
+        let mut js_channel = cx.channel();
+        // A listener shouldn't keep the process alive on its own.
+        js_channel.unref(cx);
+        Ok(Self {
+            js_channel,
+            listener: Arc::new(listener.root(cx)),
+            error_module: Arc::new(cx.this::<JsObject>()?.root(cx)),
+        })
+    }
+}
+
+impl MakeChatListener for NodeMakeChatListener {
+    fn make_listener(&self) -> Box<dyn ChatListener> {
+        Box::new(NodeChatListener(self.clone()))
+    }
+}
+
+struct NodeChatListener(NodeMakeChatListener);
+
+impl NodeChatListener {
+    /// Calls `method` on the JavaScript listener with the arguments produced by `make_args`.
+    ///
+    /// The call happens asynchronously on the JavaScript thread; there's nobody to report errors
+    /// to, so they're only logged.
+    fn send_to_listener(
+        &self,
+        method: &'static str,
+        make_args: impl for<'a> FnOnce(
+                &mut TaskContext<'a>,
+                Handle<'a, JsObject>,
+            ) -> NeonResult<Vec<Handle<'a, JsValue>>>
+            + Send
+            + 'static,
+    ) {
+        let listener = self.0.listener.clone();
+        let error_module = self.0.error_module.clone();
+        self.0.js_channel.send(move |mut cx| {
+            let result = cx.try_catch(|cx| {
+                let error_module = error_module.to_inner(cx);
+                let args = make_args(cx, error_module)?;
+                let listener = listener.to_inner(cx);
+                call_method(cx, listener, method, args)?;
+                Ok(())
+            });
+            if let Err(e) = result {
+                let description = e
+                    .to_string(&mut cx)
+                    .map(|s| s.value(&mut cx))
+                    .unwrap_or_else(|_| "<unknown>".to_string());
+                log::error!("failed to call chat listener method {method}: {description}");
+            }
+            Ok(())
+        });
+    }
+}
+
+impl ChatListener for NodeChatListener {
+    fn received_incoming_message(
+        &mut self,
+        envelope: Vec<u8>,
+        timestamp: Timestamp,
+        ack: ServerMessageAck,
+    ) {
+        self.send_to_listener("_incoming_message", move |cx, _error_module| {
+            Ok(vec![
+                envelope.convert_into(cx)?.upcast(),
+                timestamp.convert_into(cx)?.upcast(),
+                ack.convert_into(cx)?.upcast(),
+            ])
+        })
+    }
+
+    fn received_queue_empty(&mut self) {
+        self.send_to_listener("_queue_empty", |_cx, _error_module| Ok(vec![]))
+    }
+
+    fn connection_interrupted(&mut self, disconnect_cause: ChatServiceError) {
+        self.send_to_listener("_connection_interrupted", move |cx, error_module| {
+            // SignalNodeError only knows how to throw, so catch the error to pass it along.
+            let error = cx
+                .try_catch(|cx| disconnect_cause.throw(cx, error_module, "connection_interrupted"))
+                .unwrap_or_else(|error| error);
+            Ok(vec![error])
+        })
+    }
+}
@@ -16,7 +16,7 @@ use std::ops::{Deref, DerefMut, RangeInclusive};
 use std::slice;
 
 use crate::io::{InputStream, SyncInputStream};
-use crate::net::chat::ResponseAndDebugInfo;
+use crate::net::chat::{MakeChatListener, ResponseAndDebugInfo};
 use crate::support::{extend_lifetime, Array, AsType, FixedLengthBincodeSerializable, Serialized};
 
 use super::*;
@@ -649,6 +649,24 @@ bridge_trait!(SignedPreKeyStore);
 bridge_trait!(KyberPreKeyStore);
 bridge_trait!(InputStream);
 
+impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context>
+    for &'storage dyn MakeChatListener
+{
+    type ArgType = JsObject;
+    type StoredType = NodeMakeChatListener;
+
+    fn borrow(
+        cx: &mut FunctionContext<'context>,
+        foreign: Handle<'context, Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        NodeMakeChatListener::new(cx, foreign)
+    }
+
+    fn load_from(stored: &'storage mut Self::StoredType) -> Self {
+        stored
+    }
+}
+
 impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context>
     for &'storage mut dyn SyncInputStream
 {
@@ -992,6 +1010,53 @@ impl<'a> ResultTypeInfo<'a> for ResponseAndDebugInfo {
     }
 }
 
+impl<'a> ResultTypeInfo<'a> for SealedSenderDecryptionMetadata {
+    type ResultType = JsObject;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> JsResult<'a, Self::ResultType> {
+        let Self {
+            sender_uuid,
+            sender_e164,
+            sender_device_id,
+            certificate_expiration,
+            timestamp_skew_millis,
+        } = self;
+        let obj = JsObject::new(cx);
+
+        let sender_uuid = cx.string(sender_uuid);
+        let sender_e164 = sender_e164.convert_into(cx)?;
+        let sender_device_id = cx.number(u32::from(sender_device_id));
+        let certificate_expiration = certificate_expiration.convert_into(cx)?;
+        let timestamp_skew_millis = match timestamp_skew_millis {
+            Some(skew) => cx.number(skew as f64).as_value(cx),
+            None => cx.null().as_value(cx),
+        };
+
+        obj.set(cx, "senderUuid", sender_uuid)?;
+        obj.set(cx, "senderE164", sender_e164)?;
+        obj.set(cx, "senderDeviceId", sender_device_id)?;
+        obj.set(cx, "certificateExpiration", certificate_expiration)?;
+        obj.set(cx, "timestampSkewMillis", timestamp_skew_millis)?;
+
+        Ok(obj)
+    }
+}
+
+impl<'a> ResultTypeInfo<'a> for SealedSenderDecryptedMessage {
+    type ResultType = JsObject;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> JsResult<'a, Self::ResultType> {
+        let Self { metadata, message } = self;
+        let obj = JsObject::new(cx);
+
+        let metadata = metadata.convert_into(cx)?;
+        let message = message.convert_into(cx)?;
+
+        obj.set(cx, "metadata", metadata)?;
+        obj.set(cx, "message", message)?;
+
+        Ok(obj)
+    }
+}
+
 impl<'a> ResultTypeInfo<'a> for libsignal_net::cdsi::LookupResponse {
     type ResultType = JsObject;
     fn convert_into(self, cx: &mut impl Context<'a>) -> JsResult<'a, Self::ResultType> {
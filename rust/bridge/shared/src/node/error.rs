@@ -7,6 +7,7 @@ use std::fmt;
 use libsignal_net::chat::ChatServiceError;
 use libsignal_net::svr3::Error as Svr3Error;
 use paste::paste;
+use signal_media::sanitize::gif::{Error as GifError, ParseError as GifParseError};
 use signal_media::sanitize::mp4::{Error as Mp4Error, ParseError as Mp4ParseError};
 use signal_media::sanitize::webp::{Error as WebpError, ParseError as WebpParseError};
 
@@ -141,6 +142,19 @@ const SVR3_REQUEST_FAILED: &str = "SvrRequestFailed";
 const SVR3_RESTORE_FAILED: &str = "SvrRestoreFailed";
 const UNSUPPORTED_MEDIA_INPUT: &str = "UnsupportedMediaInput";
 
+/// Builds the extra properties of a [`RATE_LIMITED_ERROR`].
+fn rate_limited_props<'a>(
+    cx: &mut impl Context<'a>,
+    retry_after: std::time::Duration,
+) -> JsResult<'a, JsObject> {
+    let props = cx.empty_object();
+    let retry_after_secs = u32::try_from(retry_after.as_secs())
+        .unwrap_or(u32::MAX)
+        .convert_into(cx)?;
+    props.set(cx, "retryAfterSecs", retry_after_secs)?;
+    Ok(props)
+}
+
 impl SignalNodeError for neon::result::Throw {
     fn throw<'a>(
         self,
@@ -159,80 +173,169 @@ impl SignalNodeError for SignalProtocolError {
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> JsResult<'a, JsValue> {
-        // Check for some dedicated error types first.
-        let custom_error = match &self {
-            SignalProtocolError::DuplicatedMessage(..) => new_js_error(
+        match new_protocol_js_error(cx, module, &self, operation_name, None)? {
+            Some(error) => cx.throw(error),
+            None => {
+                // Make sure we still throw something.
+                cx.throw_error(&self.to_string())
+            }
+        }
+    }
+}
+
+/// Constructs the JS error for `error`, adding `extra_props` to any properties of its own.
+fn new_protocol_js_error<'a>(
+    cx: &mut impl Context<'a>,
+    module: Handle<'a, JsObject>,
+    error: &SignalProtocolError,
+    operation_name: &str,
+    extra_props: Option<Handle<'a, JsObject>>,
+) -> NeonResult<Option<Handle<'a, JsObject>>> {
+    let message = error.to_string();
+    // Check for some dedicated error types first.
+    let custom_error = match error {
+        SignalProtocolError::DuplicatedMessage(..) => new_js_error(
+            cx,
+            module,
+            Some("DuplicatedMessage"),
+            &message,
+            operation_name,
+            extra_props,
+        ),
+        SignalProtocolError::SealedSenderSelfSend => new_js_error(
+            cx,
+            module,
+            Some("SealedSenderSelfSend"),
+            &message,
+            operation_name,
+            extra_props,
+        ),
+        SignalProtocolError::UntrustedIdentity(addr) => {
+            let props = extra_props.unwrap_or_else(|| cx.empty_object());
+            let addr_string = cx.string(addr.name());
+            props.set(cx, "_addr", addr_string)?;
+            new_js_error(
                 cx,
                 module,
-                Some("DuplicatedMessage"),
-                &self.to_string(),
+                Some("UntrustedIdentity"),
+                &message,
                 operation_name,
-                None,
+                Some(props),
+            )
+        }
+        SignalProtocolError::InvalidRegistrationId(addr, _value) => {
+            let props = extra_props.unwrap_or_else(|| cx.empty_object());
+            let addr = addr.clone().convert_into(cx)?;
+            props.set(cx, "_addr", addr)?;
+            new_js_error(
+                cx,
+                module,
+                Some("InvalidRegistrationId"),
+                &message,
+                operation_name,
+                Some(props),
+            )
+        }
+        SignalProtocolError::InvalidSessionStructure(..) => new_js_error(
+            cx,
+            module,
+            Some("InvalidSession"),
+            &message,
+            operation_name,
+            extra_props,
+        ),
+        SignalProtocolError::InvalidSenderKeySession { distribution_id } => {
+            let props = extra_props.unwrap_or_else(|| cx.empty_object());
+            let distribution_id_str = cx.string(format!("{:x}", distribution_id.as_hyphenated()));
+            props.set(cx, "distribution_id", distribution_id_str)?;
+            new_js_error(
+                cx,
+                module,
+                Some("InvalidSenderKeySession"),
+                &message,
+                operation_name,
+                Some(props),
+            )
+        }
+        _ => new_js_error(cx, module, None, &message, operation_name, extra_props),
+    };
+    Ok(custom_error)
+}
+
+impl SignalNodeError for SealedSenderDecryptionFailure {
+    fn throw<'a>(
+        self,
+        cx: &mut impl Context<'a>,
+        module: Handle<'a, JsObject>,
+        operation_name: &str,
+    ) -> JsResult<'a, JsValue> {
+        let message = self.to_string();
+        let Self { error, metadata } = self;
+        // Every failure that got as far as the sender certificate says who it was from.
+        let props = match metadata {
+            Some(metadata) => {
+                let props = cx.empty_object();
+                let metadata = metadata.convert_into(cx)?;
+                props.set(cx, "sealedSenderMetadata", metadata)?;
+                Some(props)
+            }
+            None => None,
+        };
+        let custom_error = match error {
+            SealedSenderDecryptionError::Protocol(error) => {
+                new_protocol_js_error(cx, module, &error, operation_name, props)?
+            }
+            SealedSenderDecryptionError::UntrustedCertificate => new_js_error(
+                cx,
+                module,
+                Some("SealedSenderUntrustedCertificate"),
+                &message,
+                operation_name,
+                props,
             ),
-            SignalProtocolError::SealedSenderSelfSend => new_js_error(
+            SealedSenderDecryptionError::SelfSend => new_js_error(
                 cx,
                 module,
                 Some("SealedSenderSelfSend"),
-                &self.to_string(),
+                &message,
                 operation_name,
-                None,
+                props,
             ),
-            SignalProtocolError::UntrustedIdentity(addr) => {
-                let props = cx.empty_object();
-                let addr_string = cx.string(addr.name());
-                props.set(cx, "_addr", addr_string)?;
+            SealedSenderDecryptionError::PlaintextTooLarge { size, max_size } => {
+                let props = props.unwrap_or_else(|| cx.empty_object());
+                let size = cx.number(u32::try_from(size).unwrap_or(u32::MAX));
+                let max_size = cx.number(u32::try_from(max_size).unwrap_or(u32::MAX));
+                props.set(cx, "size", size)?;
+                props.set(cx, "maxSize", max_size)?;
                 new_js_error(
                     cx,
                     module,
-                    Some("UntrustedIdentity"),
-                    &self.to_string(),
+                    Some("SealedSenderPlaintextTooLarge"),
+                    &message,
                     operation_name,
                     Some(props),
                 )
             }
-            SignalProtocolError::InvalidRegistrationId(addr, _value) => {
-                let props = cx.empty_object();
-                let addr = addr.clone().convert_into(cx)?;
-                props.set(cx, "_addr", addr)?;
-                new_js_error(
-                    cx,
-                    module,
-                    Some("InvalidRegistrationId"),
-                    &self.to_string(),
-                    operation_name,
-                    Some(props),
-                )
-            }
-            SignalProtocolError::InvalidSessionStructure(..) => new_js_error(
-                cx,
-                module,
-                Some("InvalidSession"),
-                &self.to_string(),
-                operation_name,
-                None,
-            ),
-            SignalProtocolError::InvalidSenderKeySession { distribution_id } => {
-                let props = cx.empty_object();
-                let distribution_id_str =
-                    cx.string(format!("{:x}", distribution_id.as_hyphenated()));
-                props.set(cx, "distribution_id", distribution_id_str)?;
+            SealedSenderDecryptionError::InvalidPadding { bucket_size } => {
+                let props = props.unwrap_or_else(|| cx.empty_object());
+                let bucket_size = cx.number(u32::try_from(bucket_size).unwrap_or(u32::MAX));
+                props.set(cx, "bucketSize", bucket_size)?;
                 new_js_error(
                     cx,
                     module,
-                    Some("InvalidSenderKeySession"),
-                    &self.to_string(),
+                    Some("SealedSenderInvalidPadding"),
+                    &message,
                     operation_name,
                     Some(props),
                 )
             }
-            _ => new_js_error(cx, module, None, &self.to_string(), operation_name, None),
         };
 
         match custom_error {
             Some(error) => cx.throw(error),
             None => {
                 // Make sure we still throw something.
-                cx.throw_error(&self.to_string())
+                cx.throw_error(message)
             }
         }
     }
@@ -258,21 +361,24 @@ impl SignalNodeError for usernames::UsernameError {
         operation_name: &str,
     ) -> JsResult<'a, JsValue> {
         let name = match &self {
-            Self::BadNicknameCharacter => "BadNicknameCharacter",
-            Self::NicknameTooShort => "NicknameTooShort",
-            Self::NicknameTooLong => "NicknameTooLong",
-            Self::NicknameCannotBeEmpty => "NicknameCannotBeEmpty",
-            Self::NicknameCannotStartWithDigit => "CannotStartWithDigit",
-            Self::MissingSeparator => "MissingSeparator",
-            Self::DiscriminatorCannotBeEmpty => "DiscriminatorCannotBeEmpty",
-            Self::DiscriminatorCannotBeZero => "DiscriminatorCannotBeZero",
-            Self::DiscriminatorCannotBeSingleDigit => "DiscriminatorCannotBeSingleDigit",
-            Self::DiscriminatorCannotHaveLeadingZeros => "DiscriminatorCannotHaveLeadingZeros",
-            Self::BadDiscriminatorCharacter => "BadDiscriminatorCharacter",
-            Self::DiscriminatorTooLarge => "DiscriminatorTooLarge",
+            Self::BadNicknameCharacter => Some("BadNicknameCharacter"),
+            Self::NicknameTooShort => Some("NicknameTooShort"),
+            Self::NicknameTooLong => Some("NicknameTooLong"),
+            Self::NicknameCannotBeEmpty => Some("NicknameCannotBeEmpty"),
+            Self::NicknameCannotStartWithDigit => Some("CannotStartWithDigit"),
+            Self::MissingSeparator => Some("MissingSeparator"),
+            Self::DiscriminatorCannotBeEmpty => Some("DiscriminatorCannotBeEmpty"),
+            Self::DiscriminatorCannotBeZero => Some("DiscriminatorCannotBeZero"),
+            Self::DiscriminatorCannotBeSingleDigit => Some("DiscriminatorCannotBeSingleDigit"),
+            Self::DiscriminatorCannotHaveLeadingZeros => {
+                Some("DiscriminatorCannotHaveLeadingZeros")
+            }
+            Self::BadDiscriminatorCharacter => Some("BadDiscriminatorCharacter"),
+            Self::DiscriminatorTooLarge => Some("DiscriminatorTooLarge"),
+            Self::InvalidDiscriminatorRange => None,
         };
         let message = self.to_string();
-        match new_js_error(cx, module, Some(name), &message, operation_name, None) {
+        match new_js_error(cx, module, name, &message, operation_name, None) {
             Some(error) => cx.throw(error),
             None => {
                 // Make sure we still throw something.
@@ -371,6 +477,34 @@ impl SignalNodeError for WebpError {
     }
 }
 
+impl SignalNodeError for GifError {
+    fn throw<'a>(
+        self,
+        cx: &mut impl Context<'a>,
+        module: Handle<'a, JsObject>,
+        operation_name: &str,
+    ) -> JsResult<'a, JsValue> {
+        let name = match &self {
+            GifError::Io(_) => IO_ERROR,
+            GifError::Parse(err) => match err.kind {
+                GifParseError::InvalidBlockLayout
+                | GifParseError::InvalidInput
+                | GifParseError::MissingRequiredBlock(_)
+                | GifParseError::TruncatedBlock => INVALID_MEDIA_INPUT,
+                GifParseError::UnsupportedVersion => UNSUPPORTED_MEDIA_INPUT,
+            },
+        };
+        let message = self.to_string();
+        match new_js_error(cx, module, Some(name), &message, operation_name, None) {
+            Some(error) => cx.throw(error),
+            None => {
+                // Make sure we still throw something.
+                cx.throw_error(&message)
+            }
+        }
+    }
+}
+
 impl SignalNodeError for std::io::Error {
     fn throw<'a>(
         mut self,
@@ -404,15 +538,19 @@ impl SignalNodeError for libsignal_net::chat::ChatServiceError {
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> JsResult<'a, JsValue> {
-        let name = match self {
-            ChatServiceError::ServiceInactive => Some("ChatServiceInactive"),
-            ChatServiceError::AppExpired => Some("AppExpired"),
-            ChatServiceError::DeviceDeregistered => Some("DeviceDelinked"),
+        let (name, extra_props) = match self {
+            ChatServiceError::ServiceInactive => (Some("ChatServiceInactive"), None),
+            ChatServiceError::AppExpired => (Some("AppExpired"), None),
+            ChatServiceError::DeviceDeregistered => (Some("DeviceDelinked"), None),
+            ChatServiceError::RateLimited { retry_after } => (
+                Some(RATE_LIMITED_ERROR),
+                Some(rate_limited_props(cx, retry_after)?),
+            ),
             // TODO: Distinguish retryable errors from proper failures?
-            _ => Some(IO_ERROR),
+            _ => (Some(IO_ERROR), None),
         };
         let message = self.to_string();
-        match new_js_error(cx, module, name, &message, operation_name, None) {
+        match new_js_error(cx, module, name, &message, operation_name, extra_props) {
             Some(error) => cx.throw(error),
             None => {
                 // Make sure we still throw something.
@@ -449,21 +587,15 @@ impl SignalNodeError for libsignal_net::cdsi::LookupError {
         operation_name: &str,
     ) -> JsResult<'a, JsValue> {
         let (name, extra_props) = match self {
-            Self::RateLimited {
-                retry_after_seconds,
-            } => (
+            Self::RateLimited { retry_after } => (
                 Some(RATE_LIMITED_ERROR),
-                Some({
-                    let props = cx.empty_object();
-                    let retry_after = retry_after_seconds.convert_into(cx)?;
-                    props.set(cx, "retryAfterSecs", retry_after)?;
-                    props
-                }),
+                Some(rate_limited_props(cx, retry_after)?),
             ),
             Self::AttestationError(e) => return e.throw(cx, module, operation_name),
             Self::InvalidArgument { server_reason: _ } => (None, None),
             Self::InvalidToken => (Some("CdsiInvalidToken"), None),
             Self::ConnectionTimedOut
+            | Self::RequestTimedOut
             | Self::ConnectTransport(_)
             | Self::WebSocket(_)
             | Self::Protocol
@@ -487,9 +619,10 @@ impl SignalNodeError for libsignal_net::svr3::Error {
         operation_name: &str,
     ) -> JsResult<'a, JsValue> {
         let (name, props) = match self {
-            Svr3Error::Service(_) | Svr3Error::ConnectionTimedOut | Svr3Error::Connect(_) => {
-                (Some(IO_ERROR), None)
-            }
+            Svr3Error::Service(_)
+            | Svr3Error::ConnectionTimedOut
+            | Svr3Error::RequestTimedOut
+            | Svr3Error::Connect(_) => (Some(IO_ERROR), None),
             Svr3Error::AttestationError(inner) => {
                 return inner.throw(cx, module, operation_name);
             }
@@ -504,6 +637,10 @@ impl SignalNodeError for libsignal_net::svr3::Error {
                 }),
             ),
             Svr3Error::DataMissing => (Some(SVR3_DATA_MISSING), None),
+            Svr3Error::RateLimited { retry_after } => (
+                Some(RATE_LIMITED_ERROR),
+                Some(rate_limited_props(cx, retry_after)?),
+            ),
             Svr3Error::Protocol(_) => (None, None),
         };
 
@@ -28,6 +28,9 @@ macro_rules! node_register {
     };
 }
 
+mod chat;
+pub use chat::*;
+
 #[macro_use]
 mod convert;
 pub use convert::*;
@@ -8,7 +8,9 @@ use libsignal_bridge_macros::*;
 use crate::support::*;
 use crate::*;
 use ::attest::svr2::lookup_groupid;
-use ::signal_pin::{local_pin_hash, verify_local_pin_hash, PinHash, Result};
+use ::signal_pin::{
+    local_pin_hash, local_pin_hash_with_params, verify_local_hash, Argon2Params, PinHash, Result,
+};
 use signal_pin::Error;
 
 bridge_handle!(PinHash, node = false);
@@ -48,7 +50,12 @@ pub fn Pin_LocalHash(pin: &[u8]) -> Result<String> {
     local_pin_hash(pin)
 }
 
+#[bridge_fn(node = false)]
+pub fn Pin_LocalHashWithParams(pin: &[u8], memory_kib: u32, iterations: u32) -> Result<String> {
+    local_pin_hash_with_params(pin, &Argon2Params::new(memory_kib, iterations)?)
+}
+
 #[bridge_fn(node = false)]
 pub fn Pin_VerifyLocalHash(encoded_hash: String, pin: &[u8]) -> Result<bool> {
-    verify_local_pin_hash(&encoded_hash, pin)
+    verify_local_hash(&encoded_hash, pin)
 }
@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::time::Duration;
+
 use libsignal_bridge_macros::*;
 use libsignal_protocol::error::Result;
 use libsignal_protocol::*;
@@ -1146,6 +1148,58 @@ async fn SessionCipher_DecryptPreKeySignalMessage(
     .await
 }
 
+/// Converts the list of changed addresses returned by the bulk session operations into one flag
+/// per input address (1 if that address's session was changed, 0 otherwise).
+///
+/// The bridges can't return a list of handles, so the app wrappers use these flags to pick out the
+/// changed addresses from the ones they passed in.
+fn changed_address_flags(addresses: &[ProtocolAddress], changed: &[ProtocolAddress]) -> Vec<u8> {
+    addresses
+        .iter()
+        .map(|address| changed.contains(address).into())
+        .collect()
+}
+
+#[bridge_fn]
+async fn SessionManagement_ArchiveSessions(
+    addresses: &[&ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+) -> Result<Vec<u8>> {
+    let addresses: Vec<ProtocolAddress> = addresses.iter().map(|&a| a.clone()).collect();
+    let archived = archive_sessions(&addresses, session_store).await?;
+    Ok(changed_address_flags(&addresses, &archived))
+}
+
+#[bridge_fn]
+async fn SessionManagement_ExpireSessions(
+    addresses: &[&ProtocolAddress],
+    max_age_seconds: u32,
+    session_store: &mut dyn SessionStore,
+    now: Timestamp,
+) -> Result<Vec<u8>> {
+    let addresses: Vec<ProtocolAddress> = addresses.iter().map(|&a| a.clone()).collect();
+    let expired = expire_sessions(
+        &addresses,
+        Duration::from_secs(max_age_seconds.into()),
+        session_store,
+        now.into(),
+    )
+    .await?;
+    Ok(changed_address_flags(&addresses, &expired))
+}
+
+#[bridge_fn]
+async fn SessionManagement_PrunePreviousSessions(
+    addresses: &[&ProtocolAddress],
+    max_previous_sessions: u32,
+    session_store: &mut dyn SessionStore,
+) -> Result<Vec<u8>> {
+    let addresses: Vec<ProtocolAddress> = addresses.iter().map(|&a| a.clone()).collect();
+    let pruned =
+        prune_previous_sessions(&addresses, max_previous_sessions as usize, session_store).await?;
+    Ok(changed_address_flags(&addresses, &pruned))
+}
+
 #[bridge_fn(node = "SealedSender_Encrypt")]
 async fn SealedSessionCipher_Encrypt(
     destination: &ProtocolAddress,
@@ -1156,6 +1210,19 @@ async fn SealedSessionCipher_Encrypt(
     sealed_sender_encrypt_from_usmc(destination, content, identity_key_store, &mut rng).await
 }
 
+#[cfg(feature = "node")]
+fn sealed_sender_padding(bucket_size: u32) -> Result<SealedSenderPadding> {
+    let bucket_size = std::num::NonZeroUsize::new(bucket_size as usize).ok_or_else(|| {
+        SignalProtocolError::InvalidArgument("padding bucket size must be nonzero".to_string())
+    })?;
+    Ok(SealedSenderPadding::new(bucket_size))
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn SealedSender_PadPlaintext(plaintext: &[u8], bucket_size: u32) -> Result<Vec<u8>> {
+    Ok(sealed_sender_padding(bucket_size)?.pad(plaintext))
+}
+
 #[bridge_fn(jni = "SealedSessionCipher_1MultiRecipientEncrypt", node = false)]
 async fn SealedSender_MultiRecipientEncrypt(
     recipients: &[&ProtocolAddress],
@@ -1253,6 +1320,46 @@ async fn SealedSender_DecryptMessage(
     .await
 }
 
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn(ffi = false, jni = false)]
+async fn SealedSender_DecryptMessageWithOptions(
+    message: &[u8],
+    trust_root: &PublicKey,
+    timestamp: Timestamp,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    kyber_prekey_store: &mut dyn KyberPreKeyStore,
+    max_plaintext_size: Option<u32>,
+    local_time: Option<Timestamp>,
+    padding_bucket_size: Option<u32>,
+) -> std::result::Result<SealedSenderDecryptedMessage, SealedSenderDecryptionFailure> {
+    let options = SealedSenderDecryptOptions {
+        max_plaintext_size: max_plaintext_size.map(|size| size as usize),
+        local_time,
+        padding: padding_bucket_size.map(sealed_sender_padding).transpose()?,
+    };
+    sealed_sender_decrypt_with_options(
+        message,
+        trust_root,
+        timestamp,
+        local_e164,
+        local_uuid,
+        local_device_id.into(),
+        identity_store,
+        session_store,
+        prekey_store,
+        signed_prekey_store,
+        kyber_prekey_store,
+        options,
+    )
+    .await
+}
+
 #[bridge_fn(jni = "GroupSessionBuilder_1CreateSenderKeyDistributionMessage")]
 async fn SenderKeyDistributionMessage_Create(
     sender: &ProtocolAddress,
@@ -116,7 +116,7 @@ fn TESTING_CdsiLookupErrorConvert(
         }
         TestingCdsiLookupError::InvalidResponse => LookupError::InvalidResponse,
         TestingCdsiLookupError::RetryAfter42Seconds => LookupError::RateLimited {
-            retry_after_seconds: 42,
+            retry_after: Duration::from_secs(42),
         },
         TestingCdsiLookupError::InvalidToken => LookupError::InvalidToken,
         TestingCdsiLookupError::InvalidArgument => LookupError::InvalidArgument {
@@ -149,6 +149,7 @@ make_error_testing_enum! {
         AllConnectionRoutesFailed => AllConnectionRoutesFailed,
         ServiceInactive => ServiceInactive,
         ServiceUnavailable => ServiceUnavailable,
+        RateLimited => RetryAfter42Seconds,
     }
 }
 
@@ -183,6 +184,9 @@ fn TESTING_ChatServiceErrorConvert(
         }
         TestingChatServiceError::ServiceInactive => ChatServiceError::ServiceInactive,
         TestingChatServiceError::ServiceUnavailable => ChatServiceError::ServiceUnavailable,
+        TestingChatServiceError::RetryAfter42Seconds => ChatServiceError::RateLimited {
+            retry_after: Duration::from_secs(42),
+        },
     })
 }
 
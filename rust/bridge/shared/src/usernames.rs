@@ -11,8 +11,8 @@ use crate::*;
 
 #[allow(unused_imports)]
 use ::usernames::{
-    create_for_username, decrypt_username, NicknameLimits, Username, UsernameError,
-    UsernameLinkError,
+    create_for_username, decrypt_username, DiscriminatorPolicy, NicknameLimits, Username,
+    UsernameError, UsernameLinkError,
 };
 
 #[bridge_fn]
@@ -49,6 +49,70 @@ pub fn Username_CandidatesFrom(
     Username::candidates_from(&mut rng, &nickname, limits).map(Vec::into_boxed_slice)
 }
 
+/// Generates up to `count` candidates, along with their hashes and proofs.
+///
+/// `ranges` is a sequence of big-endian `u32` triples `(start, end, count)`, each picking `count`
+/// discriminators from `start..end`, in order. If it is empty, the default ranges are used, which
+/// prefer shorter discriminators. `excluded` is a sequence of big-endian `u32` discriminators that
+/// are never picked.
+///
+/// The result is flattened: each candidate contributes its username (as UTF-8), hash, and proof,
+/// in that order.
+#[bridge_fn]
+pub fn Username_CandidatesWithProofsFrom(
+    nickname: String,
+    min_len: u32,
+    max_len: u32,
+    ranges: &[u8],
+    excluded: &[u8],
+    count: u32,
+) -> Result<Box<[Vec<u8>]>, UsernameError> {
+    let mut rng = rand::rngs::OsRng;
+    let limits = NicknameLimits::new(min_len as usize, max_len as usize);
+    let policy = discriminator_policy(ranges, excluded)?.limited_to(count as usize);
+    let candidates = Username::candidates_with_proofs_from(&mut rng, &nickname, limits, &policy)?;
+    Ok(candidates
+        .into_iter()
+        .flat_map(|candidate| {
+            [
+                candidate.username.into_bytes(),
+                candidate.hash.to_vec(),
+                candidate.proof,
+            ]
+        })
+        .collect())
+}
+
+fn discriminator_policy(
+    ranges: &[u8],
+    excluded: &[u8],
+) -> Result<DiscriminatorPolicy, UsernameError> {
+    fn be_u32s(bytes: &[u8]) -> Result<Vec<usize>, UsernameError> {
+        if bytes.len() % 4 != 0 {
+            return Err(UsernameError::InvalidDiscriminatorRange);
+        }
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().expect("correct length")) as usize)
+            .collect())
+    }
+
+    let ranges = be_u32s(ranges)?;
+    if ranges.len() % 3 != 0 {
+        return Err(UsernameError::InvalidDiscriminatorRange);
+    }
+    let policy = if ranges.is_empty() {
+        DiscriminatorPolicy::default()
+    } else {
+        ranges
+            .chunks_exact(3)
+            .try_fold(DiscriminatorPolicy::empty(), |policy, range| {
+                policy.with_range(range[0]..range[1], range[2])
+            })?
+    };
+    Ok(policy.excluding(be_u32s(excluded)?))
+}
+
 #[bridge_fn]
 pub fn Username_HashFromParts(
     nickname: String,
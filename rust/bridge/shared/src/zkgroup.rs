@@ -422,6 +422,39 @@ fn ServerSecretParams_VerifyAuthCredentialPresentation(
     )
 }
 
+/// Parses the group public params that go with each presentation in a batch.
+///
+/// Like the presentations themselves, these should have been validated when they were constructed.
+#[cfg(feature = "jni")]
+fn parse_batch_group_public_params(group_public_params: Vec<&[u8]>) -> Vec<GroupPublicParams> {
+    group_public_params
+        .into_iter()
+        .map(|bytes| zkgroup::deserialize(bytes).expect("should have been parsed previously"))
+        .collect()
+}
+
+#[bridge_fn(ffi = false, node = false)]
+fn ServerSecretParams_VerifyAuthCredentialPresentations(
+    server_secret_params: &ServerSecretParams,
+    group_public_params: Vec<&[u8]>,
+    presentations: Vec<&[u8]>,
+    current_time_in_seconds: Timestamp,
+) -> Result<(), ZkGroupBatchVerificationFailure> {
+    assert_eq!(group_public_params.len(), presentations.len());
+    let group_public_params = parse_batch_group_public_params(group_public_params);
+    let presentations: Vec<_> = presentations
+        .into_iter()
+        .map(|bytes| {
+            AnyAuthCredentialPresentation::new(bytes).expect("should have been parsed previously")
+        })
+        .collect();
+    let batch: Vec<_> = group_public_params
+        .into_iter()
+        .zip(&presentations)
+        .collect();
+    server_secret_params.verify_auth_credential_presentations(&batch, current_time_in_seconds)
+}
+
 #[bridge_fn]
 fn ServerSecretParams_IssueExpiringProfileKeyCredentialDeterministic(
     server_secret_params: &ServerSecretParams,
@@ -458,6 +491,30 @@ fn ServerSecretParams_VerifyProfileKeyCredentialPresentation(
     )
 }
 
+#[bridge_fn(ffi = false, node = false)]
+fn ServerSecretParams_VerifyProfileKeyCredentialPresentations(
+    server_secret_params: &ServerSecretParams,
+    group_public_params: Vec<&[u8]>,
+    presentations: Vec<&[u8]>,
+    current_time_in_seconds: Timestamp,
+) -> Result<(), ZkGroupBatchVerificationFailure> {
+    assert_eq!(group_public_params.len(), presentations.len());
+    let group_public_params = parse_batch_group_public_params(group_public_params);
+    let presentations: Vec<_> = presentations
+        .into_iter()
+        .map(|bytes| {
+            AnyProfileKeyCredentialPresentation::new(bytes)
+                .expect("should have been parsed previously")
+        })
+        .collect();
+    let batch: Vec<_> = group_public_params
+        .into_iter()
+        .zip(&presentations)
+        .collect();
+    server_secret_params
+        .verify_profile_key_credential_presentations(&batch, current_time_in_seconds)
+}
+
 #[bridge_fn]
 fn ServerSecretParams_IssueReceiptCredentialDeterministic(
     server_secret_params: &ServerSecretParams,
@@ -484,6 +541,19 @@ fn ServerSecretParams_VerifyReceiptCredentialPresentation(
     server_secret_params.verify_receipt_credential_presentation(&presentation)
 }
 
+#[bridge_fn(ffi = false, node = false)]
+fn ServerSecretParams_VerifyReceiptCredentialPresentations(
+    server_secret_params: &ServerSecretParams,
+    presentations: Vec<&[u8]>,
+) -> Result<(), ZkGroupBatchVerificationFailure> {
+    let presentations: Vec<ReceiptCredentialPresentation> = presentations
+        .into_iter()
+        .map(|bytes| zkgroup::deserialize(bytes).expect("should have been parsed previously"))
+        .collect();
+    let batch: Vec<_> = presentations.iter().collect();
+    server_secret_params.verify_receipt_credential_presentations(&batch)
+}
+
 // FIXME: Should be bridge_get!
 #[bridge_fn]
 fn GroupPublicParams_GetGroupIdentifier(
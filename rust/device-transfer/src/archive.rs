@@ -0,0 +1,385 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Encrypted framing for the payload sent during a device transfer.
+//!
+//! An archive consists of a header followed by the encrypted payload, split into chunks:
+//!
+//! - The header holds the format version, the [ArchiveCipher], the total length of the payload
+//!   (as a big-endian `u64`), and a random nonce prefix. It is followed by an authentication tag
+//!   over those fields, so a reader can tell right away whether it has the right key.
+//! - Each chunk holds [CHUNK_SIZE] bytes of payload (the last one may be shorter), encrypted with
+//!   the header fields as associated data and followed by its authentication tag.
+//!
+//! Every nonce is the nonce prefix followed by a big-endian `u32` counter, starting at 0 for the
+//! header, so chunks can't be reordered or moved between archives. Since the header fixes the
+//! length of every chunk, truncating an archive or appending to it is detected as well.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use boring::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+/// The current archive format version.
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// The length of the key used to encrypt an archive.
+pub const ARCHIVE_KEY_LEN: usize = 32;
+
+/// The number of payload bytes in each chunk but the last.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+const NONCE_PREFIX_LEN: usize = 8;
+const NONCE_LEN: usize = NONCE_PREFIX_LEN + 4;
+
+/// The length of the authentication tag following the header and each chunk.
+pub const TAG_LEN: usize = 16;
+/// The header fields that are authenticated along with every chunk.
+const HEADER_FIELDS_LEN: usize = 1 + 1 + 8 + NONCE_PREFIX_LEN;
+/// The header uses the first counter value, so the payload can't use all of the rest.
+const MAX_CHUNKS: u64 = u32::MAX as u64 - 1;
+
+/// The total length of the archive header, including its authentication tag.
+pub const HEADER_LEN: usize = HEADER_FIELDS_LEN + TAG_LEN;
+
+/// Cipher used to encrypt an archive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ArchiveCipher {
+    /// AES-256 in GCM mode
+    Aes256Gcm = 1,
+}
+
+impl TryFrom<u8> for ArchiveCipher {
+    type Error = ArchiveError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ArchiveCipher::Aes256Gcm),
+            _ => Err(ArchiveError::UnsupportedCipher(value)),
+        }
+    }
+}
+
+/// Error types for reading and writing archives.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive was written with an unknown format version.
+    UnsupportedVersion(u8),
+    /// The archive was encrypted with an unknown cipher.
+    UnsupportedCipher(u8),
+    /// The archive was encrypted with a different key, or has been modified.
+    AuthenticationFailed,
+    /// More or less payload was written than was declared in the header.
+    LengthMismatch,
+    /// The payload is too long to fit in an archive.
+    PayloadTooLong,
+    /// Failure to read or write the underlying stream.
+    Io(io::Error),
+    /// Internal error in the cryptographic library.
+    InternalError(&'static str),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveError::UnsupportedVersion(v) => write!(f, "Unsupported archive version {}", v),
+            ArchiveError::UnsupportedCipher(c) => write!(f, "Unsupported archive cipher {}", c),
+            ArchiveError::AuthenticationFailed => write!(f, "Archive failed to authenticate"),
+            ArchiveError::LengthMismatch => {
+                write!(f, "Archive payload does not match its declared length")
+            }
+            ArchiveError::PayloadTooLong => write!(f, "Payload is too long for an archive"),
+            ArchiveError::Io(e) => write!(f, "IO error in archive stream ({})", e),
+            ArchiveError::InternalError(s) => write!(f, "Internal error in archive ({})", s),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<ArchiveError> for io::Error {
+    fn from(e: ArchiveError) -> Self {
+        match e {
+            ArchiveError::Io(e) => e,
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// State shared by [ArchiveWriter] and [ArchiveReader].
+struct ChunkCipher {
+    key: [u8; ARCHIVE_KEY_LEN],
+    header_fields: [u8; HEADER_FIELDS_LEN],
+    next_counter: u32,
+}
+
+impl ChunkCipher {
+    fn new(
+        key: &[u8; ARCHIVE_KEY_LEN],
+        total_length: u64,
+        nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, ArchiveError> {
+        if total_length.div_ceil(CHUNK_SIZE as u64) > MAX_CHUNKS {
+            return Err(ArchiveError::PayloadTooLong);
+        }
+        let mut header_fields = [0; HEADER_FIELDS_LEN];
+        header_fields[0] = ARCHIVE_VERSION;
+        header_fields[1] = ArchiveCipher::Aes256Gcm as u8;
+        header_fields[2..10].copy_from_slice(&total_length.to_be_bytes());
+        header_fields[10..].copy_from_slice(&nonce_prefix);
+        Ok(Self {
+            key: *key,
+            header_fields,
+            next_counter: 0,
+        })
+    }
+
+    fn next_nonce(&mut self) -> [u8; NONCE_LEN] {
+        let mut nonce = [0; NONCE_LEN];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.header_fields[10..]);
+        nonce[NONCE_PREFIX_LEN..].copy_from_slice(&self.next_counter.to_be_bytes());
+        // The length check in `new` guarantees the counter never wraps around.
+        self.next_counter += 1;
+        nonce
+    }
+
+    fn seal(&mut self, plaintext: &[u8], tag: &mut [u8; TAG_LEN]) -> Result<Vec<u8>, ArchiveError> {
+        let nonce = self.next_nonce();
+        encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce[..]),
+            &self.header_fields,
+            plaintext,
+            tag,
+        )
+        .map_err(|_| ArchiveError::InternalError("Encryption failed"))
+    }
+
+    fn open(&mut self, ciphertext: &[u8], tag: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+        let nonce = self.next_nonce();
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce[..]),
+            &self.header_fields,
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| ArchiveError::AuthenticationFailed)
+    }
+}
+
+/// Encrypts a payload of known length into an archive.
+///
+/// The header is written when the writer is created. Payload written through the [Write] impl is
+/// buffered until a whole chunk is available; [ArchiveWriter::finish] checks that the whole
+/// payload was written.
+pub struct ArchiveWriter<W: Write> {
+    inner: W,
+    cipher: ChunkCipher,
+    chunk: Vec<u8>,
+    /// Payload bytes that haven't been passed to `write` yet.
+    remaining: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive with a payload of `total_length` bytes, writing the header to `inner`.
+    pub fn new(
+        mut inner: W,
+        key: &[u8; ARCHIVE_KEY_LEN],
+        total_length: u64,
+    ) -> Result<Self, ArchiveError> {
+        let mut nonce_prefix = [0; NONCE_PREFIX_LEN];
+        boring::rand::rand_bytes(&mut nonce_prefix)
+            .map_err(|_| ArchiveError::InternalError("Generating random bytes failed"))?;
+        let mut cipher = ChunkCipher::new(key, total_length, nonce_prefix)?;
+
+        let mut tag = [0; TAG_LEN];
+        cipher.seal(&[], &mut tag)?;
+        inner.write_all(&cipher.header_fields)?;
+        inner.write_all(&tag)?;
+
+        Ok(Self {
+            inner,
+            cipher,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            remaining: total_length,
+        })
+    }
+
+    /// Gets a mutable reference to the underlying stream, e.g. to take what has been written so far.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Checks that the whole payload has been written, and returns the underlying stream.
+    pub fn finish(mut self) -> Result<W, ArchiveError> {
+        if self.remaining != 0 {
+            return Err(ArchiveError::LengthMismatch);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_chunk(&mut self) -> Result<(), ArchiveError> {
+        let mut tag = [0; TAG_LEN];
+        let ciphertext = self.cipher.seal(&self.chunk, &mut tag)?;
+        self.inner.write_all(&ciphertext)?;
+        self.inner.write_all(&tag)?;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ArchiveWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            return Err(ArchiveError::LengthMismatch.into());
+        }
+        let space_in_chunk = CHUNK_SIZE - self.chunk.len();
+        let n = buf
+            .len()
+            .min(space_in_chunk)
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        self.chunk.extend_from_slice(&buf[..n]);
+        self.remaining -= n as u64;
+        if self.chunk.len() == CHUNK_SIZE || self.remaining == 0 {
+            self.write_chunk()?;
+        }
+        Ok(n)
+    }
+
+    /// Flushes the underlying stream.
+    ///
+    /// This doesn't write out a partial chunk, since every chunk but the last must be full.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts the payload of an archive.
+///
+/// The header is read and authenticated when the reader is created. Each chunk is authenticated
+/// before any of its payload is returned from the [Read] impl. After the last chunk, the reader
+/// checks that the underlying stream has ended, so nothing can be appended to an archive.
+pub struct ArchiveReader<R: Read> {
+    inner: R,
+    cipher: ChunkCipher,
+    total_length: u64,
+    /// Decrypted payload of the current chunk, and how much of it has been read.
+    chunk: Vec<u8>,
+    position_in_chunk: usize,
+    /// Payload bytes in chunks that haven't been decrypted yet.
+    remaining: u64,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Reads and authenticates the header of an archive from `inner`.
+    pub fn new(mut inner: R, key: &[u8; ARCHIVE_KEY_LEN]) -> Result<Self, ArchiveError> {
+        let mut header = [0; HEADER_LEN];
+        inner.read_exact(&mut header)?;
+        let (fields, tag) = header.split_at(HEADER_FIELDS_LEN);
+
+        if fields[0] != ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(fields[0]));
+        }
+        let ArchiveCipher::Aes256Gcm = ArchiveCipher::try_from(fields[1])?;
+        let total_length = u64::from_be_bytes(fields[2..10].try_into().expect("correct length"));
+        let nonce_prefix = fields[10..].try_into().expect("correct length");
+
+        let mut cipher = ChunkCipher::new(key, total_length, nonce_prefix)?;
+        cipher.open(&[], tag)?;
+
+        let mut reader = Self {
+            inner,
+            cipher,
+            total_length,
+            chunk: Vec::new(),
+            position_in_chunk: 0,
+            remaining: total_length,
+        };
+        if total_length == 0 {
+            reader.expect_end()?;
+        }
+        Ok(reader)
+    }
+
+    /// The length of the payload, as declared in the header.
+    pub fn total_length(&self) -> u64 {
+        self.total_length
+    }
+
+    /// The number of archive bytes, including the tag, that the next chunk takes up, or `None` if
+    /// every chunk has been read.
+    ///
+    /// This lets a caller that receives an archive in pieces wait for a whole chunk before reading.
+    pub fn next_sealed_chunk_len(&self) -> Option<usize> {
+        (self.remaining != 0).then(|| self.remaining.min(CHUNK_SIZE as u64) as usize + TAG_LEN)
+    }
+
+    /// Gets a mutable reference to the underlying stream, e.g. to add more of the archive to it.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Checks that nothing follows the last chunk.
+    fn expect_end(&mut self) -> Result<(), ArchiveError> {
+        let mut extra = [0; 1];
+        loop {
+            match self.inner.read(&mut extra) {
+                Ok(0) => return Ok(()),
+                Ok(_) => return Err(ArchiveError::LengthMismatch),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn read_chunk(&mut self) -> Result<(), ArchiveError> {
+        let chunk_len = self.remaining.min(CHUNK_SIZE as u64) as usize;
+        let mut sealed = vec![0; chunk_len + TAG_LEN];
+        self.inner
+            .read_exact(&mut sealed)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => ArchiveError::LengthMismatch,
+                _ => e.into(),
+            })?;
+        let (ciphertext, tag) = sealed.split_at(chunk_len);
+        self.chunk = self.cipher.open(ciphertext, tag)?;
+        self.position_in_chunk = 0;
+        self.remaining -= chunk_len as u64;
+        if self.remaining == 0 {
+            self.expect_end()?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ArchiveReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position_in_chunk == self.chunk.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let available = &self.chunk[self.position_in_chunk..];
+        let n = buf.len().min(available.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position_in_chunk += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,146 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Just enough DER encoding for the Ed25519 keys and certificates BoringSSL won't build for us.
+
+pub(crate) const ED25519_SEED_LEN: usize = 32;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+
+/// id-Ed25519 (1.3.101.112)
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+/// id-at-commonName (2.5.4.3)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+/// id-at-organizationName (2.5.4.10)
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+/// id-at-organizationalUnitName (2.5.4.11)
+const OID_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+
+const SECONDS_PER_DAY: libc::time_t = 60 * 60 * 24;
+
+fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let leading_zeros = len_bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (len_bytes.len() - leading_zeros) as u8);
+        out.extend_from_slice(&len_bytes[leading_zeros..]);
+    }
+    out.extend_from_slice(contents);
+    out
+}
+
+fn sequence(elements: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &elements.concat())
+}
+
+fn ed25519_algorithm() -> Vec<u8> {
+    // The parameters are absent for Ed25519 (RFC 8410 section 3).
+    sequence(&[&tlv(TAG_OID, OID_ED25519)])
+}
+
+/// Encodes an Ed25519 private key seed as a PKCS8 PrivateKeyInfo (RFC 8410 section 7).
+pub(crate) fn ed25519_private_key_pkcs8(seed: &[u8; ED25519_SEED_LEN]) -> Vec<u8> {
+    sequence(&[
+        &tlv(TAG_INTEGER, &[0]),
+        &ed25519_algorithm(),
+        &tlv(TAG_OCTET_STRING, &tlv(TAG_OCTET_STRING, seed)),
+    ])
+}
+
+/// Encodes the same name as `build_self_signed_name`.
+fn self_signed_name(common_name: &str) -> Vec<u8> {
+    let attribute = |oid: &[u8], value: &str| {
+        tlv(
+            TAG_SET,
+            &sequence(&[&tlv(TAG_OID, oid), &tlv(TAG_UTF8_STRING, value.as_bytes())]),
+        )
+    };
+    sequence(&[
+        &attribute(OID_COMMON_NAME, common_name),
+        &attribute(OID_ORGANIZATION, "Signal Foundation"),
+        &attribute(OID_ORGANIZATIONAL_UNIT, "Device Transfer"),
+    ])
+}
+
+fn time(timestamp: libc::time_t) -> Vec<u8> {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+    let seconds_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+    let hour = seconds_of_day / 3600;
+    let minute = seconds_of_day / 60 % 60;
+    let second = seconds_of_day % 60;
+    // RFC 5280 section 4.1.2.5: UTCTime through 2049, GeneralizedTime from 2050 on.
+    if (1950..2050).contains(&year) {
+        let year = year % 100;
+        let formatted = format!("{year:02}{month:02}{day:02}{hour:02}{minute:02}{second:02}Z");
+        tlv(TAG_UTC_TIME, formatted.as_bytes())
+    } else {
+        let formatted = format!("{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}Z");
+        tlv(TAG_GENERALIZED_TIME, formatted.as_bytes())
+    }
+}
+
+/// Converts a count of days since the Unix epoch to a (year, month, day) civil date.
+///
+/// This is `civil_from_days` from Howard Hinnant's "chrono-Compatible Low-Level Date Algorithms".
+fn civil_from_days(days: libc::time_t) -> (libc::time_t, libc::time_t, libc::time_t) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + libc::time_t::from(month <= 2);
+    (year, month, day)
+}
+
+/// Encodes a version 1 TBSCertificate to be signed with Ed25519.
+///
+/// Like the RSA certificates, this has serial number 0 and no extensions.
+pub(crate) fn tbs_certificate(
+    common_name: &str,
+    not_before: libc::time_t,
+    not_after: libc::time_t,
+    subject_public_key_info: &[u8],
+) -> Vec<u8> {
+    let name = self_signed_name(common_name);
+    sequence(&[
+        &tlv(TAG_INTEGER, &[0]),
+        &ed25519_algorithm(),
+        &name,
+        &sequence(&[&time(not_before), &time(not_after)]),
+        &name,
+        subject_public_key_info,
+    ])
+}
+
+/// Combines a TBSCertificate with its Ed25519 signature.
+pub(crate) fn certificate(tbs_certificate: &[u8], signature: &[u8]) -> Vec<u8> {
+    // A BIT STRING starts with the number of unused bits in the last byte.
+    let signature_bits = [&[0][..], signature].concat();
+    sequence(&[
+        tbs_certificate,
+        &ed25519_algorithm(),
+        &tlv(TAG_BIT_STRING, &signature_bits),
+    ])
+}
@@ -14,14 +14,18 @@ use std::time::{Duration, SystemTime};
 use boring::asn1::Asn1Time;
 use boring::error::ErrorStack;
 use boring::hash::MessageDigest;
-use boring::pkey::{PKey, Private};
+use boring::pkey::{Id, PKey, Private};
 use boring::rsa::Rsa;
+use boring::sign::Signer;
 use boring::x509::{X509Builder, X509Name, X509NameBuilder, X509};
 
+pub mod archive;
+mod der;
+
 /// Error types for device transfer.
 #[derive(Copy, Clone, Debug)]
 pub enum Error {
-    /// Failure to decode some provided RSA private key.
+    /// Failure to decode some provided private key.
     KeyDecodingFailed,
     /// Internal error in device transfer.
     InternalError(&'static str),
@@ -30,7 +34,7 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::KeyDecodingFailed => write!(f, "Decoding provided RSA private key failed"),
+            Error::KeyDecodingFailed => write!(f, "Decoding provided private key failed"),
             Error::InternalError(s) => write!(f, "Internal error in device transfer ({})", s),
         }
     }
@@ -63,6 +67,17 @@ pub fn create_rsa_private_key(bits: usize, key_format: KeyFormat) -> Result<Vec<
     private_key_to_der(key, key_format)
 }
 
+/// Generate an Ed25519 private key, exported in PKCS8 format.
+///
+/// Ed25519 keys have no key-specific DER format, so unlike [create_rsa_private_key] there is no
+/// choice of [KeyFormat].
+pub fn create_ed25519_private_key() -> Result<Vec<u8>, Error> {
+    let mut seed = [0; der::ED25519_SEED_LEN];
+    boring::rand::rand_bytes(&mut seed)
+        .map_err(|_| Error::InternalError("Generating random bytes failed"))?;
+    Ok(der::ed25519_private_key_pkcs8(&seed))
+}
+
 fn private_key_to_der(key: PKey<Private>, format: KeyFormat) -> Result<Vec<u8>, Error> {
     match format {
         KeyFormat::KeySpecific => key
@@ -76,14 +91,14 @@ fn private_key_to_der(key: PKey<Private>, format: KeyFormat) -> Result<Vec<u8>,
 
 /// Generate a self-signed certificate of name `name`, expiring in `days_to_expire`.
 ///
-/// `rsa_key_pkcs8` should be the output of [create_rsa_private_key].
+/// `key_pkcs8` should be the output of [create_rsa_private_key] or
+/// [create_ed25519_private_key].
 pub fn create_self_signed_cert(
-    rsa_key_pkcs8: &[u8],
+    key_pkcs8: &[u8],
     name: &str,
     days_to_expire: u32,
 ) -> Result<Vec<u8>, Error> {
-    let rsa_key =
-        PKey::private_key_from_der(rsa_key_pkcs8).map_err(|_| Error::KeyDecodingFailed)?;
+    let key = PKey::private_key_from_der(key_pkcs8).map_err(|_| Error::KeyDecodingFailed)?;
 
     let valid_after_timestamp: libc::time_t = (SystemTime::now()
         - Duration::from_secs(60 * 60 * 24))
@@ -93,7 +108,13 @@ pub fn create_self_signed_cert(
     .try_into()
     .map_err(|_| Error::InternalError("Could not generate valid start timestamp"))?;
 
-    let cert = build_cert(rsa_key, name, valid_after_timestamp, days_to_expire)
+    if key.id() == Id::ED25519 {
+        // BoringSSL's X509 builder can't sign with Ed25519, so the certificate is encoded by hand.
+        return build_ed25519_cert(&key, name, valid_after_timestamp, days_to_expire)
+            .map_err(|_| Error::InternalError("Creating certificate failed"));
+    }
+
+    let cert = build_cert(key, name, valid_after_timestamp, days_to_expire)
         .map_err(|_| Error::InternalError("Creating certificate failed"))?;
 
     cert.to_der()
@@ -125,6 +146,25 @@ fn build_cert(
     Ok(cert_builder.build())
 }
 
+fn build_ed25519_cert(
+    key: &PKey<Private>,
+    name: &str,
+    valid_after_timestamp: libc::time_t,
+    days_to_expire: u32,
+) -> Result<Vec<u8>, ErrorStack> {
+    // Match the RSA certificates, which are valid from a day ago until `days_to_expire` from now.
+    let valid_until_timestamp =
+        valid_after_timestamp + 60 * 60 * 24 * (days_to_expire as libc::time_t + 1);
+    let tbs_certificate = der::tbs_certificate(
+        name,
+        valid_after_timestamp,
+        valid_until_timestamp,
+        &key.public_key_to_der()?,
+    );
+    let signature = Signer::new_without_digest(key)?.sign_oneshot_to_vec(&tbs_certificate)?;
+    Ok(der::certificate(&tbs_certificate, &signature))
+}
+
 fn build_self_signed_name(name: &str) -> Result<X509Name, ErrorStack> {
     let mut name_builder = X509NameBuilder::new()?;
     name_builder.append_entry_by_text("CN", name)?;
@@ -0,0 +1,145 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+use device_transfer::archive::*;
+
+const KEY: [u8; ARCHIVE_KEY_LEN] = [0x42; ARCHIVE_KEY_LEN];
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn write_archive(payload: &[u8], write_size: usize) -> Vec<u8> {
+    let mut writer = ArchiveWriter::new(Vec::new(), &KEY, payload.len() as u64).unwrap();
+    for piece in payload.chunks(write_size) {
+        writer.write_all(piece).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+fn read_archive(archive: &[u8], key: &[u8; ARCHIVE_KEY_LEN]) -> std::io::Result<Vec<u8>> {
+    let mut reader = ArchiveReader::new(archive, key).map_err(std::io::Error::from)?;
+    let mut result = Vec::new();
+    reader.read_to_end(&mut result)?;
+    assert_eq!(result.len() as u64, reader.total_length());
+    Ok(result)
+}
+
+#[test]
+fn round_trip() {
+    for len in [
+        0,
+        1,
+        CHUNK_SIZE - 1,
+        CHUNK_SIZE,
+        CHUNK_SIZE + 1,
+        3 * CHUNK_SIZE + 17,
+    ] {
+        for write_size in [1000, CHUNK_SIZE, 5 * CHUNK_SIZE] {
+            let payload = payload(len);
+            let archive = write_archive(&payload, write_size);
+            let chunk_count = len.div_ceil(CHUNK_SIZE);
+            assert_eq!(archive.len(), HEADER_LEN + len + chunk_count * 16);
+            assert_eq!(read_archive(&archive, &KEY).unwrap(), payload);
+        }
+    }
+}
+
+#[test]
+fn reading_as_pieces_arrive() {
+    let payload = payload(3 * CHUNK_SIZE + 17);
+    let archive = write_archive(&payload, CHUNK_SIZE);
+    let (header, rest) = archive.split_at(HEADER_LEN);
+
+    let mut reader = ArchiveReader::new(VecDeque::from(header.to_vec()), &KEY).unwrap();
+    let mut result = Vec::new();
+    for piece in rest.chunks(1000) {
+        reader.get_mut().extend(piece);
+        while let Some(sealed_len) = reader.next_sealed_chunk_len() {
+            if reader.get_mut().len() < sealed_len {
+                break;
+            }
+            let chunk_len = (sealed_len - TAG_LEN) as u64;
+            (&mut reader)
+                .take(chunk_len)
+                .read_to_end(&mut result)
+                .unwrap();
+        }
+    }
+    assert_eq!(reader.next_sealed_chunk_len(), None);
+    assert_eq!(result, payload);
+}
+
+#[test]
+fn wrong_key_is_detected_in_header() {
+    let archive = write_archive(&payload(100), 100);
+    let wrong_key = [0x24; ARCHIVE_KEY_LEN];
+    assert!(matches!(
+        ArchiveReader::new(&archive[..], &wrong_key),
+        Err(ArchiveError::AuthenticationFailed)
+    ));
+}
+
+#[test]
+fn modifications_are_detected() {
+    let archive = write_archive(&payload(2 * CHUNK_SIZE), CHUNK_SIZE);
+
+    // Header field (the total length), payload, and tag.
+    for index in [5, HEADER_LEN + 10, archive.len() - 1] {
+        let mut modified = archive.clone();
+        modified[index] ^= 1;
+        let err = read_archive(&modified, &KEY).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            std::io::ErrorKind::InvalidData,
+            "{index}: {err}"
+        );
+    }
+}
+
+#[test]
+fn truncation_is_detected() {
+    let archive = write_archive(&payload(2 * CHUNK_SIZE), CHUNK_SIZE);
+    for len in [HEADER_LEN, HEADER_LEN + CHUNK_SIZE + 16, archive.len() - 1] {
+        let err = read_archive(&archive[..len], &KEY).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{len}: {err}");
+    }
+}
+
+#[test]
+fn appended_bytes_are_detected() {
+    for len in [0, 10, CHUNK_SIZE, 2 * CHUNK_SIZE + 1] {
+        let mut archive = write_archive(&payload(len), CHUNK_SIZE);
+        archive.push(0);
+        let err = read_archive(&archive, &KEY).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{len}: {err}");
+    }
+}
+
+#[test]
+fn unsupported_version() {
+    let mut archive = write_archive(&payload(10), 10);
+    archive[0] = ARCHIVE_VERSION + 1;
+    assert!(matches!(
+        ArchiveReader::new(&archive[..], &KEY),
+        Err(ArchiveError::UnsupportedVersion(v)) if v == ARCHIVE_VERSION + 1
+    ));
+}
+
+#[test]
+fn writing_wrong_length_fails() {
+    let mut writer = ArchiveWriter::new(Vec::new(), &KEY, 10).unwrap();
+    writer.write_all(&[0; 5]).unwrap();
+    assert!(matches!(writer.finish(), Err(ArchiveError::LengthMismatch)));
+
+    let mut writer = ArchiveWriter::new(Vec::new(), &KEY, 10).unwrap();
+    writer.write_all(&[0; 10]).unwrap();
+    writer
+        .write_all(&[0])
+        .expect_err("cannot write past the declared length");
+}
@@ -8,8 +8,9 @@ use std::cmp::Ordering;
 use std::time::{Duration, SystemTime};
 
 use boring::asn1::Asn1Time;
-use boring::pkey::PKey;
+use boring::pkey::{Id, PKey};
 use boring::rsa::Padding;
+use boring::sign::{Signer, Verifier};
 use boring::x509::X509;
 
 use device_transfer::*;
@@ -73,3 +74,67 @@ fn test_generate_and_parse() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_generate_and_parse_ed25519() -> Result<(), Error> {
+    let key = create_ed25519_private_key()?;
+    let days_to_expire = 10;
+    let cert = create_self_signed_cert(&key, "test", days_to_expire)?;
+
+    println!("key = {}", hex::encode(&key));
+    println!("cert = {}", hex::encode(&cert));
+
+    let boring_key = PKey::private_key_from_der(&key).expect("BoringSSL can parse our private key");
+    assert_eq!(boring_key.id(), Id::ED25519);
+
+    // Try to use the key
+    let message = b"device transfer";
+    let signature = Signer::new_without_digest(&boring_key)
+        .unwrap()
+        .sign_oneshot_to_vec(message)
+        .unwrap();
+
+    let boring_cert = X509::from_der(&cert).expect("BoringSSL can parse our certificate");
+    let pubkey = boring_cert.public_key().expect("Can extract public key");
+    assert!(Verifier::new_without_digest(&pubkey)
+        .unwrap()
+        .verify_oneshot(&signature, message)
+        .unwrap());
+
+    // Self-signature verifies:
+    assert!(boring_cert.verify(&pubkey).unwrap());
+
+    // Cert should be valid an hour ago, to allow for clock skew
+    let one_hour_ago: libc::time_t = (SystemTime::now() - Duration::from_secs(60 * 60))
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Valid duration")
+        .as_secs()
+        .try_into()
+        .expect("Duration seconds should fit in i64");
+    let one_hour_ago = Asn1Time::from_unix(one_hour_ago).expect("Valid timestamp");
+    assert_eq!(
+        Ordering::Less,
+        boring_cert
+            .not_before()
+            .compare(&one_hour_ago)
+            .expect("comparison should not fail")
+    );
+
+    let before_expiration = Asn1Time::days_from_now(days_to_expire - 1).expect("Should not fail");
+    let after_expiration = Asn1Time::days_from_now(days_to_expire + 1).expect("Should not fail");
+    let expires = boring_cert.not_after();
+    assert_eq!(
+        Ordering::Greater,
+        expires
+            .compare(&before_expiration)
+            .expect("comparison should not fail")
+    );
+    assert_eq!(
+        Ordering::Less,
+        expires
+            .compare(&after_expiration)
+            .expect("comparison should not fail")
+    );
+
+    Ok(())
+}
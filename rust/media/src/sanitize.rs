@@ -5,6 +5,8 @@
 
 mod error;
 
+#[cfg(feature = "gif")]
+pub mod gif;
 #[cfg(feature = "mp4san")]
 pub mod mp4;
 #[cfg(feature = "webpsan")]
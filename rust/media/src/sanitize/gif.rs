@@ -0,0 +1,463 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A GIF format sanitizer.
+//!
+//! The sanitizer checks the block structure of a GIF file and copies it to an output, dropping
+//! any blocks that aren't needed to display it (comments, XMP and ICC profiles, and unknown
+//! extensions). Image data isn't decoded, but its framing and dimensions are checked.
+//!
+//! See <https://www.w3.org/Graphics/GIF/spec-gif89a.txt>.
+
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+/// Error type returned by [`sanitize`].
+pub type Error = super::error::SanitizerError<ParseError>;
+
+/// A description of a parse error, along with where in the input it occurred.
+pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// A block's contents don't match its size or type.
+    #[error("invalid block layout")]
+    InvalidBlockLayout,
+    /// The input is not a valid GIF file.
+    #[error("invalid input")]
+    InvalidInput,
+    /// A required block is missing.
+    #[error("missing required `{0}` block")]
+    MissingRequiredBlock(&'static str),
+    /// The input ended in the middle of a block.
+    #[error("truncated block")]
+    TruncatedBlock,
+    /// The GIF version in the header isn't supported.
+    #[error("unsupported GIF version")]
+    UnsupportedVersion,
+}
+
+/// Information about a sanitized GIF file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizedMetadata {
+    /// The width of the logical screen, in pixels.
+    pub width: u16,
+    /// The height of the logical screen, in pixels.
+    pub height: u16,
+    /// The number of images in the file.
+    pub frame_count: usize,
+}
+
+/// Sanitize a GIF input, writing the sanitized file to `output`.
+///
+/// The input is read in a single pass, and only one data sub-block (at most 255 bytes) is held in
+/// memory at a time. Anything after the GIF trailer is not read.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs on either the input or output, an `Error`
+/// is returned. In that case the contents of `output` should be discarded.
+pub fn sanitize(input: impl Read, output: impl Write) -> Result<SanitizedMetadata, Error> {
+    Sanitizer {
+        input,
+        output,
+        offset: 0,
+    }
+    .sanitize()
+}
+
+const SIGNATURE: &[u8; 3] = b"GIF";
+const SUPPORTED_VERSIONS: [&[u8; 3]; 2] = [b"87a", b"89a"];
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+const IMAGE_SEPARATOR: u8 = 0x2C;
+const TRAILER: u8 = 0x3B;
+
+const PLAIN_TEXT_LABEL: u8 = 0x01;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xF9;
+const APPLICATION_LABEL: u8 = 0xFF;
+
+const GRAPHIC_CONTROL_SIZE: u8 = 4;
+const PLAIN_TEXT_SIZE: u8 = 12;
+const APPLICATION_IDENTIFIER_SIZE: u8 = 11;
+
+/// Application extensions that affect how the file is displayed, and so are kept.
+///
+/// Both of these control how many times an animation loops.
+const KEPT_APPLICATIONS: [&[u8; APPLICATION_IDENTIFIER_SIZE as usize]; 2] =
+    [b"NETSCAPE2.0", b"ANIMEXTS1.0"];
+
+/// Flag in the packed fields of the screen and image descriptors for a color table.
+const COLOR_TABLE_FLAG: u8 = 0x80;
+const COLOR_TABLE_SIZE_MASK: u8 = 0x07;
+
+/// The range of valid minimum code sizes for the LZW-compressed image data.
+const LZW_MIN_CODE_SIZES: std::ops::RangeInclusive<u8> = 2..=8;
+
+struct Sanitizer<R, W> {
+    input: R,
+    output: W,
+    /// The number of bytes of the input that have been read.
+    offset: u64,
+}
+
+impl<R: Read, W: Write> Sanitizer<R, W> {
+    fn sanitize(mut self) -> Result<SanitizedMetadata, Error> {
+        let header: [u8; 6] = self.read_array("header")?;
+        let (signature, version) = header.split_at(3);
+        if signature != SIGNATURE {
+            return Err(self.error(ParseError::InvalidInput, "header"));
+        }
+        if !SUPPORTED_VERSIONS.iter().any(|v| v.as_slice() == version) {
+            return Err(self.error(ParseError::UnsupportedVersion, "header"));
+        }
+        self.write(&header)?;
+
+        let screen: [u8; 7] = self.read_array("logical screen descriptor")?;
+        let width = u16::from_le_bytes([screen[0], screen[1]]);
+        let height = u16::from_le_bytes([screen[2], screen[3]]);
+        if width == 0 || height == 0 {
+            return Err(self.error(ParseError::InvalidInput, "logical screen descriptor"));
+        }
+        self.write(&screen)?;
+        let has_global_color_table = self.copy_color_table(screen[4], "global color table")?;
+
+        let mut frame_count = 0;
+        loop {
+            let [introducer] = self.read_array("block introducer")?;
+            match introducer {
+                EXTENSION_INTRODUCER => self.extension()?,
+                IMAGE_SEPARATOR => {
+                    self.image(width, height, has_global_color_table)?;
+                    frame_count += 1;
+                }
+                TRAILER => break,
+                _ => return Err(self.error(ParseError::InvalidBlockLayout, "block introducer")),
+            }
+        }
+
+        if frame_count == 0 {
+            return Err(self.error(
+                ParseError::MissingRequiredBlock("image descriptor"),
+                "trailer",
+            ));
+        }
+        self.write(&[TRAILER])?;
+        self.output.flush().map_err(Error::Io)?;
+
+        Ok(SanitizedMetadata {
+            width,
+            height,
+            frame_count,
+        })
+    }
+
+    fn extension(&mut self) -> Result<(), Error> {
+        let [label] = self.read_array("extension label")?;
+        match label {
+            GRAPHIC_CONTROL_LABEL => {
+                self.write(&[EXTENSION_INTRODUCER, label])?;
+                self.copy_fixed_size_block(GRAPHIC_CONTROL_SIZE, "graphic control extension")?;
+                self.copy_sub_blocks(true, "graphic control extension")?;
+            }
+            PLAIN_TEXT_LABEL => {
+                self.write(&[EXTENSION_INTRODUCER, label])?;
+                self.copy_fixed_size_block(PLAIN_TEXT_SIZE, "plain text extension")?;
+                self.copy_sub_blocks(true, "plain text extension")?;
+            }
+            APPLICATION_LABEL => {
+                const CONTEXT: &str = "application extension";
+                let [size] = self.read_array(CONTEXT)?;
+                if size != APPLICATION_IDENTIFIER_SIZE {
+                    return Err(self.error(ParseError::InvalidBlockLayout, CONTEXT));
+                }
+                let identifier: [u8; APPLICATION_IDENTIFIER_SIZE as usize] =
+                    self.read_array(CONTEXT)?;
+                let keep = KEPT_APPLICATIONS.contains(&&identifier);
+                if keep {
+                    self.write(&[EXTENSION_INTRODUCER, label, size])?;
+                    self.write(&identifier)?;
+                }
+                self.copy_sub_blocks(keep, CONTEXT)?;
+            }
+            // Comments and unknown extensions don't affect display.
+            _ => self.copy_sub_blocks(false, "extension")?,
+        }
+        Ok(())
+    }
+
+    fn image(
+        &mut self,
+        screen_width: u16,
+        screen_height: u16,
+        has_global_color_table: bool,
+    ) -> Result<(), Error> {
+        const CONTEXT: &str = "image descriptor";
+        let descriptor: [u8; 9] = self.read_array(CONTEXT)?;
+        let [left, top, width, height] =
+            [0, 2, 4, 6].map(|i| u16::from_le_bytes([descriptor[i], descriptor[i + 1]]));
+        let fits = |start: u16, len: u16, screen_len: u16| {
+            len != 0 && u32::from(start) + u32::from(len) <= u32::from(screen_len)
+        };
+        if !fits(left, width, screen_width) || !fits(top, height, screen_height) {
+            return Err(self.error(ParseError::InvalidInput, CONTEXT));
+        }
+        self.write(&[IMAGE_SEPARATOR])?;
+        self.write(&descriptor)?;
+
+        let has_local_color_table = self.copy_color_table(descriptor[8], "local color table")?;
+        if !has_global_color_table && !has_local_color_table {
+            return Err(self.error(ParseError::MissingRequiredBlock("color table"), CONTEXT));
+        }
+
+        const DATA_CONTEXT: &str = "image data";
+        let [lzw_min_code_size] = self.read_array(DATA_CONTEXT)?;
+        if !LZW_MIN_CODE_SIZES.contains(&lzw_min_code_size) {
+            return Err(self.error(ParseError::InvalidInput, DATA_CONTEXT));
+        }
+        self.write(&[lzw_min_code_size])?;
+        self.copy_sub_blocks(true, DATA_CONTEXT)
+    }
+
+    /// Copies the color table described by the `packed` fields, if there is one.
+    ///
+    /// Returns whether there was a color table.
+    fn copy_color_table(&mut self, packed: u8, context: &'static str) -> Result<bool, Error> {
+        if packed & COLOR_TABLE_FLAG == 0 {
+            return Ok(false);
+        }
+        let entries = 2usize << (packed & COLOR_TABLE_SIZE_MASK);
+        let mut table = vec![0; 3 * entries];
+        self.read_exact(&mut table, context)?;
+        self.write(&table)?;
+        Ok(true)
+    }
+
+    /// Copies a block that must be exactly `expected_size` bytes long, including its size byte.
+    fn copy_fixed_size_block(
+        &mut self,
+        expected_size: u8,
+        context: &'static str,
+    ) -> Result<(), Error> {
+        let [size] = self.read_array(context)?;
+        if size != expected_size {
+            return Err(self.error(ParseError::InvalidBlockLayout, context));
+        }
+        let mut block = [0; u8::MAX as usize];
+        let block = &mut block[..usize::from(size)];
+        self.read_exact(block, context)?;
+        self.write(&[size])?;
+        self.write(block)
+    }
+
+    /// Reads a sequence of data sub-blocks up to and including the terminating empty block,
+    /// copying them to the output if `keep` is true.
+    fn copy_sub_blocks(&mut self, keep: bool, context: &'static str) -> Result<(), Error> {
+        let mut block = [0; u8::MAX as usize];
+        loop {
+            let [size] = self.read_array(context)?;
+            let block = &mut block[..usize::from(size)];
+            self.read_exact(block, context)?;
+            if keep {
+                self.write(&[size])?;
+                self.write(block)?;
+            }
+            if size == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self, context: &'static str) -> Result<[u8; N], Error> {
+        let mut buf = [0; N];
+        self.read_exact(&mut buf, context)?;
+        Ok(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8], context: &'static str) -> Result<(), Error> {
+        match self.input.read_exact(buf) {
+            Ok(()) => {
+                self.offset += buf.len() as u64;
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(self.error(ParseError::TruncatedBlock, context))
+            }
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.output.write_all(bytes).map_err(Error::Io)
+    }
+
+    fn error(&self, kind: ParseError, context: impl Display) -> Error {
+        Error::Parse(ParseErrorReport {
+            kind,
+            report: format!("while parsing {context} at offset {}", self.offset),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A 2x1 image with a two-color global color table.
+    const HEADER: &[u8] = &[
+        b'G', b'I', b'F', b'8', b'9', b'a', // header
+        2, 0, 1, 0, 0x80, 0, 0, // logical screen descriptor
+        0, 0, 0, 0xff, 0xff, 0xff, // global color table
+    ];
+    const GRAPHIC_CONTROL: &[u8] = &[0x21, 0xf9, 4, 0, 10, 0, 0, 0];
+    const LOOP: &[u8] = &[
+        0x21, 0xff, 11, b'N', b'E', b'T', b'S', b'C', b'A', b'P', b'E', b'2', b'.', b'0', 3, 1, 0,
+        0, 0,
+    ];
+    const IMAGE: &[u8] = &[
+        0x2c, 0, 0, 0, 0, 2, 0, 1, 0, 0, // image descriptor
+        2, 2, 0x44, 0x01, 0, // image data
+    ];
+    const COMMENT: &[u8] = &[0x21, 0xfe, 5, b'h', b'e', b'l', b'l', b'o', 0];
+    const XMP: &[u8] = &[
+        0x21, 0xff, 11, b'X', b'M', b'P', b' ', b'D', b'a', b't', b'a', b'X', b'M', b'P', 2, b'<',
+        b'>', 0,
+    ];
+    const TRAILER: &[u8] = &[0x3b];
+
+    fn sanitize_parts(parts: &[&[u8]]) -> Result<(SanitizedMetadata, Vec<u8>), Error> {
+        let input = parts.concat();
+        let mut output = Vec::new();
+        let metadata = sanitize(input.as_slice(), &mut output)?;
+        Ok((metadata, output))
+    }
+
+    fn expect_parse_error<T: std::fmt::Debug>(result: Result<T, Error>) -> ParseError {
+        match result {
+            Err(Error::Parse(report)) => report.kind,
+            other => panic!("expected parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn minimal() {
+        let parts = [HEADER, IMAGE, TRAILER];
+        let (metadata, output) = sanitize_parts(&parts).expect("valid");
+        assert_eq!(
+            metadata,
+            SanitizedMetadata {
+                width: 2,
+                height: 1,
+                frame_count: 1
+            }
+        );
+        assert_eq!(output, parts.concat());
+    }
+
+    #[test]
+    fn keeps_animation_blocks() {
+        let parts = [
+            HEADER,
+            LOOP,
+            GRAPHIC_CONTROL,
+            IMAGE,
+            GRAPHIC_CONTROL,
+            IMAGE,
+            TRAILER,
+        ];
+        let (metadata, output) = sanitize_parts(&parts).expect("valid");
+        assert_eq!(metadata.frame_count, 2);
+        assert_eq!(output, parts.concat());
+    }
+
+    #[test]
+    fn strips_metadata() {
+        let (_, output) =
+            sanitize_parts(&[HEADER, COMMENT, XMP, IMAGE, COMMENT, TRAILER]).expect("valid");
+        assert_eq!(output, [HEADER, IMAGE, TRAILER].concat());
+    }
+
+    #[test]
+    fn ignores_data_after_trailer() {
+        let (_, output) =
+            sanitize_parts(&[HEADER, IMAGE, TRAILER, b"garbage".as_slice()]).expect("valid");
+        assert_eq!(output, [HEADER, IMAGE, TRAILER].concat());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let input = [HEADER, IMAGE, TRAILER].concat();
+        for len in 0..input.len() {
+            assert_eq!(
+                expect_parse_error(sanitize(&input[..len], io::sink())),
+                ParseError::TruncatedBlock,
+                "truncated to {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut header = HEADER.to_vec();
+        header[3..6].copy_from_slice(b"90a");
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[header.as_slice(), IMAGE, TRAILER])),
+            ParseError::UnsupportedVersion
+        );
+    }
+
+    #[test]
+    fn rejects_missing_image() {
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[HEADER, TRAILER])),
+            ParseError::MissingRequiredBlock("image descriptor")
+        );
+    }
+
+    #[test]
+    fn rejects_image_outside_screen() {
+        let mut image = IMAGE.to_vec();
+        // Move the image one pixel to the right.
+        image[1] = 1;
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[HEADER, image.as_slice(), TRAILER])),
+            ParseError::InvalidInput
+        );
+    }
+
+    #[test]
+    fn rejects_empty_screen() {
+        let mut header = HEADER.to_vec();
+        // Zero width.
+        header[6] = 0;
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[header.as_slice(), IMAGE, TRAILER])),
+            ParseError::InvalidInput
+        );
+    }
+
+    #[test]
+    fn rejects_bad_graphic_control_size() {
+        let mut graphic_control = GRAPHIC_CONTROL.to_vec();
+        graphic_control[2] = 5;
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[
+                HEADER,
+                graphic_control.as_slice(),
+                IMAGE,
+                TRAILER
+            ])),
+            ParseError::InvalidBlockLayout
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_block() {
+        assert_eq!(
+            expect_parse_error(sanitize_parts(&[HEADER, [0x00].as_slice(), IMAGE, TRAILER])),
+            ParseError::InvalidBlockLayout
+        );
+    }
+}
@@ -3,6 +3,11 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use mediasan_common::Skip;
+use webpsan::{sanitize_with_config, Config};
+
 pub use webpsan::parse::ParseError;
 pub use webpsan::sanitize;
 
@@ -11,3 +16,517 @@ pub type Error = super::error::SanitizerError<ParseError>;
 
 /// A decomposed and stringified [`error_stack::Report<ParseError>`](mediasan_common::Error::Parse).
 pub type ParseErrorReport = super::error::ParseErrorReport<ParseError>;
+
+/// Information about a WebP file sanitized by [`sanitize_and_strip_metadata`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizedMetadata {
+    /// The width of the canvas, in pixels.
+    pub width: u32,
+    /// The height of the canvas, in pixels.
+    pub height: u32,
+    /// The number of frames in the file; 1 unless it is animated.
+    pub frame_count: usize,
+}
+
+/// Sanitize a WebP input, writing a copy of it without metadata to `output`.
+///
+/// The input is checked with [`sanitize`] and copied to `output` as it is read, with its EXIF and
+/// XMP chunks, along with any unknown top-level chunks, left out and the corresponding flags in
+/// the extended header cleared. The bodies of the chunks that are left out are skipped rather than
+/// read, and no more than a chunk header is held in memory at a time. Once the input has been
+/// read, `output` is seeked back to fill in the length of the stripped file.
+///
+/// # Errors
+///
+/// If the input cannot be parsed, or an IO error occurs on either the input or output, an `Error`
+/// is returned. In that case the contents of `output` should be discarded.
+pub fn sanitize_and_strip_metadata<R: Read + Skip, W: Write + Seek>(
+    input: R,
+    output: W,
+) -> Result<SanitizedMetadata, Error> {
+    let mut stripper = Stripper {
+        input,
+        output,
+        position: 0,
+        state: State::RiffHeader,
+        header: Vec::with_capacity(RIFF_HEADER_LEN),
+        riff_remaining: 0,
+        stripped_len: 0,
+        first_chunk: None,
+        frame_count: 0,
+    };
+    let config = Config::builder().allow_unknown_chunks(true).build();
+    sanitize_with_config(&mut stripper, config)?;
+    stripper.finish()
+}
+
+const RIFF_HEADER_LEN: usize = 12;
+const CHUNK_HEADER_LEN: usize = 8;
+
+/// Chunks needed to display the image; everything else is dropped.
+const KEPT_CHUNKS: [&[u8; 4]; 7] = [
+    b"VP8X", b"VP8 ", b"VP8L", b"ALPH", b"ANIM", b"ANMF", b"ICCP",
+];
+
+const EXIF_FLAG: u8 = 0x08;
+const XMP_FLAG: u8 = 0x04;
+
+/// How much of the first chunk is kept to read the canvas size from.
+const IMAGE_HEADER_LEN: usize = 10;
+
+/// The most that is read at once when [`sanitize`] skips over data that is being copied.
+const COPY_BUFFER_LEN: usize = 8 * 1024;
+
+/// Copies the input to `output` as [`sanitize`] reads it, leaving out the chunks that aren't
+/// needed to display the image.
+///
+/// This follows the top-level chunk structure of the input byte by byte, independent of how
+/// [`sanitize`] reads it, so it doesn't rely on the sanitizer reading one chunk at a time.
+struct Stripper<R, W> {
+    input: R,
+    output: W,
+    /// How many bytes of the input have been read or skipped.
+    position: u64,
+    state: State,
+    /// The part of the RIFF or chunk header being read.
+    header: Vec<u8>,
+    /// How many bytes of the RIFF chunk's contents haven't been reached yet.
+    riff_remaining: u64,
+    /// How many bytes of kept chunks have been written, including their headers and padding.
+    stripped_len: u64,
+    /// The type and start of the first chunk, which has the canvas size.
+    first_chunk: Option<([u8; 4], Vec<u8>)>,
+    frame_count: usize,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    RiffHeader,
+    ChunkHeader,
+    /// In the body or padding of a chunk.
+    ChunkBody {
+        fourcc: [u8; 4],
+        len: u32,
+        offset: u64,
+        first: bool,
+    },
+    /// Past the end of the RIFF chunk; anything here is rejected by [`sanitize`].
+    End,
+}
+
+impl<R: Read + Skip, W: Write + Seek> Stripper<R, W> {
+    /// How many more bytes the current state covers, if it's known.
+    fn state_remaining(&self) -> Option<u64> {
+        match self.state {
+            State::RiffHeader => Some((RIFF_HEADER_LEN - self.header.len()) as u64),
+            State::ChunkHeader => Some((CHUNK_HEADER_LEN - self.header.len()) as u64),
+            State::ChunkBody { len, offset, .. } => Some(padded_len(len) - offset),
+            State::End => None,
+        }
+    }
+
+    /// Whether the bytes of the current state are left out of the output.
+    fn dropping(&self) -> bool {
+        match self.state {
+            State::ChunkBody { fourcc, .. } => !KEPT_CHUNKS.contains(&&fourcc),
+            State::End => true,
+            State::RiffHeader | State::ChunkHeader => false,
+        }
+    }
+
+    /// Follows the chunk structure through `bytes`, which come next in the input, copying the
+    /// parts that are kept to the output.
+    fn consume(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        while !bytes.is_empty() {
+            let Some(remaining) = self.state_remaining() else {
+                return Ok(());
+            };
+            let (taken, rest) = bytes.split_at(bytes.len().min(remaining as usize));
+            bytes = rest;
+            match &mut self.state {
+                State::RiffHeader | State::ChunkHeader => self.header.extend_from_slice(taken),
+                State::ChunkBody {
+                    fourcc,
+                    len,
+                    offset,
+                    first,
+                } => {
+                    let start = *offset;
+                    *offset += taken.len() as u64;
+                    let (fourcc, len, first) = (*fourcc, *len, *first);
+                    self.body(fourcc, len, start, first, taken)?;
+                }
+                State::End => unreachable!("has no known length"),
+            }
+            if !matches!(self.state, State::RiffHeader) {
+                self.riff_remaining = self.riff_remaining.saturating_sub(taken.len() as u64);
+            }
+            if taken.len() as u64 == remaining {
+                self.next_state()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies part of a chunk body, from `start` bytes into the chunk, if the chunk is kept.
+    fn body(
+        &mut self,
+        fourcc: [u8; 4],
+        len: u32,
+        start: u64,
+        first: bool,
+        bytes: &[u8],
+    ) -> io::Result<()> {
+        if first {
+            if let Some((_, image_header)) = &mut self.first_chunk {
+                let wanted = IMAGE_HEADER_LEN.saturating_sub(image_header.len());
+                image_header.extend_from_slice(&bytes[..bytes.len().min(wanted)]);
+            }
+        }
+        if !KEPT_CHUNKS.contains(&&fourcc) {
+            return Ok(());
+        }
+        // Padding is always written as zeros, and only the data before it is copied.
+        let data_len = (u64::from(len).saturating_sub(start) as usize).min(bytes.len());
+        let (data, padding) = bytes.split_at(data_len);
+        match data.split_first() {
+            Some((flags, rest)) if start == 0 && &fourcc == b"VP8X" => {
+                self.output.write_all(&[flags & !(EXIF_FLAG | XMP_FLAG)])?;
+                self.output.write_all(rest)?;
+            }
+            _ => self.output.write_all(data)?,
+        }
+        if !padding.is_empty() {
+            self.output.write_all(&[0])?;
+        }
+        self.stripped_len += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Moves past a complete header or chunk.
+    fn next_state(&mut self) -> io::Result<()> {
+        self.state = match self.state {
+            State::RiffHeader => {
+                let riff_len = le_u32(&self.header[4..8]);
+                self.riff_remaining = u64::from(riff_len).saturating_sub(4);
+                // The length is filled in by `finish`.
+                self.output.write_all(&self.header[..4])?;
+                self.output.write_all(&[0; 4])?;
+                self.output.write_all(&self.header[8..])?;
+                State::ChunkHeader
+            }
+            State::ChunkHeader => {
+                let fourcc: [u8; 4] = self.header[..4].try_into().expect("correct length");
+                let len = le_u32(&self.header[4..]);
+                if KEPT_CHUNKS.contains(&&fourcc) {
+                    self.output.write_all(&self.header)?;
+                    self.stripped_len += CHUNK_HEADER_LEN as u64;
+                }
+                if &fourcc == b"ANMF" {
+                    self.frame_count += 1;
+                }
+                let first = self.first_chunk.is_none();
+                if first {
+                    self.first_chunk = Some((fourcc, Vec::with_capacity(IMAGE_HEADER_LEN)));
+                }
+                State::ChunkBody {
+                    fourcc,
+                    len,
+                    offset: 0,
+                    first,
+                }
+            }
+            State::ChunkBody { .. } => State::ChunkHeader,
+            State::End => State::End,
+        };
+        self.header.clear();
+        if matches!(self.state, State::ChunkHeader) && self.riff_remaining == 0 {
+            self.state = State::End;
+        }
+        if let State::ChunkBody { len, .. } = self.state {
+            // An empty chunk has no body to wait for.
+            if padded_len(len) == 0 {
+                self.next_state()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills in the length of the stripped file, once all of the input has been sanitized.
+    fn finish(mut self) -> Result<SanitizedMetadata, Error> {
+        if !matches!(self.state, State::End) {
+            return Err(truncated("RIFF chunk"));
+        }
+        let stripped_len = u32::try_from(4 + self.stripped_len).expect("no longer than the input");
+        self.output.seek(SeekFrom::Start(4)).map_err(Error::Io)?;
+        self.output
+            .write_all(&stripped_len.to_le_bytes())
+            .map_err(Error::Io)?;
+        self.output.seek(SeekFrom::End(0)).map_err(Error::Io)?;
+        self.output.flush().map_err(Error::Io)?;
+
+        let (fourcc, image_header) = self
+            .first_chunk
+            .as_ref()
+            .ok_or_else(|| truncated("image data"))?;
+        image_metadata(fourcc, image_header, self.frame_count.max(1))
+    }
+}
+
+impl<R: Read + Skip, W: Write + Seek> Read for Stripper<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount_read = self.input.read(buf)?;
+        self.position += amount_read as u64;
+        self.consume(&buf[..amount_read])?;
+        Ok(amount_read)
+    }
+}
+
+impl<R: Read + Skip, W: Write + Seek> Skip for Stripper<R, W> {
+    fn skip(&mut self, mut amount: u64) -> io::Result<()> {
+        let mut buf = [0; COPY_BUFFER_LEN];
+        while amount > 0 {
+            let remaining = self.state_remaining().unwrap_or(amount).min(amount);
+            if self.dropping() {
+                // Nothing here is written, so there's no need to read it.
+                self.input.skip(remaining)?;
+                self.position += remaining;
+                amount -= remaining;
+                if let State::ChunkBody { offset, .. } = &mut self.state {
+                    *offset += remaining;
+                    self.riff_remaining = self.riff_remaining.saturating_sub(remaining);
+                    if self.state_remaining() == Some(0) {
+                        self.next_state()?;
+                    }
+                }
+                continue;
+            }
+            let wanted = (remaining as usize).min(buf.len());
+            let amount_read = self.input.read(&mut buf[..wanted])?;
+            if amount_read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "skipped past the end of the input",
+                ));
+            }
+            self.position += amount_read as u64;
+            amount -= amount_read as u64;
+            self.consume(&buf[..amount_read])?;
+        }
+        Ok(())
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> io::Result<u64> {
+        self.input.stream_len()
+    }
+}
+
+/// The length of a chunk body with the padding that keeps chunks at even offsets.
+fn padded_len(len: u32) -> u64 {
+    u64::from(len) + u64::from(len % 2)
+}
+
+/// Reads the canvas size from the extended header, or the image data if there isn't one.
+fn image_metadata(
+    fourcc: &[u8; 4],
+    image_header: &[u8],
+    frame_count: usize,
+) -> Result<SanitizedMetadata, Error> {
+    let (width, height) = match fourcc {
+        b"VP8X" => {
+            let canvas = image_header.get(4..10).ok_or_else(|| truncated("VP8X"))?;
+            (le_u24(&canvas[..3]) + 1, le_u24(&canvas[3..]) + 1)
+        }
+        b"VP8 " => {
+            let frame_header = image_header.get(6..10).ok_or_else(|| truncated("VP8"))?;
+            (
+                le_u32(&[frame_header[0], frame_header[1] & 0x3f, 0, 0]),
+                le_u32(&[frame_header[2], frame_header[3] & 0x3f, 0, 0]),
+            )
+        }
+        _ => {
+            let sizes = image_header.get(1..5).ok_or_else(|| truncated("VP8L"))?;
+            let sizes = le_u32(sizes);
+            ((sizes & 0x3fff) + 1, ((sizes >> 14) & 0x3fff) + 1)
+        }
+    };
+    Ok(SanitizedMetadata {
+        width,
+        height,
+        frame_count,
+    })
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().expect("correct length"))
+}
+
+fn le_u24(bytes: &[u8]) -> u32 {
+    le_u32(&[bytes[0], bytes[1], bytes[2], 0])
+}
+
+fn truncated(context: &str) -> Error {
+    Error::Parse(ParseErrorReport {
+        kind: ParseError::TruncatedChunk,
+        report: format!("while stripping metadata from {context}"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A 1x1 lossless image.
+    const VP8L: &[u8] = &[
+        b'V', b'P', b'8', b'L', 8, 0, 0, 0, // chunk header
+        0x2f, 0, 0, 0, 0, 0x88, 0x88, 8, // VP8L data
+    ];
+    const EXIF: &[u8] = &[b'E', b'X', b'I', b'F', 3, 0, 0, 0, 1, 2, 3, 0];
+    const XMP: &[u8] = &[b'X', b'M', b'P', b' ', 2, 0, 0, 0, b'<', b'>'];
+    const UNKNOWN: &[u8] = &[b'A', b'B', b'C', b'D', 1, 0, 0, 0, 7, 0];
+
+    /// An extended header for a 1x1 canvas with the given flags.
+    fn vp8x(flags: u8) -> Vec<u8> {
+        [b"VP8X".as_slice(), &[10, 0, 0, 0, flags, 0, 0, 0], &[0; 6]].concat()
+    }
+
+    fn webp(chunks: &[&[u8]]) -> Vec<u8> {
+        let chunks = chunks.concat();
+        let riff_len = u32::try_from(4 + chunks.len()).expect("small");
+        [
+            b"RIFF".as_slice(),
+            &riff_len.to_le_bytes(),
+            b"WEBP",
+            &chunks,
+        ]
+        .concat()
+    }
+
+    fn strip(input: &[u8]) -> Result<(SanitizedMetadata, Vec<u8>), Error> {
+        let mut output = Cursor::new(Vec::new());
+        let metadata = sanitize_and_strip_metadata(Cursor::new(input), &mut output)?;
+        Ok((metadata, output.into_inner()))
+    }
+
+    #[test]
+    fn minimal() {
+        let input = webp(&[VP8L]);
+        let (metadata, output) = strip(&input).expect("valid");
+        assert_eq!(
+            metadata,
+            SanitizedMetadata {
+                width: 1,
+                height: 1,
+                frame_count: 1,
+            }
+        );
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn strips_metadata() {
+        let input = webp(&[&vp8x(EXIF_FLAG | XMP_FLAG), VP8L, EXIF, XMP]);
+        let (_, output) = strip(&input).expect("valid");
+        assert_eq!(output, webp(&[&vp8x(0), VP8L]));
+    }
+
+    #[test]
+    fn strips_unknown_chunks() {
+        let input = webp(&[&vp8x(0), VP8L, UNKNOWN]);
+        let (_, output) = strip(&input).expect("valid");
+        assert_eq!(output, webp(&[&vp8x(0), VP8L]));
+    }
+
+    #[test]
+    fn skips_metadata_without_reading_it() {
+        let large_exif = [b"EXIF".as_slice(), &4096u32.to_le_bytes(), &[0xAB; 4096]].concat();
+        let input = webp(&[&vp8x(EXIF_FLAG), VP8L, &large_exif]);
+        let mut counted = CountingInput {
+            inner: Cursor::new(&input),
+            amount_read: 0,
+        };
+        let mut output = Cursor::new(Vec::new());
+        sanitize_and_strip_metadata(&mut counted, &mut output).expect("valid");
+        assert_eq!(output.into_inner(), webp(&[&vp8x(0), VP8L]));
+        assert!(
+            counted.amount_read < 4096,
+            "read {} bytes",
+            counted.amount_read
+        );
+    }
+
+    #[test]
+    fn copies_input_read_a_byte_at_a_time() {
+        let input = webp(&[&vp8x(EXIF_FLAG | XMP_FLAG), VP8L, EXIF, XMP]);
+        let mut output = Cursor::new(Vec::new());
+        sanitize_and_strip_metadata(OneByteAtATime(Cursor::new(&input)), &mut output)
+            .expect("valid");
+        assert_eq!(output.into_inner(), webp(&[&vp8x(0), VP8L]));
+    }
+
+    /// Counts the bytes actually read, as opposed to skipped.
+    struct CountingInput<R> {
+        inner: R,
+        amount_read: usize,
+    }
+
+    impl<R: Read> Read for CountingInput<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let amount_read = self.inner.read(buf)?;
+            self.amount_read += amount_read;
+            Ok(amount_read)
+        }
+    }
+
+    impl<R: Skip> Skip for CountingInput<R> {
+        fn skip(&mut self, amount: u64) -> io::Result<()> {
+            self.inner.skip(amount)
+        }
+
+        fn stream_position(&mut self) -> io::Result<u64> {
+            self.inner.stream_position()
+        }
+
+        fn stream_len(&mut self) -> io::Result<u64> {
+            self.inner.stream_len()
+        }
+    }
+
+    /// Returns at most one byte from each read, and skips by reading.
+    struct OneByteAtATime<R>(R);
+
+    impl<R: Read> Read for OneByteAtATime<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    impl<R: Read + Skip> Skip for OneByteAtATime<R> {
+        fn skip(&mut self, amount: u64) -> io::Result<()> {
+            io::copy(&mut self.by_ref().take(amount), &mut io::sink())?;
+            Ok(())
+        }
+
+        fn stream_position(&mut self) -> io::Result<u64> {
+            self.0.stream_position()
+        }
+
+        fn stream_len(&mut self) -> io::Result<u64> {
+            self.0.stream_len()
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(strip(&[]).is_err());
+        let input = webp(&[VP8L]);
+        assert!(strip(&input[..input.len() - 1]).is_err());
+    }
+}
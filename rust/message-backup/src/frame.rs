@@ -18,6 +18,7 @@ use subtle::ConstantTimeEq as _;
 
 use crate::frame::aes_read::{Aes256CbcReader, AES_IV_SIZE};
 use crate::frame::mac_read::MacReader;
+use crate::frame::trailer_read::TrailerReader;
 use crate::key::MessageBackupKey;
 
 mod aes_read;
@@ -25,9 +26,12 @@ mod block_stream;
 mod cbc;
 mod mac_read;
 mod reader_factory;
+mod trailer_read;
 mod unpad;
+mod writer;
 
 pub use reader_factory::{CursorFactory, FileReaderFactory, LimitedReaderFactory, ReaderFactory};
+pub use writer::FramesWriter;
 
 const HMAC_LEN: usize = <<Hmac<Sha256> as OutputSizeUser>::OutputSize as Unsigned>::USIZE;
 
@@ -40,6 +44,16 @@ pub struct FramesReader<R: AsyncRead + Unpin> {
 /// Reader that computes a SHA256 HMAC of the yielded bytes.
 type HmacSha256Reader<R> = MacReader<R, Hmac<Sha256>>;
 
+/// Like [`FramesReader`], but reads the encrypted input only once.
+///
+/// Since the HMAC is at the end of the input, it can't be checked until everything has been read.
+/// The decrypted contents are produced before they have been authenticated, so callers must not
+/// trust them until [`VerifyHmac::verify_hmac`] succeeds.
+#[derive(Debug)]
+pub struct StreamingFramesReader<R: AsyncRead + Unpin> {
+    reader: GzipDecoder<BufReader<Aes256CbcReader<HmacSha256Reader<TrailerReader<R, HMAC_LEN>>>>>,
+}
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum ValidationError {
     /// io error {0}
@@ -127,6 +141,28 @@ impl<R: AsyncRead + AsyncSkip + Unpin> FramesReader<R> {
     }
 }
 
+impl<R: AsyncRead + Unpin> StreamingFramesReader<R> {
+    pub async fn new(key: &MessageBackupKey, reader: R) -> Result<Self, ValidationError> {
+        let mut content = MacReader::new_sha256(TrailerReader::new(reader), &key.hmac_key);
+
+        let mut iv = [0; AES_IV_SIZE];
+        content
+            .read_exact(&mut iv)
+            .await
+            .map_err(|e| match e.kind() {
+                futures::io::ErrorKind::UnexpectedEof => ValidationError::TooShort,
+                _ => e.into(),
+            })?;
+
+        let decrypted = Aes256CbcReader::new(&key.aes_key, &iv, content);
+        let decompressed = GzipDecoder::new(BufReader::new(decrypted));
+
+        Ok(Self {
+            reader: decompressed,
+        })
+    }
+}
+
 impl<R: AsyncRead + Unpin> AsyncRead for FramesReader<R> {
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
@@ -208,6 +244,37 @@ impl<R: AsyncRead + Unpin> VerifyHmac for FramesReader<R> {
     }
 }
 
+impl<R: AsyncRead + Unpin> AsyncRead for StreamingFramesReader<R> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<futures::io::Result<usize>> {
+        std::pin::Pin::new(&mut self.get_mut().reader).poll_read(cx, buf)
+    }
+}
+
+#[async_trait(?Send)]
+impl<R: AsyncRead + Unpin> VerifyHmac for StreamingFramesReader<R> {
+    async fn verify_hmac(self) -> Result<(), VerifyHmacError> {
+        // As with FramesReader, make sure the entire input went through the MacReader, even if
+        // the GZIPped data had trailing padding.
+        let mut reader: MacReader<_, _> = self.reader.into_inner().into_inner().into_inner();
+        futures::io::copy(&mut reader, &mut futures::io::sink()).await?;
+
+        let expected = *reader
+            .get_ref()
+            .trailer()
+            .expect("read to the end of the input");
+        let found: [u8; HMAC_LEN] = reader.finalize().into();
+        if expected.ct_eq(&found).into() {
+            Ok(())
+        } else {
+            Err(HmacMismatchError { expected, found }.into())
+        }
+    }
+}
+
 async fn hmac_sha256(
     hmac_key: &[u8],
     reader: impl AsyncRead + Unpin,
@@ -348,6 +415,59 @@ mod test {
         assert_eq!(buf, FRAME_DATA,);
     }
 
+    #[test_case(Pad)]
+    #[test_case(NoPad)]
+    fn streaming_round_trip(pad: PadCompressed) {
+        const FRAME_DATA: &[u8] = b"this was a triumph";
+
+        let encoded_frame = block_on(make_encrypted(&FAKE_MESSAGE_BACKUP_KEY, FRAME_DATA, pad));
+
+        let mut reader = block_on(StreamingFramesReader::new(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            Cursor::new(&encoded_frame),
+        ))
+        .expect("long enough");
+        let mut buf = Vec::new();
+        block_on(AsyncReadExt::read_to_end(&mut reader, &mut buf)).expect("can read");
+        assert_eq!(buf, FRAME_DATA);
+
+        block_on(reader.verify_hmac()).expect("valid HMAC");
+    }
+
+    #[test]
+    fn streaming_too_short() {
+        assert_matches!(
+            block_on(StreamingFramesReader::new(
+                &FAKE_MESSAGE_BACKUP_KEY,
+                Cursor::new([0; HMAC_LEN])
+            )),
+            Err(ValidationError::TooShort)
+        );
+    }
+
+    #[test_case(Pad)]
+    #[test_case(NoPad)]
+    fn streaming_mismatched_hmac(pad: PadCompressed) {
+        let mut encoded_frame = block_on(make_encrypted(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            b"this was a triumph",
+            pad,
+        ));
+        *encoded_frame.last_mut().unwrap() ^= 1;
+
+        let mut reader = block_on(StreamingFramesReader::new(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            Cursor::new(&encoded_frame),
+        ))
+        .expect("long enough");
+        block_on(futures::io::copy(&mut reader, &mut futures::io::sink())).expect("can read");
+
+        assert_matches!(
+            block_on(reader.verify_hmac()),
+            Err(VerifyHmacError::HmacMismatch(_))
+        );
+    }
+
     #[test_case(Pad)]
     #[test_case(NoPad)]
     fn mismatched_hmac(pad: PadCompressed) {
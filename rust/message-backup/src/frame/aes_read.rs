@@ -18,7 +18,7 @@ use crate::frame::block_stream::ExactReadBlockStream;
 use crate::frame::cbc::CbcStreamDecryptor;
 use crate::frame::unpad::UnpadLast;
 
-const AES_BLOCK_SIZE: usize = <<Aes256 as BlockSizeUser>::BlockSize as Unsigned>::USIZE;
+pub(super) const AES_BLOCK_SIZE: usize = <<Aes256 as BlockSizeUser>::BlockSize as Unsigned>::USIZE;
 const AES_KEY_SIZE: usize = <<Aes256 as KeySizeUser>::KeySize as Unsigned>::USIZE;
 pub(super) const AES_IV_SIZE: usize =
     <<cbc::Decryptor<Aes256> as IvSizeUser>::IvSize as Unsigned>::USIZE;
@@ -21,6 +21,10 @@ impl<R, M> MacReader<R, M> {
         Self { reader, mac }
     }
 
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
     pub(crate) fn finalize(self) -> GenericArray<u8, M::OutputSize>
     where
         M: Mac,
@@ -0,0 +1,172 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::pin::Pin;
+use std::task::Poll;
+
+use futures::{ready, AsyncRead};
+
+/// [`AsyncRead`]er that holds back the last `N` bytes of its input.
+///
+/// This allows reading a stream that ends with a fixed-size trailer (like a MAC) in a single
+/// pass, without knowing the stream length up front. Once the wrapped reader has been read to
+/// the end, the withheld bytes are available from [`TrailerReader::trailer`].
+#[derive(Debug)]
+pub(crate) struct TrailerReader<R, const N: usize> {
+    reader: R,
+    held: [u8; N],
+    held_len: usize,
+    reached_end: bool,
+}
+
+impl<R, const N: usize> TrailerReader<R, N> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            held: [0; N],
+            held_len: 0,
+            reached_end: false,
+        }
+    }
+
+    /// Returns the last `N` bytes of the input, once it has been read to the end.
+    pub(crate) fn trailer(&self) -> Option<&[u8; N]> {
+        self.reached_end.then_some(&self.held)
+    }
+}
+
+impl<R: AsyncRead + Unpin, const N: usize> AsyncRead for TrailerReader<R, N> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<futures::io::Result<usize>> {
+        let Self {
+            reader,
+            held,
+            held_len,
+            reached_end,
+        } = self.get_mut();
+
+        if *reached_end || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            let num_read = ready!(Pin::new(&mut *reader).poll_read(cx, buf))?;
+            if num_read == 0 {
+                if *held_len < N {
+                    return Poll::Ready(Err(futures::io::Error::new(
+                        futures::io::ErrorKind::UnexpectedEof,
+                        "input is shorter than its trailer",
+                    )));
+                }
+                *reached_end = true;
+                return Poll::Ready(Ok(0));
+            }
+
+            // The input so far is `held[..held_len]` followed by `buf[..num_read]`.
+            let total = *held_len + num_read;
+            if total <= N {
+                held[*held_len..total].copy_from_slice(&buf[..num_read]);
+                *held_len = total;
+                continue;
+            }
+
+            // Everything but the last N bytes can be produced. Since held_len <= N, this always
+            // fits in the bytes that were just read into `buf`.
+            let num_produced = total - N;
+            let combined = |i: usize| {
+                if i < *held_len {
+                    held[i]
+                } else {
+                    buf[i - *held_len]
+                }
+            };
+            let new_held: [u8; N] = std::array::from_fn(|i| combined(num_produced + i));
+
+            if num_produced > *held_len {
+                buf.copy_within(..num_produced - *held_len, *held_len);
+                buf[..*held_len].copy_from_slice(&held[..*held_len]);
+            } else {
+                buf[..num_produced].copy_from_slice(&held[..num_produced]);
+            }
+            *held = new_held;
+            *held_len = N;
+
+            return Poll::Ready(Ok(num_produced));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::io::{BufReader, Cursor};
+    use futures::{AsyncReadExt as _, FutureExt as _};
+    use test_case::test_case;
+
+    use super::*;
+
+    const TRAILER_LEN: usize = 4;
+
+    #[test_case(1; "one byte at a time")]
+    #[test_case(3; "less than the trailer")]
+    #[test_case(5; "more than the trailer")]
+    #[test_case(1024; "all at once")]
+    fn splits_trailer(read_size: usize) {
+        let input = (0..100).collect::<Vec<u8>>();
+        let mut reader = TrailerReader::<_, TRAILER_LEN>::new(BufReader::with_capacity(
+            read_size,
+            Cursor::new(&input),
+        ));
+
+        let mut contents = Vec::new();
+        let mut buf = vec![0; read_size];
+        loop {
+            let num_read = reader
+                .read(&mut buf)
+                .now_or_never()
+                .expect("future finished")
+                .expect("success");
+            if num_read == 0 {
+                break;
+            }
+            contents.extend_from_slice(&buf[..num_read]);
+        }
+
+        assert_eq!(contents, input[..input.len() - TRAILER_LEN]);
+        assert_eq!(
+            reader.trailer(),
+            Some(&input[input.len() - TRAILER_LEN..].try_into().unwrap())
+        );
+    }
+
+    #[test]
+    fn trailer_only() {
+        let mut reader = TrailerReader::<_, TRAILER_LEN>::new(Cursor::new([1, 2, 3, 4]));
+        assert_eq!(reader.trailer(), None);
+
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .now_or_never()
+            .expect("future finished")
+            .expect("success");
+        assert_eq!(contents, []);
+        assert_eq!(reader.trailer(), Some(&[1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn too_short() {
+        let mut reader = TrailerReader::<_, TRAILER_LEN>::new(Cursor::new([1, 2, 3]));
+        let err = reader
+            .read_to_end(&mut Vec::new())
+            .now_or_never()
+            .expect("future finished")
+            .expect_err("too short");
+        assert_eq!(err.kind(), futures::io::ErrorKind::UnexpectedEof);
+        assert_eq!(reader.trailer(), None);
+    }
+}
@@ -0,0 +1,180 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockEncryptMut as _, KeyIvInit as _};
+use aes::Aes256;
+use async_compression::futures::write::GzipEncoder;
+use futures::{AsyncWrite, AsyncWriteExt as _};
+use hmac::{Hmac, Mac as _};
+use sha2::Sha256;
+
+use crate::frame::aes_read::{AES_BLOCK_SIZE, AES_IV_SIZE};
+use crate::key::MessageBackupKey;
+
+/// Writer that produces an encrypted, compressed backup.
+///
+/// This is the inverse of [`FramesReader`](super::FramesReader): written bytes
+/// are GZIP-compressed, padded, encrypted with AES256-CBC, and followed by an
+/// HMAC-SHA256 of the IV and ciphertext. Compressed data is encrypted as soon
+/// as a full block is available, so only a bounded amount is held in memory.
+pub struct FramesWriter<W> {
+    writer: W,
+    compressor: GzipEncoder<Vec<u8>>,
+    /// The number of compressed bytes that have already been encrypted.
+    encrypted_len: usize,
+    encryptor: cbc::Encryptor<Aes256>,
+    hmac: Hmac<Sha256>,
+}
+
+impl<W: AsyncWrite + Unpin> FramesWriter<W> {
+    /// Starts a new backup by writing `iv` to `writer`.
+    pub async fn new(
+        key: &MessageBackupKey,
+        iv: [u8; AES_IV_SIZE],
+        mut writer: W,
+    ) -> futures::io::Result<Self> {
+        writer.write_all(&iv).await?;
+
+        let mut hmac =
+            Hmac::<Sha256>::new_from_slice(&key.hmac_key).expect("HMAC can take any size key");
+        hmac.update(&iv);
+
+        Ok(Self {
+            writer,
+            compressor: GzipEncoder::new(Vec::new()),
+            encrypted_len: 0,
+            encryptor: cbc::Encryptor::new((&key.aes_key).into(), (&iv).into()),
+            hmac,
+        })
+    }
+
+    /// Returns the output being written to.
+    ///
+    /// Only complete blocks of ciphertext are ever written to it.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub async fn write_all(&mut self, buf: &[u8]) -> futures::io::Result<()> {
+        self.compressor.write_all(buf).await?;
+        self.encrypt_complete_blocks().await
+    }
+
+    /// Finishes the backup, writing the remaining contents and the HMAC.
+    ///
+    /// The compressed contents are padded to a bucketed length before
+    /// encryption so that the size of the output leaks less about the size of
+    /// the backup.
+    pub async fn finish(mut self) -> futures::io::Result<W> {
+        self.compressor.close().await?;
+        self.encrypt_complete_blocks().await?;
+
+        let Self {
+            mut writer,
+            compressor,
+            encrypted_len,
+            encryptor,
+            mut hmac,
+        } = self;
+
+        let mut remaining = compressor.into_inner();
+        let compressed_len = encrypted_len + remaining.len();
+        remaining.resize(
+            remaining.len() + padded_len(compressed_len) - compressed_len,
+            0,
+        );
+
+        let ciphertext = encryptor.encrypt_padded_vec_mut::<Pkcs7>(&remaining);
+        hmac.update(&ciphertext);
+        writer.write_all(&ciphertext).await?;
+        writer.write_all(&hmac.finalize().into_bytes()).await?;
+        writer.flush().await?;
+
+        Ok(writer)
+    }
+
+    /// Encrypts and writes out all the complete blocks of compressed data.
+    ///
+    /// Any trailing partial block is left in place until more data is written
+    /// or the writer is finished.
+    async fn encrypt_complete_blocks(&mut self) -> futures::io::Result<()> {
+        let compressed = self.compressor.get_mut();
+        let complete_len = compressed.len() - compressed.len() % AES_BLOCK_SIZE;
+        if complete_len == 0 {
+            return Ok(());
+        }
+
+        let mut blocks = compressed.drain(..complete_len).collect::<Vec<_>>();
+        for block in blocks.chunks_exact_mut(AES_BLOCK_SIZE) {
+            self.encryptor.encrypt_block_mut(block.into());
+        }
+        self.encrypted_len += complete_len;
+
+        self.hmac.update(&blocks);
+        self.writer.write_all(&blocks).await
+    }
+}
+
+/// Returns the length that `len` bytes of compressed content are padded to.
+///
+/// Lengths are rounded up to the next integer power of 1.05, with a minimum of
+/// 541 bytes.
+fn padded_len(len: usize) -> usize {
+    const BASE: f64 = 1.05;
+    const MIN_LEN: usize = 541;
+
+    let exp = (len as f64).log(BASE).ceil();
+    let bucketed = BASE.powf(exp).floor() as usize;
+    // Guard against rounding errors producing a length that is too short.
+    bucketed.max(len).max(MIN_LEN)
+}
+
+#[cfg(test)]
+mod test {
+    use futures::executor::block_on;
+    use futures::AsyncReadExt as _;
+    use test_case::test_case;
+
+    use crate::frame::{CursorFactory, FramesReader, VerifyHmac as _};
+    use crate::key::test::FAKE_MESSAGE_BACKUP_KEY;
+
+    use super::*;
+
+    #[test_case(0; "empty")]
+    #[test_case(1; "single byte")]
+    #[test_case(100_000; "many blocks")]
+    fn round_trip(len: usize) {
+        let contents = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let encrypted = block_on(async {
+            let mut writer = FramesWriter::new(&FAKE_MESSAGE_BACKUP_KEY, [0xbb; 16], Vec::new())
+                .await
+                .expect("can write to Vec");
+            for chunk in contents.chunks(1000) {
+                writer.write_all(chunk).await.expect("can write to Vec");
+            }
+            writer.finish().await.expect("can write to Vec")
+        });
+
+        let mut reader = block_on(FramesReader::new(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            CursorFactory::new(&encrypted),
+        ))
+        .expect("valid HMAC");
+        let mut decrypted = Vec::new();
+        block_on(reader.read_to_end(&mut decrypted)).expect("can decrypt");
+        assert_eq!(decrypted, contents);
+        block_on(reader.verify_hmac()).expect("valid HMAC");
+    }
+
+    #[test_case(0, 541)]
+    #[test_case(541, 541)]
+    #[test_case(542, 568)]
+    #[test_case(10_000, 10_110)]
+    fn padded_len_is_bucketed(len: usize, expected: usize) {
+        assert_eq!(padded_len(len), expected);
+    }
+}
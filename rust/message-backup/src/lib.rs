@@ -5,16 +5,17 @@
 
 //! Signal remote message backup utilities.
 //!
-//! Contains code to read and validate message backup files.
+//! Contains code to read, validate, and write message backup files.
 
-use futures::AsyncRead;
+use futures::{AsyncRead, AsyncWrite};
 use mediasan_common::AsyncSkip;
 use protobuf::Message as _;
 
 use crate::backup::method::ValidateOnly;
 use crate::backup::{CompletedBackup, Purpose};
 use crate::frame::{
-    HmacMismatchError, ReaderFactory, UnvalidatedHmacReader, VerifyHmac, VerifyHmacError,
+    FramesWriter, HmacMismatchError, ReaderFactory, UnvalidatedHmacReader, VerifyHmac,
+    VerifyHmacError,
 };
 use crate::key::MessageBackupKey;
 use crate::parse::VarintDelimitedReader;
@@ -38,6 +39,38 @@ pub struct BackupReader<R> {
     pub visitor: fn(&dyn std::fmt::Debug),
 }
 
+/// Produces an encrypted, compressed backup from a sequence of frames.
+///
+/// Each frame is validated before it is written, using the same checks as
+/// [`BackupReader`], so that frames that refer to missing recipients or chats,
+/// or that would otherwise make the backup invalid, are rejected without being
+/// written. A rejected frame leaves the writer usable.
+///
+/// If writing an accepted frame to the output fails, the frame has already
+/// been recorded as part of the backup but may be partially written, so there
+/// is no way to roll back to a consistent state. The writer instead refuses
+/// any further frames, and [`BackupWriter::finish`] fails with
+/// [`WriteError::Failed`].
+pub struct BackupWriter<W> {
+    writer: FramesWriter<W>,
+    backup: backup::PartialBackup<ValidateOnly>,
+    failed: bool,
+}
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum WriteError {
+    /// {0}
+    BackupValidation(#[from] backup::ValidationError),
+    /// {0}
+    BackupCompletion(#[from] backup::CompletionError),
+    /// failed to serialize protobuf: {0}
+    Serialize(#[from] protobuf::Error),
+    /// io error {0}
+    Io(#[from] futures::io::Error),
+    /// an earlier write failed, so the backup is incomplete
+    Failed,
+}
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum Error {
     /// {0}
@@ -154,6 +187,109 @@ impl<R: AsyncRead + AsyncSkip + Unpin> BackupReader<frame::FramesReader<R>> {
     }
 }
 
+impl<R: AsyncRead + Unpin> BackupReader<frame::StreamingFramesReader<R>> {
+    /// Reads an encrypted backup in a single pass over `reader`.
+    ///
+    /// Unlike [`BackupReader::new_encrypted_compressed`], this doesn't need to know the length of
+    /// the input or read it twice, so it works on non-seekable streams. A synchronous
+    /// [`std::io::Read`] can be used by wrapping it in [`futures::io::AllowStdIo`].
+    ///
+    /// Frames are validated as they are decrypted, and the HMAC is checked once the end of the
+    /// input is reached; a backup with an invalid HMAC is reported as an error regardless of the
+    /// validity of its frames.
+    pub async fn new_encrypted_compressed_streaming(
+        key: &MessageBackupKey,
+        reader: R,
+        purpose: Purpose,
+    ) -> Result<Self, frame::ValidationError> {
+        let reader = frame::StreamingFramesReader::new(key, reader).await?;
+        Ok(Self {
+            reader: VarintDelimitedReader::new(reader),
+            purpose,
+            visitor: |_| (),
+        })
+    }
+}
+
+impl<W: AsyncWrite + Unpin> BackupWriter<W> {
+    /// Starts writing an encrypted backup to `writer`, beginning with
+    /// `backup_info`.
+    ///
+    /// `iv` should be freshly generated for each backup.
+    pub async fn new_encrypted_compressed(
+        key: &MessageBackupKey,
+        iv: [u8; 16],
+        writer: W,
+        backup_info: proto::backup::BackupInfo,
+        purpose: Purpose,
+    ) -> Result<Self, WriteError> {
+        let backup = backup::PartialBackup::new(backup_info.clone(), purpose);
+
+        let mut writer = FramesWriter::new(key, iv, writer).await?;
+        writer
+            .write_all(&backup_info.write_length_delimited_to_bytes()?)
+            .await?;
+
+        Ok(Self {
+            writer,
+            backup,
+            failed: false,
+        })
+    }
+
+    /// Validates and writes a single frame.
+    ///
+    /// If the frame is rejected, nothing is written. If the output can't be
+    /// written to, this and all later calls fail; see [`BackupWriter`].
+    pub async fn write_frame(
+        &mut self,
+        item: impl Into<proto::backup::frame::Item>,
+    ) -> Result<(), WriteError> {
+        if self.failed {
+            return Err(WriteError::Failed);
+        }
+
+        let frame = proto::backup::Frame {
+            item: Some(item.into()),
+            ..Default::default()
+        };
+        let serialized = frame.write_length_delimited_to_bytes()?;
+
+        self.backup.add_frame(frame)?;
+        self.writer.write_all(&serialized).await.map_err(|e| {
+            self.failed = true;
+            e
+        })?;
+        Ok(())
+    }
+
+    /// Returns the output that the encrypted backup is being written to.
+    ///
+    /// Everything in the output is complete ciphertext, so it can be drained
+    /// between calls to [`BackupWriter::write_frame`] to avoid holding the
+    /// whole backup in memory.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.get_mut()
+    }
+
+    /// Checks that the backup is complete and writes out the remaining
+    /// contents.
+    ///
+    /// The backup is only valid once this returns successfully.
+    pub async fn finish(self) -> Result<W, WriteError> {
+        let Self {
+            writer,
+            backup,
+            failed,
+        } = self;
+        if failed {
+            return Err(WriteError::Failed);
+        }
+        let _: CompletedBackup<ValidateOnly> = backup.try_into()?;
+        Ok(writer.finish().await?)
+    }
+}
+
 async fn read_all_frames<M: backup::method::Method>(
     purpose: Purpose,
     mut reader: VarintDelimitedReader<impl AsyncRead + Unpin + VerifyHmac>,
@@ -204,3 +340,186 @@ impl From<VerifyHmacError> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    use crate::key::test::FAKE_MESSAGE_BACKUP_KEY;
+    use crate::proto::backup as proto;
+
+    use super::*;
+
+    fn new_writer() -> BackupWriter<Vec<u8>> {
+        block_on(BackupWriter::new_encrypted_compressed(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            [0xbb; 16],
+            Vec::new(),
+            proto::BackupInfo::new(),
+            Purpose::RemoteBackup,
+        ))
+        .expect("can write to Vec")
+    }
+
+    #[test]
+    fn written_backup_is_valid() {
+        let mut writer = new_writer();
+        block_on(async {
+            writer
+                .write_frame(proto::AccountData::test_data())
+                .await
+                .expect("valid");
+            writer
+                .write_frame(proto::Recipient::test_data())
+                .await
+                .expect("valid");
+            writer
+                .write_frame(proto::Chat::test_data())
+                .await
+                .expect("valid");
+            writer
+                .write_frame(proto::ChatItem::test_data())
+                .await
+                .expect("valid");
+        });
+        let written = block_on(writer.finish()).expect("complete");
+
+        let reader = block_on(BackupReader::new_encrypted_compressed(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            frame::CursorFactory::new(&written),
+            Purpose::RemoteBackup,
+        ))
+        .expect("valid HMAC");
+        let ReadResult {
+            result,
+            found_unknown_fields,
+        } = block_on(reader.validate_all());
+        result.expect("valid backup");
+        assert_eq!(found_unknown_fields, Vec::new());
+    }
+
+    #[test]
+    fn rejects_frame_with_missing_reference() {
+        let mut writer = new_writer();
+        assert_matches!(
+            block_on(writer.write_frame(proto::Chat::test_data())),
+            Err(WriteError::BackupValidation(_))
+        );
+
+        // The writer is still usable after a frame is rejected.
+        block_on(writer.write_frame(proto::AccountData::test_data())).expect("valid");
+        let written = block_on(writer.finish()).expect("complete");
+
+        let reader = block_on(BackupReader::new_encrypted_compressed_streaming(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            Cursor::new(written),
+            Purpose::RemoteBackup,
+        ))
+        .expect("valid IV");
+        block_on(reader.validate_all())
+            .result
+            .expect("rejected frame was not written");
+    }
+
+    /// Accepts a limited number of bytes, then fails every write.
+    struct FailingWriter(usize);
+
+    impl AsyncWrite for FailingWriter {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<futures::io::Result<usize>> {
+            if self.0 < buf.len() {
+                return std::task::Poll::Ready(Err(futures::io::ErrorKind::BrokenPipe.into()));
+            }
+            self.0 -= buf.len();
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<futures::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<futures::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn failed_write_stops_the_writer() {
+        // Enough room for the IV, but not for the first block of ciphertext.
+        let mut writer = block_on(BackupWriter::new_encrypted_compressed(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            [0xbb; 16],
+            FailingWriter(16),
+            proto::BackupInfo::new(),
+            Purpose::RemoteBackup,
+        ))
+        .expect("IV fits");
+
+        // A frame with an incompressible unknown field is enough to fill the
+        // compressor's buffers and force a write of ciphertext.
+        let mut recipient = proto::Recipient::test_data();
+        let mut state = 1u32;
+        let noise = std::iter::repeat_with(|| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .take(100_000)
+        .collect();
+        recipient
+            .special_fields
+            .mut_unknown_fields()
+            .add_length_delimited(1000, noise);
+        assert_matches!(
+            block_on(writer.write_frame(recipient)),
+            Err(WriteError::Io(_))
+        );
+
+        assert_matches!(
+            block_on(writer.write_frame(proto::AccountData::test_data())),
+            Err(WriteError::Failed)
+        );
+        assert_matches!(block_on(writer.finish()), Err(WriteError::Failed));
+    }
+
+    #[test]
+    fn output_can_be_drained() {
+        let mut writer = new_writer();
+        let mut written = std::mem::take(writer.get_mut());
+        block_on(writer.write_frame(proto::AccountData::test_data())).expect("valid");
+        written.extend(std::mem::take(writer.get_mut()));
+        written.extend(block_on(writer.finish()).expect("complete"));
+
+        let reader = block_on(BackupReader::new_encrypted_compressed_streaming(
+            &FAKE_MESSAGE_BACKUP_KEY,
+            Cursor::new(written),
+            Purpose::RemoteBackup,
+        ))
+        .expect("valid IV");
+        block_on(reader.validate_all())
+            .result
+            .expect("valid backup");
+    }
+
+    #[test]
+    fn rejects_incomplete_backup() {
+        assert_matches!(
+            block_on(new_writer().finish()),
+            Err(WriteError::BackupCompletion(
+                backup::CompletionError::MissingAccountData
+            ))
+        );
+    }
+}
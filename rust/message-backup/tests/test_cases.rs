@@ -123,6 +123,16 @@ fn is_valid_encrypted_proto(input: Fixture<PathBuf>) {
     .unwrap_or_else(|e| panic!("expected valid, got {e}"));
     validate(reader);
 
+    // Reading the file in a single pass should give the same result.
+    let file = futures::io::AllowStdIo::new(std::fs::File::open(path).expect("can open"));
+    let reader = futures::executor::block_on(BackupReader::new_encrypted_compressed_streaming(
+        &key,
+        file,
+        Purpose::RemoteBackup,
+    ))
+    .unwrap_or_else(|e| panic!("expected valid, got {e}"));
+    validate(reader);
+
     // The CLI tool should agree.
     validator_command()
         .args([
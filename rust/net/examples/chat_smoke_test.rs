@@ -14,6 +14,7 @@ use libsignal_net::env::Svr3Env;
 use libsignal_net::infra::dns::DnsResolver;
 use libsignal_net::infra::tcp_ssl::DirectConnector;
 use libsignal_net::infra::{make_ws_config, ConnectionParams, EndpointConnection, RouteType};
+use libsignal_net::timeouts::TimeoutConfig;
 use tokio::sync::mpsc;
 
 #[derive(Parser)]
@@ -99,13 +100,12 @@ async fn test_connection(
     env: &libsignal_net::env::Env<'static, Svr3Env<'static>>,
     connection_params: Vec<ConnectionParams>,
 ) -> Result<(), ChatServiceError> {
-    let one_route_connect_timeout = Duration::from_secs(5);
+    let timeouts = TimeoutConfig::default().with_one_route_connection(Duration::from_secs(5));
     let dns_resolver = DnsResolver::new_with_static_fallback(env.static_fallback());
     let transport_connector = DirectConnector::new(dns_resolver);
     let chat_endpoint = PathAndQuery::from_static(WEB_SOCKET_PATH);
-    let chat_ws_config = make_ws_config(chat_endpoint, one_route_connect_timeout);
-    let connection =
-        EndpointConnection::new_multi(connection_params, one_route_connect_timeout, chat_ws_config);
+    let chat_ws_config = make_ws_config(chat_endpoint, timeouts.one_route_connection);
+    let connection = EndpointConnection::new_multi(connection_params, &timeouts, chat_ws_config);
 
     let (incoming_tx, _incoming_rx) = mpsc::channel(1);
     let chat = chat_service(
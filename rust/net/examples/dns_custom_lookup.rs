@@ -5,13 +5,10 @@
 use clap::{Parser, ValueEnum};
 use const_str::ip_addr;
 use either::{for_both, Either};
-use libsignal_net::infra::certs::RootCertificates;
 use libsignal_net::infra::dns::custom_resolver::CustomDnsResolver;
 use libsignal_net::infra::dns::dns_lookup::{DnsLookup, DnsLookupRequest};
-use libsignal_net::infra::dns::dns_transport_doh::DohTransport;
+use libsignal_net::infra::dns::dns_transport_doh::{DohConfig, DohTransport};
 use libsignal_net::infra::dns::dns_transport_udp::UdpTransport;
-use libsignal_net::infra::{ConnectionParams, HttpRequestDecoratorSeq, RouteType};
-use nonzero_ext::nonzero;
 use std::net::IpAddr;
 use std::sync::Arc;
 use tokio::time::Instant;
@@ -48,15 +45,7 @@ async fn main() {
             Either::Left(CustomDnsResolver::<UdpTransport>::new(ns_address))
         }
         Transport::Doh => {
-            let connection_params = ConnectionParams::new(
-                RouteType::Direct,
-                "1.1.1.1",
-                "1.1.1.1",
-                nonzero!(443u16),
-                HttpRequestDecoratorSeq::default(),
-                RootCertificates::Native,
-            );
-            Either::Right(CustomDnsResolver::<DohTransport>::new(connection_params))
+            Either::Right(CustomDnsResolver::<DohTransport>::new(DohConfig::default()))
         }
     };
 
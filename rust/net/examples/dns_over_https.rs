@@ -4,11 +4,10 @@
 //
 use clap::Parser;
 use futures_util::StreamExt;
-use libsignal_net::infra::certs::RootCertificates;
 use libsignal_net::infra::dns::custom_resolver::DnsTransport;
 use libsignal_net::infra::dns::dns_lookup::DnsLookupRequest;
-use libsignal_net::infra::dns::dns_transport_doh::DohTransport;
-use libsignal_net::infra::{ConnectionParams, HttpRequestDecoratorSeq, RouteType};
+use libsignal_net::infra::dns::dns_transport_doh::{DohConfig, DohTransport};
+use std::net::IpAddr;
 use std::num::NonZeroU16;
 use std::sync::Arc;
 
@@ -26,6 +25,9 @@ struct Args {
     /// port of the name server
     #[arg(long, default_value = "443")]
     ns_port: u16,
+    /// IP address of the name server, if `ns_address` is not an IP address itself
+    #[arg(long)]
+    ns_bootstrap_ip: Option<IpAddr>,
 }
 
 #[tokio::main]
@@ -36,21 +38,25 @@ async fn main() {
 
     let args = Args::parse();
 
-    let connection_params = ConnectionParams::new(
-        RouteType::Direct,
-        args.ns_address.as_str(),
-        args.ns_address.as_str(),
-        NonZeroU16::try_from(args.ns_port).expect("valid port value"),
-        HttpRequestDecoratorSeq::default(),
-        RootCertificates::Native,
-    );
+    let bootstrap_ip = args
+        .ns_bootstrap_ip
+        .or_else(|| args.ns_address.parse().ok())
+        .expect("either ns_address or ns_bootstrap_ip is an IP address");
+    let (bootstrap_ipv4, bootstrap_ipv6) = match bootstrap_ip {
+        IpAddr::V4(v4) => (vec![v4], vec![]),
+        IpAddr::V6(v6) => (vec![], vec![v6]),
+    };
+    let doh_config = DohConfig {
+        port: NonZeroU16::try_from(args.ns_port).expect("valid port value"),
+        ..DohConfig::new(&args.ns_address, bootstrap_ipv4, bootstrap_ipv6)
+    };
 
-    let doh_transport = DohTransport::connect(connection_params.clone(), !args.no_ipv6)
+    let doh_transport = DohTransport::connect(doh_config.clone(), !args.no_ipv6)
         .await
         .expect("connected to the DNS server");
     log::info!(
         "successfully connected to the DNS server at {:?}",
-        connection_params
+        doh_config
     );
 
     let request = DnsLookupRequest {
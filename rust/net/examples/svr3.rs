@@ -24,6 +24,7 @@ use libsignal_net::env::Svr3Env;
 use libsignal_net::infra::tcp_ssl::DirectConnector as TcpSslTransportConnector;
 use libsignal_net::svr::SvrConnection;
 use libsignal_net::svr3::{Error, OpaqueMaskedShareSet, PpssOps};
+use libsignal_net::timeouts::TimeoutConfig;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -63,7 +64,7 @@ async fn main() {
         let connection_a = EnclaveEndpointConnection::new_multi(
             env.sgx(),
             env.sgx().domain_config.connection_params_with_fallback(),
-            Duration::from_secs(10),
+            &TimeoutConfig::default().with_one_route_connection(Duration::from_secs(10)),
         );
         let a = SvrConnection::<Sgx, _>::connect(auth.clone(), &connection_a, connector.clone())
             .await
@@ -7,8 +7,8 @@ use std::default::Default;
 use std::fmt::Display;
 use std::num::{NonZeroU64, ParseIntError};
 use std::str::FromStr;
+use std::time::Duration;
 
-use http::StatusCode;
 use libsignal_core::{Aci, Pni};
 use prost::Message as _;
 use thiserror::Error;
@@ -150,9 +150,33 @@ impl LookupRequest {
             discard_e164s: Vec::new(),
         }
     }
+
+    /// Turns this into a request that resumes a lookup that was interrupted.
+    ///
+    /// `token` must be the one the server returned for this same request (see
+    /// [`CdsiConnection::send_request`]). The server has already counted all of the request's new
+    /// E164s against the rate limit, so they're sent as previous E164s this time.
+    pub fn resuming_with(self, token: Token) -> Self {
+        let Self {
+            mut new_e164s,
+            mut prev_e164s,
+            acis_and_access_keys,
+            return_acis_without_uaks,
+            token: _,
+        } = self;
+        prev_e164s.append(&mut new_e164s);
+        Self {
+            new_e164s,
+            prev_e164s,
+            acis_and_access_keys,
+            return_acis_without_uaks,
+            token: token.0,
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Issued by the server for a request, and can be saved to discount its E164s in later requests.
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Token(pub Box<[u8]>);
 
@@ -194,29 +218,29 @@ impl TryFrom<ClientResponse> for LookupResponse {
             debug_permits_used,
         } = response;
 
-        if e164_pni_aci_triples.len() % LookupResponseEntry::SERIALIZED_LEN != 0 {
+        Ok(Self {
+            records: LookupResponseEntry::parse_all(&e164_pni_aci_triples)?,
+            debug_permits_used,
+        })
+    }
+}
+
+impl LookupResponseEntry {
+    fn parse_all(e164_pni_aci_triples: &[u8]) -> Result<Vec<Self>, LookupResponseParseError> {
+        if e164_pni_aci_triples.len() % Self::SERIALIZED_LEN != 0 {
             return Err(LookupResponseParseError::InvalidNumberOfBytes {
                 actual_length: e164_pni_aci_triples.len(),
             });
         }
 
-        let records = e164_pni_aci_triples
-            .chunks(LookupResponseEntry::SERIALIZED_LEN)
+        Ok(e164_pni_aci_triples
+            .chunks(Self::SERIALIZED_LEN)
             .flat_map(|record| {
-                LookupResponseEntry::try_parse_from(
-                    record.try_into().expect("chunk size is correct"),
-                )
+                Self::try_parse_from(record.try_into().expect("chunk size is correct"))
             })
-            .collect();
-
-        Ok(Self {
-            records,
-            debug_permits_used,
-        })
+            .collect())
     }
-}
 
-impl LookupResponseEntry {
     fn try_parse_from(record: &[u8; Self::SERIALIZED_LEN]) -> Option<Self> {
         fn non_nil_uuid<T: From<Uuid>>(bytes: &uuid::Bytes) -> Option<T> {
             let uuid = Uuid::from_bytes(*bytes);
@@ -276,7 +300,7 @@ pub enum LookupError {
     /// invalid response received from the server
     InvalidResponse,
     /// retry later
-    RateLimited { retry_after_seconds: u32 },
+    RateLimited { retry_after: Duration },
     /// request token was invalid
     InvalidToken,
     /// failed to parse the response from the server
@@ -287,6 +311,8 @@ pub enum LookupError {
     WebSocket(WebSocketServiceError),
     /// connect attempt timed out
     ConnectionTimedOut,
+    /// timed out waiting for the server to respond
+    RequestTimedOut,
     /// request was invalid: {server_reason}
     InvalidArgument { server_reason: String },
     /// server error: {reason}
@@ -300,6 +326,7 @@ impl From<AttestedConnectionError> for LookupError {
             AttestedConnectionError::WebSocket(e) => Self::WebSocket(e),
             AttestedConnectionError::Protocol => Self::Protocol,
             AttestedConnectionError::Sgx(e) => Self::AttestationError(e),
+            AttestedConnectionError::RequestTimedOut => Self::RequestTimedOut,
         }
     }
 }
@@ -308,31 +335,21 @@ impl From<crate::enclave::Error> for LookupError {
     fn from(value: crate::enclave::Error) -> Self {
         use crate::enclave::Error;
         match value {
-            Error::WebSocketConnect(err) => match err {
-                WebSocketConnectError::Timeout => Self::ConnectionTimedOut,
-                WebSocketConnectError::Transport(e) => Self::ConnectTransport(e),
-                WebSocketConnectError::WebSocketError(e) => {
-                    if let tungstenite::Error::Http(response) = &e {
-                        if response.status() == StatusCode::TOO_MANY_REQUESTS {
-                            let retry_after_header = response.headers().get("retry-after");
-
-                            if let Some(retry_after_seconds) = retry_after_header
-                                .and_then(|value| value.to_str().ok())
-                                .and_then(|str| u32::from_str(str).ok())
-                            {
-                                return Self::RateLimited {
-                                    retry_after_seconds,
-                                };
-                            }
-                        }
-                    }
-                    Self::WebSocket(e.into())
+            Error::WebSocketConnect(err) => {
+                if let Some(retry_after) = err.rate_limit_retry_after() {
+                    return Self::RateLimited { retry_after };
                 }
-            },
+                match err {
+                    WebSocketConnectError::Timeout => Self::ConnectionTimedOut,
+                    WebSocketConnectError::Transport(e) => Self::ConnectTransport(e),
+                    WebSocketConnectError::WebSocketError(e) => Self::WebSocket(e.into()),
+                }
+            }
             Error::AttestationError(err) => Self::AttestationError(err),
             Error::WebSocket(err) => Self::WebSocket(err),
             Error::Protocol => Self::Protocol,
             Error::ConnectionTimedOut => Self::ConnectionTimedOut,
+            Error::RequestTimedOut => Self::RequestTimedOut,
         }
     }
 }
@@ -390,7 +407,21 @@ impl<S: AsyncDuplexStream> CdsiConnection<S> {
 }
 
 impl<S: AsyncDuplexStream> ClientResponseCollector<S> {
+    /// Acknowledges the token, then waits for all of the results.
     pub async fn collect(self) -> Result<LookupResponse, LookupError> {
+        let mut stream = self.into_stream().await?;
+        let mut records = Vec::new();
+        while let Some(batch) = stream.next_batch().await? {
+            records.extend(batch);
+        }
+        Ok(LookupResponse {
+            records,
+            debug_permits_used: stream.debug_permits_used(),
+        })
+    }
+
+    /// Acknowledges the token, after which results can be read as they arrive.
+    pub async fn into_stream(self) -> Result<LookupResponseStream<S>, LookupError> {
         let Self(mut connection) = self;
 
         let token_ack = ClientRequest {
@@ -399,31 +430,76 @@ impl<S: AsyncDuplexStream> ClientResponseCollector<S> {
         };
 
         connection.0.send(token_ack).await?;
-        let mut response: ClientResponse = connection.0.receive().await?.next_or_else(|close| {
-            close
-                .and_then(err_for_close)
-                .unwrap_or(LookupError::Protocol)
-        })?;
-        loop {
-            match connection.0.receive_bytes().await? {
-                NextOrClose::Next(decoded) => {
-                    response
-                        .merge(decoded.as_ref())
-                        .map_err(LookupError::from)?;
-                }
-                NextOrClose::Close(
-                    None
-                    | Some(CloseFrame {
-                        code: CloseCode::Normal,
-                        reason: _,
-                    }),
-                ) => break,
-                NextOrClose::Close(Some(close)) => {
-                    return Err(err_for_close(close).unwrap_or(LookupError::Protocol))
-                }
+        Ok(LookupResponseStream {
+            connection,
+            debug_permits_used: 0,
+            received_any: false,
+            finished: false,
+        })
+    }
+}
+
+/// The results of a lookup, read incrementally as the server sends them.
+///
+/// Created by [`ClientResponseCollector::into_stream`].
+#[cfg_attr(test, derive(Debug))]
+pub struct LookupResponseStream<S = SslStream<TcpStream>> {
+    connection: CdsiConnection<S>,
+    debug_permits_used: i32,
+    received_any: bool,
+    finished: bool,
+}
+
+impl<S: AsyncDuplexStream> LookupResponseStream<S> {
+    /// Waits for the next batch of results from the server.
+    ///
+    /// Returns `None` once the server has sent everything. If this fails partway through, the
+    /// lookup can be retried without being charged again for the same E164s; see
+    /// [`LookupRequest::resuming_with`].
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<LookupResponseEntry>>, LookupError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let response = match self.connection.0.receive_bytes().await {
+            Ok(NextOrClose::Next(decoded)) => ClientResponse::decode(decoded.as_ref())?,
+            Ok(NextOrClose::Close(
+                None
+                | Some(CloseFrame {
+                    code: CloseCode::Normal,
+                    reason: _,
+                }),
+            )) if self.received_any => {
+                self.finished = true;
+                return Ok(None);
+            }
+            Ok(NextOrClose::Close(close)) => {
+                self.finished = true;
+                return Err(close
+                    .and_then(err_for_close)
+                    .unwrap_or(LookupError::Protocol));
             }
+            Err(e) => {
+                self.finished = true;
+                return Err(e.into());
+            }
+        };
+        self.received_any = true;
+
+        let ClientResponse {
+            e164_pni_aci_triples,
+            token: _,
+            debug_permits_used,
+        } = response;
+        if debug_permits_used != 0 {
+            self.debug_permits_used = debug_permits_used;
         }
-        Ok(response.try_into()?)
+        Ok(Some(LookupResponseEntry::parse_all(&e164_pni_aci_triples)?))
+    }
+
+    /// The number of permits the server charged for the lookup, once it has said so.
+    pub fn debug_permits_used(&self) -> i32 {
+        self.debug_permits_used
     }
 }
 
@@ -458,7 +534,7 @@ fn err_for_close(CloseFrame { code, reason }: CloseFrame<'_>) -> Option<LookupEr
                 retry_after_seconds,
             } = serde_json::from_str(&reason).ok()?;
             Some(LookupError::RateLimited {
-                retry_after_seconds,
+                retry_after: Duration::from_secs(retry_after_seconds.into()),
             })
         }
         CdsiCloseCode::ServerInternalError | CdsiCloseCode::ServerUnavailable => {
@@ -587,11 +663,19 @@ mod test {
 
     impl FakeServerState {
         const RESPONSE_TOKEN: &'static [u8] = b"new token";
-        const RESPONSE_RECORD: LookupResponseEntry = LookupResponseEntry {
-            aci: Some(Aci::from_uuid_bytes([b'a'; 16])),
-            pni: Some(Pni::from_uuid_bytes([b'p'; 16])),
-            e164: E164(nonzero!(18005550101u64)),
-        };
+        /// Sent back in separate messages after the token is acknowledged.
+        const RESPONSE_RECORDS: [LookupResponseEntry; 2] = [
+            LookupResponseEntry {
+                aci: Some(Aci::from_uuid_bytes([b'a'; 16])),
+                pni: Some(Pni::from_uuid_bytes([b'p'; 16])),
+                e164: E164(nonzero!(18005550101u64)),
+            },
+            LookupResponseEntry {
+                aci: None,
+                pni: Some(Pni::from_uuid_bytes([b'q'; 16])),
+                e164: E164(nonzero!(18005550102u64)),
+            },
+        ];
 
         fn receive_frame(&mut self, frame: &[u8]) -> AttestedServerOutput {
             match self {
@@ -614,17 +698,23 @@ mod test {
                         "invalid message: {client_request:?}"
                     );
                     *self = Self::Finished;
-                    let mut triples_bytes = [0; LookupResponseEntry::SERIALIZED_LEN];
-                    Self::RESPONSE_RECORD.serialize_into(&mut triples_bytes);
-                    AttestedServerOutput {
-                        message: Some(
+                    let messages = Self::RESPONSE_RECORDS
+                        .iter()
+                        .enumerate()
+                        .map(|(i, record)| {
+                            let mut triples_bytes = [0; LookupResponseEntry::SERIALIZED_LEN];
+                            record.serialize_into(&mut triples_bytes);
                             ClientResponse {
-                                debug_permits_used: 1,
+                                // The real server only reports this once.
+                                debug_permits_used: (i == 0).into(),
                                 e164_pni_aci_triples: triples_bytes.to_vec(),
                                 ..Default::default()
                             }
-                            .encode_to_vec(),
-                        ),
+                            .encode_to_vec()
+                        })
+                        .collect();
+                    AttestedServerOutput {
+                        messages,
                         close_after: Some(None),
                     }
                 }
@@ -701,11 +791,64 @@ mod test {
             response,
             LookupResponse {
                 debug_permits_used: 1,
-                records: vec![FakeServerState::RESPONSE_RECORD],
+                records: FakeServerState::RESPONSE_RECORDS.to_vec(),
             }
         );
     }
 
+    #[tokio::test]
+    async fn lookup_streams_results_in_batches() {
+        let (server, client) = fake_websocket().await;
+
+        let fake_server = FakeServerState::default().into_handler();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            fake_server,
+        ));
+
+        let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+        let cdsi_connection = CdsiConnection(
+            AttestedConnection::connect(ws_client, |fake_attestation| {
+                assert_eq!(fake_attestation, FAKE_ATTESTATION);
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .expect("handshake failed"),
+        );
+
+        let (_token, collector) = cdsi_connection
+            .send_request(LookupRequest::default())
+            .await
+            .expect("request accepted");
+
+        let mut stream = collector.into_stream().await.expect("token acked");
+        for record in FakeServerState::RESPONSE_RECORDS {
+            assert_eq!(
+                stream.next_batch().await.expect("successful request"),
+                Some(vec![record])
+            );
+        }
+        assert_eq!(stream.next_batch().await.expect("closed normally"), None);
+        assert_eq!(stream.debug_permits_used(), 1);
+    }
+
+    #[test]
+    fn resuming_request_moves_new_e164s_to_prev() {
+        let e164 = |n| E164(NonZeroU64::new(n).unwrap());
+        let request = LookupRequest {
+            new_e164s: vec![e164(18005550101)],
+            prev_e164s: vec![e164(18005550102)],
+            token: b"old token".as_slice().into(),
+            ..Default::default()
+        };
+
+        let resumed = request.resuming_with(Token(b"new token".as_slice().into()));
+        assert_eq!(resumed.new_e164s, []);
+        assert_eq!(resumed.prev_e164s, [e164(18005550102), e164(18005550101)]);
+        assert_eq!(&*resumed.token, b"new token");
+    }
+
     const RETRY_AFTER_SECS: u32 = 12345;
 
     #[tokio::test]
@@ -749,9 +892,8 @@ mod test {
 
         assert_matches!(
             response,
-            Err(LookupError::RateLimited {
-                retry_after_seconds: RETRY_AFTER_SECS
-            })
+            Err(LookupError::RateLimited { retry_after })
+                if retry_after == Duration::from_secs(RETRY_AFTER_SECS.into())
         );
     }
 
@@ -799,9 +941,8 @@ mod test {
 
         assert_matches!(
             response,
-            Err(LookupError::RateLimited {
-                retry_after_seconds: RETRY_AFTER_SECS
-            })
+            Err(LookupError::RateLimited { retry_after })
+                if retry_after == Duration::from_secs(RETRY_AFTER_SECS.into())
         )
     }
 
@@ -826,9 +967,8 @@ mod test {
         let result = CdsiConnection::connect(&endpoint_connection, connector, auth).await;
         assert_matches!(
             result,
-            Err(LookupError::RateLimited {
-                retry_after_seconds: 100
-            })
+            Err(LookupError::RateLimited { retry_after })
+                if retry_after == Duration::from_secs(100)
         )
     }
 
@@ -2,6 +2,7 @@
 // Copyright 2023 Signal Messenger, LLC.
 // SPDX-License-Identifier: AGPL-3.0-only
 //
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -9,20 +10,29 @@ use ::http::uri::PathAndQuery;
 use ::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use async_trait::async_trait;
 use futures_util::future::BoxFuture;
+use futures_util::TryFutureExt as _;
 
-use crate::chat::ws::{ChatOverWebSocketServiceConnector, ServerRequest};
-use crate::infra::connection_manager::MultiRouteConnectionManager;
-use crate::infra::reconnect::{ServiceConnectorWithDecorator, ServiceWithReconnect};
-use crate::infra::ws::WebSocketClientConnector;
+use crate::chat::ws::{ChatOverWebSocketServiceConnector, ServerEvent};
+use crate::infra::connection_manager::{
+    ConnectionAttemptOutcome, ConnectionManager as _, MultiRouteConnectionManager,
+};
+use crate::infra::preconnect::PreconnectingConnector;
+use crate::infra::reconnect::{
+    ReconnectError, ServiceConnectorWithDecorator, ServiceWithReconnect,
+};
+use crate::infra::ws::{WebSocketClientConnector, WebSocketConnectError};
 use crate::infra::{
-    ConnectionInfo, EndpointConnection, HttpRequestDecorator, IpType, TransportConnector,
+    Alpn, ConnectionInfo, EndpointConnection, HttpRequestDecorator, IpType, TransportConnector,
 };
 use crate::proto;
+use crate::timeouts::PRECONNECT_EXPIRATION;
 use crate::utils::basic_authorization;
 
 pub mod chat_reconnect;
 mod error;
-use crate::timeouts::MULTI_ROUTE_CONNECTION_TIMEOUT;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
+pub mod provisioning;
 pub use error::ChatServiceError;
 
 pub mod server_requests;
@@ -39,6 +49,10 @@ pub trait ChatService {
     ///
     /// This API can be represented using different transports (e.g. WebSockets
     /// or HTTP) capable of sending [Request] objects.
+    ///
+    /// Dropping the returned future abandons just this request; the connection and any other
+    /// requests in flight are unaffected. This is how the bridge layer's cancellation tokens
+    /// cancel individual requests.
     async fn send(&self, msg: Request, timeout: Duration) -> Result<Response, ChatServiceError>;
 
     /// Establish a connection without sending a request.
@@ -145,6 +159,7 @@ impl From<ResponseProtoInvalidError> for ChatServiceError {
 pub struct Chat<AuthService, UnauthService> {
     auth_service: AuthorizedChatService<AuthService>,
     unauth_service: AnonymousChatService<UnauthService>,
+    transport_preconnector: Arc<dyn TransportPreconnect + Send + Sync>,
 }
 
 impl<AuthService, UnauthService> Chat<AuthService, UnauthService>
@@ -192,6 +207,20 @@ where
         self.unauth_service.connect_and_debug().await
     }
 
+    /// Warms up the connections to the Chat Service ahead of the first request.
+    ///
+    /// The authenticated socket is fully connected, as with [`Self::connect_authenticated`]. For
+    /// the unauthenticated socket, only DNS resolution and the TCP and TLS handshakes are done
+    /// up front; the resulting transport is used the next time the socket connects, as long as
+    /// that happens within [`PRECONNECT_EXPIRATION`].
+    pub async fn preconnect(&self) -> Result<(), ChatServiceError> {
+        let (auth_result, transport_result) = tokio::join!(
+            self.auth_service.connect(),
+            self.transport_preconnector.preconnect()
+        );
+        auth_result.and(transport_result)
+    }
+
     pub async fn disconnect(&self) {
         self.unauth_service.disconnect().await;
         self.auth_service.disconnect().await;
@@ -210,6 +239,7 @@ where
         let Self {
             auth_service,
             unauth_service,
+            transport_preconnector,
         } = self;
         {
             let auth_service = auth_service.into_dyn();
@@ -217,11 +247,53 @@ where
             Chat {
                 auth_service,
                 unauth_service,
+                transport_preconnector,
             }
         }
     }
 }
 
+/// Establishes a transport connection to the Chat Service ahead of time.
+#[async_trait]
+trait TransportPreconnect {
+    async fn preconnect(&self) -> Result<(), ChatServiceError>;
+}
+
+struct TransportPreconnector<T: TransportConnector> {
+    connection_manager: MultiRouteConnectionManager,
+    transport_connector: PreconnectingConnector<T>,
+}
+
+#[async_trait]
+impl<T: TransportConnector> TransportPreconnect for TransportPreconnector<T> {
+    async fn preconnect(&self) -> Result<(), ChatServiceError> {
+        let attempts = AtomicU16::new(0);
+        let outcome = self
+            .connection_manager
+            .connect_or_wait(|connection_params| {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                // Classified the same way as a failure to connect the socket itself.
+                self.transport_connector
+                    .preconnect(connection_params, Alpn::Http1_1)
+                    .map_err(WebSocketConnectError::Transport)
+            })
+            .await;
+        let attempts = attempts.into_inner();
+        // Report failures the same way as a failed connect, which also gives up once every route
+        // has failed and is cooling down.
+        let error = match outcome {
+            ConnectionAttemptOutcome::Attempted(Ok(())) => return Ok(()),
+            ConnectionAttemptOutcome::Attempted(Err(e)) => {
+                log::debug!("preconnect failed: {e}");
+                ReconnectError::AllRoutesFailed { attempts }
+            }
+            ConnectionAttemptOutcome::TimedOut => ReconnectError::Timeout { attempts },
+            ConnectionAttemptOutcome::WaitUntil(_) => ReconnectError::AllRoutesFailed { attempts },
+        };
+        Err(error.into())
+    }
+}
+
 /// Convenience trait for implementing [`ChatService`] on types that wrap
 /// another implementer of `ChatService`.
 ///
@@ -388,6 +460,7 @@ impl DelegatingChatService for Arc<dyn ChatServiceWithDebugInfo + Send + Sync> {
 
 fn build_authorized_chat_service(
     connection_manager_ws: &MultiRouteConnectionManager,
+    connect_timeout: Duration,
     service_connector_ws: &ChatOverWebSocketServiceConnector<impl TransportConnector + 'static>,
     username: String,
     password: String,
@@ -404,7 +477,7 @@ fn build_authorized_chat_service(
             header_auth_decorator.clone(),
         ),
         connection_manager_ws.clone(),
-        MULTI_ROUTE_CONNECTION_TIMEOUT,
+        connect_timeout,
     );
 
     AuthorizedChatService {
@@ -416,13 +489,14 @@ fn build_authorized_chat_service(
 
 fn build_anonymous_chat_service(
     connection_manager_ws: &MultiRouteConnectionManager,
+    connect_timeout: Duration,
     service_connector_ws: &ChatOverWebSocketServiceConnector<impl TransportConnector + 'static>,
 ) -> AnonymousChatService<impl ChatServiceWithDebugInfo> {
     // ws anonymous
     let chat_over_ws_anonymous = ServiceWithReconnect::new(
         service_connector_ws.clone(),
         connection_manager_ws.clone(),
-        MULTI_ROUTE_CONNECTION_TIMEOUT,
+        connect_timeout,
     );
 
     AnonymousChatService {
@@ -435,25 +509,36 @@ fn build_anonymous_chat_service(
 pub fn chat_service<T: TransportConnector + 'static>(
     endpoint: &EndpointConnection<MultiRouteConnectionManager>,
     transport_connector: T,
-    incoming_tx: tokio::sync::mpsc::Sender<ServerRequest<T::Stream>>,
+    incoming_tx: tokio::sync::mpsc::Sender<ServerEvent<T::Stream>>,
     username: String,
     password: String,
 ) -> Chat<impl ChatServiceWithDebugInfo, impl ChatServiceWithDebugInfo> {
+    let transport_connector =
+        PreconnectingConnector::new(transport_connector, PRECONNECT_EXPIRATION);
     let ws_service_connector = ChatOverWebSocketServiceConnector::new(
-        WebSocketClientConnector::new(transport_connector, endpoint.config.clone()),
+        WebSocketClientConnector::new(transport_connector.clone(), endpoint.config.clone()),
         incoming_tx,
     );
     {
         let auth_service = build_authorized_chat_service(
             &endpoint.manager,
+            endpoint.timeouts.multi_route_connection,
             &ws_service_connector,
             username,
             password,
         );
-        let unauth_service = build_anonymous_chat_service(&endpoint.manager, &ws_service_connector);
+        let unauth_service = build_anonymous_chat_service(
+            &endpoint.manager,
+            endpoint.timeouts.multi_route_connection,
+            &ws_service_connector.without_interruption_events(),
+        );
         Chat {
             auth_service,
             unauth_service,
+            transport_preconnector: Arc::new(TransportPreconnector {
+                connection_manager: endpoint.manager.clone(),
+                transport_connector,
+            }),
         }
     }
 }
@@ -3,9 +3,12 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::time::Duration;
+
 use crate::infra::errors::LogSafeDisplay;
 use crate::infra::reconnect;
 use crate::infra::ws::WebSocketServiceError;
+use crate::utils::rate_limit_retry_after;
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum ChatServiceError {
@@ -35,6 +38,8 @@ pub enum ChatServiceError {
     ServiceInactive,
     /// Service is unavailable due to the lost connection
     ServiceUnavailable,
+    /// Rate limited by the server; retry after {retry_after:?}
+    RateLimited { retry_after: Duration },
 }
 
 impl LogSafeDisplay for ChatServiceError {}
@@ -48,6 +53,12 @@ impl From<WebSocketServiceError> for ChatServiceError {
                 // but unidentified sockets should never produce a 403 anyway.
                 Self::DeviceDeregistered
             }
+            WebSocketServiceError::Http(response) => {
+                match rate_limit_retry_after(response.status(), response.headers()) {
+                    Some(retry_after) => Self::RateLimited { retry_after },
+                    None => Self::WebSocket(WebSocketServiceError::Http(response)),
+                }
+            }
             e => Self::WebSocket(e),
         }
     }
@@ -0,0 +1,554 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! An in-process chat server for end-to-end tests of chat clients.
+//!
+//! [`FakeChatServer`] accepts websocket connections made through its
+//! [`connector`](FakeChatServer::connector), and speaks the same protocol over them as the real
+//! chat server. Tests can inspect the requests clients send, decide how (and whether) to respond
+//! to each one, and push requests like incoming envelopes to connected clients.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt as _, StreamExt as _};
+use http::StatusCode;
+use libsignal_protocol::Timestamp;
+use prost::Message as _;
+use tokio::io::DuplexStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::WebSocketStream;
+
+use crate::chat::{ChatMessageType, MessageProto, RequestProto, ResponseProto};
+use crate::infra::errors::TransportConnectError;
+use crate::infra::{
+    Alpn, ConnectionInfo, ConnectionParams, DnsSource, RouteType, StreamAndInfo, TransportConnector,
+};
+
+/// The capacity of the in-memory pipe backing each connection, in each direction.
+const CONNECTION_BUFFER_SIZE: usize = 64 * 1024;
+
+/// An in-process stand-in for the chat server.
+///
+/// Cloning a `FakeChatServer` produces another handle to the same server.
+#[derive(Clone)]
+pub struct FakeChatServer {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    refuse_connections: AtomicBool,
+    /// Handles for sending frames on each connection, oldest first.
+    connections: Mutex<Vec<mpsc::UnboundedSender<Outgoing>>>,
+    request_tx: mpsc::UnboundedSender<ReceivedRequest>,
+    request_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<ReceivedRequest>>,
+}
+
+enum Outgoing {
+    Request(RequestProto, oneshot::Sender<ResponseProto>),
+    Response(ResponseProto),
+    Close,
+}
+
+/// [`TransportConnector`] that connects to a [`FakeChatServer`] instead of the network.
+#[derive(Clone)]
+pub struct FakeChatConnector {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, displaydoc::Display)]
+/// no client is connected to the fake chat server
+pub struct NoConnectedClient;
+
+impl std::error::Error for NoConnectedClient {}
+
+/// A request received from a client, waiting for a response.
+///
+/// Dropping a `ReceivedRequest` without responding means the client never gets a response, as if
+/// the server had lost the request.
+#[derive(Debug)]
+pub struct ReceivedRequest {
+    pub request: RequestProto,
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+}
+
+/// A response from a client to a request sent by [`FakeChatServer::send_request`].
+pub struct PendingResponse(oneshot::Receiver<ResponseProto>);
+
+impl Default for FakeChatServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeChatServer {
+    pub fn new() -> Self {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        Self {
+            shared: Arc::new(Shared {
+                refuse_connections: AtomicBool::new(false),
+                connections: Default::default(),
+                request_tx,
+                request_rx: tokio::sync::Mutex::new(request_rx),
+            }),
+        }
+    }
+
+    /// Returns a connector that clients can use to connect to this server.
+    pub fn connector(&self) -> FakeChatConnector {
+        FakeChatConnector {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Sets whether new connection attempts should fail.
+    ///
+    /// Existing connections are not affected; use [`Self::disconnect_all`] to close them.
+    pub fn set_refuse_connections(&self, refuse: bool) {
+        self.shared
+            .refuse_connections
+            .store(refuse, Ordering::Relaxed);
+    }
+
+    /// Returns the number of clients currently connected.
+    pub fn connection_count(&self) -> usize {
+        let mut connections = self.shared.connections.lock().expect("not poisoned");
+        connections.retain(|connection| !connection.is_closed());
+        connections.len()
+    }
+
+    /// Waits for the next request sent by any client, in the order they were received.
+    pub async fn next_request(&self) -> ReceivedRequest {
+        self.shared
+            .request_rx
+            .lock()
+            .await
+            .recv()
+            .await
+            .expect("the server holds a sender")
+    }
+
+    /// Sends a request to the most recently connected client.
+    pub fn send_request(
+        &self,
+        request: RequestProto,
+    ) -> Result<PendingResponse, NoConnectedClient> {
+        let (response_tx, response_rx) = oneshot::channel();
+        let mut connections = self.shared.connections.lock().expect("not poisoned");
+        connections.retain(|connection| !connection.is_closed());
+        connections
+            .last()
+            .ok_or(NoConnectedClient)?
+            .send(Outgoing::Request(request, response_tx))
+            .map_err(|_| NoConnectedClient)?;
+        Ok(PendingResponse(response_rx))
+    }
+
+    /// Delivers an envelope to the most recently connected client, like the server does for
+    /// queued messages.
+    pub fn send_envelope(
+        &self,
+        envelope: Vec<u8>,
+        server_delivery_timestamp: Timestamp,
+    ) -> Result<PendingResponse, NoConnectedClient> {
+        self.send_request(RequestProto {
+            verb: Some(http::Method::PUT.to_string()),
+            path: Some("/api/v1/message".to_string()),
+            body: Some(envelope),
+            headers: vec![format!(
+                "X-Signal-Timestamp: {}",
+                server_delivery_timestamp.epoch_millis()
+            )],
+            id: None,
+        })
+    }
+
+    /// Tells the most recently connected client that there are no more queued envelopes.
+    pub fn send_queue_empty(&self) -> Result<PendingResponse, NoConnectedClient> {
+        self.send_request(RequestProto {
+            verb: Some(http::Method::PUT.to_string()),
+            path: Some("/api/v1/queue/empty".to_string()),
+            body: None,
+            headers: vec![],
+            id: None,
+        })
+    }
+
+    /// Closes all open connections from the server side.
+    pub fn disconnect_all(&self) {
+        let connections =
+            std::mem::take(&mut *self.shared.connections.lock().expect("not poisoned"));
+        for connection in connections {
+            // If the connection is already closed, there's nothing to do.
+            _ = connection.send(Outgoing::Close);
+        }
+    }
+}
+
+impl ReceivedRequest {
+    /// Sends `response` as the response to this request.
+    ///
+    /// The ID of the response is filled in automatically.
+    pub fn respond(self, response: ResponseProto) {
+        let response = ResponseProto {
+            id: self.request.id,
+            ..response
+        };
+        // If the client has disconnected, the response is lost, as it would be with a real server.
+        _ = self.outgoing.send(Outgoing::Response(response));
+    }
+
+    /// Responds with `status` and an optional body.
+    pub fn respond_with_status(self, status: StatusCode, body: Option<Vec<u8>>) {
+        self.respond(ResponseProto {
+            id: None,
+            status: Some(status.as_u16().into()),
+            message: status.canonical_reason().map(String::from),
+            headers: vec![],
+            body,
+        })
+    }
+
+    /// Like [`Self::respond`], but waits for `delay` first.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn respond_after(self, delay: Duration, response: ResponseProto) {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            self.respond(response)
+        });
+    }
+}
+
+impl PendingResponse {
+    /// Waits for the client's response.
+    ///
+    /// Returns `None` if the connection was closed before the client responded.
+    pub async fn response(self) -> Option<ResponseProto> {
+        self.0.await.ok()
+    }
+}
+
+#[async_trait]
+impl TransportConnector for FakeChatConnector {
+    type Stream = DuplexStream;
+
+    async fn connect(
+        &self,
+        connection_params: &ConnectionParams,
+        _alpn: Alpn,
+    ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+        if self.shared.refuse_connections.load(Ordering::Relaxed) {
+            return Err(TransportConnectError::TcpConnectionFailed);
+        }
+
+        let (client, server) = tokio::io::duplex(CONNECTION_BUFFER_SIZE);
+        tokio::spawn(serve_connection(self.shared.clone(), server));
+
+        Ok(StreamAndInfo(
+            client,
+            ConnectionInfo {
+                route_type: RouteType::Direct,
+                dns_source: DnsSource::Static,
+                address: url::Host::Domain(connection_params.host.to_string()),
+            },
+        ))
+    }
+}
+
+/// Runs the server side of a single connection until it's closed by either end.
+async fn serve_connection(shared: Arc<Shared>, stream: DuplexStream) {
+    // Register the connection before the handshake completes, so that it is counted as soon as
+    // the client considers itself connected. Anything sent in the mean time is queued.
+    let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+    shared
+        .connections
+        .lock()
+        .expect("not poisoned")
+        .push(outgoing_tx.clone());
+
+    let websocket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            log::warn!("fake chat server: websocket handshake failed: {e}");
+            return;
+        }
+    };
+
+    run_connection(websocket, &shared.request_tx, outgoing_tx, outgoing_rx).await
+}
+
+async fn run_connection(
+    mut websocket: WebSocketStream<DuplexStream>,
+    request_tx: &mpsc::UnboundedSender<ReceivedRequest>,
+    outgoing_tx: mpsc::UnboundedSender<Outgoing>,
+    mut outgoing_rx: mpsc::UnboundedReceiver<Outgoing>,
+) {
+    let mut pending_responses = HashMap::<u64, oneshot::Sender<ResponseProto>>::new();
+    let mut next_request_id = 0;
+
+    loop {
+        let outgoing = tokio::select! {
+            incoming = websocket.next() => {
+                let data = match incoming {
+                    Some(Ok(tungstenite::Message::Binary(data))) => data,
+                    // Pings are answered by tungstenite itself.
+                    Some(Ok(
+                        tungstenite::Message::Ping(_)
+                        | tungstenite::Message::Pong(_)
+                        | tungstenite::Message::Frame(_),
+                    )) => continue,
+                    Some(Ok(tungstenite::Message::Text(_))) => {
+                        log::warn!("fake chat server: ignoring text frame");
+                        continue;
+                    }
+                    Some(Ok(tungstenite::Message::Close(_)) | Err(_)) | None => break,
+                };
+
+                let Ok(message) = MessageProto::decode(data.as_slice()) else {
+                    log::warn!("fake chat server: ignoring invalid frame");
+                    continue;
+                };
+                match (message.r#type(), message.request, message.response) {
+                    (ChatMessageType::Request, Some(request), None) => {
+                        // The receiver is never dropped while the server's state is alive.
+                        _ = request_tx.send(ReceivedRequest {
+                            request,
+                            outgoing: outgoing_tx.clone(),
+                        });
+                    }
+                    (ChatMessageType::Response, None, Some(response)) => {
+                        match response.id.and_then(|id| pending_responses.remove(&id)) {
+                            // The test may have stopped waiting for the response.
+                            Some(responder) => {
+                                _ = responder.send(response);
+                            }
+                            None => log::warn!(
+                                "fake chat server: response for unknown request {:?}",
+                                response.id
+                            ),
+                        }
+                    }
+                    _ => log::warn!("fake chat server: ignoring malformed message"),
+                }
+                continue;
+            }
+            outgoing = outgoing_rx.recv() => outgoing,
+        };
+
+        let message = match outgoing {
+            Some(Outgoing::Request(request, responder)) => {
+                let id = next_request_id;
+                next_request_id += 1;
+                pending_responses.insert(id, responder);
+                MessageProto {
+                    r#type: Some(ChatMessageType::Request.into()),
+                    request: Some(RequestProto {
+                        id: Some(id),
+                        ..request
+                    }),
+                    response: None,
+                }
+            }
+            Some(Outgoing::Response(response)) => MessageProto {
+                r#type: Some(ChatMessageType::Response.into()),
+                request: None,
+                response: Some(response),
+            },
+            Some(Outgoing::Close) | None => {
+                // The client might already be gone.
+                _ = websocket.close(None).await;
+                break;
+            }
+        };
+
+        if websocket
+            .send(tungstenite::Message::Binary(message.encode_to_vec()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use http::uri::PathAndQuery;
+    use http::Method;
+    use nonzero_ext::nonzero;
+
+    use crate::chat::server_requests::{stream_incoming_messages, ServerMessage};
+    use crate::chat::{
+        chat_service, ChatServiceError, ChatServiceWithDebugInfo, Request, TransportPreconnect as _,
+    };
+    use crate::infra::certs::RootCertificates;
+    use crate::infra::{make_ws_config, EndpointConnection};
+    use crate::timeouts::TimeoutConfig;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn fake_chat(
+        server: &FakeChatServer,
+    ) -> (
+        crate::chat::Chat<impl ChatServiceWithDebugInfo, impl ChatServiceWithDebugInfo>,
+        impl futures_util::Stream<Item = ServerMessage>,
+    ) {
+        let endpoint = EndpointConnection::new_multi(
+            [ConnectionParams::new(
+                RouteType::Direct,
+                "chat.test.signal.org",
+                "chat.test.signal.org",
+                nonzero!(443u16),
+                Default::default(),
+                RootCertificates::Signal,
+            )],
+            &TimeoutConfig::default().with_one_route_connection(TIMEOUT),
+            make_ws_config(PathAndQuery::from_static("/v1/websocket/"), TIMEOUT),
+        );
+        let (incoming_tx, incoming_rx) = mpsc::channel(1);
+        let chat = chat_service(
+            &endpoint,
+            server.connector(),
+            incoming_tx,
+            "user".to_string(),
+            "password".to_string(),
+        );
+        (chat, Box::pin(stream_incoming_messages(incoming_rx)))
+    }
+
+    fn get_request(path: &str) -> Request {
+        Request {
+            method: Method::GET,
+            path: PathAndQuery::try_from(path).expect("valid"),
+            headers: Default::default(),
+            body: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn request_and_response() {
+        let server = FakeChatServer::new();
+        let (chat, _incoming) = fake_chat(&server);
+        chat.connect_unauthenticated().await.expect("connected");
+        assert_eq!(server.connection_count(), 1);
+
+        let server_task = tokio::spawn({
+            let server = server.clone();
+            async move {
+                let received = server.next_request().await;
+                assert_eq!(received.request.verb(), "GET");
+                assert_eq!(received.request.path(), "/v1/config");
+                received.respond_with_status(StatusCode::OK, Some(b"config".to_vec()));
+            }
+        });
+
+        let response = chat
+            .send_unauthenticated(get_request("/v1/config"), TIMEOUT)
+            .await
+            .expect("response");
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body.as_deref(), Some(&b"config"[..]));
+        server_task.await.expect("server assertions pass");
+    }
+
+    #[tokio::test]
+    async fn preconnected_transport_is_reused() {
+        let server = FakeChatServer::new();
+        let (chat, _incoming) = fake_chat(&server);
+        chat.preconnect().await.expect("preconnected");
+        chat.connect_unauthenticated().await.expect("connected");
+
+        // One connection for the authenticated socket, plus the preconnected one that the
+        // unauthenticated socket picked up.
+        assert_eq!(server.connection_count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refused_preconnect_reports_failed_routes() {
+        let server = FakeChatServer::new();
+        let (chat, _incoming) = fake_chat(&server);
+        server.set_refuse_connections(true);
+
+        assert_matches!(
+            chat.transport_preconnector.preconnect().await,
+            Err(ChatServiceError::AllConnectionRoutesFailed { attempts }) if attempts > 0
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropped_request_times_out() {
+        let server = FakeChatServer::new();
+        let (chat, _incoming) = fake_chat(&server);
+        chat.connect_unauthenticated().await.expect("connected");
+
+        let server_task = tokio::spawn({
+            let server = server.clone();
+            async move { drop(server.next_request().await) }
+        });
+
+        assert_matches!(
+            chat.send_unauthenticated(get_request("/v1/config"), Duration::from_secs(1))
+                .await,
+            Err(ChatServiceError::Timeout)
+        );
+        server_task.await.expect("finished");
+    }
+
+    #[tokio::test]
+    async fn delivers_envelopes_and_receives_acks() {
+        let server = FakeChatServer::new();
+        let (chat, mut incoming) = fake_chat(&server);
+        chat.connect_authenticated().await.expect("connected");
+
+        let pending = server
+            .send_envelope(b"envelope".to_vec(), Timestamp::from_epoch_millis(1234))
+            .expect("connected");
+        let (envelope, server_delivery_timestamp, responder) = assert_matches!(
+            incoming.next().await,
+            Some(ServerMessage::IncomingMessage {
+                envelope,
+                server_delivery_timestamp,
+                responder,
+                ..
+            }) => (envelope, server_delivery_timestamp, responder)
+        );
+        assert_eq!(envelope, b"envelope");
+        assert_eq!(
+            server_delivery_timestamp,
+            Timestamp::from_epoch_millis(1234)
+        );
+
+        responder.ack().await.expect("can ack");
+        let ack = pending.response().await.expect("client responded");
+        assert_eq!(ack.status, Some(StatusCode::OK.as_u16().into()));
+
+        server.send_queue_empty().expect("connected");
+        assert_matches!(incoming.next().await, Some(ServerMessage::QueueEmpty));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disconnect_interrupts_and_refusal_blocks_reconnect() {
+        let server = FakeChatServer::new();
+        let (chat, mut incoming) = fake_chat(&server);
+        chat.connect_authenticated().await.expect("connected");
+
+        server.set_refuse_connections(true);
+        server.disconnect_all();
+        assert_matches!(
+            incoming.next().await,
+            Some(ServerMessage::ConnectionInterrupted(_))
+        );
+        assert_matches!(server.send_queue_empty(), Err(NoConnectedClient));
+        assert_matches!(chat.connect_authenticated().await, Err(_));
+        assert_eq!(server.connection_count(), 0);
+    }
+}
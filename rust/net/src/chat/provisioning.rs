@@ -0,0 +1,535 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Client for the unauthenticated provisioning websocket used when linking a new device.
+//!
+//! The new device connects to the provisioning endpoint and is assigned an address by the server.
+//! The address, together with the public half of a [`ProvisioningCipher`], is shown to the
+//! primary device (usually as a QR code, see [`provisioning_url`]). The primary device then sends
+//! a `ProvisionEnvelope` to that address, which the server forwards over the websocket.
+//!
+//! The envelope is sealed with a one-way handshake in the style of Noise's NK pattern: the sender
+//! knows our static provisioning key ahead of time, and combines it with a fresh ephemeral key
+//! whose public half is sent along with the ciphertext.
+
+use hmac::{Hmac, Mac};
+use libsignal_core::{Aci, Pni};
+use libsignal_protocol::{IdentityKey, IdentityKeyPair, KeyPair, PrivateKey, PublicKey};
+use prost::Message;
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::chat::ws::{ChatOverWebSocket, ChatOverWebSocketServiceConnector, ServerEvent};
+use crate::chat::{AutoDisconnecting, ChatServiceError};
+use crate::infra::connection_manager::MultiRouteConnectionManager;
+use crate::infra::reconnect::ServiceWithReconnect;
+use crate::infra::ws::WebSocketClientConnector;
+use crate::infra::{AsyncDuplexStream, EndpointConnection, TransportConnector};
+use crate::proto;
+
+const PROVISIONING_ADDRESS_PATH: &str = "/v1/address";
+const PROVISIONING_MESSAGE_PATH: &str = "/v1/message";
+
+/// The first byte of every provisioning envelope body.
+const ENVELOPE_VERSION: u8 = 1;
+const IV_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const KDF_INFO: &[u8] = b"TextSecure Provisioning Message";
+
+/// The server sends exactly two requests over a provisioning socket.
+const INCOMING_CHANNEL_CAPACITY: usize = 2;
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ProvisioningError {
+    /// chat service error: {0}
+    Chat(#[from] ChatServiceError),
+    /// connection closed before provisioning completed
+    ConnectionClosed,
+    /// server sent an unexpected request: {0}
+    UnexpectedRequest(String),
+    /// server sent an invalid provisioning address
+    InvalidAddress,
+    /// provisioning envelope could not be decrypted
+    InvalidEnvelope,
+    /// provisioning message is invalid: {0}
+    InvalidMessage(&'static str),
+}
+
+/// Decrypts provisioning envelopes sent to the public key of a freshly generated key pair.
+pub struct ProvisioningCipher {
+    key_pair: KeyPair,
+}
+
+impl ProvisioningCipher {
+    pub fn new<R: Rng + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            key_pair: KeyPair::generate(rng),
+        }
+    }
+
+    /// The key the primary device should encrypt its provisioning message to.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.key_pair.public_key
+    }
+
+    /// Decrypts and parses a serialized `ProvisionEnvelope`.
+    pub fn decrypt(&self, envelope: &[u8]) -> Result<ProvisionMessage, ProvisioningError> {
+        let envelope = proto::provisioning::ProvisionEnvelope::decode(envelope)
+            .map_err(|_| ProvisioningError::InvalidEnvelope)?;
+        let their_public_key = PublicKey::deserialize(envelope.public_key())
+            .map_err(|_| ProvisioningError::InvalidEnvelope)?;
+
+        let body = envelope.body();
+        if body.len() < 1 + IV_LEN + MAC_LEN || body[0] != ENVELOPE_VERSION {
+            return Err(ProvisioningError::InvalidEnvelope);
+        }
+        let (authenticated, their_mac) = body.split_at(body.len() - MAC_LEN);
+        let (iv, ciphertext) = authenticated[1..].split_at(IV_LEN);
+
+        let (cipher_key, mac_key) = derive_keys(&self.key_pair.private_key, &their_public_key)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&mac_key).expect("HMAC accepts any key size");
+        mac.update(authenticated);
+        mac.verify_slice(their_mac)
+            .map_err(|_| ProvisioningError::InvalidEnvelope)?;
+
+        let plaintext = signal_crypto::aes_256_cbc_decrypt(ciphertext, &cipher_key, iv)
+            .map_err(|_| ProvisioningError::InvalidEnvelope)?;
+        let message = proto::provisioning::ProvisionMessage::decode(plaintext.as_slice())
+            .map_err(|_| ProvisioningError::InvalidMessage("not a ProvisionMessage"))?;
+        message.try_into()
+    }
+}
+
+/// Splits the shared secret for an envelope into an AES-256-CBC key and an HMAC-SHA256 key.
+fn derive_keys(
+    our_private_key: &PrivateKey,
+    their_public_key: &PublicKey,
+) -> Result<([u8; 32], [u8; 32]), ProvisioningError> {
+    let shared_secret = our_private_key
+        .calculate_agreement(their_public_key)
+        .map_err(|_| ProvisioningError::InvalidEnvelope)?;
+    let mut derived = [0; 64];
+    hkdf::Hkdf::<Sha256>::new(None, &shared_secret)
+        .expand(KDF_INFO, &mut derived)
+        .expect("valid output length");
+    let (cipher_key, mac_key) = derived.split_at(32);
+    Ok((
+        cipher_key.try_into().expect("correct length"),
+        mac_key.try_into().expect("correct length"),
+    ))
+}
+
+/// The account information sent by the primary device to a newly linked device.
+pub struct ProvisionMessage {
+    pub aci: Aci,
+    pub pni: Pni,
+    pub number: String,
+    pub aci_identity_key_pair: IdentityKeyPair,
+    pub pni_identity_key_pair: IdentityKeyPair,
+    pub provisioning_code: String,
+    pub profile_key: [u8; 32],
+    pub read_receipts: bool,
+    pub user_agent: Option<String>,
+    pub provisioning_version: u32,
+    pub master_key: Option<[u8; 32]>,
+}
+
+impl std::fmt::Debug for ProvisionMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Leave out the keys and the provisioning code.
+        f.debug_struct("ProvisionMessage")
+            .field("aci", &self.aci)
+            .field("pni", &self.pni)
+            .field("read_receipts", &self.read_receipts)
+            .field("user_agent", &self.user_agent)
+            .field("provisioning_version", &self.provisioning_version)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TryFrom<proto::provisioning::ProvisionMessage> for ProvisionMessage {
+    type Error = ProvisioningError;
+
+    fn try_from(message: proto::provisioning::ProvisionMessage) -> Result<Self, Self::Error> {
+        fn identity_key_pair(
+            public: Option<Vec<u8>>,
+            private: Option<Vec<u8>>,
+        ) -> Result<IdentityKeyPair, ProvisioningError> {
+            let invalid = || ProvisioningError::InvalidMessage("invalid identity key pair");
+            let public =
+                IdentityKey::decode(&public.ok_or_else(invalid)?).map_err(|_| invalid())?;
+            let private =
+                PrivateKey::deserialize(&private.ok_or_else(invalid)?).map_err(|_| invalid())?;
+            if private.public_key().ok().as_ref() != Some(public.public_key()) {
+                return Err(invalid());
+            }
+            Ok(IdentityKeyPair::new(public, private))
+        }
+
+        fn uuid(value: Option<String>, field: &'static str) -> Result<Uuid, ProvisioningError> {
+            value
+                .and_then(|value| Uuid::try_parse(&value).ok())
+                .ok_or(ProvisioningError::InvalidMessage(field))
+        }
+
+        let proto::provisioning::ProvisionMessage {
+            aci_identity_key_public,
+            aci_identity_key_private,
+            pni_identity_key_public,
+            pni_identity_key_private,
+            aci,
+            pni,
+            number,
+            provisioning_code,
+            user_agent,
+            profile_key,
+            read_receipts,
+            provisioning_version,
+            master_key,
+        } = message;
+
+        Ok(Self {
+            aci: uuid(aci, "invalid ACI")?.into(),
+            pni: uuid(pni, "invalid PNI")?.into(),
+            number: number.ok_or(ProvisioningError::InvalidMessage("missing number"))?,
+            aci_identity_key_pair: identity_key_pair(
+                aci_identity_key_public,
+                aci_identity_key_private,
+            )?,
+            pni_identity_key_pair: identity_key_pair(
+                pni_identity_key_public,
+                pni_identity_key_private,
+            )?,
+            provisioning_code: provisioning_code.ok_or(ProvisioningError::InvalidMessage(
+                "missing provisioning code",
+            ))?,
+            profile_key: profile_key
+                .and_then(|key| key.try_into().ok())
+                .ok_or(ProvisioningError::InvalidMessage("invalid profile key"))?,
+            read_receipts: read_receipts.unwrap_or_default(),
+            user_agent,
+            provisioning_version: provisioning_version.unwrap_or_default(),
+            master_key: master_key
+                .map(|key| key.try_into())
+                .transpose()
+                .map_err(|_| ProvisioningError::InvalidMessage("invalid master key"))?,
+        })
+    }
+}
+
+/// Builds the `sgnl://linkdevice` URL that the primary device scans to find this device.
+pub fn provisioning_url(address: &str, public_key: &PublicKey) -> String {
+    let public_key = base64::Engine::encode(
+        &base64::engine::general_purpose::STANDARD,
+        public_key.serialize(),
+    );
+    url::Url::parse_with_params(
+        "sgnl://linkdevice",
+        [("uuid", address), ("pub_key", public_key.as_str())],
+    )
+    .expect("valid base URL")
+    .into()
+}
+
+/// An unauthenticated websocket waiting to receive a provisioning message.
+///
+/// The websocket is closed when this is dropped.
+pub struct ProvisioningConnection<S: AsyncDuplexStream + 'static> {
+    chat: AutoDisconnecting<ChatOverWebSocket<S>>,
+    incoming: mpsc::Receiver<ServerEvent<S>>,
+    cipher: ProvisioningCipher,
+}
+
+impl<S: AsyncDuplexStream + 'static> ProvisioningConnection<S> {
+    /// Connects to the provisioning websocket.
+    ///
+    /// `endpoint` must be configured with the provisioning path, e.g.
+    /// [`PROVISIONING_WEB_SOCKET_PATH`](crate::env::constants::PROVISIONING_WEB_SOCKET_PATH).
+    pub async fn connect<T: TransportConnector<Stream = S> + 'static>(
+        endpoint: &EndpointConnection<MultiRouteConnectionManager>,
+        transport_connector: T,
+        cipher: ProvisioningCipher,
+    ) -> Result<Self, ProvisioningError> {
+        let (incoming_tx, incoming) = mpsc::channel(INCOMING_CHANNEL_CAPACITY);
+        let service = ServiceWithReconnect::new(
+            ChatOverWebSocketServiceConnector::new(
+                WebSocketClientConnector::new(transport_connector, endpoint.config.clone()),
+                incoming_tx,
+            ),
+            endpoint.manager.clone(),
+            endpoint.timeouts.multi_route_connection,
+        );
+        service
+            .connect_from_inactive()
+            .await
+            .map_err(ChatServiceError::from)?;
+        let chat = service.service().await.map_err(ChatServiceError::from)?;
+        Ok(Self {
+            chat: AutoDisconnecting { inner: chat },
+            incoming,
+            cipher,
+        })
+    }
+
+    pub fn cipher(&self) -> &ProvisioningCipher {
+        &self.cipher
+    }
+
+    /// Waits for the server to assign this connection an address.
+    ///
+    /// Must be called before [`Self::provision_message`].
+    pub async fn provisioning_address(&mut self) -> Result<String, ProvisioningError> {
+        let body = next_request_body(&mut self.incoming, PROVISIONING_ADDRESS_PATH).await?;
+        proto::provisioning::ProvisioningAddress::decode(body.as_slice())
+            .ok()
+            .and_then(|address| address.address)
+            .filter(|address| !address.is_empty())
+            .ok_or(ProvisioningError::InvalidAddress)
+    }
+
+    /// Waits for the primary device's provisioning message, then closes the connection.
+    pub async fn provision_message(self) -> Result<ProvisionMessage, ProvisioningError> {
+        let Self {
+            chat,
+            mut incoming,
+            cipher,
+        } = self;
+        let result = next_request_body(&mut incoming, PROVISIONING_MESSAGE_PATH).await;
+        drop(chat);
+        cipher.decrypt(&result?)
+    }
+}
+
+/// Waits for the next request from the server, which must be a PUT to `expected_path`, and
+/// acknowledges it.
+async fn next_request_body<S: AsyncDuplexStream>(
+    incoming: &mut mpsc::Receiver<ServerEvent<S>>,
+    expected_path: &str,
+) -> Result<Vec<u8>, ProvisioningError> {
+    let request = match incoming.recv().await {
+        Some(ServerEvent::Request(request)) => request,
+        Some(ServerEvent::ConnectionInterrupted(e)) => return Err(e.into()),
+        None => return Err(ProvisioningError::ConnectionClosed),
+    };
+
+    let request_proto = request.request_proto;
+    if request_proto.verb() != http::Method::PUT.as_str() || request_proto.path() != expected_path {
+        log::error!(
+            "provisioning server sent unexpected request {} {}",
+            request_proto.verb(),
+            request_proto.path()
+        );
+        let _ignore_failed_response = request
+            .response_sender
+            .send_response(http::StatusCode::BAD_REQUEST)
+            .await;
+        return Err(ProvisioningError::UnexpectedRequest(
+            request_proto.path().to_owned(),
+        ));
+    }
+
+    request
+        .response_sender
+        .send_response(http::StatusCode::OK)
+        .await?;
+    Ok(request_proto.body.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use rand::rngs::OsRng;
+    use tokio::io::DuplexStream;
+
+    use super::*;
+    use crate::chat::ws::ServerRequest;
+    use crate::chat::RequestProto;
+
+    fn encrypt(their_public_key: &PublicKey, message: &[u8]) -> Vec<u8> {
+        let ephemeral = KeyPair::generate(&mut OsRng);
+        let (cipher_key, mac_key) =
+            derive_keys(&ephemeral.private_key, their_public_key).expect("valid keys");
+        let iv: [u8; IV_LEN] = OsRng.gen();
+
+        let mut body = vec![ENVELOPE_VERSION];
+        body.extend(iv);
+        body.extend(
+            signal_crypto::aes_256_cbc_encrypt(message, &cipher_key, &iv).expect("valid key"),
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(&mac_key).expect("any size");
+        mac.update(&body);
+        body.extend(mac.finalize().into_bytes());
+
+        proto::provisioning::ProvisionEnvelope {
+            public_key: Some(ephemeral.public_key.serialize().into()),
+            body: Some(body),
+        }
+        .encode_to_vec()
+    }
+
+    fn message_proto() -> proto::provisioning::ProvisionMessage {
+        let aci_identity = IdentityKeyPair::generate(&mut OsRng);
+        let pni_identity = IdentityKeyPair::generate(&mut OsRng);
+        proto::provisioning::ProvisionMessage {
+            aci_identity_key_public: Some(aci_identity.identity_key().serialize().into()),
+            aci_identity_key_private: Some(aci_identity.private_key().serialize()),
+            pni_identity_key_public: Some(pni_identity.identity_key().serialize().into()),
+            pni_identity_key_private: Some(pni_identity.private_key().serialize()),
+            aci: Some("9d0652a3-dcc3-4d11-975f-74d61598733f".to_owned()),
+            pni: Some("796abedb-ca4e-4f18-8803-1fde5b921f9f".to_owned()),
+            number: Some("+18005550100".to_owned()),
+            provisioning_code: Some("123456".to_owned()),
+            user_agent: Some("OWD".to_owned()),
+            profile_key: Some(vec![0x55; 32]),
+            read_receipts: Some(true),
+            provisioning_version: Some(1),
+            master_key: None,
+        }
+    }
+
+    #[test]
+    fn decrypt_round_trip() {
+        let cipher = ProvisioningCipher::new(&mut OsRng);
+        let envelope = encrypt(cipher.public_key(), &message_proto().encode_to_vec());
+
+        let message = cipher.decrypt(&envelope).expect("valid envelope");
+        assert_eq!(
+            message.aci.service_id_string(),
+            "9d0652a3-dcc3-4d11-975f-74d61598733f"
+        );
+        assert_eq!(message.number, "+18005550100");
+        assert_eq!(message.profile_key, [0x55; 32]);
+        assert!(message.read_receipts);
+        assert_eq!(message.master_key, None);
+    }
+
+    #[test]
+    fn decrypt_rejects_tampered_envelope() {
+        let cipher = ProvisioningCipher::new(&mut OsRng);
+        let envelope = encrypt(cipher.public_key(), &message_proto().encode_to_vec());
+        let mut envelope = proto::provisioning::ProvisionEnvelope::decode(envelope.as_slice())
+            .expect("valid proto");
+        envelope.body.as_mut().expect("has body")[1] ^= 1;
+
+        assert_matches!(
+            cipher.decrypt(&envelope.encode_to_vec()),
+            Err(ProvisioningError::InvalidEnvelope)
+        );
+    }
+
+    #[test]
+    fn decrypt_rejects_envelope_for_other_key() {
+        let cipher = ProvisioningCipher::new(&mut OsRng);
+        let other = ProvisioningCipher::new(&mut OsRng);
+        let envelope = encrypt(other.public_key(), &message_proto().encode_to_vec());
+
+        assert_matches!(
+            cipher.decrypt(&envelope),
+            Err(ProvisioningError::InvalidEnvelope)
+        );
+    }
+
+    #[test]
+    fn decrypt_rejects_mismatched_identity_key() {
+        let cipher = ProvisioningCipher::new(&mut OsRng);
+        let mut message = message_proto();
+        message.aci_identity_key_private = message.pni_identity_key_private.clone();
+        let envelope = encrypt(cipher.public_key(), &message.encode_to_vec());
+
+        assert_matches!(
+            cipher.decrypt(&envelope),
+            Err(ProvisioningError::InvalidMessage(_))
+        );
+    }
+
+    #[test]
+    fn url_contains_address_and_key() {
+        let cipher = ProvisioningCipher::new(&mut OsRng);
+        let url = url::Url::parse(&provisioning_url("some/address", cipher.public_key()))
+            .expect("valid URL");
+        assert_eq!(url.scheme(), "sgnl");
+        let params: Vec<_> = url.query_pairs().collect();
+        assert_eq!(params[0], ("uuid".into(), "some/address".into()));
+        assert_eq!(params[1].0, "pub_key");
+        assert_eq!(
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &*params[1].1)
+                .expect("valid base64"),
+            &*cipher.public_key().serialize()
+        );
+    }
+
+    fn put_request(id: u64, path: &str, body: Vec<u8>) -> ServerEvent<DuplexStream> {
+        ServerEvent::Request(ServerRequest::fake(RequestProto {
+            verb: Some("PUT".to_owned()),
+            path: Some(path.to_owned()),
+            body: Some(body),
+            headers: vec![],
+            id: Some(id),
+        }))
+    }
+
+    #[tokio::test]
+    async fn requests_are_read_in_order() {
+        let (tx, mut rx) = mpsc::channel(INCOMING_CHANNEL_CAPACITY);
+        tx.send(put_request(
+            1,
+            PROVISIONING_ADDRESS_PATH,
+            b"address".to_vec(),
+        ))
+        .await
+        .unwrap();
+        tx.send(put_request(
+            2,
+            PROVISIONING_MESSAGE_PATH,
+            b"envelope".to_vec(),
+        ))
+        .await
+        .unwrap();
+        drop(tx);
+
+        assert_eq!(
+            next_request_body(&mut rx, PROVISIONING_ADDRESS_PATH)
+                .await
+                .expect("address"),
+            b"address"
+        );
+        assert_eq!(
+            next_request_body(&mut rx, PROVISIONING_MESSAGE_PATH)
+                .await
+                .expect("message"),
+            b"envelope"
+        );
+        assert_matches!(
+            next_request_body(&mut rx, PROVISIONING_MESSAGE_PATH).await,
+            Err(ProvisioningError::ConnectionClosed)
+        );
+    }
+
+    #[tokio::test]
+    async fn unexpected_request_is_an_error() {
+        let (tx, mut rx) = mpsc::channel(INCOMING_CHANNEL_CAPACITY);
+        tx.send(put_request(1, "/api/v1/message", vec![]))
+            .await
+            .unwrap();
+        tx.send(ServerEvent::ConnectionInterrupted(
+            ChatServiceError::UnexpectedFrameReceived,
+        ))
+        .await
+        .unwrap();
+
+        assert_matches!(
+            next_request_body(&mut rx, PROVISIONING_ADDRESS_PATH).await,
+            Err(ProvisioningError::UnexpectedRequest(path)) if path == "/api/v1/message"
+        );
+        assert_matches!(
+            next_request_body(&mut rx, PROVISIONING_ADDRESS_PATH).await,
+            Err(ProvisioningError::Chat(
+                ChatServiceError::UnexpectedFrameReceived
+            ))
+        );
+    }
+}
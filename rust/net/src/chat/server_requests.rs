@@ -10,7 +10,7 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt as _;
 
-use crate::chat::ws::ServerRequest;
+use crate::chat::ws::{ResponseSender, ServerEvent, ServerRequest};
 use crate::chat::ChatServiceError;
 use crate::infra::AsyncDuplexStream;
 
@@ -22,8 +22,11 @@ pub enum ServerMessage {
         request_id: u64,
         envelope: Vec<u8>,
         server_delivery_timestamp: Timestamp,
-        send_ack: AckEnvelopeFuture,
+        responder: EnvelopeResponder,
     },
+    /// The authenticated connection was lost; messages that weren't acknowledged yet will be
+    /// delivered again on the next connection.
+    ConnectionInterrupted(ChatServiceError),
 }
 
 impl std::fmt::Debug for ServerMessage {
@@ -34,21 +37,57 @@ impl std::fmt::Debug for ServerMessage {
                 envelope,
                 server_delivery_timestamp,
                 request_id,
-                send_ack: _,
+                responder: _,
             } => f
                 .debug_struct("IncomingMessage")
                 .field("request_id", request_id)
                 .field("envelope", &format_args!("{} bytes", envelope.len()))
                 .field("server_delivery_timestamp", server_delivery_timestamp)
                 .finish(),
+            Self::ConnectionInterrupted(reason) => f
+                .debug_tuple("ConnectionInterrupted")
+                .field(reason)
+                .finish(),
         }
     }
 }
 
+/// Responds to a single [`ServerMessage::IncomingMessage`], either accepting or rejecting it.
+///
+/// Dropping the responder without using it leaves the server waiting; the message will be
+/// delivered again once the connection is re-established.
+pub struct EnvelopeResponder(Box<dyn FnOnce(http::StatusCode) -> AckEnvelopeFuture + Send>);
+
+impl EnvelopeResponder {
+    fn new<S: AsyncDuplexStream + 'static>(response_sender: ResponseSender<S>) -> Self {
+        Self(Box::new(move |status| {
+            Box::pin(response_sender.send_response(status))
+        }))
+    }
+
+    /// Tells the server the envelope has been processed, so it can be removed from the queue.
+    pub fn ack(self) -> AckEnvelopeFuture {
+        (self.0)(http::StatusCode::OK)
+    }
+
+    /// Tells the server the envelope could not be processed.
+    ///
+    /// The envelope stays in the queue and will be delivered again on a later connection.
+    pub fn nack(self) -> AckEnvelopeFuture {
+        (self.0)(http::StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
 pub fn stream_incoming_messages(
-    receiver: mpsc::Receiver<ServerRequest<impl AsyncDuplexStream + 'static>>,
+    receiver: mpsc::Receiver<ServerEvent<impl AsyncDuplexStream + 'static>>,
 ) -> impl Stream<Item = ServerMessage> {
-    ReceiverStream::new(receiver).filter_map(|request| {
+    ReceiverStream::new(receiver).filter_map(|event| {
+        let request = match event {
+            ServerEvent::Request(request) => request,
+            ServerEvent::ConnectionInterrupted(reason) => {
+                return Some(ServerMessage::ConnectionInterrupted(reason))
+            }
+        };
         let ServerRequest {
             request_proto,
             response_sender,
@@ -90,7 +129,7 @@ pub fn stream_incoming_messages(
                     server_delivery_timestamp: Timestamp::from_epoch_millis(
                         raw_timestamp.unwrap_or_default(),
                     ),
-                    send_ack: Box::pin(response_sender.send_response(http::StatusCode::OK)),
+                    responder: EnvelopeResponder::new(response_sender),
                 }
             }
             "" => {
@@ -105,3 +144,72 @@ pub fn stream_incoming_messages(
         Some(message)
     })
 }
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use futures_util::StreamExt as _;
+    use tokio::io::DuplexStream;
+
+    use super::*;
+    use crate::chat::RequestProto;
+
+    fn put_request(
+        id: u64,
+        path: &str,
+        headers: &[&str],
+        body: &[u8],
+    ) -> ServerEvent<DuplexStream> {
+        ServerEvent::Request(ServerRequest::fake(RequestProto {
+            verb: Some("PUT".to_owned()),
+            path: Some(path.to_owned()),
+            body: Some(body.to_vec()),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            id: Some(id),
+        }))
+    }
+
+    #[tokio::test]
+    async fn stream_delivers_typed_messages_in_order() {
+        let (tx, rx) = mpsc::channel(4);
+        let mut messages = Box::pin(stream_incoming_messages(rx));
+
+        tx.send(put_request(
+            1,
+            "/api/v1/message",
+            &["x-signal-timestamp: 1000"],
+            b"envelope",
+        ))
+        .await
+        .unwrap();
+        tx.send(put_request(2, "/unknown", &[], b"")).await.unwrap();
+        tx.send(put_request(3, "/api/v1/queue/empty", &[], b""))
+            .await
+            .unwrap();
+        tx.send(ServerEvent::ConnectionInterrupted(
+            ChatServiceError::UnexpectedFrameReceived,
+        ))
+        .await
+        .unwrap();
+        drop(tx);
+
+        let responder = assert_matches!(
+            messages.next().await,
+            Some(ServerMessage::IncomingMessage {
+                request_id: 1,
+                envelope,
+                server_delivery_timestamp,
+                responder,
+            }) if envelope == b"envelope" && server_delivery_timestamp.epoch_millis() == 1000 => responder
+        );
+        responder.nack().await.expect("fake requests can be nacked");
+        assert_matches!(messages.next().await, Some(ServerMessage::QueueEmpty));
+        assert_matches!(
+            messages.next().await,
+            Some(ServerMessage::ConnectionInterrupted(
+                ChatServiceError::UnexpectedFrameReceived
+            ))
+        );
+        assert_matches!(messages.next().await, None);
+    }
+}
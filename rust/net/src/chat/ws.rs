@@ -4,7 +4,7 @@
 //
 
 use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -12,7 +12,7 @@ use derive_where::derive_where;
 use http::header::ToStrError;
 use http::status::StatusCode;
 use prost::Message;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot};
 use tokio::time::Instant;
 use tokio_tungstenite::WebSocketStream;
 
@@ -27,6 +27,7 @@ use crate::infra::ws::{
 };
 use crate::infra::{AsyncDuplexStream, ConnectionInfo, ConnectionParams, TransportConnector};
 use crate::proto::chat_websocket::web_socket_message::Type;
+use crate::utils::rate_limit_retry_after;
 
 #[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Copy)]
 struct RequestId {
@@ -97,6 +98,16 @@ impl<S: AsyncDuplexStream> ServerRequest<S> {
     }
 }
 
+/// An item passed from a [`ChatOverWebSocket`] to whoever is consuming incoming server requests.
+#[derive(Debug)]
+pub enum ServerEvent<S> {
+    Request(ServerRequest<S>),
+    /// The connection was lost for a reason other than being stopped locally.
+    ///
+    /// Requests delivered before this can no longer be responded to.
+    ConnectionInterrupted(ChatServiceError),
+}
+
 #[derive(Debug, Default)]
 struct PendingMessagesMap {
     pending: HashMap<RequestId, oneshot::Sender<ResponseProto>>,
@@ -139,17 +150,30 @@ impl PendingMessagesMap {
 #[derive_where(Clone)]
 pub(super) struct ChatOverWebSocketServiceConnector<T: TransportConnector> {
     ws_client_connector: WebSocketClientConnector<T, ChatServiceError>,
-    incoming_tx: mpsc::Sender<ServerRequest<T::Stream>>,
+    incoming_tx: mpsc::Sender<ServerEvent<T::Stream>>,
+    report_interruptions: bool,
 }
 
 impl<T: TransportConnector> ChatOverWebSocketServiceConnector<T> {
     pub fn new(
         ws_client_connector: WebSocketClientConnector<T, ChatServiceError>,
-        incoming_tx: mpsc::Sender<ServerRequest<T::Stream>>,
+        incoming_tx: mpsc::Sender<ServerEvent<T::Stream>>,
     ) -> Self {
         Self {
             ws_client_connector,
             incoming_tx,
+            report_interruptions: true,
+        }
+    }
+
+    /// Returns a connector whose connections don't send [`ServerEvent::ConnectionInterrupted`].
+    ///
+    /// The server never sends requests over the unauthenticated connection, so listeners aren't
+    /// interested in when it goes away.
+    pub fn without_interruption_events(&self) -> Self {
+        Self {
+            report_interruptions: false,
+            ..self.clone()
         }
     }
 }
@@ -185,6 +209,7 @@ impl<T: TransportConnector> ServiceConnector for ChatOverWebSocketServiceConnect
             ws_client_reader,
             ws_client_writer.clone(),
             self.incoming_tx.clone(),
+            self.report_interruptions,
             pending_messages.clone(),
             service_status.clone(),
         ));
@@ -203,7 +228,8 @@ impl<T: TransportConnector> ServiceConnector for ChatOverWebSocketServiceConnect
 async fn reader_task<S: AsyncDuplexStream + 'static>(
     mut ws_client_reader: WebSocketClientReader<S, ChatServiceError>,
     ws_client_writer: WebSocketClientWriter<S, ChatServiceError>,
-    incoming_tx: mpsc::Sender<ServerRequest<S>>,
+    incoming_tx: mpsc::Sender<ServerEvent<S>>,
+    report_interruptions: bool,
     pending_messages: Arc<Mutex<PendingMessagesMap>>,
     service_status: ServiceStatus<ChatServiceError>,
 ) {
@@ -211,22 +237,21 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
     let mut previous_request_paths_for_logging =
         VecDeque::with_capacity(incoming_tx.max_capacity());
 
-    loop {
+    // `None` if the service was stopped from outside this task, e.g. by a call to `disconnect`.
+    let disconnect_cause = loop {
         let data = match ws_client_reader.next().await {
             Ok(NextOrClose::Next(TextOrBinary::Binary(data))) => data,
             Ok(NextOrClose::Next(TextOrBinary::Text(_))) => {
                 log::info!("Text frame received on chat websocket");
-                service_status.stop_service_with_error(ChatServiceError::UnexpectedFrameReceived);
-                break;
+                break Some(ChatServiceError::UnexpectedFrameReceived);
             }
             Ok(NextOrClose::Close(_)) => {
-                service_status.stop_service_with_error(WebSocketServiceError::ChannelClosed.into());
-                break;
-            }
-            Err(e) => {
-                service_status.stop_service_with_error(e);
-                break;
+                break Some(WebSocketServiceError::ChannelClosed.into());
             }
+            // The reader reports closes from the server as `Close`, so this only happens once the
+            // service has been stopped locally.
+            Err(ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed)) => break None,
+            Err(e) => break Some(e),
         };
 
         // binary data received
@@ -234,15 +259,12 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
             Ok(ChatMessage::Request(req)) => {
                 let server_request = match ServerRequest::new(req, ws_client_writer.clone()) {
                     Ok(server_request) => server_request,
-                    Err(e) => {
-                        service_status.stop_service_with_error(e);
-                        break;
-                    }
+                    Err(e) => break Some(e),
                 };
                 let request_path = server_request.request_proto.path().to_owned();
 
                 let request_send_start = Instant::now();
-                let delivery_result = incoming_tx.send(server_request).await;
+                let delivery_result = incoming_tx.send(ServerEvent::Request(server_request)).await;
                 let request_send_elapsed = request_send_start.elapsed();
 
                 if delivery_result.is_err() {
@@ -274,24 +296,45 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
                 previous_request_paths_for_logging.push_back(request_path);
             }
             Ok(ChatMessage::Response(id, res)) => {
-                let map = &mut pending_messages.lock().await;
-                if let Some(sender) = map.remove(&id) {
+                let sender = pending_messages.lock().expect("not poisoned").remove(&id);
+                if let Some(sender) = sender {
                     // this doesn't have to be successful,
                     // e.g. request might have timed out
                     let _ignore_failed_send = sender.send(res);
                 }
             }
-            Err(e) => {
-                service_status.stop_service_with_error(e);
-            }
+            Err(e) => break Some(e),
         }
-    }
+    };
+
     // before terminating the task, marking channel as inactive
-    service_status.stop_service();
+    let interruption = match disconnect_cause {
+        Some(e) if report_interruptions => {
+            log::info!("chat connection interrupted: {e}");
+            service_status.stop_service();
+            Some(e)
+        }
+        Some(e) => {
+            service_status.stop_service_with_error(e);
+            None
+        }
+        None => {
+            service_status.stop_service();
+            None
+        }
+    };
 
     // Clear the pending messages map. These requests don't wait on the service status just in case
     // a response comes in late; dropping the response senders is how we cancel them.
-    pending_messages.lock().await.cancel_all();
+    pending_messages.lock().expect("not poisoned").cancel_all();
+
+    if let Some(e) = interruption {
+        // This comes after any requests that were already delivered. It's fine if nobody is
+        // listening anymore.
+        let _ignore_closed_channel = incoming_tx
+            .send(ServerEvent::ConnectionInterrupted(e))
+            .await;
+    }
 }
 
 #[derive_where(Clone)]
@@ -309,12 +352,12 @@ impl<S> RemoteAddressInfo for ChatOverWebSocket<S> {
     }
 }
 
-#[async_trait]
-impl<S> ChatService for ChatOverWebSocket<S>
-where
-    S: AsyncDuplexStream,
-{
-    async fn send(&self, msg: Request, timeout: Duration) -> Result<Response, ChatServiceError> {
+impl<S: AsyncDuplexStream> ChatOverWebSocket<S> {
+    /// Writes a request to the websocket without waiting for the response.
+    ///
+    /// The returned handle can be used to wait for the response. Dropping it cancels just this
+    /// request: any response that arrives later is discarded, and the connection is unaffected.
+    async fn start_request(&self, msg: Request) -> Result<InFlightRequest, ChatServiceError> {
         // checking if channel has been closed
         if self.service_status.is_stopped() {
             return Err(WebSocketServiceError::ChannelClosed.into());
@@ -322,13 +365,20 @@ where
 
         let (response_tx, response_rx) = oneshot::channel::<ResponseProto>();
 
-        // defining a scope here to release the lock ASAP
-        let id = {
-            let map = &mut self.pending_messages.lock().await;
-            // It's possible that the service has been stopped between the check above and the
-            // insert below. This accounts for that.
-            map.insert(response_tx)
-                .map_err(|_| WebSocketServiceError::ChannelClosed)?
+        // It's possible that the service has been stopped between the check above and the
+        // insert below. This accounts for that.
+        let id = self
+            .pending_messages
+            .lock()
+            .expect("not poisoned")
+            .insert(response_tx)
+            .map_err(|_| WebSocketServiceError::ChannelClosed)?;
+        // Create the handle right away, so the request is deregistered even if writing fails or
+        // this future is dropped partway through.
+        let in_flight = InFlightRequest {
+            id,
+            response_rx,
+            pending_messages: Arc::downgrade(&self.pending_messages),
         };
 
         let msg = request_to_websocket_proto(msg, id)
@@ -336,15 +386,57 @@ where
 
         self.ws_client_writer.send(msg.encode_to_vec()).await?;
 
-        tokio::select! {
-            result = response_rx => result.map_err(|_| WebSocketServiceError::ChannelClosed.into()),
-            _ = tokio::time::sleep(timeout) => {
-                let map = &mut self.pending_messages.lock().await;
-                map.remove(&id);
-                Err(ChatServiceError::Timeout)
-            },
+        Ok(in_flight)
+    }
+}
+
+/// A request that has been sent over a [`ChatOverWebSocket`] and is waiting for a response.
+///
+/// Dropping the handle abandons the request without affecting the connection or any other
+/// requests in flight.
+#[derive(Debug)]
+struct InFlightRequest {
+    id: RequestId,
+    response_rx: oneshot::Receiver<ResponseProto>,
+    pending_messages: Weak<Mutex<PendingMessagesMap>>,
+}
+
+impl InFlightRequest {
+    /// Waits up to `timeout` for the server to respond.
+    ///
+    /// If the timeout elapses, the request is abandoned as if the handle had been dropped.
+    async fn response(mut self, timeout: Duration) -> Result<Response, ChatServiceError> {
+        let response_proto = match tokio::time::timeout(timeout, &mut self.response_rx).await {
+            Ok(result) => result.map_err(|_| WebSocketServiceError::ChannelClosed)?,
+            Err(_elapsed) => return Err(ChatServiceError::Timeout),
+        };
+        let response: Response = response_proto.try_into()?;
+        match rate_limit_retry_after(response.status, &response.headers) {
+            Some(retry_after) => Err(ChatServiceError::RateLimited { retry_after }),
+            None => Ok(response),
         }
-        .and_then(|response_proto| Ok(response_proto.try_into()?))
+    }
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        // If the response already arrived, the entry is gone and this does nothing.
+        if let Some(pending_messages) = self.pending_messages.upgrade() {
+            pending_messages
+                .lock()
+                .expect("not poisoned")
+                .remove(&self.id);
+        }
+    }
+}
+
+#[async_trait]
+impl<S> ChatService for ChatOverWebSocket<S>
+where
+    S: AsyncDuplexStream,
+{
+    async fn send(&self, msg: Request, timeout: Duration) -> Result<Response, ChatServiceError> {
+        self.start_request(msg).await?.response(timeout).await
     }
 
     async fn connect(&self) -> Result<(), ChatServiceError> {
@@ -434,10 +526,11 @@ mod test {
     use crate::chat::test::shared::{connection_manager, test_request};
     use crate::chat::ws::{
         decode_and_validate, request_to_websocket_proto, ChatMessage,
-        ChatOverWebSocketServiceConnector, ChatServiceError, RequestId, ServerRequest,
+        ChatOverWebSocketServiceConnector, ChatServiceError, RequestId, ServerEvent, ServerRequest,
     };
     use crate::chat::{ChatMessageType, ChatService, MessageProto, ResponseProto};
 
+    use crate::infra::reconnect::ServiceState;
     use crate::infra::test::shared::{
         InMemoryWarpConnector, NoReconnectService, TestError, TIMEOUT_DURATION,
     };
@@ -522,6 +615,58 @@ mod test {
         validate_server_stopped_successfully(server_res_rx).await;
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reports_interruption_but_not_disconnect() {
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            tokio::spawn(async move { while (rx.next().await).is_some() {} });
+            tx.send(warp::filters::ws::Message::close())
+                .await
+                .expect("can send")
+        });
+
+        let (ws_chat, mut incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        assert_matches!(
+            incoming_rx.recv().await,
+            Some(ServerEvent::ConnectionInterrupted(
+                ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed)
+            ))
+        );
+        assert!(ws_chat.service_status().unwrap().is_stopped());
+
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (_, mut rx) = websocket.split();
+            while (rx.next().await).is_some() {}
+        });
+        let (ws_chat, mut incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        ws_chat.disconnect().await;
+        drop(ws_chat);
+        assert_matches!(
+            tokio::time::timeout(TIMEOUT_DURATION, incoming_rx.recv()).await,
+            Ok(None) | Err(_)
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reports_interruption_when_server_drops_connection() {
+        let ws_config = test_ws_config();
+        let max_idle_time = ws_config.max_idle_time;
+        let time_before_drop = max_idle_time / 3;
+
+        // The server goes away without sending a `Close` frame.
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            tokio::time::sleep(time_before_drop).await;
+            drop(websocket);
+        });
+
+        let (ws_chat, mut incoming_rx) = create_ws_chat_service(ws_config, ws_server).await;
+        assert_matches!(
+            tokio::time::timeout(max_idle_time / 2, incoming_rx.recv()).await,
+            Ok(Some(ServerEvent::ConnectionInterrupted(_)))
+        );
+        assert!(ws_chat.service_status().unwrap().is_stopped());
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_stops_on_unexpected_frame_from_server() {
         let ws_config = test_ws_config();
@@ -583,6 +728,42 @@ mod test {
         validate_server_running(server_res_rx).await;
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reports_rate_limited_response() {
+        let (ws_server, server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            loop {
+                let msg = rx.next().await.expect("not closed").expect("not an error");
+                let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                let mut message_proto =
+                    response_for_request(&request, StatusCode::TOO_MANY_REQUESTS)
+                        .expect("is valid request");
+                message_proto
+                    .response
+                    .as_mut()
+                    .expect("is response")
+                    .headers
+                    .push("Retry-After: 7".to_string());
+                tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                    .await
+                    .expect("can send response")
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        let response = ws_chat
+            .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+            .await;
+        assert_matches!(
+            response,
+            Err(ChatServiceError::RateLimited { retry_after })
+                if retry_after == Duration::from_secs(7)
+        );
+        validate_server_running(server_res_rx).await;
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_times_out_on_late_response_from_server() {
         // creating a server that responds to requests with 200
@@ -703,7 +884,10 @@ mod test {
         let ServerRequest {
             request_proto: _,
             response_sender,
-        } = incoming_rx.recv().await.expect("server request");
+        } = assert_matches!(
+            incoming_rx.recv().await.expect("server request"),
+            ServerEvent::Request(request) => request
+        );
 
         response_sender
             .send_response(StatusCode::OK)
@@ -798,6 +982,62 @@ mod test {
         );
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_cancelled_request_does_not_affect_connection() {
+        // creating a server that responds to requests with 200 after some request processing time
+        const REQUEST_PROCESSING_DURATION: Duration =
+            Duration::from_millis(TIMEOUT_DURATION.as_millis() as u64 / 2);
+        let (ws_server, server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (tx, mut rx) = websocket.split();
+            let shared_sender = Arc::new(Mutex::new(tx));
+            loop {
+                let msg = rx.next().await.expect("not closed").expect("not an error");
+                assert!(msg.is_binary(), "not binary: {msg:?}");
+                let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                let response_proto =
+                    response_for_request(&request, StatusCode::OK).expect("response");
+                let shared_sender = shared_sender.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(REQUEST_PROCESSING_DURATION).await;
+                    let mut sender = shared_sender.lock().await;
+                    let _ignore_result = (*sender)
+                        .send(warp::ws::Message::binary(response_proto.encode_to_vec()))
+                        .await;
+                });
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+        let service = assert_matches!(&*ws_chat.inner, ServiceState::Active(service, _) => service);
+
+        let cancelled = service
+            .start_request(test_request(Method::GET, "/"))
+            .await
+            .expect("request sent");
+        drop(cancelled);
+        assert!(service
+            .pending_messages
+            .lock()
+            .expect("not poisoned")
+            .pending
+            .is_empty());
+
+        // The response to the cancelled request arrives while this one is in flight, and is
+        // dropped on the floor.
+        let in_flight = service
+            .start_request(test_request(Method::GET, "/"))
+            .await
+            .expect("request sent");
+        in_flight
+            .response(TIMEOUT_DURATION)
+            .await
+            .expect("request completed successfully");
+
+        assert!(!service.service_status.is_stopped());
+        validate_server_running(server_res_rx).await;
+    }
+
     async fn validate_server_running(mut server_res_rx: Receiver<Result<(), ServerExitError>>) {
         assert_matches!(
             tokio::time::timeout(Duration::from_millis(100), server_res_rx.recv()).await,
@@ -837,13 +1077,13 @@ mod test {
         ws_server: F,
     ) -> (
         NoReconnectService<ChatOverWebSocketServiceConnector<InMemoryWarpConnector<F>>>,
-        Receiver<ServerRequest<DuplexStream>>,
+        Receiver<ServerEvent<DuplexStream>>,
     )
     where
         F: Filter + Clone + Send + Sync + 'static,
         F::Extract: Reply,
     {
-        let (incoming_tx, incoming_rx) = mpsc::channel::<ServerRequest<DuplexStream>>(512);
+        let (incoming_tx, incoming_rx) = mpsc::channel::<ServerEvent<DuplexStream>>(512);
         let ws_connector = ChatOverWebSocketServiceConnector::new(
             WebSocketClientConnector::new(InMemoryWarpConnector::new(ws_server), ws_config),
             incoming_tx,
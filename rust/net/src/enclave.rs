@@ -4,18 +4,20 @@
 //
 
 use std::marker::PhantomData;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use attest::svr2::RaftConfig;
-use attest::{cds2, enclave, nitro, tpm2snp};
+use attest::{cds2, enclave, nitro, tdx, tpm2snp};
 use derive_where::derive_where;
 use http::uri::PathAndQuery;
 
 use crate::auth::HttpBasicAuth;
 use crate::env::{DomainConfig, Svr3Env};
+use crate::infra::backoff::BackoffPolicy;
 use crate::infra::connection_manager::{
     ConnectionManager, ErrorClass, ErrorClassifier, MultiRouteConnectionManager,
-    SingleRouteThrottlingConnectionManager,
+    NetworkChangeListener, SingleRouteThrottlingConnectionManager,
 };
 use crate::infra::errors::LogSafeDisplay;
 use crate::infra::reconnect::{ServiceConnectorWithDecorator, ServiceInitializer, ServiceState};
@@ -27,6 +29,7 @@ use crate::infra::{
     make_ws_config, AsyncDuplexStream, ConnectionParams, EndpointConnection, TransportConnector,
 };
 use crate::svr::SvrConnection;
+use crate::timeouts::TimeoutConfig;
 
 pub trait AsRaftConfig<'a> {
     fn as_raft_config(&self) -> Option<&'a RaftConfig>;
@@ -59,6 +62,10 @@ pub enum Nitro {}
 
 pub enum Tpm2Snp {}
 
+/// An enclave running in an Intel TDX trust domain, identified by its MRTD, MRCONFIGID, and RTMRs
+/// (see [`attest::tdx`])
+pub enum Tdx {}
+
 impl EnclaveKind for Cdsi {
     type RaftConfigType = ();
     fn url_path(enclave: &[u8]) -> PathAndQuery {
@@ -95,12 +102,21 @@ impl EnclaveKind for Tpm2Snp {
     }
 }
 
+impl EnclaveKind for Tdx {
+    type RaftConfigType = &'static RaftConfig;
+    fn url_path(enclave: &[u8]) -> PathAndQuery {
+        PathAndQuery::try_from(format!("/v1/{}", hex::encode(enclave))).unwrap()
+    }
+}
+
 impl Svr3Flavor for Sgx {}
 
 impl Svr3Flavor for Nitro {}
 
 impl Svr3Flavor for Tpm2Snp {}
 
+impl Svr3Flavor for Tdx {}
+
 pub trait IntoConnections {
     type Stream;
     type Connections: ArrayIsh<AttestedConnection<Self::Stream>> + Send;
@@ -236,6 +252,8 @@ pub enum Error {
     AttestationError(attest::enclave::Error),
     /// Connection timeout
     ConnectionTimedOut,
+    /// Request timeout
+    RequestTimedOut,
 }
 
 impl LogSafeDisplay for Error {}
@@ -247,6 +265,7 @@ impl From<AttestedConnectionError> for Error {
             AttestedConnectionError::WebSocket(net) => Self::WebSocket(net),
             AttestedConnectionError::Protocol => Self::Protocol,
             AttestedConnectionError::Sgx(err) => Self::AttestationError(err),
+            AttestedConnectionError::RequestTimedOut => Self::RequestTimedOut,
         }
     }
 }
@@ -324,21 +343,33 @@ async fn connect_attested<
         }
     }?;
     let attested = AttestedConnection::connect(websocket, do_handshake).await?;
-    Ok(attested)
+    Ok(attested.with_request_timeout(endpoint_connection.timeouts.request))
 }
 
 impl<E: EnclaveKind> EnclaveEndpointConnection<E, SingleRouteThrottlingConnectionManager> {
     pub fn new(endpoint: &EnclaveEndpoint<'static, E>, connect_timeout: Duration) -> Self {
+        Self::with_timeouts(
+            endpoint,
+            &TimeoutConfig::default().with_one_route_connection(connect_timeout),
+        )
+    }
+
+    /// Like [`Self::new`], but with all the connection timeouts taken from `timeouts`.
+    pub fn with_timeouts(endpoint: &EnclaveEndpoint<'static, E>, timeouts: &TimeoutConfig) -> Self {
         Self {
             endpoint_connection: EndpointConnection {
                 manager: SingleRouteThrottlingConnectionManager::new(
-                    endpoint.domain_config.connection_params(),
-                    connect_timeout,
+                    endpoint
+                        .domain_config
+                        .connection_params()
+                        .with_timeouts(*timeouts),
+                    timeouts.one_route_connection,
                 ),
                 config: make_ws_config(
                     E::url_path(endpoint.params.mr_enclave.as_ref()),
-                    connect_timeout,
+                    timeouts.one_route_connection,
                 ),
+                timeouts: *timeouts,
             },
             params: endpoint.params.clone(),
         }
@@ -349,20 +380,36 @@ impl<E: EnclaveKind> EnclaveEndpointConnection<E, MultiRouteConnectionManager> {
     pub fn new_multi(
         endpoint: &EnclaveEndpoint<'static, E>,
         connection_params: impl IntoIterator<Item = ConnectionParams>,
-        one_route_connect_timeout: Duration,
+        timeouts: &TimeoutConfig,
     ) -> Self {
         Self {
             endpoint_connection: EndpointConnection::new_multi(
                 connection_params,
-                one_route_connect_timeout,
+                timeouts,
                 make_ws_config(
                     E::url_path(endpoint.params.mr_enclave.as_ref()),
-                    one_route_connect_timeout,
+                    timeouts.one_route_connection,
                 ),
             ),
             params: endpoint.params.clone(),
         }
     }
+
+    pub fn with_backoff_policy(self, backoff_policy: Arc<dyn BackoffPolicy>) -> Self {
+        Self {
+            endpoint_connection: self.endpoint_connection.with_backoff_policy(backoff_policy),
+            ..self
+        }
+    }
+
+    pub fn with_network_change_listener(self, listener: NetworkChangeListener) -> Self {
+        Self {
+            endpoint_connection: self
+                .endpoint_connection
+                .with_network_change_listener(listener),
+            ..self
+        }
+    }
 }
 
 impl NewHandshake for Sgx {
@@ -429,6 +476,23 @@ impl NewHandshake for Tpm2Snp {
     }
 }
 
+impl NewHandshake for Tdx {
+    fn new_handshake(
+        params: &EndpointParams<Self>,
+        attestation_message: &[u8],
+    ) -> enclave::Result<enclave::Handshake> {
+        tdx::new_handshake(
+            params.mr_enclave.as_ref(),
+            attestation_message,
+            SystemTime::now(),
+            params
+                .raft_config
+                .as_raft_config()
+                .expect("Raft config must be present for Tdx"),
+        )
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::fmt::Debug;
@@ -472,6 +536,7 @@ mod test {
             endpoint_connection: EndpointConnection {
                 manager,
                 config: make_ws_config(PathAndQuery::from_static("/endpoint"), CONNECT_TIMEOUT),
+                timeouts: TimeoutConfig::default().with_one_route_connection(CONNECT_TIMEOUT),
             },
             params: EndpointParams::<Cdsi> {
                 mr_enclave,
@@ -395,4 +395,5 @@ pub const PROD: Env<'static, Svr3Env> = Env {
 
 pub mod constants {
     pub const WEB_SOCKET_PATH: &str = "/v1/websocket/";
+    pub const PROVISIONING_WEB_SOCKET_PATH: &str = "/v1/websocket/provisioning/";
 }
@@ -9,30 +9,37 @@ use std::string::ToString;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::timeouts::{WS_KEEP_ALIVE_INTERVAL, WS_MAX_IDLE_INTERVAL};
+use crate::timeouts::{TimeoutConfig, WS_KEEP_ALIVE_INTERVAL, WS_MAX_IDLE_INTERVAL};
 use ::http::uri::PathAndQuery;
 use ::http::Uri;
 use async_trait::async_trait;
 use tokio::io::{AsyncRead, AsyncWrite};
 use url::Host;
 
+use crate::infra::backoff::BackoffPolicy;
 use crate::infra::certs::RootCertificates;
 use crate::infra::connection_manager::{
-    MultiRouteConnectionManager, SingleRouteThrottlingConnectionManager,
+    MultiRouteConnectionManager, NetworkChangeListener, SingleRouteThrottlingConnectionManager,
 };
 use crate::infra::errors::TransportConnectError;
+use crate::infra::events::{ConnectionEvent, ConnectionEventObserver};
+use crate::infra::tcp_ssl::session_cache::TlsSessionCache;
 use crate::infra::ws::WebSocketConfig;
 
+pub mod backoff;
 pub mod certs;
 pub mod connection_manager;
 pub mod dns;
 pub mod errors;
+pub mod events;
 mod http_client;
+pub mod preconnect;
 pub(crate) mod reconnect;
 pub mod tcp_ssl;
 pub mod ws;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum IpType {
     Unknown = 0,
@@ -82,7 +89,10 @@ impl HttpRequestDecoratorSeq {
 /// - `port` to connect to,
 /// - `http_request_decorator`, a [HttpRequestDecorator] to apply to all HTTP requests,
 /// - `certs`, [RootCertificates] representing trusted certificates,
-/// - `dns_resolver`, a [DnsResolver] to use when resolving DNS.
+/// - `dns_resolver`, a [DnsResolver] to use when resolving DNS,
+/// - `event_observer`, an optional [ConnectionEventObserver] to report progress to,
+/// - `timeouts`, a [TimeoutConfig] bounding the TCP, TLS and WebSocket upgrade steps,
+/// - `tls_session_cache`, an optional [TlsSessionCache] used to resume earlier TLS sessions.
 /// This is also applicable to WebSocket connections (in this case, `http_request_decorator` will
 /// only be applied to the initial connection upgrade request).
 #[derive(Clone, Debug)]
@@ -93,6 +103,9 @@ pub struct ConnectionParams {
     pub port: NonZeroU16,
     pub http_request_decorator: HttpRequestDecoratorSeq,
     pub certs: RootCertificates,
+    pub event_observer: Option<Arc<dyn ConnectionEventObserver>>,
+    pub timeouts: TimeoutConfig,
+    pub tls_session_cache: Option<TlsSessionCache>,
 }
 
 impl ConnectionParams {
@@ -111,6 +124,9 @@ impl ConnectionParams {
             port,
             http_request_decorator,
             certs,
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
         }
     }
 
@@ -124,6 +140,30 @@ impl ConnectionParams {
         self.certs = certs;
         self
     }
+
+    pub fn with_event_observer(mut self, observer: Arc<dyn ConnectionEventObserver>) -> Self {
+        self.event_observer = Some(observer);
+        self
+    }
+
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn with_tls_session_cache(mut self, cache: TlsSessionCache) -> Self {
+        self.tls_session_cache = Some(cache);
+        self
+    }
+
+    /// Passes an event to the [ConnectionEventObserver], if there is one.
+    ///
+    /// `make_event` is only invoked if the event will actually be reported.
+    pub(crate) fn report_event(&self, make_event: impl FnOnce() -> ConnectionEvent) {
+        if let Some(observer) = &self.event_observer {
+            observer.on_event(make_event())
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -143,8 +183,9 @@ pub struct ConnectionInfo {
 }
 
 /// Source for the result of a hostname lookup.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, strum::Display)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, strum::Display, serde::Serialize)]
 #[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum DnsSource {
     /// The result was returned from the cache
     Cache,
@@ -162,8 +203,9 @@ pub enum DnsSource {
 }
 
 /// Type of the route used for the connection.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, strum::Display)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, strum::Display, serde::Serialize)]
 #[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum RouteType {
     /// Direct connection to the service.
     Direct,
@@ -173,6 +215,10 @@ pub enum RouteType {
     ProxyG,
     /// Connection over a custom TLS proxy
     TlsProxy,
+    /// Connection over a custom SOCKS5 proxy
+    SocksProxy,
+    /// Connection over a custom HTTP CONNECT proxy
+    HttpProxy,
     /// Test-only value
     #[cfg(test)]
     Test,
@@ -221,6 +267,28 @@ impl HttpRequestDecorator {
     }
 }
 
+/// The parts of [`ConnectionParams`] that identify a route, for caching per-route state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct RouteKey {
+    route_type: RouteType,
+    sni: Arc<str>,
+    host: Arc<str>,
+    port: NonZeroU16,
+    alpn: Alpn,
+}
+
+impl RouteKey {
+    pub(crate) fn new(connection_params: &ConnectionParams, alpn: Alpn) -> Self {
+        Self {
+            route_type: connection_params.route_type,
+            sni: connection_params.sni.clone(),
+            host: connection_params.host.clone(),
+            port: connection_params.port,
+            alpn,
+        }
+    }
+}
+
 pub struct StreamAndInfo<T>(T, ConnectionInfo);
 
 impl<T> StreamAndInfo<T> {
@@ -265,12 +333,17 @@ impl AsRef<[u8]> for Alpn {
 pub struct EndpointConnection<C> {
     pub manager: C,
     pub config: WebSocketConfig,
+    pub timeouts: TimeoutConfig,
 }
 
 impl EndpointConnection<MultiRouteConnectionManager> {
+    /// Creates a connection that tries each of `connection_params` in turn.
+    ///
+    /// Each route is given `timeouts.one_route_connection` to connect. The timeouts for the
+    /// individual steps of a route come from its own [`ConnectionParams::timeouts`].
     pub fn new_multi(
         connection_params: impl IntoIterator<Item = ConnectionParams>,
-        one_route_connect_timeout: Duration,
+        timeouts: &TimeoutConfig,
         config: WebSocketConfig,
     ) -> Self {
         Self {
@@ -280,12 +353,27 @@ impl EndpointConnection<MultiRouteConnectionManager> {
                     .map(|params| {
                         SingleRouteThrottlingConnectionManager::new(
                             params,
-                            one_route_connect_timeout,
+                            timeouts.one_route_connection,
                         )
                     })
                     .collect(),
             ),
             config,
+            timeouts: *timeouts,
+        }
+    }
+
+    pub fn with_backoff_policy(self, backoff_policy: Arc<dyn BackoffPolicy>) -> Self {
+        Self {
+            manager: self.manager.with_backoff_policy(backoff_policy),
+            ..self
+        }
+    }
+
+    pub fn with_network_change_listener(self, listener: NetworkChangeListener) -> Self {
+        Self {
+            manager: self.manager.with_network_change_listener(listener),
+            ..self
         }
     }
 }
@@ -0,0 +1,237 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt::Debug;
+use std::num::NonZeroU16;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::timeouts::{CONNECTION_ROUTE_COOLDOWN_INTERVALS, CONNECTION_ROUTE_MAX_COOLDOWN};
+
+/// Decides how long a connection route should cool down after failed attempts.
+///
+/// Used by [`SingleRouteThrottlingConnectionManager`] to schedule the next attempt on a route
+/// that has been consistently failing.
+///
+/// [`SingleRouteThrottlingConnectionManager`]: crate::infra::connection_manager::SingleRouteThrottlingConnectionManager
+pub trait BackoffPolicy: Debug + Send + Sync {
+    /// Returns how long to wait after a route has failed `consecutive_failures` times in a row.
+    ///
+    /// Returning `None` means the route has used up its attempts. It then gets a long fixed
+    /// cooldown, after which its failure count starts over.
+    fn cooldown_after(&self, consecutive_failures: NonZeroU16) -> Option<Duration>;
+}
+
+/// The built-in policy, which follows [`CONNECTION_ROUTE_COOLDOWN_INTERVALS`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultBackoffPolicy;
+
+impl BackoffPolicy for DefaultBackoffPolicy {
+    fn cooldown_after(&self, consecutive_failures: NonZeroU16) -> Option<Duration> {
+        let idx = usize::from(consecutive_failures.get() - 1);
+        Some(
+            *CONNECTION_ROUTE_COOLDOWN_INTERVALS
+                .get(idx)
+                .unwrap_or(&CONNECTION_ROUTE_MAX_COOLDOWN),
+        )
+    }
+}
+
+/// Exponential backoff with optional jitter and a limit on the number of attempts.
+///
+/// After the `n`th consecutive failure, the cooldown is
+/// `min(initial_delay * multiplier^(n - 1), max_delay)`, reduced by a random fraction of up to
+/// `jitter` of itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExponentialBackoff {
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter: f64,
+    max_attempts: Option<NonZeroU16>,
+}
+
+impl ExponentialBackoff {
+    /// Creates a policy that doubles the delay after each failure, starting at `initial_delay`
+    /// and capped at [`CONNECTION_ROUTE_MAX_COOLDOWN`], without jitter or an attempt limit.
+    pub fn new(initial_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            multiplier: 2.0,
+            max_delay: CONNECTION_ROUTE_MAX_COOLDOWN,
+            jitter: 0.0,
+            max_attempts: None,
+        }
+    }
+
+    /// Sets the factor the delay grows by after each failure.
+    ///
+    /// Values below 1 are treated as 1, i.e. a constant delay.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        Self {
+            multiplier: multiplier.max(1.0),
+            ..self
+        }
+    }
+
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Sets the largest fraction of each delay that can randomly be taken off it.
+    ///
+    /// The value is clamped to `[0, 1]`.
+    pub fn jitter(self, jitter: f64) -> Self {
+        Self {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Sets the number of consecutive failed attempts after which a route is given up on.
+    pub fn max_attempts(self, max_attempts: NonZeroU16) -> Self {
+        Self {
+            max_attempts: Some(max_attempts),
+            ..self
+        }
+    }
+
+    fn delay_without_jitter(&self, consecutive_failures: NonZeroU16) -> Duration {
+        let exponent = i32::from(consecutive_failures.get() - 1);
+        let delay_secs = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        // Converting an out-of-range value would panic, so cap it as a float first.
+        Duration::from_secs_f64(delay_secs.min(self.max_delay.as_secs_f64()))
+    }
+}
+
+impl BackoffPolicy for ExponentialBackoff {
+    fn cooldown_after(&self, consecutive_failures: NonZeroU16) -> Option<Duration> {
+        if self
+            .max_attempts
+            .is_some_and(|max_attempts| consecutive_failures >= max_attempts)
+        {
+            return None;
+        }
+        let delay = self.delay_without_jitter(consecutive_failures);
+        if self.jitter == 0.0 {
+            return Some(delay);
+        }
+        let reduction = rand::thread_rng().gen_range(0.0..=self.jitter);
+        Some(delay.mul_f64(1.0 - reduction))
+    }
+}
+
+/// A [`BackoffPolicy`] that can be replaced while connection managers are using it.
+///
+/// This lets apps use a more conservative policy while they're in the background, for example,
+/// without having to rebuild their connections.
+#[derive(Clone, Debug)]
+pub struct SwitchableBackoffPolicy(Arc<RwLock<Arc<dyn BackoffPolicy>>>);
+
+impl SwitchableBackoffPolicy {
+    pub fn new(initial: Arc<dyn BackoffPolicy>) -> Self {
+        Self(Arc::new(RwLock::new(initial)))
+    }
+
+    /// Replaces the current policy; this affects all clones of `self`.
+    pub fn set(&self, policy: Arc<dyn BackoffPolicy>) {
+        *self.0.write().expect("not poisoned") = policy;
+    }
+}
+
+impl Default for SwitchableBackoffPolicy {
+    fn default() -> Self {
+        Self::new(Arc::new(DefaultBackoffPolicy))
+    }
+}
+
+impl BackoffPolicy for SwitchableBackoffPolicy {
+    fn cooldown_after(&self, consecutive_failures: NonZeroU16) -> Option<Duration> {
+        let policy = self.0.read().expect("not poisoned").clone();
+        policy.cooldown_after(consecutive_failures)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nonzero_ext::nonzero;
+
+    use super::*;
+
+    #[test]
+    fn default_policy_follows_cooldown_intervals() {
+        let policy = DefaultBackoffPolicy;
+        for (i, expected) in CONNECTION_ROUTE_COOLDOWN_INTERVALS.iter().enumerate() {
+            let failures = NonZeroU16::new(u16::try_from(i + 1).unwrap()).unwrap();
+            assert_eq!(policy.cooldown_after(failures), Some(*expected));
+        }
+        assert_eq!(
+            policy.cooldown_after(NonZeroU16::MAX),
+            Some(CONNECTION_ROUTE_MAX_COOLDOWN)
+        );
+    }
+
+    #[test]
+    fn exponential_backoff_grows_until_max_delay() {
+        let policy = ExponentialBackoff::new(Duration::from_secs(1))
+            .multiplier(3.0)
+            .max_delay(Duration::from_secs(20));
+        let cooldowns = (1..=5)
+            .map(|n| policy.cooldown_after(NonZeroU16::new(n).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cooldowns,
+            [1, 3, 9, 20, 20].map(|secs| Some(Duration::from_secs(secs)))
+        );
+        assert_eq!(
+            policy.cooldown_after(NonZeroU16::MAX),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn exponential_backoff_gives_up_after_max_attempts() {
+        let policy = ExponentialBackoff::new(Duration::from_secs(1)).max_attempts(nonzero!(3u16));
+        assert_eq!(
+            policy.cooldown_after(nonzero!(2u16)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.cooldown_after(nonzero!(3u16)), None);
+        assert_eq!(policy.cooldown_after(nonzero!(4u16)), None);
+    }
+
+    #[test]
+    fn exponential_backoff_jitter_stays_in_range() {
+        let policy = ExponentialBackoff::new(Duration::from_secs(10)).jitter(0.5);
+        for _ in 0..100 {
+            let cooldown = policy.cooldown_after(nonzero!(1u16)).unwrap();
+            assert!(
+                (Duration::from_secs(5)..=Duration::from_secs(10)).contains(&cooldown),
+                "{cooldown:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn switchable_policy_applies_to_all_clones() {
+        let policy = SwitchableBackoffPolicy::default();
+        let clone = policy.clone();
+        assert_eq!(
+            clone.cooldown_after(nonzero!(2u16)),
+            DefaultBackoffPolicy.cooldown_after(nonzero!(2u16))
+        );
+
+        policy.set(Arc::new(
+            ExponentialBackoff::new(Duration::from_secs(7)).max_attempts(nonzero!(2u16)),
+        ));
+        assert_eq!(
+            clone.cooldown_after(nonzero!(1u16)),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(clone.cooldown_after(nonzero!(2u16)), None);
+    }
+}
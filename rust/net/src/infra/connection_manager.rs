@@ -3,23 +3,31 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::cmp::{max, min};
+use std::cmp::max;
 use std::fmt::Debug;
 use std::future::Future;
+use std::num::NonZeroU16;
 use std::ops::Add;
 use std::panic::RefUnwindSafe;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::timeouts::{CONNECTION_ROUTE_COOLDOWN_INTERVALS, CONNECTION_ROUTE_MAX_COOLDOWN};
 use async_trait::async_trait;
 use itertools::Itertools;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 use tokio::time::{timeout_at, Instant};
 
+use crate::infra::backoff::{BackoffPolicy, DefaultBackoffPolicy};
 use crate::infra::errors::LogSafeDisplay;
+use crate::infra::events::{ConnectionEvent, RouteAttemptOutcome};
 use crate::infra::ConnectionParams;
 
+/// How long a route stays in cooldown once its [`BackoffPolicy`] has given up on it.
+///
+/// Once this has passed, the route's failure count starts over, so a route that was given up on
+/// during an outage is eventually tried again.
+const GAVE_UP_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
 /// Represents the outcome of the connection attempt
 #[derive(Debug)]
 pub enum ConnectionAttemptOutcome<T, E> {
@@ -50,6 +58,11 @@ pub trait ConnectionManager: Clone + Send + Sync {
         Fut: Future<Output = Result<T, E>> + Send;
 
     fn describe_for_logging(&self) -> String;
+
+    /// Returns the listener used to abandon connection attempts when the network changes, if any.
+    fn network_change_listener(&self) -> Option<&NetworkChangeListener> {
+        None
+    }
 }
 
 #[async_trait]
@@ -73,6 +86,55 @@ where
     fn describe_for_logging(&self) -> String {
         (*self).describe_for_logging()
     }
+
+    fn network_change_listener(&self) -> Option<&NetworkChangeListener> {
+        (*self).network_change_listener()
+    }
+}
+
+/// Tells connection managers that the device's network has changed.
+///
+/// When notified, managers forget which routes have been failing, abandon connection attempts in
+/// progress, and (for long-lived services) drop their current connections so that they get
+/// re-established over the new network.
+#[derive(Clone, Debug)]
+pub struct NetworkChangeNotifier(Arc<watch::Sender<u64>>);
+
+/// The receiving side of a [NetworkChangeNotifier].
+#[derive(Clone, Debug)]
+pub struct NetworkChangeListener(watch::Receiver<u64>);
+
+impl Default for NetworkChangeNotifier {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(0).0))
+    }
+}
+
+impl NetworkChangeNotifier {
+    pub fn listener(&self) -> NetworkChangeListener {
+        NetworkChangeListener(self.0.subscribe())
+    }
+
+    pub fn network_changed(&self) {
+        self.0
+            .send_modify(|generation| *generation = generation.wrapping_add(1));
+    }
+}
+
+impl NetworkChangeListener {
+    /// Counts how many times the network has changed.
+    pub(crate) fn generation(&self) -> u64 {
+        *self.0.borrow()
+    }
+
+    /// Resolves once the network has changed since `generation` was observed.
+    pub(crate) async fn changed_since(&self, generation: u64) {
+        let mut receiver = self.0.clone();
+        if receiver.wait_for(|g| *g != generation).await.is_err() {
+            // The notifier is gone, so there won't be any more changes.
+            std::future::pending().await
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -80,9 +142,20 @@ struct ThrottlingConnectionManagerState {
     consecutive_fails: u16,
     next_attempt: Instant,
     latest_attempt: Instant,
+    /// The [NetworkChangeListener::generation] this state applies to.
+    network_generation: u64,
 }
 
 impl ThrottlingConnectionManagerState {
+    fn new(network_generation: u64) -> Self {
+        Self {
+            consecutive_fails: 0,
+            next_attempt: Instant::now(),
+            latest_attempt: Instant::now() - Duration::from_nanos(1),
+            network_generation,
+        }
+    }
+
     /// Produces a new state after a success or failure.
     ///
     /// The logic here is to track an attempt start time and to take it into
@@ -91,7 +164,12 @@ impl ThrottlingConnectionManagerState {
     /// discarded. If, however, outcomes of failed attempts are arriving out of
     /// order in which attempts started, those failures will still be reflected
     /// in `consecutive_fails`.
-    fn after_attempt(self, was_successful: bool, attempt_start_time: Instant) -> Self {
+    fn after_attempt(
+        self,
+        was_successful: bool,
+        attempt_start_time: Instant,
+        backoff_policy: &dyn BackoffPolicy,
+    ) -> Self {
         let mut s = self;
         if was_successful {
             // comparing using `>=` to guarantee that successful attempt takes precedence
@@ -102,17 +180,20 @@ impl ThrottlingConnectionManagerState {
             }
         } else if attempt_start_time > s.latest_attempt || s.consecutive_fails > 0 {
             s.latest_attempt = max(attempt_start_time, s.latest_attempt);
-            let idx: usize = s.consecutive_fails.into();
-            let cooldown_interval = CONNECTION_ROUTE_COOLDOWN_INTERVALS
-                .get(idx)
-                .unwrap_or(&CONNECTION_ROUTE_MAX_COOLDOWN);
-            s.next_attempt = Instant::now() + *cooldown_interval;
-            s.consecutive_fails = min(
-                s.consecutive_fails.saturating_add(1),
-                (CONNECTION_ROUTE_COOLDOWN_INTERVALS.len() - 1)
-                    .try_into()
-                    .unwrap(),
-            );
+            let consecutive_fails = NonZeroU16::MIN.saturating_add(s.consecutive_fails);
+            match backoff_policy.cooldown_after(consecutive_fails) {
+                Some(cooldown_interval) => {
+                    s.next_attempt = Instant::now() + cooldown_interval;
+                    s.consecutive_fails = consecutive_fails.get();
+                }
+                None => {
+                    log::info!("giving up on route after {consecutive_fails} failed attempts");
+                    // Start counting from scratch once the route has cooled down, instead of
+                    // asking the policy about ever larger failure counts.
+                    s.next_attempt = Instant::now() + GAVE_UP_COOLDOWN;
+                    s.consecutive_fails = 0;
+                }
+            }
         }
         s
     }
@@ -120,12 +201,16 @@ impl ThrottlingConnectionManagerState {
 
 /// A connection manager that only attempts one route (i.e. one [ConnectionParams])
 /// but keeps track of consecutive failed attempts and after each failure waits for a duration
-/// chosen according to [CONNECTION_ROUTE_COOLDOWN_INTERVALS] list.
+/// chosen by its [BackoffPolicy], by default the [CONNECTION_ROUTE_COOLDOWN_INTERVALS] list.
+///
+/// [CONNECTION_ROUTE_COOLDOWN_INTERVALS]: crate::timeouts::CONNECTION_ROUTE_COOLDOWN_INTERVALS
 #[derive(Clone)]
 pub struct SingleRouteThrottlingConnectionManager<C = ConnectionParams> {
     state: Arc<Mutex<ThrottlingConnectionManagerState>>,
     connection_params: C,
     connection_timeout: Duration,
+    backoff_policy: Arc<dyn BackoffPolicy>,
+    network_changes: Option<NetworkChangeListener>,
 }
 
 /// A connection manager that holds a list of [SingleRouteThrottlingConnectionManager] instances
@@ -143,6 +228,30 @@ impl<M> MultiRouteConnectionManager<M> {
     }
 }
 
+impl<C> MultiRouteConnectionManager<SingleRouteThrottlingConnectionManager<C>> {
+    /// Replaces the [BackoffPolicy] of each of the routes.
+    pub fn with_backoff_policy(self, backoff_policy: Arc<dyn BackoffPolicy>) -> Self {
+        Self {
+            route_managers: self
+                .route_managers
+                .into_iter()
+                .map(|manager| manager.with_backoff_policy(backoff_policy.clone()))
+                .collect(),
+        }
+    }
+
+    /// Makes each of the routes respond to network changes reported to `listener`.
+    pub fn with_network_change_listener(self, listener: NetworkChangeListener) -> Self {
+        Self {
+            route_managers: self
+                .route_managers
+                .into_iter()
+                .map(|manager| manager.with_network_change_listener(listener.clone()))
+                .collect(),
+        }
+    }
+}
+
 #[async_trait]
 impl<M> ConnectionManager for MultiRouteConnectionManager<M>
 where
@@ -193,6 +302,12 @@ where
                 .join(", ")
         )
     }
+
+    fn network_change_listener(&self) -> Option<&NetworkChangeListener> {
+        self.route_managers
+            .iter()
+            .find_map(ConnectionManager::network_change_listener)
+    }
 }
 
 pub enum RetryError<E> {
@@ -275,11 +390,28 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
         Self {
             connection_params,
             connection_timeout,
-            state: Arc::new(Mutex::new(ThrottlingConnectionManagerState {
-                consecutive_fails: 0,
-                next_attempt: Instant::now(),
-                latest_attempt: Instant::now() - Duration::from_nanos(1),
-            })),
+            state: Arc::new(Mutex::new(ThrottlingConnectionManagerState::new(0))),
+            backoff_policy: Arc::new(DefaultBackoffPolicy),
+            network_changes: None,
+        }
+    }
+
+    pub fn with_backoff_policy(self, backoff_policy: Arc<dyn BackoffPolicy>) -> Self {
+        Self {
+            backoff_policy,
+            ..self
+        }
+    }
+
+    /// Resets the route's cooldown whenever the network changes, and abandons any attempt that's
+    /// in progress at the time.
+    ///
+    /// An abandoned attempt is reported as [ConnectionAttemptOutcome::TimedOut] and doesn't count
+    /// as a failure.
+    pub fn with_network_change_listener(self, listener: NetworkChangeListener) -> Self {
+        Self {
+            network_changes: Some(listener),
+            ..self
         }
     }
 
@@ -293,16 +425,55 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
         Fun: Fn(&'a C) -> Fut + Send + Sync,
         Fut: Future<Output = Result<T, E>> + Send,
     {
-        let state = self.state.lock().await.clone();
+        self.connect_or_wait_counting_failures(connection_fn)
+            .await
+            .0
+    }
+
+    /// Like [`Self::connect_or_wait`], but also returns the route's failure count after an
+    /// attempt, read while the state is still locked.
+    ///
+    /// The count is 0 if no attempt was made.
+    async fn connect_or_wait_counting_failures<'a, T, E, Fun, Fut>(
+        &'a self,
+        connection_fn: Fun,
+    ) -> (ConnectionAttemptOutcome<T, E>, u16)
+    where
+        T: Send,
+        E: Send,
+        Fun: Fn(&'a C) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, E>> + Send,
+    {
+        let network_generation = self
+            .network_changes
+            .as_ref()
+            .map_or(0, NetworkChangeListener::generation);
+        let state = {
+            let mut guard = self.state.lock().await;
+            if guard.network_generation != network_generation {
+                log::debug!("network changed; resetting route state");
+                *guard = ThrottlingConnectionManagerState::new(network_generation);
+            }
+            guard.clone()
+        };
         let attempt_start_time = Instant::now();
         if attempt_start_time < state.next_attempt {
-            return ConnectionAttemptOutcome::WaitUntil(state.next_attempt);
+            return (ConnectionAttemptOutcome::WaitUntil(state.next_attempt), 0);
         }
-        let connection_result_or_timeout = timeout_at(
+        let attempt = timeout_at(
             attempt_start_time.add(self.connection_timeout),
             connection_fn(&self.connection_params),
-        )
-        .await;
+        );
+        let connection_result_or_timeout = match &self.network_changes {
+            None => attempt.await,
+            Some(network_changes) => tokio::select! {
+                result = attempt => result,
+                () = network_changes.changed_since(network_generation) => {
+                    log::info!("network changed; abandoning connection attempt");
+                    return (ConnectionAttemptOutcome::TimedOut, 0);
+                }
+            },
+        };
 
         let mut s = self.state.lock().await;
 
@@ -311,12 +482,19 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
         let was_successful = connection_result_or_timeout
             .as_ref()
             .map_or(false, |r| r.is_ok());
-        let new_state = s.clone().after_attempt(was_successful, attempt_start_time);
+        let new_state = s.clone().after_attempt(
+            was_successful,
+            attempt_start_time,
+            self.backoff_policy.as_ref(),
+        );
+        let consecutive_fails = new_state.consecutive_fails;
         *s = new_state;
 
-        connection_result_or_timeout.map_or(ConnectionAttemptOutcome::TimedOut, |result| {
-            ConnectionAttemptOutcome::Attempted(result)
-        })
+        let outcome = connection_result_or_timeout
+            .map_or(ConnectionAttemptOutcome::TimedOut, |result| {
+                ConnectionAttemptOutcome::Attempted(result)
+            });
+        (outcome, consecutive_fails)
     }
 }
 
@@ -338,12 +516,44 @@ impl ConnectionManager for SingleRouteThrottlingConnectionManager {
         Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
         Fut: Future<Output = Result<T, E>> + Send,
     {
-        self.connect_or_wait(connection_fn).await
+        let params = &self.connection_params;
+        if params.event_observer.is_none() {
+            return self.connect_or_wait(connection_fn).await;
+        }
+
+        let start = Instant::now();
+        let (outcome, consecutive_failures) =
+            self.connect_or_wait_counting_failures(connection_fn).await;
+        let outcome_for_event = match &outcome {
+            ConnectionAttemptOutcome::Attempted(Ok(_)) => RouteAttemptOutcome::Succeeded,
+            ConnectionAttemptOutcome::Attempted(Err(_)) => RouteAttemptOutcome::Failed,
+            ConnectionAttemptOutcome::TimedOut => RouteAttemptOutcome::TimedOut,
+            ConnectionAttemptOutcome::WaitUntil(next_attempt) => {
+                params.report_event(|| ConnectionEvent::RouteCoolingDown {
+                    route: params.route_type,
+                    host: params.host.clone(),
+                    remaining: next_attempt.saturating_duration_since(start),
+                });
+                return outcome;
+            }
+        };
+        params.report_event(|| ConnectionEvent::RouteAttempt {
+            route: params.route_type,
+            host: params.host.clone(),
+            duration: start.elapsed(),
+            outcome: outcome_for_event,
+            consecutive_failures,
+        });
+        outcome
     }
 
     fn describe_for_logging(&self) -> String {
         self.connection_params.route_type.to_string()
     }
+
+    fn network_change_listener(&self) -> Option<&NetworkChangeListener> {
+        self.network_changes.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -357,12 +567,15 @@ mod test {
     use nonzero_ext::nonzero;
     use tokio::time;
 
+    use crate::infra::backoff::ExponentialBackoff;
     use crate::infra::certs::RootCertificates;
+    use crate::infra::events::ConnectionEventLog;
     use crate::infra::test::shared::{
         TestError, FEW_ATTEMPTS, LONG_CONNECTION_TIME, MANY_ATTEMPTS, TIMEOUT_DURATION,
         TIME_ADVANCE_VALUE,
     };
     use crate::infra::{HttpRequestDecoratorSeq, RouteType};
+    use crate::timeouts::CONNECTION_ROUTE_MAX_COOLDOWN;
 
     use super::*;
 
@@ -466,6 +679,210 @@ mod test {
         assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_uses_backoff_policy() {
+        const COOLDOWN: Duration = Duration::from_secs(10);
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+        )
+        .with_backoff_policy(Arc::new(ExponentialBackoff::new(COOLDOWN).multiplier(1.0)));
+
+        let failed_at = Instant::now();
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+            .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+            .await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+        );
+
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::WaitUntil(t) if t == failed_at + COOLDOWN
+        );
+
+        time::advance(COOLDOWN).await;
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_gives_up_when_backoff_policy_does() {
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+        )
+        .with_backoff_policy(Arc::new(
+            ExponentialBackoff::new(Duration::from_secs(1)).max_attempts(nonzero!(2u16)),
+        ));
+
+        for _ in 0..2 {
+            let attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+                .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+                .await;
+            assert_matches!(
+                attempt_outcome,
+                ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+            );
+            time::advance(Duration::from_secs(1)).await;
+        }
+
+        time::advance(CONNECTION_ROUTE_MAX_COOLDOWN * 10).await;
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::WaitUntil(_));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_retries_after_giving_up() {
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+        )
+        .with_backoff_policy(Arc::new(
+            ExponentialBackoff::new(Duration::from_secs(1)).max_attempts(nonzero!(1u16)),
+        ));
+
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+            .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+            .await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+        );
+        assert_matches!(
+            manager
+                .connect_or_wait(|_| future::ready(Ok::<(), TestError>(())))
+                .await,
+            ConnectionAttemptOutcome::WaitUntil(_)
+        );
+
+        time::advance(GAVE_UP_COOLDOWN).await;
+        // The failure count has started over, so the policy allows another attempt.
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+            .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+            .await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+        );
+        assert_eq!(manager.state.lock().await.consecutive_fails, 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_forgets_cooldown_when_network_changes() {
+        let notifier = NetworkChangeNotifier::default();
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+        )
+        .with_backoff_policy(Arc::new(
+            ExponentialBackoff::new(Duration::from_secs(1)).max_attempts(nonzero!(1u16)),
+        ))
+        .with_network_change_listener(notifier.listener());
+
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+            .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+            .await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+        );
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::WaitUntil(_));
+
+        notifier.network_changed();
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_abandons_attempt_when_network_changes() {
+        let notifier = NetworkChangeNotifier::default();
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+        )
+        .with_network_change_listener(notifier.listener());
+
+        let start = Instant::now();
+        let (attempt_outcome, ()) = tokio::join!(
+            manager.connect_or_wait(|_| future::pending::<Result<(), TestError>>()),
+            async {
+                time::sleep(TIME_ADVANCE_VALUE).await;
+                notifier.network_changed();
+            }
+        );
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::TimedOut);
+        assert_eq!(start.elapsed(), TIME_ADVANCE_VALUE);
+
+        // The abandoned attempt doesn't count as a failure.
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            manager.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn single_route_manager_reports_attempts_and_cooldowns() {
+        const COOLDOWN: Duration = Duration::from_secs(10);
+        let event_log = Arc::new(ConnectionEventLog::new(nonzero!(10usize)));
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1).with_event_observer(event_log.clone()),
+            TIMEOUT_DURATION,
+        )
+        .with_backoff_policy(Arc::new(ExponentialBackoff::new(COOLDOWN)));
+
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            ConnectionManager::connect_or_wait(&manager, |_| async {
+                time::sleep(TIME_ADVANCE_VALUE).await;
+                Err(TestError::Expected)
+            })
+            .await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::Attempted(Err(TestError::Expected))
+        );
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            ConnectionManager::connect_or_wait(&manager, |_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::WaitUntil(_));
+
+        time::advance(COOLDOWN).await;
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            ConnectionManager::connect_or_wait(&manager, |_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+
+        assert_eq!(
+            event_log.take_events(),
+            [
+                ConnectionEvent::RouteAttempt {
+                    route: RouteType::Test,
+                    host: ROUTE_1.into(),
+                    duration: TIME_ADVANCE_VALUE,
+                    outcome: RouteAttemptOutcome::Failed,
+                    consecutive_failures: 1,
+                },
+                ConnectionEvent::RouteCoolingDown {
+                    route: RouteType::Test,
+                    host: ROUTE_1.into(),
+                    remaining: COOLDOWN,
+                },
+                ConnectionEvent::RouteAttempt {
+                    route: RouteType::Test,
+                    host: ROUTE_1.into(),
+                    duration: Duration::ZERO,
+                    outcome: RouteAttemptOutcome::Succeeded,
+                    consecutive_failures: 0,
+                },
+            ]
+        );
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn multi_route_manager_picks_working_route() {
         let manager_1 = SingleRouteThrottlingConnectionManager::new(
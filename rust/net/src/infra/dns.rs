@@ -10,20 +10,17 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use crate::timeouts::{DNS_FALLBACK_LOOKUP_TIMEOUTS, DNS_SYSTEM_LOOKUP_TIMEOUT};
-use nonzero_ext::nonzero;
 use oneshot_broadcast::Sender;
 use tokio::time::Instant;
 
-use crate::infra::certs::RootCertificates;
 use crate::infra::dns::custom_resolver::CustomDnsResolver;
 use crate::infra::dns::dns_errors::Error;
 use crate::infra::dns::dns_lookup::{DnsLookup, DnsLookupRequest, StaticDnsMap, SystemDnsLookup};
-use crate::infra::dns::dns_transport_doh::{DohTransport, CLOUDFLARE_NS};
+use crate::infra::dns::dns_transport_doh::{DohConfig, DohTransport};
 use crate::infra::dns::dns_types::ResourceType;
 use crate::infra::dns::dns_utils::oneshot_broadcast::Receiver;
 use crate::infra::dns::dns_utils::{log_safe_domain, oneshot_broadcast};
 use crate::infra::dns::lookup_result::LookupResult;
-use crate::infra::{ConnectionParams, HttpRequestDecoratorSeq, RouteType};
 use crate::utils;
 
 pub mod custom_resolver;
@@ -66,7 +63,6 @@ impl Default for DnsResolver {
 }
 
 impl DnsResolver {
-    #[cfg(test)]
     pub(crate) fn new_custom(lookup_options: Vec<(Box<dyn DnsLookup>, Duration)>) -> Self {
         DnsResolver {
             lookup_options: Arc::new(lookup_options),
@@ -74,30 +70,21 @@ impl DnsResolver {
         }
     }
 
-    /// Creates a DNS resolver that will only use a provided static map
-    /// to resolve DNS lookups
-    pub(crate) fn new_from_static_map(static_map: HashMap<&'static str, LookupResult>) -> Self {
-        DnsResolver {
-            lookup_options: Arc::new(vec![(
-                Box::new(StaticDnsMap(static_map)),
-                Duration::from_millis(1),
-            )]),
-            state: Default::default(),
-        }
-    }
-
     /// Creates a DNS resolver with a default resolution strategy
     /// to be used for most of the external use cases
     pub fn new_with_static_fallback(static_map: HashMap<&'static str, LookupResult>) -> Self {
-        let connection_params = ConnectionParams::new(
-            RouteType::Direct,
-            CLOUDFLARE_NS,
-            CLOUDFLARE_NS,
-            nonzero!(443u16),
-            HttpRequestDecoratorSeq::default(),
-            RootCertificates::Native,
-        );
-        let custom_resolver = Box::new(CustomDnsResolver::<DohTransport>::new(connection_params));
+        Self::new_with_doh_fallback(DohConfig::default(), static_map)
+    }
+
+    /// Like [`DnsResolver::new_with_static_fallback`], but with a custom DNS-over-HTTPS server.
+    ///
+    /// Lookups go to the system resolver first, then to the DoH server described by `doh_config`
+    /// (whose results are cached according to their TTLs), and finally to `static_map`.
+    pub fn new_with_doh_fallback(
+        doh_config: DohConfig,
+        static_map: HashMap<&'static str, LookupResult>,
+    ) -> Self {
+        let custom_resolver = Box::new(CustomDnsResolver::<DohTransport>::new(doh_config));
         let fallback_lookups = DNS_FALLBACK_LOOKUP_TIMEOUTS
             .iter()
             .map(|timeout| (custom_resolver.clone() as Box<dyn DnsLookup>, *timeout));
@@ -121,6 +108,19 @@ impl DnsResolver {
         }
     }
 
+    /// Forgets all cached results and abandons lookups in progress, so that the next lookup of
+    /// every hostname starts from scratch.
+    pub fn clear_cache(&self) {
+        self.state
+            .lock()
+            .expect("not poisoned")
+            .in_flight_lookups
+            .clear();
+        for (lookup, _timeout) in self.lookup_options.iter() {
+            lookup.clear_cache();
+        }
+    }
+
     pub async fn lookup_ip(&self, hostname: &str) -> Result<LookupResult> {
         match self.start_or_join_lookup(hostname).val().await {
             Ok(r) => r,
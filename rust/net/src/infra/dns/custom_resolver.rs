@@ -103,6 +103,11 @@ impl<T: DnsTransport + Sync + 'static> CustomDnsResolver<T> {
         }
     }
 
+    /// Drops all cached results, e.g. because they came from a network the device has left.
+    pub fn clear_cache(&self) {
+        self.cache.lock().expect("not poisoned").clear();
+    }
+
     fn cache_get(&self, hostname: &str) -> Option<LookupResult> {
         let mut guard = self.cache.lock().expect("not poisoned");
         match guard.get(hostname) {
@@ -584,6 +589,30 @@ pub(crate) mod test {
         assert_lookup_result_content_equal(&result_3.unwrap(), IP_V4_LIST_2, IP_V6_LIST_2);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn clear_cache_forces_new_query() {
+        let (transport, resolver) =
+            TestDnsTransportWithTwoResponses::transport_and_custom_dns_resolver(|_, q_num, txs| {
+                let [tx_1, tx_2] = txs;
+                let (ipv4s, ipv6s) = if q_num == 1 {
+                    (IP_V4_LIST_1, IP_V6_LIST_1)
+                } else {
+                    (IP_V4_LIST_2, IP_V6_LIST_2)
+                };
+                tx_1.send(ok_query_result_ipv4(NORMAL_TTL, ipv4s)).unwrap();
+                tx_2.send(ok_query_result_ipv6(NORMAL_TTL, ipv6s)).unwrap();
+            });
+
+        let result_1 = resolver.resolve(test_request()).await;
+        resolver.clear_cache();
+        // the cached result hasn't expired, but it's gone
+        let result_2 = resolver.resolve(test_request()).await;
+
+        assert_eq!(2, transport.queries_count());
+        assert_lookup_result_content_equal(&result_1.unwrap(), IP_V4_LIST_1, IP_V6_LIST_1);
+        assert_lookup_result_content_equal(&result_2.unwrap(), IP_V4_LIST_2, IP_V6_LIST_2);
+    }
+
     #[tokio::test(start_paused = true)]
     async fn results_cached_even_if_received_late() {
         // second result is sent within the `LONG_TIMEOUT`, but after the `RESOLUTION_DELAY`
@@ -23,6 +23,9 @@ pub struct DnsLookupRequest {
 #[async_trait]
 pub trait DnsLookup: Send + Sync {
     async fn dns_lookup(&self, request: DnsLookupRequest) -> dns::Result<LookupResult>;
+
+    /// Forgets any results remembered from previous lookups.
+    fn clear_cache(&self) {}
 }
 
 /// Performs DNS lookup using system resolver
@@ -71,4 +74,8 @@ where
     async fn dns_lookup(&self, request: DnsLookupRequest) -> dns::Result<LookupResult> {
         self.resolve(request).await
     }
+
+    fn clear_cache(&self) {
+        CustomDnsResolver::clear_cache(self)
+    }
 }
@@ -3,16 +3,18 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use crate::infra::certs::RootCertificates;
 use crate::infra::dns::custom_resolver::{DnsQueryResult, DnsTransport};
 use crate::infra::dns::dns_errors::Error;
-use crate::infra::dns::dns_lookup::DnsLookupRequest;
+use crate::infra::dns::dns_lookup::{DnsLookup, DnsLookupRequest};
 use crate::infra::dns::dns_message::{parse_a_record, parse_aaaa_record};
 use crate::infra::dns::dns_types::ResourceType;
 use crate::infra::dns::lookup_result::LookupResult;
 use crate::infra::dns::{dns_message, DnsResolver};
 use crate::infra::http_client::{http2_client, AggregatingHttp2Client};
 use crate::infra::tcp_ssl::DirectConnector;
-use crate::infra::{dns, ConnectionParams, DnsSource};
+use crate::infra::{dns, ConnectionParams, DnsSource, HttpRequestDecoratorSeq, RouteType};
+use crate::timeouts::TimeoutConfig;
 use async_trait::async_trait;
 use bytes::Bytes;
 use const_str::ip_addr;
@@ -20,9 +22,11 @@ use futures_util::stream::{BoxStream, FuturesUnordered};
 use http::request::Builder;
 use http::uri::PathAndQuery;
 use http::Method;
-use std::collections::HashMap;
+use nonzero_ext::nonzero;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::num::NonZeroU16;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub const CLOUDFLARE_NS: &str = "1.1.1.1";
 pub const MAX_RESPONSE_SIZE: usize = 10240;
@@ -39,42 +43,133 @@ pub const KNOWN_NAMESERVERS: &[(&str, Ipv4Addr, Ipv6Addr)] = &[
     ),
 ];
 
-fn dns_resolver_for_known_ns(ipv6_enabled: bool) -> DnsResolver {
-    let map: HashMap<_, _> = KNOWN_NAMESERVERS
-        .iter()
-        .map(|(name, ipv4, ipv6)| {
-            (
-                *name,
-                LookupResult::new(DnsSource::Static, vec![*ipv4], vec![*ipv6]),
-            )
-        })
-        .collect();
-    let result = DnsResolver::new_from_static_map(map);
-    result.set_ipv6_enabled(ipv6_enabled);
-    result
+/// Path of the DoH endpoint on most public servers.
+///
+/// <https://datatracker.ietf.org/doc/html/rfc8484#section-4.1.1>
+const DEFAULT_DOH_PATH: &str = "/dns-query";
+
+/// Describes a DNS-over-HTTPS server to be used for DNS lookups.
+#[derive(Clone, Debug)]
+pub struct DohConfig {
+    /// Hostname of the server, used for the TLS handshake.
+    ///
+    /// This can also be an IP address, as it is for [`CLOUDFLARE_NS`].
+    pub hostname: Arc<str>,
+    pub port: NonZeroU16,
+    pub path: PathAndQuery,
+    pub certs: RootCertificates,
+    /// Pre-resolved addresses of the server.
+    ///
+    /// These are the only addresses used to reach the server, so that the resolver doesn't depend
+    /// on the very DNS lookups it's meant to be a fallback for.
+    pub bootstrap_ipv4: Vec<Ipv4Addr>,
+    pub bootstrap_ipv6: Vec<Ipv6Addr>,
+}
+
+impl DohConfig {
+    /// Creates a config for a server on the default HTTPS port and path, trusting the system's
+    /// root certificates.
+    pub fn new(
+        hostname: &str,
+        bootstrap_ipv4: Vec<Ipv4Addr>,
+        bootstrap_ipv6: Vec<Ipv6Addr>,
+    ) -> Self {
+        Self {
+            hostname: hostname.into(),
+            port: nonzero!(443u16),
+            path: PathAndQuery::from_static(DEFAULT_DOH_PATH),
+            certs: RootCertificates::Native,
+            bootstrap_ipv4,
+            bootstrap_ipv6,
+        }
+    }
+
+    /// Returns configs for each of the [`KNOWN_NAMESERVERS`].
+    pub fn known_nameservers() -> impl Iterator<Item = Self> {
+        KNOWN_NAMESERVERS
+            .iter()
+            .map(|(name, ipv4, ipv6)| Self::new(name, vec![*ipv4], vec![*ipv6]))
+    }
+
+    fn connection_params(&self) -> ConnectionParams {
+        ConnectionParams {
+            route_type: RouteType::Direct,
+            sni: self.hostname.clone(),
+            host: self.hostname.clone(),
+            port: self.port,
+            http_request_decorator: HttpRequestDecoratorSeq::default(),
+            certs: self.certs.clone(),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
+        }
+    }
+
+    fn bootstrap_resolver(&self, ipv6_enabled: bool) -> DnsResolver {
+        let lookup = BootstrapDnsLookup {
+            hostname: self.hostname.clone(),
+            result: LookupResult::new(
+                DnsSource::Static,
+                self.bootstrap_ipv4.clone(),
+                self.bootstrap_ipv6.clone(),
+            ),
+        };
+        let resolver = DnsResolver::new_custom(vec![(Box::new(lookup), Duration::from_millis(1))]);
+        resolver.set_ipv6_enabled(ipv6_enabled);
+        resolver
+    }
+}
+
+impl Default for DohConfig {
+    /// Uses Cloudflare's server at [`CLOUDFLARE_NS`].
+    fn default() -> Self {
+        Self::new(
+            CLOUDFLARE_NS,
+            vec![ip_addr!(v4, "1.1.1.1")],
+            vec![ip_addr!(v6, "2606:4700:4700::1111")],
+        )
+    }
+}
+
+/// Resolves the DoH server's own hostname to its bootstrap addresses.
+struct BootstrapDnsLookup {
+    hostname: Arc<str>,
+    result: LookupResult,
+}
+
+#[async_trait]
+impl DnsLookup for BootstrapDnsLookup {
+    async fn dns_lookup(&self, request: DnsLookupRequest) -> dns::Result<LookupResult> {
+        if request.hostname == self.hostname && !self.result.is_empty() {
+            Ok(self.result.clone())
+        } else {
+            Err(Error::NoData)
+        }
+    }
 }
 
 /// DNS transport that sends queries over HTTPS
 #[derive(Clone, Debug)]
 pub struct DohTransport {
     http_client: AggregatingHttp2Client,
+    path: PathAndQuery,
 }
 
 #[async_trait]
 impl DnsTransport for DohTransport {
-    type ConnectionParameters = ConnectionParams;
+    type ConnectionParameters = DohConfig;
 
     fn dns_source() -> DnsSource {
         DnsSource::DnsOverHttpsLookup
     }
 
-    async fn connect(
-        connection_params: Self::ConnectionParameters,
-        ipv6_enabled: bool,
-    ) -> dns::Result<Self> {
-        let connector = DirectConnector::new(dns_resolver_for_known_ns(ipv6_enabled));
-        match http2_client(&connector, connection_params, MAX_RESPONSE_SIZE).await {
-            Ok(http_client) => Ok(Self { http_client }),
+    async fn connect(config: Self::ConnectionParameters, ipv6_enabled: bool) -> dns::Result<Self> {
+        let connector = DirectConnector::new(config.bootstrap_resolver(ipv6_enabled));
+        match http2_client(&connector, config.connection_params(), MAX_RESPONSE_SIZE).await {
+            Ok(http_client) => Ok(Self {
+                http_client,
+                path: config.path,
+            }),
             Err(error) => {
                 log::error!("Failed to create HTTP2 client: {}", error);
                 Err(Error::TransportFailure)
@@ -118,7 +213,7 @@ impl DohTransport {
         let (response_parts, response_body) = self
             .http_client
             .send_request_aggregate_response(
-                PathAndQuery::from_static("/dns-query"),
+                self.path.clone(),
                 builder,
                 Bytes::from(request_message),
             )
@@ -143,3 +238,47 @@ impl DohTransport {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const DOH_HOSTNAME: &str = "doh.example.com";
+    const BOOTSTRAP_IPV4: Ipv4Addr = ip_addr!(v4, "192.0.2.1");
+    const BOOTSTRAP_IPV6: Ipv6Addr = ip_addr!(v6, "2001:db8::1");
+
+    fn test_config() -> DohConfig {
+        DohConfig::new(DOH_HOSTNAME, vec![BOOTSTRAP_IPV4], vec![BOOTSTRAP_IPV6])
+    }
+
+    #[tokio::test]
+    async fn bootstrap_resolver_only_resolves_server_hostname() {
+        let resolver = test_config().bootstrap_resolver(true);
+
+        let result = resolver.lookup_ip(DOH_HOSTNAME).await.expect("resolved");
+        assert_eq!(result.ipv4, [BOOTSTRAP_IPV4]);
+        assert_eq!(result.ipv6, [BOOTSTRAP_IPV6]);
+
+        assert_matches!(resolver.lookup_ip("chat.signal.org").await, Err(_));
+    }
+
+    #[tokio::test]
+    async fn bootstrap_resolver_respects_ipv6_enabled() {
+        let resolver = test_config().bootstrap_resolver(false);
+
+        let result = resolver.lookup_ip(DOH_HOSTNAME).await.expect("resolved");
+        assert_eq!(result.ipv4, [BOOTSTRAP_IPV4]);
+        assert!(result.ipv6.is_empty());
+    }
+
+    #[test]
+    fn default_config_matches_known_nameserver() {
+        let default = DohConfig::default();
+        let known = DohConfig::known_nameservers()
+            .find(|c| c.hostname == default.hostname)
+            .expect("default is a known nameserver");
+        assert_eq!(default.bootstrap_ipv4, known.bootstrap_ipv4);
+        assert_eq!(default.bootstrap_ipv6, known.bootstrap_ipv6);
+    }
+}
@@ -26,6 +26,8 @@ pub enum TransportConnectError {
     CertError,
     /// Failed to establish SSL connection: {0}
     SslFailedHandshake(FailedHandshakeReason),
+    /// Proxy handshake failed
+    ProxyProtocol,
 }
 
 #[derive(Debug)]
@@ -50,6 +52,14 @@ pub struct FailedHandshakeReason {
     code: Option<boring::ssl::ErrorCode>,
 }
 
+impl FailedHandshakeReason {
+    /// The handshake didn't complete within the allotted time.
+    pub(crate) const TIMED_OUT: Self = Self {
+        io: Some(std::io::ErrorKind::TimedOut),
+        code: None,
+    };
+}
+
 impl<S> From<HandshakeError<S>> for FailedHandshakeReason {
     fn from(value: HandshakeError<S>) -> Self {
         log::debug!("handshake error: {value}");
@@ -103,7 +113,8 @@ impl From<TransportConnectError> for std::io::Error {
             TransportConnectError::TcpConnectionFailed => ErrorKind::ConnectionRefused,
             TransportConnectError::SslFailedHandshake(_)
             | TransportConnectError::SslError(_)
-            | TransportConnectError::CertError => ErrorKind::InvalidData,
+            | TransportConnectError::CertError
+            | TransportConnectError::ProxyProtocol => ErrorKind::InvalidData,
             TransportConnectError::DnsError => ErrorKind::NotFound,
         };
         Self::new(kind, value.to_string())
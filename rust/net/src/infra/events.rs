@@ -0,0 +1,204 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use crate::infra::{DnsSource, IpType, RouteType};
+
+/// Something that happened while establishing a connection.
+///
+/// Events never contain proxy hostnames or IP addresses, only the (public) hostnames of the
+/// services being connected to.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ConnectionEvent {
+    /// A DNS lookup finished; `source` is `None` if it failed.
+    DnsLookup {
+        route: RouteType,
+        #[serde(rename = "durationMillis", serialize_with = "serialize_millis")]
+        duration: Duration,
+        source: Option<DnsSource>,
+    },
+    /// A TCP connection attempt to one of the resolved addresses finished.
+    ///
+    /// Attempts that were abandoned because another address connected first are not reported.
+    TcpConnect {
+        route: RouteType,
+        ip_type: IpType,
+        #[serde(rename = "durationMillis", serialize_with = "serialize_millis")]
+        duration: Duration,
+        succeeded: bool,
+    },
+    /// A TLS handshake with a service (not with a TLS proxy) finished.
+    TlsHandshake {
+        route: RouteType,
+        host: Arc<str>,
+        #[serde(rename = "durationMillis", serialize_with = "serialize_millis")]
+        duration: Duration,
+        succeeded: bool,
+    },
+    /// A websocket upgrade request finished.
+    ///
+    /// `status` is the HTTP status the server responded with, if it got that far.
+    WebSocketUpgrade {
+        route: RouteType,
+        host: Arc<str>,
+        #[serde(rename = "durationMillis", serialize_with = "serialize_millis")]
+        duration: Duration,
+        status: Option<u16>,
+    },
+    /// A connection manager made an attempt over one of its routes.
+    ///
+    /// `consecutive_failures` counts this attempt, so it is 0 if the route was chosen and 1 or
+    /// more if it will be retried later. It is also 0 when the route's backoff policy has just
+    /// given up on it, since the count starts over once the route has cooled down.
+    RouteAttempt {
+        route: RouteType,
+        host: Arc<str>,
+        #[serde(rename = "durationMillis", serialize_with = "serialize_millis")]
+        duration: Duration,
+        outcome: RouteAttemptOutcome,
+        consecutive_failures: u16,
+    },
+    /// A connection manager skipped a route because it failed recently.
+    RouteCoolingDown {
+        route: RouteType,
+        host: Arc<str>,
+        #[serde(rename = "remainingMillis", serialize_with = "serialize_millis")]
+        remaining: Duration,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RouteAttemptOutcome {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+/// Receives [`ConnectionEvent`]s for all connections made with a particular
+/// [`ConnectionParams`](crate::infra::ConnectionParams).
+///
+/// Events are reported synchronously from the task making the connection, so implementations
+/// should return quickly.
+pub trait ConnectionEventObserver: Debug + Send + Sync {
+    fn on_event(&self, event: ConnectionEvent);
+}
+
+/// A [`ConnectionEventObserver`] that keeps the most recent events until they're collected.
+#[derive(Debug)]
+pub struct ConnectionEventLog {
+    capacity: NonZeroUsize,
+    events: Mutex<VecDeque<ConnectionEvent>>,
+}
+
+impl ConnectionEventLog {
+    /// Creates an empty log; once `capacity` events are waiting, the oldest ones are dropped.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            events: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Removes and returns the oldest recorded event, if there is one.
+    pub fn take_event(&self) -> Option<ConnectionEvent> {
+        self.events.lock().expect("not poisoned").pop_front()
+    }
+
+    /// Removes and returns all recorded events, oldest first.
+    pub fn take_events(&self) -> Vec<ConnectionEvent> {
+        std::mem::take(&mut *self.events.lock().expect("not poisoned")).into()
+    }
+}
+
+impl ConnectionEventObserver for ConnectionEventLog {
+    fn on_event(&self, event: ConnectionEvent) {
+        let mut events = self.events.lock().expect("not poisoned");
+        if events.len() == self.capacity.get() {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nonzero_ext::nonzero;
+
+    use super::*;
+
+    fn cooling_down(remaining_secs: u64) -> ConnectionEvent {
+        ConnectionEvent::RouteCoolingDown {
+            route: RouteType::Test,
+            host: "chat.example".into(),
+            remaining: Duration::from_secs(remaining_secs),
+        }
+    }
+
+    #[test]
+    fn log_drops_oldest_events_when_full() {
+        let log = ConnectionEventLog::new(nonzero!(2usize));
+        for i in 1..=3 {
+            log.on_event(cooling_down(i));
+        }
+        assert_eq!(log.take_event(), Some(cooling_down(2)));
+        assert_eq!(log.take_events(), [cooling_down(3)]);
+        assert!(log.take_events().is_empty());
+        assert_eq!(log.take_event(), None);
+    }
+
+    #[test]
+    fn events_serialize_with_camel_case_fields() {
+        let event = ConnectionEvent::RouteAttempt {
+            route: RouteType::ProxyF,
+            host: "chat.example".into(),
+            duration: Duration::from_millis(1500),
+            outcome: RouteAttemptOutcome::TimedOut,
+            consecutive_failures: 2,
+        };
+        assert_eq!(
+            serde_json::to_value(event).expect("can serialize"),
+            serde_json::json!({
+                "type": "routeAttempt",
+                "route": "proxyf",
+                "host": "chat.example",
+                "durationMillis": 1500,
+                "outcome": "timedOut",
+                "consecutiveFailures": 2,
+            })
+        );
+
+        let event = ConnectionEvent::DnsLookup {
+            route: RouteType::Direct,
+            duration: Duration::from_millis(20),
+            source: Some(DnsSource::DnsOverHttpsLookup),
+        };
+        assert_eq!(
+            serde_json::to_value(event).expect("can serialize"),
+            serde_json::json!({
+                "type": "dnsLookup",
+                "route": "direct",
+                "durationMillis": 20,
+                "source": "dnsoverhttpslookup",
+            })
+        );
+    }
+}
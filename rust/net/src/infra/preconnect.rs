@@ -0,0 +1,257 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use derive_where::derive_where;
+use tokio::time::Instant;
+
+use crate::infra::errors::TransportConnectError;
+use crate::infra::{Alpn, ConnectionParams, RouteKey, StreamAndInfo, TransportConnector};
+
+/// [`TransportConnector`] that can establish a connection ahead of time.
+///
+/// A connection made by [`PreconnectingConnector::preconnect`] is handed out by the next call to
+/// [`connect`](TransportConnector::connect) for the same route, as long as that happens before
+/// the connection expires. Otherwise it is dropped and a new connection is made as usual.
+///
+/// Clones share the saved connection.
+#[derive_where(Clone; C)]
+pub struct PreconnectingConnector<C: TransportConnector> {
+    inner: C,
+    saved: Arc<Mutex<Option<SavedConnection<C::Stream>>>>,
+    expiration: Duration,
+}
+
+struct SavedConnection<S> {
+    route: RouteKey,
+    established_at: Instant,
+    stream: StreamAndInfo<S>,
+}
+
+impl<C: TransportConnector> PreconnectingConnector<C> {
+    pub fn new(inner: C, expiration: Duration) -> Self {
+        Self {
+            inner,
+            saved: Default::default(),
+            expiration,
+        }
+    }
+
+    /// Connects to the given route and saves the connection for a later call to
+    /// [`connect`](TransportConnector::connect).
+    ///
+    /// Replaces any connection saved previously.
+    pub async fn preconnect(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+    ) -> Result<(), TransportConnectError> {
+        let stream = self.inner.connect(connection_params, alpn).await?;
+        let established_at = Instant::now();
+        *self.saved.lock().expect("not poisoned") = Some(SavedConnection {
+            route: RouteKey::new(connection_params, alpn),
+            established_at,
+            stream,
+        });
+
+        // Don't hold on to the connection past its expiration even if nobody asks for it.
+        let saved = Arc::downgrade(&self.saved);
+        let expiration = self.expiration;
+        tokio::spawn(async move {
+            tokio::time::sleep(expiration).await;
+            let Some(saved) = saved.upgrade() else {
+                return;
+            };
+            let mut saved = saved.lock().expect("not poisoned");
+            if saved
+                .as_ref()
+                .is_some_and(|saved| saved.established_at == established_at)
+            {
+                *saved = None;
+            }
+        });
+        Ok(())
+    }
+
+    /// Takes the saved connection if it was made for the given route and hasn't expired yet.
+    ///
+    /// An expired connection is dropped either way.
+    fn take_saved(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+    ) -> Option<StreamAndInfo<C::Stream>> {
+        let mut saved = self.saved.lock().expect("not poisoned");
+        if saved
+            .as_ref()
+            .is_some_and(|saved| saved.established_at.elapsed() >= self.expiration)
+        {
+            log::debug!("dropping expired preconnected transport");
+            *saved = None;
+        }
+        if saved
+            .as_ref()
+            .is_some_and(|saved| saved.route == RouteKey::new(connection_params, alpn))
+        {
+            saved.take().map(|saved| saved.stream)
+        } else {
+            None
+        }
+    }
+}
+
+#[async_trait]
+impl<C: TransportConnector> TransportConnector for PreconnectingConnector<C> {
+    type Stream = C::Stream;
+
+    async fn connect(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+    ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+        if let Some(stream) = self.take_saved(connection_params, alpn) {
+            log::debug!(
+                "using preconnected transport to {}:{}",
+                connection_params.host,
+                connection_params.port
+            );
+            return Ok(stream);
+        }
+        self.inner.connect(connection_params, alpn).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use nonzero_ext::nonzero;
+    use tokio::io::DuplexStream;
+
+    use super::*;
+    use crate::infra::certs::RootCertificates;
+    use crate::infra::{ConnectionInfo, DnsSource, HttpRequestDecoratorSeq, RouteType};
+
+    const EXPIRATION: Duration = Duration::from_secs(10);
+
+    /// Connector that counts how many connections it has made.
+    #[derive(Clone, Default)]
+    struct CountingConnector(Arc<AtomicUsize>);
+
+    impl CountingConnector {
+        fn count(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl TransportConnector for CountingConnector {
+        type Stream = DuplexStream;
+
+        async fn connect(
+            &self,
+            connection_params: &ConnectionParams,
+            _alpn: Alpn,
+        ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let (stream, _other_end) = tokio::io::duplex(64);
+            Ok(StreamAndInfo(
+                stream,
+                ConnectionInfo {
+                    route_type: connection_params.route_type,
+                    dns_source: DnsSource::Test,
+                    address: url::Host::Domain(connection_params.host.to_string()),
+                },
+            ))
+        }
+    }
+
+    fn params(host: &str) -> ConnectionParams {
+        ConnectionParams::new(
+            RouteType::Test,
+            host,
+            host,
+            nonzero!(443u16),
+            HttpRequestDecoratorSeq::default(),
+            RootCertificates::Signal,
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn preconnected_transport_is_used_once() {
+        let inner = CountingConnector::default();
+        let connector = PreconnectingConnector::new(inner.clone(), EXPIRATION);
+        let params = params("chat.signal.org");
+
+        connector
+            .preconnect(&params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 1);
+
+        let _stream = connector
+            .connect(&params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 1);
+
+        let _stream = connector
+            .connect(&params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn preconnected_transport_expires() {
+        let inner = CountingConnector::default();
+        let connector = PreconnectingConnector::new(inner.clone(), EXPIRATION);
+        let params = params("chat.signal.org");
+
+        connector
+            .preconnect(&params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        tokio::time::advance(EXPIRATION).await;
+
+        let _stream = connector
+            .connect(&params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn preconnected_transport_is_only_used_for_the_same_route() {
+        let inner = CountingConnector::default();
+        let connector = PreconnectingConnector::new(inner.clone(), EXPIRATION);
+
+        connector
+            .preconnect(&params("chat.signal.org"), Alpn::Http1_1)
+            .await
+            .expect("can connect");
+
+        let _stream = connector
+            .connect(&params("cdsi.signal.org"), Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        let _stream = connector
+            .connect(&params("chat.signal.org"), Alpn::Http2)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 3);
+
+        // The saved connection is still there for the original route.
+        let _stream = connector
+            .clone()
+            .connect(&params("chat.signal.org"), Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(inner.count(), 3);
+    }
+}
@@ -17,7 +17,7 @@ use tokio::time::{timeout_at, Instant};
 use tokio_util::sync::CancellationToken;
 
 use crate::infra::connection_manager::{
-    ConnectionAttemptOutcome, ConnectionManager, ErrorClass, ErrorClassifier,
+    ConnectionAttemptOutcome, ConnectionManager, ErrorClass, ErrorClassifier, NetworkChangeListener,
 };
 use crate::infra::errors::LogSafeDisplay;
 use crate::infra::{ConnectionInfo, ConnectionParams, HttpRequestDecorator};
@@ -453,8 +453,24 @@ where
 
     fn schedule_reconnect(&self, service_status: ServiceStatus<C::StartError>) {
         let service_with_reconnect = self.clone();
+        let network_changes = self
+            .data
+            .service_initializer
+            .connection_manager
+            .network_change_listener()
+            .cloned();
+        let mut network_generation = network_changes
+            .as_ref()
+            .map_or(0, NetworkChangeListener::generation);
         tokio::spawn(async move {
-            let _ = service_status.service_cancellation.cancelled().await;
+            tokio::select! {
+                () = service_status.stopped() => {}
+                () = network_changed_since(network_changes.as_ref(), network_generation) => {
+                    // The connection is probably using the old network, so replace it.
+                    log::info!("network changed; restarting service");
+                    service_status.stop_service();
+                }
+            }
             if let Some(error) = service_status.get_error() {
                 log::debug!("Service stopped due to an error: {:?}", error);
                 log::info!("Service stopped due to an error: {}", error);
@@ -468,8 +484,16 @@ where
             let mut sleep_until = Instant::now();
             loop {
                 if sleep_until > Instant::now() {
-                    tokio::time::sleep_until(sleep_until).await;
+                    tokio::select! {
+                        () = tokio::time::sleep_until(sleep_until) => {}
+                        () = network_changed_since(network_changes.as_ref(), network_generation) => {
+                            log::info!("network changed; reconnecting without waiting for cooldown");
+                        }
+                    }
                 }
+                network_generation = network_changes
+                    .as_ref()
+                    .map_or(0, NetworkChangeListener::generation);
                 log::debug!("attempting reconnect");
                 match service_with_reconnect.reconnect_if_active().await {
                     Ok(_) => {
@@ -506,6 +530,13 @@ where
     }
 }
 
+async fn network_changed_since(listener: Option<&NetworkChangeListener>, generation: u64) {
+    match listener {
+        Some(listener) => listener.changed_since(generation).await,
+        None => std::future::pending().await,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::fmt::Debug;
@@ -523,7 +554,9 @@ mod test {
 
     use super::*;
     use crate::infra::certs::RootCertificates;
-    use crate::infra::connection_manager::SingleRouteThrottlingConnectionManager;
+    use crate::infra::connection_manager::{
+        NetworkChangeNotifier, SingleRouteThrottlingConnectionManager,
+    };
     use crate::infra::test::shared::{
         TestError, LONG_CONNECTION_TIME, NORMAL_CONNECTION_TIME, TIMEOUT_DURATION,
         TIME_ADVANCE_VALUE,
@@ -883,6 +916,66 @@ mod test {
         assert_matches!(service_with_reconnect.service().await, Ok(_));
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn network_change_cuts_reconnect_cooldown_short() {
+        let notifier = NetworkChangeNotifier::default();
+        let connector = TestServiceConnector::new();
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(),
+            TIMEOUT_DURATION,
+        )
+        .with_network_change_listener(notifier.listener());
+        let service_with_reconnect =
+            ServiceWithReconnect::new(connector.clone(), manager, TIMEOUT_DURATION);
+        service_with_reconnect
+            .connect_from_inactive()
+            .await
+            .expect("connected");
+        let service = service_with_reconnect.service().await.expect("service");
+
+        // internet connection lost
+        connector.set_service_healthy(false);
+        service.close_channel();
+
+        sleep_and_catch_up(NORMAL_CONNECTION_TIME).await;
+        sleep_and_catch_up(CONNECTION_ROUTE_COOLDOWN_INTERVALS[0] + NORMAL_CONNECTION_TIME).await;
+        sleep_and_catch_up(CONNECTION_ROUTE_COOLDOWN_INTERVALS[1] + NORMAL_CONNECTION_TIME).await;
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 4);
+        assert_matches!(service_with_reconnect.service().await, Err(_));
+
+        // The next attempt would normally wait for CONNECTION_ROUTE_COOLDOWN_INTERVALS[2], but
+        // switching networks makes it happen right away.
+        assert!(NORMAL_CONNECTION_TIME < CONNECTION_ROUTE_COOLDOWN_INTERVALS[2]);
+        connector.set_service_healthy(true);
+        notifier.network_changed();
+        sleep_and_catch_up(NORMAL_CONNECTION_TIME).await;
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 5);
+        assert_matches!(service_with_reconnect.service().await, Ok(_));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn network_change_restarts_active_service() {
+        let notifier = NetworkChangeNotifier::default();
+        let connector = TestServiceConnector::new();
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(),
+            TIMEOUT_DURATION,
+        )
+        .with_network_change_listener(notifier.listener());
+        let service_with_reconnect =
+            ServiceWithReconnect::new(connector.clone(), manager, TIMEOUT_DURATION);
+        service_with_reconnect
+            .connect_from_inactive()
+            .await
+            .expect("connected");
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 1);
+
+        notifier.network_changed();
+        sleep_and_catch_up(NORMAL_CONNECTION_TIME).await;
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 2);
+        assert_matches!(service_with_reconnect.service().await, Ok(_));
+    }
+
     #[tokio::test(start_paused = true)]
     async fn service_stops_reconnect_attempts_if_disconnected_after_some_time() {
         let (connector, service_with_reconnect) = connector_and_service();
@@ -6,28 +6,37 @@
 use std::net::IpAddr;
 use std::num::NonZeroU16;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::timeouts::TCP_CONNECTION_ATTEMPT_DELAY;
 use async_trait::async_trait;
-use boring::ssl::{ConnectConfiguration, SslConnector, SslMethod};
-use futures_util::TryFutureExt;
+use boring::ssl::{ConnectConfiguration, SslConnector, SslConnectorBuilder, SslMethod};
+use futures_util::{FutureExt as _, TryFutureExt};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
+use tokio::time::Instant;
 use tokio_boring::SslStream;
 use tokio_util::either::Either;
 
 use crate::infra::certs::RootCertificates;
 use crate::infra::dns::DnsResolver;
-use crate::infra::errors::TransportConnectError;
+use crate::infra::errors::{FailedHandshakeReason, TransportConnectError};
+use crate::infra::events::ConnectionEvent;
+use crate::infra::tcp_ssl::proxy::{TunnelProtocol, TunnelProxyConnector};
 use crate::infra::{
-    Alpn, ConnectionInfo, ConnectionParams, RouteType, StreamAndInfo, TransportConnector,
+    Alpn, ConnectionInfo, ConnectionParams, IpType, RouteType, StreamAndInfo, TransportConnector,
 };
-use crate::utils::first_ok;
+use crate::utils::first_ok_staggered;
+
+pub mod proxy;
+pub mod session_cache;
 
 #[derive(Clone)]
 pub enum TcpSslConnector {
     Direct(DirectConnector),
     Proxied(ProxyConnector),
+    /// Connects through a SOCKS5 or HTTP CONNECT proxy.
+    Tunneled(TunnelProxyConnector),
     /// Used when configuring one of the other kinds of connector isn't possible, perhaps because
     /// invalid configuration options were provided.
     Invalid(DnsResolver),
@@ -35,12 +44,16 @@ pub enum TcpSslConnector {
 
 impl TcpSslConnector {
     pub fn set_ipv6_enabled(&mut self, ipv6_enabled: bool) {
-        let dns_resolver = match self {
-            TcpSslConnector::Direct(c) => &mut c.dns_resolver,
-            TcpSslConnector::Proxied(c) => &mut c.dns_resolver,
+        self.dns_resolver().set_ipv6_enabled(ipv6_enabled);
+    }
+
+    pub fn dns_resolver(&self) -> &DnsResolver {
+        match self {
+            TcpSslConnector::Direct(c) => &c.dns_resolver,
+            TcpSslConnector::Proxied(c) => &c.dns_resolver,
+            TcpSslConnector::Tunneled(c) => &c.dns_resolver,
             TcpSslConnector::Invalid(resolver) => resolver,
-        };
-        dns_resolver.set_ipv6_enabled(ipv6_enabled);
+        }
     }
 }
 
@@ -67,7 +80,7 @@ impl TransportConnector for DirectConnector {
     ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
         let StreamAndInfo(tcp_stream, remote_address) = connect_tcp(
             &self.dns_resolver,
-            connection_params.route_type,
+            connection_params,
             &connection_params.sni,
             connection_params.port,
         )
@@ -88,6 +101,15 @@ impl DirectConnector {
         let Self { dns_resolver } = self;
         ProxyConnector::new(dns_resolver.clone(), proxy_addr)
     }
+
+    pub fn with_tunnel_proxy(
+        &self,
+        proxy_addr: (&str, NonZeroU16),
+        protocol: TunnelProtocol,
+    ) -> TunnelProxyConnector {
+        let Self { dns_resolver } = self;
+        TunnelProxyConnector::new(dns_resolver.clone(), proxy_addr, protocol)
+    }
 }
 
 #[derive(Clone)]
@@ -116,7 +138,7 @@ impl TransportConnector for ProxyConnector {
     ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
         let StreamAndInfo(tcp_stream, remote_address) = connect_tcp(
             &self.dns_resolver,
-            connection_params.route_type,
+            connection_params,
             &self.proxy_host,
             self.proxy_port,
         )
@@ -130,7 +152,15 @@ impl TransportConnector for ProxyConnector {
                     self.proxy_port
                 );
                 let ssl_config = ssl_config(&self.proxy_certs, &self.proxy_host, None)?;
-                Either::Left(tokio_boring::connect(ssl_config, &self.proxy_host, tcp_stream).await?)
+                Either::Left(
+                    tls_handshake(
+                        ssl_config,
+                        &self.proxy_host,
+                        tcp_stream,
+                        connection_params.timeouts.tls_handshake,
+                    )
+                    .await?,
+                )
             }
             ShouldUseTls::No => {
                 log::debug!(
@@ -194,12 +224,22 @@ fn ssl_config(
     host_name: &str,
     alpn: Option<Alpn>,
 ) -> Result<ConnectConfiguration, TransportConnectError> {
+    Ok(ssl_connector_builder(certs, host_name, alpn)?
+        .build()
+        .configure()?)
+}
+
+fn ssl_connector_builder(
+    certs: &RootCertificates,
+    host_name: &str,
+    alpn: Option<Alpn>,
+) -> Result<SslConnectorBuilder, TransportConnectError> {
     let mut ssl = SslConnector::builder(SslMethod::tls_client())?;
     certs.apply_to_connector(&mut ssl, host_name)?;
     if let Some(alpn) = alpn {
         ssl.set_alpn_protos(alpn.as_ref())?;
     }
-    Ok(ssl.build().configure()?)
+    Ok(ssl)
 }
 
 async fn connect_tls<S: AsyncRead + AsyncWrite + Unpin>(
@@ -207,61 +247,110 @@ async fn connect_tls<S: AsyncRead + AsyncWrite + Unpin>(
     connection_params: &ConnectionParams,
     alpn: Alpn,
 ) -> Result<SslStream<S>, TransportConnectError> {
-    let ssl_config = ssl_config(&connection_params.certs, &connection_params.sni, Some(alpn))?;
+    let ssl_config = match &connection_params.tls_session_cache {
+        Some(cache) => cache.configure(connection_params, alpn)?,
+        None => ssl_config(&connection_params.certs, &connection_params.sni, Some(alpn))?,
+    };
 
-    Ok(tokio_boring::connect(ssl_config, &connection_params.sni, transport).await?)
+    let handshake_start = Instant::now();
+    let result = tls_handshake(
+        ssl_config,
+        &connection_params.sni,
+        transport,
+        connection_params.timeouts.tls_handshake,
+    )
+    .await;
+    connection_params.report_event(|| ConnectionEvent::TlsHandshake {
+        route: connection_params.route_type,
+        host: connection_params.host.clone(),
+        duration: handshake_start.elapsed(),
+        succeeded: result.is_ok(),
+    });
+    result
 }
 
+/// Performs a TLS handshake over `transport`, giving up if it takes longer than `timeout`.
+async fn tls_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    ssl_config: ConnectConfiguration,
+    domain: &str,
+    transport: S,
+    timeout: Duration,
+) -> Result<SslStream<S>, TransportConnectError> {
+    match tokio::time::timeout(
+        timeout,
+        tokio_boring::connect(ssl_config, domain, transport),
+    )
+    .await
+    {
+        Ok(result) => Ok(result?),
+        Err(_elapsed) => Err(TransportConnectError::SslFailedHandshake(
+            FailedHandshakeReason::TIMED_OUT,
+        )),
+    }
+}
+
+/// Resolves `host` and connects to one of its addresses.
+///
+/// `connection_params` supplies the route type and the event observer; `host` and `port` may
+/// refer to a proxy rather than the service itself.
 async fn connect_tcp(
     dns_resolver: &DnsResolver,
-    route_type: RouteType,
+    connection_params: &ConnectionParams,
     host: &str,
     port: NonZeroU16,
 ) -> Result<StreamAndInfo<TcpStream>, TransportConnectError> {
+    let route_type = connection_params.route_type;
+    let tcp_connect_timeout = connection_params.timeouts.tcp_connect;
+    let lookup_start = Instant::now();
     let dns_lookup = dns_resolver
         .lookup_ip(host)
         .await
-        .map_err(|_| TransportConnectError::DnsError)?;
-
-    if dns_lookup.is_empty() {
-        return Err(TransportConnectError::DnsError);
-    }
+        .ok()
+        .filter(|lookup| !lookup.is_empty());
+    connection_params.report_event(|| ConnectionEvent::DnsLookup {
+        route: route_type,
+        duration: lookup_start.elapsed(),
+        source: dns_lookup.as_ref().map(|lookup| lookup.source()),
+    });
+    let dns_lookup = dns_lookup.ok_or(TransportConnectError::DnsError)?;
 
     let dns_source = dns_lookup.source();
 
     // The idea is to go through the list of candidate IP addresses
     // and to attempt a connection to each of them, giving each one a `CONNECTION_ATTEMPT_DELAY` headstart
-    // before moving on to the next candidate.
-    // The process stops once we have a successful connection.
-
-    // First, for each resolved IP address, constructing a future
-    // that incorporates the delay based on its position in the list.
-    // This way we can start all futures at once and simply wait for the first one to complete successfully.
-    let staggered_futures = dns_lookup.into_iter().enumerate().map(|(idx, ip)| {
-        let delay = TCP_CONNECTION_ATTEMPT_DELAY * idx.try_into().unwrap();
-        async move {
-            if !delay.is_zero() {
-                tokio::time::sleep(delay).await;
-            }
-            TcpStream::connect((ip, port.into()))
-                .inspect_err(|e| {
-                    log::debug!("failed to connect to IP [{}] with an error: {:?}", ip, e)
+    // before moving on to the next candidate. If an attempt fails before its headstart is up,
+    // the next one is started right away, so that e.g. a broken IPv6 path doesn't hold up IPv4.
+    // The process stops once we have a successful connection, and any attempts
+    // still in progress at that point are cancelled. Each attempt on its own is
+    // abandoned after the route's TCP connect timeout.
+    let connection_attempts = dns_lookup.into_iter().map(|ip| {
+        let attempt_start = Instant::now();
+        tokio::time::timeout(tcp_connect_timeout, TcpStream::connect((ip, port.into())))
+            .map(|result| result.unwrap_or_else(|elapsed| Err(elapsed.into())))
+            .inspect(move |result| {
+                connection_params.report_event(|| ConnectionEvent::TcpConnect {
+                    route: route_type,
+                    ip_type: IpType::from_host(&ip_addr_to_host(ip)),
+                    duration: attempt_start.elapsed(),
+                    succeeded: result.is_ok(),
                 })
-                .await
-                .map(|r| {
-                    StreamAndInfo(
-                        r,
-                        ConnectionInfo {
-                            route_type,
-                            dns_source,
-                            address: ip_addr_to_host(ip),
-                        },
-                    )
-                })
-        }
+            })
+            .inspect_err(move |e| {
+                log::debug!("failed to connect to IP [{}] with an error: {:?}", ip, e)
+            })
+            .map_ok(move |r| {
+                StreamAndInfo(
+                    r,
+                    ConnectionInfo {
+                        route_type,
+                        dns_source,
+                        address: ip_addr_to_host(ip),
+                    },
+                )
+            })
     });
 
-    first_ok(staggered_futures)
+    first_ok_staggered(connection_attempts, TCP_CONNECTION_ATTEMPT_DELAY)
         .await
         .ok_or(TransportConnectError::TcpConnectionFailed)
 }
@@ -325,6 +414,12 @@ impl TransportConnector for TcpSslConnector {
                 .connect(connection_params, alpn)
                 .await
                 .map(|s| s.map_stream(Either::Right)),
+            // The tunnel is already stripped off by the time the TLS session
+            // starts, so the stream has the same type as a direct connection.
+            Self::Tunneled(tunneled) => tunneled
+                .connect(connection_params, alpn)
+                .await
+                .map(|s| s.map_stream(Either::Left)),
             Self::Invalid(_) => Err(TransportConnectError::InvalidConfiguration),
         }
         .map(|s| s.map_stream(TcpSslConnectorStream))
@@ -343,6 +438,12 @@ impl From<ProxyConnector> for TcpSslConnector {
     }
 }
 
+impl From<TunnelProxyConnector> for TcpSslConnector {
+    fn from(value: TunnelProxyConnector) -> Self {
+        Self::Tunneled(value)
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod testutil {
     use std::future::Future;
@@ -538,8 +639,11 @@ mod test {
     use super::testutil::*;
     use super::*;
     use crate::infra::dns::lookup_result::LookupResult;
-    use crate::infra::HttpRequestDecoratorSeq;
+    use crate::infra::events::ConnectionEventLog;
+    use crate::infra::{DnsSource, HttpRequestDecoratorSeq};
+    use crate::timeouts::TimeoutConfig;
     use assert_matches::assert_matches;
+    use nonzero_ext::nonzero;
     use std::borrow::Cow;
     use std::collections::HashMap;
     use std::net::Ipv6Addr;
@@ -559,6 +663,9 @@ mod test {
             port: addr.port().try_into().expect("bound port"),
             http_request_decorator: HttpRequestDecoratorSeq::default(),
             certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
         };
 
         let StreamAndInfo(stream, info) = connector
@@ -578,6 +685,56 @@ mod test {
         make_http_request_response_over(stream).await
     }
 
+    #[tokio::test]
+    async fn connect_to_server_reports_events() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let event_log = Arc::new(ConnectionEventLog::new(nonzero!(10usize)));
+        let connector = DirectConnector::new(DnsResolver::new_with_static_fallback(HashMap::from(
+            [(SERVER_HOSTNAME, LookupResult::localhost())],
+        )));
+        let connection_params = ConnectionParams::new(
+            RouteType::Test,
+            SERVER_HOSTNAME,
+            SERVER_HOSTNAME,
+            addr.port().try_into().expect("bound port"),
+            HttpRequestDecoratorSeq::default(),
+            RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        )
+        .with_event_observer(event_log.clone());
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        make_http_request_response_over(stream).await;
+
+        let events = event_log.take_events();
+        assert_matches!(
+            &events[..],
+            [
+                ConnectionEvent::DnsLookup {
+                    route: RouteType::Test,
+                    source: Some(DnsSource::Static),
+                    ..
+                },
+                ConnectionEvent::TcpConnect {
+                    route: RouteType::Test,
+                    ip_type: IpType::V6,
+                    succeeded: true,
+                    ..
+                },
+                ConnectionEvent::TlsHandshake {
+                    route: RouteType::Test,
+                    host,
+                    succeeded: true,
+                    ..
+                },
+            ] if &**host == SERVER_HOSTNAME
+        );
+    }
+
     #[tokio::test]
     async fn connect_through_proxy() {
         let (addr, server) = localhost_http_server();
@@ -609,6 +766,9 @@ mod test {
             port: addr.port().try_into().expect("bound port"),
             http_request_decorator: HttpRequestDecoratorSeq::default(),
             certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
         };
 
         let StreamAndInfo(stream, info) = connector
@@ -654,6 +814,9 @@ mod test {
             port: addr.port().try_into().expect("bound port"),
             http_request_decorator: HttpRequestDecoratorSeq::default(),
             certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
         };
 
         let StreamAndInfo(stream, info) = connector
@@ -673,6 +836,41 @@ mod test {
         make_http_request_response_over(stream).await;
     }
 
+    #[tokio::test]
+    async fn tls_handshake_times_out() {
+        // A server that accepts TCP connections but never responds to the TLS handshake.
+        let listener = tokio::net::TcpListener::bind((Ipv6Addr::LOCALHOST, 0))
+            .await
+            .expect("can bind");
+        let addr = listener.local_addr().expect("bound");
+        let _server_handle = tokio::spawn(async move {
+            let mut accepted = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted.push(stream);
+            }
+        });
+
+        let connector = DirectConnector::new(DnsResolver::new_with_static_fallback(HashMap::from(
+            [(SERVER_HOSTNAME, LookupResult::localhost())],
+        )));
+        let connection_params = ConnectionParams::new(
+            RouteType::Test,
+            SERVER_HOSTNAME,
+            SERVER_HOSTNAME,
+            addr.port().try_into().expect("bound port"),
+            HttpRequestDecoratorSeq::default(),
+            RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        )
+        .with_timeouts(TimeoutConfig::default().with_tls_handshake(Duration::from_millis(50)));
+
+        match connector.connect(&connection_params, Alpn::Http1_1).await {
+            Ok(_) => panic!("should have timed out"),
+            Err(e) => {
+                assert_matches!(e, TransportConnectError::SslFailedHandshake(_));
+            }
+        }
+    }
+
     #[tokio::test]
     async fn connect_through_invalid() {
         let (addr, server) = localhost_http_server();
@@ -688,6 +886,9 @@ mod test {
             port: addr.port().try_into().expect("bound port"),
             http_request_decorator: HttpRequestDecoratorSeq::default(),
             certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
         };
 
         match connector.connect(&connection_params, Alpn::Http1_1).await {
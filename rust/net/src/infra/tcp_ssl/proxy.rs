@@ -0,0 +1,773 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Connecting through SOCKS5 and HTTP CONNECT proxies.
+//!
+//! Unlike the TLS proxy used by [`ProxyConnector`](super::ProxyConnector), these
+//! proxies are told explicitly which host to connect to. The target is always
+//! sent to the proxy by name so that its DNS resolution happens on the proxy's
+//! side and the hostname is never looked up locally.
+
+use std::net::IpAddr;
+use std::num::NonZeroU16;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use tokio::net::TcpStream;
+use tokio_boring::SslStream;
+
+use crate::infra::dns::DnsResolver;
+use crate::infra::errors::TransportConnectError;
+use crate::infra::tcp_ssl::{connect_tcp, connect_tls};
+use crate::infra::{
+    Alpn, ConnectionInfo, ConnectionParams, RouteType, StreamAndInfo, TransportConnector,
+};
+use crate::utils::basic_authorization;
+
+/// Credentials presented to a proxy server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// The protocol used to ask a proxy server to open a tunnel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TunnelProtocol {
+    /// SOCKS5 ([RFC 1928]), optionally with username/password authentication
+    /// ([RFC 1929]).
+    ///
+    /// [RFC 1928]: https://datatracker.ietf.org/doc/html/rfc1928
+    /// [RFC 1929]: https://datatracker.ietf.org/doc/html/rfc1929
+    Socks5 { auth: Option<ProxyAuth> },
+    /// HTTP `CONNECT` tunneling, optionally with `Basic` proxy authorization.
+    HttpConnect { auth: Option<ProxyAuth> },
+}
+
+impl TunnelProtocol {
+    fn route_type(&self) -> RouteType {
+        match self {
+            TunnelProtocol::Socks5 { .. } => RouteType::SocksProxy,
+            TunnelProtocol::HttpConnect { .. } => RouteType::HttpProxy,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TunnelProtocol::Socks5 { .. } => "SOCKS5",
+            TunnelProtocol::HttpConnect { .. } => "HTTP CONNECT",
+        }
+    }
+}
+
+/// Establishes TLS connections through a SOCKS5 or HTTP CONNECT proxy.
+///
+/// The TCP connection to the proxy itself is made using the local
+/// [`DnsResolver`]; the TLS connection to the target is made end-to-end over
+/// the tunnel.
+#[derive(Clone)]
+pub struct TunnelProxyConnector {
+    pub dns_resolver: DnsResolver,
+    proxy_host: Arc<str>,
+    proxy_port: NonZeroU16,
+    protocol: TunnelProtocol,
+}
+
+#[async_trait]
+impl TransportConnector for TunnelProxyConnector {
+    type Stream = SslStream<TcpStream>;
+
+    async fn connect(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+    ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+        let StreamAndInfo(mut tcp_stream, remote_address) = connect_tcp(
+            &self.dns_resolver,
+            connection_params,
+            &self.proxy_host,
+            self.proxy_port,
+        )
+        .await?;
+
+        log::debug!(
+            "connecting to {} proxy {}:{}",
+            self.protocol.name(),
+            self.proxy_host,
+            self.proxy_port
+        );
+
+        // Like the direct connector, the TCP-level target is the SNI host.
+        let target_host = &connection_params.sni;
+        let target_port = connection_params.port;
+        match &self.protocol {
+            TunnelProtocol::Socks5 { auth } => {
+                socks5_handshake(&mut tcp_stream, target_host, target_port, auth.as_ref()).await?
+            }
+            TunnelProtocol::HttpConnect { auth } => {
+                http_connect_handshake(&mut tcp_stream, target_host, target_port, auth.as_ref())
+                    .await?
+            }
+        }
+
+        let tls_stream = connect_tls(tcp_stream, connection_params, alpn).await?;
+
+        Ok(StreamAndInfo(
+            tls_stream,
+            ConnectionInfo {
+                route_type: self.protocol.route_type(),
+                ..remote_address
+            },
+        ))
+    }
+}
+
+impl TunnelProxyConnector {
+    pub fn new(
+        dns_resolver: DnsResolver,
+        (proxy_host, proxy_port): (&str, NonZeroU16),
+        protocol: TunnelProtocol,
+    ) -> Self {
+        Self {
+            dns_resolver,
+            proxy_host: proxy_host.into(),
+            proxy_port,
+            protocol,
+        }
+    }
+
+    pub fn set_proxy(&mut self, (host, port): (&str, NonZeroU16), protocol: TunnelProtocol) {
+        self.proxy_host = host.into();
+        self.proxy_port = port;
+        self.protocol = protocol;
+    }
+}
+
+/// Logs the underlying I/O error and reports a failed proxy handshake.
+fn proxy_io_error(e: std::io::Error) -> TransportConnectError {
+    log::info!("I/O error during proxy handshake: {}", e.kind());
+    TransportConnectError::ProxyProtocol
+}
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_AUTH_NONE: u8 = 0x00;
+const SOCKS5_AUTH_USERNAME_PASSWORD: u8 = 0x02;
+const SOCKS5_USERNAME_PASSWORD_VERSION: u8 = 0x01;
+const SOCKS5_USERNAME_PASSWORD_SUCCESS: u8 = 0x00;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_RESERVED: u8 = 0x00;
+const SOCKS5_ADDR_IPV4: u8 = 0x01;
+const SOCKS5_ADDR_DOMAIN: u8 = 0x03;
+const SOCKS5_ADDR_IPV6: u8 = 0x04;
+const SOCKS5_REPLY_SUCCEEDED: u8 = 0x00;
+
+/// Performs the client side of a SOCKS5 `CONNECT` handshake on `stream`.
+///
+/// Hostnames are sent as-is (address type `DOMAINNAME`) so that they are
+/// resolved by the proxy; IP literals are sent as addresses.
+async fn socks5_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target_host: &str,
+    target_port: NonZeroU16,
+    auth: Option<&ProxyAuth>,
+) -> Result<(), TransportConnectError> {
+    // Method negotiation.
+    let methods: &[u8] = match auth {
+        None => &[SOCKS5_AUTH_NONE],
+        Some(_) => &[SOCKS5_AUTH_NONE, SOCKS5_AUTH_USERNAME_PASSWORD],
+    };
+    let mut greeting = vec![SOCKS5_VERSION, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await.map_err(proxy_io_error)?;
+
+    let mut method_selection = [0; 2];
+    stream
+        .read_exact(&mut method_selection)
+        .await
+        .map_err(proxy_io_error)?;
+    match (method_selection, auth) {
+        ([SOCKS5_VERSION, SOCKS5_AUTH_NONE], _) => {}
+        ([SOCKS5_VERSION, SOCKS5_AUTH_USERNAME_PASSWORD], Some(auth)) => {
+            socks5_authenticate(stream, auth).await?
+        }
+        ([SOCKS5_VERSION, method], _) => {
+            log::info!("SOCKS5 proxy selected unsupported authentication method {method:#04x}");
+            return Err(TransportConnectError::ProxyProtocol);
+        }
+        ([version, _], _) => {
+            log::info!("SOCKS5 proxy replied with unexpected version {version}");
+            return Err(TransportConnectError::ProxyProtocol);
+        }
+    }
+
+    // The CONNECT request itself.
+    let mut request = vec![SOCKS5_VERSION, SOCKS5_CMD_CONNECT, SOCKS5_RESERVED];
+    match target_host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            request.push(SOCKS5_ADDR_IPV4);
+            request.extend_from_slice(&v4.octets());
+        }
+        Ok(IpAddr::V6(v6)) => {
+            request.push(SOCKS5_ADDR_IPV6);
+            request.extend_from_slice(&v6.octets());
+        }
+        Err(_) => {
+            let len = u8::try_from(target_host.len())
+                .map_err(|_| TransportConnectError::InvalidConfiguration)?;
+            request.push(SOCKS5_ADDR_DOMAIN);
+            request.push(len);
+            request.extend_from_slice(target_host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&target_port.get().to_be_bytes());
+    stream.write_all(&request).await.map_err(proxy_io_error)?;
+
+    let mut reply = [0; 4];
+    stream
+        .read_exact(&mut reply)
+        .await
+        .map_err(proxy_io_error)?;
+    let [version, reply_code, _reserved, address_type] = reply;
+    if version != SOCKS5_VERSION {
+        log::info!("SOCKS5 proxy replied with unexpected version {version}");
+        return Err(TransportConnectError::ProxyProtocol);
+    }
+    if reply_code != SOCKS5_REPLY_SUCCEEDED {
+        log::info!("SOCKS5 proxy refused to connect: reply code {reply_code:#04x}");
+        return Err(TransportConnectError::ProxyProtocol);
+    }
+
+    // Skip over the bound address and port, which we have no use for.
+    let bound_address_len: usize = match address_type {
+        SOCKS5_ADDR_IPV4 => 4,
+        SOCKS5_ADDR_IPV6 => 16,
+        SOCKS5_ADDR_DOMAIN => stream.read_u8().await.map_err(proxy_io_error)?.into(),
+        _ => {
+            log::info!("SOCKS5 proxy replied with unknown address type {address_type}");
+            return Err(TransportConnectError::ProxyProtocol);
+        }
+    };
+    let mut bound_address_and_port = vec![0; bound_address_len + 2];
+    stream
+        .read_exact(&mut bound_address_and_port)
+        .await
+        .map_err(proxy_io_error)?;
+
+    Ok(())
+}
+
+/// Performs username/password sub-negotiation as described in RFC 1929.
+async fn socks5_authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    ProxyAuth { username, password }: &ProxyAuth,
+) -> Result<(), TransportConnectError> {
+    let username_len =
+        u8::try_from(username.len()).map_err(|_| TransportConnectError::InvalidConfiguration)?;
+    let password_len =
+        u8::try_from(password.len()).map_err(|_| TransportConnectError::InvalidConfiguration)?;
+
+    let mut request = vec![SOCKS5_USERNAME_PASSWORD_VERSION, username_len];
+    request.extend_from_slice(username.as_bytes());
+    request.push(password_len);
+    request.extend_from_slice(password.as_bytes());
+    stream.write_all(&request).await.map_err(proxy_io_error)?;
+
+    let mut reply = [0; 2];
+    stream
+        .read_exact(&mut reply)
+        .await
+        .map_err(proxy_io_error)?;
+    match reply {
+        [SOCKS5_USERNAME_PASSWORD_VERSION, SOCKS5_USERNAME_PASSWORD_SUCCESS] => Ok(()),
+        [_, status] => {
+            log::info!("SOCKS5 proxy rejected credentials: status {status:#04x}");
+            Err(TransportConnectError::ProxyProtocol)
+        }
+    }
+}
+
+/// Upper bound on the size of the proxy's response to a `CONNECT` request.
+const MAX_HTTP_CONNECT_RESPONSE_HEAD_LEN: usize = 8 * 1024;
+
+/// Sends an HTTP `CONNECT` request on `stream` and waits for a successful
+/// response.
+async fn http_connect_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target_host: &str,
+    target_port: NonZeroU16,
+    auth: Option<&ProxyAuth>,
+) -> Result<(), TransportConnectError> {
+    let authority = match target_host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => format!("[{target_host}]:{target_port}"),
+        Ok(IpAddr::V4(_)) | Err(_) => format!("{target_host}:{target_port}"),
+    };
+
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some(ProxyAuth { username, password }) = auth {
+        let header_value = basic_authorization(username, password);
+        request.push_str("Proxy-Authorization: ");
+        request.push_str(header_value.to_str().expect("base64 is ASCII"));
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(proxy_io_error)?;
+
+    // Read the response one byte at a time so that nothing past the end of the
+    // headers is consumed; everything after that belongs to the TLS session.
+    let mut head = Vec::with_capacity(128);
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HTTP_CONNECT_RESPONSE_HEAD_LEN {
+            log::info!("HTTP proxy response headers are too long");
+            return Err(TransportConnectError::ProxyProtocol);
+        }
+        head.push(stream.read_u8().await.map_err(proxy_io_error)?);
+    }
+
+    let status_line = head
+        .split(|b| *b == b'\n')
+        .next()
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .unwrap_or_default()
+        .trim_end();
+    let status = match status_line.split(' ').collect::<Vec<_>>()[..] {
+        [version, status, ..] if version.starts_with("HTTP/1.") => status.parse::<u16>().ok(),
+        _ => None,
+    };
+    match status {
+        Some(200..=299) => Ok(()),
+        Some(status) => {
+            log::info!("HTTP proxy refused to connect: status {status}");
+            Err(TransportConnectError::ProxyProtocol)
+        }
+        None => {
+            log::info!("HTTP proxy sent a malformed response");
+            Err(TransportConnectError::ProxyProtocol)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testutil {
+    use std::future::Future;
+    use std::net::{Ipv6Addr, SocketAddr};
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    /// Starts a SOCKS5 or HTTP CONNECT proxy listening on `::1`.
+    ///
+    /// Requires that clients ask for `upstream_host` by name and present `auth`
+    /// if it's set, then proxies the connection to `upstream_addr`.
+    pub(crate) fn localhost_tunnel_proxy(
+        protocol: TunnelProtocol,
+        upstream_host: &'static str,
+        upstream_addr: SocketAddr,
+    ) -> (SocketAddr, impl Future<Output = ()>) {
+        let listener = std::net::TcpListener::bind((Ipv6Addr::LOCALHOST, 0)).expect("can bind");
+        listener.set_nonblocking(true).expect("can set nonblocking");
+        let listen_addr = listener.local_addr().expect("is bound to local addr");
+        let tcp_listener = tokio::net::TcpListener::from_std(listener).expect("can use std socket");
+        let proxy = async move {
+            loop {
+                let (mut input_stream, _remote_addr) =
+                    tcp_listener.accept().await.expect("incoming connection");
+
+                let (host, port) = match &protocol {
+                    TunnelProtocol::Socks5 { auth } => {
+                        accept_socks5(&mut input_stream, auth.as_ref()).await
+                    }
+                    TunnelProtocol::HttpConnect { auth } => {
+                        accept_http_connect(&mut input_stream, auth.as_ref()).await
+                    }
+                };
+                assert_eq!(host, upstream_host);
+                assert_eq!(port, upstream_addr.port());
+
+                let mut upstream_stream = TcpStream::connect(upstream_addr)
+                    .await
+                    .expect("can connect to upstream");
+                tokio::io::copy_bidirectional(&mut input_stream, &mut upstream_stream)
+                    .await
+                    .expect("can proxy");
+            }
+        };
+
+        (listen_addr, proxy)
+    }
+
+    async fn accept_socks5(stream: &mut TcpStream, auth: Option<&ProxyAuth>) -> (String, u16) {
+        let mut greeting = [0; 2];
+        stream.read_exact(&mut greeting).await.expect("can read");
+        assert_eq!(greeting[0], SOCKS5_VERSION);
+        let mut methods = vec![0; greeting[1].into()];
+        stream.read_exact(&mut methods).await.expect("can read");
+
+        match auth {
+            None => {
+                assert!(methods.contains(&SOCKS5_AUTH_NONE), "{methods:?}");
+                stream
+                    .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NONE])
+                    .await
+                    .expect("can write");
+            }
+            Some(ProxyAuth { username, password }) => {
+                assert!(
+                    methods.contains(&SOCKS5_AUTH_USERNAME_PASSWORD),
+                    "{methods:?}"
+                );
+                stream
+                    .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_USERNAME_PASSWORD])
+                    .await
+                    .expect("can write");
+
+                let version = stream.read_u8().await.expect("can read");
+                assert_eq!(version, SOCKS5_USERNAME_PASSWORD_VERSION);
+                let mut received_username = vec![0; stream.read_u8().await.unwrap().into()];
+                stream.read_exact(&mut received_username).await.unwrap();
+                let mut received_password = vec![0; stream.read_u8().await.unwrap().into()];
+                stream.read_exact(&mut received_password).await.unwrap();
+                assert_eq!(received_username, username.as_bytes());
+                assert_eq!(received_password, password.as_bytes());
+
+                stream
+                    .write_all(&[
+                        SOCKS5_USERNAME_PASSWORD_VERSION,
+                        SOCKS5_USERNAME_PASSWORD_SUCCESS,
+                    ])
+                    .await
+                    .expect("can write");
+            }
+        }
+
+        let mut request = [0; 4];
+        stream.read_exact(&mut request).await.expect("can read");
+        assert_eq!(
+            request,
+            [
+                SOCKS5_VERSION,
+                SOCKS5_CMD_CONNECT,
+                SOCKS5_RESERVED,
+                SOCKS5_ADDR_DOMAIN
+            ],
+            "host should be sent by name"
+        );
+        let mut host = vec![0; stream.read_u8().await.expect("can read").into()];
+        stream.read_exact(&mut host).await.expect("can read");
+        let port = stream.read_u16().await.expect("can read");
+
+        stream
+            .write_all(&[
+                SOCKS5_VERSION,
+                SOCKS5_REPLY_SUCCEEDED,
+                SOCKS5_RESERVED,
+                SOCKS5_ADDR_IPV4,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ])
+            .await
+            .expect("can write");
+
+        (String::from_utf8(host).expect("host is UTF-8"), port)
+    }
+
+    async fn accept_http_connect(
+        stream: &mut TcpStream,
+        auth: Option<&ProxyAuth>,
+    ) -> (String, u16) {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.expect("can read"));
+        }
+        let head = String::from_utf8(head).expect("request is UTF-8");
+        let mut lines = head.lines();
+
+        let request_line = lines.next().expect("has request line");
+        let authority = assert_matches::assert_matches!(
+            request_line.split(' ').collect::<Vec<_>>()[..],
+            ["CONNECT", authority, "HTTP/1.1"] => authority
+        );
+
+        let authorization = lines.find_map(|line| line.strip_prefix("Proxy-Authorization: "));
+        assert_eq!(
+            authorization,
+            auth.map(|ProxyAuth { username, password }| basic_authorization(username, password))
+                .as_ref()
+                .map(|value| value.to_str().unwrap())
+        );
+
+        stream
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .await
+            .expect("can write");
+
+        let (host, port) = authority.rsplit_once(':').expect("has port");
+        (host.to_owned(), port.parse().expect("valid port"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::net::Ipv6Addr;
+
+    use assert_matches::assert_matches;
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::testutil::*;
+    use super::*;
+    use crate::infra::certs::RootCertificates;
+    use crate::infra::dns::lookup_result::LookupResult;
+    use crate::infra::tcp_ssl::testutil::*;
+    use crate::infra::HttpRequestDecoratorSeq;
+    use crate::timeouts::TimeoutConfig;
+
+    const TARGET_PORT: NonZeroU16 = nonzero_ext::nonzero!(443u16);
+
+    fn test_auth() -> ProxyAuth {
+        ProxyAuth {
+            username: "user".to_owned(),
+            password: "hunter2".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_through_socks5_proxy() {
+        connect_through_tunnel_proxy(TunnelProtocol::Socks5 { auth: None }).await
+    }
+
+    #[tokio::test]
+    async fn connect_through_socks5_proxy_with_auth() {
+        connect_through_tunnel_proxy(TunnelProtocol::Socks5 {
+            auth: Some(test_auth()),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn connect_through_http_connect_proxy() {
+        connect_through_tunnel_proxy(TunnelProtocol::HttpConnect { auth: None }).await
+    }
+
+    #[tokio::test]
+    async fn connect_through_http_connect_proxy_with_auth() {
+        connect_through_tunnel_proxy(TunnelProtocol::HttpConnect {
+            auth: Some(test_auth()),
+        })
+        .await
+    }
+
+    async fn connect_through_tunnel_proxy(protocol: TunnelProtocol) {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let (proxy_addr, proxy) = localhost_tunnel_proxy(protocol.clone(), SERVER_HOSTNAME, addr);
+        let _proxy_handle = tokio::spawn(proxy);
+
+        // Only the proxy's name can be resolved locally; the server's name has
+        // to be resolved by the proxy.
+        let connector = TunnelProxyConnector::new(
+            DnsResolver::new_with_static_fallback(HashMap::from([(
+                PROXY_HOSTNAME,
+                LookupResult::localhost(),
+            )])),
+            (PROXY_HOSTNAME, proxy_addr.port().try_into().unwrap()),
+            protocol.clone(),
+        );
+
+        let connection_params = ConnectionParams {
+            route_type: RouteType::Test,
+            sni: SERVER_HOSTNAME.into(),
+            host: "localhost".to_string().into(),
+            port: addr.port().try_into().expect("bound port"),
+            http_request_decorator: HttpRequestDecoratorSeq::default(),
+            certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+            event_observer: None,
+            timeouts: TimeoutConfig::default(),
+            tls_session_cache: None,
+        };
+
+        let StreamAndInfo(stream, info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+
+        assert_eq!(
+            info,
+            ConnectionInfo {
+                address: url::Host::Ipv6(Ipv6Addr::LOCALHOST),
+                dns_source: crate::infra::DnsSource::Static,
+                route_type: protocol.route_type(),
+            }
+        );
+
+        make_http_request_response_over(stream).await;
+    }
+
+    #[tokio::test]
+    async fn socks5_sends_ip_literal_as_address() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = async move {
+            let mut greeting = [0; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server
+                .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NONE])
+                .await
+                .unwrap();
+
+            let mut request = [0; 10];
+            server.read_exact(&mut request).await.unwrap();
+            server
+                .write_all(&[
+                    SOCKS5_VERSION,
+                    SOCKS5_REPLY_SUCCEEDED,
+                    SOCKS5_RESERVED,
+                    SOCKS5_ADDR_IPV4,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                ])
+                .await
+                .unwrap();
+            request
+        };
+
+        let (result, request) = tokio::join!(
+            socks5_handshake(&mut client, "192.0.2.1", TARGET_PORT, None),
+            server
+        );
+        assert_matches!(result, Ok(()));
+        assert_eq!(
+            request,
+            [
+                SOCKS5_VERSION,
+                SOCKS5_CMD_CONNECT,
+                SOCKS5_RESERVED,
+                SOCKS5_ADDR_IPV4,
+                192,
+                0,
+                2,
+                1,
+                0x01,
+                0xbb
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn socks5_no_acceptable_auth_method() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = async move {
+            let mut greeting = [0; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            // 0xFF: no acceptable methods
+            server.write_all(&[SOCKS5_VERSION, 0xFF]).await.unwrap();
+        };
+
+        let (result, ()) = tokio::join!(
+            socks5_handshake(&mut client, SERVER_HOSTNAME, TARGET_PORT, None),
+            server
+        );
+        assert_matches!(result, Err(TransportConnectError::ProxyProtocol));
+    }
+
+    #[tokio::test]
+    async fn socks5_connect_refused() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = async move {
+            let mut greeting = [0; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server
+                .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NONE])
+                .await
+                .unwrap();
+
+            let mut request = vec![0; 7 + SERVER_HOSTNAME.len()];
+            server.read_exact(&mut request).await.unwrap();
+            // 0x04: host unreachable
+            server
+                .write_all(&[SOCKS5_VERSION, 0x04, SOCKS5_RESERVED, SOCKS5_ADDR_IPV4])
+                .await
+                .unwrap();
+        };
+
+        let (result, ()) = tokio::join!(
+            socks5_handshake(&mut client, SERVER_HOSTNAME, TARGET_PORT, None),
+            server
+        );
+        assert_matches!(result, Err(TransportConnectError::ProxyProtocol));
+    }
+
+    #[tokio::test]
+    async fn http_connect_proxy_authentication_required() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = async move {
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(server.read_u8().await.unwrap());
+            }
+            server
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        };
+
+        let (result, ()) = tokio::join!(
+            http_connect_handshake(&mut client, SERVER_HOSTNAME, TARGET_PORT, None),
+            server
+        );
+        assert_matches!(result, Err(TransportConnectError::ProxyProtocol));
+    }
+
+    #[tokio::test]
+    async fn http_connect_does_not_consume_past_headers() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = async move {
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(server.read_u8().await.unwrap());
+            }
+            server
+                .write_all(b"HTTP/1.1 200 OK\r\nX-Extra: 1\r\n\r\ntunneled")
+                .await
+                .unwrap();
+            String::from_utf8(head).unwrap()
+        };
+
+        let (result, request) = tokio::join!(
+            http_connect_handshake(&mut client, SERVER_HOSTNAME, TARGET_PORT, None),
+            server
+        );
+        assert_matches!(result, Ok(()));
+        assert_eq!(
+            request,
+            format!(
+                "CONNECT {SERVER_HOSTNAME}:443 HTTP/1.1\r\nHost: {SERVER_HOSTNAME}:443\r\n\r\n"
+            )
+        );
+
+        let mut rest = [0; 8];
+        client.read_exact(&mut rest).await.unwrap();
+        assert_eq!(&rest, b"tunneled");
+    }
+}
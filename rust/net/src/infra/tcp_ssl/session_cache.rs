@@ -0,0 +1,265 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, Weak};
+
+use boring::ssl::{ConnectConfiguration, SslConnector, SslSession, SslSessionCacheMode};
+
+use crate::infra::errors::TransportConnectError;
+use crate::infra::tcp_ssl::ssl_connector_builder;
+use crate::infra::{Alpn, ConnectionParams, RouteKey};
+
+/// Per-route TLS state that is kept across connections to speed up reconnects.
+///
+/// For each route, this holds on to the configured [`SslConnector`], so that the root
+/// certificates don't have to be loaded again, and the most recent session the server offered
+/// for resumption (e.g. a TLS 1.3 session ticket). Each session is used at most once, as is
+/// recommended for TLS 1.3 tickets; a successful connection usually receives a new one.
+///
+/// At most `capacity` routes are kept, evicting the least recently used. Clones share the same
+/// cache.
+#[derive(Clone)]
+pub struct TlsSessionCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+struct CacheInner {
+    capacity: NonZeroUsize,
+    routes: HashMap<RouteKey, RouteTlsState>,
+    /// Routes in order of use, least recent first.
+    order: VecDeque<RouteKey>,
+    next_connector_id: u64,
+}
+
+struct RouteTlsState {
+    /// Distinguishes connectors built for the same route, so that a session is never paired with
+    /// a connector other than the one it came from.
+    connector_id: u64,
+    connector: SslConnector,
+    session: Option<SslSession>,
+}
+
+impl std::fmt::Debug for TlsSessionCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsSessionCache").finish_non_exhaustive()
+    }
+}
+
+impl TlsSessionCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner {
+                capacity,
+                routes: HashMap::new(),
+                order: VecDeque::new(),
+                next_connector_id: 0,
+            })),
+        }
+    }
+
+    /// Forgets everything saved for all routes.
+    ///
+    /// This should be called when the network changes, since the saved sessions are unlikely to
+    /// be useful afterwards.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().expect("not poisoned");
+        inner.routes.clear();
+        inner.order.clear();
+    }
+
+    /// Produces the TLS configuration for a new connection to the given route, resuming the last
+    /// session if there is one.
+    pub(super) fn configure(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+    ) -> Result<ConnectConfiguration, TransportConnectError> {
+        let route = RouteKey::new(connection_params, alpn);
+        let (connector, session) = {
+            let mut inner = self.inner.lock().expect("not poisoned");
+            inner.touch(&route);
+            match inner.routes.get_mut(&route) {
+                Some(state) => (state.connector.clone(), state.session.take()),
+                None => {
+                    let connector_id = inner.next_connector_id;
+                    inner.next_connector_id += 1;
+                    let connector = self.build_connector(connection_params, alpn, connector_id)?;
+                    inner.insert(
+                        route,
+                        RouteTlsState {
+                            connector_id,
+                            connector: connector.clone(),
+                            session: None,
+                        },
+                    );
+                    (connector, None)
+                }
+            }
+        };
+
+        let mut config = connector.configure()?;
+        if let Some(session) = session {
+            log::debug!("attempting to resume TLS session");
+            // SAFETY: `session` was received on a connection configured by `connector`, so it
+            // belongs to the same SSL context as `config`.
+            unsafe { config.set_session(&session)? };
+        }
+        Ok(config)
+    }
+
+    fn build_connector(
+        &self,
+        connection_params: &ConnectionParams,
+        alpn: Alpn,
+        connector_id: u64,
+    ) -> Result<SslConnector, TransportConnectError> {
+        let mut builder =
+            ssl_connector_builder(&connection_params.certs, &connection_params.sni, Some(alpn))?;
+        builder.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+
+        let inner: Weak<Mutex<CacheInner>> = Arc::downgrade(&self.inner);
+        let route = RouteKey::new(connection_params, alpn);
+        builder.set_new_session_callback(move |_ssl, session| {
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+            let mut inner = inner.lock().expect("not poisoned");
+            match inner.routes.get_mut(&route) {
+                Some(state) if state.connector_id == connector_id => state.session = Some(session),
+                // The route was evicted or cleared since this connection started.
+                _ => {}
+            }
+        });
+        Ok(builder.build())
+    }
+
+    #[cfg(test)]
+    fn route_count(&self) -> usize {
+        self.inner.lock().expect("not poisoned").routes.len()
+    }
+}
+
+impl CacheInner {
+    /// Marks `route` as the most recently used.
+    fn touch(&mut self, route: &RouteKey) {
+        if let Some(index) = self.order.iter().position(|r| r == route) {
+            let route = self.order.remove(index).expect("just found");
+            self.order.push_back(route);
+        }
+    }
+
+    fn insert(&mut self, route: RouteKey, state: RouteTlsState) {
+        while self.routes.len() >= self.capacity.get() {
+            let Some(evicted) = self.order.pop_front() else {
+                break;
+            };
+            self.routes.remove(&evicted);
+        }
+        self.order.push_back(route.clone());
+        self.routes.insert(route, state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use nonzero_ext::nonzero;
+
+    use super::*;
+    use crate::infra::certs::RootCertificates;
+    use crate::infra::dns::lookup_result::LookupResult;
+    use crate::infra::dns::DnsResolver;
+    use crate::infra::tcp_ssl::testutil::*;
+    use crate::infra::tcp_ssl::DirectConnector;
+    use crate::infra::{HttpRequestDecoratorSeq, RouteType, StreamAndInfo, TransportConnector};
+
+    fn params(host: &str, port: u16, cache: &TlsSessionCache) -> ConnectionParams {
+        ConnectionParams::new(
+            RouteType::Test,
+            SERVER_HOSTNAME,
+            host,
+            port.try_into().expect("nonzero"),
+            HttpRequestDecoratorSeq::default(),
+            RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        )
+        .with_tls_session_cache(cache.clone())
+    }
+
+    #[tokio::test]
+    async fn reconnect_resumes_session() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let connector = DirectConnector::new(DnsResolver::new_with_static_fallback(HashMap::from(
+            [(SERVER_HOSTNAME, LookupResult::localhost())],
+        )));
+        let cache = TlsSessionCache::new(nonzero!(4usize));
+        let connection_params = params(SERVER_HOSTNAME, addr.port(), &cache);
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert!(!stream.ssl().session_reused());
+        // The server sends its session ticket after the handshake, so read something first.
+        make_http_request_response_over(stream).await;
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert!(stream.ssl().session_reused());
+        make_http_request_response_over(stream).await;
+    }
+
+    #[tokio::test]
+    async fn clear_prevents_resumption() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let connector = DirectConnector::new(DnsResolver::new_with_static_fallback(HashMap::from(
+            [(SERVER_HOSTNAME, LookupResult::localhost())],
+        )));
+        let cache = TlsSessionCache::new(nonzero!(4usize));
+        let connection_params = params(SERVER_HOSTNAME, addr.port(), &cache);
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        make_http_request_response_over(stream).await;
+
+        cache.clear();
+        assert_eq!(cache.route_count(), 0);
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert!(!stream.ssl().session_reused());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_route() {
+        let cache = TlsSessionCache::new(nonzero!(2usize));
+        let a = params("a.signal.org.local", 443, &cache);
+        let b = params("b.signal.org.local", 443, &cache);
+        let c = params("c.signal.org.local", 443, &cache);
+
+        let _ = cache.configure(&a, Alpn::Http1_1).expect("valid");
+        let _ = cache.configure(&b, Alpn::Http1_1).expect("valid");
+        // Use `a` again so that `b` is the least recently used.
+        let _ = cache.configure(&a, Alpn::Http1_1).expect("valid");
+        let _ = cache.configure(&c, Alpn::Http1_1).expect("valid");
+
+        let inner = cache.inner.lock().expect("not poisoned");
+        assert!(inner.routes.contains_key(&RouteKey::new(&a, Alpn::Http1_1)));
+        assert!(!inner.routes.contains_key(&RouteKey::new(&b, Alpn::Http1_1)));
+        assert!(inner.routes.contains_key(&RouteKey::new(&c, Alpn::Http1_1)));
+    }
+}
@@ -24,11 +24,13 @@ use tungstenite::protocol::CloseFrame;
 use tungstenite::{http, Message};
 
 use crate::infra::errors::LogSafeDisplay;
+use crate::infra::events::ConnectionEvent;
 use crate::infra::reconnect::{ServiceConnector, ServiceStatus};
 use crate::infra::ws::error::{HttpFormatError, ProtocolError, SpaceError};
 use crate::infra::{
     Alpn, AsyncDuplexStream, ConnectionInfo, ConnectionParams, StreamAndInfo, TransportConnector,
 };
+use crate::timeouts::REQUEST_TIMEOUT;
 use crate::utils::timeout;
 
 pub mod error;
@@ -201,16 +203,18 @@ where
 {
     pub async fn send(&self, message: impl Into<Message>) -> Result<(), E> {
         run_and_update_status(&self.service_status, || {
-            async {
-                let mut guard = self.ws_sink.lock().await;
-                guard.send(message.into()).await?;
-                guard.flush().await?;
-                Ok(())
-            }
-            .map_err(|e: tungstenite::Error| WebSocketServiceError::from(e).into())
+            self.send_unchecked(message.into())
+                .map_err(|e| WebSocketServiceError::from(e).into())
         })
         .await
     }
+
+    /// Sends without checking or updating the service status.
+    async fn send_unchecked(&self, message: Message) -> Result<(), tungstenite::Error> {
+        let mut guard = self.ws_sink.lock().await;
+        guard.send(message).await?;
+        guard.flush().await
+    }
 }
 
 #[derive(Debug)]
@@ -247,7 +251,17 @@ where
                     _ = self.service_status.stopped() => Event::StopService,
                 } {
                     Event::SendKeepAlive => {
-                        self.ws_writer.send(Message::Ping(vec![])).await?;
+                        match self.ws_writer.send_unchecked(Message::Ping(vec![])).await {
+                            Ok(()) => {}
+                            Err(
+                                tungstenite::Error::ConnectionClosed
+                                | tungstenite::Error::AlreadyClosed,
+                            ) => {
+                                log::warn!("websocket connection was closed by the server");
+                                return Ok(NextOrClose::Close(None));
+                            }
+                            Err(e) => return Err(WebSocketServiceError::from(e).into()),
+                        }
                         self.last_keepalive_sent = Instant::now();
                         continue;
                     }
@@ -263,7 +277,10 @@ where
                 };
                 // now checking if whatever we've read from the stream is a message
                 let message = match maybe_message {
-                    None | Some(Err(tungstenite::Error::ConnectionClosed)) => {
+                    None
+                    | Some(Err(
+                        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed,
+                    )) => {
                         log::warn!("websocket connection was unexpectedly closed");
                         return Ok(NextOrClose::Close(None));
                     }
@@ -343,12 +360,27 @@ async fn connect_websocket<T: TransportConnector>(
         .http_request_decorator
         .decorate_request(request_builder);
 
-    let (ws_stream, _response) = tokio_tungstenite::client_async_with_config(
-        request_builder.body(()).expect("can get request body"),
-        ssl_stream,
-        Some(ws_config),
+    let upgrade_start = Instant::now();
+    let result = tokio::time::timeout(
+        connection_params.timeouts.websocket_upgrade,
+        tokio_tungstenite::client_async_with_config(
+            request_builder.body(()).expect("can get request body"),
+            ssl_stream,
+            Some(ws_config),
+        ),
     )
-    .await?;
+    .await;
+    connection_params.report_event(|| ConnectionEvent::WebSocketUpgrade {
+        route: connection_params.route_type,
+        host: connection_params.host.clone(),
+        duration: upgrade_start.elapsed(),
+        status: match &result {
+            Ok(Ok((_ws_stream, response))) => Some(response.status().as_u16()),
+            Ok(Err(tungstenite::Error::Http(response))) => Some(response.status().as_u16()),
+            Ok(Err(_)) | Err(_) => None,
+        },
+    });
+    let (ws_stream, _response) = result.map_err(|_elapsed| WebSocketConnectError::Timeout)??;
 
     Ok((ws_stream, remote_address))
 }
@@ -431,6 +463,7 @@ pub enum AttestedConnectionError {
     ClientConnection(attest::client_connection::Error),
     Sgx(attest::enclave::Error),
     WebSocket(WebSocketServiceError),
+    RequestTimedOut,
 }
 
 impl From<enclave::Error> for AttestedConnectionError {
@@ -458,12 +491,19 @@ pub type DefaultStream = tokio_boring::SslStream<tokio::net::TcpStream>;
 pub struct AttestedConnection<S> {
     websocket: WebSocketClient<S, WebSocketServiceError>,
     client_connection: ClientConnection,
+    request_timeout: Duration,
 }
 
 impl<S> AttestedConnection<S> {
     pub(crate) fn remote_address(&self) -> &url::Host {
         &self.websocket.connection_info.address
     }
+
+    /// Sets how long to wait for each message from the server, instead of [`REQUEST_TIMEOUT`].
+    pub(crate) fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
 }
 
 impl<S> AsMut<AttestedConnection<S>> for AttestedConnection<S> {
@@ -525,6 +565,7 @@ where
         Ok(Self {
             websocket,
             client_connection,
+            request_timeout: REQUEST_TIMEOUT,
         })
     }
 
@@ -562,7 +603,12 @@ where
     pub(crate) async fn receive_bytes(
         &mut self,
     ) -> Result<NextOrClose<Vec<u8>>, AttestedConnectionError> {
-        let received = self.websocket.receive().await?;
+        let received = timeout(
+            self.request_timeout,
+            AttestedConnectionError::RequestTimedOut,
+            self.websocket.receive().map_err(Into::into),
+        )
+        .await?;
         let received = match received {
             NextOrClose::Close(frame) => return Ok(NextOrClose::Close(frame)),
             NextOrClose::Next(t) => t.try_into_binary()?,
@@ -655,17 +701,17 @@ pub(crate) mod testutil {
 
     /// Response to an incoming frame.
     ///
-    /// Zero or one frames to reply with followed by an optional close.
+    /// Frames to reply with, in order, followed by an optional close.
     #[derive(Default)]
     pub(crate) struct AttestedServerOutput {
-        pub(crate) message: Option<Vec<u8>>,
+        pub(crate) messages: Vec<Vec<u8>>,
         pub(crate) close_after: Option<Option<CloseFrame<'static>>>,
     }
 
     impl AttestedServerOutput {
         pub(crate) fn message(contents: Vec<u8>) -> Self {
             Self {
-                message: Some(contents),
+                messages: vec![contents],
                 ..Default::default()
             }
         }
@@ -741,10 +787,10 @@ pub(crate) mod testutil {
 
             let AttestedServerOutput {
                 close_after,
-                message,
+                messages,
             } = on_message(received);
 
-            if let Some(payload) = message {
+            for payload in messages {
                 let mut outgoing = vec![0; payload.len() + 16 /* snow tag len */];
                 let written = server_transport
                     .write_message(&payload, &mut outgoing)
@@ -940,4 +986,31 @@ mod test {
             AttestedConnectionError::Protocol
         );
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn attested_connection_request_timeout() {
+        let (server, client) = fake_websocket().await;
+        // A server that never responds.
+        tokio::task::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            |_message| AttestedServerOutput::default(),
+        ));
+
+        let mut connection =
+            AttestedConnection::connect(websocket_test_client(client), |fake_attestation| {
+                assert_eq!(fake_attestation, FAKE_ATTESTATION);
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .unwrap()
+            .with_request_timeout(Duration::from_secs(5));
+
+        let start = Instant::now();
+        assert_matches!(
+            run_attested_interaction(&mut connection, ECHO_BYTES).await,
+            Err(AttestedConnectionError::RequestTimedOut)
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
 }
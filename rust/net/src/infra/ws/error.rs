@@ -8,8 +8,10 @@
 //! [`std::fmt::Display`] impl doesn't contain any user data.
 
 use std::borrow::Borrow;
+use std::time::Duration;
 
 use crate::infra::errors::{LogSafeDisplay, TransportConnectError};
+use crate::utils::rate_limit_retry_after;
 
 /// Errors that can occur when connecting a websocket.
 #[derive(Debug, thiserror::Error)]
@@ -33,6 +35,21 @@ impl std::fmt::Display for WebSocketConnectError {
 
 impl LogSafeDisplay for WebSocketConnectError {}
 
+impl WebSocketConnectError {
+    /// Returns how long to wait before trying again if the server rejected the connection because
+    /// the client is being rate limited.
+    pub(crate) fn rate_limit_retry_after(&self) -> Option<Duration> {
+        match self {
+            WebSocketConnectError::WebSocketError(tungstenite::Error::Http(response)) => {
+                rate_limit_retry_after(response.status(), response.headers())
+            }
+            WebSocketConnectError::Transport(_)
+            | WebSocketConnectError::Timeout
+            | WebSocketConnectError::WebSocketError(_) => None,
+        }
+    }
+}
+
 /// Mirror of [`tungstenite::error::Error`].
 ///
 /// Provides a user-data-free [`std::fmt::Display`] implementation.
@@ -18,7 +18,10 @@ use futures_util::future::try_join_all;
 use libsignal_svr3::{Backup, EvaluationResult, MaskedShareSet, Restore};
 use rand_core::CryptoRngCore;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::num::NonZeroU32;
+use std::time::Duration;
+use zeroize::Zeroizing;
 
 const MASKED_SHARE_SET_FORMAT: u8 = 0;
 
@@ -147,6 +150,10 @@ pub enum Error {
     DataMissing,
     /// Connect timed out
     ConnectionTimedOut,
+    /// Timed out waiting for the server to respond
+    RequestTimedOut,
+    /// Rate limited by the server; retry after {retry_after:?}
+    RateLimited { retry_after: Duration },
 }
 
 impl From<DeserializeError> for Error {
@@ -184,11 +191,15 @@ impl From<super::svr::Error> for Error {
     fn from(err: super::svr::Error) -> Self {
         use super::svr::Error as SvrError;
         match err {
-            SvrError::WebSocketConnect(inner) => Self::Connect(inner),
+            SvrError::WebSocketConnect(inner) => match inner.rate_limit_retry_after() {
+                Some(retry_after) => Self::RateLimited { retry_after },
+                None => Self::Connect(inner),
+            },
             SvrError::WebSocket(inner) => Self::Service(inner),
             SvrError::Protocol => Self::Protocol("General SVR protocol error".to_string()),
             SvrError::AttestationError(inner) => Self::AttestationError(inner),
             SvrError::ConnectionTimedOut => Self::ConnectionTimedOut,
+            SvrError::RequestTimedOut => Self::RequestTimedOut,
         }
     }
 }
@@ -217,6 +228,124 @@ pub trait PpssOps<S>: PpssSetup<S> {
     ) -> Result<EvaluationResult, Error>;
 
     async fn remove(connections: Self::Connections) -> Result<(), Error>;
+
+    /// Moves a secret backed up to a previous set of enclaves to the current one.
+    ///
+    /// The secret is restored using `share_set` from the enclaves produced by `connect_previous`,
+    /// then backed up again to `connections`, and the new share set is returned. If the backup
+    /// fails, `state` keeps the restored secret, so calling this again with the same `state` picks
+    /// up from there without connecting to the previous enclaves or using up another try.
+    async fn migrate_backup<ConnectPrevious>(
+        state: &mut MigrationState,
+        connect_previous: impl FnOnce() -> ConnectPrevious + Send,
+        connections: Self::Connections,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error>
+    where
+        ConnectPrevious: Future<Output = Result<Self::Connections, Error>> + Send;
+}
+
+/// Progress of a [`PpssOps::migrate_backup`] that hasn't finished yet.
+///
+/// Once the secret has been restored from the previous enclaves this holds on to it, so it should
+/// be kept no longer than needed to retry the migration.
+///
+/// The secret is zeroed when the state is dropped or the migration completes.
+#[derive(Default)]
+pub struct MigrationState {
+    restored_secret: Option<Zeroizing<[u8; 32]>>,
+}
+
+impl MigrationState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the secret has already been restored, i.e. the previous enclaves aren't needed.
+    pub fn has_restored_secret(&self) -> bool {
+        self.restored_secret.is_some()
+    }
+
+    async fn migrate(
+        &mut self,
+        steps: &mut impl MigrationSteps,
+    ) -> Result<OpaqueMaskedShareSet, Error> {
+        let secret = match &self.restored_secret {
+            Some(secret) => secret.clone(),
+            None => self
+                .restored_secret
+                .insert(Zeroizing::new(steps.restore_previous().await?))
+                .clone(),
+        };
+        let new_share_set = steps.backup(&secret).await?;
+        self.restored_secret = None;
+        Ok(new_share_set)
+    }
+}
+
+/// The enclave interactions of [`PpssOps::migrate_backup`], split out from the bookkeeping in
+/// [`MigrationState`].
+trait MigrationSteps {
+    async fn restore_previous(&mut self) -> Result<[u8; 32], Error>;
+    async fn backup(&mut self, secret: &[u8; 32]) -> Result<OpaqueMaskedShareSet, Error>;
+}
+
+struct EnclaveMigrationSteps<'a, Env, S, Connect, R>
+where
+    Env: PpssSetup<S>,
+{
+    connect_previous: Option<Connect>,
+    connections: Option<Env::Connections>,
+    password: &'a str,
+    share_set: Option<OpaqueMaskedShareSet>,
+    max_tries: NonZeroU32,
+    rng: &'a mut R,
+}
+
+impl<S, Env, Connect, ConnectFuture, R> MigrationSteps
+    for EnclaveMigrationSteps<'_, Env, S, Connect, R>
+where
+    S: AsyncDuplexStream + 'static,
+    Env: PpssSetup<S>,
+    Connect: FnOnce() -> ConnectFuture + Send,
+    ConnectFuture: Future<Output = Result<Env::Connections, Error>> + Send,
+    R: CryptoRngCore + Send,
+{
+    async fn restore_previous(&mut self) -> Result<[u8; 32], Error> {
+        let connect_previous = self
+            .connect_previous
+            .take()
+            .expect("the previous enclaves are only restored from once");
+        let share_set = self.share_set.take().expect("restored at most once");
+        let previous = connect_previous().await?;
+        let restored =
+            <Env as PpssOps<S>>::restore(previous, self.password, share_set, &mut *self.rng)
+                .await?;
+        Ok(restored.value)
+    }
+
+    async fn backup(&mut self, secret: &[u8; 32]) -> Result<OpaqueMaskedShareSet, Error> {
+        let connections = self.connections.take().expect("backed up at most once");
+        <Env as PpssOps<S>>::backup(
+            connections,
+            self.password,
+            *secret,
+            self.max_tries,
+            &mut *self.rng,
+        )
+        .await
+    }
+}
+
+impl std::fmt::Debug for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MigrationState")
+            .field("has_restored_secret", &self.has_restored_secret())
+            .finish()
+    }
 }
 
 #[async_trait]
@@ -276,6 +405,29 @@ impl<S: AsyncDuplexStream + 'static, Env: PpssSetup<S>> PpssOps<S> for Env {
         let _responses = collect_responses(results, addresses)?;
         Ok(())
     }
+
+    async fn migrate_backup<ConnectPrevious>(
+        state: &mut MigrationState,
+        connect_previous: impl FnOnce() -> ConnectPrevious + Send,
+        connections: Self::Connections,
+        password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error>
+    where
+        ConnectPrevious: Future<Output = Result<Self::Connections, Error>> + Send,
+    {
+        let mut steps = EnclaveMigrationSteps::<Self, S, _, _> {
+            connect_previous: Some(connect_previous),
+            connections: Some(connections),
+            password,
+            share_set: Some(share_set),
+            max_tries,
+            rng,
+        };
+        state.migrate(&mut steps).await
+    }
 }
 
 fn collect_responses<'a>(
@@ -294,6 +446,7 @@ fn collect_responses<'a>(
 #[cfg(test)]
 mod test {
     use super::*;
+    use assert_matches::assert_matches;
 
     fn new_empty_share_set() -> OpaqueMaskedShareSet {
         OpaqueMaskedShareSet {
@@ -331,4 +484,87 @@ mod test {
             DeserializeError::BadVersion(_),
         ));
     }
+
+    #[test]
+    fn migration_state_does_not_debug_print_secret() {
+        let state = MigrationState {
+            restored_secret: Some(Zeroizing::new([0xaa; 32])),
+        };
+        assert_eq!(
+            format!("{state:?}"),
+            "MigrationState { has_restored_secret: true }"
+        );
+    }
+
+    /// Stands in for the enclaves, counting how often each step runs.
+    #[derive(Default)]
+    struct FakeMigrationSteps {
+        restores: usize,
+        backups: Vec<[u8; 32]>,
+        fail_backups: bool,
+    }
+
+    const MIGRATED_SECRET: [u8; 32] = [0x55; 32];
+
+    impl MigrationSteps for FakeMigrationSteps {
+        async fn restore_previous(&mut self) -> Result<[u8; 32], Error> {
+            self.restores += 1;
+            Ok(MIGRATED_SECRET)
+        }
+
+        async fn backup(&mut self, secret: &[u8; 32]) -> Result<OpaqueMaskedShareSet, Error> {
+            self.backups.push(*secret);
+            if self.fail_backups {
+                return Err(Error::ConnectionTimedOut);
+            }
+            Ok(new_empty_share_set())
+        }
+    }
+
+    #[tokio::test]
+    async fn migration_restores_then_backs_up() {
+        let mut state = MigrationState::new();
+        let mut steps = FakeMigrationSteps::default();
+        state.migrate(&mut steps).await.expect("migrated");
+        assert_eq!(steps.restores, 1);
+        assert_eq!(steps.backups, [MIGRATED_SECRET]);
+        assert!(!state.has_restored_secret());
+    }
+
+    #[tokio::test]
+    async fn migration_resumes_after_failed_backup() {
+        let mut state = MigrationState::new();
+        let mut steps = FakeMigrationSteps {
+            fail_backups: true,
+            ..Default::default()
+        };
+        assert_matches!(
+            state.migrate(&mut steps).await,
+            Err(Error::ConnectionTimedOut)
+        );
+        assert!(state.has_restored_secret());
+
+        steps.fail_backups = false;
+        state.migrate(&mut steps).await.expect("migrated");
+        // The saved secret was used instead of contacting the previous enclaves again.
+        assert_eq!(steps.restores, 1);
+        assert_eq!(steps.backups, [MIGRATED_SECRET, MIGRATED_SECRET]);
+        assert!(!state.has_restored_secret());
+    }
+
+    #[test]
+    fn rejected_connection_with_retry_after_is_rate_limited() {
+        let response = http::Response::builder()
+            .status(http::StatusCode::TOO_MANY_REQUESTS)
+            .header(http::header::RETRY_AFTER, "30")
+            .body(None)
+            .expect("valid response");
+        let err = Error::from(crate::svr::Error::WebSocketConnect(
+            WebSocketConnectError::WebSocketError(tungstenite::Error::Http(response)),
+        ));
+        assert!(
+            matches!(err, Error::RateLimited { retry_after } if retry_after == Duration::from_secs(30)),
+            "{err:?}"
+        );
+    }
 }
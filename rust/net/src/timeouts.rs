@@ -23,6 +23,9 @@ pub const WS_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
 /// Maximum time of incoming packets inactivity allowed on a WebSocket connection
 pub const WS_MAX_IDLE_INTERVAL: Duration = Duration::from_secs(45);
 
+/// How long a connection established ahead of time is kept around waiting to be used
+pub const PRECONNECT_EXPIRATION: Duration = Duration::from_secs(10);
+
 /// Timeout for a connect operation that attempts one route
 /// (this includes DNS resolution, TCP connection, and SSL handshake)
 pub const ONE_ROUTE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
@@ -50,3 +53,90 @@ pub const CONNECTION_ROUTE_COOLDOWN_INTERVALS: [Duration; 8] = [
 
 /// Maximum value of a coolduwn interval between connection attempts
 pub const CONNECTION_ROUTE_MAX_COOLDOWN: Duration = Duration::from_secs(64);
+
+/// Timeout for a single request sent over an established connection
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeouts for the individual steps of establishing a connection and using it.
+///
+/// The [`Default`] value matches the constants in this module. The per-step timeouts for TCP,
+/// TLS and the WebSocket upgrade default to [`ONE_ROUTE_CONNECTION_TIMEOUT`], so unless they are
+/// set explicitly, each step is only bounded by the timeout for the whole route.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeoutConfig {
+    /// Timeout for a TCP connection attempt to a single IP address
+    pub tcp_connect: Duration,
+    /// Timeout for a TLS handshake, including the one with a TLS proxy
+    pub tls_handshake: Duration,
+    /// Timeout for the HTTP exchange that upgrades a connection to a WebSocket
+    pub websocket_upgrade: Duration,
+    /// Timeout for a connect operation that attempts one route
+    pub one_route_connection: Duration,
+    /// Timeout for a connect operation that attempts multiple routes
+    pub multi_route_connection: Duration,
+    /// Timeout for a single request sent over an established connection
+    pub request: Duration,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            tcp_connect: ONE_ROUTE_CONNECTION_TIMEOUT,
+            tls_handshake: ONE_ROUTE_CONNECTION_TIMEOUT,
+            websocket_upgrade: ONE_ROUTE_CONNECTION_TIMEOUT,
+            one_route_connection: ONE_ROUTE_CONNECTION_TIMEOUT,
+            multi_route_connection: MULTI_ROUTE_CONNECTION_TIMEOUT,
+            request: REQUEST_TIMEOUT,
+        }
+    }
+}
+
+impl TimeoutConfig {
+    pub fn with_tcp_connect(mut self, timeout: Duration) -> Self {
+        self.tcp_connect = timeout;
+        self
+    }
+
+    pub fn with_tls_handshake(mut self, timeout: Duration) -> Self {
+        self.tls_handshake = timeout;
+        self
+    }
+
+    pub fn with_websocket_upgrade(mut self, timeout: Duration) -> Self {
+        self.websocket_upgrade = timeout;
+        self
+    }
+
+    pub fn with_one_route_connection(mut self, timeout: Duration) -> Self {
+        self.one_route_connection = timeout;
+        self
+    }
+
+    pub fn with_multi_route_connection(mut self, timeout: Duration) -> Self {
+        self.multi_route_connection = timeout;
+        self
+    }
+
+    pub fn with_request(mut self, timeout: Duration) -> Self {
+        self.request = timeout;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builder_overrides_only_the_given_timeout() {
+        let config = TimeoutConfig::default().with_tls_handshake(Duration::from_secs(5));
+        assert_eq!(
+            config,
+            TimeoutConfig {
+                tls_handshake: Duration::from_secs(5),
+                ..TimeoutConfig::default()
+            }
+        );
+        assert_eq!(config.one_route_connection, ONE_ROUTE_CONNECTION_TIMEOUT);
+    }
+}
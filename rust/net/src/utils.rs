@@ -6,10 +6,10 @@
 use base64::prelude::{Engine as _, BASE64_STANDARD};
 use futures_util::stream::FuturesUnordered;
 use futures_util::StreamExt;
-use http::HeaderValue;
+use http::{HeaderMap, HeaderValue, StatusCode};
 use std::future;
 use std::future::Future;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 /// Constructs the value of the `Authorization` header for the `Basic` auth scheme.
 pub(crate) fn basic_authorization(username: &str, password: &str) -> HeaderValue {
@@ -18,6 +18,35 @@ pub(crate) fn basic_authorization(username: &str, password: &str) -> HeaderValue
     HeaderValue::try_from(auth).expect("valid header value")
 }
 
+/// Parses the value of a `Retry-After` header, which is either a number of seconds or an HTTP-date.
+///
+/// Dates are converted to a delay relative to `now`, rounded up to a whole number of seconds; dates
+/// in the past mean no delay.
+pub(crate) fn parse_retry_after(value: &HeaderValue, now: SystemTime) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    let delay = date.duration_since(now).unwrap_or_default();
+    Some(Duration::from_secs(
+        delay.as_secs() + u64::from(delay.subsec_nanos() != 0),
+    ))
+}
+
+/// Returns how long the server asked the client to wait, if a response with the given `status`
+/// and `headers` means the client is being rate limited.
+///
+/// Both 429 Too Many Requests and 508 Loop Detected are used by Signal servers to mean "slow down",
+/// but only when they come with a valid `Retry-After` header.
+pub(crate) fn rate_limit_retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::LOOP_DETECTED {
+        return None;
+    }
+    let value = headers.get(http::header::RETRY_AFTER)?;
+    parse_retry_after(value, SystemTime::now())
+}
+
 /// Requires a `Future` to complete before the specified duration has elapsed.
 ///
 /// Takes in a future whose return type is `Result<T, E>`, a `duration` timeout,
@@ -50,6 +79,48 @@ where
         .await
 }
 
+/// Like [`first_ok`], but starts the futures one at a time, in the order given.
+///
+/// Each future is given a `delay` head start before the next one is started. If all of the
+/// futures started so far fail before their time is up, the next one is started right away
+/// instead. This is the staggered start of the "Happy Eyeballs" algorithm ([RFC 8305 section 5]).
+///
+/// Once a future completes successfully, the rest are dropped, which cancels any attempts
+/// still in progress as well as those that were never started.
+///
+/// [RFC 8305 section 5]: https://datatracker.ietf.org/doc/html/rfc8305#section-5
+pub async fn first_ok_staggered<T, E, F, I>(futures: I, delay: Duration) -> Option<T>
+where
+    F: Future<Output = Result<T, E>>,
+    I: IntoIterator<Item = F>,
+{
+    let mut not_started = futures.into_iter();
+    let mut in_flight = FuturesUnordered::new();
+
+    while let Some(next) = not_started.next() {
+        in_flight.push(next);
+
+        let next_start = tokio::time::sleep(delay);
+        tokio::pin!(next_start);
+        loop {
+            tokio::select! {
+                result = in_flight.next() => match result {
+                    Some(Ok(t)) => return Some(t),
+                    Some(Err(_)) => continue,
+                    // Everything started so far has failed; don't wait for the delay.
+                    None => break,
+                },
+                () = &mut next_start => break,
+            }
+        }
+    }
+
+    in_flight
+        .filter_map(|result| future::ready(result.ok()))
+        .next()
+        .await
+}
+
 /// In the tokio time paused test mode, if some logic is supposed to wake up at specific time
 /// and a test wants to make sure it observes the result of that logic without moving
 /// the time past that point, it's not enough to call `sleep()` or `advance()` alone.
@@ -79,6 +150,52 @@ mod test {
     use std::time::Duration;
     use tokio::time;
 
+    #[test]
+    fn parse_retry_after_accepts_seconds_and_dates() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+        let parse = |value: &'static str| parse_retry_after(&HeaderValue::from_static(value), now);
+
+        assert_eq!(parse("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse(" 0 "), Some(Duration::ZERO));
+        assert_eq!(
+            parse("Sun, 06 Nov 1994 08:51:37 GMT"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse("Sun, 06 Nov 1994 08:00:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(
+            parse_retry_after(
+                &HeaderValue::from_static("Sun, 06 Nov 1994 08:49:38 GMT"),
+                now + Duration::from_millis(500)
+            ),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(parse("-5"), None);
+        assert_eq!(parse("soon"), None);
+    }
+
+    #[test]
+    fn rate_limit_retry_after_requires_status_and_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            rate_limit_retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            None
+        );
+        headers.insert(http::header::RETRY_AFTER, HeaderValue::from_static("30"));
+        assert_eq!(
+            rate_limit_retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit_retry_after(StatusCode::LOOP_DETECTED, &headers),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit_retry_after(StatusCode::SERVICE_UNAVAILABLE, &headers),
+            None
+        );
+    }
+
     #[tokio::test(start_paused = true)]
     async fn first_ok_picks_the_result_from_earliest_finished_future() {
         let future_1 = future(30, Ok(1));
@@ -105,6 +222,77 @@ mod test {
         assert!(first_ok(vec![future_1, future_2, future_3]).await.is_none())
     }
 
+    const STAGGER_DELAY: Duration = Duration::from_millis(100);
+
+    #[tokio::test(start_paused = true)]
+    async fn first_ok_staggered_waits_for_delay_before_starting_next() {
+        let start = time::Instant::now();
+        // The second future would finish first if it were started right away.
+        let future_1 = future(150, Ok(1));
+        let future_2 = future(10, Ok(2));
+        let result = first_ok_staggered(vec![future_1, future_2], STAGGER_DELAY)
+            .await
+            .unwrap();
+        assert_eq!(2, result);
+        assert_eq!(start.elapsed(), STAGGER_DELAY + Duration::from_millis(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn first_ok_staggered_starts_next_immediately_on_failure() {
+        let start = time::Instant::now();
+        let future_1 = future(10, Err("error"));
+        let future_2 = future(10, Ok(2));
+        let result = first_ok_staggered(vec![future_1, future_2], STAGGER_DELAY)
+            .await
+            .unwrap();
+        assert_eq!(2, result);
+        assert_eq!(start.elapsed(), Duration::from_millis(20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn first_ok_staggered_keeps_waiting_for_earlier_attempts() {
+        let future_1 = future(150, Ok(1));
+        let future_2 = future(200, Err("error"));
+        let result = first_ok_staggered(vec![future_1, future_2], STAGGER_DELAY)
+            .await
+            .unwrap();
+        assert_eq!(1, result);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn first_ok_staggered_cancels_remaining_attempts() {
+        let completed = Arc::new(AtomicBool::new(false));
+        let slow_future = {
+            let completed = completed.clone();
+            Box::pin(async move {
+                time::sleep(Duration::from_millis(1000)).await;
+                completed.store(true, Ordering::Relaxed);
+                Ok::<u32, &str>(1)
+            })
+        };
+        let fast_future = Box::pin(future(10, Ok(2)));
+
+        let futures: Vec<std::pin::Pin<Box<dyn Future<Output = Result<u32, &str>>>>> =
+            vec![slow_future, fast_future];
+        let result = first_ok_staggered(futures, STAGGER_DELAY).await.unwrap();
+        assert_eq!(2, result);
+
+        sleep_and_catch_up(Duration::from_millis(2000)).await;
+        assert!(!completed.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn first_ok_staggered_returns_none_if_all_failed() {
+        let future_1 = future(30, Err("error 1"));
+        let future_2 = future(10, Err("error 2"));
+        let future_3 = future(20, Err("error 3"));
+        assert!(
+            first_ok_staggered(vec![future_1, future_2, future_3], STAGGER_DELAY)
+                .await
+                .is_none()
+        )
+    }
+
     #[tokio::test(start_paused = true)]
     async fn sleep_and_catch_up_showcase() {
         const DURATION: Duration = Duration::from_millis(100);
@@ -12,6 +12,8 @@ pub enum Error {
     DecodingError(argon2::password_hash::errors::Error),
     /// Error looking up mrenclave
     MrenclaveLookupError,
+    /// Argon2 parameters are below the supported minimum
+    WeakParams,
 }
 
 impl From<argon2::Error> for Error {
@@ -12,6 +12,7 @@
 //!      for that data. See `PinHash`
 //!   2. Creating a [PHC-string encoded](https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md#specification)
 //!      password hash of the pin that can be stored locally and validated against the pin later.
+//!      Clients can pick their own Argon2id cost for this hash, within limits. See `Argon2Params`
 //!
 //! In either case, all pins are UTF-8 encoded bytes that must be normalized *before* being provided
 //! to this library. Normalizing a string pin requires the following steps:
@@ -21,14 +22,15 @@
 //!  3. The string must then be [NFKD normalized](https://unicode.org/reports/tr15/#Norm_Forms)
 //!
 
-use argon2::password_hash::{rand_core, Salt, SaltString};
+use argon2::password_hash::{self, rand_core, Salt, SaltString};
 use argon2::{
-    Algorithm, Argon2, ParamsBuilder, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+    Algorithm, Argon2, Params, ParamsBuilder, PasswordHash, PasswordHasher, PasswordVerifier,
+    Version,
 };
 use hkdf::Hkdf;
 use sha2::Sha256;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 #[derive(Clone, Debug)]
 pub struct PinHash {
@@ -87,15 +89,86 @@ impl PinHash {
     }
 }
 
+/// Cost parameters for an Argon2id local pin hash, for use with [`local_pin_hash_with_params`].
+///
+/// Devices that can't spare the memory for a given hash can make up for it with more iterations,
+/// but the total cost can't go below that of [`local_pin_hash`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Argon2Params {
+    memory_kib: u32,
+    iterations: u32,
+}
+
+impl Argon2Params {
+    /// The least memory that can be used, in KiB.
+    pub const MIN_MEMORY_KIB: u32 = 512;
+    /// The fewest passes over memory that can be used.
+    pub const MIN_ITERATIONS: u32 = 2;
+    /// The least total cost (memory in KiB times iterations) that can be used.
+    pub const MIN_TOTAL_COST: u64 = 512 * 64;
+
+    /// Checks that the given parameters meet the minimums above.
+    ///
+    /// Returns [`Error::WeakParams`](crate::Error::WeakParams) if they don't.
+    pub fn new(memory_kib: u32, iterations: u32) -> Result<Self> {
+        if memory_kib < Self::MIN_MEMORY_KIB
+            || iterations < Self::MIN_ITERATIONS
+            || u64::from(memory_kib) * u64::from(iterations) < Self::MIN_TOTAL_COST
+        {
+            return Err(Error::WeakParams);
+        }
+        Ok(Self {
+            memory_kib,
+            iterations,
+        })
+    }
+
+    pub fn memory_kib(&self) -> u32 {
+        self.memory_kib
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    fn hasher(&self) -> Argon2<'static> {
+        Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            ParamsBuilder::new()
+                .m_cost(self.memory_kib)
+                .p_cost(1)
+                .t_cost(self.iterations)
+                .output_len(32)
+                .build()
+                .expect("minimums are above Argon2's"),
+        )
+    }
+}
+
 /// Create a PHC encoded password hash string. This string may be verified later with
-/// `verify_local_pin_hash`.
+/// `verify_local_hash`.
 ///
 /// # Arguments
 /// * `pin` - UTF-8 encoding of the pin. The pin *must* be normalized first.
 pub fn local_pin_hash(pin: &[u8]) -> Result<String> {
+    local_pin_hash_with_salt(pin, &generate_salt())
+}
+
+/// Create a PHC encoded Argon2id password hash string with the given cost. This string may be
+/// verified later with `verify_local_hash`, which reads the parameters back out of it.
+///
+/// # Arguments
+/// * `pin` - UTF-8 encoding of the pin. The pin *must* be normalized first.
+/// * `params` - The Argon2id memory and iteration cost to use
+pub fn local_pin_hash_with_params(pin: &[u8], params: &Argon2Params) -> Result<String> {
+    let hash = params.hasher().hash_password(pin, &generate_salt())?;
+    Ok(hash.to_string())
+}
+
+fn generate_salt() -> SaltString {
     static_assertions::const_assert_eq!(Salt::RECOMMENDED_LENGTH, 16);
-    let salt = SaltString::generate(&mut rand_core::OsRng);
-    local_pin_hash_with_salt(pin, &salt)
+    SaltString::generate(&mut rand_core::OsRng)
 }
 
 fn local_pin_hash_with_salt<'a>(pin: &[u8], salt: impl Into<Salt<'a>>) -> Result<String> {
@@ -116,9 +189,43 @@ fn local_pin_hash_with_salt<'a>(pin: &[u8], salt: impl Into<Salt<'a>>) -> Result
 
 /// Verify an encoded password hash against a pin
 ///
+/// The hash is recomputed with the algorithm and parameters recorded in `encoded_hash`, so this
+/// accepts hashes from both `local_pin_hash` and `local_pin_hash_with_params`. The result is
+/// compared in constant time.
+///
+/// Returns `Ok(false)` only if the pin doesn't match; a malformed or unsupported hash is an error.
+/// So is a hash whose cost is below the minimums of [`Argon2Params`], which gives
+/// [`Error::WeakParams`](crate::Error::WeakParams), so that a stored hash can't be swapped for one
+/// that is cheaper to brute-force.
+///
 /// # Arguments
+/// * `encoded_hash` - A PHC-string formatted representation of the hash, as returned by `local_pin_hash`
 /// * `pin` - UTF-8 encoding of the pin. The pin *must* be normalized first.
+pub fn verify_local_hash(encoded_hash: &str, pin: &[u8]) -> Result<bool> {
+    let parsed = PasswordHash::new(encoded_hash)?;
+    if parsed.salt.is_none() || parsed.hash.is_none() {
+        return Err(password_hash::Error::PhcStringField.into());
+    }
+    let params = Params::try_from(&parsed)?;
+    Argon2Params::new(params.m_cost(), params.t_cost())?;
+    // `Output`'s equality, used by `verify_password`, is constant-time.
+    match Argon2::default().verify_password(pin, &parsed) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Verify an encoded password hash against a pin
+///
+/// Like [`verify_local_hash`], this accepts hashes from both `local_pin_hash` and
+/// `local_pin_hash_with_params`. Only a hash that can't be parsed is an error, though: one that
+/// parses but can't be verified, such as one using an unsupported algorithm or missing its output,
+/// gives `Ok(false)`, as if the pin didn't match.
+///
+/// # Arguments
 /// * `encoded_hash` - A PHC-string formatted representation of the hash, as returned by `local_pin_hash`
+/// * `pin` - UTF-8 encoding of the pin. The pin *must* be normalized first.
 pub fn verify_local_pin_hash(encoded_hash: &str, pin: &[u8]) -> Result<bool> {
     let parsed = PasswordHash::new(encoded_hash)?;
     Ok(Argon2::default().verify_password(pin, &parsed).is_ok())
@@ -130,7 +237,10 @@ mod test {
     use hmac::{Hmac, Mac};
     use sha2::Sha256;
 
-    use crate::hash::{local_pin_hash, verify_local_pin_hash, PinHash};
+    use crate::hash::{
+        local_pin_hash, local_pin_hash_with_params, verify_local_hash, verify_local_pin_hash,
+        Argon2Params, PinHash,
+    };
 
     use super::*;
 
@@ -230,6 +340,75 @@ mod test {
         assert!(!verify_local_pin_hash(&phc_string, b"wrongpin").unwrap());
     }
 
+    #[test]
+    fn unverifiable_hashes() {
+        let pin = b"apassword";
+        let without_output = "$argon2i$v=19$m=512,t=64,p=1$ICEiIyQlJicoKSorLC0uLw";
+        let unknown_algorithm = "$argon3$v=19$m=512,t=64,p=1$ICEiIyQlJicoKSorLC0uLw$NeZzhiNv4cRmRMct9scf7d838bzmHJvrZtU/0BH0v/U";
+        for phc_string in [without_output, unknown_algorithm] {
+            assert!(!verify_local_pin_hash(phc_string, pin).unwrap());
+            assert!(verify_local_hash(phc_string, pin).is_err());
+        }
+
+        assert!(verify_local_pin_hash("not a hash", pin).is_err());
+        assert!(verify_local_hash("not a hash", pin).is_err());
+    }
+
+    #[test]
+    fn params_enforce_minimums() {
+        assert!(Argon2Params::new(512, 64).is_ok());
+        assert!(Argon2Params::new(4096, 8).is_ok());
+        assert_eq!(Argon2Params::new(256, 1024), Err(Error::WeakParams));
+        assert_eq!(Argon2Params::new(1024 * 1024, 1), Err(Error::WeakParams));
+        assert_eq!(Argon2Params::new(1024, 16), Err(Error::WeakParams));
+    }
+
+    #[test]
+    fn verify_with_params() {
+        let pin = b"hunter2";
+        let params = Argon2Params::new(1024, 32).expect("valid");
+        let phc_string = local_pin_hash_with_params(pin, &params).expect("should hash");
+        assert!(phc_string.starts_with("$argon2id$v=19$m=1024,t=32,p=1$"));
+        assert!(verify_local_hash(&phc_string, pin).unwrap());
+        assert!(!verify_local_hash(&phc_string, b"wrongpin").unwrap());
+    }
+
+    #[test]
+    fn verify_rejects_invalid_hashes() {
+        let pin = b"apassword";
+        let valid = "$argon2i$v=19$m=512,t=64,p=1$ICEiIyQlJicoKSorLC0uLw$NeZzhiNv4cRmRMct9scf7d838bzmHJvrZtU/0BH0v/U";
+        assert!(verify_local_hash(valid, pin).unwrap());
+
+        let unsupported_algorithm = valid.replace("argon2i", "bcrypt");
+        assert!(verify_local_hash(&unsupported_algorithm, pin).is_err());
+
+        let missing_hash = "$argon2i$v=19$m=512,t=64,p=1$ICEiIyQlJicoKSorLC0uLw";
+        assert!(verify_local_hash(missing_hash, pin).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_weak_params() {
+        let pin = b"apassword";
+        for (m_cost, t_cost) in [(256, 1024), (32 * 1024, 1), (1024, 16)] {
+            let hasher = Argon2::new(
+                Algorithm::Argon2id,
+                Version::V0x13,
+                Params::new(m_cost, t_cost, 1, None).expect("valid params"),
+            );
+            let weak = hasher
+                .hash_password(pin, &generate_salt())
+                .expect("should hash")
+                .to_string();
+            // The lenient verifier only checks the pin.
+            assert!(verify_local_pin_hash(&weak, pin).unwrap());
+            assert_eq!(verify_local_hash(&weak, pin), Err(Error::WeakParams));
+            assert_eq!(
+                verify_local_hash(&weak, b"wrongpin"),
+                Err(Error::WeakParams)
+            );
+        }
+    }
+
     #[test]
     fn known_salt() {
         let username = "username";
@@ -7,4 +7,7 @@ mod error;
 mod hash;
 
 pub use error::{Error, Result};
-pub use hash::{local_pin_hash, verify_local_pin_hash, PinHash};
+pub use hash::{
+    local_pin_hash, local_pin_hash_with_params, verify_local_hash, verify_local_pin_hash,
+    Argon2Params, PinHash,
+};
@@ -13,9 +13,9 @@ use crate::shoapi::ShoApi;
 use crate::shohmacsha256::ShoHmacSha256;
 use crate::simple_types::*;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
-use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::ristretto::{RistrettoPoint, VartimeRistrettoPrecomputation};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
+use curve25519_dalek::traits::{MultiscalarMul, VartimePrecomputedMultiscalarMul};
 
 // POKSHO implements the "Sigma protocol for arbitrary linear relations" described in section
 // 19.5.3 of https://crypto.stanford.edu/~dabo/cryptobook/BonehShoup_0_4.pdf
@@ -113,6 +113,18 @@ struct Equation {
     rhs: Vec<Term>,
 }
 
+/// Precomputed multiplication tables for one equation, shared by a batch of proofs.
+struct BatchEquation {
+    /// For each term of the right-hand side, followed by the left-hand side, whether its point has
+    /// the same value in every proof of the batch.
+    fixed_terms: Vec<bool>,
+    /// Tables for the fixed points, in the same order as `fixed_terms`.
+    fixed_points: VartimeRistrettoPrecomputation,
+}
+
+/// Below this many proofs, building tables costs more than it saves.
+const MIN_PROOFS_FOR_PRECOMPUTATION: usize = 4;
+
 pub struct Statement {
     // We store the Schnorr ZKP equations using scalar and point indices
     // which are numbered from zero, and are assigned sequentially based
@@ -250,21 +262,9 @@ impl Statement {
         point_args: &PointArgs,
         message: &[u8],
     ) -> Result<(), PokshoError> {
-        let proof = Proof::from_slice(proof_bytes).ok_or(VerificationFailure)?;
-        if proof.response.len() != self.scalar_vec.len() {
-            return Err(VerificationFailure);
-        }
+        let proof = self.parse_proof(proof_bytes)?;
         let all_points = self.sort_points(point_args)?;
 
-        // Absorb the protocol label L, statement description D, and point values A
-        let mut sho = ShoHmacSha256::new(b"POKSHO_Ristretto_SHOHMACSHA256"); // L
-        sho.absorb(&self.to_bytes()); // D
-        for point in &all_points {
-            // A
-            sho.absorb(&point.compress().to_bytes());
-        }
-        sho.ratchet();
-
         // Reconstruct commitment
         //
         // commitment R = F(s) - h*A
@@ -276,8 +276,168 @@ impl Statement {
         let commitment =
             self.homomorphism_with_subtraction(&proof.response, &all_points, Some(proof.challenge));
 
+        self.check_challenge(
+            self.transcript_prefix(),
+            &proof,
+            &all_points,
+            &commitment,
+            message,
+        )
+    }
+
+    /// Verifies many proofs for this statement, returning a result for each one.
+    ///
+    /// This is equivalent to calling [`verify_proof`](Self::verify_proof) on each proof, but
+    /// faster for large batches. Points that have the same value in every proof, such as fixed
+    /// generators and the verifier's own public keys, get multiplication tables that are built
+    /// once and shared by the whole batch, and the description of the statement is only hashed
+    /// once.
+    ///
+    /// Each commitment is still reconstructed with its own multi-scalar multiplication: a poksho
+    /// proof carries its challenge rather than its commitment, and the commitment must be hashed
+    /// to check the challenge, so the proofs can't be combined into a single random linear
+    /// combination.
+    pub fn verify_proofs<'a>(
+        &self,
+        proofs: impl IntoIterator<Item = (&'a [u8], &'a PointArgs, &'a [u8])>,
+    ) -> Vec<Result<(), PokshoError>> {
+        let parsed: Vec<Result<(Proof, Vec<RistrettoPoint>, &[u8]), PokshoError>> = proofs
+            .into_iter()
+            .map(|(proof_bytes, point_args, message)| {
+                let proof = self.parse_proof(proof_bytes)?;
+                let all_points = self.sort_points(point_args)?;
+                Ok((proof, all_points, message))
+            })
+            .collect();
+
+        let batch_points: Vec<&[RistrettoPoint]> = parsed
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .map(|(_proof, all_points, _message)| &all_points[..])
+            .collect();
+        let batch_equations = (batch_points.len() >= MIN_PROOFS_FOR_PRECOMPUTATION)
+            .then(|| self.batch_equations(&batch_points));
+
+        let transcript_prefix = self.transcript_prefix();
+        parsed
+            .into_iter()
+            .map(|result| {
+                let (proof, all_points, message) = result?;
+                let commitment = match &batch_equations {
+                    Some(batch_equations) => {
+                        self.commitment_from_batch_equations(batch_equations, &proof, &all_points)
+                    }
+                    None => self.homomorphism_with_subtraction(
+                        &proof.response,
+                        &all_points,
+                        Some(proof.challenge),
+                    ),
+                };
+                self.check_challenge(
+                    transcript_prefix.clone(),
+                    &proof,
+                    &all_points,
+                    &commitment,
+                    message,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns a SHO that has absorbed the protocol label L and statement description D.
+    fn transcript_prefix(&self) -> ShoHmacSha256 {
+        let mut sho = ShoHmacSha256::new(b"POKSHO_Ristretto_SHOHMACSHA256"); // L
+        sho.absorb(&self.to_bytes()); // D
+        sho
+    }
+
+    fn parse_proof(&self, proof_bytes: &[u8]) -> Result<Proof, PokshoError> {
+        let proof = Proof::from_slice(proof_bytes).ok_or(VerificationFailure)?;
+        if proof.response.len() != self.scalar_vec.len() {
+            return Err(VerificationFailure);
+        }
+        Ok(proof)
+    }
+
+    /// Builds tables for the points that are the same in every one of `batch_points`.
+    fn batch_equations(&self, batch_points: &[&[RistrettoPoint]]) -> Vec<BatchEquation> {
+        let (first, rest) = batch_points
+            .split_first()
+            .expect("only used for non-empty batches");
+        let is_fixed: Vec<bool> = (0..first.len())
+            .map(|i| rest.iter().all(|all_points| all_points[i] == first[i]))
+            .collect();
+
+        self.equations
+            .iter()
+            .map(|e| {
+                let points = e.rhs.iter().map(|term| term.point).chain([e.lhs]);
+                BatchEquation {
+                    fixed_terms: points.clone().map(|p| is_fixed[p as usize]).collect(),
+                    fixed_points: VartimeRistrettoPrecomputation::new(
+                        points
+                            .filter(|p| is_fixed[*p as usize])
+                            .map(|p| first[p as usize]),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// Like [`homomorphism_with_subtraction`](Self::homomorphism_with_subtraction) with a
+    /// challenge, but using tables for the fixed points of a batch.
+    fn commitment_from_batch_equations(
+        &self,
+        batch_equations: &[BatchEquation],
+        proof: &Proof,
+        all_points: &[RistrettoPoint],
+    ) -> G2 {
+        let mut g2 = self.g2_new();
+        for (e, batch_equation) in self.equations.iter().zip(batch_equations) {
+            let terms = e
+                .rhs
+                .iter()
+                .map(|Term { scalar, point }| (proof.response[*scalar as usize], *point))
+                .chain([(-proof.challenge, e.lhs)]);
+            let (fixed_terms, dynamic_terms): (Vec<_>, Vec<_>) = terms
+                .zip(&batch_equation.fixed_terms)
+                .partition(|(_term, is_fixed)| **is_fixed);
+
+            // Variable-time multiplication is safe here: the only scalars are the proof's response
+            // and challenge, which are public.
+            g2.push(
+                batch_equation.fixed_points.vartime_mixed_multiscalar_mul(
+                    fixed_terms.iter().map(|((scalar, _point), _)| scalar),
+                    dynamic_terms.iter().map(|((scalar, _point), _)| scalar),
+                    dynamic_terms
+                        .iter()
+                        .map(|((_scalar, point), _)| all_points[*point as usize]),
+                ),
+            );
+        }
+        g2
+    }
+
+    /// Recomputes the challenge from the point values A, `commitment`, and `message`, and checks
+    /// it against the proof's.
+    ///
+    /// `sho` must already have absorbed the label L and statement description D.
+    fn check_challenge(
+        &self,
+        mut sho: ShoHmacSha256,
+        proof: &Proof,
+        all_points: &[RistrettoPoint],
+        commitment: &[RistrettoPoint],
+        message: &[u8],
+    ) -> Result<(), PokshoError> {
+        for point in all_points {
+            // A
+            sho.absorb(&point.compress().to_bytes());
+        }
+        sho.ratchet();
+
         // Reconstruct challenge from commitment and message
-        for point in &commitment {
+        for point in commitment {
             // R
             sho.absorb(&point.compress().to_bytes());
         }
@@ -458,6 +618,53 @@ mod tests {
         assert!(s.to_bytes() == vec![1, 1, 2, 0, 0, 1, 2]);
     }
 
+    #[test]
+    fn test_verify_proofs_with_varying_points() {
+        let mut st = Statement::new();
+        st.add("A", &[("a", "G")]);
+        st.add("B", &[("a", "H")]);
+
+        let H = RISTRETTO_BASEPOINT_POINT * Scalar::from(1000u64);
+        let cases: Vec<(PointArgs, Vec<u8>)> = (1..=6u64)
+            .map(|i| {
+                let a = Scalar::from(i);
+                let mut scalar_args = ScalarArgs::new();
+                scalar_args.add("a", a);
+                let mut point_args = PointArgs::new();
+                point_args.add("A", RISTRETTO_BASEPOINT_POINT * a);
+                point_args.add("H", H);
+                point_args.add("B", H * a);
+                let proof = st
+                    .prove(&scalar_args, &point_args, b"message", &[i as u8; 32])
+                    .expect("valid");
+                (point_args, proof)
+            })
+            .collect();
+
+        let mut batch: Vec<(&[u8], &PointArgs, &[u8])> = cases
+            .iter()
+            .map(|(point_args, proof)| (&proof[..], point_args, &b"message"[..]))
+            .collect();
+        assert!(st.verify_proofs(batch.clone()).iter().all(Result::is_ok));
+
+        // Swap two proofs, so that each is checked against the other's points.
+        batch.swap(1, 2);
+        batch[1].1 = &cases[1].0;
+        batch[2].1 = &cases[2].0;
+        let results = st.verify_proofs(batch);
+        assert!(matches!(
+            results[..],
+            [
+                Ok(()),
+                Err(VerificationFailure),
+                Err(VerificationFailure),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+            ]
+        ));
+    }
+
     #[test]
     #[allow(
         clippy::needless_range_loop,
@@ -618,5 +825,25 @@ mod tests {
             st.verify_proof(&proof2, &point_args, &message),
             Err(VerificationFailure)
         ));
+
+        // Test batch verification, which should agree with verifying each proof individually
+        proof[prooflen - 1] -= 1;
+        let results = st.verify_proofs([
+            (&proof[..], &point_args, &message[..]),
+            (&proof2[..], &point_args, &message[..]),
+            (&proof[..], &point_args, &block32[..]),
+            (&proof[..], &point_args2, &message[..]),
+            (&proof[..], &point_args, &message[..]),
+        ]);
+        assert!(matches!(
+            results[..],
+            [
+                Ok(()),
+                Err(VerificationFailure),
+                Err(VerificationFailure),
+                Err(PokshoError::BadArgsWrongNumberOfPointArgs),
+                Ok(()),
+            ]
+        ));
     }
 }
@@ -21,6 +21,10 @@ enum KeyType {
     #[cfg(feature = "mlkem1024")]
     #[value(name = "mlkem")]
     MlKem,
+    #[value(name = "mlkem768")]
+    MlKem768,
+    #[value(name = "mlkem1024")]
+    MlKem1024,
 }
 
 impl From<KeyType> for libsignal_protocol::kem::KeyType {
@@ -29,6 +33,8 @@ impl From<KeyType> for libsignal_protocol::kem::KeyType {
             KeyType::Kyber => Self::Kyber1024,
             #[cfg(feature = "mlkem1024")]
             KeyType::MlKem => Self::MLKEM1024,
+            KeyType::MlKem768 => Self::MLKEM768,
+            KeyType::MlKem1024 => Self::MLKEM1024Final,
         }
     }
 }
@@ -108,6 +108,17 @@ impl PublicKey {
         result.into_boxed_slice()
     }
 
+    /// The length of [`Self::serialize`]'s result.
+    pub(crate) fn serialized_len(&self) -> usize {
+        1 + self.key_data().len()
+    }
+
+    /// Like [`Self::serialize`], but appends to an existing buffer.
+    pub(crate) fn serialize_into(&self, buffer: &mut Vec<u8>) {
+        buffer.push(self.key_type().value());
+        buffer.extend_from_slice(self.key_data());
+    }
+
     pub fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<bool> {
         self.verify_signature_for_multipart_message(&[message], signature)
     }
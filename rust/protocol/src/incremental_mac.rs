@@ -4,9 +4,12 @@
 //
 
 use aes::cipher::Unsigned;
+use displaydoc::Display;
+use futures_util::{AsyncRead, AsyncReadExt as _};
 use hmac::digest::generic_array::{ArrayLength, GenericArray};
 use hmac::Mac;
 use sha2::digest::{FixedOutput, MacError, Output};
+use thiserror::Error;
 
 #[derive(Clone)]
 pub struct Incremental<M: Mac + Clone> {
@@ -18,8 +21,71 @@ pub struct Incremental<M: Mac + Clone> {
 #[derive(Clone)]
 pub struct Validating<M: Mac + Clone> {
     incremental: Incremental<M>,
+    // The state as of the end of the last chunk that was validated, to go back to if a chunk turns
+    // out to be invalid
+    last_validated: Incremental<M>,
     // Expected MACs in reversed order, to efficiently pop them from off the end
     expected: Vec<Output<M>>,
+    validated_bytes: u64,
+    consumed_bytes: u64,
+}
+
+/// Error returned by [`Validating::validate_stream`] and [`Incremental::resume_validating`].
+#[derive(Debug, Display, Error)]
+pub enum StreamValidationError {
+    /// chunk starting at offset {offset} does not match its MAC
+    InvalidChunk { offset: u64 },
+    /// validation checkpoint is invalid or was made with a different chunk size
+    InvalidCheckpoint,
+    /// failed to read input: {0}
+    Io(#[from] std::io::Error),
+}
+
+/// How far a [`Validating`] got, in a form that can be saved to resume validation later, possibly
+/// in another process.
+///
+/// The MAC state itself can't be saved, so [`Incremental::resume_validating`] reads the validated
+/// part of the input again from wherever it was stored. It doesn't have to be fetched again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationCheckpoint {
+    chunk_size: u64,
+    validated_offset: u64,
+}
+
+const CHECKPOINT_VERSION: u8 = 1;
+const CHECKPOINT_LEN: usize = 1 + 2 * std::mem::size_of::<u64>();
+
+impl ValidationCheckpoint {
+    /// The number of bytes from the start of the input that were validated.
+    pub fn validated_offset(&self) -> u64 {
+        self.validated_offset
+    }
+
+    pub fn serialize(&self) -> [u8; CHECKPOINT_LEN] {
+        let mut result = [0; CHECKPOINT_LEN];
+        result[0] = CHECKPOINT_VERSION;
+        result[1..9].copy_from_slice(&self.chunk_size.to_be_bytes());
+        result[9..].copy_from_slice(&self.validated_offset.to_be_bytes());
+        result
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, StreamValidationError> {
+        let bytes: &[u8; CHECKPOINT_LEN] = bytes
+            .try_into()
+            .map_err(|_| StreamValidationError::InvalidCheckpoint)?;
+        if bytes[0] != CHECKPOINT_VERSION {
+            return Err(StreamValidationError::InvalidCheckpoint);
+        }
+        let chunk_size = u64::from_be_bytes(bytes[1..9].try_into().expect("correct length"));
+        let validated_offset = u64::from_be_bytes(bytes[9..].try_into().expect("correct length"));
+        if chunk_size == 0 || validated_offset % chunk_size != 0 {
+            return Err(StreamValidationError::InvalidCheckpoint);
+        }
+        Ok(Self {
+            chunk_size,
+            validated_offset,
+        })
+    }
 }
 
 const MINIMUM_CHUNK_SIZE: usize = 64 * 1024;
@@ -67,17 +133,46 @@ impl<M: Mac + Clone> Incremental<M> {
             .rev()
             .collect();
         Validating {
+            last_validated: self.clone(),
             incremental: self,
             expected,
+            validated_bytes: 0,
+            consumed_bytes: 0,
         }
     }
 
-    pub fn update<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = Output<M>> + 'a {
-        let split_point = std::cmp::min(bytes.len(), self.unused_length);
-        let (to_write, overflow) = bytes.split_at(split_point);
+    /// Picks up validation from `checkpoint`, made by [`Validating::checkpoint`].
+    ///
+    /// `saved_input` must produce the part of the input that had been validated, from the start.
+    /// It is validated again, since the MAC state has to be rebuilt from it anyway; if it doesn't
+    /// match, this fails with [`StreamValidationError::InvalidChunk`]. The rest of the input can
+    /// then be passed in starting from [`ValidationCheckpoint::validated_offset`].
+    pub async fn resume_validating<A, I>(
+        self,
+        macs: I,
+        checkpoint: ValidationCheckpoint,
+        saved_input: impl AsyncRead + Unpin,
+    ) -> Result<Validating<M>, StreamValidationError>
+    where
+        A: AsRef<[u8]>,
+        I: IntoIterator<Item = A>,
+        <I as IntoIterator>::IntoIter: DoubleEndedIterator,
+    {
+        if checkpoint.chunk_size != self.chunk_size as u64 {
+            return Err(StreamValidationError::InvalidCheckpoint);
+        }
+        let mut validating = self.validating(macs);
+        validating
+            .validate_stream(saved_input.take(checkpoint.validated_offset))
+            .await?;
+        if validating.consumed_offset() != checkpoint.validated_offset {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(validating)
+    }
 
-        std::iter::once(to_write)
-            .chain(overflow.chunks(self.chunk_size))
+    pub fn update<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = Output<M>> + 'a {
+        self.split_into_chunks(bytes)
             .flat_map(move |chunk| self.update_chunk(chunk))
     }
 
@@ -85,6 +180,14 @@ impl<M: Mac + Clone> Incremental<M> {
         self.mac.finalize().into_bytes()
     }
 
+    /// Splits `bytes` at the chunk boundaries, taking into account what has been passed in so far.
+    fn split_into_chunks<'a>(&self, bytes: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        let split_point = std::cmp::min(bytes.len(), self.unused_length);
+        let (to_write, overflow) = bytes.split_at(split_point);
+
+        std::iter::once(to_write).chain(overflow.chunks(self.chunk_size))
+    }
+
     fn update_chunk(&mut self, bytes: &[u8]) -> Option<Output<M>> {
         assert!(bytes.len() <= self.unused_length);
         self.mac.update(bytes);
@@ -104,24 +207,102 @@ impl<M: Mac + Clone> Incremental<M> {
 }
 
 impl<M: Mac + Clone> Validating<M> {
+    /// Passes in more of the input, returning the number of bytes in the chunks it completed.
+    ///
+    /// If a chunk doesn't match its MAC, all of its bytes are dropped, including any passed in by
+    /// earlier calls, and the input so far ends at [`validated_offset`](Self::validated_offset)
+    /// again. The chunk can then be fetched again and passed in from there.
     pub fn update(&mut self, bytes: &[u8]) -> Result<usize, MacError> {
-        let mut result = Ok(0);
-        let macs = self.incremental.update(bytes);
-
-        let mut whole_chunks = 0;
-        for mac in macs {
-            match self.expected.last() {
-                Some(expected) if expected == &mac => {
-                    whole_chunks += 1;
-                    self.expected.pop();
-                }
-                _ => {
-                    result = Err(MacError);
+        let chunk_size = self.incremental.chunk_size;
+        let mut validated_bytes = 0;
+        for chunk in self.incremental.split_into_chunks(bytes) {
+            self.consumed_bytes += chunk.len() as u64;
+            let Some(mac) = self.incremental.update_chunk(chunk) else {
+                continue;
+            };
+            if self.expected.last() != Some(&mac) {
+                self.incremental = self.last_validated.clone();
+                self.consumed_bytes = self.validated_bytes;
+                return Err(MacError);
+            }
+            self.expected.pop();
+            self.last_validated = self.incremental.clone();
+            self.validated_bytes += chunk_size as u64;
+            validated_bytes += chunk_size;
+        }
+        Ok(validated_bytes)
+    }
+
+    /// The number of bytes from the start of the input that have been validated so far.
+    ///
+    /// Only whole chunks are counted, so this is always a multiple of the chunk size.
+    pub fn validated_offset(&self) -> u64 {
+        self.validated_bytes
+    }
+
+    /// Saves how far validation has got, to resume from later with
+    /// [`Incremental::resume_validating`].
+    ///
+    /// Bytes of an incomplete chunk are not included, so resuming continues from
+    /// [`validated_offset`](Self::validated_offset).
+    pub fn checkpoint(&self) -> ValidationCheckpoint {
+        ValidationCheckpoint {
+            chunk_size: self.incremental.chunk_size as u64,
+            validated_offset: self.validated_bytes,
+        }
+    }
+
+    /// The number of bytes from the start of the input that have been passed in so far.
+    ///
+    /// This is where the input should continue from. It is ahead of
+    /// [`validated_offset`](Self::validated_offset) when the input so far doesn't end on a chunk
+    /// boundary, since the bytes of the incomplete chunk have been taken but not yet validated.
+    pub fn consumed_offset(&self) -> u64 {
+        self.consumed_bytes
+    }
+
+    /// Reads the rest of the input from `reader`, validating it one chunk at a time.
+    ///
+    /// `reader` must produce the input starting from wherever the last call to
+    /// [`update`](Self::update) or `validate_stream` left off. Reading continues until the end of
+    /// the input; bytes of a trailing incomplete chunk are validated by
+    /// [`finalize`](Self::finalize), as usual.
+    ///
+    /// Each chunk is only passed on once it has been read completely, so if reading fails, the
+    /// bytes read since the last chunk boundary are dropped. Validation can then be resumed by
+    /// calling this again with a reader that starts at [`consumed_offset`](Self::consumed_offset),
+    /// without going over the bytes already taken again. Note that this is only the same as
+    /// [`validated_offset`](Self::validated_offset) if the input taken so far ended on a chunk
+    /// boundary; an earlier call to [`update`](Self::update) may have left part of a chunk
+    /// pending.
+    ///
+    /// If a chunk doesn't match its MAC, this fails with [`StreamValidationError::InvalidChunk`]
+    /// and the chunk is dropped, as with [`update`](Self::update). The chunk can then be fetched
+    /// again and validation resumed by calling this with a reader that starts at its offset.
+    pub async fn validate_stream(
+        &mut self,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> Result<(), StreamValidationError> {
+        let mut chunk = vec![0; self.incremental.chunk_size];
+        loop {
+            // If the input so far didn't end on a chunk boundary, only read up to the next one.
+            let wanted = &mut chunk[..self.incremental.unused_length];
+            let mut filled = 0;
+            while filled < wanted.len() {
+                match reader.read(&mut wanted[filled..]).await? {
+                    0 => break,
+                    n => filled += n,
                 }
             }
+            let is_complete = filled == wanted.len();
+
+            let offset = self.validated_bytes;
+            self.update(&wanted[..filled])
+                .map_err(|MacError| StreamValidationError::InvalidChunk { offset })?;
+            if !is_complete {
+                return Ok(());
+            }
         }
-        let validated_bytes = whole_chunks * self.incremental.chunk_size;
-        result.map(|_| validated_bytes)
     }
 
     pub fn finalize(self) -> Result<usize, MacError> {
@@ -136,6 +317,7 @@ impl<M: Mac + Clone> Validating<M> {
 
 #[cfg(test)]
 mod test {
+    use futures_util::FutureExt as _;
     use hex_literal::hex;
     use hmac::Hmac;
     use proptest::prelude::*;
@@ -347,6 +529,316 @@ mod test {
         });
     }
 
+    fn expected_macs(key: &[u8], bytes: &[u8]) -> Vec<[u8; 32]> {
+        let mut incremental = new_incremental(key, TEST_CHUNK_SIZE);
+        let mut macs: Vec<_> = incremental.update(bytes).collect();
+        macs.push(incremental.finalize());
+        macs.into_iter().map(|mac| mac.into()).collect()
+    }
+
+    /// Produces `data` and then fails, as if the connection dropped.
+    struct InterruptedReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl AsyncRead for InterruptedReader<'_> {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            if self.data.is_empty() {
+                return std::task::Poll::Ready(Err(std::io::ErrorKind::ConnectionReset.into()));
+            }
+            let data = self.data;
+            let n = std::cmp::min(buf.len(), data.len());
+            let (read, rest) = data.split_at(n);
+            buf[..n].copy_from_slice(read);
+            self.data = rest;
+            std::task::Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn validate_stream() {
+        let key = TEST_HMAC_KEY;
+        proptest!(|(input in ".{0,200}")| {
+            let bytes = input.as_bytes();
+            let mut validating =
+                new_incremental(key, TEST_CHUNK_SIZE).validating(expected_macs(key, bytes));
+            validating
+                .validate_stream(futures_util::io::Cursor::new(bytes))
+                .now_or_never()
+                .expect("sync")
+                .expect("validation should succeed");
+            assert_eq!(
+                validating.validated_offset() as usize,
+                bytes.len() / TEST_CHUNK_SIZE * TEST_CHUNK_SIZE
+            );
+            validating.finalize().expect("finalize: validation should succeed");
+        });
+    }
+
+    #[test]
+    fn validate_stream_reports_offset_of_invalid_chunk() {
+        let key = TEST_HMAC_KEY;
+        let mut bytes = [0x5a; 5 * TEST_CHUNK_SIZE];
+        let macs = expected_macs(key, &bytes);
+        bytes[2 * TEST_CHUNK_SIZE + 7] ^= 0xff;
+
+        let mut validating = new_incremental(key, TEST_CHUNK_SIZE).validating(macs);
+        let result = validating
+            .validate_stream(futures_util::io::Cursor::new(&bytes[..]))
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(
+                result,
+                Err(StreamValidationError::InvalidChunk { offset }) if offset == 2 * TEST_CHUNK_SIZE as u64
+            ),
+            "{result:?}"
+        );
+        assert_eq!(validating.validated_offset(), 2 * TEST_CHUNK_SIZE as u64);
+        assert_eq!(validating.consumed_offset(), 2 * TEST_CHUNK_SIZE as u64);
+    }
+
+    #[test]
+    fn validate_stream_resumes_after_invalid_chunk() {
+        let key = TEST_HMAC_KEY;
+        let bytes: Vec<u8> = (0..=255).cycle().take(4 * TEST_CHUNK_SIZE + 5).collect();
+        let mut validating =
+            new_incremental(key, TEST_CHUNK_SIZE).validating(expected_macs(key, &bytes));
+
+        let mut corrupted = bytes.clone();
+        corrupted[2 * TEST_CHUNK_SIZE + 7] ^= 0xff;
+        let result = validating
+            .validate_stream(futures_util::io::Cursor::new(&corrupted[..]))
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(
+                result,
+                Err(StreamValidationError::InvalidChunk { offset }) if offset == 2 * TEST_CHUNK_SIZE as u64
+            ),
+            "{result:?}"
+        );
+
+        // Fetch the bad chunk again.
+        let resume_at = validating.consumed_offset() as usize;
+        assert_eq!(resume_at, 2 * TEST_CHUNK_SIZE);
+        validating
+            .validate_stream(futures_util::io::Cursor::new(&bytes[resume_at..]))
+            .now_or_never()
+            .expect("sync")
+            .expect("validation should succeed");
+        assert_eq!(validating.validated_offset(), 4 * TEST_CHUNK_SIZE as u64);
+        validating
+            .finalize()
+            .expect("finalize: validation should succeed");
+    }
+
+    #[test]
+    fn update_drops_all_of_invalid_chunk() {
+        let key = TEST_HMAC_KEY;
+        let bytes: Vec<u8> = (0..=255).cycle().take(3 * TEST_CHUNK_SIZE).collect();
+        let mut validating =
+            new_incremental(key, TEST_CHUNK_SIZE).validating(expected_macs(key, &bytes));
+
+        assert_eq!(
+            validating
+                .update(&bytes[..TEST_CHUNK_SIZE + 10])
+                .expect("valid"),
+            TEST_CHUNK_SIZE
+        );
+        let mut corrupted = bytes[TEST_CHUNK_SIZE + 10..2 * TEST_CHUNK_SIZE].to_vec();
+        corrupted[0] ^= 0xff;
+        validating.update(&corrupted).expect_err("MacError");
+        assert_eq!(validating.validated_offset(), TEST_CHUNK_SIZE as u64);
+        assert_eq!(validating.consumed_offset(), TEST_CHUNK_SIZE as u64);
+
+        validating
+            .update(&bytes[TEST_CHUNK_SIZE..])
+            .expect("validation should succeed");
+        validating
+            .finalize()
+            .expect("finalize: validation should succeed");
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let key = TEST_HMAC_KEY;
+        let bytes: Vec<u8> = (0..=255).cycle().take(4 * TEST_CHUNK_SIZE + 5).collect();
+        let macs = expected_macs(key, &bytes);
+        let mut validating = new_incremental(key, TEST_CHUNK_SIZE).validating(macs.clone());
+        validating
+            .update(&bytes[..2 * TEST_CHUNK_SIZE + 10])
+            .expect("valid");
+
+        let checkpoint = ValidationCheckpoint::deserialize(&validating.checkpoint().serialize())
+            .expect("valid checkpoint");
+        assert_eq!(checkpoint, validating.checkpoint());
+        let resume_at = checkpoint.validated_offset() as usize;
+        assert_eq!(resume_at, 2 * TEST_CHUNK_SIZE);
+
+        let mut resumed = new_incremental(key, TEST_CHUNK_SIZE)
+            .resume_validating(
+                macs.clone(),
+                checkpoint,
+                futures_util::io::Cursor::new(&bytes[..resume_at]),
+            )
+            .now_or_never()
+            .expect("sync")
+            .expect("saved input is valid");
+        assert_eq!(resumed.consumed_offset(), resume_at as u64);
+        resumed
+            .validate_stream(futures_util::io::Cursor::new(&bytes[resume_at..]))
+            .now_or_never()
+            .expect("sync")
+            .expect("validation should succeed");
+        resumed
+            .finalize()
+            .expect("finalize: validation should succeed");
+
+        let mut corrupted = bytes[..resume_at].to_vec();
+        corrupted[3] ^= 0xff;
+        let result = new_incremental(key, TEST_CHUNK_SIZE)
+            .resume_validating(
+                macs.clone(),
+                checkpoint,
+                futures_util::io::Cursor::new(&corrupted[..]),
+            )
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(
+                result,
+                Err(StreamValidationError::InvalidChunk { offset: 0 })
+            ),
+            "{:?}",
+            result.map(|_| ())
+        );
+
+        let result = new_incremental(key, TEST_CHUNK_SIZE)
+            .resume_validating(
+                macs.clone(),
+                checkpoint,
+                futures_util::io::Cursor::new(&bytes[..resume_at - 1]),
+            )
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(result, Err(StreamValidationError::Io(_))),
+            "{:?}",
+            result.map(|_| ())
+        );
+
+        let result = new_incremental(key, 2 * TEST_CHUNK_SIZE)
+            .resume_validating(
+                macs,
+                checkpoint,
+                futures_util::io::Cursor::new(&bytes[..resume_at]),
+            )
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(result, Err(StreamValidationError::InvalidCheckpoint)),
+            "{:?}",
+            result.map(|_| ())
+        );
+    }
+
+    #[test]
+    fn invalid_checkpoints() {
+        let valid = ValidationCheckpoint {
+            chunk_size: TEST_CHUNK_SIZE as u64,
+            validated_offset: 2 * TEST_CHUNK_SIZE as u64,
+        }
+        .serialize();
+        ValidationCheckpoint::deserialize(&valid).expect("valid");
+        ValidationCheckpoint::deserialize(&valid[1..]).expect_err("too short");
+
+        let mut wrong_version = valid;
+        wrong_version[0] ^= 0xff;
+        ValidationCheckpoint::deserialize(&wrong_version).expect_err("wrong version");
+
+        let mut not_on_chunk_boundary = valid;
+        not_on_chunk_boundary[CHECKPOINT_LEN - 1] ^= 1;
+        ValidationCheckpoint::deserialize(&not_on_chunk_boundary).expect_err("not a whole chunk");
+    }
+
+    #[test]
+    fn validate_stream_resumes_after_read_failure() {
+        let key = TEST_HMAC_KEY;
+        let bytes: Vec<u8> = (0..=255).cycle().take(4 * TEST_CHUNK_SIZE + 5).collect();
+        let mut validating =
+            new_incremental(key, TEST_CHUNK_SIZE).validating(expected_macs(key, &bytes));
+
+        // Fail partway through the third chunk.
+        let interrupted_at = 2 * TEST_CHUNK_SIZE + 10;
+        let result = validating
+            .validate_stream(InterruptedReader {
+                data: &bytes[..interrupted_at],
+            })
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(result, Err(StreamValidationError::Io(_))),
+            "{result:?}"
+        );
+        let resume_at = validating.validated_offset() as usize;
+        assert_eq!(resume_at, 2 * TEST_CHUNK_SIZE);
+
+        validating
+            .validate_stream(futures_util::io::Cursor::new(&bytes[resume_at..]))
+            .now_or_never()
+            .expect("sync")
+            .expect("validation should succeed");
+        assert_eq!(validating.validated_offset(), 4 * TEST_CHUNK_SIZE as u64);
+        validating
+            .finalize()
+            .expect("finalize: validation should succeed");
+    }
+
+    #[test]
+    fn validate_stream_resumes_after_partial_update() {
+        let key = TEST_HMAC_KEY;
+        let bytes: Vec<u8> = (0..=255).cycle().take(4 * TEST_CHUNK_SIZE + 5).collect();
+        let mut validating =
+            new_incremental(key, TEST_CHUNK_SIZE).validating(expected_macs(key, &bytes));
+
+        // Leave part of the first chunk pending.
+        let updated_to = 10;
+        assert_eq!(validating.update(&bytes[..updated_to]).expect("valid"), 0);
+        assert_eq!(validating.validated_offset(), 0);
+        assert_eq!(validating.consumed_offset(), updated_to as u64);
+
+        // Fail before the rest of the first chunk has been read.
+        let interrupted_at = 20;
+        let result = validating
+            .validate_stream(InterruptedReader {
+                data: &bytes[updated_to..interrupted_at],
+            })
+            .now_or_never()
+            .expect("sync");
+        assert!(
+            matches!(result, Err(StreamValidationError::Io(_))),
+            "{result:?}"
+        );
+        assert_eq!(validating.validated_offset(), 0);
+        let resume_at = validating.consumed_offset() as usize;
+        assert_eq!(resume_at, updated_to);
+
+        validating
+            .validate_stream(futures_util::io::Cursor::new(&bytes[resume_at..]))
+            .now_or_never()
+            .expect("sync")
+            .expect("validation should succeed");
+        assert_eq!(validating.consumed_offset(), bytes.len() as u64);
+        validating
+            .finalize()
+            .expect("finalize: validation should succeed");
+    }
+
     const KIBIBYTES: usize = 1024;
     const MEBIBYTES: usize = 1024 * KIBIBYTES;
     const GIBIBYTES: usize = 1024 * MEBIBYTES;
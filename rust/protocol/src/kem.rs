@@ -12,7 +12,21 @@
 //! `SecretKey::decapsulate(ct: Ciphertext)` to construct the same `SharedSecret`.
 //!
 //! # Supported KEMs
-//! The NIST standardized Kyber1024 and Kyber768 KEMs are currently supported.
+//! The NIST standardized Kyber1024 and Kyber768 KEMs are currently supported, as well as
+//! ML-KEM-768 and ML-KEM-1024 from the final FIPS 203 standard.
+//!
+//! `KeyType::MLKEM1024` (type byte 0x0A, behind the `mlkem1024` feature) is a pre-standard draft
+//! of ML-KEM. [`KeyType::MLKEM1024Final`] (0x0C) is the final FIPS 203 version. Keys and
+//! ciphertexts have the same lengths in both, but the draft generates keys differently and made no
+//! compatibility promises, so the two get different type bytes and are never mixed up: a final
+//! ciphertext is rejected by a draft key and vice versa. Draft keys are not migrated; anything that
+//! generated them should generate new `MLKEM1024Final` keys instead.
+//!
+//! Every key and ciphertext carries its own type byte, so keys of different types can be used
+//! side by side. In PQXDH, the sender always encapsulates to whatever type of key the recipient
+//! advertised, which means existing Kyber pre-keys remain usable while new ones are uploaded
+//! using ML-KEM. [`KeyType::negotiate`] picks a type for new keys when both sides' support is
+//! known.
 //!
 //! # Serialization
 //! `PublicKey`s and `SecretKey`s have serialization functions that encode the
@@ -53,6 +67,8 @@
 mod kyber1024;
 #[cfg(any(feature = "kyber768", test))]
 mod kyber768;
+mod ml_kem1024;
+mod ml_kem768;
 #[cfg(feature = "mlkem1024")]
 mod mlkem1024;
 
@@ -150,9 +166,13 @@ pub enum KeyType {
     Kyber768,
     /// Kyber1024 key
     Kyber1024,
-    /// ML-KEM 1024 key
+    /// ML-KEM 1024 key (pre-standard draft)
     #[cfg(feature = "mlkem1024")]
     MLKEM1024,
+    /// ML-KEM-768 key (FIPS 203)
+    MLKEM768,
+    /// ML-KEM-1024 key (FIPS 203)
+    MLKEM1024Final,
 }
 
 impl KeyType {
@@ -163,6 +183,8 @@ impl KeyType {
             KeyType::Kyber1024 => 0x08,
             #[cfg(feature = "mlkem1024")]
             KeyType::MLKEM1024 => 0x0A,
+            KeyType::MLKEM768 => 0x0B,
+            KeyType::MLKEM1024Final => 0x0C,
         }
     }
 
@@ -176,8 +198,20 @@ impl KeyType {
             KeyType::Kyber1024 => &kyber1024::Parameters,
             #[cfg(feature = "mlkem1024")]
             KeyType::MLKEM1024 => &mlkem1024::Parameters,
+            KeyType::MLKEM768 => &ml_kem768::Parameters,
+            KeyType::MLKEM1024Final => &ml_kem1024::Parameters,
         }
     }
+
+    /// Picks the type to use for new keys, given the types supported locally and by the peer.
+    ///
+    /// Returns the first type in `ours`, which should be in order of preference, that also
+    /// appears in `theirs`, or `None` if there is no type in common.
+    pub fn negotiate(ours: &[KeyType], theirs: &[KeyType]) -> Option<KeyType> {
+        ours.iter()
+            .find(|key_type| theirs.contains(key_type))
+            .copied()
+    }
 }
 
 impl TryFrom<u8> for KeyType {
@@ -190,6 +224,8 @@ impl TryFrom<u8> for KeyType {
             0x08 => Ok(KeyType::Kyber1024),
             #[cfg(feature = "mlkem1024")]
             0x0A => Ok(KeyType::MLKEM1024),
+            0x0B => Ok(KeyType::MLKEM768),
+            0x0C => Ok(KeyType::MLKEM1024Final),
             t => Err(SignalProtocolError::BadKEMKeyType(t)),
         }
     }
@@ -599,4 +635,138 @@ mod tests {
             kyber1024::Parameters.shared_secret_length()
         );
     }
+
+    #[test]
+    fn test_ml_kem_keypairs() {
+        for (key_type, params) in [
+            (
+                KeyType::MLKEM768,
+                &ml_kem768::Parameters as &dyn DynParameters,
+            ),
+            (KeyType::MLKEM1024Final, &ml_kem1024::Parameters),
+        ] {
+            let kp = KeyPair::generate(key_type);
+            assert_eq!(
+                params.secret_key_length() + 1,
+                kp.secret_key.serialize().len()
+            );
+            assert_eq!(
+                params.public_key_length() + 1,
+                kp.public_key.serialize().len()
+            );
+            let (ss_for_sender, ct) = kp.public_key.encapsulate();
+            assert_eq!(params.ciphertext_length() + 1, ct.len());
+            assert_eq!(params.shared_secret_length(), ss_for_sender.len());
+            let ss_for_recipient = kp.secret_key.decapsulate(&ct).expect("decapsulation works");
+            assert_eq!(ss_for_recipient, ss_for_sender);
+
+            let pk = PublicKey::deserialize(&kp.public_key.serialize()).expect("round trips");
+            assert_eq!(pk.key_type(), key_type);
+            assert_eq!(pk.serialize(), kp.public_key.serialize());
+        }
+    }
+
+    #[test]
+    fn test_cross_version_decapsulation() {
+        // An existing Kyber1024 pre-key, as it would have been stored before ML-KEM was added.
+        let mut serialized_sk = vec![KeyType::Kyber1024.value()];
+        serialized_sk.extend_from_slice(include_bytes!("kem/test-data/sk.dat"));
+        let mut serialized_pk = vec![KeyType::Kyber1024.value()];
+        serialized_pk.extend_from_slice(include_bytes!("kem/test-data/pk.dat"));
+        let old = KeyPair::from_public_and_private(&serialized_pk, &serialized_sk)
+            .expect("old keys still deserialize");
+
+        let new_768 = KeyPair::generate(KeyType::MLKEM768);
+        let new_1024 = KeyPair::generate(KeyType::MLKEM1024Final);
+        let all = [&old, &new_768, &new_1024];
+
+        let type_bytes = all.map(|kp| kp.public_key.serialize()[0]);
+        assert_eq!(type_bytes, [0x08, 0x0B, 0x0C]);
+
+        for sender_view in all {
+            let (ss_for_sender, ct) = sender_view.public_key.encapsulate();
+            for recipient in all {
+                let result = recipient.secret_key.decapsulate(&ct);
+                if recipient.public_key.key_type() == sender_view.public_key.key_type() {
+                    assert_eq!(result.expect("same type decapsulates"), ss_for_sender);
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(SignalProtocolError::WrongKEMKeyType(ct_type, key_type))
+                            if ct_type == sender_view.public_key.key_type().value()
+                                && key_type == recipient.public_key.key_type().value()
+                    ));
+                }
+            }
+        }
+
+        assert!(matches!(
+            KeyPair::from_public_and_private(
+                &new_1024.public_key.serialize(),
+                &old.secret_key.serialize()
+            ),
+            Err(SignalProtocolError::WrongKEMKeyType(0x08, 0x0C))
+        ));
+    }
+
+    #[cfg(feature = "mlkem1024")]
+    #[test]
+    fn test_draft_and_final_mlkem1024_are_distinct() {
+        let draft = KeyPair::from_public_and_private(
+            include_bytes!("kem/test-data/mlkem-pk.dat"),
+            include_bytes!("kem/test-data/mlkem-sk.dat"),
+        )
+        .expect("draft keys still deserialize");
+        assert_eq!(draft.public_key.key_type(), KeyType::MLKEM1024);
+        let fips = KeyPair::generate(KeyType::MLKEM1024Final);
+
+        // The keys are the same size, so only the type byte tells them apart.
+        assert_eq!(
+            draft.public_key.serialize().len(),
+            fips.public_key.serialize().len()
+        );
+        assert_eq!(
+            draft.secret_key.serialize().len(),
+            fips.secret_key.serialize().len()
+        );
+
+        let (_, draft_ct) = draft.public_key.encapsulate();
+        let (_, fips_ct) = fips.public_key.encapsulate();
+        assert!(matches!(
+            fips.secret_key.decapsulate(&draft_ct),
+            Err(SignalProtocolError::WrongKEMKeyType(0x0A, 0x0C))
+        ));
+        assert!(matches!(
+            draft.secret_key.decapsulate(&fips_ct),
+            Err(SignalProtocolError::WrongKEMKeyType(0x0C, 0x0A))
+        ));
+    }
+
+    #[cfg(not(feature = "mlkem1024"))]
+    #[test]
+    fn test_draft_mlkem1024_requires_feature() {
+        assert!(matches!(
+            PublicKey::deserialize(include_bytes!("kem/test-data/mlkem-pk.dat")),
+            Err(SignalProtocolError::BadKEMKeyType(0x0A))
+        ));
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let ours = [
+            KeyType::MLKEM1024Final,
+            KeyType::MLKEM768,
+            KeyType::Kyber1024,
+        ];
+        assert_eq!(
+            KeyType::negotiate(&ours, &[KeyType::Kyber1024, KeyType::MLKEM1024Final]),
+            Some(KeyType::MLKEM1024Final)
+        );
+        assert_eq!(
+            KeyType::negotiate(&ours, &[KeyType::Kyber1024]),
+            Some(KeyType::Kyber1024)
+        );
+        assert_eq!(KeyType::negotiate(&ours, &[KeyType::Kyber768]), None);
+        assert_eq!(KeyType::negotiate(&[], &ours), None);
+    }
 }
@@ -0,0 +1,63 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! ML-KEM-1024 as standardized in FIPS 203.
+//!
+//! Keys are tagged with their own type byte and so are never confused with Kyber keys, nor with the
+//! pre-standard draft in `mlkem1024`.
+
+use libcrux_ml_kem::mlkem1024::{
+    self, MlKem1024Ciphertext, MlKem1024PrivateKey, MlKem1024PublicKey,
+};
+use libcrux_ml_kem::{KEY_GENERATION_SEED_SIZE, SHARED_SECRET_SIZE};
+use rand::rngs::OsRng;
+use rand::RngCore as _;
+
+use super::{KeyMaterial, Public, Secret};
+use crate::Result;
+
+pub(crate) struct Parameters;
+
+impl super::Parameters for Parameters {
+    const PUBLIC_KEY_LENGTH: usize = 1568;
+    const SECRET_KEY_LENGTH: usize = 3168;
+    const CIPHERTEXT_LENGTH: usize = 1568;
+    const SHARED_SECRET_LENGTH: usize = SHARED_SECRET_SIZE;
+
+    fn generate() -> (KeyMaterial<Public>, KeyMaterial<Secret>) {
+        let mut seed = [0; KEY_GENERATION_SEED_SIZE];
+        OsRng.fill_bytes(&mut seed);
+        let (sk, pk) = mlkem1024::generate_key_pair(seed).into_parts();
+        (
+            KeyMaterial::new(pk.as_slice()[..].into()),
+            KeyMaterial::new(sk.as_slice()[..].into()),
+        )
+    }
+
+    fn encapsulate(pub_key: &KeyMaterial<Public>) -> (super::SharedSecret, super::RawCiphertext) {
+        let mlkem_pk =
+            MlKem1024PublicKey::try_from(&pub_key[..]).expect("valid ML-KEM-1024 public key bytes");
+        let mut randomness = [0; SHARED_SECRET_SIZE];
+        OsRng.fill_bytes(&mut randomness);
+        let (mlkem_ct, mlkem_ss) = mlkem1024::encapsulate(&mlkem_pk, randomness);
+        (
+            mlkem_ss.as_slice()[..].into(),
+            mlkem_ct.as_slice()[..].into(),
+        )
+    }
+
+    fn decapsulate(
+        secret_key: &KeyMaterial<Secret>,
+        ciphertext: &[u8],
+    ) -> Result<super::SharedSecret> {
+        let mlkem_sk = MlKem1024PrivateKey::try_from(&secret_key[..])
+            .expect("valid ML-KEM-1024 secret key bytes");
+        let mlkem_ct =
+            MlKem1024Ciphertext::try_from(ciphertext).expect("valid ML-KEM-1024 ciphertext");
+        let mlkem_ss = mlkem1024::decapsulate(&mlkem_sk, &mlkem_ct);
+
+        Ok(mlkem_ss.as_slice()[..].into())
+    }
+}
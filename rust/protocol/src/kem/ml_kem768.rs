@@ -0,0 +1,60 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! ML-KEM-768 as standardized in FIPS 203.
+//!
+//! Keys are tagged with their own type byte and so are never confused with Kyber keys.
+
+use libcrux_ml_kem::mlkem768::{self, MlKem768Ciphertext, MlKem768PrivateKey, MlKem768PublicKey};
+use libcrux_ml_kem::{KEY_GENERATION_SEED_SIZE, SHARED_SECRET_SIZE};
+use rand::rngs::OsRng;
+use rand::RngCore as _;
+
+use super::{KeyMaterial, Public, Secret};
+use crate::Result;
+
+pub(crate) struct Parameters;
+
+impl super::Parameters for Parameters {
+    const PUBLIC_KEY_LENGTH: usize = 1184;
+    const SECRET_KEY_LENGTH: usize = 2400;
+    const CIPHERTEXT_LENGTH: usize = 1088;
+    const SHARED_SECRET_LENGTH: usize = SHARED_SECRET_SIZE;
+
+    fn generate() -> (KeyMaterial<Public>, KeyMaterial<Secret>) {
+        let mut seed = [0; KEY_GENERATION_SEED_SIZE];
+        OsRng.fill_bytes(&mut seed);
+        let (sk, pk) = mlkem768::generate_key_pair(seed).into_parts();
+        (
+            KeyMaterial::new(pk.as_slice()[..].into()),
+            KeyMaterial::new(sk.as_slice()[..].into()),
+        )
+    }
+
+    fn encapsulate(pub_key: &KeyMaterial<Public>) -> (super::SharedSecret, super::RawCiphertext) {
+        let mlkem_pk =
+            MlKem768PublicKey::try_from(&pub_key[..]).expect("valid ML-KEM-768 public key bytes");
+        let mut randomness = [0; SHARED_SECRET_SIZE];
+        OsRng.fill_bytes(&mut randomness);
+        let (mlkem_ct, mlkem_ss) = mlkem768::encapsulate(&mlkem_pk, randomness);
+        (
+            mlkem_ss.as_slice()[..].into(),
+            mlkem_ct.as_slice()[..].into(),
+        )
+    }
+
+    fn decapsulate(
+        secret_key: &KeyMaterial<Secret>,
+        ciphertext: &[u8],
+    ) -> Result<super::SharedSecret> {
+        let mlkem_sk = MlKem768PrivateKey::try_from(&secret_key[..])
+            .expect("valid ML-KEM-768 secret key bytes");
+        let mlkem_ct =
+            MlKem768Ciphertext::try_from(ciphertext).expect("valid ML-KEM-768 ciphertext");
+        let mlkem_ss = mlkem768::decapsulate(&mlkem_sk, &mlkem_ct);
+
+        Ok(mlkem_ss.as_slice()[..].into())
+    }
+}
@@ -38,6 +38,7 @@ mod sealed_sender;
 mod sender_keys;
 mod session;
 mod session_cipher;
+mod session_management;
 mod state;
 mod storage;
 mod timestamp;
@@ -60,24 +61,34 @@ pub use identity_key::{IdentityKey, IdentityKeyPair};
 pub use protocol::{
     extract_decryption_error_message_from_serialized_content, CiphertextMessage,
     CiphertextMessageType, DecryptionErrorMessage, KyberPayload, PlaintextContent,
-    PreKeySignalMessage, SenderKeyDistributionMessage, SenderKeyMessage, SignalMessage,
+    PreKeySignalMessage, PreKeySignalMessageRef, SenderKeyDistributionMessage, SenderKeyMessage,
+    SenderKeyMessageRef, SignalMessage, SignalMessageRef,
 };
 pub use ratchet::{
     initialize_alice_session_record, initialize_bob_session_record, AliceSignalProtocolParameters,
     BobSignalProtocolParameters,
 };
 pub use sealed_sender::{
-    sealed_sender_decrypt, sealed_sender_decrypt_to_usmc, sealed_sender_encrypt,
-    sealed_sender_encrypt_from_usmc, sealed_sender_multi_recipient_encrypt,
+    sealed_sender_decrypt, sealed_sender_decrypt_to_usmc, sealed_sender_decrypt_with_options,
+    sealed_sender_encrypt, sealed_sender_encrypt_from_usmc, sealed_sender_encrypt_with_padding,
+    sealed_sender_multi_recipient_encrypt,
+    sealed_sender_multi_recipient_encrypt_reporting_failures,
     sealed_sender_multi_recipient_encrypt_using_legacy_ephemeral_key_derivation, ContentHint,
-    SealedSenderDecryptionResult, SealedSenderV2SentMessage, SealedSenderV2SentMessageRecipient,
-    SenderCertificate, ServerCertificate, UnidentifiedSenderMessageContent,
+    FailedRecipientPolicy, SealedSenderDecryptOptions, SealedSenderDecryptedMessage,
+    SealedSenderDecryptionError, SealedSenderDecryptionFailure, SealedSenderDecryptionMetadata,
+    SealedSenderDecryptionResult, SealedSenderMultiRecipientEncryptResult,
+    SealedSenderMultiRecipientFailure, SealedSenderPadding, SealedSenderV2SentMessage,
+    SealedSenderV2SentMessageRecipient, SenderCertificate, ServerCertificate,
+    UnidentifiedSenderMessageContent,
 };
 pub use sender_keys::SenderKeyRecord;
 pub use session::{process_prekey, process_prekey_bundle};
 pub use session_cipher::{
-    message_decrypt, message_decrypt_prekey, message_decrypt_signal, message_encrypt,
+    message_decrypt, message_decrypt_prekey, message_decrypt_prekey_with_time,
+    message_decrypt_signal, message_decrypt_signal_with_time, message_decrypt_with_time,
+    message_encrypt,
 };
+pub use session_management::{archive_sessions, expire_sessions, prune_previous_sessions};
 pub use state::{
     GenericSignedPreKey, KyberPreKeyId, KyberPreKeyRecord, PreKeyBundle, PreKeyBundleContent,
     PreKeyId, PreKeyRecord, SessionRecord, SignedPreKeyId, SignedPreKeyRecord,
@@ -15,6 +15,10 @@ use sha2::Sha256;
 use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
+mod borrowed;
+use borrowed::FieldWriter;
+pub use borrowed::{PreKeySignalMessageRef, SenderKeyMessageRef, SignalMessageRef};
+
 pub(crate) const CIPHERTEXT_MESSAGE_CURRENT_VERSION: u8 = 4;
 // Backward compatible, lacking Kyber keys, version
 pub(crate) const CIPHERTEXT_MESSAGE_PRE_KYBER_VERSION: u8 = 3;
@@ -81,35 +85,67 @@ impl SignalMessage {
         sender_identity_key: &IdentityKey,
         receiver_identity_key: &IdentityKey,
     ) -> Result<Self> {
-        let message = proto::wire::SignalMessage {
-            ratchet_key: Some(sender_ratchet_key.serialize().into_vec()),
-            counter: Some(counter),
-            previous_counter: Some(previous_counter),
-            ciphertext: Some(Vec::<u8>::from(ciphertext)),
-        };
-        let mut serialized = Vec::with_capacity(1 + message.encoded_len() + Self::MAC_LENGTH);
-        serialized.push(((message_version & 0xF) << 4) | CIPHERTEXT_MESSAGE_CURRENT_VERSION);
-        message
-            .encode(&mut serialized)
-            .expect("can always append to a buffer");
-        let mac = Self::compute_mac(
+        let mut serialized = Vec::new();
+        Self::serialize_into(
+            &mut serialized,
+            message_version,
+            mac_key,
+            &sender_ratchet_key,
+            counter,
+            previous_counter,
+            ciphertext,
             sender_identity_key,
             receiver_identity_key,
-            mac_key,
-            &serialized,
         )?;
-        serialized.extend_from_slice(&mac);
-        let serialized = serialized.into_boxed_slice();
         Ok(Self {
             message_version,
             sender_ratchet_key,
             counter,
             previous_counter,
             ciphertext: ciphertext.into(),
-            serialized,
+            serialized: serialized.into_boxed_slice(),
         })
     }
 
+    /// Like [`SignalMessage::new`], but appends the serialized message to `buffer` instead of
+    /// creating a new `SignalMessage`.
+    ///
+    /// If this fails, `buffer` is left unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn serialize_into(
+        buffer: &mut Vec<u8>,
+        message_version: u8,
+        mac_key: &[u8],
+        sender_ratchet_key: &PublicKey,
+        counter: u32,
+        previous_counter: u32,
+        ciphertext: &[u8],
+        sender_identity_key: &IdentityKey,
+        receiver_identity_key: &IdentityKey,
+    ) -> Result<()> {
+        let start = buffer.len();
+        buffer.push(((message_version & 0xF) << 4) | CIPHERTEXT_MESSAGE_CURRENT_VERSION);
+        FieldWriter::new(buffer)
+            .public_key(1, sender_ratchet_key)
+            .uint32(2, counter)
+            .uint32(3, previous_counter)
+            .bytes(4, ciphertext);
+        let mac = match Self::compute_mac(
+            sender_identity_key,
+            receiver_identity_key,
+            mac_key,
+            &buffer[start..],
+        ) {
+            Ok(mac) => mac,
+            Err(e) => {
+                buffer.truncate(start);
+                return Err(e);
+            }
+        };
+        buffer.extend_from_slice(&mac);
+        Ok(())
+    }
+
     #[inline]
     pub fn message_version(&self) -> u8 {
         self.message_version
@@ -274,23 +310,20 @@ impl PreKeySignalMessage {
         identity_key: IdentityKey,
         message: SignalMessage,
     ) -> Result<Self> {
-        let proto_message = proto::wire::PreKeySignalMessage {
-            registration_id: Some(registration_id),
-            pre_key_id: pre_key_id.map(|id| id.into()),
-            signed_pre_key_id: Some(signed_pre_key_id.into()),
-            kyber_pre_key_id: kyber_payload.as_ref().map(|kyber| kyber.pre_key_id.into()),
-            kyber_ciphertext: kyber_payload
+        let mut serialized = Vec::new();
+        Self::serialize_into(
+            &mut serialized,
+            message_version,
+            registration_id,
+            pre_key_id,
+            signed_pre_key_id,
+            kyber_payload
                 .as_ref()
-                .map(|kyber| kyber.ciphertext.to_vec()),
-            base_key: Some(base_key.serialize().into_vec()),
-            identity_key: Some(identity_key.serialize().into_vec()),
-            message: Some(Vec::from(message.as_ref())),
-        };
-        let mut serialized = Vec::with_capacity(1 + proto_message.encoded_len());
-        serialized.push(((message_version & 0xF) << 4) | CIPHERTEXT_MESSAGE_CURRENT_VERSION);
-        proto_message
-            .encode(&mut serialized)
-            .expect("can always append to a Vec");
+                .map(|kyber| (kyber.pre_key_id, &kyber.ciphertext[..])),
+            &base_key,
+            &identity_key,
+            message.serialized(),
+        );
         Ok(Self {
             message_version,
             registration_id,
@@ -304,6 +337,41 @@ impl PreKeySignalMessage {
         })
     }
 
+    /// Like [`PreKeySignalMessage::new`], but appends the serialized message to `buffer` instead
+    /// of creating a new `PreKeySignalMessage`.
+    ///
+    /// `message` is a serialized [`SignalMessage`], as written by [`SignalMessage::serialize_into`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn serialize_into(
+        buffer: &mut Vec<u8>,
+        message_version: u8,
+        registration_id: u32,
+        pre_key_id: Option<PreKeyId>,
+        signed_pre_key_id: SignedPreKeyId,
+        kyber_payload: Option<(KyberPreKeyId, &[u8])>,
+        base_key: &PublicKey,
+        identity_key: &IdentityKey,
+        message: &[u8],
+    ) {
+        buffer.push(((message_version & 0xF) << 4) | CIPHERTEXT_MESSAGE_CURRENT_VERSION);
+        let mut fields = FieldWriter::new(buffer);
+        // In field number order, like prost.
+        if let Some(pre_key_id) = pre_key_id {
+            fields.uint32(1, pre_key_id.into());
+        }
+        fields
+            .public_key(2, base_key)
+            .public_key(3, identity_key.public_key())
+            .bytes(4, message)
+            .uint32(5, registration_id)
+            .uint32(6, signed_pre_key_id.into());
+        if let Some((kyber_pre_key_id, kyber_ciphertext)) = kyber_payload {
+            fields
+                .uint32(7, kyber_pre_key_id.into())
+                .bytes(8, kyber_ciphertext);
+        }
+    }
+
     #[inline]
     pub fn message_version(&self) -> u8 {
         self.message_version
@@ -455,20 +523,17 @@ impl SenderKeyMessage {
         csprng: &mut R,
         signature_key: &PrivateKey,
     ) -> Result<Self> {
-        let proto_message = proto::wire::SenderKeyMessage {
-            distribution_uuid: Some(distribution_id.as_bytes().to_vec()),
-            chain_id: Some(chain_id),
-            iteration: Some(iteration),
-            ciphertext: Some(ciphertext.to_vec()),
-        };
-        let proto_message_len = proto_message.encoded_len();
-        let mut serialized = Vec::with_capacity(1 + proto_message_len + Self::SIGNATURE_LEN);
-        serialized.push(((message_version & 0xF) << 4) | SENDERKEY_MESSAGE_CURRENT_VERSION);
-        proto_message
-            .encode(&mut serialized)
-            .expect("can always append to a buffer");
-        let signature = signature_key.calculate_signature(&serialized, csprng)?;
-        serialized.extend_from_slice(&signature[..]);
+        let mut serialized = Vec::new();
+        Self::serialize_into(
+            &mut serialized,
+            message_version,
+            distribution_id,
+            chain_id,
+            iteration,
+            &ciphertext,
+            csprng,
+            signature_key,
+        )?;
         Ok(Self {
             message_version: SENDERKEY_MESSAGE_CURRENT_VERSION,
             distribution_id,
@@ -479,6 +544,39 @@ impl SenderKeyMessage {
         })
     }
 
+    /// Like [`SenderKeyMessage::new`], but appends the serialized message to `buffer` instead of
+    /// creating a new `SenderKeyMessage`.
+    ///
+    /// If this fails, `buffer` is left unchanged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn serialize_into<R: CryptoRng + Rng>(
+        buffer: &mut Vec<u8>,
+        message_version: u8,
+        distribution_id: Uuid,
+        chain_id: u32,
+        iteration: u32,
+        ciphertext: &[u8],
+        csprng: &mut R,
+        signature_key: &PrivateKey,
+    ) -> Result<()> {
+        let start = buffer.len();
+        buffer.push(((message_version & 0xF) << 4) | SENDERKEY_MESSAGE_CURRENT_VERSION);
+        FieldWriter::new(buffer)
+            .bytes(1, distribution_id.as_bytes())
+            .uint32(2, chain_id)
+            .uint32(3, iteration)
+            .bytes(4, ciphertext);
+        let signature = match signature_key.calculate_signature(&buffer[start..], csprng) {
+            Ok(signature) => signature,
+            Err(e) => {
+                buffer.truncate(start);
+                return Err(e);
+            }
+        };
+        buffer.extend_from_slice(&signature[..]);
+        Ok(())
+    }
+
     pub fn verify_signature(&self, signature_key: &PublicKey) -> Result<bool> {
         let valid = signature_key.verify_signature(
             &self.serialized[..self.serialized.len() - Self::SIGNATURE_LEN],
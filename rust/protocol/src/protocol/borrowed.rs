@@ -0,0 +1,1197 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Views of serialized protocol messages that borrow from the input instead of copying it.
+//!
+//! These parse the same format as the owned message types, but keep byte fields like the
+//! ciphertext as slices of the serialized message, so parsing doesn't allocate. This matters for
+//! code that only needs to inspect a large number of messages, such as a server routing them.
+//!
+//! The owned types' `serialize_into` functions go the other way, writing a message into a
+//! caller-provided buffer using `FieldWriter`.
+
+use uuid::Uuid;
+
+use super::{
+    PreKeySignalMessage, SenderKeyMessage, SignalMessage, CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+    CIPHERTEXT_MESSAGE_PRE_KYBER_VERSION, SENDERKEY_MESSAGE_CURRENT_VERSION,
+};
+use crate::state::{KyberPreKeyId, PreKeyId, SignedPreKeyId};
+use crate::{
+    CiphertextMessageType, IdentityKey, KyberPayload, PublicKey, Result, SignalProtocolError,
+};
+
+/// A borrowed view of a serialized [`SignalMessage`].
+#[derive(Debug, Clone, Copy)]
+pub struct SignalMessageRef<'a> {
+    message_version: u8,
+    sender_ratchet_key: PublicKey,
+    counter: u32,
+    previous_counter: u32,
+    ciphertext: &'a [u8],
+    serialized: &'a [u8],
+}
+
+impl<'a> SignalMessageRef<'a> {
+    #[inline]
+    pub fn message_version(&self) -> u8 {
+        self.message_version
+    }
+
+    #[inline]
+    pub fn sender_ratchet_key(&self) -> &PublicKey {
+        &self.sender_ratchet_key
+    }
+
+    #[inline]
+    pub fn counter(&self) -> u32 {
+        self.counter
+    }
+
+    #[inline]
+    pub fn serialized(&self) -> &'a [u8] {
+        self.serialized
+    }
+
+    #[inline]
+    pub fn body(&self) -> &'a [u8] {
+        self.ciphertext
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for SignalMessageRef<'a> {
+    type Error = SignalProtocolError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        if value.len() < SignalMessage::MAC_LENGTH + 1 {
+            return Err(SignalProtocolError::CiphertextMessageTooShort(value.len()));
+        }
+        let message_version = check_ciphertext_message_version(value[0])?;
+
+        let mut ratchet_key = None;
+        let mut counter = None;
+        let mut previous_counter = None;
+        let mut ciphertext = None;
+        let mut fields = Fields::new(&value[1..value.len() - SignalMessage::MAC_LENGTH]);
+        while let Some((number, field)) = fields.next_field()? {
+            match number {
+                1 => ratchet_key = Some(field.bytes()?),
+                2 => counter = Some(field.uint32()?),
+                3 => previous_counter = Some(field.uint32()?),
+                4 => ciphertext = Some(field.bytes()?),
+                _ => {}
+            }
+        }
+
+        let sender_ratchet_key = PublicKey::deserialize(
+            ratchet_key.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+        )?;
+        Ok(Self {
+            message_version,
+            sender_ratchet_key,
+            counter: counter.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+            previous_counter: previous_counter.unwrap_or(0),
+            ciphertext: ciphertext.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+            serialized: value,
+        })
+    }
+}
+
+impl From<SignalMessageRef<'_>> for SignalMessage {
+    fn from(value: SignalMessageRef<'_>) -> Self {
+        Self {
+            message_version: value.message_version,
+            sender_ratchet_key: value.sender_ratchet_key,
+            counter: value.counter,
+            previous_counter: value.previous_counter,
+            ciphertext: value.ciphertext.into(),
+            serialized: value.serialized.into(),
+        }
+    }
+}
+
+/// A borrowed view of a serialized [`PreKeySignalMessage`].
+#[derive(Debug, Clone, Copy)]
+pub struct PreKeySignalMessageRef<'a> {
+    message_version: u8,
+    registration_id: u32,
+    pre_key_id: Option<PreKeyId>,
+    signed_pre_key_id: SignedPreKeyId,
+    kyber_payload: Option<(KyberPreKeyId, &'a [u8])>,
+    base_key: PublicKey,
+    identity_key: IdentityKey,
+    message: SignalMessageRef<'a>,
+    serialized: &'a [u8],
+}
+
+impl<'a> PreKeySignalMessageRef<'a> {
+    #[inline]
+    pub fn message_version(&self) -> u8 {
+        self.message_version
+    }
+
+    #[inline]
+    pub fn registration_id(&self) -> u32 {
+        self.registration_id
+    }
+
+    #[inline]
+    pub fn pre_key_id(&self) -> Option<PreKeyId> {
+        self.pre_key_id
+    }
+
+    #[inline]
+    pub fn signed_pre_key_id(&self) -> SignedPreKeyId {
+        self.signed_pre_key_id
+    }
+
+    #[inline]
+    pub fn kyber_pre_key_id(&self) -> Option<KyberPreKeyId> {
+        self.kyber_payload.map(|(id, _ciphertext)| id)
+    }
+
+    #[inline]
+    pub fn kyber_ciphertext(&self) -> Option<&'a [u8]> {
+        self.kyber_payload.map(|(_id, ciphertext)| ciphertext)
+    }
+
+    #[inline]
+    pub fn base_key(&self) -> &PublicKey {
+        &self.base_key
+    }
+
+    #[inline]
+    pub fn identity_key(&self) -> &IdentityKey {
+        &self.identity_key
+    }
+
+    #[inline]
+    pub fn message(&self) -> &SignalMessageRef<'a> {
+        &self.message
+    }
+
+    #[inline]
+    pub fn serialized(&self) -> &'a [u8] {
+        self.serialized
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PreKeySignalMessageRef<'a> {
+    type Error = SignalProtocolError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        if value.is_empty() {
+            return Err(SignalProtocolError::CiphertextMessageTooShort(value.len()));
+        }
+        let message_version = check_ciphertext_message_version(value[0])?;
+
+        let mut registration_id = None;
+        let mut pre_key_id = None;
+        let mut signed_pre_key_id = None;
+        let mut kyber_pre_key_id = None;
+        let mut kyber_ciphertext = None;
+        let mut base_key = None;
+        let mut identity_key = None;
+        let mut message = None;
+        let mut fields = Fields::new(&value[1..]);
+        while let Some((number, field)) = fields.next_field()? {
+            match number {
+                1 => pre_key_id = Some(field.uint32()?),
+                2 => base_key = Some(field.bytes()?),
+                3 => identity_key = Some(field.bytes()?),
+                4 => message = Some(field.bytes()?),
+                5 => registration_id = Some(field.uint32()?),
+                6 => signed_pre_key_id = Some(field.uint32()?),
+                7 => kyber_pre_key_id = Some(field.uint32()?),
+                8 => kyber_ciphertext = Some(field.bytes()?),
+                _ => {}
+            }
+        }
+
+        let base_key = base_key.ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        let identity_key = identity_key.ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        let message = message.ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        let signed_pre_key_id =
+            signed_pre_key_id.ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+
+        // Check things in the same order as the owned message, so that errors match.
+        let base_key = PublicKey::deserialize(base_key)?;
+
+        let kyber_payload = match (kyber_pre_key_id, kyber_ciphertext) {
+            (Some(id), Some(ct)) => Some((id.into(), ct)),
+            (None, None) if message_version <= CIPHERTEXT_MESSAGE_PRE_KYBER_VERSION => None,
+            (None, None) => {
+                return Err(SignalProtocolError::InvalidMessage(
+                    CiphertextMessageType::PreKey,
+                    "Kyber pre key must be present for this session version",
+                ));
+            }
+            _ => {
+                return Err(SignalProtocolError::InvalidMessage(
+                    CiphertextMessageType::PreKey,
+                    "Both or neither kyber pre_key_id and kyber_ciphertext can be present",
+                ));
+            }
+        };
+
+        Ok(Self {
+            message_version,
+            registration_id: registration_id.unwrap_or(0),
+            pre_key_id: pre_key_id.map(|id| id.into()),
+            signed_pre_key_id: signed_pre_key_id.into(),
+            kyber_payload,
+            base_key,
+            identity_key: IdentityKey::try_from(identity_key)?,
+            message: SignalMessageRef::try_from(message)?,
+            serialized: value,
+        })
+    }
+}
+
+impl From<PreKeySignalMessageRef<'_>> for PreKeySignalMessage {
+    fn from(value: PreKeySignalMessageRef<'_>) -> Self {
+        Self {
+            message_version: value.message_version,
+            registration_id: value.registration_id,
+            pre_key_id: value.pre_key_id,
+            signed_pre_key_id: value.signed_pre_key_id,
+            kyber_payload: value
+                .kyber_payload
+                .map(|(id, ciphertext)| KyberPayload::new(id, ciphertext.into())),
+            base_key: value.base_key,
+            identity_key: value.identity_key,
+            message: value.message.into(),
+            serialized: value.serialized.into(),
+        }
+    }
+}
+
+/// A borrowed view of a serialized [`SenderKeyMessage`].
+#[derive(Debug, Clone, Copy)]
+pub struct SenderKeyMessageRef<'a> {
+    message_version: u8,
+    distribution_id: Uuid,
+    chain_id: u32,
+    iteration: u32,
+    ciphertext: &'a [u8],
+    serialized: &'a [u8],
+}
+
+impl<'a> SenderKeyMessageRef<'a> {
+    pub fn verify_signature(&self, signature_key: &PublicKey) -> Result<bool> {
+        let (message, signature) = self
+            .serialized
+            .split_at(self.serialized.len() - SenderKeyMessage::SIGNATURE_LEN);
+        signature_key.verify_signature(message, signature)
+    }
+
+    #[inline]
+    pub fn message_version(&self) -> u8 {
+        self.message_version
+    }
+
+    #[inline]
+    pub fn distribution_id(&self) -> Uuid {
+        self.distribution_id
+    }
+
+    #[inline]
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    #[inline]
+    pub fn iteration(&self) -> u32 {
+        self.iteration
+    }
+
+    #[inline]
+    pub fn ciphertext(&self) -> &'a [u8] {
+        self.ciphertext
+    }
+
+    #[inline]
+    pub fn serialized(&self) -> &'a [u8] {
+        self.serialized
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for SenderKeyMessageRef<'a> {
+    type Error = SignalProtocolError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        if value.len() < 1 + SenderKeyMessage::SIGNATURE_LEN {
+            return Err(SignalProtocolError::CiphertextMessageTooShort(value.len()));
+        }
+        let message_version = value[0] >> 4;
+        if message_version < SENDERKEY_MESSAGE_CURRENT_VERSION {
+            return Err(SignalProtocolError::LegacyCiphertextVersion(
+                message_version,
+            ));
+        }
+        if message_version > SENDERKEY_MESSAGE_CURRENT_VERSION {
+            return Err(SignalProtocolError::UnrecognizedCiphertextVersion(
+                message_version,
+            ));
+        }
+
+        let mut distribution_id = None;
+        let mut chain_id = None;
+        let mut iteration = None;
+        let mut ciphertext = None;
+        let mut fields = Fields::new(&value[1..value.len() - SenderKeyMessage::SIGNATURE_LEN]);
+        while let Some((number, field)) = fields.next_field()? {
+            match number {
+                1 => distribution_id = Some(field.bytes()?),
+                2 => chain_id = Some(field.uint32()?),
+                3 => iteration = Some(field.uint32()?),
+                4 => ciphertext = Some(field.bytes()?),
+                _ => {}
+            }
+        }
+
+        let distribution_id = distribution_id
+            .and_then(|bytes| Uuid::from_slice(bytes).ok())
+            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        Ok(Self {
+            message_version,
+            distribution_id,
+            chain_id: chain_id.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+            iteration: iteration.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+            ciphertext: ciphertext.ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+            serialized: value,
+        })
+    }
+}
+
+impl From<SenderKeyMessageRef<'_>> for SenderKeyMessage {
+    fn from(value: SenderKeyMessageRef<'_>) -> Self {
+        Self {
+            message_version: value.message_version,
+            distribution_id: value.distribution_id,
+            chain_id: value.chain_id,
+            iteration: value.iteration,
+            ciphertext: value.ciphertext.into(),
+            serialized: value.serialized.into(),
+        }
+    }
+}
+
+fn check_ciphertext_message_version(first_byte: u8) -> Result<u8> {
+    let message_version = first_byte >> 4;
+    if message_version < CIPHERTEXT_MESSAGE_PRE_KYBER_VERSION {
+        return Err(SignalProtocolError::LegacyCiphertextVersion(
+            message_version,
+        ));
+    }
+    if message_version > CIPHERTEXT_MESSAGE_CURRENT_VERSION {
+        return Err(SignalProtocolError::UnrecognizedCiphertextVersion(
+            message_version,
+        ));
+    }
+    Ok(message_version)
+}
+
+/// A field value from an encoded protobuf message.
+enum FieldValue<'a> {
+    Varint(u64),
+    LengthDelimited(&'a [u8]),
+    /// A fixed-width value or a group, which none of the messages here use.
+    ///
+    /// These are only valid for unknown fields; prost rejects them for known ones.
+    Skipped,
+}
+
+impl<'a> FieldValue<'a> {
+    fn uint32(self) -> Result<u32> {
+        match self {
+            // Like prost, truncate rather than reject out-of-range values.
+            Self::Varint(value) => Ok(value as u32),
+            Self::LengthDelimited(_) | Self::Skipped => {
+                Err(SignalProtocolError::InvalidProtobufEncoding)
+            }
+        }
+    }
+
+    fn bytes(self) -> Result<&'a [u8]> {
+        match self {
+            Self::LengthDelimited(bytes) => Ok(bytes),
+            Self::Varint(_) | Self::Skipped => Err(SignalProtocolError::InvalidProtobufEncoding),
+        }
+    }
+}
+
+const WIRE_TYPE_VARINT: u32 = 0;
+const WIRE_TYPE_FIXED64: u32 = 1;
+const WIRE_TYPE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_TYPE_START_GROUP: u32 = 3;
+const WIRE_TYPE_END_GROUP: u32 = 4;
+const WIRE_TYPE_FIXED32: u32 = 5;
+
+/// Reads the fields of an encoded protobuf message in order, without copying them.
+///
+/// This accepts exactly the encodings that prost's generated decoders do. As with prost, when a
+/// field appears more than once, the caller should keep the last value.
+struct Fields<'a> {
+    remaining: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    /// How deeply groups can be nested in skipped fields; prost's default.
+    const RECURSION_LIMIT: u32 = 100;
+
+    fn new(encoded: &'a [u8]) -> Self {
+        Self { remaining: encoded }
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u32, FieldValue<'a>)>> {
+        if self.remaining.is_empty() {
+            return Ok(None);
+        }
+        let (number, wire_type) = self.key()?;
+        let value = match wire_type {
+            WIRE_TYPE_VARINT => FieldValue::Varint(self.varint()?),
+            WIRE_TYPE_LENGTH_DELIMITED => {
+                let len = self.length()?;
+                FieldValue::LengthDelimited(self.take(len)?)
+            }
+            _ => {
+                self.skip(number, wire_type, Self::RECURSION_LIMIT)?;
+                FieldValue::Skipped
+            }
+        };
+        Ok(Some((number, value)))
+    }
+
+    /// Reads a field key, returning its field number and wire type.
+    fn key(&mut self) -> Result<(u32, u32)> {
+        let key = u32::try_from(self.varint()?)
+            .map_err(|_| SignalProtocolError::InvalidProtobufEncoding)?;
+        let wire_type = key & 0b111;
+        let number = key >> 3;
+        if wire_type > WIRE_TYPE_FIXED32 || number == 0 {
+            return Err(SignalProtocolError::InvalidProtobufEncoding);
+        }
+        Ok((number, wire_type))
+    }
+
+    /// Skips the value of a field whose key has already been read, like prost's `skip_field`.
+    fn skip(&mut self, number: u32, wire_type: u32, recursion_budget: u32) -> Result<()> {
+        if recursion_budget == 0 {
+            return Err(SignalProtocolError::InvalidProtobufEncoding);
+        }
+        match wire_type {
+            WIRE_TYPE_VARINT => {
+                self.varint()?;
+            }
+            WIRE_TYPE_FIXED64 => {
+                self.take(8)?;
+            }
+            WIRE_TYPE_LENGTH_DELIMITED => {
+                let len = self.length()?;
+                self.take(len)?;
+            }
+            WIRE_TYPE_START_GROUP => loop {
+                let (inner_number, inner_wire_type) = self.key()?;
+                if inner_wire_type == WIRE_TYPE_END_GROUP {
+                    if inner_number != number {
+                        return Err(SignalProtocolError::InvalidProtobufEncoding);
+                    }
+                    break;
+                }
+                self.skip(inner_number, inner_wire_type, recursion_budget - 1)?;
+            },
+            WIRE_TYPE_FIXED32 => {
+                self.take(4)?;
+            }
+            // An end-group key without a matching start.
+            _ => return Err(SignalProtocolError::InvalidProtobufEncoding),
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining.len() {
+            return Err(SignalProtocolError::InvalidProtobufEncoding);
+        }
+        let (taken, rest) = self.remaining.split_at(len);
+        self.remaining = rest;
+        Ok(taken)
+    }
+
+    fn length(&mut self) -> Result<usize> {
+        usize::try_from(self.varint()?).map_err(|_| SignalProtocolError::InvalidProtobufEncoding)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let (&byte, rest) = self
+                .remaining
+                .split_first()
+                .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+            self.remaining = rest;
+            // The tenth byte only has room for one more bit.
+            if i == 9 && byte > 1 {
+                break;
+            }
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SignalProtocolError::InvalidProtobufEncoding)
+    }
+}
+
+/// Appends the fields of a protobuf message to a buffer, encoding them the same way prost does.
+///
+/// Fields are written in the order they're added, so callers should add them in order of field
+/// number, which is the order prost uses.
+pub(super) struct FieldWriter<'a> {
+    buffer: &'a mut Vec<u8>,
+}
+
+impl<'a> FieldWriter<'a> {
+    pub(super) fn new(buffer: &'a mut Vec<u8>) -> Self {
+        Self { buffer }
+    }
+
+    pub(super) fn uint32(&mut self, number: u32, value: u32) -> &mut Self {
+        self.key(number, WIRE_TYPE_VARINT);
+        self.varint(value.into());
+        self
+    }
+
+    pub(super) fn bytes(&mut self, number: u32, value: &[u8]) -> &mut Self {
+        self.key(number, WIRE_TYPE_LENGTH_DELIMITED);
+        self.varint(value.len() as u64);
+        self.buffer.extend_from_slice(value);
+        self
+    }
+
+    /// Writes `key.serialize()` as a `bytes` field, without allocating.
+    pub(super) fn public_key(&mut self, number: u32, key: &PublicKey) -> &mut Self {
+        self.key(number, WIRE_TYPE_LENGTH_DELIMITED);
+        self.varint(key.serialized_len() as u64);
+        key.serialize_into(self.buffer);
+        self
+    }
+
+    fn key(&mut self, number: u32, wire_type: u32) {
+        self.varint(u64::from((number << 3) | wire_type));
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use prost::Message;
+    use rand::rngs::OsRng;
+    use rand::{CryptoRng, Rng};
+
+    use super::*;
+    use crate::{proto, KeyPair};
+
+    fn create_signal_message<T: Rng + CryptoRng>(csprng: &mut T) -> Result<SignalMessage> {
+        let mut mac_key = [0u8; 32];
+        csprng.fill_bytes(&mut mac_key);
+        let mut ciphertext = [0u8; 20];
+        csprng.fill_bytes(&mut ciphertext);
+
+        SignalMessage::new(
+            4,
+            &mac_key,
+            KeyPair::generate(csprng).public_key,
+            42,
+            41,
+            &ciphertext,
+            &KeyPair::generate(csprng).public_key.into(),
+            &KeyPair::generate(csprng).public_key.into(),
+        )
+    }
+
+    #[test]
+    fn signal_message_matches_owned() -> Result<()> {
+        let message = create_signal_message(&mut OsRng)?;
+        let borrowed = SignalMessageRef::try_from(message.serialized())?;
+        assert_eq!(borrowed.message_version(), message.message_version());
+        assert_eq!(borrowed.sender_ratchet_key(), message.sender_ratchet_key());
+        assert_eq!(borrowed.counter(), message.counter());
+        assert_eq!(borrowed.body(), message.body());
+        assert_eq!(
+            borrowed.serialized().as_ptr(),
+            message.serialized().as_ptr()
+        );
+
+        let owned = SignalMessage::from(borrowed);
+        assert_eq!(owned.previous_counter, message.previous_counter);
+        assert_eq!(owned.serialized(), message.serialized());
+        Ok(())
+    }
+
+    #[test]
+    fn pre_key_signal_message_matches_owned() -> Result<()> {
+        let mut csprng = OsRng;
+        let message = PreKeySignalMessage::new(
+            4,
+            365,
+            Some(23.into()),
+            97.into(),
+            Some(KyberPayload::new(
+                7.into(),
+                vec![1, 2, 3].into_boxed_slice(),
+            )),
+            KeyPair::generate(&mut csprng).public_key,
+            KeyPair::generate(&mut csprng).public_key.into(),
+            create_signal_message(&mut csprng)?,
+        )?;
+        let borrowed = PreKeySignalMessageRef::try_from(message.serialized())?;
+        assert_eq!(borrowed.message_version(), message.message_version());
+        assert_eq!(borrowed.registration_id(), message.registration_id());
+        assert_eq!(borrowed.pre_key_id(), message.pre_key_id());
+        assert_eq!(borrowed.signed_pre_key_id(), message.signed_pre_key_id());
+        assert_eq!(borrowed.kyber_pre_key_id(), message.kyber_pre_key_id());
+        assert_eq!(
+            borrowed.kyber_ciphertext(),
+            message.kyber_ciphertext().map(|ct| &ct[..])
+        );
+        assert_eq!(borrowed.base_key(), message.base_key());
+        assert_eq!(borrowed.identity_key(), message.identity_key());
+        assert_eq!(
+            borrowed.message().serialized(),
+            message.message().serialized()
+        );
+
+        let owned = PreKeySignalMessage::from(borrowed);
+        assert_eq!(owned.serialized(), message.serialized());
+        assert_eq!(owned.message().serialized(), message.message().serialized());
+        Ok(())
+    }
+
+    #[test]
+    fn sender_key_message_matches_owned() -> Result<()> {
+        let mut csprng = OsRng;
+        let signature_key_pair = KeyPair::generate(&mut csprng);
+        let message = SenderKeyMessage::new(
+            SENDERKEY_MESSAGE_CURRENT_VERSION,
+            Uuid::from_u128(0xd1d1d1d1_7000_11eb_b32a_33b8a8a487a6),
+            42,
+            7,
+            [1u8, 2, 3].into(),
+            &mut csprng,
+            &signature_key_pair.private_key,
+        )?;
+        let borrowed = SenderKeyMessageRef::try_from(message.serialized())?;
+        assert_eq!(borrowed.message_version(), message.message_version());
+        assert_eq!(borrowed.distribution_id(), message.distribution_id());
+        assert_eq!(borrowed.chain_id(), message.chain_id());
+        assert_eq!(borrowed.iteration(), message.iteration());
+        assert_eq!(borrowed.ciphertext(), message.ciphertext());
+        assert!(borrowed.verify_signature(&signature_key_pair.public_key)?);
+
+        let owned = SenderKeyMessage::from(borrowed);
+        assert_eq!(owned.serialized(), message.serialized());
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() -> Result<()> {
+        let message = create_signal_message(&mut OsRng)?;
+        let serialized = message.serialized();
+        for len in 0..serialized.len() {
+            assert!(
+                SignalMessageRef::try_from(&serialized[..len]).is_err(),
+                "parsed message truncated to {len} bytes"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        // field 1, varint 150; field 9, bytes "hi"; field 10, fixed32; field 11, fixed64;
+        // field 12, a group containing field 1
+        let encoded = [
+            0x08, 0x96, 0x01, 0x4a, 0x02, b'h', b'i', 0x55, 1, 2, 3, 4, 0x59, 1, 2, 3, 4, 5, 6, 7,
+            8, 0x63, 0x08, 0x01, 0x64,
+        ];
+        let mut fields = Fields::new(&encoded);
+        match fields.next_field().expect("valid") {
+            Some((1, FieldValue::Varint(150))) => {}
+            _ => panic!("wrong first field"),
+        }
+        match fields.next_field().expect("valid") {
+            Some((9, FieldValue::LengthDelimited(bytes))) => assert_eq!(bytes, b"hi"),
+            _ => panic!("wrong second field"),
+        }
+        for expected_number in [10, 11, 12] {
+            match fields.next_field().expect("valid") {
+                Some((number, FieldValue::Skipped)) => assert_eq!(number, expected_number),
+                _ => panic!("field {expected_number} not skipped"),
+            }
+        }
+        assert!(fields.next_field().expect("valid").is_none());
+    }
+
+    #[test]
+    fn known_fields_with_the_wrong_wire_type_are_rejected() {
+        // field 2 (counter) as fixed32, fixed64, bytes, and a group
+        for encoded in [
+            &[0x15, 1, 2, 3, 4][..],
+            &[0x11, 1, 2, 3, 4, 5, 6, 7, 8],
+            &[0x12, 0x01, 0x00],
+            &[0x13, 0x14],
+        ] {
+            assert!(proto::wire::SignalMessage::decode(encoded).is_err());
+            assert!(decode_with_fields(encoded).is_err());
+        }
+    }
+
+    /// A field of a protobuf message, for generating encodings that may or may not be valid.
+    #[derive(Debug, Clone)]
+    enum TestField {
+        Varint(u64),
+        Bytes(Vec<u8>),
+        Fixed32(u32),
+        Fixed64(u64),
+        Group(Vec<(u32, TestField)>),
+        /// A key with the given wire type, followed by arbitrary bytes instead of a value.
+        Raw(u8, Vec<u8>),
+    }
+
+    fn push_varint(mut value: u64, buffer: &mut Vec<u8>) {
+        while value >= 0x80 {
+            buffer.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        buffer.push(value as u8);
+    }
+
+    fn push_key(number: u32, wire_type: u8, buffer: &mut Vec<u8>) {
+        // This deliberately allows field numbers too large for a valid key.
+        push_varint((u64::from(number) << 3) | u64::from(wire_type), buffer);
+    }
+
+    fn encode_fields(fields: &[(u32, TestField)], buffer: &mut Vec<u8>) {
+        for (number, field) in fields {
+            let number = *number;
+            match field {
+                TestField::Varint(value) => {
+                    push_key(number, 0, buffer);
+                    push_varint(*value, buffer);
+                }
+                TestField::Bytes(bytes) => {
+                    push_key(number, 2, buffer);
+                    push_varint(bytes.len() as u64, buffer);
+                    buffer.extend_from_slice(bytes);
+                }
+                TestField::Fixed32(value) => {
+                    push_key(number, 5, buffer);
+                    buffer.extend_from_slice(&value.to_le_bytes());
+                }
+                TestField::Fixed64(value) => {
+                    push_key(number, 1, buffer);
+                    buffer.extend_from_slice(&value.to_le_bytes());
+                }
+                TestField::Group(fields) => {
+                    push_key(number, 3, buffer);
+                    encode_fields(fields, buffer);
+                    push_key(number, 4, buffer);
+                }
+                TestField::Raw(wire_type, bytes) => {
+                    push_key(number, *wire_type, buffer);
+                    buffer.extend_from_slice(bytes);
+                }
+            }
+        }
+    }
+
+    fn field_number() -> impl Strategy<Value = u32> {
+        prop_oneof![
+            16 => 1u32..=10,
+            1 => Just(0),
+            1 => Just((1 << 29) - 1),
+            1 => Just(1 << 29),
+            1 => any::<u32>(),
+        ]
+    }
+
+    /// Generates a field of any kind, using `interesting_bytes` for some of the `bytes` values.
+    fn any_field(interesting_bytes: Vec<Vec<u8>>) -> impl Strategy<Value = (u32, TestField)> {
+        let value = prop_oneof![
+            any::<u64>().prop_map(TestField::Varint),
+            (0u64..300).prop_map(TestField::Varint),
+            proptest::sample::select(interesting_bytes).prop_map(TestField::Bytes),
+            proptest::collection::vec(any::<u8>(), 0..40).prop_map(TestField::Bytes),
+            any::<u32>().prop_map(TestField::Fixed32),
+            any::<u64>().prop_map(TestField::Fixed64),
+            (0u8..8, proptest::collection::vec(any::<u8>(), 0..4))
+                .prop_map(|(wire_type, bytes)| TestField::Raw(wire_type, bytes)),
+        ];
+        let value = value.prop_recursive(3, 16, 4, |inner| {
+            proptest::collection::vec((field_number(), inner), 0..4).prop_map(TestField::Group)
+        });
+        (field_number(), value)
+    }
+
+    /// Generates variations on a valid message: the header byte, fields, and trailer (MAC or
+    /// signature) are each sometimes changed, and other fields are added around the valid ones.
+    fn mutated_message(
+        header: u8,
+        fields: Vec<(u32, TestField)>,
+        trailer_len: usize,
+    ) -> impl Strategy<Value = Vec<u8>> {
+        let interesting_bytes: Vec<Vec<u8>> = fields
+            .iter()
+            .filter_map(|(_number, field)| match field {
+                TestField::Bytes(bytes) => Some(bytes.clone()),
+                _ => None,
+            })
+            .collect();
+        let extra_fields = || proptest::collection::vec(any_field(interesting_bytes.clone()), 0..3);
+        let fields: Vec<_> = fields
+            .into_iter()
+            .map(|field| {
+                prop_oneof![
+                    6 => Just(Some(field)),
+                    1 => Just(None),
+                    1 => any_field(interesting_bytes.clone()).prop_map(Some),
+                ]
+            })
+            .collect();
+        (
+            prop_oneof![4 => Just(header), 1 => any::<u8>()],
+            extra_fields(),
+            fields,
+            extra_fields(),
+            prop_oneof![4 => Just(trailer_len), 1 => 0..=trailer_len],
+        )
+            .prop_map(|(header, prefix, fields, suffix, trailer_len)| {
+                let fields: Vec<_> = prefix
+                    .into_iter()
+                    .chain(fields.into_iter().flatten())
+                    .chain(suffix)
+                    .collect();
+                let mut encoded = vec![header];
+                encode_fields(&fields, &mut encoded);
+                encoded.resize(encoded.len() + trailer_len, 0xaa);
+                encoded
+            })
+    }
+
+    /// Decodes a `SignalMessage` protobuf with [`Fields`], for comparison with prost.
+    fn decode_with_fields(encoded: &[u8]) -> Result<proto::wire::SignalMessage> {
+        let mut message = proto::wire::SignalMessage::default();
+        let mut fields = Fields::new(encoded);
+        while let Some((number, field)) = fields.next_field()? {
+            match number {
+                1 => message.ratchet_key = Some(field.bytes()?.to_vec()),
+                2 => message.counter = Some(field.uint32()?),
+                3 => message.previous_counter = Some(field.uint32()?),
+                4 => message.ciphertext = Some(field.bytes()?.to_vec()),
+                _ => {}
+            }
+        }
+        Ok(message)
+    }
+
+    #[test]
+    fn field_parsing_matches_prost() {
+        let encoded_fields = proptest::collection::vec(any_field(vec![vec![5; 33]]), 0..8)
+            .prop_map(|fields| {
+                let mut encoded = vec![];
+                encode_fields(&fields, &mut encoded);
+                encoded
+            });
+        let any_encoding = prop_oneof![
+            encoded_fields,
+            proptest::collection::vec(any::<u8>(), 0..40),
+        ];
+        proptest!(|(encoded in any_encoding)| {
+            prop_assert_eq!(
+                decode_with_fields(&encoded).ok(),
+                proto::wire::SignalMessage::decode(&encoded[..]).ok()
+            );
+        });
+    }
+
+    #[test]
+    fn nested_groups_match_prost() {
+        for depth in [1, 2, 99, 100, 101] {
+            for innermost in [vec![], vec![(2, TestField::Varint(1))]] {
+                let mut group = TestField::Group(innermost);
+                for _ in 1..depth {
+                    group = TestField::Group(vec![(9, group)]);
+                }
+                let mut encoded = vec![];
+                encode_fields(&[(9, group)], &mut encoded);
+                assert_eq!(
+                    decode_with_fields(&encoded).ok(),
+                    proto::wire::SignalMessage::decode(&encoded[..]).ok(),
+                    "depth {depth}"
+                );
+            }
+        }
+
+        // An end-group key without a start, and one with the wrong field number.
+        for fields in [
+            vec![(9, TestField::Raw(4, vec![]))],
+            vec![
+                (9, TestField::Raw(3, vec![])),
+                (10, TestField::Raw(4, vec![])),
+            ],
+        ] {
+            let mut encoded = vec![];
+            encode_fields(&fields, &mut encoded);
+            assert!(proto::wire::SignalMessage::decode(&encoded[..]).is_err());
+            assert!(decode_with_fields(&encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn signal_message_parsing_matches_owned() -> Result<()> {
+        let message = create_signal_message(&mut OsRng)?;
+        let fields = vec![
+            (
+                1,
+                TestField::Bytes(message.sender_ratchet_key().serialize().into()),
+            ),
+            (2, TestField::Varint(message.counter().into())),
+            (3, TestField::Varint(message.previous_counter.into())),
+            (4, TestField::Bytes(message.body().to_vec())),
+        ];
+        proptest!(|(encoded in mutated_message(
+            message.serialized()[0],
+            fields.clone(),
+            SignalMessage::MAC_LENGTH,
+        ))| {
+            prop_assert_eq!(
+                format!("{:?}", SignalMessage::try_from(&encoded[..])),
+                format!("{:?}", SignalMessageRef::try_from(&encoded[..]).map(SignalMessage::from))
+            );
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn pre_key_signal_message_parsing_matches_owned() -> Result<()> {
+        let mut csprng = OsRng;
+        let message = create_signal_message(&mut csprng)?;
+        let fields = vec![
+            (5, TestField::Varint(365)),
+            (1, TestField::Varint(23)),
+            (6, TestField::Varint(97)),
+            (7, TestField::Varint(7)),
+            (8, TestField::Bytes(vec![1, 2, 3])),
+            (
+                2,
+                TestField::Bytes(KeyPair::generate(&mut csprng).public_key.serialize().into()),
+            ),
+            (
+                3,
+                TestField::Bytes(KeyPair::generate(&mut csprng).public_key.serialize().into()),
+            ),
+            (4, TestField::Bytes(message.serialized().to_vec())),
+        ];
+        proptest!(|(encoded in mutated_message(message.serialized()[0], fields.clone(), 0))| {
+            prop_assert_eq!(
+                format!("{:?}", PreKeySignalMessage::try_from(&encoded[..])),
+                format!(
+                    "{:?}",
+                    PreKeySignalMessageRef::try_from(&encoded[..]).map(PreKeySignalMessage::from)
+                )
+            );
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn sender_key_message_parsing_matches_owned() -> Result<()> {
+        let mut csprng = OsRng;
+        let signature_key_pair = KeyPair::generate(&mut csprng);
+        let message = SenderKeyMessage::new(
+            SENDERKEY_MESSAGE_CURRENT_VERSION,
+            Uuid::from_u128(0xd1d1d1d1_7000_11eb_b32a_33b8a8a487a6),
+            42,
+            7,
+            [1u8, 2, 3].into(),
+            &mut csprng,
+            &signature_key_pair.private_key,
+        )?;
+        let fields = vec![
+            (
+                1,
+                TestField::Bytes(message.distribution_id().as_bytes().to_vec()),
+            ),
+            (2, TestField::Varint(message.chain_id().into())),
+            (3, TestField::Varint(message.iteration().into())),
+            (4, TestField::Bytes(message.ciphertext().to_vec())),
+        ];
+        proptest!(|(encoded in mutated_message(
+            message.serialized()[0],
+            fields.clone(),
+            SenderKeyMessage::SIGNATURE_LEN,
+        ))| {
+            prop_assert_eq!(
+                format!("{:?}", SenderKeyMessage::try_from(&encoded[..])),
+                format!(
+                    "{:?}",
+                    SenderKeyMessageRef::try_from(&encoded[..]).map(SenderKeyMessage::from)
+                )
+            );
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_into_matches_prost() {
+        let mut csprng = OsRng;
+        let ratchet_key = KeyPair::generate(&mut csprng).public_key;
+        let base_key = KeyPair::generate(&mut csprng).public_key;
+        let identity_key = IdentityKey::new(KeyPair::generate(&mut csprng).public_key);
+        let signature_key_pair = KeyPair::generate(&mut csprng);
+        let distribution_id = Uuid::from_u128(0xd1d1d1d1_7000_11eb_b32a_33b8a8a487a6);
+        let mac_key = [7u8; 32];
+        let any_kyber_payload =
+            proptest::option::of((any::<u32>(), proptest::collection::vec(any::<u8>(), 0..50)));
+
+        proptest!(|(
+            prefix in proptest::collection::vec(any::<u8>(), 0..8),
+            counter in any::<u32>(),
+            previous_counter in any::<u32>(),
+            ciphertext in proptest::collection::vec(any::<u8>(), 0..200),
+            pre_key_id in proptest::option::of(any::<u32>()),
+            kyber in any_kyber_payload
+        )| {
+            // Each message is appended to a buffer that already has something in it.
+            let mut buffer = prefix.clone();
+            SignalMessage::serialize_into(
+                &mut buffer,
+                CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+                &mac_key,
+                &ratchet_key,
+                counter,
+                previous_counter,
+                &ciphertext,
+                &identity_key,
+                &identity_key,
+            )
+            .expect("valid");
+            prop_assert_eq!(&buffer[..prefix.len()], &prefix[..]);
+            let signal_message = &buffer[prefix.len()..];
+            let expected = proto::wire::SignalMessage {
+                ratchet_key: Some(ratchet_key.serialize().into()),
+                counter: Some(counter),
+                previous_counter: Some(previous_counter),
+                ciphertext: Some(ciphertext.clone()),
+            }
+            .encode_to_vec();
+            prop_assert_eq!(signal_message[0], 0x44);
+            prop_assert_eq!(
+                &signal_message[1..signal_message.len() - SignalMessage::MAC_LENGTH],
+                &expected[..]
+            );
+            let parsed = SignalMessage::try_from(signal_message).expect("valid");
+            prop_assert!(parsed
+                .verify_mac(&identity_key, &identity_key, &mac_key)
+                .expect("valid"));
+
+            let mut buffer = prefix.clone();
+            PreKeySignalMessage::serialize_into(
+                &mut buffer,
+                CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+                365,
+                pre_key_id.map(Into::into),
+                97.into(),
+                kyber.as_ref().map(|(id, ciphertext)| ((*id).into(), &ciphertext[..])),
+                &base_key,
+                &identity_key,
+                signal_message,
+            );
+            prop_assert_eq!(&buffer[..prefix.len()], &prefix[..]);
+            let expected = proto::wire::PreKeySignalMessage {
+                registration_id: Some(365),
+                pre_key_id,
+                signed_pre_key_id: Some(97),
+                kyber_pre_key_id: kyber.as_ref().map(|(id, _ciphertext)| *id),
+                kyber_ciphertext: kyber.as_ref().map(|(_id, ciphertext)| ciphertext.clone()),
+                base_key: Some(base_key.serialize().into()),
+                identity_key: Some(identity_key.serialize().into()),
+                message: Some(signal_message.to_vec()),
+            }
+            .encode_to_vec();
+            prop_assert_eq!(buffer[prefix.len()], 0x44);
+            prop_assert_eq!(&buffer[prefix.len() + 1..], &expected[..]);
+
+            let mut buffer = prefix.clone();
+            SenderKeyMessage::serialize_into(
+                &mut buffer,
+                SENDERKEY_MESSAGE_CURRENT_VERSION,
+                distribution_id,
+                counter,
+                previous_counter,
+                &ciphertext,
+                &mut OsRng,
+                &signature_key_pair.private_key,
+            )
+            .expect("valid");
+            prop_assert_eq!(&buffer[..prefix.len()], &prefix[..]);
+            let sender_key_message = &buffer[prefix.len()..];
+            let expected = proto::wire::SenderKeyMessage {
+                distribution_uuid: Some(distribution_id.as_bytes().to_vec()),
+                chain_id: Some(counter),
+                iteration: Some(previous_counter),
+                ciphertext: Some(ciphertext.clone()),
+            }
+            .encode_to_vec();
+            prop_assert_eq!(sender_key_message[0], 0x33);
+            prop_assert_eq!(
+                &sender_key_message[1..sender_key_message.len() - SenderKeyMessage::SIGNATURE_LEN],
+                &expected[..]
+            );
+            let parsed = SenderKeyMessage::try_from(sender_key_message).expect("valid");
+            prop_assert!(parsed
+                .verify_signature(&signature_key_pair.public_key)
+                .expect("valid"));
+        });
+    }
+
+    #[test]
+    fn failed_serialize_into_leaves_the_buffer_unchanged() {
+        let mut csprng = OsRng;
+        let key = KeyPair::generate(&mut csprng).public_key;
+        let mut buffer = vec![1, 2, 3];
+        SignalMessage::serialize_into(
+            &mut buffer,
+            CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+            b"too short",
+            &key,
+            1,
+            0,
+            b"ciphertext",
+            &key.into(),
+            &key.into(),
+        )
+        .expect_err("invalid MAC key");
+        assert_eq!(buffer, [1, 2, 3]);
+    }
+
+    #[test]
+    fn overlong_varints_are_rejected() {
+        let encoded = [
+            0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02,
+        ];
+        assert!(Fields::new(&encoded).next_field().is_err());
+    }
+}
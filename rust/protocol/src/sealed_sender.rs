@@ -24,6 +24,7 @@ use subtle::ConstantTimeEq;
 
 use proto::sealed_sender::unidentified_sender_message::message::Type as ProtoMessageType;
 
+use std::num::NonZeroUsize;
 use std::ops::Range;
 use std::time::SystemTime;
 
@@ -808,6 +809,89 @@ pub async fn sealed_sender_encrypt<R: Rng + CryptoRng>(
     sealed_sender_encrypt_from_usmc(destination, &usmc, identity_store, rng).await
 }
 
+/// Like [`sealed_sender_encrypt`], but pads `ptext` according to `padding` before encrypting it.
+///
+/// The recipient can check and strip the padding by passing the same policy as
+/// [`SealedSenderDecryptOptions::padding`].
+#[allow(clippy::too_many_arguments)]
+pub async fn sealed_sender_encrypt_with_padding<R: Rng + CryptoRng>(
+    destination: &ProtocolAddress,
+    sender_cert: &SenderCertificate,
+    ptext: &[u8],
+    padding: SealedSenderPadding,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    now: SystemTime,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    sealed_sender_encrypt(
+        destination,
+        sender_cert,
+        &padding.pad(ptext),
+        session_store,
+        identity_store,
+        now,
+        rng,
+    )
+    .await
+}
+
+/// A padding policy for the inner plaintext of a sealed sender message, so that the length of the
+/// message only reveals which size bucket its plaintext falls in.
+///
+/// The plaintext is followed by a single `0x80` byte and then zero bytes up to the next multiple of
+/// the bucket size. The padding is never empty, so a plaintext that already fills a whole number of
+/// buckets gains another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SealedSenderPadding {
+    bucket_size: NonZeroUsize,
+}
+
+impl SealedSenderPadding {
+    const PADDING_START: u8 = 0x80;
+
+    pub fn new(bucket_size: NonZeroUsize) -> Self {
+        Self { bucket_size }
+    }
+
+    pub fn bucket_size(&self) -> NonZeroUsize {
+        self.bucket_size
+    }
+
+    /// Pads `plaintext` to the end of its bucket.
+    pub fn pad(&self, plaintext: &[u8]) -> Vec<u8> {
+        let bucket_size = self.bucket_size.get();
+        let padded_len = (plaintext.len() / bucket_size + 1) * bucket_size;
+
+        let mut padded = Vec::with_capacity(padded_len);
+        padded.extend_from_slice(plaintext);
+        padded.push(Self::PADDING_START);
+        padded.resize(padded_len, 0);
+        padded
+    }
+
+    /// Checks that `padded` was produced by [`SealedSenderPadding::pad`] with this policy, and
+    /// returns the original plaintext if so.
+    pub fn unpad<'a>(&self, padded: &'a [u8]) -> Option<&'a [u8]> {
+        let bucket_size = self.bucket_size.get();
+        if padded.len() % bucket_size != 0 {
+            return None;
+        }
+        let start = padded.iter().rposition(|&b| b != 0)?;
+        if padded[start] != Self::PADDING_START || padded.len() - start > bucket_size {
+            return None;
+        }
+        Some(&padded[..start])
+    }
+}
+
+impl Default for SealedSenderPadding {
+    /// 160-byte buckets, the size that Signal clients pad message content to.
+    fn default() -> Self {
+        Self::new(NonZeroUsize::new(160).expect("nonzero"))
+    }
+}
+
 /// This method implements the single-key single-recipient [KEM] described in [this Signal blog
 /// post], a.k.a. Sealed Sender v1.
 ///
@@ -1278,8 +1362,87 @@ where
         identity_store,
         rng,
         sealed_sender_v2::USE_LEGACY_EPHEMERAL_KEY_DERIVATION_FOR_ENCRYPT,
+        None,
     )
     .await
+    .map(|(serialized, _failures)| serialized)
+}
+
+/// What [`sealed_sender_multi_recipient_encrypt_reporting_failures`] should do with recipients that
+/// can't be encrypted for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailedRecipientPolicy {
+    /// Report every failed recipient, but don't produce a message.
+    Report,
+    /// Report every failed recipient, and produce a message for the remaining recipients that lists
+    /// the failed ones as excluded.
+    ExcludeAndReport,
+}
+
+/// A recipient that a multi-recipient sealed sender message couldn't be encrypted for.
+#[derive(Debug)]
+pub struct SealedSenderMultiRecipientFailure {
+    pub recipient: ServiceId,
+    /// Why encryption failed, such as [`SignalProtocolError::SessionNotFound`] or
+    /// [`SignalProtocolError::InvalidRegistrationId`].
+    pub error: SignalProtocolError,
+}
+
+/// The result of [`sealed_sender_multi_recipient_encrypt_reporting_failures`].
+#[derive(Debug)]
+pub struct SealedSenderMultiRecipientEncryptResult {
+    /// The encrypted message, or `None` if any recipient failed under
+    /// [`FailedRecipientPolicy::Report`].
+    pub message: Option<Vec<u8>>,
+    /// Every recipient that couldn't be encrypted for, in the order they appeared in
+    /// `destinations`.
+    pub failures: Vec<SealedSenderMultiRecipientFailure>,
+}
+
+/// Like [`sealed_sender_multi_recipient_encrypt`], but doesn't give up on the first recipient that
+/// can't be encrypted for.
+///
+/// Recipients whose identity key is missing, whose session has no current state, or whose session
+/// has an invalid registration ID are collected into
+/// [`failures`](SealedSenderMultiRecipientEncryptResult::failures) instead of failing the whole
+/// batch; what happens to them then is determined by `failed_recipient_policy`. Errors that don't
+/// belong to a single recipient, like a destination name that isn't a [ServiceId], still fail the
+/// whole call.
+///
+/// A recipient's devices don't have to be contiguous in `destinations`; they are gathered
+/// together first, so that each recipient is either encrypted for or excluded as a whole.
+pub async fn sealed_sender_multi_recipient_encrypt_reporting_failures<
+    R: Rng + CryptoRng,
+    X: IntoIterator<Item = ServiceId>,
+>(
+    destinations: &[&ProtocolAddress],
+    destination_sessions: &[&SessionRecord],
+    excluded_recipients: X,
+    usmc: &UnidentifiedSenderMessageContent,
+    identity_store: &dyn IdentityKeyStore,
+    failed_recipient_policy: FailedRecipientPolicy,
+    rng: &mut R,
+) -> Result<SealedSenderMultiRecipientEncryptResult>
+where
+    X::IntoIter: ExactSizeIterator,
+{
+    let (serialized, failures) = sealed_sender_multi_recipient_encrypt_impl(
+        destinations,
+        destination_sessions,
+        excluded_recipients,
+        usmc,
+        identity_store,
+        rng,
+        sealed_sender_v2::USE_LEGACY_EPHEMERAL_KEY_DERIVATION_FOR_ENCRYPT,
+        Some(failed_recipient_policy),
+    )
+    .await?;
+
+    let message = match failed_recipient_policy {
+        FailedRecipientPolicy::Report if !failures.is_empty() => None,
+        FailedRecipientPolicy::Report | FailedRecipientPolicy::ExcludeAndReport => Some(serialized),
+    };
+    Ok(SealedSenderMultiRecipientEncryptResult { message, failures })
 }
 
 /// For testing only.
@@ -1305,10 +1468,13 @@ where
         identity_store,
         rng,
         false,
+        None,
     )
     .await
+    .map(|(serialized, _failures)| serialized)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn sealed_sender_multi_recipient_encrypt_impl<
     R: Rng + CryptoRng,
     X: IntoIterator<Item = ServiceId>,
@@ -1320,7 +1486,9 @@ async fn sealed_sender_multi_recipient_encrypt_impl<
     identity_store: &dyn IdentityKeyStore,
     rng: &mut R,
     should_use_legacy_ephemeral_key_derivation: bool,
-) -> Result<Vec<u8>>
+    // If None, the first failed recipient fails the whole call.
+    failed_recipient_policy: Option<FailedRecipientPolicy>,
+) -> Result<(Vec<u8>, Vec<SealedSenderMultiRecipientFailure>)>
 where
     X::IntoIter: ExactSizeIterator,
 {
@@ -1330,6 +1498,16 @@ where
         ));
     }
 
+    // When failures are handled per recipient, a recipient's devices must succeed or fail
+    // together, so gather each recipient's devices into a single group.
+    let regrouped: (Vec<&ProtocolAddress>, Vec<&SessionRecord>);
+    let (destinations, destination_sessions) = if failed_recipient_policy.is_some() {
+        regrouped = group_destinations_by_service_id(destinations, destination_sessions)?;
+        (&regrouped.0[..], &regrouped.1[..])
+    } else {
+        (destinations, destination_sessions)
+    };
+
     let excluded_recipients = excluded_recipients.into_iter();
     let our_identity = identity_store.get_identity_key_pair().await?;
 
@@ -1360,35 +1538,40 @@ where
         ciphertext
     };
 
+    let mut failures = vec![];
+
     // Group the destinations by name, and fetch identity keys once for each name. This optimizes
     // for the common case where all of a recipient's devices are included contiguously in the
     // destination list. (If the caller *doesn't* do this, that's on them; the message will still be
     // valid but some key material will be redundantly computed and encoded in the output.)
-    let identity_keys_and_ranges: Vec<(IdentityKey, Range<usize>)> = {
+    let identity_keys_and_ranges: Vec<(ServiceId, IdentityKey, Range<usize>)> = {
         let mut identity_keys_and_ranges = vec![];
-        for (_, mut next_group) in &destinations
-            .iter()
-            .enumerate()
-            .group_by(|(_i, next)| next.name())
-        {
+        for (_, mut next_group) in &destinations.iter().enumerate().group_by(|(_i, next)| {
+            ServiceId::parse_from_service_id_string(next.name()).ok_or_else(|| next.name())
+        }) {
             let (i, &destination) = next_group
                 .next()
                 .expect("at least one element in every group");
             // We can't put this before the call to `next()` because `count` consumes the rest of
             // the iterator.
             let count = 1 + next_group.count();
-            let their_identity =
-                identity_store
-                    .get_identity(destination)
-                    .await?
-                    .ok_or_else(|| {
-                        log::error!("missing identity key for {}", destination);
-                        // Returned as a SessionNotFound error because (a) we don't have an identity
-                        // error that includes the address, and (b) re-establishing the session should
-                        // re-fetch the identity.
-                        SignalProtocolError::SessionNotFound(destination.clone())
-                    })?;
-            identity_keys_and_ranges.push((their_identity, i..i + count));
+            let their_service_id = parse_destination_service_id(destination)?;
+            let Some(their_identity) = identity_store.get_identity(destination).await? else {
+                log::error!("missing identity key for {}", destination);
+                // Reported as a SessionNotFound error because (a) we don't have an identity error
+                // that includes the address, and (b) re-establishing the session should re-fetch
+                // the identity.
+                let error = SignalProtocolError::SessionNotFound(destination.clone());
+                if failed_recipient_policy.is_none() {
+                    return Err(error);
+                }
+                failures.push(SealedSenderMultiRecipientFailure {
+                    recipient: their_service_id,
+                    error,
+                });
+                continue;
+            };
+            identity_keys_and_ranges.push((their_service_id, their_identity, i..i + count));
         }
         identity_keys_and_ranges
     };
@@ -1401,16 +1584,9 @@ where
     let serialize_recipient_destinations_into = |serialized: &mut Vec<u8>,
                                                  destinations: &[&ProtocolAddress],
                                                  sessions: &[&SessionRecord],
+                                                 their_service_id: ServiceId,
                                                  their_identity: &IdentityKey|
      -> Result<()> {
-        let their_service_id = ServiceId::parse_from_service_id_string(destinations[0].name())
-            .ok_or_else(|| {
-                SignalProtocolError::InvalidArgument(format!(
-                    "multi-recipient sealed sender requires recipients' ServiceId (not {})",
-                    destinations[0].name()
-                ))
-            })?;
-
         serialized.extend_from_slice(&their_service_id.service_id_fixed_width_binary());
 
         debug_assert_eq!(
@@ -1477,26 +1653,37 @@ where
         Ok(())
     };
 
-    let process_chunk =
-        |serialized: &mut Vec<u8>, chunk: &[(IdentityKey, Range<usize>)]| -> Result<()> {
-            for (their_identity, destination_range) in chunk {
-                let these_destinations = &destinations[destination_range.clone()];
-                let these_sessions = &destination_sessions[destination_range.clone()];
-                serialize_recipient_destinations_into(
-                    serialized,
-                    these_destinations,
-                    these_sessions,
-                    their_identity,
-                )?;
+    // Returns the recipients in the chunk that failed, removing anything partially serialized for
+    // them.
+    let process_chunk = |serialized: &mut Vec<u8>,
+                         chunk: &[(ServiceId, IdentityKey, Range<usize>)]|
+     -> Result<Vec<SealedSenderMultiRecipientFailure>> {
+        let mut failures = vec![];
+        for (their_service_id, their_identity, destination_range) in chunk {
+            let these_destinations = &destinations[destination_range.clone()];
+            let these_sessions = &destination_sessions[destination_range.clone()];
+            let start = serialized.len();
+            if let Err(error) = serialize_recipient_destinations_into(
+                serialized,
+                these_destinations,
+                these_sessions,
+                *their_service_id,
+                their_identity,
+            ) {
+                if failed_recipient_policy.is_none() {
+                    return Err(error);
+                }
+                serialized.truncate(start);
+                failures.push(SealedSenderMultiRecipientFailure {
+                    recipient: *their_service_id,
+                    error,
+                });
             }
-            Ok(())
-        };
-
-    let mut serialized: Vec<u8> = vec![SEALED_SENDER_V2_SERVICE_ID_FULL_VERSION];
+        }
+        Ok(failures)
+    };
 
-    let count_of_recipients = identity_keys_and_ranges.len() + excluded_recipients.len();
-    prost::encode_length_delimiter(count_of_recipients, &mut serialized)
-        .expect("can always resize a Vec");
+    let mut serialized_recipients: Vec<u8> = vec![];
 
     // Fan out to N threads, like Rayon would. But don't bother for less than 6 items.
     let parallelism = std::thread::available_parallelism()
@@ -1507,17 +1694,17 @@ where
         crate::utils::div_ceil(identity_keys_and_ranges.len(), parallelism),
     );
 
-    if parallelism == 1 || chunk_size >= identity_keys_and_ranges.len() {
-        process_chunk(&mut serialized, &identity_keys_and_ranges)?;
+    let chunk_failures = if parallelism == 1 || chunk_size >= identity_keys_and_ranges.len() {
+        process_chunk(&mut serialized_recipients, &identity_keys_and_ranges)?
     } else {
         let mut chunks = identity_keys_and_ranges.chunks(chunk_size);
         // We'll process the first chunk on the current thread once we've spawned all the others.
         let first_chunk = chunks.next().expect("at least one chunk, tested above");
 
         let mut all_outputs = Vec::new();
-        all_outputs.resize_with(chunks.len(), || Ok(vec![]));
+        all_outputs.resize_with(chunks.len(), || Ok((vec![], vec![])));
 
-        rayon::scope(|scope| -> Result<()> {
+        let mut chunk_failures = rayon::scope(|scope| -> Result<_> {
             let mut outputs = &mut all_outputs[..];
             for chunk in chunks {
                 let (next_output, remaining_outputs) = outputs
@@ -1525,20 +1712,46 @@ where
                     .expect("as many outputs as remaining chunks");
                 scope.spawn(|_| {
                     let mut serialized = vec![];
-                    *next_output = process_chunk(&mut serialized, chunk).map(|_| serialized);
+                    *next_output = process_chunk(&mut serialized, chunk)
+                        .map(|failures| (serialized, failures));
                 });
                 outputs = remaining_outputs;
             }
 
-            process_chunk(&mut serialized, first_chunk)
+            process_chunk(&mut serialized_recipients, first_chunk)
         })?;
 
         for output in all_outputs {
-            serialized.extend(output?);
+            let (output, failures) = output?;
+            serialized_recipients.extend(output);
+            chunk_failures.extend(failures);
         }
-    }
+        chunk_failures
+    };
+
+    let included_count = identity_keys_and_ranges.len() - chunk_failures.len();
+    failures.extend(chunk_failures);
+
+    let excluded_failures: Vec<ServiceId> =
+        if failed_recipient_policy == Some(FailedRecipientPolicy::ExcludeAndReport) {
+            failures
+                .iter()
+                .map(|failure| failure.recipient)
+                .unique()
+                .collect()
+        } else {
+            vec![]
+        };
+
+    let mut serialized: Vec<u8> = vec![SEALED_SENDER_V2_SERVICE_ID_FULL_VERSION];
 
-    for excluded in excluded_recipients {
+    let count_of_recipients = included_count + excluded_recipients.len() + excluded_failures.len();
+    prost::encode_length_delimiter(count_of_recipients, &mut serialized)
+        .expect("can always resize a Vec");
+
+    serialized.extend(serialized_recipients);
+
+    for excluded in excluded_recipients.chain(excluded_failures) {
         serialized.extend_from_slice(&excluded.service_id_fixed_width_binary());
         serialized.push(0);
     }
@@ -1546,7 +1759,36 @@ where
     serialized.extend_from_slice(e_pub.public_key_bytes()?);
     serialized.extend_from_slice(&ciphertext);
 
-    Ok(serialized)
+    Ok((serialized, failures))
+}
+
+fn parse_destination_service_id(destination: &ProtocolAddress) -> Result<ServiceId> {
+    ServiceId::parse_from_service_id_string(destination.name()).ok_or_else(|| {
+        SignalProtocolError::InvalidArgument(format!(
+            "multi-recipient sealed sender requires recipients' ServiceId (not {})",
+            destination.name()
+        ))
+    })
+}
+
+/// Reorders `destinations` (and their sessions) so that each recipient's devices are contiguous,
+/// keeping recipients in the order they first appear.
+fn group_destinations_by_service_id<'a, 'b>(
+    destinations: &[&'a ProtocolAddress],
+    destination_sessions: &[&'b SessionRecord],
+) -> Result<(Vec<&'a ProtocolAddress>, Vec<&'b SessionRecord>)> {
+    let mut indices_by_service_id: IndexMap<ServiceId, Vec<usize>> = IndexMap::new();
+    for (i, destination) in destinations.iter().enumerate() {
+        indices_by_service_id
+            .entry(parse_destination_service_id(destination)?)
+            .or_default()
+            .push(i);
+    }
+    Ok(indices_by_service_id
+        .into_values()
+        .flatten()
+        .map(|i| (destinations[i], destination_sessions[i]))
+        .unzip())
 }
 
 /// Represents a single recipient in an SSv2 SentMessage.
@@ -1971,6 +2213,9 @@ impl SealedSenderDecryptionResult {
 /// the embedded [`SenderCertificate`]. The sender certificate (signed by the [`ServerCertificate`])
 /// is then validated against the `trust_root` baked into the client to ensure that the sender's
 /// identity was not forged.
+///
+/// See [`sealed_sender_decrypt_with_options`] for a version that reports more about the message and
+/// about why it couldn't be decrypted.
 #[allow(clippy::too_many_arguments)]
 pub async fn sealed_sender_decrypt(
     ciphertext: &[u8],
@@ -1985,72 +2230,312 @@ pub async fn sealed_sender_decrypt(
     signed_pre_key_store: &dyn SignedPreKeyStore,
     kyber_pre_key_store: &mut dyn KyberPreKeyStore,
 ) -> Result<SealedSenderDecryptionResult> {
+    sealed_sender_decrypt_with_options(
+        ciphertext,
+        trust_root,
+        timestamp,
+        local_e164,
+        local_uuid,
+        local_device_id,
+        identity_store,
+        session_store,
+        pre_key_store,
+        signed_pre_key_store,
+        kyber_pre_key_store,
+        SealedSenderDecryptOptions::default(),
+    )
+    .await
+    .map(Into::into)
+    .map_err(|failure| failure.error.into())
+}
+
+/// Extra checks and reporting for [`sealed_sender_decrypt_with_options`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SealedSenderDecryptOptions {
+    /// The largest inner plaintext to accept, in bytes.
+    ///
+    /// This is checked against the length of the inner ciphertext before it is decrypted. Since
+    /// that ciphertext is made of whole 16-byte blocks, the limit is effectively rounded up to one
+    /// less than a multiple of 16. When [`SealedSenderDecryptOptions::padding`] is set, the limit
+    /// applies to the padded plaintext.
+    pub max_plaintext_size: Option<usize>,
+    /// The padding the inner plaintext must have, which is stripped from the returned message.
+    ///
+    /// This can only be checked once the message has been decrypted, so a message with the wrong
+    /// padding has already advanced the session when it is rejected.
+    pub padding: Option<SealedSenderPadding>,
+    /// The local time at which the message was received, used to fill in
+    /// [`SealedSenderDecryptionMetadata::timestamp_skew_millis`].
+    ///
+    /// This is also recorded as the time the session was last used; if it is not given, the
+    /// system clock is used instead. The server-delivered timestamp is never used for this.
+    pub local_time: Option<Timestamp>,
+}
+
+/// What was learned about a sealed sender message from its sender certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealedSenderDecryptionMetadata {
+    pub sender_uuid: String,
+    pub sender_e164: Option<String>,
+    pub sender_device_id: DeviceId,
+    pub certificate_expiration: Timestamp,
+    /// How far the server-delivered timestamp is ahead of
+    /// [`SealedSenderDecryptOptions::local_time`], in milliseconds, or `None` if no local time was
+    /// given. Negative if the server's timestamp is behind.
+    pub timestamp_skew_millis: Option<i64>,
+}
+
+/// A successful result of [`sealed_sender_decrypt_with_options`].
+#[derive(Debug)]
+pub struct SealedSenderDecryptedMessage {
+    pub metadata: SealedSenderDecryptionMetadata,
+    pub message: Vec<u8>,
+}
+
+impl From<SealedSenderDecryptedMessage> for SealedSenderDecryptionResult {
+    fn from(value: SealedSenderDecryptedMessage) -> Self {
+        let SealedSenderDecryptedMessage { metadata, message } = value;
+        Self {
+            sender_uuid: metadata.sender_uuid,
+            sender_e164: metadata.sender_e164,
+            device_id: metadata.sender_device_id,
+            message,
+        }
+    }
+}
+
+/// Why [`sealed_sender_decrypt_with_options`] failed.
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub enum SealedSenderDecryptionError {
+    /// {0}
+    Protocol(#[from] SignalProtocolError),
+    /// sender certificate failed validation against the trust root
+    UntrustedCertificate,
+    /// self send of a sealed sender message
+    SelfSend,
+    /// plaintext of at least {size} bytes is larger than the maximum of {max_size}
+    PlaintextTooLarge { size: usize, max_size: usize },
+    /// plaintext is not padded to a multiple of {bucket_size} bytes
+    InvalidPadding { bucket_size: usize },
+}
+
+impl From<SealedSenderDecryptionError> for SignalProtocolError {
+    fn from(value: SealedSenderDecryptionError) -> Self {
+        match value {
+            SealedSenderDecryptionError::Protocol(e) => e,
+            SealedSenderDecryptionError::UntrustedCertificate => {
+                SignalProtocolError::InvalidSealedSenderMessage(
+                    "trust root validation failed".to_string(),
+                )
+            }
+            SealedSenderDecryptionError::SelfSend => SignalProtocolError::SealedSenderSelfSend,
+            e @ (SealedSenderDecryptionError::PlaintextTooLarge { .. }
+            | SealedSenderDecryptionError::InvalidPadding { .. }) => {
+                SignalProtocolError::InvalidSealedSenderMessage(e.to_string())
+            }
+        }
+    }
+}
+
+/// A failed result of [`sealed_sender_decrypt_with_options`].
+#[derive(Debug)]
+pub struct SealedSenderDecryptionFailure {
+    pub error: SealedSenderDecryptionError,
+    /// The sender's details, if the message got far enough to reveal them.
+    ///
+    /// These come from the sender certificate, which can only be trusted if `error` is not
+    /// [`SealedSenderDecryptionError::UntrustedCertificate`].
+    pub metadata: Option<SealedSenderDecryptionMetadata>,
+}
+
+impl From<SignalProtocolError> for SealedSenderDecryptionFailure {
+    fn from(error: SignalProtocolError) -> Self {
+        Self {
+            error: error.into(),
+            metadata: None,
+        }
+    }
+}
+
+impl std::fmt::Display for SealedSenderDecryptionFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for SealedSenderDecryptionFailure {}
+
+/// Like [`sealed_sender_decrypt`], but reports what it can about the message even when decryption
+/// fails, and can reject oversized or incorrectly padded plaintexts.
+///
+/// Once the outer layer is decrypted, every result, successful or not, includes the sender
+/// information from the [`SenderCertificate`], so that callers can tell who a failed message was
+/// from without parsing error strings.
+#[allow(clippy::too_many_arguments)]
+pub async fn sealed_sender_decrypt_with_options(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: Timestamp,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: DeviceId,
+    identity_store: &mut dyn IdentityKeyStore,
+    session_store: &mut dyn SessionStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &dyn SignedPreKeyStore,
+    kyber_pre_key_store: &mut dyn KyberPreKeyStore,
+    options: SealedSenderDecryptOptions,
+) -> std::result::Result<SealedSenderDecryptedMessage, SealedSenderDecryptionFailure> {
     let usmc = sealed_sender_decrypt_to_usmc(ciphertext, identity_store).await?;
 
-    if !usmc.sender()?.validate(trust_root, timestamp)? {
-        return Err(SignalProtocolError::InvalidSealedSenderMessage(
-            "trust root validation failed".to_string(),
-        ));
+    let sender = usmc.sender()?;
+    let metadata = SealedSenderDecryptionMetadata {
+        sender_uuid: sender.sender_uuid()?.to_string(),
+        sender_e164: sender.sender_e164()?.map(|s| s.to_string()),
+        sender_device_id: sender.sender_device_id()?,
+        certificate_expiration: sender.expiration()?,
+        timestamp_skew_millis: options
+            .local_time
+            .map(|local_time| timestamp.epoch_millis() as i64 - local_time.epoch_millis() as i64),
+    };
+    let fail = |error: SealedSenderDecryptionError| SealedSenderDecryptionFailure {
+        error,
+        metadata: Some(metadata.clone()),
+    };
+
+    if !sender
+        .validate(trust_root, timestamp)
+        .map_err(|e| fail(e.into()))?
+    {
+        return Err(fail(SealedSenderDecryptionError::UntrustedCertificate));
     }
 
-    let is_local_uuid = local_uuid == usmc.sender()?.sender_uuid()?;
+    let is_local_uuid = local_uuid == metadata.sender_uuid;
 
-    let is_local_e164 = match (local_e164, usmc.sender()?.sender_e164()?) {
-        (Some(l), Some(s)) => l == s,
+    let is_local_e164 = match (local_e164, &metadata.sender_e164) {
+        (Some(l), Some(s)) => &l == s,
         (_, _) => false,
     };
 
-    if (is_local_e164 || is_local_uuid) && usmc.sender()?.sender_device_id()? == local_device_id {
-        return Err(SignalProtocolError::SealedSenderSelfSend);
+    if (is_local_e164 || is_local_uuid) && metadata.sender_device_id == local_device_id {
+        return Err(fail(SealedSenderDecryptionError::SelfSend));
     }
 
     let mut rng = rand::rngs::OsRng;
+    let now = options
+        .local_time
+        .map_or_else(SystemTime::now, SystemTime::from);
+
+    let remote_address =
+        ProtocolAddress::new(metadata.sender_uuid.clone(), metadata.sender_device_id);
+
+    let decrypted = async {
+        let message = match usmc.msg_type()? {
+            CiphertextMessageType::Whisper => {
+                let ctext = SignalMessage::try_from(usmc.contents()?)?;
+                check_plaintext_size(ctext.body(), options.max_plaintext_size)?;
+                session_cipher::message_decrypt_signal_with_time(
+                    &ctext,
+                    &remote_address,
+                    session_store,
+                    identity_store,
+                    now,
+                    &mut rng,
+                )
+                .await?
+            }
+            CiphertextMessageType::PreKey => {
+                let ctext = PreKeySignalMessage::try_from(usmc.contents()?)?;
+                check_plaintext_size(ctext.message().body(), options.max_plaintext_size)?;
+                session_cipher::message_decrypt_prekey_with_time(
+                    &ctext,
+                    &remote_address,
+                    session_store,
+                    identity_store,
+                    pre_key_store,
+                    signed_pre_key_store,
+                    kyber_pre_key_store,
+                    now,
+                    &mut rng,
+                )
+                .await?
+            }
+            msg_type => {
+                return Err(SealedSenderDecryptionError::Protocol(
+                    SignalProtocolError::InvalidMessage(
+                        msg_type,
+                        "unexpected message type for sealed_sender_decrypt",
+                    ),
+                ))
+            }
+        };
+        Ok::<_, SealedSenderDecryptionError>(message)
+    }
+    .await;
+    let mut message = decrypted.map_err(fail)?;
 
-    let remote_address = ProtocolAddress::new(
-        usmc.sender()?.sender_uuid()?.to_string(),
-        usmc.sender()?.sender_device_id()?,
-    );
+    if let Some(padding) = options.padding {
+        let unpadded_len = padding
+            .unpad(&message)
+            .ok_or_else(|| {
+                fail(SealedSenderDecryptionError::InvalidPadding {
+                    bucket_size: padding.bucket_size().get(),
+                })
+            })?
+            .len();
+        message.truncate(unpadded_len);
+    }
 
-    let message = match usmc.msg_type()? {
-        CiphertextMessageType::Whisper => {
-            let ctext = SignalMessage::try_from(usmc.contents()?)?;
-            session_cipher::message_decrypt_signal(
-                &ctext,
-                &remote_address,
-                session_store,
-                identity_store,
-                &mut rng,
-            )
-            .await?
-        }
-        CiphertextMessageType::PreKey => {
-            let ctext = PreKeySignalMessage::try_from(usmc.contents()?)?;
-            session_cipher::message_decrypt_prekey(
-                &ctext,
-                &remote_address,
-                session_store,
-                identity_store,
-                pre_key_store,
-                signed_pre_key_store,
-                kyber_pre_key_store,
-                &mut rng,
-            )
-            .await?
-        }
-        msg_type => {
-            return Err(SignalProtocolError::InvalidMessage(
-                msg_type,
-                "unexpected message type for sealed_sender_decrypt",
-            ));
-        }
+    Ok(SealedSenderDecryptedMessage { metadata, message })
+}
+
+/// Rejects an inner message whose AES-CBC `body` can only hold a plaintext larger than `max_size`.
+fn check_plaintext_size(
+    body: &[u8],
+    max_size: Option<usize>,
+) -> std::result::Result<(), SealedSenderDecryptionError> {
+    const BLOCK_SIZE: usize = 16;
+    let Some(max_size) = max_size else {
+        return Ok(());
     };
+    // PKCS#7 padding always adds between 1 and BLOCK_SIZE bytes.
+    let smallest_plaintext = body.len().saturating_sub(BLOCK_SIZE);
+    if smallest_plaintext > max_size {
+        return Err(SealedSenderDecryptionError::PlaintextTooLarge {
+            size: smallest_plaintext,
+            max_size,
+        });
+    }
+    Ok(())
+}
 
-    Ok(SealedSenderDecryptionResult {
-        sender_uuid: usmc.sender()?.sender_uuid()?.to_string(),
-        sender_e164: usmc.sender()?.sender_e164()?.map(|s| s.to_string()),
-        device_id: usmc.sender()?.sender_device_id()?,
-        message,
-    })
+#[test]
+fn test_padding() {
+    let padding = SealedSenderPadding::new(NonZeroUsize::new(16).expect("nonzero"));
+
+    for len in [0, 1, 15, 16, 17, 100] {
+        let plaintext = vec![0xaa; len];
+        let padded = padding.pad(&plaintext);
+        assert_eq!(padded.len(), (len / 16 + 1) * 16);
+        assert_eq!(padding.unpad(&padded), Some(plaintext.as_slice()));
+    }
+
+    // Trailing zeros in the plaintext are kept.
+    assert_eq!(
+        padding.unpad(&padding.pad(&[1, 0, 0])),
+        Some([1, 0, 0].as_slice())
+    );
+
+    let mut padded = padding.pad(&[1, 2, 3]);
+    assert_eq!(padding.unpad(&padded[..15]), None, "not a whole bucket");
+    padded[15] = 1;
+    assert_eq!(padding.unpad(&padded), None, "nonzero padding");
+    assert_eq!(padding.unpad(&[0; 16]), None, "no padding start");
+    assert_eq!(padding.unpad(&[]), None, "empty");
+
+    let mut too_much_padding = vec![0; 32];
+    too_much_padding[0] = 0x80;
+    assert_eq!(padding.unpad(&too_much_padding), None, "extra bucket");
 }
 
 #[test]
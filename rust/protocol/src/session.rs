@@ -231,6 +231,7 @@ pub async fn process_prekey_bundle<R: Rng + CryptoRng>(
         .await?;
 
     session_record.promote_state(session);
+    session_record.set_last_used(now);
 
     session_store
         .store_session(remote_address, &session_record)
@@ -144,6 +144,7 @@ pub async fn message_encrypt(
         .save_identity(remote_address, &their_identity_key)
         .await?;
 
+    session_record.set_last_used(now);
     session_store
         .store_session(remote_address, &session_record)
         .await?;
@@ -160,13 +161,49 @@ pub async fn message_decrypt<R: Rng + CryptoRng>(
     signed_pre_key_store: &dyn SignedPreKeyStore,
     kyber_pre_key_store: &mut dyn KyberPreKeyStore,
     csprng: &mut R,
+) -> Result<Vec<u8>> {
+    message_decrypt_with_time(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        kyber_pre_key_store,
+        SystemTime::now(),
+        csprng,
+    )
+    .await
+}
+
+/// Like [`message_decrypt`], but records `now` as the time the session was last used instead of
+/// reading the system clock.
+#[allow(clippy::too_many_arguments)]
+pub async fn message_decrypt_with_time<R: Rng + CryptoRng>(
+    ciphertext: &CiphertextMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &dyn SignedPreKeyStore,
+    kyber_pre_key_store: &mut dyn KyberPreKeyStore,
+    now: SystemTime,
+    csprng: &mut R,
 ) -> Result<Vec<u8>> {
     match ciphertext {
         CiphertextMessage::SignalMessage(m) => {
-            message_decrypt_signal(m, remote_address, session_store, identity_store, csprng).await
+            message_decrypt_signal_with_time(
+                m,
+                remote_address,
+                session_store,
+                identity_store,
+                now,
+                csprng,
+            )
+            .await
         }
         CiphertextMessage::PreKeySignalMessage(m) => {
-            message_decrypt_prekey(
+            message_decrypt_prekey_with_time(
                 m,
                 remote_address,
                 session_store,
@@ -174,6 +211,7 @@ pub async fn message_decrypt<R: Rng + CryptoRng>(
                 pre_key_store,
                 signed_pre_key_store,
                 kyber_pre_key_store,
+                now,
                 csprng,
             )
             .await
@@ -195,6 +233,34 @@ pub async fn message_decrypt_prekey<R: Rng + CryptoRng>(
     signed_pre_key_store: &dyn SignedPreKeyStore,
     kyber_pre_key_store: &mut dyn KyberPreKeyStore,
     csprng: &mut R,
+) -> Result<Vec<u8>> {
+    message_decrypt_prekey_with_time(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        kyber_pre_key_store,
+        SystemTime::now(),
+        csprng,
+    )
+    .await
+}
+
+/// Like [`message_decrypt_prekey`], but records `now` as the time the session was last used
+/// instead of reading the system clock.
+#[allow(clippy::too_many_arguments)]
+pub async fn message_decrypt_prekey_with_time<R: Rng + CryptoRng>(
+    ciphertext: &PreKeySignalMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &dyn SignedPreKeyStore,
+    kyber_pre_key_store: &mut dyn KyberPreKeyStore,
+    now: SystemTime,
+    csprng: &mut R,
 ) -> Result<Vec<u8>> {
     let mut session_record = session_store
         .load_session(remote_address)
@@ -239,6 +305,7 @@ pub async fn message_decrypt_prekey<R: Rng + CryptoRng>(
         csprng,
     )?;
 
+    session_record.set_last_used(now);
     session_store
         .store_session(remote_address, &session_record)
         .await?;
@@ -262,6 +329,27 @@ pub async fn message_decrypt_signal<R: Rng + CryptoRng>(
     session_store: &mut dyn SessionStore,
     identity_store: &mut dyn IdentityKeyStore,
     csprng: &mut R,
+) -> Result<Vec<u8>> {
+    message_decrypt_signal_with_time(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        SystemTime::now(),
+        csprng,
+    )
+    .await
+}
+
+/// Like [`message_decrypt_signal`], but records `now` as the time the session was last used
+/// instead of reading the system clock.
+pub async fn message_decrypt_signal_with_time<R: Rng + CryptoRng>(
+    ciphertext: &SignalMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    now: SystemTime,
+    csprng: &mut R,
 ) -> Result<Vec<u8>> {
     let mut session_record = session_store
         .load_session(remote_address)
@@ -305,6 +393,7 @@ pub async fn message_decrypt_signal<R: Rng + CryptoRng>(
         .save_identity(remote_address, &their_identity_key)
         .await?;
 
+    session_record.set_last_used(now);
     session_store
         .store_session(remote_address, &session_record)
         .await?;
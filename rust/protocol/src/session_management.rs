@@ -0,0 +1,93 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Maintenance operations that apply to many sessions at once.
+//!
+//! Each operation loads all the affected records with one call to
+//! [`SessionStore::load_sessions`] and writes back only the ones that changed with one call to
+//! [`SessionStore::store_sessions`].
+
+use std::time::{Duration, SystemTime};
+
+use crate::{ProtocolAddress, Result, SessionRecord, SessionStore};
+
+/// Loads the sessions for `addresses`, applies `update` to each one that exists, and stores the
+/// ones for which `update` returned `true`.
+///
+/// Returns the addresses whose sessions were changed.
+async fn update_sessions(
+    addresses: &[ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+    mut update: impl FnMut(&mut SessionRecord) -> Result<bool>,
+) -> Result<Vec<ProtocolAddress>> {
+    let records = session_store.load_sessions(addresses).await?;
+
+    let mut changed = Vec::new();
+    for (address, record) in addresses.iter().zip(records) {
+        let Some(mut record) = record else {
+            continue;
+        };
+        if update(&mut record)? {
+            changed.push((address.clone(), record));
+        }
+    }
+
+    if !changed.is_empty() {
+        session_store.store_sessions(&changed).await?;
+    }
+    Ok(changed.into_iter().map(|(address, _)| address).collect())
+}
+
+/// Archives the current session state for each of `addresses`.
+///
+/// This is equivalent to calling [`SessionRecord::archive_current_state`] on each address's
+/// session and storing it again; addresses with no session, or no current session state, are
+/// skipped. Returns the addresses whose sessions were archived.
+pub async fn archive_sessions(
+    addresses: &[ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+) -> Result<Vec<ProtocolAddress>> {
+    update_sessions(addresses, session_store, |record| {
+        Ok(record.archive_current_state_if_present())
+    })
+    .await
+}
+
+/// Archives the current session state for each of `addresses` that has not been used to encrypt or
+/// decrypt a message within `max_age` of `now`.
+///
+/// Sessions that have never recorded a use (because they were last stored by an older version of
+/// this library) are left alone, as are sessions whose expiration would be too far in the future
+/// to represent. Returns the addresses whose sessions were archived.
+pub async fn expire_sessions(
+    addresses: &[ProtocolAddress],
+    max_age: Duration,
+    session_store: &mut dyn SessionStore,
+    now: SystemTime,
+) -> Result<Vec<ProtocolAddress>> {
+    update_sessions(addresses, session_store, |record| {
+        let is_expired = record
+            .last_used()
+            .and_then(|last_used| last_used.checked_add(max_age))
+            .is_some_and(|expiration| expiration < now);
+        Ok(is_expired && record.archive_current_state_if_present())
+    })
+    .await
+}
+
+/// Discards all but the `max_previous_sessions` most recent archived session states for each of
+/// `addresses`.
+///
+/// The current session state is never affected. Returns the addresses whose sessions were pruned.
+pub async fn prune_previous_sessions(
+    addresses: &[ProtocolAddress],
+    max_previous_sessions: usize,
+    session_store: &mut dyn SessionStore,
+) -> Result<Vec<ProtocolAddress>> {
+    update_sessions(addresses, session_store, |record| {
+        Ok(record.truncate_previous_session_states(max_previous_sessions))
+    })
+    .await
+}
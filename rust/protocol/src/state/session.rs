@@ -585,6 +585,7 @@ impl From<&SessionState> for SessionStructure {
 pub struct SessionRecord {
     current_session: Option<SessionState>,
     previous_sessions: Vec<Vec<u8>>,
+    last_used_timestamp: u64,
 }
 
 impl SessionRecord {
@@ -592,6 +593,7 @@ impl SessionRecord {
         Self {
             current_session: None,
             previous_sessions: Vec::new(),
+            last_used_timestamp: 0,
         }
     }
 
@@ -599,6 +601,7 @@ impl SessionRecord {
         Self {
             current_session: Some(state),
             previous_sessions: Vec::new(),
+            last_used_timestamp: 0,
         }
     }
 
@@ -609,6 +612,7 @@ impl SessionRecord {
         Ok(Self {
             current_session: record.current_session.map(|s| s.into()),
             previous_sessions: record.previous_sessions,
+            last_used_timestamp: record.last_used_timestamp,
         })
     }
 
@@ -692,6 +696,11 @@ impl SessionRecord {
         }
     }
 
+    /// Like [`Self::archive_current_state`], but returns whether there was a state to archive.
+    pub(crate) fn archive_current_state_if_present(&mut self) -> bool {
+        self.archive_current_state_inner()
+    }
+
     pub fn archive_current_state(&mut self) -> Result<(), SignalProtocolError> {
         if !self.archive_current_state_inner() {
             log::info!("Skipping archive, current session state is fresh");
@@ -699,10 +708,41 @@ impl SessionRecord {
         Ok(())
     }
 
+    /// Discards all but the `max_count` most recent previous session states.
+    ///
+    /// Returns `true` if any states were discarded.
+    pub(crate) fn truncate_previous_session_states(&mut self, max_count: usize) -> bool {
+        if self.previous_sessions.len() <= max_count {
+            return false;
+        }
+        self.previous_sessions.truncate(max_count);
+        true
+    }
+
+    /// The number of archived session states kept alongside the current one.
+    pub fn previous_session_count(&self) -> usize {
+        self.previous_sessions.len()
+    }
+
+    /// When this record was last used to encrypt or decrypt a message, to the nearest second.
+    ///
+    /// Returns `None` for records that have never been used since this started being tracked.
+    pub fn last_used(&self) -> Option<SystemTime> {
+        (self.last_used_timestamp != 0)
+            .then(|| SystemTime::UNIX_EPOCH + Duration::from_secs(self.last_used_timestamp))
+    }
+
+    pub(crate) fn set_last_used(&mut self, now: SystemTime) {
+        self.last_used_timestamp = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+    }
+
     pub fn serialize(&self) -> Result<Vec<u8>, SignalProtocolError> {
         let record = RecordStructure {
             current_session: self.current_session.as_ref().map(|s| s.into()),
             previous_sessions: self.previous_sessions.clone(),
+            last_used_timestamp: self.last_used_timestamp,
         };
         Ok(record.encode_to_vec())
     }
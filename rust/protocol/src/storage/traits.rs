@@ -140,6 +140,32 @@ pub trait SessionStore {
         address: &ProtocolAddress,
         record: &SessionRecord,
     ) -> Result<()>;
+
+    /// Look up the sessions corresponding to each of `addresses`, in order.
+    ///
+    /// The default implementation calls [`Self::load_session`] for each address. Stores that can
+    /// fetch several records at once should override this.
+    async fn load_sessions(
+        &self,
+        addresses: &[ProtocolAddress],
+    ) -> Result<Vec<Option<SessionRecord>>> {
+        let mut records = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            records.push(self.load_session(address).await?);
+        }
+        Ok(records)
+    }
+
+    /// Set the entry for each address in `records` to the corresponding record.
+    ///
+    /// The default implementation calls [`Self::store_session`] for each entry. Stores that can
+    /// write several records at once should override this.
+    async fn store_sessions(&mut self, records: &[(ProtocolAddress, SessionRecord)]) -> Result<()> {
+        for (address, record) in records {
+            self.store_session(address, record).await?;
+        }
+        Ok(())
+    }
 }
 
 /// Interface for storing sender key records, allowing multiple keys per user.
@@ -10,7 +10,7 @@ use futures_util::FutureExt;
 use libsignal_protocol::*;
 use rand::rngs::OsRng;
 
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 #[test]
@@ -301,6 +301,424 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
     .expect("sync")
 }
 
+#[test]
+fn test_sealed_sender_decrypt_with_options() -> Result<(), SignalProtocolError> {
+    async {
+        let mut rng = OsRng;
+
+        let alice_device_id: DeviceId = 23.into();
+        let bob_device_id: DeviceId = 42.into();
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let alice_pubkey = *alice_store.get_identity_key_pair().await?.public_key();
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut rng).await?;
+
+        process_prekey_bundle(
+            &bob_uuid_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let expires = Timestamp::from_epoch_millis(1605722925);
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            expires,
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let expected_metadata = |timestamp_skew_millis| SealedSenderDecryptionMetadata {
+            sender_uuid: alice_uuid.clone(),
+            sender_e164: None,
+            sender_device_id: alice_device_id,
+            certificate_expiration: expires,
+            timestamp_skew_millis,
+        };
+
+        let alice_ptext = vec![1, 2, 3, 23, 99];
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let server_timestamp = expires.sub_millis(100);
+        let decrypted = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            server_timestamp,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions {
+                max_plaintext_size: Some(alice_ptext.len()),
+                local_time: Some(server_timestamp.add_millis(25)),
+                padding: None,
+            },
+        )
+        .await
+        .expect("can decrypt");
+
+        assert_eq!(decrypted.message, alice_ptext);
+        assert_eq!(decrypted.metadata, expected_metadata(Some(-25)));
+
+        let alice_uuid_address = ProtocolAddress::new(alice_uuid.clone(), alice_device_id);
+        let last_used = |bob_store: &InMemSignalProtocolStore| {
+            bob_store
+                .session_store
+                .load_existing_sessions(&[&alice_uuid_address])
+                .expect("has session")[0]
+                .last_used()
+        };
+        // Last use is recorded to the second.
+        let whole_seconds = |time: SystemTime| {
+            let since_epoch = time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .expect("after 1970");
+            SystemTime::UNIX_EPOCH + Duration::from_secs(since_epoch.as_secs())
+        };
+        assert_eq!(
+            last_used(&bob_store),
+            Some(whole_seconds(server_timestamp.add_millis(25).into()))
+        );
+
+        // Without a local time, the session's last use comes from the system clock, not the
+        // server timestamp.
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let before_decrypting = SystemTime::now();
+        sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            server_timestamp,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions::default(),
+        )
+        .await
+        .expect("can decrypt");
+
+        assert!(last_used(&bob_store).expect("recorded") >= whole_seconds(before_decrypting));
+
+        // Now with an expired cert: the sender is still reported.
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let failure = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.add_millis(11),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions::default(),
+        )
+        .await
+        .expect_err("should fail");
+
+        assert!(matches!(
+            failure.error,
+            SealedSenderDecryptionError::UntrustedCertificate
+        ));
+        assert_eq!(failure.metadata, Some(expected_metadata(None)));
+
+        // And with a plaintext that's too large, which is rejected before decrypting.
+
+        let large_ptext = vec![7; 40];
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &large_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let failure = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.sub_millis(1),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions {
+                max_plaintext_size: Some(20),
+                local_time: None,
+                padding: None,
+            },
+        )
+        .await
+        .expect_err("should fail");
+
+        // 40 bytes are encrypted as 48, which could hold as few as 32.
+        assert!(matches!(
+            failure.error,
+            SealedSenderDecryptionError::PlaintextTooLarge {
+                size: 32,
+                max_size: 20
+            }
+        ));
+        assert_eq!(failure.metadata, Some(expected_metadata(None)));
+
+        // The session didn't advance, so the message can still be decrypted without the limit.
+        let decrypted = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.sub_millis(1),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions::default(),
+        )
+        .await
+        .expect("can decrypt");
+        assert_eq!(decrypted.message, large_ptext);
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
+#[test]
+fn test_sealed_sender_padding() -> Result<(), SignalProtocolError> {
+    async {
+        let mut rng = OsRng;
+
+        let alice_device_id: DeviceId = 23.into();
+        let bob_device_id: DeviceId = 42.into();
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let alice_pubkey = *alice_store.get_identity_key_pair().await?.public_key();
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut rng).await?;
+
+        process_prekey_bundle(
+            &bob_uuid_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let expires = Timestamp::from_epoch_millis(1605722925);
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            expires,
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let padding = SealedSenderPadding::default();
+        let with_padding = SealedSenderDecryptOptions {
+            padding: Some(padding),
+            ..Default::default()
+        };
+        let alice_ptext = vec![1, 2, 3, 23, 99];
+
+        let alice_ctext = sealed_sender_encrypt_with_padding(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            padding,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let decrypted = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.sub_millis(1),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            with_padding,
+        )
+        .await
+        .expect("can decrypt");
+        assert_eq!(decrypted.message, alice_ptext);
+
+        // Without the policy, the padding is left in place.
+
+        let alice_ctext = sealed_sender_encrypt_with_padding(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            padding,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let decrypted = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.sub_millis(1),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            SealedSenderDecryptOptions::default(),
+        )
+        .await
+        .expect("can decrypt");
+        assert_eq!(decrypted.message.len(), padding.bucket_size().get());
+        assert_eq!(decrypted.message, padding.pad(&alice_ptext));
+
+        // A message without padding is rejected, but still reports its sender.
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            SystemTime::now(),
+            &mut rng,
+        )
+        .await?;
+
+        let failure = sealed_sender_decrypt_with_options(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires.sub_millis(1),
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            &mut bob_store.kyber_pre_key_store,
+            with_padding,
+        )
+        .await
+        .expect_err("should fail");
+
+        assert!(matches!(
+            failure.error,
+            SealedSenderDecryptionError::InvalidPadding { bucket_size: 160 }
+        ));
+        assert_eq!(
+            failure.metadata.map(|metadata| metadata.sender_uuid),
+            Some(alice_uuid)
+        );
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
 #[test]
 fn test_sender_key_in_sealed_sender() -> Result<(), SignalProtocolError> {
     async {
@@ -976,6 +1394,265 @@ fn test_sealed_sender_multi_recipient_encrypt_with_bad_registration_id(
     .expect("sync")
 }
 
+#[test]
+fn test_sealed_sender_multi_recipient_encrypt_reporting_failures() -> Result<(), SignalProtocolError>
+{
+    async {
+        let mut rng = OsRng;
+
+        let alice_device_id: DeviceId = 23.into();
+        let bob_device_id: DeviceId = 42.into();
+        let carol_device_id: DeviceId = 1.into();
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+        let carol_uuid = "38381c3b-2606-4ca7-9310-7cb927f2ab4a".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+        let carol_uuid_address = ProtocolAddress::new(carol_uuid.clone(), carol_device_id);
+        let bob_service_id = ServiceId::parse_from_service_id_string(&bob_uuid).unwrap();
+        let carol_service_id = ServiceId::parse_from_service_id_string(&carol_uuid).unwrap();
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+        let mut carol_store = support::test_in_memory_protocol_store()?;
+
+        let alice_pubkey = *alice_store.get_identity_key_pair().await?.public_key();
+
+        for (address, store) in [
+            (&bob_uuid_address, &mut bob_store),
+            (&carol_uuid_address, &mut carol_store),
+        ] {
+            let pre_key_bundle = create_pre_key_bundle(store, &mut rng).await?;
+            process_prekey_bundle(
+                address,
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                &pre_key_bundle,
+                SystemTime::now(),
+                &mut rng,
+            )
+            .await?;
+        }
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            Timestamp::from_epoch_millis(1605722925),
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let alice_usmc = UnidentifiedSenderMessageContent::new(
+            CiphertextMessageType::SenderKey,
+            sender_cert,
+            vec![],
+            ContentHint::Implicit,
+            Some([42].to_vec()),
+        )?;
+
+        let bob_session = alice_store
+            .session_store
+            .load_session(&bob_uuid_address)
+            .await?
+            .expect("present");
+        let mut carol_session = alice_store
+            .session_store
+            .load_session(&carol_uuid_address)
+            .await?
+            .expect("present");
+        carol_session.archive_current_state()?;
+
+        let recipients = [&bob_uuid_address, &carol_uuid_address];
+        let sessions = [&bob_session, &carol_session];
+
+        let reported = sealed_sender_multi_recipient_encrypt_reporting_failures(
+            &recipients,
+            &sessions,
+            [],
+            &alice_usmc,
+            &alice_store.identity_store,
+            FailedRecipientPolicy::Report,
+            &mut rng,
+        )
+        .await?;
+        assert!(reported.message.is_none());
+        assert_eq!(reported.failures.len(), 1);
+        assert_eq!(reported.failures[0].recipient, carol_service_id);
+        let description = reported.failures[0].error.to_string();
+        assert!(
+            description.contains(&carol_uuid_address.to_string()),
+            "should mention recipient in message \"{}\"",
+            description
+        );
+
+        let excluded = sealed_sender_multi_recipient_encrypt_reporting_failures(
+            &recipients,
+            &sessions,
+            [],
+            &alice_usmc,
+            &alice_store.identity_store,
+            FailedRecipientPolicy::ExcludeAndReport,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(excluded.failures.len(), 1);
+        assert_eq!(excluded.failures[0].recipient, carol_service_id);
+
+        let message = excluded
+            .message
+            .expect("produced for the remaining recipients");
+        let parsed = SealedSenderV2SentMessage::parse(&message)?;
+        assert_eq!(parsed.recipients.len(), 2);
+        assert_eq!(parsed.recipients[&bob_service_id].devices.len(), 1);
+        assert!(parsed.recipients[&carol_service_id].devices.is_empty());
+
+        // Without a policy, the first failure still fails the whole batch.
+        assert!(sealed_sender_multi_recipient_encrypt(
+            &recipients,
+            &sessions,
+            [],
+            &alice_usmc,
+            &alice_store.identity_store,
+            &mut rng,
+        )
+        .await
+        .is_err());
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
+#[test]
+fn test_sealed_sender_multi_recipient_encrypt_reporting_failures_for_scattered_devices(
+) -> Result<(), SignalProtocolError> {
+    async {
+        let mut rng = OsRng;
+
+        let alice_device_id: DeviceId = 23.into();
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+        let carol_uuid = "38381c3b-2606-4ca7-9310-7cb927f2ab4a".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), 42.into());
+        let carol_first_address = ProtocolAddress::new(carol_uuid.clone(), 1.into());
+        let carol_second_address = ProtocolAddress::new(carol_uuid.clone(), 2.into());
+        let bob_service_id = ServiceId::parse_from_service_id_string(&bob_uuid).unwrap();
+        let carol_service_id = ServiceId::parse_from_service_id_string(&carol_uuid).unwrap();
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let alice_pubkey = *alice_store.get_identity_key_pair().await?.public_key();
+
+        for address in [
+            &bob_uuid_address,
+            &carol_first_address,
+            &carol_second_address,
+        ] {
+            let mut store = support::test_in_memory_protocol_store()?;
+            let pre_key_bundle = create_pre_key_bundle(&mut store, &mut rng).await?;
+            process_prekey_bundle(
+                address,
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                &pre_key_bundle,
+                SystemTime::now(),
+                &mut rng,
+            )
+            .await?;
+        }
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            Timestamp::from_epoch_millis(1605722925),
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let alice_usmc = UnidentifiedSenderMessageContent::new(
+            CiphertextMessageType::SenderKey,
+            sender_cert,
+            vec![],
+            ContentHint::Implicit,
+            Some([42].to_vec()),
+        )?;
+
+        let bob_session = alice_store
+            .session_store
+            .load_session(&bob_uuid_address)
+            .await?
+            .expect("present");
+        let mut carol_first_session = alice_store
+            .session_store
+            .load_session(&carol_first_address)
+            .await?
+            .expect("present");
+        carol_first_session.archive_current_state()?;
+        let carol_second_session = alice_store
+            .session_store
+            .load_session(&carol_second_address)
+            .await?
+            .expect("present");
+
+        // Carol's working device comes after Bob, apart from her broken one.
+        let recipients = [
+            &carol_first_address,
+            &bob_uuid_address,
+            &carol_second_address,
+        ];
+        let sessions = [&carol_first_session, &bob_session, &carol_second_session];
+
+        let excluded = sealed_sender_multi_recipient_encrypt_reporting_failures(
+            &recipients,
+            &sessions,
+            [],
+            &alice_usmc,
+            &alice_store.identity_store,
+            FailedRecipientPolicy::ExcludeAndReport,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(excluded.failures.len(), 1);
+        assert_eq!(excluded.failures[0].recipient, carol_service_id);
+
+        let message = excluded
+            .message
+            .expect("produced for the remaining recipients");
+        let parsed = SealedSenderV2SentMessage::parse(&message)?;
+        assert_eq!(parsed.recipients.len(), 2);
+        assert_eq!(parsed.recipients[&bob_service_id].devices.len(), 1);
+        assert!(
+            parsed.recipients[&carol_service_id].devices.is_empty(),
+            "carol must be excluded, not encrypted for"
+        );
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
 #[test]
 fn test_decryption_error_in_sealed_sender() -> Result<(), SignalProtocolError> {
     async {
@@ -232,6 +232,85 @@ fn test_basic_prekey() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_prekey_bundles_with_mixed_kem_types() -> TestResult {
+    async {
+        let mut csprng = OsRng;
+
+        let bob_device_id: DeviceId = 1.into();
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), bob_device_id);
+
+        // Bob uploaded a Kyber1024 pre-key before switching to ML-KEM.
+        let mut bob_store_builder = TestStoreBuilder::new()
+            .with_pre_key(IdChoice::Next)
+            .with_signed_pre_key(IdChoice::Next)
+            .with_kyber_pre_key(IdChoice::Next);
+        let old_bundle = bob_store_builder.make_bundle_with_latest_keys(bob_device_id);
+
+        bob_store_builder.add_pre_key(IdChoice::Next);
+        bob_store_builder.add_kyber_pre_key_of_type(IdChoice::Next, kem::KeyType::MLKEM768);
+        let ml_kem768_bundle = bob_store_builder.make_bundle_with_latest_keys(bob_device_id);
+
+        bob_store_builder.add_pre_key(IdChoice::Next);
+        bob_store_builder.add_kyber_pre_key_of_type(IdChoice::Next, kem::KeyType::MLKEM1024Final);
+        let ml_kem1024_bundle = bob_store_builder.make_bundle_with_latest_keys(bob_device_id);
+
+        for (i, (bundle, expected_key_type)) in [
+            (old_bundle, kem::KeyType::Kyber1024),
+            (ml_kem768_bundle, kem::KeyType::MLKEM768),
+            (ml_kem1024_bundle, kem::KeyType::MLKEM1024Final),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            assert_eq!(
+                bundle.kyber_pre_key_public()?.map(|k| k.key_type()),
+                Some(expected_key_type)
+            );
+
+            let sender_address = ProtocolAddress::new(format!("+1415222222{i}"), 1.into());
+            let mut sender_store = TestStoreBuilder::new().store;
+
+            process_prekey_bundle(
+                &bob_address,
+                &mut sender_store.session_store,
+                &mut sender_store.identity_store,
+                &bundle,
+                SystemTime::now(),
+                &mut csprng,
+            )
+            .await?;
+            assert_eq!(
+                sender_store.session_version(&bob_address)?,
+                KYBER_AWARE_MESSAGE_VERSION
+            );
+
+            let original_message = format!("message encapsulated with {expected_key_type}");
+            let outgoing_message =
+                encrypt(&mut sender_store, &bob_address, &original_message).await?;
+            assert_eq!(
+                outgoing_message.message_type(),
+                CiphertextMessageType::PreKey
+            );
+
+            let ptext = decrypt(
+                &mut bob_store_builder.store,
+                &sender_address,
+                &outgoing_message,
+            )
+            .await?;
+            assert_eq!(
+                String::from_utf8(ptext).expect("valid utf8"),
+                original_message
+            );
+        }
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
 #[test]
 #[ignore = "slow to run locally"]
 fn test_chain_jump_over_limit() -> TestResult {
@@ -743,6 +822,72 @@ fn test_optional_one_time_prekey() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_bulk_session_management() -> TestResult {
+    async {
+        let mut store = test_in_memory_protocol_store()?;
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let addresses: Vec<_> = (0..4)
+            .map(|i| ProtocolAddress::new(format!("+1415555000{i}"), 1.into()))
+            .collect();
+        for address in &addresses {
+            let (alice_session, _bob_session) = initialize_sessions_v4()?;
+            assert_eq!(alice_session.last_used(), None);
+            store.store_session(address, &alice_session).await?;
+        }
+
+        // Have addresses[2] and addresses[3] last used 10 days and 1 day ago, respectively.
+        for (address, used_at) in [(&addresses[2], now - 10 * day), (&addresses[3], now - day)] {
+            message_encrypt(
+                b"hello",
+                address,
+                &mut store.session_store,
+                &mut store.identity_store,
+                used_at,
+            )
+            .await?;
+        }
+
+        let missing = ProtocolAddress::new("+14155559999".to_owned(), 1.into());
+        let archived = archive_sessions(
+            &[addresses[0].clone(), addresses[1].clone(), missing],
+            &mut store.session_store,
+        )
+        .await?;
+        assert_eq!(archived, &addresses[..2]);
+
+        let expired =
+            expire_sessions(&addresses, Duration::MAX, &mut store.session_store, now).await?;
+        assert_eq!(expired, &[] as &[ProtocolAddress]);
+
+        let expired = expire_sessions(&addresses, 7 * day, &mut store.session_store, now).await?;
+        assert_eq!(expired, &addresses[2..3]);
+
+        for (i, address) in addresses.iter().enumerate() {
+            let record = store.load_session(address).await?.expect("still present");
+            assert_eq!(record.has_usable_sender_chain(now)?, i == 3, "{address}");
+            assert_eq!(
+                record.previous_session_count(),
+                usize::from(i < 3),
+                "{address}"
+            );
+        }
+
+        let pruned = prune_previous_sessions(&addresses, 0, &mut store.session_store).await?;
+        assert_eq!(pruned, &addresses[..3]);
+        for address in &addresses {
+            let record = store.load_session(address).await?.expect("still present");
+            assert_eq!(record.previous_session_count(), 0, "{address}");
+        }
+
+        Ok(())
+    }
+    .now_or_never()
+    .expect("sync")
+}
+
 #[test]
 fn test_basic_session() -> TestResult {
     let (alice_session, bob_session) = initialize_sessions_v3()?;
@@ -47,6 +47,10 @@ pub(crate) const DISCRIMINATOR_RANGES: [Range<usize>; 8] = [
     100_000_000..1_000_000_000,
 ];
 
+/// Discriminators must be smaller than this; the largest have nine digits.
+pub(crate) const DISCRIMINATOR_LIMIT: usize =
+    DISCRIMINATOR_RANGES[DISCRIMINATOR_RANGES.len() - 1].end;
+
 pub(crate) const CANDIDATES_PER_RANGE: [usize; 8] = [4, 3, 3, 2, 2, 2, 2, 2];
 
 pub const USERNAME_LINK_ENTROPY_SIZE: usize = 32;
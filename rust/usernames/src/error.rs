@@ -29,6 +29,8 @@ pub enum UsernameError {
     BadDiscriminatorCharacter,
     /// Value is too large to be a username discriminator
     DiscriminatorTooLarge,
+    /// Discriminator range is empty, overlaps another range, or has fewer values than requested
+    InvalidDiscriminatorRange,
 }
 
 #[derive(displaydoc::Display, Debug, thiserror::Error)]
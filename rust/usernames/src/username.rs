@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Add, Range, RangeInclusive};
 use std::str::FromStr;
@@ -10,14 +11,15 @@ use std::str::FromStr;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use lazy_static::lazy_static;
-use rand::Rng;
+use rand::{CryptoRng, Rng};
 use sha2::{Digest, Sha512};
 
 use poksho::args::{PointArgs, ScalarArgs};
 use poksho::{PokshoError, Statement};
 
 use crate::constants::{
-    BASE_POINTS, CANDIDATES_PER_RANGE, DISCRIMINATOR_RANGES, MAX_NICKNAME_LENGTH,
+    BASE_POINTS, CANDIDATES_PER_RANGE, DISCRIMINATOR_LIMIT, DISCRIMINATOR_RANGES,
+    MAX_NICKNAME_LENGTH,
 };
 use crate::error::{ProofVerificationFailure, UsernameError};
 
@@ -94,6 +96,113 @@ impl NicknameLimits {
     }
 }
 
+/// Determines which discriminators are used when generating username candidates.
+///
+/// A policy is a list of discriminator ranges, each with the number of candidates to pick from
+/// it at random. Candidates are produced in the order the ranges were added, so callers that
+/// prefer short discriminators should add the short ranges first. The default policy picks a few
+/// candidates of every length from 2 to 9 digits, favoring the shorter ones.
+#[derive(Clone, Debug)]
+pub struct DiscriminatorPolicy {
+    ranges: Vec<Range<usize>>,
+    counts: Vec<usize>,
+    excluded: HashSet<usize>,
+}
+
+impl Default for DiscriminatorPolicy {
+    fn default() -> Self {
+        Self {
+            ranges: DISCRIMINATOR_RANGES.to_vec(),
+            counts: CANDIDATES_PER_RANGE.to_vec(),
+            excluded: HashSet::new(),
+        }
+    }
+}
+
+impl DiscriminatorPolicy {
+    /// A policy with no ranges, to be filled in with [`with_range`](Self::with_range).
+    pub fn empty() -> Self {
+        Self {
+            ranges: vec![],
+            counts: vec![],
+            excluded: HashSet::new(),
+        }
+    }
+
+    /// Adds a range to pick `count` discriminators from, after all the ranges added so far.
+    ///
+    /// The range must not overlap any of the ones already added, and must have at least `count`
+    /// values. Unlike the default policy, it cannot include single-digit discriminators, which
+    /// would come out with a leading zero.
+    pub fn with_range(mut self, range: Range<usize>, count: usize) -> Result<Self, UsernameError> {
+        if range.start == 0 {
+            return Err(UsernameError::DiscriminatorCannotBeZero);
+        }
+        if range.start < 10 {
+            return Err(UsernameError::DiscriminatorCannotBeSingleDigit);
+        }
+        if range.end > DISCRIMINATOR_LIMIT {
+            return Err(UsernameError::DiscriminatorTooLarge);
+        }
+        let overlaps = |other: &Range<usize>| range.start < other.end && other.start < range.end;
+        if range.len() < count || self.ranges.iter().any(overlaps) {
+            return Err(UsernameError::InvalidDiscriminatorRange);
+        }
+        self.ranges.push(range);
+        self.counts.push(count);
+        Ok(self)
+    }
+
+    /// Never picks any of `discriminators`.
+    ///
+    /// If too many values of a range are excluded, fewer candidates will be picked from it.
+    pub fn excluding(mut self, discriminators: impl IntoIterator<Item = usize>) -> Self {
+        self.excluded.extend(discriminators);
+        self
+    }
+
+    /// Picks at most `total` discriminators, dropping candidates from the last ranges first.
+    pub fn limited_to(mut self, total: usize) -> Self {
+        let mut remaining = total;
+        for count in &mut self.counts {
+            *count = std::cmp::min(*count, remaining);
+            remaining -= *count;
+        }
+        self
+    }
+
+    fn random_discriminators<R: Rng>(&self, rng: &mut R) -> Result<Vec<usize>, UsernameError> {
+        // Draw extra values from each range to make up for any that get excluded.
+        let draw_counts: Vec<usize> = std::iter::zip(&self.ranges, &self.counts)
+            .map(|(range, count)| {
+                let excluded_in_range = self.excluded.iter().filter(|d| range.contains(*d)).count();
+                std::cmp::min(count + excluded_in_range, range.len())
+            })
+            .collect();
+        let mut drawn = random_discriminators(rng, &draw_counts, &self.ranges)?.into_iter();
+
+        let mut results = Vec::with_capacity(self.counts.iter().sum());
+        for (draw_count, count) in std::iter::zip(draw_counts, &self.counts) {
+            let from_range: Vec<usize> = drawn.by_ref().take(draw_count).collect();
+            results.extend(
+                from_range
+                    .into_iter()
+                    .filter(|d| !self.excluded.contains(d))
+                    .take(*count),
+            );
+        }
+        Ok(results)
+    }
+}
+
+/// A generated username, along with its hash and a proof for reserving it.
+#[derive(Debug)]
+pub struct UsernameCandidate {
+    pub username: String,
+    pub hash: [u8; 32],
+    pub proof: Vec<u8>,
+}
+
 impl Username {
     pub fn new(s: &str) -> Result<Self, UsernameError> {
         let (nickname, discriminator) =
@@ -164,16 +273,47 @@ impl Username {
         rng: &mut R,
         nickname: &str,
         limits: NicknameLimits,
+    ) -> Result<Vec<String>, UsernameError> {
+        Self::candidates_with_policy(rng, nickname, limits, &DiscriminatorPolicy::default())
+    }
+
+    pub fn candidates_with_policy<R: Rng>(
+        rng: &mut R,
+        nickname: &str,
+        limits: NicknameLimits,
+        policy: &DiscriminatorPolicy,
     ) -> Result<Vec<String>, UsernameError> {
         validate_nickname(nickname, &limits)?;
-        let candidates = random_discriminators(rng, &CANDIDATES_PER_RANGE, &DISCRIMINATOR_RANGES)
-            .unwrap()
+        let candidates = policy
+            .random_discriminators(rng)?
             .iter()
             .map(|d| Self::format_parts(nickname, d))
             .collect();
         Ok(candidates)
     }
 
+    /// Generates candidates like [`candidates_with_policy`](Self::candidates_with_policy), and
+    /// computes the hash and proof for each one as well.
+    pub fn candidates_with_proofs_from<R: Rng + CryptoRng>(
+        rng: &mut R,
+        nickname: &str,
+        limits: NicknameLimits,
+        policy: &DiscriminatorPolicy,
+    ) -> Result<Vec<UsernameCandidate>, UsernameError> {
+        Self::candidates_with_policy(rng, nickname, limits, policy)?
+            .into_iter()
+            .map(|username| {
+                let parsed = Self::new(&username)?;
+                let randomness: [u8; 32] = rng.gen();
+                Ok(UsernameCandidate {
+                    hash: parsed.hash(),
+                    proof: parsed.proof(&randomness)?,
+                    username,
+                })
+            })
+            .collect()
+    }
+
     fn format_parts(nickname: &str, discriminator: impl std::fmt::Display) -> String {
         format!("{nickname}.{discriminator:0>2}")
     }
@@ -543,6 +683,120 @@ mod test {
         let _ = random_discriminators(&mut rng, &counts, &DISCRIMINATOR_RANGES);
     }
 
+    #[test]
+    fn policy_picks_from_ranges_in_order() {
+        let mut rng = rand::thread_rng();
+        let policy = DiscriminatorPolicy::empty()
+            .with_range(1_000..10_000, 2)
+            .and_then(|policy| policy.with_range(10..100, 3))
+            .unwrap();
+        let ds = policy.random_discriminators(&mut rng).unwrap();
+        assert_eq!(ds.len(), 5);
+        assert!(ds[..2].iter().all(|d| (1_000..10_000).contains(d)));
+        assert!(ds[2..].iter().all(|d| (10..100).contains(d)));
+    }
+
+    #[test]
+    fn policy_skips_excluded_discriminators() {
+        let mut rng = rand::thread_rng();
+        let policy = DiscriminatorPolicy::empty()
+            .with_range(10..20, 5)
+            .unwrap()
+            .excluding(10..15);
+        for _ in 0..100 {
+            let mut ds = policy.random_discriminators(&mut rng).unwrap();
+            ds.sort();
+            assert_eq!(ds, [15, 16, 17, 18, 19]);
+        }
+
+        // Not enough values left to pick from.
+        let policy = DiscriminatorPolicy::empty()
+            .with_range(10..20, 5)
+            .unwrap()
+            .excluding(10..17);
+        let mut ds = policy.random_discriminators(&mut rng).unwrap();
+        ds.sort();
+        assert_eq!(ds, [17, 18, 19]);
+    }
+
+    #[test]
+    fn policy_limited_to_keeps_earlier_ranges() {
+        let mut rng = rand::thread_rng();
+        let ds = DiscriminatorPolicy::default()
+            .limited_to(6)
+            .random_discriminators(&mut rng)
+            .unwrap();
+        assert_eq!(ds.len(), 6);
+        assert!(ds[..4].iter().all(|d| DISCRIMINATOR_RANGES[0].contains(d)));
+        assert!(ds[4..].iter().all(|d| DISCRIMINATOR_RANGES[1].contains(d)));
+    }
+
+    #[test]
+    fn policy_rejects_invalid_ranges() {
+        for (range, count, expected) in [
+            (10..20, 11, UsernameError::InvalidDiscriminatorRange),
+            (0..10, 1, UsernameError::DiscriminatorCannotBeZero),
+            (1..10, 1, UsernameError::DiscriminatorCannotBeSingleDigit),
+            (1..100, 1, UsernameError::DiscriminatorCannotBeSingleDigit),
+            (
+                100_000_000..1_000_000_001,
+                1,
+                UsernameError::DiscriminatorTooLarge,
+            ),
+        ] {
+            let result = DiscriminatorPolicy::empty().with_range(range.clone(), count);
+            assert_eq!(
+                result.map(|_| ()).expect_err("invalid"),
+                expected,
+                "{range:?}"
+            );
+        }
+        DiscriminatorPolicy::empty()
+            .with_range(100_000_000..1_000_000_000, 1)
+            .expect("largest range is valid");
+    }
+
+    #[test]
+    fn policy_rejects_overlapping_ranges() {
+        let policy = DiscriminatorPolicy::empty()
+            .with_range(100..200, 1)
+            .unwrap();
+        for range in [150..250, 50..101, 100..200, 120..130] {
+            assert_eq!(
+                policy
+                    .clone()
+                    .with_range(range.clone(), 1)
+                    .map(|_| ())
+                    .expect_err("overlapping"),
+                UsernameError::InvalidDiscriminatorRange,
+                "{range:?}"
+            );
+        }
+        policy
+            .with_range(200..300, 1)
+            .and_then(|policy| policy.with_range(10..100, 1))
+            .expect("adjacent ranges are fine");
+    }
+
+    #[test]
+    fn candidates_with_proofs_are_valid() {
+        let mut rng = rand::rngs::OsRng;
+        let policy = DiscriminatorPolicy::default().limited_to(5);
+        let candidates = Username::candidates_with_proofs_from(
+            &mut rng,
+            "ab_c",
+            NicknameLimits::default(),
+            &policy,
+        )
+        .unwrap();
+        assert_eq!(candidates.len(), 5);
+        for candidate in candidates {
+            let username = Username::new(&candidate.username).unwrap();
+            assert_eq!(candidate.hash, username.hash());
+            Username::verify_proof(&candidate.proof, candidate.hash).unwrap();
+        }
+    }
+
     #[test]
     fn nickname_limits() {
         NicknameLimits::default(); // should not panic
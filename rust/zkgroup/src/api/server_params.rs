@@ -4,6 +4,8 @@
 //
 
 use partial_default::PartialDefault;
+use rayon::iter::{IndexedParallelIterator as _, ParallelIterator as _};
+use rayon::slice::ParallelSlice as _;
 use serde::{Deserialize, Serialize};
 
 use crate::common::constants::*;
@@ -208,6 +210,60 @@ impl ServerSecretParams {
         }
     }
 
+    /// Verifies many auth credential presentations at once.
+    ///
+    /// This is equivalent to calling [`Self::verify_auth_credential_presentation`] on each
+    /// presentation, but proof verification for V3 presentations is shared across the batch (see
+    /// [`poksho::Statement::verify_proofs`]). V4 presentations are verified one at a time. All of
+    /// it is spread across rayon's thread pool. On failure, every presentation that failed is
+    /// reported.
+    pub fn verify_auth_credential_presentations(
+        &self,
+        presentations: &[(
+            api::groups::GroupPublicParams,
+            &api::auth::AnyAuthCredentialPresentation,
+        )],
+        current_time: Timestamp,
+    ) -> Result<(), ZkGroupBatchVerificationFailure> {
+        let statement =
+            crypto::proofs::AuthCredentialWithPniPresentationProof::get_poksho_statement();
+        verify_in_chunks(presentations, |chunk| {
+            let mut valid = vec![true; chunk.len()];
+            let mut pending_proofs = vec![];
+            for (i, (group_public_params, presentation)) in chunk.iter().enumerate() {
+                if Self::check_auth_credential_redemption_time(
+                    presentation.get_redemption_time(),
+                    current_time,
+                )
+                .is_err()
+                {
+                    valid[i] = false;
+                    continue;
+                }
+
+                match presentation {
+                    api::auth::AnyAuthCredentialPresentation::V3(presentation) => {
+                        let point_args = presentation.proof.point_args(
+                            self.auth_credentials_with_pni_key_pair,
+                            group_public_params.uid_enc_public_key,
+                            presentation.aci_ciphertext,
+                            presentation.pni_ciphertext,
+                            presentation.redemption_time,
+                        );
+                        pending_proofs.push((i, point_args, presentation.proof.poksho_proof()));
+                    }
+                    api::auth::AnyAuthCredentialPresentation::V4(presentation) => {
+                        valid[i] = presentation
+                            .verify(self, group_public_params, presentation.redemption_time())
+                            .is_ok();
+                    }
+                }
+            }
+            verify_poksho_proofs(&statement, &pending_proofs, &mut valid);
+            valid
+        })
+    }
+
     pub fn verify_auth_credential_with_pni_presentation(
         &self,
         group_public_params: api::groups::GroupPublicParams,
@@ -251,6 +307,53 @@ impl ServerSecretParams {
         }
     }
 
+    /// Verifies many profile key credential presentations at once.
+    ///
+    /// This is equivalent to calling [`Self::verify_profile_key_credential_presentation`] on each
+    /// presentation, but proof verification is shared across the batch (see
+    /// [`poksho::Statement::verify_proofs`]), and spread across rayon's thread pool. On failure,
+    /// every presentation that failed is reported.
+    pub fn verify_profile_key_credential_presentations(
+        &self,
+        presentations: &[(
+            api::groups::GroupPublicParams,
+            &api::profiles::AnyProfileKeyCredentialPresentation,
+        )],
+        current_time: Timestamp,
+    ) -> Result<(), ZkGroupBatchVerificationFailure> {
+        let statement =
+            crypto::proofs::ExpiringProfileKeyCredentialPresentationProof::get_poksho_statement();
+        verify_in_chunks(presentations, |chunk| {
+            let mut valid = vec![true; chunk.len()];
+            let mut pending_proofs = vec![];
+            for (i, (group_public_params, presentation)) in chunk.iter().enumerate() {
+                match presentation {
+                    api::profiles::AnyProfileKeyCredentialPresentation::V1(_)
+                    | api::profiles::AnyProfileKeyCredentialPresentation::V2(_) => {
+                        valid[i] = false;
+                    }
+                    api::profiles::AnyProfileKeyCredentialPresentation::V3(presentation) => {
+                        if presentation.credential_expiration_time <= current_time {
+                            valid[i] = false;
+                            continue;
+                        }
+                        let point_args = presentation.proof.point_args(
+                            self.expiring_profile_key_credentials_key_pair,
+                            presentation.uid_enc_ciphertext,
+                            group_public_params.uid_enc_public_key,
+                            presentation.profile_key_enc_ciphertext,
+                            group_public_params.profile_key_enc_public_key,
+                            presentation.credential_expiration_time,
+                        );
+                        pending_proofs.push((i, point_args, presentation.proof.poksho_proof()));
+                    }
+                }
+            }
+            verify_poksho_proofs(&statement, &pending_proofs, &mut valid);
+            valid
+        })
+    }
+
     pub fn verify_expiring_profile_key_credential_presentation(
         &self,
         group_public_params: api::groups::GroupPublicParams,
@@ -378,6 +481,35 @@ impl ServerSecretParams {
             presentation.get_receipt_struct(),
         )
     }
+
+    /// Verifies many receipt credential presentations at once.
+    ///
+    /// This is equivalent to calling [`Self::verify_receipt_credential_presentation`] on each
+    /// presentation, but proof verification is shared across the batch (see
+    /// [`poksho::Statement::verify_proofs`]), and spread across rayon's thread pool. On failure,
+    /// every presentation that failed is reported.
+    pub fn verify_receipt_credential_presentations(
+        &self,
+        presentations: &[&api::receipts::ReceiptCredentialPresentation],
+    ) -> Result<(), ZkGroupBatchVerificationFailure> {
+        let statement = crypto::proofs::ReceiptCredentialPresentationProof::get_poksho_statement();
+        verify_in_chunks(presentations, |chunk| {
+            let mut valid = vec![true; chunk.len()];
+            let pending_proofs: Vec<_> = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, presentation)| {
+                    let point_args = presentation.proof.point_args(
+                        self.receipt_credentials_key_pair,
+                        presentation.get_receipt_struct(),
+                    );
+                    (i, point_args, presentation.proof.poksho_proof())
+                })
+                .collect();
+            verify_poksho_proofs(&statement, &pending_proofs, &mut valid);
+            valid
+        })
+    }
 }
 
 impl ServerPublicParams {
@@ -661,3 +793,49 @@ impl ServerPublicParams {
         }
     }
 }
+
+/// How many items batch verification hands to each rayon task.
+const BATCH_VERIFICATION_CHUNK_SIZE: usize = 64;
+
+/// Runs `verify_chunk` over `items` in parallel, collecting the indexes of the items it reports as
+/// invalid.
+fn verify_in_chunks<T: Sync>(
+    items: &[T],
+    verify_chunk: impl Fn(&[T]) -> Vec<bool> + Sync,
+) -> Result<(), ZkGroupBatchVerificationFailure> {
+    let failed_indexes: Vec<usize> = items
+        .par_chunks(BATCH_VERIFICATION_CHUNK_SIZE)
+        .enumerate()
+        .flat_map_iter(|(chunk_index, chunk)| {
+            verify_chunk(chunk)
+                .into_iter()
+                .enumerate()
+                .filter(|(_i, valid)| !valid)
+                .map(move |(i, _valid)| chunk_index * BATCH_VERIFICATION_CHUNK_SIZE + i)
+        })
+        .collect();
+
+    if failed_indexes.is_empty() {
+        Ok(())
+    } else {
+        Err(ZkGroupBatchVerificationFailure { failed_indexes })
+    }
+}
+
+/// Verifies each of `proofs` against `statement`, marking the index of each failed proof as invalid.
+fn verify_poksho_proofs(
+    statement: &poksho::Statement,
+    proofs: &[(usize, poksho::PointArgs, &[u8])],
+    valid: &mut [bool],
+) {
+    let results = statement.verify_proofs(
+        proofs
+            .iter()
+            .map(|(_i, point_args, proof)| (*proof, point_args, &[][..])),
+    );
+    for ((i, _point_args, _proof), result) in proofs.iter().zip(results) {
+        if result.is_err() {
+            valid[*i] = false;
+        }
+    }
+}
@@ -15,6 +15,13 @@ impl From<VerificationFailure> for ZkGroupVerificationFailure {
     }
 }
 
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+/// Verification failed for the items at indexes {failed_indexes:?} in a batch
+pub struct ZkGroupBatchVerificationFailure {
+    /// The indexes of every item that failed verification, in increasing order.
+    pub failed_indexes: Vec<usize>,
+}
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 /// Failed to deserialize {0}
 pub struct ZkGroupDeserializationFailure(&'static str);
@@ -34,6 +34,26 @@ pub fn deserialize<'a, T: Deserialize<'a> + PartialDefault>(
     Ok(result)
 }
 
+/// Like [`deserialize`], but decodes into an existing value, reusing its allocations where
+/// possible.
+///
+/// This is meant for decoding many values of the same type one after another, such as
+/// presentations, which contain variable-length proofs. Presentations don't get borrowed
+/// counterparts like the protocol messages do, since their proofs are decoded into owned
+/// vectors; reusing one value through this function avoids most of the same allocations.
+///
+/// If decoding fails, `place` is left in an unspecified (but valid) state.
+pub fn deserialize_into<'a, T: Deserialize<'a>>(
+    bytes: &'a [u8],
+    place: &mut T,
+) -> Result<(), ZkGroupDeserializationFailure> {
+    T::deserialize_in_place(
+        &mut bincode::Deserializer::from_slice(bytes, zkgroup_bincode_options()),
+        place,
+    )
+    .map_err(|_| ZkGroupDeserializationFailure::new::<T>())
+}
+
 /// Serializes a type using the standard zkgroup encoding (based on bincode).
 pub fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
     zkgroup_bincode_options()
@@ -41,6 +61,13 @@ pub fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
         .expect("cannot fail")
 }
 
+/// Like [`serialize`], but appends to an existing buffer instead of allocating a new one.
+pub fn serialize_into<T: Serialize>(value: &T, buffer: &mut Vec<u8>) {
+    zkgroup_bincode_options()
+        .serialize_into(buffer, value)
+        .expect("cannot fail")
+}
+
 /// Constant version number `C` as a type.
 ///
 /// Zero-sized type that converts to and from for the value `C` via `Into`,
@@ -150,6 +177,29 @@ mod test {
         crate::deserialize::<T>(&serialized).expect_err("invalid version");
     }
 
+    #[test]
+    fn into_existing_values() {
+        let first = WithVersionByte::test_value();
+        let second = WithVersionByte {
+            leading: VersionByte,
+            string: "another".to_string(),
+        };
+
+        let mut buffer = crate::serialize(&first);
+        let first_len = buffer.len();
+        crate::serialize_into(&second, &mut buffer);
+        assert_eq!(&buffer[..first_len], crate::serialize(&first));
+        assert_eq!(&buffer[first_len..], crate::serialize(&second));
+
+        let mut place = WithVersionByte::partial_default();
+        crate::deserialize_into(&buffer[..first_len], &mut place).expect("can deserialize");
+        assert_eq!(place, first);
+        crate::deserialize_into(&buffer[first_len..], &mut place).expect("can deserialize");
+        assert_eq!(place, second);
+
+        crate::deserialize_into(&buffer, &mut place).expect_err("trailing bytes");
+    }
+
     #[test]
     fn version_byte_error_message() {
         let mut bincode_serialized =
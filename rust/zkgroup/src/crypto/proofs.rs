@@ -708,6 +708,32 @@ impl AuthCredentialWithPniPresentationProof {
         pni_ciphertext: uid_encryption::Ciphertext,
         redemption_time: Timestamp,
     ) -> Result<(), ZkGroupVerificationFailure> {
+        let point_args = self.point_args(
+            credentials_key_pair,
+            uid_enc_public_key,
+            aci_ciphertext,
+            pni_ciphertext,
+            redemption_time,
+        );
+        match Self::get_poksho_statement().verify_proof(&self.poksho_proof, &point_args, &[]) {
+            Err(_) => Err(ZkGroupVerificationFailure),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn poksho_proof(&self) -> &[u8] {
+        &self.poksho_proof
+    }
+
+    /// Computes the points for verifying this proof against [`Self::get_poksho_statement`].
+    pub(crate) fn point_args(
+        &self,
+        credentials_key_pair: credentials::KeyPair<credentials::AuthCredentialWithPni>,
+        uid_enc_public_key: uid_encryption::PublicKey,
+        aci_ciphertext: uid_encryption::Ciphertext,
+        pni_ciphertext: uid_encryption::Ciphertext,
+        redemption_time: Timestamp,
+    ) -> poksho::PointArgs {
         let uid_enc_system = uid_encryption::SystemParams::get_hardcoded();
         let credentials_system = credentials::SystemParams::get_hardcoded();
 
@@ -720,7 +746,7 @@ impl AuthCredentialWithPniPresentationProof {
             C_y4,
             C_y5,
             C_V,
-            poksho_proof,
+            poksho_proof: _,
         } = self;
 
         let (C_x0, C_x1, C_y1, C_y2, C_y3, C_y4, C_y5, C_V) =
@@ -776,11 +802,7 @@ impl AuthCredentialWithPniPresentationProof {
         point_args.add("C_y3", C_y3);
         point_args.add("G_y3", credentials_system.G_y[3]);
         point_args.add("0", RistrettoPoint::identity());
-
-        match Self::get_poksho_statement().verify_proof(poksho_proof, &point_args, &[]) {
-            Err(_) => Err(ZkGroupVerificationFailure),
-            Ok(_) => Ok(()),
-        }
+        point_args
     }
 }
 
@@ -941,6 +963,34 @@ impl ExpiringProfileKeyCredentialPresentationProof {
         profile_key_enc_public_key: profile_key_encryption::PublicKey,
         credential_expiration_time: Timestamp,
     ) -> Result<(), ZkGroupVerificationFailure> {
+        let point_args = self.point_args(
+            credentials_key_pair,
+            uid_ciphertext,
+            uid_enc_public_key,
+            profile_key_ciphertext,
+            profile_key_enc_public_key,
+            credential_expiration_time,
+        );
+        match Self::get_poksho_statement().verify_proof(&self.poksho_proof, &point_args, &[]) {
+            Err(_) => Err(ZkGroupVerificationFailure),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn poksho_proof(&self) -> &[u8] {
+        &self.poksho_proof
+    }
+
+    /// Computes the points for verifying this proof against [`Self::get_poksho_statement`].
+    pub(crate) fn point_args(
+        &self,
+        credentials_key_pair: credentials::KeyPair<credentials::ExpiringProfileKeyCredential>,
+        uid_ciphertext: uid_encryption::Ciphertext,
+        uid_enc_public_key: uid_encryption::PublicKey,
+        profile_key_ciphertext: profile_key_encryption::Ciphertext,
+        profile_key_enc_public_key: profile_key_encryption::PublicKey,
+        credential_expiration_time: Timestamp,
+    ) -> poksho::PointArgs {
         let uid_enc_system = uid_encryption::SystemParams::get_hardcoded();
         let profile_key_enc_system = profile_key_encryption::SystemParams::get_hardcoded();
         let credentials_system = credentials::SystemParams::get_hardcoded();
@@ -954,7 +1004,7 @@ impl ExpiringProfileKeyCredentialPresentationProof {
             C_y4,
             C_y5,
             C_V,
-            poksho_proof,
+            poksho_proof: _,
         } = self;
 
         let (C_x0, C_x1, C_y1, C_y2, C_y3, C_y4, C_V) =
@@ -1013,11 +1063,7 @@ impl ExpiringProfileKeyCredentialPresentationProof {
         point_args.add("C_y3", C_y3);
         point_args.add("G_y3", credentials_system.G_y[3]);
         point_args.add("0", RistrettoPoint::identity());
-
-        match Self::get_poksho_statement().verify_proof(poksho_proof, &point_args, &[]) {
-            Err(_) => Err(ZkGroupVerificationFailure),
-            Ok(_) => Ok(()),
-        }
+        point_args
     }
 }
 
@@ -1093,6 +1139,23 @@ impl ReceiptCredentialPresentationProof {
         credentials_key_pair: credentials::KeyPair<credentials::ReceiptCredential>,
         receipt_struct: ReceiptStruct,
     ) -> Result<(), ZkGroupVerificationFailure> {
+        let point_args = self.point_args(credentials_key_pair, receipt_struct);
+        match Self::get_poksho_statement().verify_proof(&self.poksho_proof, &point_args, &[]) {
+            Err(_) => Err(ZkGroupVerificationFailure),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub(crate) fn poksho_proof(&self) -> &[u8] {
+        &self.poksho_proof
+    }
+
+    /// Computes the points for verifying this proof against [`Self::get_poksho_statement`].
+    pub(crate) fn point_args(
+        &self,
+        credentials_key_pair: credentials::KeyPair<credentials::ReceiptCredential>,
+        receipt_struct: ReceiptStruct,
+    ) -> poksho::PointArgs {
         let credentials_system = credentials::SystemParams::get_hardcoded();
         let M = credentials::convert_to_points_receipt_struct(receipt_struct);
 
@@ -1102,7 +1165,7 @@ impl ReceiptCredentialPresentationProof {
             C_y1,
             C_y2,
             C_V,
-            poksho_proof,
+            poksho_proof: _,
         } = self;
         let (C_x0, C_x1, C_y1, C_y2, C_V) = (*C_x0, *C_x1, *C_y1, *C_y2, *C_V);
 
@@ -1129,10 +1192,6 @@ impl ReceiptCredentialPresentationProof {
         point_args.add("G_x1", credentials_system.G_x1);
         point_args.add("G_y1", credentials_system.G_y[1]);
         point_args.add("G_y2", credentials_system.G_y[2]);
-
-        match Self::get_poksho_statement().verify_proof(poksho_proof, &point_args, &[]) {
-            Err(_) => Err(ZkGroupVerificationFailure),
-            Ok(_) => Ok(()),
-        }
+        point_args
     }
 }
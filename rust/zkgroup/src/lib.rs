@@ -12,5 +12,5 @@ pub mod crypto;
 pub use api::*;
 pub use common::constants::*;
 pub use common::errors::*;
-pub use common::serialization::{deserialize, serialize};
+pub use common::serialization::{deserialize, deserialize_into, serialize, serialize_into};
 pub use common::simple_types::*;
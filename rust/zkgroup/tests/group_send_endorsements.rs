@@ -228,3 +228,64 @@ fn test_client_rejects_bad_expirations() {
         DAY_ALIGNED_TIMESTAMP.add_seconds(1000 * SECONDS_PER_DAY),
     );
 }
+
+#[test]
+fn test_server_rejects_expired_or_mismatched_tokens() {
+    let randomness1: RandomnessBytes = [0x43u8; RANDOMNESS_LEN];
+    let randomness2: RandomnessBytes = [0x44u8; RANDOMNESS_LEN];
+    let randomness3: RandomnessBytes = [0x45u8; RANDOMNESS_LEN];
+
+    // first set up a group
+    let client_user_id = libsignal_core::Aci::from_uuid_bytes([0x04u8; UUID_LEN]);
+    let moxie_user_id =
+        libsignal_core::Aci::from(uuid::uuid!("e36fdce7-36da-4c6f-a21b-9afe2b754650"));
+    let group_members: [libsignal_core::ServiceId; 2] =
+        [client_user_id.into(), moxie_user_id.into()];
+
+    let group_secret_params = zkgroup::groups::GroupSecretParams::generate(randomness1);
+    let ciphertexts = group_members.map(|member| group_secret_params.encrypt_service_id(member));
+
+    // server generated materials; issuance request -> issuance response
+    let server_secret_params = zkgroup::ServerSecretParams::generate(randomness2);
+    let expiration = DAY_ALIGNED_TIMESTAMP.add_seconds(SECONDS_PER_DAY);
+    let todays_key =
+        zkgroup::groups::GroupSendDerivedKeyPair::for_expiration(expiration, &server_secret_params);
+    let response = zkgroup::groups::GroupSendEndorsementsResponse::issue(
+        ciphertexts,
+        &todays_key,
+        randomness3,
+    );
+
+    // client generated materials; issuance response -> redemption request
+    let server_public_params = server_secret_params.get_public_params();
+    let endorsements = response
+        .receive_with_service_ids(
+            group_members,
+            DAY_ALIGNED_TIMESTAMP,
+            &group_secret_params,
+            &server_public_params,
+        )
+        .expect("issued endorsements should be valid");
+    let token = endorsements[1]
+        .decompressed
+        .to_token(&group_secret_params)
+        .into_full_token(expiration);
+
+    // server verification of the credential presentation
+    token
+        .verify([moxie_user_id.into()], expiration, &todays_key)
+        .expect("valid up to and including the expiration");
+    token
+        .verify(
+            [moxie_user_id.into()],
+            expiration.add_seconds(1),
+            &todays_key,
+        )
+        .expect_err("expired");
+    token
+        .verify([client_user_id.into()], DAY_ALIGNED_TIMESTAMP, &todays_key)
+        .expect_err("wrong recipient");
+    token
+        .verify(group_members, DAY_ALIGNED_TIMESTAMP, &todays_key)
+        .expect_err("wrong set of recipients");
+}
@@ -531,3 +531,95 @@ fn test_profile_key_credential_presentation_v1_does_not_verify() {
         )
         .is_err());
 }
+
+#[test]
+fn test_batch_verify_auth_credential_presentations() {
+    let server_secret_params = zkgroup::ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+
+    let group_public_params = zkgroup::groups::GroupSecretParams::derive_from_master_key(
+        zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1),
+    )
+    .get_public_params();
+    let other_group_public_params = zkgroup::groups::GroupSecretParams::derive_from_master_key(
+        zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_2),
+    )
+    .get_public_params();
+
+    let redemption_time = Timestamp::from_epoch_seconds(123456 * SECONDS_PER_DAY);
+
+    let v3 =
+        zkgroup::auth::AnyAuthCredentialPresentation::new(AUTH_CREDENTIAL_PRESENTATION_V3_RESULT)
+            .unwrap();
+    let v4 =
+        zkgroup::auth::AnyAuthCredentialPresentation::new(AUTH_CREDENTIAL_PRESENTATION_V4_RESULT)
+            .unwrap();
+
+    // Span several chunks, and mix versions within each chunk.
+    let mut presentations: Vec<_> = (0..150)
+        .map(|i| (group_public_params, if i % 3 == 0 { &v4 } else { &v3 }))
+        .collect();
+
+    server_secret_params
+        .verify_auth_credential_presentations(&presentations, redemption_time)
+        .expect("all valid");
+
+    presentations[4].0 = other_group_public_params;
+    presentations[99].0 = other_group_public_params;
+    presentations[129].0 = other_group_public_params;
+    let failure = server_secret_params
+        .verify_auth_credential_presentations(&presentations, redemption_time)
+        .expect_err("some invalid");
+    assert_eq!(failure.failed_indexes, [4, 99, 129]);
+
+    let failure = server_secret_params
+        .verify_auth_credential_presentations(
+            &presentations,
+            redemption_time.add_seconds(2 * SECONDS_PER_DAY + 2),
+        )
+        .expect_err("all expired");
+    assert_eq!(failure.failed_indexes, (0..150).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_batch_verify_profile_key_credential_presentations() {
+    let server_secret_params = zkgroup::ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+
+    let group_public_params = zkgroup::groups::GroupSecretParams::derive_from_master_key(
+        zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1),
+    )
+    .get_public_params();
+    let other_group_public_params = zkgroup::groups::GroupSecretParams::derive_from_master_key(
+        zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_2),
+    )
+    .get_public_params();
+
+    let expiration = Timestamp::from_epoch_seconds(17 * SECONDS_PER_DAY);
+
+    let expiring = zkgroup::profiles::AnyProfileKeyCredentialPresentation::new(
+        PROFILE_KEY_CREDENTIAL_PRESENTATION_V3_RESULT,
+    )
+    .unwrap();
+    let v1 = zkgroup::profiles::AnyProfileKeyCredentialPresentation::new(
+        PROFILE_KEY_CREDENTIAL_PRESENTATION_V1,
+    )
+    .unwrap();
+
+    let mut presentations = vec![(group_public_params, &expiring); 150];
+
+    server_secret_params
+        .verify_profile_key_credential_presentations(&presentations, expiration.sub_seconds(5))
+        .expect("all valid");
+
+    presentations[3].0 = other_group_public_params;
+    presentations[64] = (group_public_params, &v1);
+    presentations[130].0 = other_group_public_params;
+    let failure = server_secret_params
+        .verify_profile_key_credential_presentations(&presentations, expiration.sub_seconds(5))
+        .expect_err("some invalid");
+    assert_eq!(failure.failed_indexes, [3, 64, 130]);
+
+    let failure = server_secret_params
+        .verify_profile_key_credential_presentations(&presentations[..10], expiration)
+        .expect_err("all expired");
+    assert_eq!(failure.failed_indexes, (0..10).collect::<Vec<_>>());
+}
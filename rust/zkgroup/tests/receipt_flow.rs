@@ -149,3 +149,46 @@ fn test_api() {
         .verify_receipt_credential_presentation(&bad_presentation)
         .expect_err("This Presentation Should Be Bad");
 }
+
+#[test]
+fn test_batch_verify_receipt_credential_presentations() {
+    let server_secret_params = ServerSecretParams::generate([0x42u8; RANDOMNESS_LEN]);
+    let server_public_params = server_secret_params.get_public_params();
+
+    // Distinct receipts, so that the batch has both shared and per-presentation points.
+    let presentations: Vec<ReceiptCredentialPresentation> = (0..10u8)
+        .map(|i| {
+            let context = server_public_params.create_receipt_credential_request_context(
+                [i; RANDOMNESS_LEN],
+                [i; RECEIPT_SERIAL_LEN],
+            );
+            let response = server_secret_params.issue_receipt_credential(
+                [0x43u8; RANDOMNESS_LEN],
+                &context.get_request(),
+                Timestamp::from_epoch_seconds(31337),
+                3,
+            );
+            let credential = server_public_params
+                .receive_receipt_credential(&context, &response)
+                .expect("valid");
+            server_public_params
+                .create_receipt_credential_presentation([0x44u8; RANDOMNESS_LEN], &credential)
+        })
+        .collect();
+
+    let mut batch: Vec<&ReceiptCredentialPresentation> = presentations.iter().collect();
+    server_secret_params
+        .verify_receipt_credential_presentations(&batch)
+        .expect("all valid");
+
+    let mut presentation_bytes = bincode::serialize(&presentations[6]).unwrap();
+    let i = presentation_bytes.len() - 17;
+    presentation_bytes[i] += 1;
+    let bad_presentation =
+        bincode::deserialize::<ReceiptCredentialPresentation>(&presentation_bytes).unwrap();
+    batch[6] = &bad_presentation;
+    let failure = server_secret_params
+        .verify_receipt_credential_presentations(&batch)
+        .expect_err("one invalid");
+    assert_eq!(failure.failed_indexes, [6]);
+}
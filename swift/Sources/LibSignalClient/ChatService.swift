@@ -12,17 +12,66 @@ public enum IpType: UInt8 {
 }
 
 public protocol ChatListener: AnyObject {
-    func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, sendAck: @escaping () async throws -> Void)
+    /// Called for each message delivered by the server, in the order the server sent them.
+    ///
+    /// Every message must be acknowledged exactly once using `ack`.
+    func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, ack: ServerMessageAck)
+
+    /// Called once all messages queued while the client was offline have been delivered.
     func chatServiceDidReceiveQueueEmpty(_ chat: ChatService)
+
+    /// Called when the authenticated connection is lost for any reason other than a call to
+    /// ``ChatService/disconnect()``.
+    ///
+    /// Messages will stop arriving until the service is connected again.
+    func chatServiceConnectionWasInterrupted(_ chat: ChatService, error: Error)
 }
 
-internal class ChatListenerBridge {
-    private class AckHandleOwner: NativeHandleOwner {
-        override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
-            signal_server_message_ack_destroy(handle)
+extension ChatListener {
+    public func chatServiceConnectionWasInterrupted(_: ChatService, error _: Error) {}
+}
+
+/// Acknowledges a message delivered to a ``ChatListener``.
+///
+/// Each message must be acknowledged exactly once, with either ``send()`` or ``sendNack()``.
+public class ServerMessageAck: NativeHandleOwner {
+    private let tokioAsyncContext: TokioAsyncContext
+
+    internal init(owned handle: OpaquePointer, tokioAsyncContext: TokioAsyncContext) {
+        self.tokioAsyncContext = tokioAsyncContext
+        super.init(owned: handle)
+    }
+
+    internal required init(owned handle: OpaquePointer) {
+        fatalError("should not be called directly for a ServerMessageAck")
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        signal_server_message_ack_destroy(handle)
+    }
+
+    /// Tells the server the message was processed, so it can be removed from the queue.
+    public func send() async throws {
+        _ = try await self.tokioAsyncContext.invokeAsyncFunction { promise, asyncContext in
+            self.withNativeHandle { ackHandle in
+                signal_server_message_ack_send(promise, asyncContext, ackHandle)
+            }
         }
     }
 
+    /// Tells the server the message could not be processed right now.
+    ///
+    /// The message stays queued and will be delivered again later.
+    public func sendNack() async throws {
+        _ = try await self.tokioAsyncContext.invokeAsyncFunction { promise, asyncContext in
+            self.withNativeHandle { ackHandle in
+                signal_server_message_ack_send_nack(promise, asyncContext, ackHandle)
+            }
+        }
+    }
+}
+
+internal class ChatListenerBridge {
     weak var chatService: ChatService?
     let chatListener: ChatListener
 
@@ -38,21 +87,16 @@ internal class ChatListenerBridge {
     func makeListenerStruct() -> SignalFfiChatListenerStruct {
         let receivedIncomingMessage: SignalReceivedIncomingMessage = { rawCtx, envelope, timestamp, ackHandle in
             defer { signal_free_buffer(envelope.base, envelope.length) }
-            let ackHandleOwner = AckHandleOwner(owned: ackHandle!)
 
             let bridge = Unmanaged<ChatListenerBridge>.fromOpaque(rawCtx!).takeUnretainedValue()
             guard let chatService = bridge.chatService else {
+                failOnError(signal_server_message_ack_destroy(ackHandle))
                 return
             }
 
+            let ack = ServerMessageAck(owned: ackHandle!, tokioAsyncContext: chatService.tokioAsyncContext)
             let envelopeData = Data(bytes: envelope.base, count: envelope.length)
-            bridge.chatListener.chatService(chatService, didReceiveIncomingMessage: envelopeData, serverDeliveryTimestamp: timestamp) {
-                _ = try await chatService.tokioAsyncContext.invokeAsyncFunction { promise, asyncContext in
-                    ackHandleOwner.withNativeHandle { ackHandle in
-                        signal_server_message_ack_send(promise, asyncContext, ackHandle)
-                    }
-                }
-            }
+            bridge.chatListener.chatService(chatService, didReceiveIncomingMessage: envelopeData, serverDeliveryTimestamp: timestamp, ack: ack)
         }
         let receivedQueueEmpty: SignalReceivedQueueEmpty = { rawCtx in
             let bridge = Unmanaged<ChatListenerBridge>.fromOpaque(rawCtx!).takeUnretainedValue()
@@ -62,11 +106,28 @@ internal class ChatListenerBridge {
 
             bridge.chatListener.chatServiceDidReceiveQueueEmpty(chatService)
         }
+        let connectionInterrupted: SignalConnectionInterrupted = { rawCtx, error in
+            let bridge = Unmanaged<ChatListenerBridge>.fromOpaque(rawCtx!).takeUnretainedValue()
+            // Always convert the error, so that it gets freed.
+            let disconnectCause: Error
+            do {
+                try checkError(error)
+                disconnectCause = SignalError.internalError("missing disconnect cause")
+            } catch {
+                disconnectCause = error
+            }
+            guard let chatService = bridge.chatService else {
+                return
+            }
+
+            bridge.chatListener.chatServiceConnectionWasInterrupted(chatService, error: disconnectCause)
+        }
 
         return .init(
             ctx: Unmanaged.passRetained(self).toOpaque(),
             received_incoming_message: receivedIncomingMessage,
             received_queue_empty: receivedQueueEmpty,
+            connection_interrupted: connectionInterrupted,
             destroy: { rawCtx in
                 _ = Unmanaged<AnyObject>.fromOpaque(rawCtx!).takeRetainedValue()
             }
@@ -186,6 +247,12 @@ public class ChatService: NativeHandleOwner {
         super.init(owned: handle!)
     }
 
+    /// Wraps an existing native chat service, such as one connected to a fake server in tests.
+    internal init(owned handle: OpaquePointer, tokioAsyncContext: TokioAsyncContext) {
+        self.tokioAsyncContext = tokioAsyncContext
+        super.init(owned: handle)
+    }
+
     internal required init(owned handle: OpaquePointer) {
         fatalError("should not be called directly for a ChatService")
     }
@@ -251,6 +318,27 @@ public class ChatService: NativeHandleOwner {
         return DebugInfo(consuming: rawDebugInfo)
     }
 
+    /// Warms up the connections to the Chat Service ahead of the first request, to hide the latency
+    /// of establishing them.
+    ///
+    /// The authenticated connection is fully established, as with ``connectAuthenticated()``. For
+    /// the unauthenticated connection, only the DNS lookup and the TCP and TLS handshakes are done
+    /// ahead of time; the result is kept for a short while and used by the next call to
+    /// ``connectUnauthenticated()``.
+    ///
+    /// - Throws: ``SignalError/appExpired(_:)`` if the current app version is too old (as judged by
+    ///   the server).
+    /// - Throws: ``SignalError/deviceDeregistered(_:)`` if the current device has been deregistered
+    ///   or delinked.
+    /// - Throws: Other ``SignalError``s for other kinds of failures.
+    public func preconnect() async throws {
+        _ = try await self.tokioAsyncContext.invokeAsyncFunction { promise, tokioAsyncContext in
+            withNativeHandle { chatService in
+                signal_chat_service_preconnect(promise, tokioAsyncContext, chatService)
+            }
+        }
+    }
+
     /// Initiates termination of the underlying connection to the Chat Service. After the service is
     /// disconnected, it will not attempt to automatically reconnect until you call
     /// ``connectAuthenticated()`` and/or ``connectUnauthenticated()``.
@@ -0,0 +1,139 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import Foundation
+import SignalFfi
+
+/// Something that happened while establishing a connection.
+///
+/// Events never contain proxy hostnames or IP addresses, only the (public) hostnames of the
+/// services being connected to. See ``Net/takeConnectionEvents()``.
+public class ConnectionEvent: NativeHandleOwner {
+    public enum Kind: UInt8 {
+        // Must be kept in sync with libsignal-net's ConnectionEvent.
+        case dnsLookup = 0
+        case tcpConnect = 1
+        case tlsHandshake = 2
+        case webSocketUpgrade = 3
+        case routeAttempt = 4
+        case routeCoolingDown = 5
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_connection_event_destroy(handle)
+    }
+
+    public var kind: Kind {
+        let rawKind = withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_connection_event_get_kind($0, nativeHandle)
+                }
+            }
+        }
+        guard let kind = Kind(rawValue: rawKind) else {
+            fatalError("unknown connection event kind \(rawKind)")
+        }
+        return kind
+    }
+
+    /// The kind of route used, e.g. `direct` or `proxyf`.
+    public var route: String {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningString {
+                    signal_connection_event_get_route($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The service being connected to; `nil` for DNS lookups and TCP connections.
+    public var host: String? {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningOptionalString {
+                    signal_connection_event_get_host($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// How long the step took or, for ``Kind/routeCoolingDown``, how long the route has left to
+    /// cool down.
+    public var durationMillis: UInt32 {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_connection_event_get_duration_millis($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// Whether a ``Kind/tcpConnect`` or ``Kind/tlsHandshake`` succeeded; `false` for other kinds
+    /// of events.
+    public var succeeded: Bool {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningBool {
+                    signal_connection_event_get_succeeded($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// Where a DNS lookup's result came from; `nil` if the lookup failed.
+    public var dnsSource: String? {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningOptionalString {
+                    signal_connection_event_get_dns_source($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The IP version of a ``Kind/tcpConnect``.
+    public var ipType: IpType? {
+        return self.optionalInteger(signal_connection_event_get_ip_type).map {
+            IpType(rawValue: UInt8($0)) ?? .unknown
+        }
+    }
+
+    /// The HTTP status of the server's response to a ``Kind/webSocketUpgrade``, if any.
+    public var httpStatus: UInt32? {
+        return self.optionalInteger(signal_connection_event_get_http_status)
+    }
+
+    /// How a ``Kind/routeAttempt`` ended: `succeeded`, `failed`, or `timedOut`.
+    public var routeAttemptOutcome: String? {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningOptionalString {
+                    signal_connection_event_get_route_attempt_outcome($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// How many times in a row the route of a ``Kind/routeAttempt`` has failed, counting this
+    /// attempt.
+    public var consecutiveFailures: UInt32? {
+        return self.optionalInteger(signal_connection_event_get_consecutive_failures)
+    }
+
+    private func optionalInteger(_ getter: (UnsafeMutablePointer<UInt32>?, OpaquePointer?) -> SignalFfiErrorRef?) -> UInt32? {
+        let value = withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    getter($0, nativeHandle)
+                }
+            }
+        }
+        // UInt32.max represents None.
+        return value == UInt32.max ? nil : value
+    }
+}
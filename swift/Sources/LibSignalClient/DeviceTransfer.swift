@@ -25,6 +25,19 @@ public struct DeviceTransferKey {
         return Self(privateKey: privateKey)
     }
 
+    /// Generates an Ed25519 key, which is much faster to generate than an RSA key.
+    ///
+    /// Ed25519 keys are always in PKCS#8 format.
+    public static func generateEd25519() -> Self {
+        let privateKey = failOnError {
+            try invokeFnReturningArray {
+                signal_device_transfer_generate_ed25519_private_key($0)
+            }
+        }
+
+        return Self(privateKey: privateKey)
+    }
+
     public func privateKeyMaterial() -> [UInt8] {
         return self.privateKey
     }
@@ -44,3 +57,77 @@ public struct DeviceTransferKey {
         }
     }
 }
+
+/// Encrypts a payload of known length into an archive, to be sent during a device transfer.
+///
+/// Each call to ``update(_:)`` returns the archive bytes that are ready so far, starting with the
+/// header; ``finish()`` checks that the whole payload was provided and returns the rest.
+public class DeviceTransferArchiveWriter: NativeHandleOwner {
+    public convenience init(key: [UInt8], totalLength: UInt64) throws {
+        let key = try ByteArray(newContents: key, expectedLength: 32)
+        let handle = try key.withUnsafePointerToSerialized { key in
+            var outputHandle: OpaquePointer?
+            try checkError(signal_device_transfer_archive_writer_new(&outputHandle, key, totalLength))
+            return outputHandle
+        }
+        self.init(owned: handle!)
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_device_transfer_archive_writer_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ bytes: Bytes) throws -> [UInt8] {
+        return try withNativeHandle { writer in
+            try bytes.withUnsafeBorrowedBuffer { bytes in
+                try invokeFnReturningArray {
+                    signal_device_transfer_archive_writer_update($0, writer, bytes, 0, UInt32(bytes.length))
+                }
+            }
+        }
+    }
+
+    public func finish() throws -> [UInt8] {
+        return try withNativeHandle { writer in
+            try invokeFnReturningArray {
+                signal_device_transfer_archive_writer_finish($0, writer)
+            }
+        }
+    }
+}
+
+/// Decrypts the payload of an archive received during a device transfer.
+///
+/// Each call to ``update(_:)`` returns the payload of every chunk completed by the new bytes, once
+/// it has been authenticated; ``finish()`` checks that the whole archive was provided.
+public class DeviceTransferArchiveReader: NativeHandleOwner {
+    public convenience init(key: [UInt8]) throws {
+        let key = try ByteArray(newContents: key, expectedLength: 32)
+        let handle = try key.withUnsafePointerToSerialized { key in
+            var outputHandle: OpaquePointer?
+            try checkError(signal_device_transfer_archive_reader_new(&outputHandle, key))
+            return outputHandle
+        }
+        self.init(owned: handle!)
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_device_transfer_archive_reader_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ bytes: Bytes) throws -> [UInt8] {
+        return try withNativeHandle { reader in
+            try bytes.withUnsafeBorrowedBuffer { bytes in
+                try invokeFnReturningArray {
+                    signal_device_transfer_archive_reader_update($0, reader, bytes, 0, UInt32(bytes.length))
+                }
+            }
+        }
+    }
+
+    public func finish() throws {
+        try withNativeHandle { reader in
+            try checkError(signal_device_transfer_archive_reader_finish(reader))
+        }
+    }
+}
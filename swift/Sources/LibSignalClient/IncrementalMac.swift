@@ -73,10 +73,64 @@ public class ValidatingMacContext: NativeHandleOwner {
         self.init(owned: handle!)
     }
 
+    /// Resumes validation from a checkpoint saved with ``checkpoint()``, possibly by another process.
+    ///
+    /// `savedInput` must produce the input from the start up to the checkpoint. It is read and validated again,
+    /// but doesn't have to be fetched again. The rest of the input can then be passed to ``update(_:)``
+    /// starting from ``validatedOffset``.
+    ///
+    /// - Throws: `SignalError.ioError` if `savedInput` can't be read, doesn't match, or is too short, or if the
+    ///   checkpoint was made for a different chunk size.
+    public convenience init<
+        Key: ContiguousBytes,
+        Digest: ContiguousBytes,
+        Checkpoint: ContiguousBytes
+    >(
+        key: Key,
+        chunkSize sizeChoice: SizeChoice,
+        expectingDigest digest: Digest,
+        resumingFrom checkpoint: Checkpoint,
+        savedInput: SignalInputStream
+    ) throws {
+        let chunkSize = try sizeChoice.sizeInBytes()
+        let handle: OpaquePointer? = try key.withUnsafeBorrowedBuffer { keyBuffer in
+            try digest.withUnsafeBorrowedBuffer { digestBuffer in
+                try checkpoint.withUnsafeBorrowedBuffer { checkpointBuffer in
+                    try withInputStream(savedInput) { ffiInput in
+                        var macHandle: OpaquePointer?
+                        try checkError(signal_validating_mac_resume(&macHandle, keyBuffer, chunkSize, digestBuffer, checkpointBuffer, ffiInput))
+                        return macHandle
+                    }
+                }
+            }
+        }
+        self.init(owned: handle!)
+    }
+
     override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
         return signal_validating_mac_destroy(handle)
     }
 
+    /// The number of bytes from the start of the input that have been validated so far.
+    ///
+    /// If ``update(_:)`` fails, the chunk that didn't match is dropped, and this is where it starts. It can then be
+    /// fetched again and passed in from here.
+    public var validatedOffset: UInt64 {
+        failOnError {
+            try invokeFnReturningInteger {
+                signal_validating_mac_validated_offset($0, unsafeNativeHandle)
+            }
+        }
+    }
+
+    /// Saves how far validation has got, to resume from later with
+    /// ``init(key:chunkSize:expectingDigest:resumingFrom:savedInput:)``.
+    public func checkpoint() throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_validating_mac_checkpoint($0, unsafeNativeHandle)
+        }
+    }
+
     public func update<Bytes: ContiguousBytes>(_ bytes: Bytes) throws -> UInt32 {
         let validBytesCount = try bytes.withUnsafeBorrowedBuffer { bytesPtr in
             try invokeFnReturningInteger {
@@ -73,6 +73,101 @@ public func sanitizeWebp(input: SignalInputStream, length ignored: UInt64) throw
     try sanitizeWebp(input: input)
 }
 
+/// Sanitize a GIF input.
+///
+/// The sanitizer checks the validity of a GIF file input and removes blocks that aren't needed to display it, such as
+/// comments and XMP metadata.
+///
+/// - Parameters:
+///  - input: A GIF format input stream.
+///
+/// - Returns: The sanitized GIF file and its dimensions.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `SignalError.invalidMediaInput` If the input could not be parsed because it was invalid.
+///  - `SignalError.unsupportedMediaInput` If the input could not be parsed because it's unsupported in some way.
+public func sanitizeGif(input: SignalInputStream) throws -> SanitizedImage {
+    return try withInputStream(input) { ffiInput in
+        try invokeFnReturningNativeHandle {
+            signal_gif_sanitizer_sanitize($0, ffiInput)
+        }
+    }
+}
+
+/// Sanitize a WebP input, removing its metadata.
+///
+/// The input is checked as by ``sanitizeWebp(input:)`` and copied as it is read, without its EXIF and XMP metadata or
+/// any unknown chunks. The bodies of the chunks that are left out are skipped rather than read.
+///
+/// - Parameters:
+///  - input: A WebP format input stream.
+///
+/// - Returns: The sanitized WebP file and its dimensions.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `SignalError.invalidMediaInput` If the input could not be parsed because it was invalid.
+///  - `SignalError.unsupportedMediaInput` If the input could not be parsed because it's unsupported in some way.
+public func sanitizeWebpAndStripMetadata(input: SignalInputStream) throws -> SanitizedImage {
+    return try withInputStream(input) { ffiInput in
+        try invokeFnReturningNativeHandle {
+            signal_webp_sanitizer_sanitize_and_strip_metadata($0, ffiInput)
+        }
+    }
+}
+
+/// A GIF or WebP file with its metadata removed, along with its dimensions.
+public class SanitizedImage: NativeHandleOwner {
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sanitized_image_destroy(handle)
+    }
+
+    /// The sanitized file, ready to be sent in place of the input.
+    public var data: [UInt8] {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_sanitized_image_get_data($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The width of the image, in pixels.
+    public var width: UInt32 {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_sanitized_image_get_width($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The height of the image, in pixels.
+    public var height: UInt32 {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_sanitized_image_get_height($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The number of frames in the image; 1 unless it is animated.
+    public var frameCount: UInt32 {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_sanitized_image_get_frame_count($0, nativeHandle)
+                }
+            }
+        }
+    }
+}
+
 public class SanitizedMetadata: ClonableHandleOwner {
     override internal class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
         return signal_sanitized_metadata_clone(&newHandle, currentHandle)
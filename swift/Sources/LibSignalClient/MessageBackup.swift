@@ -65,6 +65,121 @@ public func validateMessageBackup(
     return outcome.unknownFields
 }
 
+/// Validates a message backup file in a single pass over `stream`.
+///
+/// Unlike ``validateMessageBackup(key:purpose:length:makeStream:)``, this doesn't need the length
+/// of the backup file up front or a second copy of the stream.
+///
+/// - Parameters:
+///  - key: The key used to decrypt the backup file.
+///  - purpose: Whether the backup is intended for transfer or remote storage.
+///  - stream: The InputStream that produces the backup file.
+///
+/// - Returns: an object describing the validation outcome.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `MessageBackupValidationError`: If validation fails
+public func validateMessageBackup(
+    key: MessageBackupKey, purpose: MessageBackupPurpose, stream: SignalInputStream
+) throws -> MessageBackupUnknownFields {
+    let outcome: ValidationOutcome = try withInputStream(stream) { input in
+        try key.withNativeHandle { key in
+            try invokeFnReturningNativeHandle {
+                signal_message_backup_validator_validate_stream($0, key, input, purpose.rawValue)
+            }
+        }
+    }
+
+    if let errorMessage = outcome.errorMessage {
+        throw MessageBackupValidationError(errorMessage: errorMessage, unknownFields: outcome.unknownFields)
+    }
+    return outcome.unknownFields
+}
+
+/// Produces an encrypted message backup file from serialized `Frame`s.
+///
+/// Each frame is validated before it is written, using the same checks as
+/// ``validateMessageBackup(key:purpose:stream:)``, so a frame that would make the backup invalid is
+/// rejected without being written. The encrypted file is returned a piece at a time, and is only
+/// complete once ``finish()`` succeeds.
+public class MessageBackupWriter: NativeHandleOwner {
+    /// Starts a new backup file.
+    ///
+    /// - Parameters:
+    ///  - key: The key used to encrypt the backup file.
+    ///  - purpose: Whether the backup is intended for transfer or remote storage.
+    ///  - backupInfo: The serialized `BackupInfo` that the file starts with.
+    ///
+    /// - Throws: `SignalError.invalidArgument` if `backupInfo` can't be parsed.
+    public convenience init<Bytes: ContiguousBytes>(
+        key: MessageBackupKey, purpose: MessageBackupPurpose, backupInfo: Bytes
+    ) throws {
+        let handle = try key.withNativeHandle { key in
+            try backupInfo.withUnsafeBorrowedBuffer { backupInfo in
+                var outputHandle: OpaquePointer?
+                try checkError(signal_message_backup_writer_new(&outputHandle, key, backupInfo, purpose.rawValue))
+                return outputHandle
+            }
+        }
+        self.init(owned: handle!)
+    }
+
+    internal required init(owned handle: OpaquePointer) {
+        super.init(owned: handle)
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        signal_message_backup_writer_destroy(handle)
+    }
+
+    /// Validates and writes a serialized `Frame`.
+    ///
+    /// A rejected frame leaves the writer usable.
+    ///
+    /// - Returns: The next piece of the encrypted file, which may be empty.
+    ///
+    /// - Throws: `MessageBackupValidationError` if the frame is rejected, or the writer has been
+    ///   finished.
+    public func writeFrame<Bytes: ContiguousBytes>(_ frame: Bytes) throws -> [UInt8] {
+        try rethrowingAsValidationError {
+            try self.withNativeHandle { writer in
+                try frame.withUnsafeBorrowedBuffer { frame in
+                    try invokeFnReturningArray {
+                        signal_message_backup_writer_write_frame($0, writer, frame)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that the backup is complete and returns the rest of the encrypted file.
+    ///
+    /// The writer can't be used afterwards, even if this fails.
+    ///
+    /// - Returns: The last piece of the encrypted file.
+    ///
+    /// - Throws: `MessageBackupValidationError` if the backup is incomplete, or the writer has
+    ///   already been finished.
+    public func finish() throws -> [UInt8] {
+        try rethrowingAsValidationError {
+            try self.withNativeHandle { writer in
+                try invokeFnReturningArray {
+                    signal_message_backup_writer_finish($0, writer)
+                }
+            }
+        }
+    }
+}
+
+private func rethrowingAsValidationError<Result>(_ body: () throws -> Result) throws -> Result {
+    do {
+        return try body()
+    } catch SignalError.invalidArgument(let message) {
+        throw MessageBackupValidationError(errorMessage: message, unknownFields: MessageBackupUnknownFields(fields: []))
+    }
+}
+
 /// The outcome of a failed validation attempt.
 public struct MessageBackupValidationError: Error {
     /// The human-readable error that caused validation to fail.
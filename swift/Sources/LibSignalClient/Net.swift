@@ -32,6 +32,17 @@ public class Net {
         self.svr3 = Svr3Client(self.asyncContext, self.connectionManager)
     }
 
+    /// Like ``init(env:userAgent:)``, but with different timeouts.
+    ///
+    /// `connectTimeout` bounds each attempt to connect to a server over a single route, for chat,
+    /// CDSI, and SVR3 alike. `requestTimeout` is how long to wait for each response from CDSI or
+    /// SVR3 once connected.
+    public init(env: Environment, userAgent: String, connectTimeout: TimeInterval, requestTimeout: TimeInterval) {
+        self.asyncContext = TokioAsyncContext()
+        self.connectionManager = ConnectionManager(env: env, userAgent: userAgent, connectTimeout: connectTimeout, requestTimeout: requestTimeout)
+        self.svr3 = Svr3Client(self.asyncContext, self.connectionManager)
+    }
+
     /// Sets the proxy host to be used for all new connections (until overridden).
     ///
     /// Sets a domain name and port to be used to proxy all new outgoing connections. The proxy can
@@ -42,14 +53,72 @@ public class Net {
         try self.connectionManager.setProxy(host: host, port: port)
     }
 
+    /// Sets a SOCKS5 proxy to be used for all new connections (until overridden).
+    ///
+    /// Hostnames of Signal services are passed to the proxy unresolved, so DNS lookups for them
+    /// happen on the proxy's side. `username` and `password` must either both be provided or both
+    /// be `nil`.
+    ///
+    /// - Throws: if the host, port, or credentials are not structurally valid, such as a port of 0.
+    public func setSocksProxy(host: String, port: UInt16, username: String? = nil, password: String? = nil) throws {
+        try self.connectionManager.setSocksProxy(host: host, port: port, username: username, password: password)
+    }
+
+    /// Sets an HTTP CONNECT proxy to be used for all new connections (until overridden).
+    ///
+    /// Hostnames of Signal services are passed to the proxy unresolved, so DNS lookups for them
+    /// happen on the proxy's side. `username` and `password` must either both be provided or both
+    /// be `nil`.
+    ///
+    /// - Throws: if the host, port, or credentials are not structurally valid, such as a port of 0.
+    public func setHttpProxy(host: String, port: UInt16, username: String? = nil, password: String? = nil) throws {
+        try self.connectionManager.setHttpProxy(host: host, port: port, username: username, password: password)
+    }
+
     /// Clears the proxy host (if any) so that future connections will be made directly.
     ///
-    /// Clears any proxy configuration set via ``Net/setProxy(host:port:)``. If
-    /// none was set, calling this method is a no-op.
+    /// Clears any proxy configuration set via ``Net/setProxy(host:port:)``,
+    /// ``Net/setSocksProxy(host:port:username:password:)``, or
+    /// ``Net/setHttpProxy(host:port:username:password:)``. If none was set, calling this method is
+    /// a no-op.
     public func clearProxy() {
         self.connectionManager.clearProxy()
     }
 
+    /// Changes how long to wait before retrying a server that couldn't be reached.
+    ///
+    /// After each consecutive failure the delay grows by `multiplierPercent` percent of itself
+    /// (200 doubles it), starting at `initialDelayMillis` and capped at `maxDelayMillis`. Up to
+    /// `jitterPercent` percent of each delay is randomly taken off it, so that many clients don't
+    /// all retry at once. After `maxAttempts` consecutive failures, the server is not retried for a
+    /// long cooldown, after which the attempts start over; if `nil`, attempts continue
+    /// indefinitely.
+    public func setBackoffPolicy(initialDelayMillis: UInt32, maxDelayMillis: UInt32, multiplierPercent: UInt32 = 200, jitterPercent: UInt32 = 20, maxAttempts: UInt32? = nil) {
+        self.connectionManager.setBackoffPolicy(initialDelayMillis: initialDelayMillis, maxDelayMillis: maxDelayMillis, multiplierPercent: multiplierPercent, jitterPercent: jitterPercent, maxAttempts: maxAttempts ?? 0)
+    }
+
+    /// Restores the default delays between attempts to reach a server.
+    public func resetBackoffPolicy() {
+        self.connectionManager.resetBackoffPolicy()
+    }
+
+    /// Returns the connection events recorded since the last call, and forgets them.
+    ///
+    /// Events are returned oldest first. Only the most recent events are kept, so this should be
+    /// called regularly if events are being collected.
+    public func takeConnectionEvents() -> [ConnectionEvent] {
+        self.connectionManager.takeConnectionEvents()
+    }
+
+    /// Notifies libsignal that the device has switched networks, e.g. from Wi-Fi to cellular.
+    ///
+    /// Cached DNS results and delays before retrying unreachable servers are discarded, connection
+    /// attempts in progress are abandoned, and open connections are re-established on the new
+    /// network.
+    public func networkChanged() {
+        self.connectionManager.networkChanged()
+    }
+
     /// Like ``cdsiLookup(auth:request:)`` but with the parameters to ``CdsiLookupRequest`` broken out.
     public func cdsiLookup(
         auth: Auth,
@@ -122,8 +191,8 @@ public class Net {
         return ChatService(tokioAsyncContext: self.asyncContext, connectionManager: self.connectionManager, username: username, password: password)
     }
 
-    private var asyncContext: TokioAsyncContext
-    private var connectionManager: ConnectionManager
+    internal private(set) var asyncContext: TokioAsyncContext
+    internal private(set) var connectionManager: ConnectionManager
 }
 
 /// Authentication information used for connecting to CDSI servers.
@@ -365,6 +434,12 @@ internal class ConnectionManager: NativeHandleOwner {
         self.init(owned: handle!)
     }
 
+    convenience init(env: Net.Environment, userAgent: String, connectTimeout: TimeInterval, requestTimeout: TimeInterval) {
+        var handle: OpaquePointer?
+        failOnError(signal_connection_manager_new_with_timeouts(&handle, env.rawValue, userAgent, UInt32(connectTimeout * 1000), UInt32(requestTimeout * 1000)))
+        self.init(owned: handle!)
+    }
+
     internal func setProxy(host: String, port: UInt16) throws {
         try self.withNativeHandle {
             // We have to cast to Int32 because of how the port number is validated...for Java.
@@ -372,12 +447,56 @@ internal class ConnectionManager: NativeHandleOwner {
         }
     }
 
+    internal func setSocksProxy(host: String, port: UInt16, username: String?, password: String?) throws {
+        try self.withNativeHandle {
+            try checkError(signal_connection_manager_set_socks_proxy($0, host, Int32(port), username, password))
+        }
+    }
+
+    internal func setHttpProxy(host: String, port: UInt16, username: String?, password: String?) throws {
+        try self.withNativeHandle {
+            try checkError(signal_connection_manager_set_http_proxy($0, host, Int32(port), username, password))
+        }
+    }
+
     internal func clearProxy() {
         self.withNativeHandle {
             failOnError(signal_connection_manager_clear_proxy($0))
         }
     }
 
+    internal func setBackoffPolicy(initialDelayMillis: UInt32, maxDelayMillis: UInt32, multiplierPercent: UInt32, jitterPercent: UInt32, maxAttempts: UInt32) {
+        self.withNativeHandle {
+            failOnError(signal_connection_manager_set_backoff_policy($0, initialDelayMillis, maxDelayMillis, multiplierPercent, jitterPercent, maxAttempts))
+        }
+    }
+
+    internal func resetBackoffPolicy() {
+        self.withNativeHandle {
+            failOnError(signal_connection_manager_reset_backoff_policy($0))
+        }
+    }
+
+    internal func takeConnectionEvents() -> [ConnectionEvent] {
+        self.withNativeHandle { connectionManager in
+            var events: [ConnectionEvent] = []
+            while let event: ConnectionEvent = failOnError({
+                try invokeFnReturningOptionalNativeHandle {
+                    signal_connection_manager_take_connection_event($0, connectionManager)
+                }
+            }) {
+                events.append(event)
+            }
+            return events
+        }
+    }
+
+    internal func networkChanged() {
+        self.withNativeHandle {
+            failOnError(signal_connection_manager_network_changed($0))
+        }
+    }
+
     override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
         signal_connection_manager_destroy(handle)
     }
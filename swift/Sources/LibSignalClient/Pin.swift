@@ -30,11 +30,31 @@ public func hashLocalPin<Bytes: ContiguousBytes>(_ pin: Bytes) throws -> String
     }
 }
 
+/// Create an encoded password hash string with a caller-selected Argon2id cost.
+///
+/// This creates a hashed pin that should be used for local pin verification only. Devices that
+/// can't spare the memory for ``hashLocalPin(_:)`` can use less memory and more iterations instead,
+/// but the total cost (memory times iterations) may not go below that of ``hashLocalPin(_:)``.
+///
+/// - parameter pin: A normalized, UTF-8 encoded byte representation of the pin
+/// - parameter memoryKib: The memory to use, in KiB; at least 512
+/// - parameter iterations: The number of passes over memory; at least 2
+/// - returns: A hashed pin string that can be verified later
+public func hashLocalPin<Bytes: ContiguousBytes>(_ pin: Bytes, memoryKib: UInt32, iterations: UInt32) throws -> String {
+    try pin.withUnsafeBorrowedBuffer { buffer in
+        try invokeFnReturningString {
+            signal_pin_local_hash_with_params($0, buffer, memoryKib, iterations)
+        }
+    }
+}
+
 /// Verify an encoded password hash against a pin
 ///
 /// - parameter pin: A normalized, UTF-8 encoded byte representation of the pin to verify
 /// - parameter encodedHash: An encoded string of the hash, as returned by `localHash`
 /// - returns: true if the pin matches the hash, false otherwise
+/// - throws: ``SignalError/invalidArgument(_:)`` if the hash can't be parsed, uses an unsupported
+///   algorithm, or has a cost below the minimums of ``hashLocalPin(_:memoryKib:iterations:)``
 ///
 public func verifyLocalPin<Bytes: ContiguousBytes>(_ pin: Bytes, againstEncodedHash encodedHash: String) throws -> Bool {
     try encodedHash.withCString { hashPtr in
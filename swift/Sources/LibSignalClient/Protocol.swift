@@ -89,6 +89,79 @@ public func processPreKeyBundle(
     }
 }
 
+/// Runs a bulk session operation, which reports one flag per address, and returns the addresses
+/// whose sessions were changed.
+private func updateSessions(
+    for addresses: [ProtocolAddress],
+    sessionStore: SessionStore,
+    context: StoreContext,
+    _ body: (UnsafeMutablePointer<SignalOwnedBuffer>?, SignalBorrowedSliceOfProtocolAddress, UnsafePointer<SignalSessionStore>) -> SignalFfiErrorRef?
+) throws -> [ProtocolAddress] {
+    // Use withExtendedLifetime instead of withNativeHandle for the array of wrapper objects,
+    // which isn't compatible with withNativeHandle's simple lexical scoping.
+    let changed = try withExtendedLifetime(addresses) {
+        let addressHandles = addresses.map { $0.unsafeNativeHandle }
+        return try addressHandles.withUnsafeBufferPointer { addressHandles in
+            let addressHandlesBuffer = SignalBorrowedSliceOfProtocolAddress(base: addressHandles.baseAddress, length: addressHandles.count)
+            return try withSessionStore(sessionStore, context) { ffiSessionStore in
+                try invokeFnReturningArray {
+                    body($0, addressHandlesBuffer, ffiSessionStore)
+                }
+            }
+        }
+    }
+    return zip(addresses, changed).filter { $0.1 != 0 }.map { $0.0 }
+}
+
+/// Archives the current session state for each of `addresses`.
+///
+/// Addresses with no session, or no current session state, are skipped. Returns the addresses whose
+/// sessions were archived.
+public func archiveSessions(
+    for addresses: [ProtocolAddress],
+    sessionStore: SessionStore,
+    context: StoreContext
+) throws -> [ProtocolAddress] {
+    return try updateSessions(for: addresses, sessionStore: sessionStore, context: context) {
+        signal_session_management_archive_sessions($0, $1, $2)
+    }
+}
+
+/// Archives the current session state for each of `addresses` that has not been used to encrypt or
+/// decrypt a message within `maxAge` of `now`.
+///
+/// Sessions that have never recorded a use are left alone. Returns the addresses whose sessions were
+/// archived.
+public func expireSessions(
+    for addresses: [ProtocolAddress],
+    maxAge: TimeInterval,
+    sessionStore: SessionStore,
+    now: Date = Date(),
+    context: StoreContext
+) throws -> [ProtocolAddress] {
+    precondition(maxAge >= 0, "maxAge must not be negative")
+    // Anything longer than this is more than 136 years, which is as good as never expiring.
+    let maxAgeSeconds = UInt32(min(maxAge, TimeInterval(UInt32.max)))
+    return try updateSessions(for: addresses, sessionStore: sessionStore, context: context) {
+        signal_session_management_expire_sessions($0, $1, maxAgeSeconds, $2, UInt64(now.timeIntervalSince1970 * 1000))
+    }
+}
+
+/// Discards all but the `maxPreviousSessions` most recent archived session states for each of
+/// `addresses`.
+///
+/// The current session state is never affected. Returns the addresses whose sessions were pruned.
+public func prunePreviousSessions(
+    for addresses: [ProtocolAddress],
+    keeping maxPreviousSessions: UInt32,
+    sessionStore: SessionStore,
+    context: StoreContext
+) throws -> [ProtocolAddress] {
+    return try updateSessions(for: addresses, sessionStore: sessionStore, context: context) {
+        signal_session_management_prune_previous_sessions($0, $1, maxPreviousSessions, $2)
+    }
+}
+
 public func groupEncrypt<Bytes: ContiguousBytes>(
     _ message: Bytes,
     from sender: ProtocolAddress,
@@ -210,6 +210,110 @@ public class Svr3Client {
             }
         }
     }
+
+    /// Move a secret backed up to the SVR3 enclaves of `previous` to the ones of this client.
+    ///
+    /// The secret is restored from the previous enclaves, using up one of its
+    /// tries there, then backed up again here with `maxTries` tries. The share
+    /// set from the previous backup is no longer needed once this returns; the
+    /// new one should be stored instead.
+    ///
+    /// If the backup to the new enclaves fails, `state` keeps hold of the
+    /// restored secret. Calling this method again with the same `state` then
+    /// skips the previous enclaves entirely, so a failed migration can be
+    /// retried without using up more tries there. Once the migration succeeds,
+    /// the secret is cleared from `state`.
+    ///
+    /// - Parameters:
+    ///   - previous: The client for the enclaves the secret is currently backed
+    ///     up to.
+    ///   - state: The progress of this migration, to be reused when retrying
+    ///     it.
+    ///   - password: User-provided password that the secret was backed up
+    ///     with. It is used for the new backup as well.
+    ///   - shareSet: A serialized masked share set returned by
+    ///     ``backup(_:password:maxTries:auth:)`` on `previous`.
+    ///   - maxTries: Maximum allowed number of restore attempts for the
+    ///     migrated secret. Must be positive.
+    ///   - auth: An instance of ``Auth`` containing the username and password
+    ///     obtained from the Chat Server, used for both sets of enclaves.
+    ///
+    /// - Returns:
+    ///   A byte array containing the serialized masked share set of the new
+    ///   backup.
+    ///
+    /// - Throws:
+    ///   On error, throws a ``SignalError``. Expected error cases are
+    ///   - `SignalError.networkError` for a network-level connectivity issue,
+    ///     including connection timeouts.
+    ///   - `SignalError.networkProtocolError` for an SVR3 or attested
+    ///     connection protocol issue.
+    ///   - `SignalError.svrDataMissing` when either the maximum number of
+    ///     restores has been exceeded on the previous enclaves or the value has
+    ///     never been backed up there.
+    ///   - `SignalError.svrRestoreFailed` when the restore failed due to a bad
+    ///     combination of password and share set.
+    public func migrate(
+        from previous: Svr3Client,
+        state: Svr3MigrationState,
+        password: String,
+        shareSet: some ContiguousBytes,
+        maxTries: UInt32,
+        auth: Auth
+    ) async throws -> [UInt8] {
+        let output = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
+            withNativeHandles(self.connectionManager, previous.connectionManager, state) { connectionManager, previousConnectionManager, state in
+                shareSet.withUnsafeBorrowedBuffer { shareSetBuffer in
+                    signal_svr3_migrate(
+                        promise,
+                        asyncContext,
+                        connectionManager,
+                        previousConnectionManager,
+                        state,
+                        password,
+                        shareSetBuffer,
+                        maxTries,
+                        auth.username,
+                        auth.password
+                    )
+                }
+            }
+        }
+        defer {
+            signal_free_buffer(output.base, output.length)
+        }
+        return Array(UnsafeBufferPointer(start: output.base, count: output.length))
+    }
+}
+
+/// The progress of a ``Svr3Client/migrate(from:state:password:shareSet:maxTries:auth:)`` that
+/// can be retried.
+///
+/// Once the secret has been restored from the previous enclaves, this holds on
+/// to it until the migration succeeds, so it should not be kept around any
+/// longer than necessary.
+public class Svr3MigrationState: NativeHandleOwner {
+    public convenience init() {
+        var handle: OpaquePointer?
+        failOnError(signal_svr3_migration_state_new(&handle))
+        self.init(owned: handle!)
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        signal_svr3_migration_state_destroy(handle)
+    }
+
+    /// Whether the secret has been restored, so the previous enclaves are no
+    /// longer needed.
+    public var hasRestoredSecret: Bool {
+        failOnError {
+            try self.withNativeHandle { handle in
+                try invokeFnReturningBool {
+                    signal_svr3_migration_state_has_restored_secret($0, handle)
+                }
+            }
+        }
+    }
 }
 
 public struct RestoredSecret {
@@ -15,6 +15,11 @@ public struct Username {
         self.hash = try generateHash(self.value)
     }
 
+    private init(value: String, hash: [UInt8]) {
+        self.value = value
+        self.hash = hash
+    }
+
     public init<
         LinkBytes: ContiguousBytes,
         RandBytes: ContiguousBytes
@@ -94,6 +99,47 @@ public struct Username {
         }
         return try allCandidates.map { try Username($0) }
     }
+
+    /// Generates up to `count` candidates, along with a proof for each one.
+    ///
+    /// Discriminators are taken from each of `discriminatorRanges` in order, `count` from each,
+    /// and never from `excluded`. If `discriminatorRanges` is empty, the default ranges are used,
+    /// preferring shorter discriminators.
+    ///
+    /// - Throws: ``SignalError/invalidArgument(_:)`` if a range is empty, overlaps another one, or
+    ///   has fewer values than its count.
+    public static func candidatesWithProofs(
+        from nickname: String,
+        count: UInt32,
+        withValidLengthWithin lengthRange: ClosedRange<UInt32> = 3...32,
+        discriminatorRanges: [(range: Range<UInt32>, count: UInt32)] = [],
+        excluding excluded: [UInt32] = []
+    ) throws -> [(username: Username, proof: [UInt8])] {
+        var packedRanges: [UInt8] = []
+        for (range, rangeCount) in discriminatorRanges {
+            for value in [range.lowerBound, range.upperBound, rangeCount] {
+                withUnsafeBytes(of: value.bigEndian) { packedRanges.append(contentsOf: $0) }
+            }
+        }
+        var packedExcluded: [UInt8] = []
+        for value in excluded {
+            withUnsafeBytes(of: value.bigEndian) { packedExcluded.append(contentsOf: $0) }
+        }
+        let flattened = try nickname.withCString { nicknamePtr in
+            try packedRanges.withUnsafeBorrowedBuffer { packedRanges in
+                try packedExcluded.withUnsafeBorrowedBuffer { packedExcluded in
+                    try invokeFnReturningBytestringArray {
+                        signal_username_candidates_with_proofs_from($0, nicknamePtr, lengthRange.lowerBound, lengthRange.upperBound, packedRanges, packedExcluded, count)
+                    }
+                }
+            }
+        }
+        // Each candidate is represented as its username, hash, and proof.
+        return stride(from: 0, to: flattened.count, by: 3).map { i in
+            let username = Username(value: String(decoding: flattened[i], as: UTF8.self), hash: flattened[i + 1])
+            return (username, flattened[i + 2])
+        }
+    }
 }
 
 extension Username: CustomStringConvertible {
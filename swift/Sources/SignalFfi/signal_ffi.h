@@ -215,10 +215,20 @@ typedef struct SignalChat SignalChat;
 
 typedef struct SignalCiphertextMessage SignalCiphertextMessage;
 
+typedef struct SignalConnectionEvent SignalConnectionEvent;
+
 typedef struct SignalConnectionManager SignalConnectionManager;
 
 typedef struct SignalDecryptionErrorMessage SignalDecryptionErrorMessage;
 
+typedef struct SignalDeviceTransferArchiveReader SignalDeviceTransferArchiveReader;
+
+typedef struct SignalDeviceTransferArchiveWriter SignalDeviceTransferArchiveWriter;
+
+typedef struct SignalFakeChatServer SignalFakeChatServer;
+
+typedef struct SignalFakeChatServerRequest SignalFakeChatServerRequest;
+
 typedef struct SignalFingerprint SignalFingerprint;
 
 typedef struct SignalHsmEnclaveClient SignalHsmEnclaveClient;
@@ -239,6 +249,15 @@ typedef struct SignalMessageBackupKey SignalMessageBackupKey;
 
 typedef struct SignalMessageBackupValidationOutcome SignalMessageBackupValidationOutcome;
 
+/**
+ * Produces an encrypted backup in memory.
+ *
+ * The ciphertext is handed back as it is produced, so apps can write it out
+ * without holding the whole backup in memory. The writer is taken by
+ * [`MessageBackupWriter_Finish`], after which it can't be used.
+ */
+typedef struct SignalMessageBackupWriter SignalMessageBackupWriter;
+
 typedef struct SignalNonSuspendingBackgroundThreadRuntime SignalNonSuspendingBackgroundThreadRuntime;
 
 typedef struct SignalOtherTestingHandleType SignalOtherTestingHandleType;
@@ -262,6 +281,11 @@ typedef struct SignalProtocolAddress SignalProtocolAddress;
 
 typedef struct SignalPublicKey SignalPublicKey;
 
+/**
+ * A GIF or WebP file with its metadata removed, along with its dimensions.
+ */
+typedef struct SignalSanitizedImage SignalSanitizedImage;
+
 /**
  * Sanitized metadata returned by the sanitizer.
  */
@@ -299,6 +323,14 @@ typedef struct SignalMessage SignalMessage;
 
 typedef struct SignalSignedPreKeyRecord SignalSignedPreKeyRecord;
 
+/**
+ * Progress of an [`Svr3Migrate`] that can be retried.
+ *
+ * The state is taken for the duration of a migration, so concurrent migrations using the same
+ * state don't see each other's progress.
+ */
+typedef struct SignalSvr3MigrationState SignalSvr3MigrationState;
+
 typedef struct SignalTestingHandleType SignalTestingHandleType;
 
 typedef struct SignalTokioAsyncContext SignalTokioAsyncContext;
@@ -630,10 +662,27 @@ typedef struct {
   SignalCancellationId cancellation_id;
 } SignalCPromiseFfiResponseAndDebugInfo;
 
+/**
+ * A C callback used to report the results of Rust futures.
+ *
+ * cbindgen will produce independent C types like `SignalCPromisei32` and
+ * `SignalCPromiseProtocolAddress`.
+ *
+ * This derives Copy because it behaves like a C type; nevertheless, a promise should still only be
+ * completed once.
+ */
+typedef struct {
+  void (*complete)(SignalFfiError *error, SignalFakeChatServerRequest *const *result, const void *context);
+  const void *context;
+  SignalCancellationId cancellation_id;
+} SignalCPromiseFakeChatServerRequest;
+
 typedef void (*SignalReceivedIncomingMessage)(void *ctx, SignalOwnedBuffer envelope, uint64_t timestamp_millis, SignalServerMessageAck *cleanup);
 
 typedef void (*SignalReceivedQueueEmpty)(void *ctx);
 
+typedef void (*SignalConnectionInterrupted)(void *ctx, SignalFfiError *error);
+
 typedef void (*SignalDestroyChatListener)(void *ctx);
 
 /**
@@ -647,6 +696,7 @@ typedef struct {
   void *ctx;
   SignalReceivedIncomingMessage received_incoming_message;
   SignalReceivedQueueEmpty received_queue_empty;
+  SignalConnectionInterrupted connection_interrupted;
   SignalDestroyChatListener destroy;
 } SignalFfiChatListenerStruct;
 
@@ -1196,6 +1246,12 @@ SignalFfiError *signal_decrypt_message(SignalOwnedBuffer *out, const SignalMessa
 
 SignalFfiError *signal_decrypt_pre_key_message(SignalOwnedBuffer *out, const SignalPreKeySignalMessage *message, const SignalProtocolAddress *protocol_address, const SignalSessionStore *session_store, const SignalIdentityKeyStore *identity_key_store, const SignalPreKeyStore *prekey_store, const SignalSignedPreKeyStore *signed_prekey_store, const SignalKyberPreKeyStore *kyber_prekey_store);
 
+SignalFfiError *signal_session_management_archive_sessions(SignalOwnedBuffer *out, SignalBorrowedSliceOfProtocolAddress addresses, const SignalSessionStore *session_store);
+
+SignalFfiError *signal_session_management_expire_sessions(SignalOwnedBuffer *out, SignalBorrowedSliceOfProtocolAddress addresses, uint32_t max_age_seconds, const SignalSessionStore *session_store, uint64_t now);
+
+SignalFfiError *signal_session_management_prune_previous_sessions(SignalOwnedBuffer *out, SignalBorrowedSliceOfProtocolAddress addresses, uint32_t max_previous_sessions, const SignalSessionStore *session_store);
+
 SignalFfiError *signal_sealed_session_cipher_encrypt(SignalOwnedBuffer *out, const SignalProtocolAddress *destination, const SignalUnidentifiedSenderMessageContent *content, const SignalIdentityKeyStore *identity_key_store);
 
 SignalFfiError *signal_sealed_sender_multi_recipient_encrypt(SignalOwnedBuffer *out, SignalBorrowedSliceOfProtocolAddress recipients, SignalBorrowedSliceOfSessionRecord recipient_sessions, SignalBorrowedBuffer excluded_recipients, const SignalUnidentifiedSenderMessageContent *content, const SignalIdentityKeyStore *identity_key_store);
@@ -1216,8 +1272,26 @@ SignalFfiError *signal_device_transfer_generate_private_key(SignalOwnedBuffer *o
 
 SignalFfiError *signal_device_transfer_generate_private_key_with_format(SignalOwnedBuffer *out, uint8_t key_format);
 
+SignalFfiError *signal_device_transfer_generate_ed25519_private_key(SignalOwnedBuffer *out);
+
 SignalFfiError *signal_device_transfer_generate_certificate(SignalOwnedBuffer *out, SignalBorrowedBuffer private_key, const char *name, uint32_t days_to_expire);
 
+SignalFfiError *signal_device_transfer_archive_writer_destroy(SignalDeviceTransferArchiveWriter *p);
+
+SignalFfiError *signal_device_transfer_archive_writer_new(SignalDeviceTransferArchiveWriter **out, const uint8_t (*key)[32], uint64_t total_length);
+
+SignalFfiError *signal_device_transfer_archive_writer_update(SignalOwnedBuffer *out, SignalDeviceTransferArchiveWriter *writer, SignalBorrowedBuffer bytes, uint32_t offset, uint32_t length);
+
+SignalFfiError *signal_device_transfer_archive_writer_finish(SignalOwnedBuffer *out, SignalDeviceTransferArchiveWriter *writer);
+
+SignalFfiError *signal_device_transfer_archive_reader_destroy(SignalDeviceTransferArchiveReader *p);
+
+SignalFfiError *signal_device_transfer_archive_reader_new(SignalDeviceTransferArchiveReader **out, const uint8_t (*key)[32]);
+
+SignalFfiError *signal_device_transfer_archive_reader_update(SignalOwnedBuffer *out, SignalDeviceTransferArchiveReader *reader, SignalBorrowedBuffer bytes, uint32_t offset, uint32_t length);
+
+SignalFfiError *signal_device_transfer_archive_reader_finish(SignalDeviceTransferArchiveReader *reader);
+
 SignalFfiError *signal_cds2_client_state_new(SignalSgxClientState **out, SignalBorrowedBuffer mrenclave, SignalBorrowedBuffer attestation_msg, uint64_t current_timestamp);
 
 SignalFfiError *signal_sgx_client_state_destroy(SignalSgxClientState *p);
@@ -1504,12 +1578,48 @@ SignalFfiError *signal_verify_signature(bool *out, SignalBorrowedBuffer cert_pem
 
 SignalFfiError *signal_connection_manager_new(SignalConnectionManager **out, uint8_t environment, const char *user_agent);
 
+SignalFfiError *signal_connection_manager_new_with_timeouts(SignalConnectionManager **out, uint8_t environment, const char *user_agent, uint32_t connect_timeout_millis, uint32_t request_timeout_millis);
+
 SignalFfiError *signal_connection_manager_set_proxy(const SignalConnectionManager *connection_manager, const char *host, int32_t port);
 
+SignalFfiError *signal_connection_manager_set_socks_proxy(const SignalConnectionManager *connection_manager, const char *host, int32_t port, const char *username, const char *password);
+
+SignalFfiError *signal_connection_manager_set_http_proxy(const SignalConnectionManager *connection_manager, const char *host, int32_t port, const char *username, const char *password);
+
 SignalFfiError *signal_connection_manager_clear_proxy(const SignalConnectionManager *connection_manager);
 
+SignalFfiError *signal_connection_manager_set_backoff_policy(const SignalConnectionManager *connection_manager, uint32_t initial_delay_millis, uint32_t max_delay_millis, uint32_t multiplier_percent, uint32_t jitter_percent, uint32_t max_attempts);
+
+SignalFfiError *signal_connection_manager_reset_backoff_policy(const SignalConnectionManager *connection_manager);
+
+SignalFfiError *signal_connection_manager_take_connection_event(SignalConnectionEvent **out, const SignalConnectionManager *connection_manager);
+
+SignalFfiError *signal_connection_manager_network_changed(const SignalConnectionManager *connection_manager);
+
 SignalFfiError *signal_connection_manager_destroy(SignalConnectionManager *p);
 
+SignalFfiError *signal_connection_event_destroy(SignalConnectionEvent *p);
+
+SignalFfiError *signal_connection_event_get_kind(uint8_t *out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_route(const char **out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_host(const char **out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_duration_millis(uint32_t *out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_succeeded(bool *out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_dns_source(const char **out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_ip_type(uint32_t *out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_http_status(uint32_t *out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_route_attempt_outcome(const char **out, const SignalConnectionEvent *event);
+
+SignalFfiError *signal_connection_event_get_consecutive_failures(uint32_t *out, const SignalConnectionEvent *event);
+
 SignalFfiError *signal_create_otp(const char **out, const char *username, SignalBorrowedBuffer secret);
 
 SignalFfiError *signal_create_otp_from_base64(const char **out, const char *username, const char *secret);
@@ -1520,6 +1630,14 @@ SignalFfiError *signal_svr3_restore(SignalCPromiseOwnedBufferOfc_uchar *promise,
 
 SignalFfiError *signal_svr3_remove(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *enclave_password);
 
+SignalFfiError *signal_svr3_migration_state_destroy(SignalSvr3MigrationState *p);
+
+SignalFfiError *signal_svr3_migration_state_new(SignalSvr3MigrationState **out);
+
+SignalFfiError *signal_svr3_migration_state_has_restored_secret(bool *out, const SignalSvr3MigrationState *state);
+
+SignalFfiError *signal_svr3_migrate(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const SignalConnectionManager *previous_connection_manager, const SignalSvr3MigrationState *migration_state, const char *password, SignalBorrowedBuffer share_set, uint32_t max_tries, const char *username, const char *enclave_password);
+
 SignalFfiError *signal_lookup_request_new(SignalLookupRequest **out);
 
 SignalFfiError *signal_lookup_request_add_e164(const SignalLookupRequest *request, const char *e164);
@@ -1560,6 +1678,8 @@ SignalFfiError *signal_chat_service_connect_unauth(SignalCPromiseFfiChatServiceD
 
 SignalFfiError *signal_chat_service_connect_auth(SignalCPromiseFfiChatServiceDebugInfo *promise, const SignalTokioAsyncContext *async_runtime, const SignalChat *chat);
 
+SignalFfiError *signal_chat_service_preconnect(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalChat *chat);
+
 SignalFfiError *signal_chat_service_unauth_send(SignalCPromiseFfiChatResponse *promise, const SignalTokioAsyncContext *async_runtime, const SignalChat *chat, const SignalHttpRequest *http_request, uint32_t timeout_millis);
 
 SignalFfiError *signal_chat_service_unauth_send_and_debug(SignalCPromiseFfiResponseAndDebugInfo *promise, const SignalTokioAsyncContext *async_runtime, const SignalChat *chat, const SignalHttpRequest *http_request, uint32_t timeout_millis);
@@ -1572,10 +1692,36 @@ SignalFfiError *signal_chat_server_set_listener(const SignalTokioAsyncContext *r
 
 SignalFfiError *signal_testing_chat_service_inject_raw_server_request(const SignalChat *chat, SignalBorrowedBuffer bytes);
 
+SignalFfiError *signal_testing_chat_service_inject_connection_interrupted(const SignalChat *chat);
+
+SignalFfiError *signal_fake_chat_server_destroy(SignalFakeChatServer *p);
+
+SignalFfiError *signal_fake_chat_server_request_destroy(SignalFakeChatServerRequest *p);
+
+SignalFfiError *signal_testing_fake_chat_server_new(SignalFakeChatServer **out);
+
+SignalFfiError *signal_testing_chat_service_new_with_fake_server(SignalChat **out, const SignalConnectionManager *connection_manager, const SignalFakeChatServer *server, const char *username, const char *password);
+
+SignalFfiError *signal_testing_fake_chat_server_set_refuse_connections(const SignalFakeChatServer *server, bool refuse);
+
+SignalFfiError *signal_testing_fake_chat_server_disconnect_all(const SignalFakeChatServer *server);
+
+SignalFfiError *signal_testing_fake_chat_server_send_envelope(const SignalFakeChatServer *server, SignalBorrowedBuffer envelope, uint64_t server_delivery_timestamp);
+
+SignalFfiError *signal_testing_fake_chat_server_send_queue_empty(const SignalFakeChatServer *server);
+
+SignalFfiError *signal_testing_fake_chat_server_receive_request(SignalCPromiseFakeChatServerRequest *promise, const SignalTokioAsyncContext *async_runtime, const SignalFakeChatServer *server);
+
+SignalFfiError *signal_testing_fake_chat_server_request_get_http_request(SignalHttpRequest **out, const SignalFakeChatServerRequest *request);
+
+SignalFfiError *signal_testing_fake_chat_server_request_respond(const SignalTokioAsyncContext *runtime, const SignalFakeChatServerRequest *request, uint16_t status, SignalBorrowedBuffer body, uint32_t delay_millis);
+
 SignalFfiError *signal_server_message_ack_destroy(SignalServerMessageAck *p);
 
 SignalFfiError *signal_server_message_ack_send(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalServerMessageAck *ack);
 
+SignalFfiError *signal_server_message_ack_send_nack(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalServerMessageAck *ack);
+
 SignalFfiError *signal_tokio_async_context_destroy(SignalTokioAsyncContext *p);
 
 SignalFfiError *signal_tokio_async_context_new(SignalTokioAsyncContext **out);
@@ -1596,6 +1742,8 @@ SignalFfiError *signal_pin_hash_from_username_mrenclave(SignalPinHash **out, Sig
 
 SignalFfiError *signal_pin_local_hash(const char **out, SignalBorrowedBuffer pin);
 
+SignalFfiError *signal_pin_local_hash_with_params(const char **out, SignalBorrowedBuffer pin, uint32_t memory_kib, uint32_t iterations);
+
 SignalFfiError *signal_pin_verify_local_hash(bool *out, const char *encoded_hash, SignalBorrowedBuffer pin);
 
 SignalFfiError *signal_svr2_client_new(SignalSgxClientState **out, SignalBorrowedBuffer mrenclave, SignalBorrowedBuffer attestation_msg, uint64_t current_timestamp);
@@ -1614,8 +1762,14 @@ SignalFfiError *signal_validating_mac_destroy(SignalValidatingMac *p);
 
 SignalFfiError *signal_validating_mac_initialize(SignalValidatingMac **out, SignalBorrowedBuffer key, uint32_t chunk_size, SignalBorrowedBuffer digests);
 
+SignalFfiError *signal_validating_mac_resume(SignalValidatingMac **out, SignalBorrowedBuffer key, uint32_t chunk_size, SignalBorrowedBuffer digests, SignalBorrowedBuffer checkpoint, const SignalInputStream *saved_input);
+
 SignalFfiError *signal_validating_mac_update(int32_t *out, SignalValidatingMac *mac, SignalBorrowedBuffer bytes, uint32_t offset, uint32_t length);
 
+SignalFfiError *signal_validating_mac_validated_offset(uint64_t *out, const SignalValidatingMac *mac);
+
+SignalFfiError *signal_validating_mac_checkpoint(SignalOwnedBuffer *out, const SignalValidatingMac *mac);
+
 SignalFfiError *signal_validating_mac_finalize(int32_t *out, SignalValidatingMac *mac);
 
 SignalFfiError *signal_message_backup_key_destroy(SignalMessageBackupKey *p);
@@ -1630,6 +1784,16 @@ SignalFfiError *signal_message_backup_validation_outcome_get_unknown_fields(Sign
 
 SignalFfiError *signal_message_backup_validator_validate(SignalMessageBackupValidationOutcome **out, const SignalMessageBackupKey *key, const SignalInputStream *first_stream, const SignalInputStream *second_stream, uint64_t len, uint8_t purpose);
 
+SignalFfiError *signal_message_backup_validator_validate_stream(SignalMessageBackupValidationOutcome **out, const SignalMessageBackupKey *key, const SignalInputStream *stream, uint8_t purpose);
+
+SignalFfiError *signal_message_backup_writer_destroy(SignalMessageBackupWriter *p);
+
+SignalFfiError *signal_message_backup_writer_new(SignalMessageBackupWriter **out, const SignalMessageBackupKey *key, SignalBorrowedBuffer backup_info, uint8_t purpose);
+
+SignalFfiError *signal_message_backup_writer_write_frame(SignalOwnedBuffer *out, const SignalMessageBackupWriter *writer, SignalBorrowedBuffer frame);
+
+SignalFfiError *signal_message_backup_writer_finish(SignalOwnedBuffer *out, const SignalMessageBackupWriter *writer);
+
 SignalFfiError *signal_username_hash(uint8_t (*out)[32], const char *username);
 
 SignalFfiError *signal_username_proof(SignalOwnedBuffer *out, const char *username, SignalBorrowedBuffer randomness);
@@ -1638,6 +1802,8 @@ SignalFfiError *signal_username_verify(SignalBorrowedBuffer proof, SignalBorrowe
 
 SignalFfiError *signal_username_candidates_from(SignalStringArray *out, const char *nickname, uint32_t min_len, uint32_t max_len);
 
+SignalFfiError *signal_username_candidates_with_proofs_from(SignalBytestringArray *out, const char *nickname, uint32_t min_len, uint32_t max_len, SignalBorrowedBuffer ranges, SignalBorrowedBuffer excluded, uint32_t count);
+
 SignalFfiError *signal_username_hash_from_parts(uint8_t (*out)[32], const char *nickname, const char *discriminator, uint32_t min_len, uint32_t max_len);
 
 SignalFfiError *signal_username_link_create(SignalOwnedBuffer *out, const char *username, SignalBorrowedBuffer entropy);
@@ -1656,6 +1822,34 @@ SignalFfiError *signal_mp4_sanitizer_sanitize(SignalSanitizedMetadata **out, con
 SignalFfiError *signal_webp_sanitizer_sanitize(const SignalSyncInputStream *input);
 #endif
 
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_webp_sanitizer_sanitize_and_strip_metadata(SignalSanitizedImage **out, const SignalSyncInputStream *input);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_gif_sanitizer_sanitize(SignalSanitizedImage **out, const SignalSyncInputStream *input);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_sanitized_image_destroy(SignalSanitizedImage *p);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_sanitized_image_get_data(SignalOwnedBuffer *out, const SignalSanitizedImage *sanitized);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_sanitized_image_get_width(uint32_t *out, const SignalSanitizedImage *sanitized);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_sanitized_image_get_height(uint32_t *out, const SignalSanitizedImage *sanitized);
+#endif
+
+#if defined(SIGNAL_MEDIA_SUPPORTED)
+SignalFfiError *signal_sanitized_image_get_frame_count(uint32_t *out, const SignalSanitizedImage *sanitized);
+#endif
+
 #if defined(SIGNAL_MEDIA_SUPPORTED)
 SignalFfiError *signal_sanitized_metadata_destroy(SignalSanitizedMetadata *p);
 #endif
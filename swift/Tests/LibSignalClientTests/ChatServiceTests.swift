@@ -20,6 +20,12 @@ extension ChatService {
             }
         }
     }
+
+    func injectConnectionInterrupted() {
+        withNativeHandle { handle in
+            failOnError(signal_testing_chat_service_inject_connection_interrupted(handle))
+        }
+    }
 }
 
 final class ChatServiceTests: TestCaseBase {
@@ -162,7 +168,7 @@ final class ChatServiceTests: TestCaseBase {
                 self.secondMessageReceived = secondMessageReceived
             }
 
-            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, sendAck: () async throws -> Void) {
+            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, ack: ServerMessageAck) {
                 // This assumes a little-endian platform.
                 XCTAssertEqual(envelope, withUnsafeBytes(of: serverDeliveryTimestamp) { Data($0) })
                 switch serverDeliveryTimestamp {
@@ -227,6 +233,100 @@ final class ChatServiceTests: TestCaseBase {
         XCTAssertEqual(listener.stage, 3)
     }
 
+    func testListenerConnectionInterrupted() throws {
+        class Listener: ChatListener {
+            let expectation: XCTestExpectation
+            init(expectation: XCTestExpectation) {
+                self.expectation = expectation
+            }
+
+            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, ack: ServerMessageAck) {
+                XCTFail("unexpected message")
+            }
+
+            func chatServiceDidReceiveQueueEmpty(_: ChatService) {
+                XCTFail("unexpected queue empty")
+            }
+
+            func chatServiceConnectionWasInterrupted(_: ChatService, error: Error) {
+                XCTAssertNotNil(error as? SignalError)
+                self.expectation.fulfill()
+            }
+        }
+
+        let net = Net(env: .staging, userAgent: Self.userAgent)
+        let chat = net.createChatService(username: "", password: "")
+        let listener = Listener(expectation: expectation(description: "connection interrupted"))
+        chat.setListener(listener)
+
+        chat.injectConnectionInterrupted()
+
+        waitForExpectations(timeout: 2)
+    }
+
+    func testFakeServer() async throws {
+        class Listener: ChatListener {
+            let events: AsyncStream<String>.Continuation
+            init(events: AsyncStream<String>.Continuation) {
+                self.events = events
+            }
+
+            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, ack: ServerMessageAck) {
+                self.events.yield("message \(serverDeliveryTimestamp): \(envelope.hexString)")
+            }
+
+            func chatServiceDidReceiveQueueEmpty(_: ChatService) {
+                self.events.yield("queue empty")
+            }
+        }
+
+        let net = Net(env: .staging, userAgent: Self.userAgent)
+        let server = FakeChatServer(net: net)
+        do {
+            try server.sendQueueEmpty()
+            XCTFail("should have failed")
+        } catch SignalError.chatServiceInactive(_) {}
+
+        let chat = server.createChatService(net: net, username: "", password: "")
+        var continuation: AsyncStream<String>.Continuation!
+        let events = AsyncStream<String> { continuation = $0 }
+        chat.setListener(Listener(events: continuation))
+        try await chat.connectUnauthenticated()
+
+        async let response = chat.unauthenticatedSend(.init(method: "GET", pathAndQuery: "/v1/config", headers: ["x-test": "value"], timeout: 5))
+        let received = try await server.receiveRequest()
+        try received.request.withNativeHandle { request in
+            XCTAssertEqual("GET", try invokeFnReturningString {
+                signal_testing_chat_request_get_method($0, request)
+            })
+            XCTAssertEqual("/v1/config", try invokeFnReturningString {
+                signal_testing_chat_request_get_path($0, request)
+            })
+            XCTAssertEqual("value", try invokeFnReturningString {
+                signal_testing_chat_request_get_header_value($0, request, "x-test")
+            })
+        }
+        received.respond(status: 200, body: Data("config".utf8))
+        let finishedResponse = try await response
+        XCTAssertEqual(200, finishedResponse.status)
+        XCTAssertEqual(Data("config".utf8), finishedResponse.body)
+
+        // Envelopes go to the most recently connected client, which is now the authenticated one.
+        try await chat.connectAuthenticated()
+        try server.sendEnvelope(Data([1, 2, 3]), serverDeliveryTimestamp: 1000)
+        try server.sendQueueEmpty()
+        var receivedEvents: [String] = []
+        for await event in events {
+            receivedEvents.append(event)
+            if receivedEvents.count == 2 {
+                break
+            }
+        }
+        XCTAssertEqual(["message 1000: 010203", "queue empty"], receivedEvents)
+
+        try await chat.disconnect()
+    }
+
 #endif
 
     func testListenerCleanup() throws {
@@ -241,7 +341,7 @@ final class ChatServiceTests: TestCaseBase {
             }
 
             func chatServiceDidReceiveQueueEmpty(_: ChatService) {}
-            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, sendAck: () async throws -> Void) {}
+            func chatService(_ chat: ChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, ack: ServerMessageAck) {}
         }
 
         let net = Net(env: .staging, userAgent: Self.userAgent)
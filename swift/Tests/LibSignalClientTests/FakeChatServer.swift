@@ -0,0 +1,128 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+// These testing endpoints aren't generated in device builds, to save on code size.
+#if !os(iOS) || targetEnvironment(simulator)
+
+import Foundation
+@testable import LibSignalClient
+import SignalFfi
+
+extension SignalCPromiseFakeChatServerRequest: PromiseStruct {
+    public typealias Result = OpaquePointer
+}
+
+/// An in-process chat server, for testing ``ChatService`` end to end.
+internal class FakeChatServer: NativeHandleOwner {
+    private let tokioAsyncContext: TokioAsyncContext
+
+    init(net: Net) {
+        var handle: OpaquePointer?
+        failOnError(signal_testing_fake_chat_server_new(&handle))
+        self.tokioAsyncContext = net.asyncContext
+        super.init(owned: handle!)
+    }
+
+    internal required init(owned handle: OpaquePointer) {
+        fatalError("should not be called directly for a FakeChatServer")
+    }
+
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_fake_chat_server_destroy(handle)
+    }
+
+    /// Like ``Net/createChatService(username:password:)``, but connects to this server instead.
+    func createChatService(net: Net, username: String, password: String) -> ChatService {
+        var handle: OpaquePointer?
+        net.connectionManager.withNativeHandle { connectionManager in
+            withNativeHandle { server in
+                failOnError(signal_testing_chat_service_new_with_fake_server(&handle, connectionManager, server, username, password))
+            }
+        }
+        return ChatService(owned: handle!, tokioAsyncContext: self.tokioAsyncContext)
+    }
+
+    func setRefuseConnections(_ refuse: Bool) {
+        withNativeHandle { server in
+            failOnError(signal_testing_fake_chat_server_set_refuse_connections(server, refuse))
+        }
+    }
+
+    func disconnectAll() {
+        withNativeHandle { server in
+            failOnError(signal_testing_fake_chat_server_disconnect_all(server))
+        }
+    }
+
+    /// Delivers an envelope to the most recently connected client.
+    ///
+    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if no client is connected.
+    func sendEnvelope(_ envelope: Data, serverDeliveryTimestamp: UInt64) throws {
+        try withNativeHandle { server in
+            try envelope.withUnsafeBorrowedBuffer { envelope in
+                try checkError(signal_testing_fake_chat_server_send_envelope(server, envelope, serverDeliveryTimestamp))
+            }
+        }
+    }
+
+    /// Tells the most recently connected client that there are no more queued envelopes.
+    ///
+    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if no client is connected.
+    func sendQueueEmpty() throws {
+        try withNativeHandle { server in
+            try checkError(signal_testing_fake_chat_server_send_queue_empty(server))
+        }
+    }
+
+    /// Waits for the next request sent by any client.
+    func receiveRequest() async throws -> ReceivedRequest {
+        let handle = try await self.tokioAsyncContext.invokeAsyncFunction { promise, tokioAsyncContext in
+            withNativeHandle { server in
+                signal_testing_fake_chat_server_receive_request(promise, tokioAsyncContext, server)
+            }
+        }
+        return ReceivedRequest(owned: handle, tokioAsyncContext: self.tokioAsyncContext)
+    }
+
+    /// A request received by a ``FakeChatServer``, waiting for a response.
+    internal class ReceivedRequest: NativeHandleOwner {
+        private let tokioAsyncContext: TokioAsyncContext
+
+        init(owned handle: OpaquePointer, tokioAsyncContext: TokioAsyncContext) {
+            self.tokioAsyncContext = tokioAsyncContext
+            super.init(owned: handle)
+        }
+
+        internal required init(owned handle: OpaquePointer) {
+            fatalError("should not be called directly for a ReceivedRequest")
+        }
+
+        override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+            return signal_fake_chat_server_request_destroy(handle)
+        }
+
+        /// The request, for inspection with the `signal_testing_chat_request_get_*` functions.
+        var request: ChatService.InternalRequest {
+            var handle: OpaquePointer?
+            withNativeHandle { request in
+                failOnError(signal_testing_fake_chat_server_request_get_http_request(&handle, request))
+            }
+            return ChatService.InternalRequest(owned: handle!)
+        }
+
+        /// Responds with `status` after `delay`; an empty body is sent as no body at all.
+        func respond(status: UInt16, body: Data = Data(), delay: TimeInterval = 0) {
+            self.tokioAsyncContext.withNativeHandle { tokioAsyncContext in
+                withNativeHandle { request in
+                    body.withUnsafeBorrowedBuffer { body in
+                        failOnError(signal_testing_fake_chat_server_request_respond(tokioAsyncContext, request, status, body, UInt32(delay * 1000)))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#endif
@@ -45,6 +45,55 @@ class IncrementalMacTests: TestCaseBase {
         }
     }
 
+    func testResumeAfterFailure() throws {
+        var corruptInput = self.TEST_INPUT
+        corruptInput[1][10] ^= 0xFF
+
+        let mac = try ValidatingMacContext(key: TEST_KEY, chunkSize: CHUNK_SIZE, expectingDigest: TEST_DIGEST)
+        XCTAssertEqual(0, try mac.update(corruptInput[0]))
+        do {
+            _ = try mac.update(corruptInput[1])
+            XCTFail("Should have failed")
+        } catch SignalError.verificationFailed {
+        } catch {
+            XCTFail("Unexpected error thrown")
+        }
+        // The whole first chunk was dropped, so fetch it again.
+        XCTAssertEqual(0, mac.validatedOffset)
+        let input = self.TEST_INPUT.reduce(Data(), +)
+        XCTAssertEqual(32, try mac.update(input))
+        _ = try mac.finalize()
+    }
+
+    func testResumeFromCheckpoint() throws {
+        let input = self.TEST_INPUT.reduce(Data(), +)
+        let mac = try ValidatingMacContext(key: TEST_KEY, chunkSize: CHUNK_SIZE, expectingDigest: TEST_DIGEST)
+        XCTAssertEqual(32, try mac.update(input.prefix(40)))
+        let checkpoint = try mac.checkpoint()
+        XCTAssertEqual(32, mac.validatedOffset)
+
+        let resumed = try ValidatingMacContext(
+            key: TEST_KEY,
+            chunkSize: CHUNK_SIZE,
+            expectingDigest: TEST_DIGEST,
+            resumingFrom: checkpoint,
+            savedInput: SignalInputStreamAdapter(input.prefix(32))
+        )
+        XCTAssertEqual(32, resumed.validatedOffset)
+        XCTAssertEqual(0, try resumed.update(input.dropFirst(32)))
+        XCTAssertEqual(18, try resumed.finalize())
+
+        var corruptSavedInput = input.prefix(32)
+        corruptSavedInput[1] ^= 0xFF
+        XCTAssertThrowsError(try ValidatingMacContext(
+            key: TEST_KEY,
+            chunkSize: CHUNK_SIZE,
+            expectingDigest: TEST_DIGEST,
+            resumingFrom: checkpoint,
+            savedInput: SignalInputStreamAdapter(corruptSavedInput)
+        ))
+    }
+
     func testIncrementalValidationFailureInFinalize() throws {
         var corruptInput = self.TEST_INPUT
         corruptInput[2][0] ^= 0xFF
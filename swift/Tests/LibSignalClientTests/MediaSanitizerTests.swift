@@ -66,6 +66,20 @@ class WebpSanitizerTests: TestCaseBase {
         try sanitizeWebp(input: SignalInputStreamAdapter(input))
     }
 
+    func testMinimalWebpUnchangedByStripping() throws {
+        let input = webp()
+        let sanitized = try sanitizeWebpAndStripMetadata(input: SignalInputStreamAdapter(input))
+        XCTAssertEqual(sanitized.data, input)
+        XCTAssertEqual(sanitized.width, 1)
+        XCTAssertEqual(sanitized.height, 1)
+        XCTAssertEqual(sanitized.frameCount, 1)
+    }
+
+    func testWebpExifRemoved() throws {
+        let sanitized = try sanitizeWebpAndStripMetadata(input: SignalInputStreamAdapter(extendedWebp(withExif: true)))
+        XCTAssertEqual(sanitized.data, extendedWebp(withExif: false))
+    }
+
     func testWebpIoError() throws {
         XCTAssertThrowsError(try sanitizeWebp(input: ErrorInputStream(), len: 1)) { error in
             if case SignalError.ioError = error {} else { XCTFail("\(error)") }
@@ -73,6 +87,30 @@ class WebpSanitizerTests: TestCaseBase {
     }
 }
 
+class GifSanitizerTests: TestCaseBase {
+    func testEmptyGif() {
+        let input: [UInt8] = []
+        XCTAssertThrowsError(try sanitizeGif(input: SignalInputStreamAdapter(input))) { error in
+            if case SignalError.invalidMediaInput = error {} else { XCTFail("\(error)") }
+        }
+    }
+
+    func testMinimalGif() throws {
+        let input = gif(extensions: [])
+        let sanitized = try sanitizeGif(input: SignalInputStreamAdapter(input))
+        XCTAssertEqual(sanitized.data, input)
+        XCTAssertEqual(sanitized.width, 1)
+        XCTAssertEqual(sanitized.height, 1)
+        XCTAssertEqual(sanitized.frameCount, 1)
+    }
+
+    func testGifCommentRemoved() throws {
+        let comment: [UInt8] = [0x21, 0xFE, 2, 0x68, 0x69, 0] // "hi"
+        let sanitized = try sanitizeGif(input: SignalInputStreamAdapter(gif(extensions: comment)))
+        XCTAssertEqual(sanitized.data, gif(extensions: []))
+    }
+}
+
 private func ftyp() -> [UInt8] {
     var ftyp: [UInt8] = []
     ftyp.append(contentsOf: [0, 0, 0, 20]) // box size
@@ -134,6 +172,44 @@ private func webp() -> [UInt8] {
     return webp
 }
 
+/// A 1x1 WebP with an extended header, optionally followed by EXIF metadata.
+private func extendedWebp(withExif: Bool) -> [UInt8] {
+    var webp: [UInt8] = []
+    webp.append(contentsOf: "RIFF") // chunk type
+    webp.append(contentsOf: [withExif ? 50 : 38, 0, 0, 0]) // chunk size
+    webp.append(contentsOf: "WEBP") // webp header
+
+    webp.append(contentsOf: "VP8X") // chunk type
+    webp.append(contentsOf: [10, 0, 0, 0]) // chunk size
+    webp.append(contentsOf: [withExif ? 0x08 : 0, 0, 0, 0]) // flags
+    webp.append(contentsOf: [0, 0, 0, 0, 0, 0]) // canvas size
+
+    webp.append(contentsOf: "VP8L") // chunk type
+    webp.append(contentsOf: [8, 0, 0, 0]) // chunk size
+    webp.append(contentsOf: [0x2F, 0, 0, 0, 0, 0x88, 0x88, 8]) // VP8L data
+
+    if withExif {
+        webp.append(contentsOf: "EXIF") // chunk type
+        webp.append(contentsOf: [4, 0, 0, 0]) // chunk size
+        webp.append(contentsOf: [1, 2, 3, 4]) // EXIF data
+    }
+
+    return webp
+}
+
+/// A 1x1 GIF with the given extension blocks before its image.
+private func gif(extensions: [UInt8]) -> [UInt8] {
+    var gif: [UInt8] = []
+    gif.append(contentsOf: "GIF89a".utf8) // header
+    gif.append(contentsOf: [1, 0, 1, 0, 0x80, 0, 0]) // logical screen descriptor
+    gif.append(contentsOf: [0, 0, 0, 0xFF, 0xFF, 0xFF]) // global color table
+    gif.append(contentsOf: extensions)
+    gif.append(contentsOf: [0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0]) // image descriptor
+    gif.append(contentsOf: [2, 2, 0x44, 0x01, 0]) // image data
+    gif.append(contentsOf: [0x3B]) // trailer
+    return gif
+}
+
 private func assertSanitizedMetadataEqual(_ sanitized: SanitizedMetadata, dataOffset: Int, dataLen: Int, metadata: (any Sequence<UInt8>)?) {
     if let metadata = metadata {
         XCTAssertNotNil(sanitized.metadata)
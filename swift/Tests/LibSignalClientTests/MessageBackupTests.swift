@@ -63,6 +63,37 @@ class MessageBackupTests: TestCaseBase {
         }
     }
 
+    func testWriter() throws {
+        // A Frame containing AccountData with only a profile key and the required account settings.
+        let accountDataFrame: [UInt8] = [0x0A, 0x27, 0x0A, 0x20] + Array(repeating: 0xAA, count: 32) + [0x4A, 0x03, 0x88, 0x01, 0x01]
+
+        let writer = try MessageBackupWriter(key: MessageBackupKey.testKey(), purpose: .remoteBackup, backupInfo: [])
+        // A frame with no item is rejected, but doesn't stop the writer.
+        XCTAssertThrowsError(try writer.writeFrame([])) { error in
+            if error is MessageBackupValidationError {} else { XCTFail("\(error)") }
+        }
+        var bytes = try writer.writeFrame(accountDataFrame)
+        bytes += try writer.finish()
+
+        let outcome = try Self.validateBackup(bytes: bytes)
+        XCTAssertEqual(outcome.fields, [])
+
+        XCTAssertThrowsError(try writer.finish()) { error in
+            if error is MessageBackupValidationError {} else { XCTFail("\(error)") }
+        }
+    }
+
+    func testWriterRejectsIncompleteBackup() throws {
+        let writer = try MessageBackupWriter(key: MessageBackupKey.testKey(), purpose: .remoteBackup, backupInfo: [])
+        XCTAssertThrowsError(try writer.finish()) { error in
+            if let error = error as? MessageBackupValidationError {
+                XCTAssert(error.errorMessage.contains("AccountData"), "\(error.errorMessage)")
+            } else {
+                XCTFail("\(error)")
+            }
+        }
+    }
+
     static func validateBackup(bytes: some Collection<UInt8>) throws -> MessageBackupUnknownFields {
         try validateMessageBackup(key: MessageBackupKey.testKey(), purpose: .remoteBackup, length: UInt64(bytes.count), makeStream: { SignalInputStreamAdapter(bytes) })
     }
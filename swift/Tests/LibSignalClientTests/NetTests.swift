@@ -232,6 +232,66 @@ final class Svr3Tests: TestCaseBase {
         }
     }
 
+    func testMigrate() async throws {
+        let tries = UInt32(10)
+        let previous = Net(env: .staging, userAgent: userAgent)
+
+        let previousShareSet = try await previous.svr3.backup(
+            self.storedSecret,
+            password: "password",
+            maxTries: tries,
+            auth: self.state!.auth
+        )
+
+        let migrationState = Svr3MigrationState()
+        let shareSet = try await state!.net.svr3.migrate(
+            from: previous.svr3,
+            state: migrationState,
+            password: "password",
+            shareSet: previousShareSet,
+            maxTries: tries,
+            auth: self.state!.auth
+        )
+        XCTAssertFalse(migrationState.hasRestoredSecret)
+
+        let restoredSecret = try await state!.net.svr3.restore(
+            password: "password",
+            shareSet: shareSet,
+            auth: self.state!.auth
+        )
+        XCTAssertEqual(restoredSecret.value, self.storedSecret)
+        XCTAssertEqual(restoredSecret.triesRemaining, tries - 1)
+    }
+
+    func testMigrateWithInvalidPassword() async throws {
+        let previous = Net(env: .staging, userAgent: userAgent)
+
+        let previousShareSet = try await previous.svr3.backup(
+            self.storedSecret,
+            password: "password",
+            maxTries: 10,
+            auth: self.state!.auth
+        )
+
+        let migrationState = Svr3MigrationState()
+        do {
+            _ = try await self.state!.net.svr3.migrate(
+                from: previous.svr3,
+                state: migrationState,
+                password: "invalid password",
+                shareSet: previousShareSet,
+                maxTries: 10,
+                auth: self.state!.auth
+            )
+            XCTFail("Should have thrown")
+        } catch SignalError.svrRestoreFailed(_, _) {
+            // Success!
+        } catch {
+            XCTFail("Unexpected exception: '\(error)'")
+        }
+        XCTAssertFalse(migrationState.hasRestoredSecret)
+    }
+
     func testCorruptedShareSet() async throws {
         var shareSet = try await state!.net.svr3.backup(
             self.storedSecret,
@@ -33,6 +33,34 @@ class PinTests: TestCaseBase {
         XCTAssertFalse(try! verifyLocalPin(Array("badpassword".utf8), againstEncodedHash: hash))
     }
 
+    func testVerifyWithParams() {
+        let pin = Array("password".utf8)
+        let hash = try! hashLocalPin(pin, memoryKib: 1024, iterations: 32)
+        XCTAssert(hash.hasPrefix("$argon2id$"))
+        XCTAssertTrue(try! verifyLocalPin(pin, againstEncodedHash: hash))
+        XCTAssertFalse(try! verifyLocalPin(Array("badpassword".utf8), againstEncodedHash: hash))
+    }
+
+    func testWeakParams() {
+        XCTAssertThrowsError(try hashLocalPin(Array("password".utf8), memoryKib: 512, iterations: 1)) {
+            guard case SignalError.invalidArgument(_) = $0 else {
+                XCTFail("wrong error: \($0)")
+                return
+            }
+        }
+    }
+
+    func testWeakEncodedHash() {
+        // The cost is checked before the pin, so the hash output doesn't matter.
+        let weakHash = "$argon2id$v=19$m=512,t=1,p=1$ICEiIyQlJicoKSorLC0uLw$NeZzhiNv4cRmRMct9scf7d838bzmHJvrZtU/0BH0v/U"
+        XCTAssertThrowsError(try verifyLocalPin(Array("password".utf8), againstEncodedHash: weakHash)) {
+            guard case SignalError.invalidArgument(_) = $0 else {
+                XCTFail("wrong error: \($0)")
+                return
+            }
+        }
+    }
+
     func testKnown() {
         let pin = Array("password".utf8)
         // echo "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f" | xxd -r -p | base64
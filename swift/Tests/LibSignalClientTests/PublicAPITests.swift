@@ -480,6 +480,49 @@ class PublicAPITests: TestCaseBase {
         }
     }
 
+    func testDeviceTransferArchive() throws {
+        let key = [UInt8](repeating: 0x42, count: 32)
+        let payload = (0..<200_000).map { UInt8($0 % 251) }
+
+        let writer = try DeviceTransferArchiveWriter(key: key, totalLength: UInt64(payload.count))
+        var archive = try writer.update(payload[..<1000])
+        archive += try writer.update(payload[1000...])
+        archive += try writer.finish()
+
+        let reader = try DeviceTransferArchiveReader(key: key)
+        var decrypted: [UInt8] = []
+        for start in stride(from: 0, to: archive.count, by: 10_000) {
+            decrypted += try reader.update(archive[start..<min(start + 10_000, archive.count)])
+        }
+        try reader.finish()
+        XCTAssertEqual(payload, decrypted)
+
+        let truncatedReader = try DeviceTransferArchiveReader(key: key)
+        XCTAssertNoThrow(try truncatedReader.update(archive.dropLast()))
+        XCTAssertThrowsError(try truncatedReader.finish()) {
+            guard case SignalError.ioError = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+
+        let extendedReader = try DeviceTransferArchiveReader(key: key)
+        XCTAssertThrowsError(try extendedReader.update(archive + [0])) {
+            guard case SignalError.ioError = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+    }
+
+    func testDeviceTransferArchiveWrongLength() throws {
+        let key = [UInt8](repeating: 0x42, count: 32)
+        let writer = try DeviceTransferArchiveWriter(key: key, totalLength: 10)
+        _ = try writer.update([UInt8](repeating: 0, count: 5))
+        XCTAssertThrowsError(try writer.finish())
+        XCTAssertThrowsError(try DeviceTransferArchiveWriter(key: [1, 2, 3], totalLength: 10))
+    }
+
     func testSignAlternateIdentity() {
         let primary = IdentityKeyPair.generate()
         let secondary = IdentityKeyPair.generate()
@@ -303,6 +303,55 @@ class SessionTests: TestCaseBase {
         XCTAssertFalse(session.hasCurrentState)
     }
 
+    func testBulkSessionManagement() throws {
+        let day: TimeInterval = 60 * 60 * 24
+        let now = Date()
+        let alice_store = InMemorySignalProtocolStore()
+
+        let addresses = try (0..<3).map { try ProtocolAddress(name: "+1415555000\($0)", deviceId: 1) }
+        for address in addresses {
+            initializeSessionsV4(alice_store: alice_store, bob_store: InMemorySignalProtocolStore(), bob_address: address)
+        }
+        // Have addresses[0] last used 10 days ago.
+        _ = try signalEncrypt(
+            message: [1, 2, 3],
+            for: addresses[0],
+            sessionStore: alice_store,
+            identityStore: alice_store,
+            now: now - 10 * day,
+            context: NullContext()
+        )
+
+        let missing = try ProtocolAddress(name: "+14155559999", deviceId: 1)
+        XCTAssertEqual(
+            try archiveSessions(for: [addresses[2], missing], sessionStore: alice_store, context: NullContext()),
+            [addresses[2]]
+        )
+
+        XCTAssertEqual(
+            try expireSessions(for: addresses, maxAge: .infinity, sessionStore: alice_store, now: now, context: NullContext()),
+            []
+        )
+        XCTAssertEqual(
+            try expireSessions(for: addresses, maxAge: 7 * day, sessionStore: alice_store, now: now, context: NullContext()),
+            [addresses[0]]
+        )
+
+        for (i, address) in addresses.enumerated() {
+            let session = try XCTUnwrap(alice_store.loadSession(for: address, context: NullContext()))
+            XCTAssertEqual(session.hasCurrentState, i == 1, "\(address)")
+        }
+
+        XCTAssertEqual(
+            try prunePreviousSessions(for: addresses, keeping: 0, sessionStore: alice_store, context: NullContext()),
+            [addresses[0], addresses[2]]
+        )
+        XCTAssertEqual(
+            try prunePreviousSessions(for: addresses, keeping: 0, sessionStore: alice_store, context: NullContext()),
+            []
+        )
+    }
+
     func testSealedSenderGroupCipher() throws {
         let alice_address = try! ProtocolAddress(name: "9d0652a3-dcc3-4d11-975f-74d61598733f", deviceId: 1)
         let bob_address = try! ProtocolAddress(name: "6838237D-02F6-4098-B110-698253D15961", deviceId: 1)
@@ -37,6 +37,42 @@ class UsernameTests: TestCaseBase {
         }
     }
 
+    func testCandidatesWithProofsFromRanges() throws {
+        let candidates = try Username.candidatesWithProofs(
+            from: "SiGNAl",
+            count: 5,
+            discriminatorRanges: [(100..<106, 5)],
+            excluding: [103]
+        )
+        XCTAssertEqual(5, candidates.count)
+        let names = candidates.map { String(describing: $0.username) }
+        XCTAssertEqual(Set(["SiGNAl.100", "SiGNAl.101", "SiGNAl.102", "SiGNAl.104", "SiGNAl.105"]), Set(names))
+        for (username, proof) in candidates {
+            try Username.verify(proof: proof, forHash: username.hash)
+        }
+    }
+
+    func testCandidatesWithProofsFromInvalidRanges() throws {
+        XCTAssertThrowsError(try Username.candidatesWithProofs(from: "SiGNAl", count: 5, discriminatorRanges: [(1..<10, 5)])) {
+            guard case SignalError.usernameDiscriminatorCannotBeSingleDigit = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+        XCTAssertThrowsError(try Username.candidatesWithProofs(from: "SiGNAl", count: 5, discriminatorRanges: [(100..<2_000_000_000, 5)])) {
+            guard case SignalError.usernameDiscriminatorTooLarge = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+        XCTAssertThrowsError(try Username.candidatesWithProofs(from: "SiGNAl", count: 5, discriminatorRanges: [(100..<200, 5), (150..<250, 5)])) {
+            guard case SignalError.invalidArgument = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+    }
+
     func testInvalidNicknames() throws {
         for nickname in ["hi", "way_too_long_to_be_a_reasonable_nickname", "I⍰Unicode", "s p a c e s", "0zerostart"] {
             XCTAssertThrowsError(try Username.candidates(from: nickname))